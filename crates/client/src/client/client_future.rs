@@ -15,11 +15,12 @@ use proto::xfer::{
     DnsMultiplexer, DnsMultiplexerConnect, DnsMultiplexerSerialResponse, DnsRequest,
     DnsRequestOptions, DnsRequestSender, DnsResponse, DnsStreamHandle, OneshotDnsResponseReceiver,
 };
-use rand;
 
 use error::*;
-use op::{Message, MessageType, OpCode, Query, update_message};
+use op::{update_message, Message, MessageType, OpCode, Query};
 use rr::dnssec::Signer;
+#[cfg(any(feature = "openssl", feature = "ring"))]
+use rr::dnssec::TsigSigner;
 use rr::{DNSClass, Name, Record, RecordSet, RecordType};
 
 // TODO: this should be configurable
@@ -87,6 +88,40 @@ where
     }
 }
 
+#[cfg(any(feature = "openssl", feature = "ring"))]
+impl<F, S>
+    ClientFuture<
+        DnsMultiplexerConnect<F, S, TsigSigner>,
+        DnsMultiplexer<S, TsigSigner, Box<dyn DnsStreamHandle>>,
+        DnsMultiplexerSerialResponse,
+    >
+where
+    F: Future<Item = S, Error = ProtoError> + Send + 'static,
+    S: DnsClientStream + Send + 'static,
+{
+    /// Spawns a new ClientFuture Stream, which signs all requests with TSIG and verifies the
+    ///  responses to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of bytes that can be used to send/receive DNS messages
+    ///              (see TcpClientStream or UdpClientStream)
+    /// * `stream_handle` - The handle for the `stream` on which bytes can be sent/received.
+    /// * `timeout_duration` - All requests may fail due to lack of response, this is the time to
+    ///                        wait for a response before canceling the request.
+    /// * `signer` - The TSIG key shared with the server
+    pub fn with_tsig_signer(
+        stream: F,
+        stream_handle: Box<dyn DnsStreamHandle>,
+        timeout_duration: Duration,
+        signer: Arc<TsigSigner>,
+    ) -> (Self, BasicClientHandle<DnsMultiplexerSerialResponse>) {
+        let mp =
+            DnsMultiplexer::with_timeout(stream, stream_handle, timeout_duration, Some(signer));
+        Self::connect(mp)
+    }
+}
+
 impl<F, S, R> ClientFuture<F, S, R>
 where
     F: Future<Item = S, Error = ProtoError> + 'static + Send,
@@ -295,17 +330,17 @@ pub trait ClientHandle: 'static + Clone + DnsHandle + Send {
         debug!("notifying: {} {:?}", name, query_type);
 
         // build the message
+        //  the id is assigned from the RandSource of the connection when the message is sent
         let mut message: Message = Message::new();
-        let id: u16 = rand::random();
-        message.set_id(id)
-           // 3.3. NOTIFY is similar to QUERY in that it has a request message with
-           // the header QR flag "clear" and a response message with QR "set".  The
-           // response message contains no useful information, but its reception by
-           // the master is an indication that the slave has received the NOTIFY
-           // and that the master can remove the slave from any retry queue for
-           // this NOTIFY event.
-           .set_message_type(MessageType::Query)
-           .set_op_code(OpCode::Notify);
+        message
+            // 3.3. NOTIFY is similar to QUERY in that it has a request message with
+            // the header QR flag "clear" and a response message with QR "set".  The
+            // response message contains no useful information, but its reception by
+            // the master is an indication that the slave has received the NOTIFY
+            // and that the master can remove the slave from any retry queue for
+            // this NOTIFY event.
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Notify);
 
         // Extended dns
         {
@@ -373,7 +408,7 @@ pub trait ClientHandle: 'static + Clone + DnsHandle + Send {
     {
         let rrset = rrset.into();
         let message = update_message::create(rrset, zone_origin);
-        
+
         ClientResponse(self.send(message))
     }
 
@@ -0,0 +1,448 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! MDNS client collecting the answers of all responders to a query
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll, Stream};
+use proto::error::ProtoError;
+use proto::multicast::{MdnsClientConnect, MdnsClientStream, MdnsQueryType, MDNS_IPV4, MDNS_IPV6};
+use proto::op::{Message, MessageType, OpCode, Query};
+use proto::rr::{DNSClass, Name, Record, RecordType};
+use proto::xfer::{DnsStreamHandle, SerialMessage};
+use rand;
+use tokio::timer::Delay;
+
+/// MDNS client for queries which may be answered by many responders
+///
+/// Unlike the `MdnsClientConnection`, which completes a query with the first response, the
+///  queries of this client collect the answers of all responses received within a time window.
+#[derive(Clone)]
+pub struct MdnsClient {
+    multicast_addr: SocketAddr,
+    packet_ttl: Option<u32>,
+    ipv4_if: Option<Ipv4Addr>,
+    ipv6_if: Option<u32>,
+    unicast_response: bool,
+}
+
+impl MdnsClient {
+    /// associates the socket to the well-known ipv4 multicast address
+    pub fn new_ipv4(packet_ttl: Option<u32>, ipv4_if: Option<Ipv4Addr>) -> Self {
+        Self::new(*MDNS_IPV4, packet_ttl, ipv4_if, None)
+    }
+
+    /// associates the socket to the well-known ipv6 multicast address
+    pub fn new_ipv6(packet_ttl: Option<u32>, ipv6_if: Option<u32>) -> Self {
+        Self::new(*MDNS_IPV6, packet_ttl, None, ipv6_if)
+    }
+
+    /// associates the socket to the given multicast address
+    ///
+    /// # Arguments
+    ///
+    /// * `multicast_addr` - the multicast group and port the queries are sent to
+    /// * `packet_ttl` - the ttl of the packets, `Some(1)` keeps them on the local network
+    /// * `ipv4_if` - the address of the interface to use for ipv4
+    /// * `ipv6_if` - the index of the interface to use for ipv6
+    pub fn new(
+        multicast_addr: SocketAddr,
+        packet_ttl: Option<u32>,
+        ipv4_if: Option<Ipv4Addr>,
+        ipv6_if: Option<u32>,
+    ) -> Self {
+        MdnsClient {
+            multicast_addr,
+            packet_ttl,
+            ipv4_if,
+            ipv6_if,
+            unicast_response: false,
+        }
+    }
+
+    /// Request unicast responses, i.e. send QU questions instead of the default QM questions
+    ///
+    /// See [RFC 6762](https://tools.ietf.org/html/rfc6762#section-5.4)
+    pub fn set_unicast_response(&mut self, unicast_response: bool) -> &mut Self {
+        self.unicast_response = unicast_response;
+        self
+    }
+
+    /// Sends a query to the multicast group, and collects the answers until the window closes
+    ///
+    /// The query does not request recursion. All responses with the id of the query, or with the
+    ///  id zero of multicast responses, received within the window are merged into the resulting
+    ///  message, without duplicate records. Only the answers to the query, and CNAMEs of its name,
+    ///  are kept; responses without any, e.g. unsolicited announcements of other names, are
+    ///  dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the label to lookup
+    /// * `query_class` - most likely this should always be DNSClass::IN
+    /// * `query_type` - record type to lookup
+    /// * `window` - the time to wait for responses, starting with the query
+    pub fn query(
+        &self,
+        name: Name,
+        query_class: DNSClass,
+        query_type: RecordType,
+        window: Duration,
+    ) -> MdnsQueryFuture {
+        let (connect, mut sender) = MdnsClientStream::new(
+            self.multicast_addr,
+            MdnsQueryType::OneShot,
+            self.packet_ttl,
+            self.ipv4_if,
+            self.ipv6_if,
+        );
+
+        let mut query = Query::query(name, query_type);
+        query
+            .set_query_class(query_class)
+            .set_mdns_unicast_response(self.unicast_response);
+
+        let mut message = Message::new();
+        message
+            .set_id(rand::random())
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false)
+            .add_query(query);
+
+        // the request is buffered until the stream is connected
+        let error = message
+            .to_vec()
+            .and_then(|bytes| sender.send(SerialMessage::new(bytes, self.multicast_addr)))
+            .err();
+
+        // the response carries the query, and collects the answers
+        message.set_message_type(MessageType::Response);
+
+        MdnsQueryFuture {
+            state: MdnsQueryState::Connecting(connect),
+            _sender: sender,
+            response: Some(message),
+            window: Delay::new(Instant::now() + window),
+            error,
+        }
+    }
+}
+
+enum MdnsQueryState {
+    Connecting(MdnsClientConnect),
+    Collecting(MdnsClientStream),
+}
+
+/// A future that resolves to the answers collected for an mDNS query
+#[must_use = "futures do nothing unless polled"]
+pub struct MdnsQueryFuture {
+    state: MdnsQueryState,
+    // the stream is closed when the sender is dropped
+    _sender: Box<dyn DnsStreamHandle + Send>,
+    response: Option<Message>,
+    window: Delay,
+    error: Option<ProtoError>,
+}
+
+/// Adds the records of the response to the collected ones, if it is a response to the query
+fn collect(collected: &mut Message, serial_message: SerialMessage) {
+    let mut message = match serial_message.to_message() {
+        Ok(message) => message,
+        Err(e) => {
+            debug!("dropping undecodable mDNS response: {}", e);
+            return;
+        }
+    };
+
+    if message.message_type() != MessageType::Response
+        || (message.id() != collected.id() && message.id() != 0)
+    {
+        debug!(
+            "dropping mDNS message {} from {}, not a response to {}",
+            message.id(),
+            serial_message.addr(),
+            collected.id()
+        );
+        return;
+    }
+
+    let answers = message
+        .take_answers()
+        .into_iter()
+        .filter(|record| {
+            collected
+                .queries()
+                .iter()
+                .any(|query| answers_query(record, query))
+        })
+        .collect::<Vec<_>>();
+    if answers.is_empty() {
+        debug!(
+            "dropping mDNS message {} from {}, no answer to {}",
+            message.id(),
+            serial_message.addr(),
+            collected.id()
+        );
+        return;
+    }
+
+    for record in answers {
+        if !collected.answers().contains(&record) {
+            collected.add_answer(record);
+        }
+    }
+    for record in message.take_name_servers() {
+        if !collected.name_servers().contains(&record) {
+            collected.add_name_server(record);
+        }
+    }
+    for record in message.take_additionals() {
+        if !collected.additionals().contains(&record) {
+            collected.add_additional(record);
+        }
+    }
+}
+
+/// Returns true if the record has the name, type and class of the query, or is a CNAME of its name
+fn answers_query(record: &Record, query: &Query) -> bool {
+    record.name() == query.name()
+        && (record.rr_type() == query.query_type()
+            || record.rr_type() == RecordType::CNAME
+            || query.query_type() == RecordType::ANY)
+        && (record.dns_class() == query.query_class() || query.query_class() == DNSClass::ANY)
+}
+
+impl Future for MdnsQueryFuture {
+    type Item = Message;
+    type Error = ProtoError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        loop {
+            let stream = match self.state {
+                MdnsQueryState::Connecting(ref mut connect) => match connect.poll()? {
+                    Async::Ready(stream) => stream,
+                    Async::NotReady => break,
+                },
+                MdnsQueryState::Collecting(ref mut stream) => {
+                    while let Async::Ready(serial_message) = stream.poll()? {
+                        match serial_message {
+                            Some(serial_message) => collect(
+                                self.response.as_mut().expect("polled after completion"),
+                                serial_message,
+                            ),
+                            // no more responses can arrive
+                            None => {
+                                return Ok(Async::Ready(
+                                    self.response.take().expect("polled after completion"),
+                                ))
+                            }
+                        }
+                    }
+                    break;
+                }
+            };
+
+            self.state = MdnsQueryState::Collecting(stream);
+        }
+
+        try_ready!(self.window.poll());
+        Ok(Async::Ready(
+            self.response.take().expect("polled after completion"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv6Addr};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    use futures::future;
+    use proto::multicast::MdnsStream;
+    use proto::rr::RData;
+    use tokio::runtime::current_thread::Runtime;
+    use tokio::timer::Timeout;
+
+    use super::*;
+
+    // 250 appears to be unused/unregistered, the port is distinct from the ones of the proto tests
+    fn test_mdns_addr() -> SocketAddr {
+        SocketAddr::new(IpAddr::from(Ipv4Addr::new(224, 0, 0, 250)), 5389)
+    }
+
+    /// Answers the first query received on the multicast group with an A record of the address
+    fn spawn_responder(
+        mdns_addr: SocketAddr,
+        address: Ipv4Addr,
+        ready: Arc<Barrier>,
+    ) -> thread::JoinHandle<()> {
+        thread::Builder::new()
+            .name("test_mdns_client:responder".to_string())
+            .spawn(move || {
+                let mut io_loop = Runtime::new().unwrap();
+
+                // TTLs are 1 so that multicast test packets never leave the test host
+                let (stream, sender) =
+                    MdnsStream::new(mdns_addr, MdnsQueryType::OneShotJoin, Some(1), None, None);
+                let mut stream = io_loop
+                    .block_on(stream)
+                    .expect("could not create mDNS responder");
+                ready.wait();
+
+                let (request, client_addr) = loop {
+                    let (serial_message, stream_tmp) = io_loop
+                        .block_on(Timeout::new(
+                            stream.into_future().map_err(|(e, _)| e),
+                            Duration::from_secs(5),
+                        ))
+                        .expect("no query received");
+                    stream = stream_tmp;
+
+                    let serial_message = serial_message.expect("responder stream closed");
+                    match serial_message.to_message() {
+                        Ok(ref message) if message.message_type() == MessageType::Query => {
+                            break (message.clone(), serial_message.addr())
+                        }
+                        _ => continue,
+                    }
+                };
+
+                assert!(!request.recursion_desired());
+                let query = request.queries()[0].clone();
+                assert!(query.mdns_unicast_response());
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_authoritative(true)
+                    .add_answer(Record::from_rdata(
+                        query.name().clone(),
+                        120,
+                        RData::A(address),
+                    ));
+                sender
+                    .unbounded_send(SerialMessage::new(response.to_vec().unwrap(), client_addr))
+                    .unwrap();
+
+                // the response is sent while the stream is polled
+                io_loop.spawn(stream.for_each(|_| Ok(())).map_err(|_| ()));
+                io_loop
+                    .block_on(Delay::new(Instant::now() + Duration::from_millis(500)))
+                    .unwrap();
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_collect_drops_unrelated() {
+        let name = Name::from_ascii("printer.local.").unwrap();
+        let mut collected = Message::new();
+        collected
+            .set_id(0x1234)
+            .set_message_type(MessageType::Response)
+            .add_query(Query::query(name.clone(), RecordType::A));
+
+        let responder = SocketAddr::new(IpAddr::from(Ipv4Addr::new(192, 168, 0, 1)), 5353);
+        let response = |answer: Record| {
+            let mut message = Message::new();
+            message
+                .set_id(0)
+                .set_message_type(MessageType::Response)
+                .set_authoritative(true)
+                .add_answer(answer)
+                .add_additional(Record::from_rdata(
+                    Name::from_ascii("host.local.").unwrap(),
+                    120,
+                    RData::A(Ipv4Addr::new(192, 168, 0, 1)),
+                ));
+            SerialMessage::new(message.to_vec().unwrap(), responder)
+        };
+
+        // an unsolicited announcement of another name, or of another type, is dropped entirely
+        let unrelated = Name::from_ascii("speaker.local.").unwrap();
+        collect(
+            &mut collected,
+            response(Record::from_rdata(
+                unrelated,
+                120,
+                RData::A(Ipv4Addr::new(192, 168, 0, 2)),
+            )),
+        );
+        collect(
+            &mut collected,
+            response(Record::from_rdata(
+                name.clone(),
+                120,
+                RData::AAAA(Ipv6Addr::LOCALHOST),
+            )),
+        );
+        assert!(collected.answers().is_empty());
+        assert!(collected.additionals().is_empty());
+
+        let answer = Record::from_rdata(name, 120, RData::A(Ipv4Addr::new(192, 168, 0, 3)));
+        collect(&mut collected, response(answer.clone()));
+        assert_eq!(collected.answers(), &[answer]);
+        assert_eq!(collected.additionals().len(), 1);
+    }
+
+    #[test]
+    fn test_query_aggregates_answers() {
+        let mdns_addr = test_mdns_addr();
+        let ready = Arc::new(Barrier::new(3));
+        let responders = vec![
+            spawn_responder(mdns_addr, Ipv4Addr::new(192, 168, 0, 1), ready.clone()),
+            spawn_responder(mdns_addr, Ipv4Addr::new(192, 168, 0, 2), ready.clone()),
+        ];
+        ready.wait();
+
+        let name = Name::from_ascii("printer.local.").unwrap();
+        let mut client = MdnsClient::new(mdns_addr, Some(1), None, None);
+        client.set_unicast_response(true);
+
+        let mut io_loop = Runtime::new().unwrap();
+        let response = io_loop
+            .block_on(future::lazy(|| {
+                client.query(
+                    name.clone(),
+                    DNSClass::IN,
+                    RecordType::A,
+                    Duration::from_secs(1),
+                )
+            }))
+            .expect("mDNS query failed");
+
+        for responder in responders {
+            responder.join().expect("responder failed");
+        }
+
+        assert_eq!(response.message_type(), MessageType::Response);
+        assert_eq!(response.queries()[0].name(), &name);
+
+        let mut addresses: Vec<Ipv4Addr> = response
+            .answers()
+            .iter()
+            .filter_map(|record| match *record.rdata() {
+                RData::A(address) => Some(address),
+                _ => None,
+            })
+            .collect();
+        addresses.sort();
+        assert_eq!(
+            addresses,
+            vec![Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(192, 168, 0, 2)]
+        );
+    }
+}
@@ -6,6 +6,9 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Update related operations for Messages
+//!
+//! The ids of the messages are left unset, they are assigned from the `RandSource` of the
+//!  connection when the message is sent.
 
 use std::fmt::Debug;
 
@@ -172,7 +175,6 @@ pub fn create(rrset: RecordSet, zone_origin: Name) -> Message {
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -239,7 +241,6 @@ pub fn append(rrset: RecordSet, zone_origin: Name, must_exist: bool) -> Message
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -317,7 +318,6 @@ pub fn compare_and_swap(current: RecordSet, new: RecordSet, zone_origin: Name) -
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -397,7 +397,6 @@ pub fn delete_by_rdata(mut rrset: RecordSet, zone_origin: Name) -> Message {
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -465,7 +464,6 @@ pub fn delete_rrset(mut record: Record, zone_origin: Name) -> Message {
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -525,7 +523,6 @@ pub fn delete_all(name_of_records: Name, zone_origin: Name, dns_class: DNSClass)
     // build the message
     let mut message: Message = Message::new();
     message
-        .set_id(rand::random())
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Update)
         .set_recursion_desired(false);
@@ -1,5 +1,13 @@
+use std::collections::HashMap;
+use std::str;
+
+use data_encoding::BASE64;
+#[cfg(feature = "openssl")]
+use openssl::bn::{BigNum, BigNumContext};
+#[cfg(feature = "openssl")]
+use openssl::ec::{EcGroup, EcKey, EcPoint};
 #[cfg(feature = "openssl")]
-use openssl::ec::EcKey;
+use openssl::nid::Nid;
 #[cfg(feature = "openssl")]
 use openssl::rsa::Rsa;
 #[cfg(feature = "openssl")]
@@ -22,6 +30,8 @@ pub enum KeyFormat {
     Pem,
     /// Pkcs8, a pkcs8 formatted private key
     Pkcs8,
+    /// The `.private` key file of BIND, as written by `dnssec-keygen`, these are never encrypted
+    Bind,
 }
 
 impl KeyFormat {
@@ -33,6 +43,10 @@ impl KeyFormat {
         password: Option<&str>,
         algorithm: Algorithm,
     ) -> DnsSecResult<KeyPair<Private>> {
+        if self == KeyFormat::Bind {
+            return decode_bind_key(bytes, algorithm);
+        }
+
         //  empty string prevents openssl from triggering a read from stdin...
         let password = password.unwrap_or("");
         let password = password.as_bytes();
@@ -41,6 +55,7 @@ impl KeyFormat {
             Algorithm::Unknown(v) => { 
                 Err(format!("unknown algorithm: {}", v).into())
             }
+            e @ Algorithm::ECCGOST => Err(format!("unsupported Algorithm: {:?}", e).into()),
             #[cfg(feature = "openssl")]
             e @ Algorithm::RSASHA1 | e @ Algorithm::RSASHA1NSEC3SHA1 => {
                 Err(format!("unsupported Algorithm (insecure): {:?}", e).into())
@@ -144,6 +159,9 @@ impl KeyFormat {
             Algorithm::Unknown(v) => {
                 return Err(format!("unknown algorithm: {}", v).into())
             }
+            e @ Algorithm::ECCGOST => {
+                return Err(format!("unsupported Algorithm: {:?}", e).into())
+            }
             #[cfg(feature = "openssl")]
             e @ Algorithm::RSASHA1 | e @ Algorithm::RSASHA1NSEC3SHA1 => {
                 return Err(format!("unsupported Algorithm (insecure): {:?}", e).into())
@@ -267,6 +285,115 @@ impl KeyFormat {
     }
 }
 
+/// Decodes a private key in the format of BIND
+///
+/// The format is a list of fields, the field names depend on the algorithm, the values of the key
+///  material are base64 encoded. For example the ECDSA key of RFC 6605, section 6.1:
+///
+/// ```text
+/// Private-key-format: v1.2
+/// Algorithm: 13 (ECDSAP256SHA256)
+/// PrivateKey: GU6SnQ/Ou+xC5RumuIUIuJZteXT2z0O/ok1s38Et6mQ=
+/// ```
+fn decode_bind_key(bytes: &[u8], algorithm: Algorithm) -> DnsSecResult<KeyPair<Private>> {
+    let text = str::from_utf8(bytes)
+        .map_err(|e| format!("BIND private key is not valid UTF-8: {}", e))?;
+
+    let mut fields = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut field = line.splitn(2, ':');
+        match (field.next(), field.next()) {
+            (Some(name), Some(value)) => fields.insert(name.trim(), value.trim()),
+            _ => return Err(format!("malformed line in BIND private key: {}", line).into()),
+        };
+    }
+
+    match fields.get("Private-key-format") {
+        Some(version) if version.starts_with("v1.") => (),
+        version => {
+            return Err(format!("unsupported BIND private key format: {:?}", version).into())
+        }
+    }
+
+    // the number is followed by the mnemonic, e.g. `13 (ECDSAP256SHA256)`
+    let key_algorithm = fields
+        .get("Algorithm")
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse::<u8>().ok())
+        .map(Algorithm::from_u8)
+        .ok_or("Algorithm missing from BIND private key")?;
+    if key_algorithm != algorithm {
+        return Err(format!(
+            "BIND private key is for {:?}, expected {:?}",
+            key_algorithm, algorithm
+        )
+        .into());
+    }
+
+    let field = |name: &str| -> DnsSecResult<Vec<u8>> {
+        let value = fields
+            .get(name)
+            .ok_or_else(|| format!("{} missing from BIND private key", name))?;
+
+        BASE64
+            .decode(value.as_bytes())
+            .map_err(|e| format!("could not decode {} of BIND private key: {}", name, e).into())
+    };
+
+    match algorithm {
+        e @ Algorithm::RSASHA1 | e @ Algorithm::RSASHA1NSEC3SHA1 => {
+            Err(format!("unsupported Algorithm (insecure): {:?}", e).into())
+        }
+        #[cfg(feature = "openssl")]
+        Algorithm::RSASHA256 | Algorithm::RSASHA512 => {
+            let number = |name: &str| -> DnsSecResult<BigNum> {
+                BigNum::from_slice(&field(name)?).map_err(DnsSecError::from)
+            };
+
+            let key = Rsa::from_private_components(
+                number("Modulus")?,
+                number("PublicExponent")?,
+                number("PrivateExponent")?,
+                number("Prime1")?,
+                number("Prime2")?,
+                number("Exponent1")?,
+                number("Exponent2")?,
+                number("Coefficient")?,
+            )?;
+
+            KeyPair::from_rsa(key)
+        }
+        #[cfg(feature = "openssl")]
+        Algorithm::ECDSAP256SHA256 | Algorithm::ECDSAP384SHA384 => {
+            let curve = if algorithm == Algorithm::ECDSAP256SHA256 {
+                Nid::X9_62_PRIME256V1
+            } else {
+                Nid::SECP384R1
+            };
+            let group = EcGroup::from_curve_name(curve)?;
+            let private_key = BigNum::from_slice(&field("PrivateKey")?)?;
+
+            // only the private key is stored, the public key is derived from it
+            let context = BigNumContext::new()?;
+            let mut public_key = EcPoint::new(&group)?;
+            // deprecated in later releases of openssl, but its replacement isn't in all of them
+            #[allow(deprecated)]
+            public_key.mul_generator(&group, &private_key, &context)?;
+
+            let key = EcKey::from_private_components(&group, &private_key, &public_key)?;
+            KeyPair::from_ec_key(key)
+        }
+        #[cfg(feature = "ring")]
+        Algorithm::ED25519 => {
+            // this is the 32 byte seed of the key
+            let key = Ed25519KeyPair::from_seed_unchecked(&field("PrivateKey")?)?;
+
+            Ok(KeyPair::from_ed25519(key))
+        }
+        e => Err(format!("unsupported Algorithm with BIND private keys: {:?}", e).into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     pub use super::*;
@@ -313,6 +440,125 @@ mod tests {
         encode_decode_with_format(KeyFormat::Pkcs8, algorithm, true, true);
     }
 
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_ec_decode_bind() {
+        // RFC 6605, section 6.1
+        let private_key = b"Private-key-format: v1.2
+Algorithm: 13 (ECDSAP256SHA256)
+PrivateKey: GU6SnQ/Ou+xC5RumuIUIuJZteXT2z0O/ok1s38Et6mQ=
+";
+        let public_key = "GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edb\
+                          krSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA==";
+
+        decode_bind_with_public_key(private_key, Algorithm::ECDSAP256SHA256, public_key);
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn test_ed25519_decode_bind() {
+        // RFC 8080, section 6.1
+        let private_key = b"Private-key-format: v1.2
+Algorithm: 15 (ED25519)
+PrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=
+";
+        let public_key = "l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=";
+
+        decode_bind_with_public_key(private_key, Algorithm::ED25519, public_key);
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_rsa_decode_bind() {
+        use openssl::bn::BigNumRef;
+
+        let algorithm = Algorithm::RSASHA256;
+        let rsa = Rsa::generate(2048).unwrap();
+
+        let field = |name: &str, number: &BigNumRef| {
+            format!("{}: {}\n", name, BASE64.encode(&number.to_vec()))
+        };
+        let private_key = [
+            "Private-key-format: v1.3\nAlgorithm: 8 (RSASHA256)\n".to_string(),
+            field("Modulus", rsa.n()),
+            field("PublicExponent", rsa.e()),
+            field("PrivateExponent", rsa.d()),
+            field("Prime1", rsa.p().unwrap()),
+            field("Prime2", rsa.q().unwrap()),
+            field("Exponent1", rsa.dmp1().unwrap()),
+            field("Exponent2", rsa.dmq1().unwrap()),
+            field("Coefficient", rsa.iqmp().unwrap()),
+            "Created: 20200101000000\n".to_string(),
+        ]
+        .concat();
+
+        let key = KeyFormat::Bind
+            .decode_key(private_key.as_bytes(), None, algorithm)
+            .expect("failed to decode");
+        let expected = KeyPair::from_rsa(rsa).unwrap();
+        assert_eq!(
+            key.to_public_bytes().unwrap(),
+            expected.to_public_bytes().unwrap()
+        );
+        sign_and_verify(&key, algorithm);
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn test_decode_bind_wrong_algorithm() {
+        let private_key = b"Private-key-format: v1.2
+Algorithm: 15 (ED25519)
+PrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=
+";
+
+        assert!(KeyFormat::Bind
+            .decode_key(private_key, None, Algorithm::ECDSAP256SHA256)
+            .is_err());
+        assert!(KeyFormat::Bind
+            .decode_key(&private_key[..40], None, Algorithm::ED25519)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rsasha1_decode_bind() {
+        let private_key = b"Private-key-format: v1.3
+Algorithm: 5 (RSASHA1)
+Modulus: AQAB
+";
+
+        match KeyFormat::Bind.decode_key(private_key, None, Algorithm::RSASHA1) {
+            Err(e) => assert!(e.to_string().contains("insecure")),
+            Ok(_) => panic!("RSASHA1 should not be supported"),
+        }
+    }
+
+    #[cfg(test)]
+    fn decode_bind_with_public_key(private_key: &[u8], algorithm: Algorithm, public_key: &str) {
+        let key = KeyFormat::Bind
+            .decode_key(private_key, None, algorithm)
+            .expect("failed to decode");
+
+        let public_key: String = public_key.split_whitespace().collect();
+        assert_eq!(
+            key.to_dnskey(algorithm).unwrap().public_key(),
+            &BASE64.decode(public_key.as_bytes()).unwrap()[..]
+        );
+        sign_and_verify(&key, algorithm);
+    }
+
+    #[cfg(test)]
+    fn sign_and_verify(key: &KeyPair<Private>, algorithm: Algorithm) {
+        use rr::dnssec::{Verifier, TBS};
+
+        let tbs = TBS::from(&b"www.example.com"[..]);
+        let sig = key.sign(algorithm, &tbs).unwrap();
+        assert!(key
+            .to_dnskey(algorithm)
+            .unwrap()
+            .verify(tbs.as_ref(), &sig)
+            .is_ok());
+    }
+
     #[cfg(test)]
     fn encode_decode_with_format(
         key_format: KeyFormat,
@@ -29,6 +29,8 @@ use ring::{rand,
 use error::*;
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use rr::dnssec::DigestType;
+#[cfg(feature = "openssl")]
+use rr::dnssec::ecdsa_signature_from_der;
 use rr::dnssec::{Algorithm, PublicKeyBuf};
 use rr::dnssec::{HasPrivate, HasPublic, Private, TBS};
 use rr::rdata::key::KeyUsage;
@@ -353,67 +355,7 @@ impl<K: HasPrivate> KeyPair<K> {
                     }
 
                     // Convert DER signature to raw signature (see RFC 6605 Section 4)
-                    if bytes.len() < 8 {
-                        return Err("unexpected signature format (length too short)".into());
-                    }
-                    let expect = |pos: usize, expected: u8| -> DnsSecResult<()> {
-                        if bytes[pos] != expected {
-                            return Err(format!(
-                                "unexpected signature format ({}, {}))",
-                                pos, expected
-                            )
-                            .into());
-                        }
-                        Ok(())
-                    };
-                    // Sanity checks
-                    expect(0, 0x30)?;
-                    expect(1, (bytes.len() - 2) as u8)?;
-                    expect(2, 0x02)?;
-                    let p1_len = bytes[3] as usize;
-                    let p2_pos = 4 + p1_len;
-                    expect(p2_pos, 0x02)?;
-                    let p2_len = bytes[p2_pos + 1] as usize;
-                    if p2_pos + 2 + p2_len > bytes.len() {
-                        return Err("unexpected signature format (invalid length)".into());
-                    }
-
-                    let p1 = &bytes[4..p2_pos];
-                    let p2 = &bytes[p2_pos + 2..p2_pos + 2 + p2_len];
-
-                    // For P-256, each integer MUST be encoded as 32 octets;
-                    // for P-384, each integer MUST be encoded as 48 octets.
-                    let part_len = match algorithm {
-                        Algorithm::ECDSAP256SHA256 => 32,
-                        Algorithm::ECDSAP384SHA384 => 48,
-                        _ => return Err("unexpected algorithm".into()),
-                    };
-                    let mut ret = Vec::<u8>::new();
-                    {
-                        let mut write_part = |mut part: &[u8]| -> DnsSecResult<()> {
-                            // We need to pad or trim the octet string to expected length
-                            if part.len() > part_len + 1 {
-                                return Err("invalid signature data".into());
-                            }
-                            if part.len() == part_len + 1 {
-                                // Trim leading zero
-                                if part[0] != 0x00 {
-                                    return Err("invalid signature data".into());
-                                }
-                                part = &part[1..];
-                            }
-                            for _ in 0..(part_len - part.len()) {
-                                // Pad with zeros. All numbers are big-endian here.
-                                ret.push(0x00);
-                            }
-                            ret.extend(part);
-                            Ok(())
-                        };
-                        write_part(p1)?;
-                        write_part(p2)?;
-                    }
-                    assert_eq!(ret.len(), part_len * 2);
-                    Ok(ret)
+                    ecdsa_signature_from_der(algorithm, &bytes).map_err(Into::into)
                 })
             }
             #[cfg(feature = "ring")]
@@ -444,6 +386,7 @@ impl KeyPair<Private> {
             Algorithm::Unknown(_) => { 
                 Err(DnsSecErrorKind::Message("unknown algorithm").into())
             }
+            Algorithm::ECCGOST => Err(DnsSecErrorKind::Message("unsupported algorithm").into()),
             #[cfg(feature = "openssl")]
             Algorithm::RSASHA1
             | Algorithm::RSASHA1NSEC3SHA1
@@ -481,6 +424,7 @@ impl KeyPair<Private> {
             Algorithm::Unknown(_) => { 
                 Err(DnsSecErrorKind::Message("unknown algorithm").into())
             }
+            Algorithm::ECCGOST => Err(DnsSecErrorKind::Message("unsupported algorithm").into()),
             #[cfg(feature = "openssl")]
             Algorithm::RSASHA1
             | Algorithm::RSASHA1NSEC3SHA1
@@ -24,7 +24,9 @@ mod signer;
 use proto::rr::dnssec;
 
 pub use self::dnssec::Algorithm;
+pub use self::dnssec::AlgorithmPolicy;
 pub use self::dnssec::DigestType;
+pub use self::dnssec::{ecdsa_signature_from_der, ecdsa_signature_to_der};
 #[cfg(any(feature = "openssl", feature = "ring"))]
 pub use self::key_format::KeyFormat;
 pub use self::keypair::KeyPair;
@@ -32,11 +34,18 @@ pub use self::dnssec::Nsec3HashAlgorithm;
 pub use self::dnssec::PublicKey;
 pub use self::dnssec::PublicKeyBuf;
 pub use self::dnssec::PublicKeyEnum;
+#[cfg(any(feature = "openssl", feature = "ring"))]
+pub use self::signer::sign_rrset;
 pub use self::signer::Signer;
 pub use self::dnssec::SupportedAlgorithms;
 pub use self::dnssec::TrustAnchor;
+pub use self::dnssec::TrustAnchors;
 pub use self::dnssec::tbs;
 pub use self::dnssec::TBS;
+pub use self::dnssec::{format_timestamp, parse_timestamp};
+pub use self::dnssec::{BogusReason, Validity};
+#[cfg(any(feature = "openssl", feature = "ring"))]
+pub use self::dnssec::{TsigSigner, TsigVerifier};
 pub use self::dnssec::Verifier;
 
 pub use error::DnsSecError;
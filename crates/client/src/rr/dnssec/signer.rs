@@ -492,6 +492,21 @@ impl Signer {
         tbs::message_tbs(message, pre_sig0).and_then(|tbs| self.sign(&tbs))
     }
 
+    /// Signs the RRset with this key, see `sign_rrset()`
+    ///
+    /// The signature is valid from `inception` for the `sig_duration` of this Signer.
+    pub fn sign_rrset(&self, records: &[Record], inception: u32) -> DnsSecResult<RData> {
+        sign_rrset(
+            records,
+            &self.key,
+            self.algorithm,
+            &self.signer_name,
+            self.calculate_key_tag()?,
+            inception,
+            inception.wrapping_add(self.sig_duration.num_seconds() as u32),
+        )
+    }
+
     /// Extracts a public KEY from this Signer
     pub fn to_dnskey(&self) -> DnsSecResult<DNSKEY> {
         // TODO: this interface should allow for setting if this is a secure entry point vs. ZSK
@@ -515,6 +530,63 @@ impl Signer {
     }
 }
 
+/// Signs the RRset, returning the RRSIG record data for it
+///
+/// The name, type, class and original TTL of the RRset are those of the first record, records with
+///  a different name, type or class are not signed. The signed data is in the canonical form of
+///  [RFC 4034, section 6](https://tools.ietf.org/html/rfc4034#section-6), see `tbs::rrset_tbs`.
+///
+/// # Arguments
+///
+/// * `records` - the RRset to sign
+/// * `key` - the private key to sign with
+/// * `algorithm` - the algorithm of the key
+/// * `signer_name` - the name of the zone of the key, i.e. the name of its DNSKEY record
+/// * `key_tag` - the key tag of the DNSKEY record
+/// * `inception` - the epoch seconds from when the signature is valid
+/// * `expiration` - the epoch seconds until when the signature is valid
+#[cfg(any(feature = "openssl", feature = "ring"))]
+pub fn sign_rrset(
+    records: &[Record],
+    key: &KeyPair<Private>,
+    algorithm: Algorithm,
+    signer_name: &Name,
+    key_tag: u16,
+    inception: u32,
+    expiration: u32,
+) -> DnsSecResult<RData> {
+    let first = records.first().ok_or("can not sign an empty RRset")?;
+
+    // the labels don't include the wildcard label, RFC 4034 section 3.1.3
+    let num_labels = first.name().num_labels();
+    let tbs = tbs::rrset_tbs(
+        first.name(),
+        first.dns_class(),
+        num_labels,
+        first.rr_type(),
+        algorithm,
+        first.ttl(),
+        expiration,
+        inception,
+        key_tag,
+        signer_name,
+        records,
+    )?;
+    let signature = key.sign(algorithm, &tbs)?;
+
+    Ok(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+        first.rr_type(),
+        algorithm,
+        num_labels,
+        first.ttl(),
+        expiration,
+        inception,
+        key_tag,
+        signer_name.clone(),
+        signature,
+    ))))
+}
+
 impl MessageFinalizer for Signer {
     #[cfg(any(feature = "openssl", feature = "ring"))]
     fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>> {
@@ -649,6 +721,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_sig0() {
+        let mut question: Message = Message::new();
+        let mut query: Query = Query::new();
+        query.set_name(Name::parse("example.com.", None).unwrap());
+        question.add_query(query);
+
+        let rsa = Rsa::generate(2048).unwrap();
+        let key = KeyPair::from_rsa(rsa).unwrap();
+        let sig0key = key.to_sig0key(Algorithm::RSASHA256).unwrap();
+        let signer = Signer::sig0(sig0key.clone(), key, Name::root());
+
+        question
+            .finalize(&signer, 1_000)
+            .expect("should have signed");
+        let mut received = Message::from_vec(&question.to_vec().unwrap()).unwrap();
+        sig0key
+            .verify_sig0(&received, 1_000)
+            .expect("SIG(0) should verify");
+
+        // the signature times out after 5 minutes
+        assert!(sig0key.verify_sig0(&received, 1_301).is_err());
+        assert!(sig0key.verify_sig0(&received, 999).is_err());
+
+        // another key didn't sign it
+        let other = KeyPair::from_rsa(Rsa::generate(2048).unwrap())
+            .unwrap()
+            .to_sig0key(Algorithm::RSASHA256)
+            .unwrap();
+        assert!(other.verify_sig0(&received, 1_000).is_err());
+
+        // any change to the message invalidates the signature
+        received.set_id(received.id().wrapping_add(1));
+        assert!(sig0key.verify_sig0(&received, 1_000).is_err());
+
+        // as does a missing one
+        assert!(sig0key.verify_sig0(&Message::new(), 1_000).is_err());
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_sign_and_verify_rrset() {
@@ -705,6 +816,64 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn test_sign_rrset() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let key = KeyPair::from_rsa(rsa).unwrap();
+        let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
+        let key_tag = dnskey.calculate_key_tag().unwrap();
+        let signer_name = Name::parse("example.com.", None).unwrap();
+
+        // out of canonical order, and not lowercase
+        let name = Name::parse("*.Example.COM.", None).unwrap();
+        let rrset = vec![
+            Record::from_rdata(
+                name.clone(),
+                3600,
+                RData::NS(Name::parse("B.iana-servers.net.", None).unwrap()),
+            ),
+            Record::from_rdata(
+                name.clone(),
+                3600,
+                RData::NS(Name::parse("a.iana-servers.net.", None).unwrap()),
+            ),
+        ];
+
+        let rdata = sign_rrset(
+            &rrset,
+            &key,
+            Algorithm::RSASHA256,
+            &signer_name,
+            key_tag,
+            1_000,
+            2_000,
+        )
+        .unwrap();
+        let rrsig = rdata.as_dnssec().and_then(DNSSECRData::as_sig).unwrap();
+
+        assert_eq!(rrsig.type_covered(), RecordType::NS);
+        assert_eq!(rrsig.algorithm(), Algorithm::RSASHA256);
+        assert_eq!(rrsig.num_labels(), 2);
+        assert_eq!(rrsig.original_ttl(), 3600);
+        assert_eq!(rrsig.sig_inception(), 1_000);
+        assert_eq!(rrsig.sig_expiration(), 2_000);
+        assert_eq!(rrsig.key_tag(), key_tag);
+        assert_eq!(rrsig.signer_name(), &signer_name);
+
+        // the order of the records is irrelevant
+        let mut reversed = rrset.clone();
+        reversed.reverse();
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, rrsig, &reversed)
+            .is_ok());
+
+        let mut other = rrset.clone();
+        other[0].set_rdata(RData::NS(Name::parse("c.iana-servers.net.", None).unwrap()));
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, rrsig, &other)
+            .is_err());
+    }
+
     fn get_rsa_from_vec(params: &[u32]) -> Result<Rsa<Private>, openssl::error::ErrorStack> {
         Rsa::from_private_components(
             BigNum::from_u32(params[0]).unwrap(), // modulus: n
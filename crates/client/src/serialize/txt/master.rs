@@ -14,12 +14,17 @@
  * limitations under the License.
  */
 use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use error::*;
 use rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
 use serialize::txt::master_lex::{Lexer, Token};
-use serialize::txt::parse_rdata::RDataParser;
+use serialize::txt::parse_rdata;
+
+/// The maximum number of records of a single `$GENERATE` entry
+const MAX_GENERATE_RECORDS: u32 = 65_536;
 
 /// ```text
 /// 5. MASTER FILES
@@ -122,12 +127,25 @@ use serialize::txt::parse_rdata::RDataParser;
 ///                 the line is ignored.
 /// ```
 #[derive(Default)]
-pub struct Parser;
+pub struct Parser {
+    /// the files being parsed, the including ones first, for resolving and checking `$INCLUDE`s
+    files: Vec<PathBuf>,
+}
 
 impl Parser {
     /// Returns a new Zone file parser
     pub fn new() -> Self {
-        Parser
+        Parser { files: vec![] }
+    }
+
+    /// Returns a new Zone file parser for the file at `path`
+    ///
+    /// Relative `$INCLUDE` file names are resolved against the directory of the file, otherwise
+    ///  against the current directory.
+    pub fn with_path<P: Into<PathBuf>>(path: P) -> Self {
+        Parser {
+            files: vec![path.into()],
+        }
     }
 
     /// Parse a file from the Lexer
@@ -140,14 +158,33 @@ impl Parser {
         lexer: Lexer,
         origin: Option<Name>,
     ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
-        let mut lexer = lexer;
         let mut records: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+        let origin = self.parse_records(lexer, origin, None, None, &mut records)?;
+
+        //
+        // build the Authority and return.
+        let origin = origin.ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
+        })?;
+        Ok((origin, records))
+    }
+
+    /// Parses the entries of a file into `records`, returning the origin at the end of the file
+    fn parse_records(
+        &mut self,
+        lexer: Lexer,
+        origin: Option<Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<Option<Name>> {
+        let mut lexer = lexer;
 
         let mut origin: Option<Name> = origin;
         let mut current_name: Option<Name> = None;
         let mut rtype: Option<RecordType> = None;
-        let mut ttl: Option<u32> = None;
-        let mut class: Option<DNSClass> = None;
+        let mut ttl: Option<u32> = ttl;
+        let mut class: Option<DNSClass> = class;
         let mut state = State::StartLine;
 
         while let Some(t) = lexer.next_token()? {
@@ -158,9 +195,10 @@ impl Parser {
 
                     match t {
                         // if Dollar, then $INCLUDE or $ORIGIN
-                        Token::Include => unimplemented!(),
+                        Token::Include => State::Include(vec![]),
                         Token::Origin => State::Origin,
                         Token::Ttl => State::Ttl,
+                        Token::Generate => State::Generate(vec![]),
 
                         // if CharData, then Name then ttl_class_type
                         Token::CharData(data) => {
@@ -197,7 +235,46 @@ impl Parser {
                         _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
                     }
                 }
-                State::Include => unimplemented!(),
+                State::Include(include_parts) => {
+                    let mut include_parts = include_parts;
+                    match t {
+                        Token::EOL => {
+                            self.include(include_parts, &origin, ttl, class, records)?;
+                            State::StartLine
+                        }
+                        Token::CharData(part) => {
+                            include_parts.push(part);
+                            State::Include(include_parts)
+                        }
+                        Token::Quoted(part) => {
+                            include_parts.push(Self::quoted_to_string(part)?);
+                            State::Include(include_parts)
+                        }
+                        _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                    }
+                }
+                State::Generate(generate_parts) => {
+                    let mut generate_parts = generate_parts;
+                    match t {
+                        Token::EOL => {
+                            Self::generate(generate_parts, &origin, ttl, class, records)?;
+                            State::StartLine
+                        }
+                        Token::CharData(part) => {
+                            generate_parts.push(part);
+                            State::Generate(generate_parts)
+                        }
+                        Token::Quoted(part) => {
+                            generate_parts.push(Self::quoted_to_string(part)?);
+                            State::Generate(generate_parts)
+                        }
+                        Token::At => {
+                            generate_parts.push("@".to_string());
+                            State::Generate(generate_parts)
+                        }
+                        _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                    }
+                }
                 State::TtlClassType => {
                     match t {
                         // if number, TTL
@@ -241,11 +318,16 @@ impl Parser {
                                 rtype,
                                 &mut ttl,
                                 class,
-                                &mut records,
+                                records,
                             )?;
                             State::StartLine
                         }
                         Token::CharData(part) => {
+                            let mut record_parts = record_parts;
+                            record_parts.push(part.into_bytes());
+                            State::Record(record_parts)
+                        }
+                        Token::Quoted(part) => {
                             let mut record_parts = record_parts;
                             record_parts.push(part);
                             State::Record(record_parts)
@@ -253,7 +335,7 @@ impl Parser {
                         // TODO: we should not tokenize the list...
                         Token::List(list) => {
                             let mut record_parts = record_parts;
-                            record_parts.extend(list);
+                            record_parts.extend(list.into_iter().map(String::into_bytes));
                             State::Record(record_parts)
                         }
                         _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
@@ -263,28 +345,113 @@ impl Parser {
         }
 
         //Extra flush at the end for the case of missing endline
-        if let State::Record(record_parts) = state {
-            Self::flush_record(
+        match state {
+            State::Record(record_parts) => Self::flush_record(
                 record_parts,
                 &origin,
                 &current_name,
                 rtype,
                 &mut ttl,
                 class,
-                &mut records,
-            )?;
+                records,
+            )?,
+            State::Include(include_parts) => {
+                self.include(include_parts, &origin, ttl, class, records)?
+            }
+            State::Generate(generate_parts) => {
+                Self::generate(generate_parts, &origin, ttl, class, records)?
+            }
+            _ => (),
         }
 
-        //
-        // build the Authority and return.
-        let origin = origin.ok_or_else(|| {
-            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
-        })?;
-        Ok((origin, records))
+        Ok(origin)
+    }
+
+    /// Parses a single resource record, e.g. the `Display` of a `Record`
+    ///
+    /// ```text
+    /// www.example.com. 3600 IN A 192.0.2.1
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - the record, with the owner name, optionally the TTL and class, type and rdata
+    /// * `origin` - the origin of relative names, and of `@`
+    /// * `ttl` - the TTL if the record does not specify one
+    /// * `class` - the class if the record does not specify one
+    pub fn parse_record(
+        line: &str,
+        origin: Option<&Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+    ) -> ParseResult<Record> {
+        let mut lexer = Lexer::new(line);
+        let mut ttl = ttl;
+        let mut class = class;
+
+        let name = match lexer.next_token()? {
+            Some(Token::CharData(data)) => Name::parse(&data, origin)?,
+            Some(Token::At) => origin.cloned().ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
+            })?,
+            Some(t) => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+            None => return Err(ParseErrorKind::Message("record name not specified").into()),
+        };
+
+        // the TTL and class may come in either order before the type
+        let rtype = loop {
+            match lexer.next_token()? {
+                Some(Token::CharData(data)) => {
+                    if let Ok(record_ttl) = Self::parse_time(&data) {
+                        ttl = Some(record_ttl);
+                    } else if let Ok(record_class) = DNSClass::from_str(&data) {
+                        class = Some(record_class);
+                    } else {
+                        break RecordType::from_str(&data)?;
+                    }
+                }
+                Some(t) => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                None => return Err(ParseErrorKind::Message("record type not specified").into()),
+            }
+        };
+
+        let mut record_parts: Vec<Vec<u8>> = vec![];
+        while let Some(t) = lexer.next_token()? {
+            match t {
+                Token::CharData(part) => record_parts.push(part.into_bytes()),
+                Token::Quoted(part) => record_parts.push(part),
+                Token::List(list) => record_parts.extend(list.into_iter().map(String::into_bytes)),
+                Token::EOL => break,
+                _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+            }
+        }
+
+        // only one record is parsed
+        match lexer.next_token()? {
+            None | Some(Token::EOL) => (),
+            Some(t) => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+        }
+
+        let rdata =
+            parse_rdata::parse_bytes(rtype, record_parts.iter().map(AsRef::as_ref), origin)?;
+
+        let mut record = Record::new();
+        record
+            .set_name(name)
+            .set_rr_type(rtype)
+            .set_ttl(ttl.ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message("record ttl not specified"))
+            })?)
+            .set_dns_class(class.ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message("record class not specified"))
+            })?)
+            .set_rdata(rdata);
+
+        Ok(record)
     }
 
     fn flush_record(
-        record_parts: Vec<String>,
+        record_parts: Vec<Vec<u8>>,
         origin: &Option<Name>,
         current_name: &Option<Name>,
         rtype: Option<RecordType>,
@@ -294,7 +461,7 @@ impl Parser {
     ) -> ParseResult<()> {
         // call out to parsers for difference record types
         // all tokens as part of the Record should be chardata...
-        let rdata = RData::parse(
+        let rdata = parse_rdata::parse_bytes(
             rtype.ok_or_else(|| {
                 ParseError::from(ParseErrorKind::Message("record type not specified"))
             })?,
@@ -363,6 +530,265 @@ impl Parser {
         Ok(())
     }
 
+    /// Adds the records of an `$INCLUDE` entry
+    ///
+    /// ```text
+    /// $INCLUDE <file-name> [<domain-name>]
+    /// ```
+    ///
+    /// The domain name is the origin of the included file, by default the current origin. The
+    ///  included file starts with the current TTL and class, and changes to the origin, TTL or
+    ///  class in it do not apply to the including file. Including a file that is already being
+    ///  parsed is an error.
+    fn include(
+        &mut self,
+        include_parts: Vec<String>,
+        origin: &Option<Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<()> {
+        let mut include_parts = include_parts.into_iter();
+        let file_name = include_parts.next().ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$INCLUDE file name not specified"))
+        })?;
+        let include_origin = match include_parts.next() {
+            Some(name) => Some(Name::parse(&name, origin.as_ref())?),
+            None => origin.clone(),
+        };
+        if let Some(part) = include_parts.next() {
+            return Err(
+                ParseErrorKind::Msg(format!("unexpected $INCLUDE argument: {}", part)).into(),
+            );
+        }
+
+        let path = match self.files.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(&file_name),
+            None => PathBuf::from(&file_name),
+        };
+        let read_error = |e| {
+            ParseError::from(ParseErrorKind::Msg(format!(
+                "failed to read $INCLUDE {}: {}",
+                path.display(),
+                e
+            )))
+        };
+        let canonical_path = fs::canonicalize(&path).map_err(read_error)?;
+        if self
+            .files
+            .iter()
+            .filter_map(|file| fs::canonicalize(file).ok())
+            .any(|file| file == canonical_path)
+        {
+            return Err(ParseErrorKind::Msg(format!("$INCLUDE loop at {}", path.display())).into());
+        }
+        let txt = fs::read_to_string(&path).map_err(read_error)?;
+
+        debug!(
+            "including {} with origin {:?}",
+            path.display(),
+            include_origin
+        );
+        self.files.push(path.clone());
+        let result = self.parse_records(Lexer::new(&txt), include_origin, ttl, class, records);
+        self.files.pop();
+
+        result.map(|_| ())
+    }
+
+    /// Adds the records of a `$GENERATE` entry, the BIND extension for ranges of records
+    ///
+    /// ```text
+    /// $GENERATE <start>-<stop>[/<step>] <lhs> [<ttl>] [<class>] <type> <rhs>
+    /// ```
+    ///
+    /// A record is added for every value of the range, from the owner name `lhs` and the rdata
+    ///  `rhs`, in which the value replaces each `$`. The TTL and class default to the current ones,
+    ///  the current owner name is not changed. See `generate_substitute` for the modifiers.
+    fn generate(
+        generate_parts: Vec<String>,
+        origin: &Option<Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<()> {
+        let mut generate_parts = generate_parts.into_iter();
+        let range = generate_parts.next().ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$GENERATE range not specified"))
+        })?;
+        let lhs = generate_parts.next().ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$GENERATE lhs not specified"))
+        })?;
+
+        let (start, stop, step) = Self::parse_generate_range(&range)?;
+
+        // [<TTL>] [<class>] <type>, like the records
+        let mut ttl = ttl;
+        let mut class = class;
+        let rtype = loop {
+            let data = generate_parts.next().ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message("record type not specified"))
+            })?;
+
+            if let Ok(generate_ttl) = Self::parse_time(&data) {
+                ttl = Some(generate_ttl);
+            } else if let Ok(generate_class) = DNSClass::from_str(&data) {
+                class = Some(generate_class);
+            } else {
+                break RecordType::from_str(&data)?;
+            }
+        };
+        let rhs: Vec<String> = generate_parts.collect();
+
+        for value in (start..=stop).step_by(step as usize) {
+            let name = if "@" == lhs {
+                origin.clone()
+            } else {
+                Some(Name::parse(
+                    &Self::generate_substitute(&lhs, value)?,
+                    origin.as_ref(),
+                )?)
+            };
+            let record_parts = rhs
+                .iter()
+                .map(|part| Self::generate_substitute(part, value).map(String::into_bytes))
+                .collect::<ParseResult<Vec<Vec<u8>>>>()?;
+
+            Self::flush_record(
+                record_parts,
+                origin,
+                &name,
+                Some(rtype),
+                &mut ttl,
+                class,
+                records,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Quoted strings are octets, outside of the rdata of records they must be UTF-8
+    fn quoted_to_string(quoted: Vec<u8>) -> ParseResult<String> {
+        String::from_utf8(quoted).map_err(|e| {
+            ParseErrorKind::Msg(format!("quoted string is not valid UTF-8: {}", e)).into()
+        })
+    }
+
+    /// Parses the `<start>-<stop>[/<step>]` range of a `$GENERATE` entry, of at most
+    ///  `MAX_GENERATE_RECORDS` values
+    fn parse_generate_range(range: &str) -> ParseResult<(u32, u32, u32)> {
+        let invalid = || {
+            ParseError::from(ParseErrorKind::Msg(format!(
+                "invalid $GENERATE range: {}",
+                range
+            )))
+        };
+
+        let (bounds, step) = match range.find('/') {
+            Some(slash) => (
+                &range[..slash],
+                u32::from_str(&range[slash + 1..]).map_err(|_| invalid())?,
+            ),
+            None => (range, 1),
+        };
+        let dash = bounds.find('-').ok_or_else(invalid)?;
+        let start = u32::from_str(&bounds[..dash]).map_err(|_| invalid())?;
+        let stop = u32::from_str(&bounds[dash + 1..]).map_err(|_| invalid())?;
+
+        if start > stop || step == 0 {
+            return Err(invalid());
+        }
+        if (stop - start) / step >= MAX_GENERATE_RECORDS {
+            return Err(ParseErrorKind::Msg(format!(
+                "$GENERATE range {} exceeds {} records",
+                range, MAX_GENERATE_RECORDS
+            ))
+            .into());
+        }
+
+        Ok((start, stop, step))
+    }
+
+    /// Replaces the `$` of a `$GENERATE` template with the value, `\$` is a literal `$`
+    ///
+    /// `${<offset>[,<width>[,<base>]]}` adds the offset to the value, and formats it in the base,
+    ///  one of `d`, `o`, `x` or `X`, padded with zeros to the width.
+    fn generate_substitute(template: &str, value: u32) -> ParseResult<String> {
+        let mut substituted = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&'$') => {
+                    chars.next();
+                    substituted.push('$');
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+
+                    let mut modifier = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => modifier.push(ch),
+                            None => {
+                                return Err(ParseErrorKind::Msg(format!(
+                                    "unclosed $GENERATE modifier: {}",
+                                    template
+                                ))
+                                .into())
+                            }
+                        }
+                    }
+
+                    substituted.push_str(&Self::generate_modify(&modifier, value)?);
+                }
+                '$' => substituted.push_str(&value.to_string()),
+                ch => substituted.push(ch),
+            }
+        }
+
+        Ok(substituted)
+    }
+
+    /// Formats the value with the `<offset>[,<width>[,<base>]]` of a `$GENERATE` modifier
+    fn generate_modify(modifier: &str, value: u32) -> ParseResult<String> {
+        let invalid = || {
+            ParseError::from(ParseErrorKind::Msg(format!(
+                "invalid $GENERATE modifier: {}",
+                modifier
+            )))
+        };
+
+        let mut fields = modifier.split(',');
+        let offset = match fields.next() {
+            Some(offset) if !offset.is_empty() => i64::from_str(offset).map_err(|_| invalid())?,
+            _ => 0,
+        };
+        let width = match fields.next() {
+            Some(width) => usize::from_str(width).map_err(|_| invalid())?,
+            None => 0,
+        };
+        let base = fields.next().unwrap_or("d");
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+
+        let value = i64::from(value) + offset;
+        if value < 0 {
+            return Err(invalid());
+        }
+
+        match base {
+            "d" => Ok(format!("{:0width$}", value, width = width)),
+            "o" => Ok(format!("{:0width$o}", value, width = width)),
+            "x" => Ok(format!("{:0width$x}", value, width = width)),
+            "X" => Ok(format!("{:0width$X}", value, width = width)),
+            _ => Err(invalid()),
+        }
+    }
+
     /// parses the string following the rules from:
     ///  https://tools.ietf.org/html/rfc2308 (NXCaching RFC) and
     ///  http://www.zytrax.com/books/dns/apa/time.html
@@ -441,7 +867,8 @@ enum State {
     StartLine,    // start of line, @, $<WORD>, Name, Blank
     TtlClassType, // [<TTL>] [<class>] <type>,
     Ttl,          // $TTL <time>
-    Record(Vec<String>),
-    Include, // $INCLUDE <filename>
+    Record(Vec<Vec<u8>>),
+    Include(Vec<String>), // $INCLUDE <filename> [<origin>]
     Origin,
+    Generate(Vec<String>), // $GENERATE <range> <lhs> [<ttl>] [<class>] <type> <rhs>
 }
@@ -5,7 +5,6 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::char;
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -30,6 +29,7 @@ impl<'a> Lexer<'a> {
     pub fn next_token(&mut self) -> LexerResult<Option<Token>> {
         let mut char_data_vec: Option<Vec<String>> = None;
         let mut char_data: Option<String> = None;
+        let mut quoted_data: Option<Vec<u8>> = None;
 
         for i in 0..4096 {
             // max chars in a single lex, helps with issues in the lexer...
@@ -79,7 +79,7 @@ impl<'a> Lexer<'a> {
                         }
                         Some('"') => {
                             self.txt.next();
-                            char_data = Some(String::new());
+                            quoted_data = Some(Vec::new());
                             self.state = State::Quote;
                         }
                         Some(';') => self.state = State::Comment { is_list: false },
@@ -123,16 +123,14 @@ impl<'a> Lexer<'a> {
                         Some('"') => {
                             self.state = State::RestOfLine;
                             self.txt.next();
-                            return Ok(Some(Token::CharData(
-                                char_data.take().unwrap_or_else(|| "".into()),
-                            )));
+                            return Ok(Some(Token::Quoted(quoted_data.take().unwrap_or_default())));
                         }
                         Some('\\') => {
-                            Self::push_to_str(&mut char_data, self.escape_seq()?)?;
+                            self.escape_seq(&mut quoted_data)?;
                         }
                         Some(ch) => {
                             self.txt.next();
-                            Self::push_to_str(&mut char_data, ch)?;
+                            Self::push_to_bytes(&mut quoted_data, ch)?;
                         }
                         None => return Err(LexerErrorKind::UnclosedQuotedString.into()),
                     }
@@ -144,6 +142,11 @@ impl<'a> Lexer<'a> {
                             self.txt.next();
                             Self::push_to_str(&mut char_data, ch.unwrap())?;
                         }
+                        // a $ without a name is char data, e.g. the iterator of $GENERATE
+                        Some(_) | None if char_data.as_ref().map_or(false, String::is_empty) => {
+                            char_data = Some("$".to_string());
+                            self.state = State::CharData { is_list: false };
+                        }
                         // finishes the Dollar...
                         Some(_) | None => {
                             self.state = State::RestOfLine;
@@ -160,10 +163,10 @@ impl<'a> Lexer<'a> {
                                 return Ok(Some(Token::Origin));
                             } else if "TTL" == dollar {
                                 return Ok(Some(Token::Ttl));
+                            } else if "GENERATE" == dollar {
+                                return Ok(Some(Token::Generate));
                             } else {
-                                return Err(LexerErrorKind::UnrecognizedDollar(
-                                    char_data.take().unwrap_or_else(|| "".into()),
-                                ).into());
+                                return Err(LexerErrorKind::UnrecognizedDollar(dollar).into());
                             }
                         }
                     }
@@ -276,7 +279,15 @@ impl<'a> Lexer<'a> {
             })
     }
 
-    fn escape_seq(&mut self) -> LexerResult<char> {
+    fn push_to_bytes(collect: &mut Option<Vec<u8>>, ch: char) -> LexerResult<()> {
+        collect
+            .as_mut()
+            .ok_or_else(|| LexerErrorKind::IllegalState("collect is None").into())
+            .map(|bytes| bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()))
+    }
+
+    /// Pushes the escaped octet, \DDD, or character, \X, to the quoted string
+    fn escape_seq(&mut self, collect: &mut Option<Vec<u8>>) -> LexerResult<()> {
         // escaped character, let's decode it.
         self.txt.next(); // consume the escape
         let ch = self
@@ -285,7 +296,7 @@ impl<'a> Lexer<'a> {
 
         if !ch.is_control() {
             if ch.is_numeric() {
-                // in this case it's an escaped octal: \DDD
+                // in this case it's an escaped decimal octet: \DDD
                 let d1: u32 = self
                     .txt
                     .next()
@@ -311,15 +322,19 @@ impl<'a> Lexer<'a> {
                             .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
                     })??; // gobble
 
-                let val: u32 = (d1 << 16) + (d2 << 8) + d3;
-                let ch: char = char::from_u32(val)
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::UnrecognizedOctet(val)))?;
+                let val: u32 = d1 * 100 + d2 * 10 + d3;
+                if val > 0xFF {
+                    return Err(LexerErrorKind::UnrecognizedOctet(val).into());
+                }
 
-                Ok(ch)
+                collect
+                    .as_mut()
+                    .ok_or_else(|| LexerErrorKind::IllegalState("collect is None").into())
+                    .map(|bytes| bytes.push(val as u8))
             } else {
                 // this is an escaped char: \X
                 self.txt.next(); // gobble the char
-                Ok(ch)
+                Self::push_to_bytes(collect, ch)
             }
         } else {
             Err(LexerErrorKind::IllegalCharacter(ch).into())
@@ -355,8 +370,10 @@ pub enum Token {
     Blank,
     /// (..) TODO, this is probably wrong, List maybe should just skip line endings
     List(Vec<String>),
-    /// [a-zA-Z, non-control utf8, ., -, 0-9]+, ".*"
+    /// [a-zA-Z, non-control utf8, ., -, 0-9]+
     CharData(String),
+    /// ".*", any octet can be escaped as \DDD, so this is not necessarily utf8
+    Quoted(Vec<u8>),
     /// @
     At,
     /// $INCLUDE
@@ -365,6 +382,8 @@ pub enum Token {
     Origin,
     /// $TTL
     Ttl,
+    /// $GENERATE
+    Generate,
     /// \n or \r\n
     EOL,
 }
@@ -461,38 +480,43 @@ mod lex_test {
     fn quoted_txt() {
         assert_eq!(
             Lexer::new("\"Quoted\"").next_token().unwrap().unwrap(),
-            Token::CharData("Quoted".to_string())
+            Token::Quoted(b"Quoted".to_vec())
         );
         assert_eq!(
             Lexer::new("\";@$\"").next_token().unwrap().unwrap(),
-            Token::CharData(";@$".to_string())
+            Token::Quoted(b";@$".to_vec())
         );
         assert_eq!(
             Lexer::new("\"some \\A\"").next_token().unwrap().unwrap(),
-            Token::CharData("some A".to_string())
+            Token::Quoted(b"some A".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\Aa\"").next_token().unwrap().unwrap(),
-            Token::CharData("aAa".to_string())
+            Token::Quoted(b"aAa".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\$\"").next_token().unwrap().unwrap(),
-            Token::CharData("a$".to_string())
+            Token::Quoted(b"a$".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\077\"").next_token().unwrap().unwrap(),
-            Token::CharData("a\u{707}".to_string())
+            Token::Quoted(b"aM".to_vec())
+        );
+        assert_eq!(
+            Lexer::new("\"a\\000b\"").next_token().unwrap().unwrap(),
+            Token::Quoted(b"a\0b".to_vec())
         );
 
         assert!(Lexer::new("\"a\\\"").next_token().is_err());
         assert!(Lexer::new("\"a\\0\"").next_token().is_err());
         assert!(Lexer::new("\"a\\07\"").next_token().is_err());
+        assert!(Lexer::new("\"a\\256\"").next_token().is_err());
 
         let mut lexer = Lexer::new("\"multi\nline\ntext\"");
 
         assert_eq!(
             next_token(&mut lexer).unwrap(),
-            Token::CharData("multi\nline\ntext".to_string())
+            Token::Quoted(b"multi\nline\ntext".to_vec())
         );
         assert_eq!(next_token(&mut lexer), None);
 
@@ -500,12 +524,18 @@ mod lex_test {
 
         assert_eq!(
             next_token(&mut lexer).unwrap(),
-            Token::CharData("multi\r\nline\r\ntext".to_string())
+            Token::Quoted(b"multi\r\nline\r\ntext".to_vec())
         );
         assert_eq!(next_token(&mut lexer).unwrap(), Token::EOL);
         assert_eq!(next_token(&mut lexer), None);
 
         assert!(Lexer::new("\"multi").next_token().is_err());
+
+        // octets above 127 are not characters
+        assert_eq!(
+            Lexer::new("\"\\255\\000\"").next_token().unwrap().unwrap(),
+            Token::Quoted(vec![0xFF, 0x00])
+        );
     }
 
     #[test]
@@ -551,6 +581,11 @@ mod lex_test {
             Token::Origin
         );
         assert_eq!(next_token(&mut Lexer::new("$TTL")).unwrap(), Token::Ttl);
+        assert_eq!(
+            next_token(&mut Lexer::new("$GENERATE")).unwrap(),
+            Token::Generate
+        );
+        assert!(Lexer::new("$UNKNOWN").next_token().is_err());
         assert_eq!(next_token(&mut Lexer::new("\n")), Some(Token::EOL));
         assert_eq!(next_token(&mut Lexer::new("\r\n")), Some(Token::EOL));
     }
@@ -759,4 +794,32 @@ $INCLUDE <SUBSYS>ISI-MAILBOXES.TXT",
         );
         assert!(next_token(&mut lexer).is_none());
     }
+
+    #[test]
+    fn generate() {
+        let mut lexer = Lexer::new("$GENERATE 1-10 $ PTR host-${0,3,d}.example.com.");
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::Generate);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("1-10".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("$".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("PTR".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("host-${0,3,d}.example.com.".to_string())
+        );
+        assert!(next_token(&mut lexer).is_none());
+
+        assert_eq!(
+            next_token(&mut Lexer::new("${1,2,x}")).unwrap(),
+            Token::CharData("${1,2,x}".to_string())
+        );
+    }
 }
@@ -16,8 +16,10 @@
 
 //! record data enum variants
 
+use std::str;
+
 use error::*;
-use rr::rdata::DNSSECRecordType;
+use rr::rdata::{DNSSECRData, DNSSECRecordType};
 use rr::{Name, RData, RecordType};
 use serialize::txt::rdata_parsers::*;
 
@@ -45,6 +47,16 @@ impl RDataParser for RData {
             RecordType::AXFR => panic!("parsing AXFR doesn't make sense"), // valid panic, never should happen
             RecordType::CAA => caa::parse(tokens).map(RData::CAA)?,
             RecordType::CNAME => RData::CNAME(name::parse(tokens, origin)?),
+            RecordType::DHCID => RData::DHCID(dhcid::parse(tokens)?),
+            RecordType::DNAME => RData::DNAME(name::parse(tokens, origin)?),
+            RecordType::HTTPS | RecordType::SVCB => {
+                return Err(ParseErrorKind::Msg(format!(
+                    "parsing {} is not supported",
+                    record_type
+                ))
+                .into())
+            }
+            RecordType::HINFO => RData::HINFO(hinfo::parse(tokens)?),
             RecordType::IXFR => panic!("parsing IXFR doesn't make sense"), // valid panic, never should happen
             RecordType::MX => RData::MX(mx::parse(tokens, origin)?),
             RecordType::NAPTR => RData::NAPTR(naptr::parse(tokens, origin)?),
@@ -56,18 +68,16 @@ impl RDataParser for RData {
             RecordType::SOA => RData::SOA(soa::parse(tokens, origin)?),
             RecordType::SRV => RData::SRV(srv::parse(tokens, origin)?),
             RecordType::SSHFP => RData::SSHFP(sshfp::parse(tokens)?),
+            RecordType::TKEY => panic!("parsing TKEY doesn't make sense"), // valid panic, never should happen
             RecordType::TLSA => RData::TLSA(tlsa::parse(tokens)?),
-            RecordType::TXT => RData::TXT(txt::parse(tokens)?),
+            RecordType::TXT => RData::TXT(txt::parse(tokens.map(str::as_bytes))?),
+            RecordType::URI => RData::URI(uri::parse(tokens)?),
             RecordType::DNSSEC(DNSSECRecordType::SIG) => panic!("parsing SIG doesn't make sense"), // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => {
-                panic!("DNSKEY should be dynamically generated")
-            } // valid panic, never should happen
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => RData::from(dnskey::parse(tokens)?),
             RecordType::DNSSEC(DNSSECRecordType::KEY) => {
                 panic!("KEY should be dynamically generated")
             } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::DS) => {
-                panic!("DS should be dynamically generated")
-            } // valid panic, never should happen
+            RecordType::DNSSEC(DNSSECRecordType::DS) => RData::from(ds::parse(tokens)?),
             RecordType::DNSSEC(DNSSECRecordType::NSEC) => {
                 panic!("NSEC should be dynamically generated")
             } // valid panic, never should happen
@@ -78,8 +88,9 @@ impl RDataParser for RData {
                 panic!("NSEC3PARAM should be dynamically generated")
             } // valid panic, never should happen
             RecordType::DNSSEC(DNSSECRecordType::RRSIG) => {
-                panic!("RRSIG should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::SIG(sig::parse(tokens, origin)?))
+            }
+            RecordType::DNSSEC(DNSSECRecordType::TSIG) => panic!("parsing TSIG doesn't make sense"), // valid panic, never should happen
             RecordType::DNSSEC(DNSSECRecordType::Unknown(code)) => {
                 panic!("Unknown dnssec record type, if you want to support this type, please file an issue against Trust-DNS: {}", code)
             } // valid panic, never should happen
@@ -93,3 +104,29 @@ impl RDataParser for RData {
         Ok(rdata)
     }
 }
+
+/// Parse the RData from a set of Tokens, which may contain any octet, e.g. from quoted strings
+///
+/// Only TXT keeps the octets as they are, the tokens of all other record types must be UTF-8.
+pub(crate) fn parse_bytes<'i, I: Iterator<Item = &'i [u8]>>(
+    record_type: RecordType,
+    tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<RData> {
+    if let RecordType::TXT = record_type {
+        return txt::parse(tokens).map(RData::TXT);
+    }
+
+    let tokens = tokens
+        .map(|token| {
+            str::from_utf8(token).map_err(|e| {
+                ParseError::from(ParseErrorKind::Msg(format!(
+                    "{} data is not valid UTF-8: {}",
+                    record_type, e
+                )))
+            })
+        })
+        .collect::<ParseResult<Vec<&str>>>()?;
+
+    RData::parse(record_type, tokens.into_iter(), origin)
+}
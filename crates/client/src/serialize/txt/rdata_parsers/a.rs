@@ -30,3 +30,24 @@ pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<Ipv4
         .and_then(|s| Ipv4Addr::from_str(s).map_err(Into::into))?;
     Ok(address)
 }
+
+#[test]
+fn test_parsing() {
+    use rr::{RData, RecordType};
+    use serialize::txt::parse_rdata::RDataParser;
+
+    assert_eq!(
+        parse(vec!["192.0.2.1"].into_iter()).unwrap(),
+        Ipv4Addr::new(192, 0, 2, 1)
+    );
+    assert_eq!(
+        RData::parse(RecordType::A, vec!["192.0.2.1"].into_iter(), None).unwrap(),
+        RData::A(Ipv4Addr::new(192, 0, 2, 1))
+    );
+
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["192.0.2"].into_iter()).is_err());
+    assert!(parse(vec!["192.0.2.256"].into_iter()).is_err());
+    assert!(parse(vec!["2001:db8::1"].into_iter()).is_err());
+    assert!(parse(vec!["www.example.com."].into_iter()).is_err());
+}
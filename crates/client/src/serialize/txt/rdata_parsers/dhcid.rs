@@ -0,0 +1,63 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DHCID records for DHCP client identifiers
+
+use error::*;
+use rr::rdata::DHCID;
+
+/// Parse the RData from a set of tokens.
+///
+/// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.2)
+///
+/// ```text
+/// 3.2.  DHCID Presentation Format
+///
+///    In DNS master files, the RDATA is represented as a single block in
+///    base-64 encoding identical to that used for representing binary data
+///    in [3], Section 3.  The data may be divided up into any number of
+///    white-space-separated substrings, each of which is itself a valid
+///    base-64 encoding, that are concatenated to obtain the full signature.
+///    These substrings can span lines using the standard parentheses.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<DHCID> {
+    let mut rdata = Vec::new();
+    for token in tokens {
+        rdata.extend(::data_encoding::BASE64.decode(token.as_bytes())?);
+    }
+
+    if rdata.len() < 3 {
+        return Err(ParseErrorKind::Message("DHCID rdata must be at least 3 octets").into());
+    }
+
+    let identifier_type = u16::from_be_bytes([rdata[0], rdata[1]]);
+    let digest_type = rdata[2];
+    let digest = rdata.split_off(3);
+
+    Ok(DHCID::new(identifier_type, digest_type, digest))
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["äöüäööüä"].into_iter()).is_err());
+    assert!(parse(vec!["AAE="].into_iter()).is_err());
+
+    // RFC 4701, section 3.6.1
+    let dhcid = parse(vec!["AAIBY2/AuCccgoJbsaxcQc9TUapptP69lOjxfNuVAA2kjEA="].into_iter())
+        .expect("failed to parse DHCID");
+    assert_eq!(dhcid.identifier_type(), 2);
+    assert_eq!(dhcid.digest_type(), 1);
+    assert_eq!(dhcid.digest().len(), 32);
+
+    // the base64 may be split across whitespace
+    assert_eq!(
+        parse(vec!["AAIBY2/AuCccgoJbsaxcQc9TUapptP69", "lOjxfNuVAA2kjEA="].into_iter())
+            .expect("failed to parse split DHCID"),
+        dhcid
+    );
+}
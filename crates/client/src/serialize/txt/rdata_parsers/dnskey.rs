@@ -0,0 +1,185 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNSKEY records for the public keys of signed zones
+//!
+//! [RFC 4034](https://tools.ietf.org/html/rfc4034#section-2.3)
+//!
+//! ```text
+//! 2.3.  DNSKEY RR Example
+//!
+//!    The following DNSKEY RR stores a DNS zone key for example.com.
+//!
+//!    example.com. 86400 IN DNSKEY 256 3 5 ( AQPSKmynfzW4kyBv015MUG2DeIQ3
+//!                                              Cbl+BBZH4b/0PY1kxkmvHjcZc8no
+//!                                              kfzj31GajIQKY+5CptLr3buXA10h
+//!                                              WqTkF7H6RfoRqXQeogmMHfpftf6z
+//!                                              Mv1LyBUgia7za6ZEzOJBOztyvhjL
+//!                                              742iU/TpPSEDhm2SNKLijfUppn1U
+//!                                              aNvv4w==  )
+//!
+//!    The first four text fields specify the owner name, TTL, Class, and RR
+//!    type (DNSKEY).  Value 256 indicates that the Zone Key bit (bit 7) in
+//!    the Flags field has value 1.  Value 3 is the fixed Protocol value.
+//!    Value 5 indicates the public key algorithm.  Appendix A.1 identifies
+//!    algorithm type 5 as RSA/SHA1 and indicates that the format of the
+//!    RSA/SHA1 public key field is defined in [RFC3110].  The remaining
+//!    text is a Base64 encoding of the public key.
+//! ```
+
+use error::*;
+use rr::dnssec::Algorithm;
+use rr::rdata::DNSKEY;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034](https://tools.ietf.org/html/rfc4034#section-2.2)
+///
+/// ```text
+/// 2.2.  The DNSKEY RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Flag field MUST be represented as an unsigned decimal integer.
+///    Given the currently defined flags, the possible values are: 0, 256,
+///    and 257.
+///
+///    The Protocol Field MUST be represented as an unsigned decimal integer
+///    with a value of 3.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic as specified in Appendix A.1.
+///
+///    The Public Key field MUST be represented as a Base64 encoding of the
+///    Public Key.  Whitespace is allowed within the Base64 text.  For a
+///    definition of Base64 encoding, see [RFC3548].
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DNSKEY> {
+    fn missing_field<E: From<ParseErrorKind>>(field: &str) -> E {
+        ParseErrorKind::Msg(format!("DNSKEY {} field missing", field)).into()
+    }
+
+    let flags: u16 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("flags"))?
+        .parse()?;
+
+    // the reserved bits are ignored, as they are when reading the wire format
+    let zone_key = flags & 0b0000_0001_0000_0000 != 0;
+    let secure_entry_point = flags & 0b0000_0000_0000_0001 != 0;
+    let revoke = flags & 0b0000_0000_1000_0000 != 0;
+
+    let protocol: u8 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("protocol"))?
+        .parse()?;
+    if protocol != 3 {
+        return Err(ParseErrorKind::Msg(format!("DNSKEY protocol must be 3: {}", protocol)).into());
+    }
+
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("algorithm"))
+        .and_then(parse_algorithm)?;
+
+    // the key may be split across any number of tokens, e.g. inside of parentheses
+    let encoded_public_key: String = tokens
+        .flat_map(str::chars)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if encoded_public_key.is_empty() {
+        return Err(missing_field("public key"));
+    }
+    let public_key = ::data_encoding::BASE64.decode(encoded_public_key.as_bytes())?;
+
+    Ok(DNSKEY::new(
+        zone_key,
+        secure_entry_point,
+        revoke,
+        algorithm,
+        public_key,
+    ))
+}
+
+/// Parses the algorithm number or mnemonic, see [RFC 4034](https://tools.ietf.org/html/rfc4034#appendix-A.1)
+pub(crate) fn parse_algorithm(token: &str) -> ParseResult<Algorithm> {
+    if let Ok(value) = token.parse::<u8>() {
+        return Ok(Algorithm::from_u8(value));
+    }
+
+    [
+        Algorithm::RSASHA1,
+        Algorithm::RSASHA1NSEC3SHA1,
+        Algorithm::RSASHA256,
+        Algorithm::RSASHA512,
+        Algorithm::ECDSAP256SHA256,
+        Algorithm::ECDSAP384SHA384,
+        Algorithm::ED25519,
+    ]
+    .iter()
+    .cloned()
+    .find(|algorithm| algorithm.as_str().eq_ignore_ascii_case(token))
+    .ok_or_else(|| ParseErrorKind::Msg(format!("unknown algorithm: {}", token)).into())
+}
+
+#[test]
+fn test_parsing() {
+    use rr::rdata::dnskey;
+    use serialize::binary::BinEncoder;
+
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["256", "3", "5"].into_iter()).is_err());
+    assert!(parse(vec!["256", "2", "5", "AQPSKmynfzW4kyBv015MUG2DeIQ3"].into_iter()).is_err());
+    assert!(parse(vec!["256", "3", "FOO", "AQPSKmynfzW4kyBv015MUG2DeIQ3"].into_iter()).is_err());
+    assert!(parse(vec!["65536", "3", "5", "AQPSKmynfzW4kyBv015MUG2DeIQ3"].into_iter()).is_err());
+    assert!(parse(vec!["256", "3", "5", "äöüäööüä"].into_iter()).is_err());
+
+    // RFC 4034, section 2.3
+    let encoded_public_key = vec![
+        "AQPSKmynfzW4kyBv015MUG2DeIQ3",
+        "Cbl+BBZH4b/0PY1kxkmvHjcZc8no",
+        "kfzj31GajIQKY+5CptLr3buXA10h",
+        "WqTkF7H6RfoRqXQeogmMHfpftf6z",
+        "Mv1LyBUgia7za6ZEzOJBOztyvhjL",
+        "742iU/TpPSEDhm2SNKLijfUppn1U",
+        "aNvv4w==",
+    ];
+    let public_key = ::data_encoding::BASE64
+        .decode(encoded_public_key.concat().as_bytes())
+        .unwrap();
+
+    let tokens = vec!["256", "3", "5"];
+    let rdata = parse(tokens.into_iter().chain(encoded_public_key.iter().cloned()))
+        .expect("failed to parse DNSKEY");
+    assert!(rdata.zone_key());
+    assert!(!rdata.secure_entry_point());
+    assert!(!rdata.revoke());
+    assert_eq!(rdata.algorithm(), Algorithm::RSASHA1);
+    assert_eq!(rdata.public_key(), &public_key[..]);
+
+    // the RRSIG example of section 3.3 is signed with this key
+    assert_eq!(rdata.calculate_key_tag().unwrap(), 2642);
+
+    let mut bytes = Vec::new();
+    dnskey::emit(&mut BinEncoder::new(&mut bytes), &rdata).unwrap();
+    let mut expected = vec![0x01, 0x00, 3, 5];
+    expected.extend_from_slice(&public_key);
+    assert_eq!(bytes, expected);
+
+    // the mnemonic is accepted for the algorithm, and whitespace within the key is ignored
+    let rdata = parse(vec!["257", "3", "rsasha1", "AQPSKmynfzW4 kyBv015MUG2DeIQ3"].into_iter())
+        .expect("failed to parse DNSKEY");
+    assert!(rdata.zone_key());
+    assert!(rdata.secure_entry_point());
+    assert_eq!(rdata.algorithm(), Algorithm::RSASHA1);
+    assert_eq!(rdata.public_key(), &public_key[..21]);
+
+    let rdata = parse(vec!["385", "3", "8", "AQPSKmynfzW4kyBv015MUG2DeIQ3"].into_iter())
+        .expect("failed to parse DNSKEY");
+    assert!(rdata.revoke());
+    assert_eq!(rdata.algorithm(), Algorithm::RSASHA256);
+}
@@ -0,0 +1,126 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DS records for delegation signers, the digests of the keys of child zones
+
+use proto::serialize::hex;
+
+use error::*;
+use rr::dnssec::DigestType;
+use rr::rdata::DS;
+
+use super::dnskey::parse_algorithm;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034](https://tools.ietf.org/html/rfc4034#section-5.3)
+///
+/// ```text
+/// 5.3.  The DS RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic specified in Appendix A.1.
+///
+///    The Digest Type field MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Digest MUST be represented as a sequence of case-insensitive
+///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
+///    text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DS> {
+    fn missing_field<E: From<ParseErrorKind>>(field: &str) -> E {
+        ParseErrorKind::Msg(format!("DS {} field missing", field)).into()
+    }
+
+    let key_tag: u16 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("key tag"))?
+        .parse()?;
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("algorithm"))
+        .and_then(parse_algorithm)?;
+    let digest_type: u8 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("digest type"))?
+        .parse()?;
+    let digest_type = DigestType::from_u8(digest_type)?;
+
+    // the digest may be split across any number of tokens
+    let digest = hex::decode(&tokens.collect::<String>())?;
+    if digest.is_empty() {
+        return Err(missing_field("digest"));
+    }
+
+    Ok(DS::new(key_tag, algorithm, digest_type, digest))
+}
+
+#[test]
+fn test_parsing() {
+    use proto::serialize::binary::{BinDecoder, BinEncoder, Restrict};
+    use rr::dnssec::Algorithm;
+    use rr::rdata::ds;
+
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["60485", "5", "1"].into_iter()).is_err());
+    assert!(parse(vec!["60485", "FOO", "1", "2BB183AF"].into_iter()).is_err());
+    assert!(parse(vec!["60485", "5", "3", "2BB183AF"].into_iter()).is_err());
+    assert!(parse(vec!["65536", "5", "1", "2BB183AF"].into_iter()).is_err());
+    assert!(parse(vec!["60485", "5", "1", "2BB183AG"].into_iter()).is_err());
+    assert!(parse(vec!["60485", "5", "1", "-"].into_iter()).is_err());
+
+    // RFC 4034, section 5.4
+    let rdata = parse(
+        vec![
+            "60485",
+            "5",
+            "1",
+            "2BB183AF5F22588179A53B0A98631FAD1A292118",
+        ]
+        .into_iter(),
+    )
+    .expect("failed to parse DS");
+    assert_eq!(rdata.key_tag(), 60485);
+    assert_eq!(*rdata.algorithm(), Algorithm::RSASHA1);
+    assert_eq!(rdata.digest_type(), DigestType::SHA1);
+    assert_eq!(rdata.digest().len(), 20);
+    assert_eq!(
+        rdata.to_string(),
+        "60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118"
+    );
+
+    let mut bytes = Vec::new();
+    ds::emit(&mut BinEncoder::new(&mut bytes), &rdata).unwrap();
+    let read_rdata = ds::read(
+        &mut BinDecoder::new(&bytes),
+        Restrict::new(bytes.len() as u16),
+    )
+    .expect("failed to read DS");
+    assert_eq!(read_rdata, rdata);
+
+    // the mnemonic is accepted for the algorithm, the digest may be lowercase and split
+    let split = parse(
+        vec![
+            "60485",
+            "RSASHA1",
+            "1",
+            "2bb183af5f22588179a5",
+            "3b0a98631fad1a292118",
+        ]
+        .into_iter(),
+    )
+    .expect("failed to parse DS");
+    assert_eq!(split, rdata);
+
+    let printed = rdata.to_string();
+    assert_eq!(parse(printed.split_whitespace()).unwrap(), rdata);
+}
@@ -0,0 +1,51 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HINFO records for host information
+
+use error::*;
+use rr::rdata::HINFO;
+
+/// Parse the RData from a set of Tokens
+///
+/// ```text
+/// IN HINFO "x86_64" "Linux"
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<HINFO> {
+    let cpu = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("cpu".to_string())))
+        .map(|s| s.as_bytes().to_vec().into_boxed_slice())?;
+
+    let os = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("os".to_string())))
+        .map(|s| s.as_bytes().to_vec().into_boxed_slice())?;
+
+    if tokens.next().is_some() {
+        return Err(ParseErrorKind::Message("too many fields for HINFO").into());
+    }
+
+    Ok(HINFO::new(cpu, os))
+}
+
+#[test]
+fn test_parsing() {
+    let tokens = super::tokenize("\"x86_64\" \"Linux\"");
+    let hinfo = parse(tokens.iter().map(String::as_str)).expect("failed to parse HINFO");
+    assert_eq!(hinfo.cpu(), b"x86_64");
+    assert_eq!(hinfo.os(), b"Linux");
+
+    let tokens = super::tokenize("\"RFC8482\" \"\"");
+    assert_eq!(
+        parse(tokens.iter().map(String::as_str)).expect("failed to parse HINFO"),
+        HINFO::rfc8482()
+    );
+
+    assert!(parse(vec!["x86_64"].into_iter()).is_err());
+    assert!(parse(vec!["x86_64", "Linux", "extra"].into_iter()).is_err());
+}
@@ -22,13 +22,36 @@
 pub mod a;
 pub mod aaaa;
 pub mod caa;
+pub mod dhcid;
+pub mod dnskey;
+pub mod ds;
+pub mod hinfo;
 pub mod mx;
 pub mod name;
 pub mod naptr;
 pub mod null;
 pub mod openpgpkey;
+pub mod sig;
 pub mod soa;
 pub mod srv;
 pub mod sshfp;
 pub mod tlsa;
 pub mod txt;
+pub mod uri;
+
+/// Splits the rdata of a master file line into the tokens the parsers receive
+#[cfg(test)]
+fn tokenize(rdata: &str) -> Vec<String> {
+    use serialize::txt::{Lexer, Token};
+
+    let mut lexer = Lexer::new(rdata);
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token().expect("failed to lex rdata") {
+        match token {
+            Token::CharData(data) => tokens.push(data),
+            Token::Quoted(data) => tokens.push(String::from_utf8(data).expect("not utf8")),
+            token => panic!("unexpected token: {:?}", token),
+        }
+    }
+    tokens
+}
@@ -94,6 +94,37 @@ fn test_parsing() {
     );
 }
 
+#[test]
+fn test_parsing_sip() {
+    // the regexp keeps its delimiters, and the escaped backslashes survive as single ones
+    let tokens = super::tokenize(
+        "100 10 \"u\" \"E2U+sip\" \"!^\\\\+44111555(.+)$!sip:7\\\\1@sip.example.com!\" .",
+    );
+    let naptr = parse(tokens.iter().map(String::as_str), None).expect("failed to parse NAPTR");
+    assert_eq!(naptr.order(), 100);
+    assert_eq!(naptr.preference(), 10);
+    assert_eq!(naptr.flags(), b"u");
+    assert_eq!(naptr.services(), b"E2U+sip");
+    assert_eq!(
+        naptr.regexp(),
+        &b"!^\\+44111555(.+)$!sip:7\\1@sip.example.com!"[..]
+    );
+    assert!(naptr.replacement().is_root());
+
+    // an empty regexp, with the replacement relative to the origin
+    let tokens = super::tokenize("100 50 \"s\" \"SIP+D2U\" \"\" _sip._udp");
+    let naptr = parse(
+        tokens.iter().map(String::as_str),
+        Some(&Name::from_str("example.com.").unwrap()),
+    )
+    .expect("failed to parse NAPTR");
+    assert!(naptr.regexp().is_empty());
+    assert_eq!(
+        *naptr.replacement(),
+        Name::from_str("_sip._udp.example.com.").unwrap()
+    );
+}
+
 #[test]
 fn test_parsing_fails() {
     // IN NAPTR 100  50  "a"    "z3950+N2L+N2C"     ""   cidserver.example.com.
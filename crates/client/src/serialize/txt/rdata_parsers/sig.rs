@@ -0,0 +1,255 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! RRSIG records for the signatures over RRSets of signed zones
+//!
+//! [RFC 4034](https://tools.ietf.org/html/rfc4034#section-3.3)
+//!
+//! ```text
+//! 3.3.  RRSIG RR Example
+//!
+//!    The following RRSIG RR stores the signature for the A RRset of
+//!    host.example.com:
+//!
+//!    host.example.com. 86400 IN RRSIG A 5 3 86400 20030322173103 (
+//!                                   20030220173103 2642 example.com.
+//!                                   oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTr
+//!                                   PYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o
+//!                                   B9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3t
+//!                                   GNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkG
+//!                                   J5D6fwFm8nN+6pBzeDQfsS3Ap3o= )
+//! ```
+
+use std::str::FromStr;
+
+use error::*;
+use rr::dnssec::parse_timestamp;
+use rr::rdata::SIG;
+use rr::{Name, RecordType};
+
+use super::dnskey::parse_algorithm;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034](https://tools.ietf.org/html/rfc4034#section-3.2)
+///
+/// ```text
+/// 3.2.  The RRSIG RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Type Covered field is represented as an RR type mnemonic.  When
+///    the mnemonic is not known, the TYPE representation as described in
+///    [RFC3597], Section 5, MUST be used.
+///
+///    The Algorithm field value MUST be represented either as an unsigned
+///    decimal integer or as an algorithm mnemonic, as specified in Appendix
+///    A.1.
+///
+///    The Labels field value MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Original TTL field value MUST be represented as an unsigned
+///    decimal integer.
+///
+///    The Signature Expiration Time and Inception Time field values MUST be
+///    represented either as an unsigned decimal integer indicating seconds
+///    since 1 January 1970 00:00:00 UTC, or in the form YYYYMMDDHHmmSS in
+///    UTC, where:
+///
+///       YYYY is the year (0001-9999, but see Section 3.1.5);
+///       MM is the month number (01-12);
+///       DD is the day of the month (01-31);
+///       HH is the hour, in 24 hour notation (00-23);
+///       mm is the minute (00-59); and
+///       SS is the second (00-59).
+///
+///    Note that it is always possible to distinguish between these two
+///    formats because the YYYYMMDDHHmmSS format will always be exactly 14
+///    digits, while the decimal representation of a 32-bit unsigned
+///    integer can never be longer than 10 digits.
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Signer's Name field value MUST be represented as a domain name.
+///
+///    The Signature field is represented as a Base64 encoding of the
+///    signature.  Whitespace is allowed within the Base64 text.  See
+///    Section 3.1.8.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<SIG> {
+    fn missing_field<E: From<ParseErrorKind>>(field: &str) -> E {
+        ParseErrorKind::Msg(format!("RRSIG {} field missing", field)).into()
+    }
+
+    let type_covered = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("type covered"))
+        .and_then(|token| RecordType::from_str(token).map_err(ParseError::from))?;
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("algorithm"))
+        .and_then(parse_algorithm)?;
+    let num_labels: u8 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("labels"))?
+        .parse()?;
+    let original_ttl: u32 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("original ttl"))?
+        .parse()?;
+    let sig_expiration = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("signature expiration"))
+        .and_then(parse_time)?;
+    let sig_inception = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("signature inception"))
+        .and_then(parse_time)?;
+    let key_tag: u16 = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("key tag"))?
+        .parse()?;
+    let signer_name = tokens
+        .next()
+        .ok_or_else(|| missing_field::<ParseError>("signer's name"))
+        .and_then(|token| Name::parse(token, origin).map_err(ParseError::from))?;
+
+    // the signature may be split across any number of tokens, e.g. inside of parentheses
+    let encoded_sig: String = tokens
+        .flat_map(str::chars)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if encoded_sig.is_empty() {
+        return Err(missing_field("signature"));
+    }
+    let sig = ::data_encoding::BASE64.decode(encoded_sig.as_bytes())?;
+
+    Ok(SIG::new(
+        type_covered,
+        algorithm,
+        num_labels,
+        original_ttl,
+        sig_expiration,
+        sig_inception,
+        key_tag,
+        signer_name,
+        sig,
+    ))
+}
+
+/// Parses the signature expiration or inception, either in seconds since the epoch or as
+///  `YYYYMMDDHHmmSS` in UTC
+fn parse_time(token: &str) -> ParseResult<u32> {
+    if token.len() == 14 {
+        parse_timestamp(token).map_err(ParseError::from)
+    } else {
+        token.parse().map_err(ParseError::from)
+    }
+}
+
+#[test]
+fn test_parse_time() {
+    assert_eq!(parse_time("20030322173103").unwrap(), 1_048_354_263);
+    assert_eq!(parse_time("20240229235959").unwrap(), 1_709_251_199);
+    assert!(parse_time("20230229000000").is_err());
+
+    // seconds since the epoch
+    assert_eq!(parse_time("1048354263").unwrap(), 1_048_354_263);
+    assert_eq!(parse_time("0").unwrap(), 0);
+    assert!(parse_time("4294967296").is_err());
+    assert!(parse_time("2023010100000").is_err());
+}
+
+#[test]
+fn test_parsing() {
+    use proto::serialize::binary::{BinDecoder, BinEncoder, Restrict};
+    use rr::dnssec::Algorithm;
+    use rr::rdata::sig;
+
+    // RFC 4034, section 3.3
+    let tokens = super::tokenize(
+        "A 5 3 86400 20030322173103 20030220173103 2642 example.com. \
+         oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTr PYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o \
+         B9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3t GNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkG \
+         J5D6fwFm8nN+6pBzeDQfsS3Ap3o=",
+    );
+    let rdata = parse(tokens.iter().map(String::as_str), None).expect("failed to parse RRSIG");
+    assert_eq!(rdata.type_covered(), RecordType::A);
+    assert_eq!(rdata.algorithm(), Algorithm::RSASHA1);
+    assert_eq!(rdata.num_labels(), 3);
+    assert_eq!(rdata.original_ttl(), 86400);
+    assert_eq!(rdata.sig_expiration(), 1_048_354_263);
+    assert_eq!(rdata.sig_inception(), 1_045_762_263);
+    assert_eq!(rdata.key_tag(), 2642);
+    assert_eq!(
+        rdata.signer_name(),
+        &Name::from_str("example.com.").unwrap()
+    );
+    assert_eq!(rdata.sig().len(), 128);
+
+    // parse -> emit -> read
+    let mut bytes = Vec::new();
+    sig::emit(&mut BinEncoder::new(&mut bytes), &rdata).unwrap();
+    let mut decoder = BinDecoder::new(&bytes);
+    let read = sig::read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+    assert_eq!(read, rdata);
+
+    // the times may be in seconds, the signer relative to the origin
+    let origin = Name::from_str("example.com.").unwrap();
+    let rdata = parse(
+        vec![
+            "MX",
+            "8",
+            "2",
+            "3600",
+            "1048354263",
+            "1045762263",
+            "12345",
+            "sub",
+            "AAEC",
+        ]
+        .into_iter(),
+        Some(&origin),
+    )
+    .expect("failed to parse RRSIG");
+    assert_eq!(rdata.type_covered(), RecordType::MX);
+    assert_eq!(rdata.algorithm(), Algorithm::RSASHA256);
+    assert_eq!(rdata.sig_expiration(), 1_048_354_263);
+    assert_eq!(rdata.sig_inception(), 1_045_762_263);
+    assert_eq!(
+        rdata.signer_name(),
+        &Name::from_str("sub.example.com.").unwrap()
+    );
+    assert_eq!(rdata.sig(), &[0, 1, 2]);
+
+    assert!(parse(::std::iter::empty(), None).is_err());
+    let valid = [
+        "A",
+        "8",
+        "2",
+        "3600",
+        "20230101000000",
+        "20221201000000",
+        "12345",
+        "example.com.",
+    ];
+    assert!(parse(valid.iter().cloned(), None).is_err());
+    assert!(parse(valid.iter().cloned().chain(Some("äöü")), None).is_err());
+    let mut invalid = valid;
+    invalid[0] = "FOO";
+    assert!(parse(invalid.iter().cloned().chain(Some("AAEC")), None).is_err());
+    let mut invalid = valid;
+    invalid[4] = "20231232000000";
+    assert!(parse(invalid.iter().cloned().chain(Some("AAEC")), None).is_err());
+    let mut invalid = valid;
+    invalid[6] = "65536";
+    assert!(parse(invalid.iter().cloned().chain(Some("AAEC")), None).is_err());
+}
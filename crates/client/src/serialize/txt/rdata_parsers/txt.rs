@@ -20,7 +20,8 @@ use error::*;
 use rr::rdata::TXT;
 
 /// Parse the RData from a set of Tokens
-pub fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<TXT> {
-    let txt_data: Vec<String> = tokens.map(ToString::to_string).collect();
-    Ok(TXT::new(txt_data))
+///
+/// The tokens are octets, quoted strings can contain any octet escaped as `\DDD`.
+pub fn parse<'i, I: Iterator<Item = &'i [u8]>>(tokens: I) -> ParseResult<TXT> {
+    Ok(TXT::from_bytes(tokens.collect()))
 }
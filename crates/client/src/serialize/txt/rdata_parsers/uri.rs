@@ -0,0 +1,66 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! URI records for publishing mappings from hostnames to URIs
+
+use std::str::FromStr;
+
+use error::*;
+use rr::rdata::URI;
+
+/// Parse the RData from a set of Tokens
+///
+/// The quotes around the target are removed by the lexer, along with any escapes inside of them.
+///
+/// [RFC 7553](https://tools.ietf.org/html/rfc7553#section-4.4)
+///
+/// ```text
+/// 4.4.  Target
+///
+///    The URI of the target, enclosed in double-quote characters ('"'),
+///    where the URI is as specified in RFC 3986 [RFC3986].
+///
+/// $ORIGIN example.com.
+/// ; status    ttl   class  rr   priority   weight  target
+/// _ftp._tcp    IN    URI 10 1 "ftp://ftp1.example.com/public"
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<URI> {
+    let priority: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("priority".to_string())))
+        .and_then(|s| u16::from_str(s).map_err(Into::into))?;
+
+    let weight: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("weight".to_string())))
+        .and_then(|s| u16::from_str(s).map_err(Into::into))?;
+
+    let target = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("target".to_string())))?;
+    if target.is_empty() {
+        return Err(ParseError::from("URI target must not be empty"));
+    }
+
+    Ok(URI::new(priority, weight, target.to_string()))
+}
+
+#[test]
+fn test_parsing() {
+    let tokens = super::tokenize("10 1 \"https://www.example.com/search?q=a%20b\"");
+    let uri = parse(tokens.iter().map(String::as_str)).expect("failed to parse URI");
+    assert_eq!(uri.priority(), 10);
+    assert_eq!(uri.weight(), 1);
+    assert_eq!(uri.target(), "https://www.example.com/search?q=a%20b");
+}
+
+#[test]
+fn test_parsing_fails() {
+    assert!(parse(vec!["10", "1"].into_iter()).is_err());
+    assert!(parse(vec!["10", "1", ""].into_iter()).is_err());
+    assert!(parse(vec!["65536", "1", "https://www.example.com/"].into_iter()).is_err());
+}
@@ -13,7 +13,7 @@ use std::time::Duration;
 
 use tokio_tcp::TcpStream;
 use proto::tcp::{TcpClientConnect, TcpClientStream};
-use proto::xfer::{DnsMultiplexer, DnsMultiplexerConnect, DnsRequestSender};
+use proto::xfer::{DnsMultiplexer, DnsMultiplexerConnect, DnsRequestSender, RandSource};
 
 use client::ClientConnection;
 use error::*;
@@ -26,6 +26,7 @@ use rr::dnssec::Signer;
 pub struct TcpClientConnection {
     name_server: SocketAddr,
     timeout: Duration,
+    rand_source: Option<Arc<dyn RandSource>>,
 }
 
 impl TcpClientConnection {
@@ -55,8 +56,15 @@ impl TcpClientConnection {
         Ok(TcpClientConnection {
             name_server,
             timeout,
+            rand_source: None,
         })
     }
+
+    /// Uses the source for the ids of the queries, instead of the default `OsRandSource`
+    pub fn with_rand_source(mut self, rand_source: Arc<dyn RandSource>) -> Self {
+        self.rand_source = Some(rand_source);
+        self
+    }
 }
 
 impl ClientConnection for TcpClientConnection {
@@ -67,6 +75,11 @@ impl ClientConnection for TcpClientConnection {
     fn new_stream(&self, signer: Option<Arc<Signer>>) -> Self::SenderFuture {
         let (tcp_client_stream, handle) =
             TcpClientStream::<TcpStream>::with_timeout(self.name_server, self.timeout);
-        DnsMultiplexer::new(tcp_client_stream, handle, signer)
+        let stream = DnsMultiplexer::new(tcp_client_stream, handle, signer);
+
+        match self.rand_source {
+            Some(ref rand_source) => stream.with_rand_source(rand_source.clone()),
+            None => stream,
+        }
     }
 }
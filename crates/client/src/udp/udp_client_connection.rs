@@ -12,7 +12,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use proto::udp::{UdpClientConnect, UdpClientStream};
-use proto::xfer::DnsRequestSender;
+use proto::xfer::{DnsRequestSender, RandSource};
 
 use client::ClientConnection;
 use error::*;
@@ -27,6 +27,7 @@ use tokio_udp::UdpSocket;
 pub struct UdpClientConnection {
     name_server: SocketAddr,
     timeout: Duration,
+    rand_source: Option<Arc<dyn RandSource>>,
 }
 
 impl UdpClientConnection {
@@ -44,8 +45,15 @@ impl UdpClientConnection {
         Ok(UdpClientConnection {
             name_server,
             timeout,
+            rand_source: None,
         })
     }
+
+    /// Uses the source for the ids of the queries, instead of the default `OsRandSource`
+    pub fn with_rand_source(mut self, rand_source: Arc<dyn RandSource>) -> Self {
+        self.rand_source = Some(rand_source);
+        self
+    }
 }
 
 impl ClientConnection for UdpClientConnection {
@@ -54,6 +62,12 @@ impl ClientConnection for UdpClientConnection {
     type SenderFuture = UdpClientConnect<UdpSocket, Signer>;
 
     fn new_stream(&self, signer: Option<Arc<Signer>>) -> Self::SenderFuture {
-        UdpClientStream::with_timeout_and_signer(self.name_server, self.timeout, signer)
+        let stream =
+            UdpClientStream::with_timeout_and_signer(self.name_server, self.timeout, signer);
+
+        match self.rand_source {
+            Some(ref rand_source) => stream.with_rand_source(rand_source.clone()),
+            None => stream,
+        }
     }
 }
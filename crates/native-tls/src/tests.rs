@@ -37,6 +37,17 @@ fn test_tls_client_stream_ipv4() {
     tls_client_stream_test(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), false)
 }
 
+#[test]
+fn test_tls_client_stream_accept_invalid_hostname() {
+    // the certificate is for ns.example.com
+    tls_client_stream_test_with_name(
+        IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+        false,
+        "www.example.com",
+        true,
+    )
+}
+
 // FIXME: mtls is disabled at the moment, it causes a hang on Linux, and is currently not supported on macOS
 #[cfg(feature = "mtls")]
 #[test]
@@ -63,8 +74,18 @@ fn read_file(path: &str) -> Vec<u8> {
     bytes
 }
 
-#[allow(unused, unused_mut)]
 fn tls_client_stream_test(server_addr: IpAddr, mtls: bool) {
+    tls_client_stream_test_with_name(server_addr, mtls, "ns.example.com", false)
+}
+
+/// Connects to the test server, which has a certificate for ns.example.com, as `dns_name`
+#[allow(unused, unused_mut)]
+fn tls_client_stream_test_with_name(
+    server_addr: IpAddr,
+    mtls: bool,
+    dns_name: &'static str,
+    accept_invalid_hostnames: bool,
+) {
     let succeeded = Arc::new(atomic::AtomicBool::new(false));
     let succeeded_clone = succeeded.clone();
     thread::Builder::new()
@@ -87,7 +108,6 @@ fn tls_client_stream_test(server_addr: IpAddr, mtls: bool) {
     let root_cert_der = read_file(&format!("{}/../../tests/test-data/ca.der", server_path));
 
     // Generate X509 certificate
-    let dns_name = "ns.example.com";
     let server_pkcs12_der = read_file(&format!("{}/../../tests/test-data/cert.p12", server_path));
 
     // TODO: need a timeout on listen
@@ -183,6 +203,7 @@ fn tls_client_stream_test(server_addr: IpAddr, mtls: bool) {
     // barrier.wait();
     let mut builder = TlsStreamBuilder::new();
     builder.add_ca(trust_chain);
+    builder.danger_accept_invalid_hostnames(accept_invalid_hostnames);
 
     // fix MTLS
     // if mtls {
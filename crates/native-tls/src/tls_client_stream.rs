@@ -49,6 +49,14 @@ impl TlsClientStreamBuilder {
         self.0.identity(pkcs12);
     }
 
+    /// Controls the validation of the certificate of the server against the `dns_name`, on by default.
+    ///
+    /// See [`TlsStreamBuilder::danger_accept_invalid_hostnames`].
+    pub fn danger_accept_invalid_hostnames(&mut self, accept_invalid_hostnames: bool) {
+        self.0
+            .danger_accept_invalid_hostnames(accept_invalid_hostnames);
+    }
+
     /// Creates a new TlsStream to the specified name_server
     ///
     /// # Arguments
@@ -23,9 +23,14 @@ use trust_dns_proto::xfer::BufStreamHandle;
 /// A TlsStream counterpart to the TcpStream which embeds a secure TlsStream
 pub type TlsStream = TcpStream<TokioTlsStream<TokioTcpStream>>;
 
-fn tls_new(certs: Vec<Certificate>, pkcs12: Option<Identity>) -> io::Result<TlsConnector> {
+fn tls_new(
+    certs: Vec<Certificate>,
+    pkcs12: Option<Identity>,
+    accept_invalid_hostnames: bool,
+) -> io::Result<TlsConnector> {
     let mut builder = TlsConnector::builder();
     builder.min_protocol_version(Some(Tlsv12));
+    builder.danger_accept_invalid_hostnames(accept_invalid_hostnames);
 
     for cert in certs {
         builder.add_root_certificate(cert);
@@ -62,6 +67,7 @@ pub fn tls_from_stream(
 pub struct TlsStreamBuilder {
     ca_chain: Vec<Certificate>,
     identity: Option<Identity>,
+    accept_invalid_hostnames: bool,
 }
 
 impl TlsStreamBuilder {
@@ -70,6 +76,7 @@ impl TlsStreamBuilder {
         TlsStreamBuilder {
             ca_chain: vec![],
             identity: None,
+            accept_invalid_hostnames: false,
         }
     }
 
@@ -86,6 +93,16 @@ impl TlsStreamBuilder {
         self.identity = Some(identity);
     }
 
+    /// Controls the validation of the certificate of the server against the `dns_name`, on by default.
+    ///
+    /// *WARNING* accepting certificates for other names allows any holder of a trusted certificate to
+    /// impersonate the server. This is the opportunistic privacy profile of
+    /// [RFC 7858](https://tools.ietf.org/html/rfc7858#section-4.1), the connection is encrypted but
+    /// the server is not authenticated.
+    pub fn danger_accept_invalid_hostnames(&mut self, accept_invalid_hostnames: bool) {
+        self.accept_invalid_hostnames = accept_invalid_hostnames;
+    }
+
     /// Creates a new TlsStream to the specified name_server
     ///
     /// [RFC 7858](https://tools.ietf.org/html/rfc7858), DNS over TLS, May 2016
@@ -122,7 +139,11 @@ impl TlsStreamBuilder {
         let (message_sender, outbound_messages) = unbounded();
         let message_sender = BufStreamHandle::new(message_sender);
 
-        let tls_connector = match ::tls_stream::tls_new(self.ca_chain, self.identity) {
+        let tls_connector = match ::tls_stream::tls_new(
+            self.ca_chain,
+            self.identity,
+            self.accept_invalid_hostnames,
+        ) {
             Ok(c) => TokioTlsConnector::from(c),
             Err(e) => {
                 return (
@@ -11,6 +11,7 @@
 
 use std::{fmt, io, sync};
 
+use crate::op::ResponseCode;
 use crate::rr::{Name, RecordType};
 
 #[cfg(not(feature = "openssl"))]
@@ -32,6 +33,21 @@ pub type ProtoResult<T> = ::std::result::Result<T, ProtoError>;
 /// The error kind for errors that get returned in the crate
 #[derive(Eq, PartialEq, Debug, Fail)]
 pub enum ProtoErrorKind {
+    /// A record set failed DNSSec validation
+    #[cfg(feature = "dnssec")]
+    #[fail(
+        display = "record set is bogus name: {} record_type: {} reason: {}",
+        name, record_type, reason
+    )]
+    Bogus {
+        /// The record set name
+        name: Name,
+        /// The record type
+        record_type: RecordType,
+        /// The reason the record set is bogus
+        reason: crate::rr::dnssec::BogusReason,
+    },
+
     /// An error caused by a canceled future
     #[fail(display = "future was canceled: {:?}", _0)]
     Canceled(::futures::sync::oneshot::Canceled),
@@ -58,6 +74,21 @@ pub enum ProtoErrorKind {
     #[fail(display = "dns key value unknown, must be 3: {}", _0)]
     DnsKeyProtocolNot3(u8),
 
+    /// The public key of a DNSKEY does not have the length of its algorithm
+    #[cfg(feature = "dnssec")]
+    #[fail(
+        display = "dns key of algorithm {} must be {} bytes: {}",
+        algorithm, expected, len
+    )]
+    DnsKeyPublicKeyLength {
+        /// The algorithm of the key
+        algorithm: crate::rr::dnssec::Algorithm,
+        /// The length of the key
+        len: usize,
+        /// The length required by the algorithm
+        expected: usize,
+    },
+
     /// A domain name was too long
     #[fail(display = "name label data exceed 255: {}", _0)]
     DomainNameTooLong(usize),
@@ -69,6 +100,18 @@ pub enum ProtoErrorKind {
     )]
     EdnsNameNotRoot(crate::rr::Name),
 
+    /// The rdata length is larger than the remaining data of the message
+    #[fail(
+        display = "rdata length too large for remaining bytes, need: {} remain: {}",
+        len, remaining
+    )]
+    RDataLengthExceedsBuffer {
+        /// The length of the rdata
+        len: usize,
+        /// The number of remaining bytes
+        remaining: usize,
+    },
+
     /// The length of rdata read was not as expected
     #[fail(display = "incorrect rdata length read: {} expected: {}", read, len)]
     IncorrectRDataLengthRead {
@@ -78,6 +121,18 @@ pub enum ProtoErrorKind {
         len: usize,
     },
 
+    /// The header claimed more records for a section than were present
+    #[fail(
+        display = "message ended before all records were read, count: {} read: {}",
+        count, read
+    )]
+    IncorrectRecordCount {
+        /// The count of records in the header
+        count: usize,
+        /// The number of records actually present
+        read: usize,
+    },
+
     /// Label bytes exceeded the limit of 63
     #[fail(display = "label bytes exceed 63: {}", _0)]
     LabelBytesTooLong(usize),
@@ -91,6 +146,18 @@ pub enum ProtoErrorKind {
         ptr: u16,
     },
 
+    /// A name pointer must point to the start of a label
+    #[fail(
+        display = "label points to data not at a label idx: {} ptr: {}",
+        idx, ptr
+    )]
+    PointerNotToLabel {
+        /// index of the label containing this pointer
+        idx: usize,
+        /// location to which the pointer is directing
+        ptr: u16,
+    },
+
     /// The maximum buffer size was exceeded
     #[fail(display = "maximum buffer size exceeded: {}", _0)]
     MaxBufferSizeExceeded(usize),
@@ -126,6 +193,34 @@ pub enum ProtoErrorKind {
         record_type: RecordType,
     },
 
+    /// The keys of the SvcParams of SVCB and HTTPS records must be ascending and unique
+    #[fail(
+        display = "svc param key {} must be greater than the previous key {}",
+        key, previous
+    )]
+    SvcParamKeyNotAscending {
+        /// The out of order, or duplicate, key
+        key: u16,
+        /// The key before it
+        previous: u16,
+    },
+
+    /// TSIG verification failed, with the extended rcode to report
+    #[fail(display = "tsig verification failed: {}", _0)]
+    TsigVerificationFailed(ResponseCode),
+
+    /// The RData of a record is not of the type of the record, it can not be emitted
+    #[fail(
+        display = "rdata of type {} for a record of type {}",
+        rdata_type, record_type
+    )]
+    UnexpectedRData {
+        /// The type of the record
+        record_type: RecordType,
+        /// The type of the rdata
+        rdata_type: RecordType,
+    },
+
     /// An unknown algorithm type was found
     #[fail(display = "algorithm type value unknown: {}", _0)]
     UnknownAlgorithmTypeValue(u8),
@@ -146,6 +241,10 @@ pub enum ProtoErrorKind {
     #[fail(display = "record type value unknown: {}", _0)]
     UnknownRecordTypeValue(u16),
 
+    /// An unknown or unsupported TSIG algorithm was found, reported as BADKEY
+    #[fail(display = "tsig algorithm unknown or unsupported: {}", _0)]
+    UnknownTsigAlgorithm(Name),
+
     /// An unrecognized label code was found
     #[fail(display = "unrecognized label code: {:b}", _0)]
     UnrecognizedLabelCode(u8),
@@ -203,6 +302,16 @@ impl ProtoError {
     pub fn kind(&self) -> &ProtoErrorKind {
         self.inner.get_context()
     }
+
+    /// The Extended DNS Error, RFC 8914, for a SERVFAIL caused by this error, i.e. the reason the
+    ///  records failed DNSSec validation
+    #[cfg(feature = "dnssec")]
+    pub fn extended_error(&self) -> Option<crate::rr::rdata::opt::EdnsOption> {
+        match *self.kind() {
+            ProtoErrorKind::Bogus { reason, .. } => Some(reason.into()),
+            _ => None,
+        }
+    }
 }
 
 impl Clone for ProtoError {
@@ -390,15 +499,40 @@ impl Clone for ProtoErrorKind {
     fn clone(&self) -> Self {
         use self::ProtoErrorKind::*;
         match *self {
+            #[cfg(feature = "dnssec")]
+            Bogus {
+                ref name,
+                record_type,
+                reason,
+            } => Bogus {
+                name: name.clone(),
+                record_type,
+                reason,
+            },
             Canceled(ref c) => Canceled(*c),
             CharacterDataTooLong { max, len } => CharacterDataTooLong { max, len },
             LabelOverlapsWithOther { label, other } => LabelOverlapsWithOther { label, other },
             DnsKeyProtocolNot3(protocol) => DnsKeyProtocolNot3(protocol),
+            #[cfg(feature = "dnssec")]
+            DnsKeyPublicKeyLength {
+                algorithm,
+                len,
+                expected,
+            } => DnsKeyPublicKeyLength {
+                algorithm,
+                len,
+                expected,
+            },
             DomainNameTooLong(len) => DomainNameTooLong(len),
             EdnsNameNotRoot(ref found) => EdnsNameNotRoot(found.clone()),
             IncorrectRDataLengthRead { read, len } => IncorrectRDataLengthRead { read, len },
+            RDataLengthExceedsBuffer { len, remaining } => {
+                RDataLengthExceedsBuffer { len, remaining }
+            }
+            IncorrectRecordCount { count, read } => IncorrectRecordCount { count, read },
             LabelBytesTooLong(len) => LabelBytesTooLong(len),
             PointerNotPriorToLabel { idx, ptr } => PointerNotPriorToLabel { idx, ptr },
+            PointerNotToLabel { idx, ptr } => PointerNotToLabel { idx, ptr },
             MaxBufferSizeExceeded(max) => MaxBufferSizeExceeded(max),
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
@@ -411,11 +545,21 @@ impl Clone for ProtoErrorKind {
                 name: name.clone(),
                 record_type: *record_type,
             },
+            SvcParamKeyNotAscending { key, previous } => SvcParamKeyNotAscending { key, previous },
+            TsigVerificationFailed(code) => TsigVerificationFailed(code),
+            UnexpectedRData {
+                record_type,
+                rdata_type,
+            } => UnexpectedRData {
+                record_type,
+                rdata_type,
+            },
             UnknownAlgorithmTypeValue(value) => UnknownAlgorithmTypeValue(value),
             UnknownDnsClassStr(ref value) => UnknownDnsClassStr(value.clone()),
             UnknownDnsClassValue(value) => UnknownDnsClassValue(value),
             UnknownRecordTypeStr(ref value) => UnknownRecordTypeStr(value.clone()),
             UnknownRecordTypeValue(value) => UnknownRecordTypeValue(value),
+            UnknownTsigAlgorithm(ref name) => UnknownTsigAlgorithm(name.clone()),
             UnrecognizedLabelCode(value) => UnrecognizedLabelCode(value),
             UnrecognizedNsec3Flags(flags) => UnrecognizedNsec3Flags(flags),
 
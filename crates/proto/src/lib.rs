@@ -11,8 +11,6 @@
 
 //! Trust-DNS Protocol library
 
-#[cfg(feature = "dnssec")]
-extern crate data_encoding;
 #[macro_use]
 extern crate enum_as_inner;
 #[cfg(test)]
@@ -23,6 +21,7 @@ extern crate futures;
 extern crate idna;
 #[macro_use]
 extern crate lazy_static;
+extern crate linked_hash_map;
 #[macro_use]
 extern crate log;
 #[cfg(feature = "openssl")]
@@ -53,6 +52,8 @@ pub mod op;
 pub mod rr;
 pub mod serialize;
 pub mod tcp;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod udp;
 pub mod xfer;
 
@@ -167,10 +167,7 @@ impl<'a> From<&'a Edns> for Record {
         }
         record.set_ttl(ttl);
 
-        // now for each option, write out the option array
-        //  also, since this is a hash, there is no guarantee that ordering will be preserved from
-        //  the original binary format.
-        // maybe switch to: https://crates.io/crates/linked-hash-map/
+        // the options keep the order they were read or set in
         record.set_rdata(RData::OPT(value.options().clone()));
 
         record
@@ -16,18 +16,22 @@
 
 //! Basic protocol message for DNS
 
+use std::collections::HashSet;
+use std::fmt;
 use std::iter;
 use std::mem;
 use std::ops::Deref;
+use std::slice::Iter;
 use std::sync::Arc;
 
 use super::{Edns, Header, MessageType, OpCode, Query, ResponseCode};
 use crate::error::*;
-use crate::rr::{Record, RecordType};
+use crate::rr::rdata::opt::EdnsOption;
+use crate::rr::{Name, RData, Record, RecordSet, RecordType};
 use crate::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, EncodeMode};
 
 #[cfg(feature = "dnssec")]
-use crate::rr::dnssec::rdata::DNSSECRecordType;
+use crate::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType};
 
 /// The basic request and response datastructure, used for all DNS protocols.
 ///
@@ -117,6 +121,48 @@ pub struct HeaderCounts {
     pub additional_count: usize,
 }
 
+/// The section of a Message that a record is in, see `Message::all_records`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MessageSection {
+    /// The answer section, or the prerequisites of an update, see `Message::answers`
+    Answer,
+    /// The authority section, or the updates of an update, see `Message::name_servers`
+    Authority,
+    /// The additional section, see `Message::additionals` and `Message::sig0`
+    Additional,
+}
+
+/// An iterator over the records of all sections of a Message, see `Message::all_records`
+#[derive(Debug, Clone)]
+pub struct MessageRecords<'a> {
+    answers: Iter<'a, Record>,
+    name_servers: Iter<'a, Record>,
+    additionals: iter::Chain<Iter<'a, Record>, Iter<'a, Record>>,
+}
+
+impl<'a> Iterator for MessageRecords<'a> {
+    type Item = (MessageSection, &'a Record);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.answers.next() {
+            return Some((MessageSection::Answer, record));
+        }
+        if let Some(record) = self.name_servers.next() {
+            return Some((MessageSection::Authority, record));
+        }
+        self.additionals
+            .next()
+            .map(|record| (MessageSection::Additional, record))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.answers.len() + self.name_servers.len() + self.additionals.size_hint().0;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for MessageRecords<'a> {}
+
 impl Message {
     /// Returns a new "empty" Message
     pub fn new() -> Self {
@@ -321,7 +367,12 @@ impl Message {
     }
 
     /// Add the EDNS section to the Message
+    ///
+    /// Any OPT records added to the additional section are removed, the EDNS section is the only
+    ///  OPT record of the emitted Message.
     pub fn set_edns(&mut self, edns: Edns) -> &mut Self {
+        self.additionals
+            .retain(|record| record.rr_type() != RecordType::OPT);
         self.edns = Some(edns);
         self
     }
@@ -336,6 +387,17 @@ impl Message {
         self
     }
 
+    /// Add a TSIG record, i.e. authenticate this message with a shared secret
+    ///
+    /// Like SIG0 records, the TSIG record is always emitted as the final additional record, and so
+    ///  this must only be used after all other records have been associated.
+    #[cfg(feature = "dnssec")]
+    pub fn add_tsig(&mut self, record: Record) -> &mut Self {
+        assert_eq!(RecordType::DNSSEC(DNSSECRecordType::TSIG), record.rr_type());
+        self.sig0.push(record);
+        self
+    }
+
     /// Gets the header of the Message
     pub fn header(&self) -> &Header {
         &self.header
@@ -404,6 +466,44 @@ impl Message {
         &self.queries
     }
 
+    /// Returns `true` if this message has the id and the question of the query
+    ///
+    /// The names of the queries are compared case-insensitively, their types and classes exactly.
+    ///  Off-path attackers spoofing a response need to guess the question in addition to the id.
+    ///
+    /// Only an error without answers may leave out the question and match on the id alone, e.g.
+    ///  a FORMERR of a server which could not parse the query, RFC 1035 section 4.1.1, or the
+    ///  response to an UPDATE, which may leave out the zone, RFC 2136 section 3.8.
+    pub fn matches_query(&self, query: &Message) -> bool {
+        if self.id() != query.id() {
+            return false;
+        }
+
+        if self.queries.is_empty() {
+            let is_update = self.op_code() == OpCode::Update && query.op_code() == OpCode::Update;
+
+            return self.answers.is_empty()
+                && match self.response_code() {
+                    ResponseCode::FormErr
+                    | ResponseCode::NotImp
+                    | ResponseCode::Refused
+                    | ResponseCode::ServFail => true,
+                    _ => is_update,
+                };
+        }
+
+        self.queries.len() == query.queries.len()
+            && self
+                .queries
+                .iter()
+                .zip(query.queries.iter())
+                .all(|(ours, theirs)| {
+                    ours.name() == theirs.name()
+                        && ours.query_type() == theirs.query_type()
+                        && ours.query_class() == theirs.query_class()
+                })
+    }
+
     /// ```text
     /// Answer          Carries RRs which directly answer the query.
     /// ```
@@ -504,6 +604,13 @@ impl Message {
         self.edns.as_ref().map_or(0, Edns::version)
     }
 
+    /// # Return value
+    ///
+    /// the DO bit as defined in the EDNS record, false without EDNS
+    pub fn dnssec_ok(&self) -> bool {
+        self.edns.iter().any(Edns::dnssec_ok)
+    }
+
     /// [RFC 2535, Domain Name System Security Extensions, March 1999](https://tools.ietf.org/html/rfc2535#section-4)
     ///
     /// ```text
@@ -522,6 +629,92 @@ impl Message {
         &self.sig0
     }
 
+    /// The TSIG record, if this message was authenticated with a shared secret
+    ///
+    /// TSIG records are kept with the SIG0 records, and must be the final record of the message.
+    #[cfg(feature = "dnssec")]
+    pub fn tsig(&self) -> Option<&Record> {
+        self.sig0
+            .last()
+            .filter(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::TSIG))
+    }
+
+    /// Returns an iterator over the records of the answer, authority and additional sections, in
+    ///  that order, each with the section it is in
+    ///
+    /// The SIG0 and TSIG records follow the additional records, as they do in the encoded Message.
+    ///  EDNS is not included, the OPT record is only created when the Message is emitted.
+    pub fn all_records(&self) -> MessageRecords {
+        MessageRecords {
+            answers: self.answers.iter(),
+            name_servers: self.name_servers.iter(),
+            additionals: self.additionals.iter().chain(self.sig0.iter()),
+        }
+    }
+
+    /// Groups all the answer, name server and additional records in the Message into RRsets
+    ///
+    /// Records are grouped by name, type and class. With the `dnssec` feature, each RRSIG is
+    ///  associated to the RRset with the same name and class which it covers, see `SIG::type_covered`,
+    ///  RRSIGs which do not cover any RRset in the Message are dropped. Each RecordSet is then the
+    ///  input that is necessary for `Verifier::verify_rrsig`.
+    pub fn rrsets(&self) -> Vec<RecordSet> {
+        let records = self
+            .answers
+            .iter()
+            .chain(self.name_servers.iter())
+            .chain(self.additionals.iter());
+
+        let mut rrsets: Vec<RecordSet> = Vec::new();
+        for record in records.clone().filter(|r| !Self::is_rrsig(r)) {
+            let existing = rrsets.iter_mut().find(|rrset| {
+                rrset.name() == record.name()
+                    && rrset.record_type() == record.rr_type()
+                    && rrset.dns_class() == record.dns_class()
+            });
+
+            if let Some(rrset) = existing {
+                rrset.insert(record.clone(), 0);
+            } else {
+                let mut rrset =
+                    RecordSet::with_ttl(record.name().clone(), record.rr_type(), record.ttl());
+                rrset.set_dns_class(record.dns_class());
+                rrset.insert(record.clone(), 0);
+                rrsets.push(rrset);
+            }
+        }
+
+        // the rrsigs must be added after all records, inserting records clears the rrsigs
+        #[cfg(feature = "dnssec")]
+        for rrsig in records.filter(|r| Self::is_rrsig(r)) {
+            if let RData::DNSSEC(DNSSECRData::SIG(ref sig)) = *rrsig.rdata() {
+                let covered = rrsets.iter_mut().find(|rrset| {
+                    rrset.name() == rrsig.name()
+                        && rrset.record_type() == sig.type_covered()
+                        && rrset.dns_class() == rrsig.dns_class()
+                });
+
+                if let Some(rrset) = covered {
+                    rrset.insert_rrsig(rrsig.clone());
+                } else {
+                    debug!("no rrset in message covered by rrsig: {:?}", rrsig);
+                }
+            }
+        }
+
+        rrsets
+    }
+
+    #[cfg(feature = "dnssec")]
+    fn is_rrsig(record: &Record) -> bool {
+        record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+    }
+
+    #[cfg(not(feature = "dnssec"))]
+    fn is_rrsig(_: &Record) -> bool {
+        false
+    }
+
     // TODO: only necessary in tests, should it be removed?
     /// this is necessary to match the counts in the header from the record sections
     ///  this happens implicitly on write_to, so no need to call before write_to
@@ -542,13 +735,25 @@ impl Message {
 
     /// Attempts to read the specified number of `Query`s
     pub fn read_queries(decoder: &mut BinDecoder, count: usize) -> ProtoResult<Vec<Query>> {
-        let mut queries = Vec::with_capacity(count);
-        for _ in 0..count {
+        // the count is not trusted for the allocation, the smallest query is the root name, a type and
+        //  a class
+        let mut queries = Vec::with_capacity(count.min(decoder.remaining() / 5));
+        for read in 0..count {
+            Self::verify_more_records(decoder, count, read)?;
             queries.push(Query::read(decoder)?);
         }
         Ok(queries)
     }
 
+    /// Returns an error if the message ended before all records of a section were read
+    fn verify_more_records(decoder: &BinDecoder, count: usize, read: usize) -> ProtoResult<()> {
+        if decoder.is_empty() {
+            Err(ProtoErrorKind::IncorrectRecordCount { count, read }.into())
+        } else {
+            Ok(())
+        }
+    }
+
     /// Attempts to read the specified number of records
     ///
     /// # Returns
@@ -560,13 +765,15 @@ impl Message {
         count: usize,
         is_additional: bool,
     ) -> ProtoResult<(Vec<Record>, Option<Edns>, Vec<Record>)> {
-        let mut records: Vec<Record> = Vec::with_capacity(count);
+        // the smallest record is the root name, a type, class, ttl and the length of empty rdata
+        let mut records: Vec<Record> = Vec::with_capacity(count.min(decoder.remaining() / 11));
         let mut edns: Option<Edns> = None;
         let mut sig0s: Vec<Record> = Vec::with_capacity(if is_additional { 1 } else { 0 });
 
         // sig0 must be last, once this is set, disable.
         let mut saw_sig0 = false;
-        for _ in 0..count {
+        for read in 0..count {
+            Self::verify_more_records(decoder, count, read)?;
             let record = Record::read(decoder)?;
 
             if !is_additional {
@@ -577,7 +784,8 @@ impl Message {
             } else {
                 match record.rr_type() {
                     #[cfg(feature = "dnssec")]
-                    RecordType::DNSSEC(DNSSECRecordType::SIG) => {
+                    RecordType::DNSSEC(DNSSECRecordType::SIG)
+                    | RecordType::DNSSEC(DNSSECRecordType::TSIG) => {
                         saw_sig0 = true;
                         sig0s.push(record);
                     }
@@ -604,6 +812,10 @@ impl Message {
     }
 
     /// Decodes a message from the buffer.
+    ///
+    /// Any input is either decoded or returns an error, this does not panic, and only allocates in
+    ///  proportion to the length of the buffer, e.g. for fuzzing. This is the same as
+    ///  `BinDecodable::from_bytes`.
     pub fn from_vec(buffer: &[u8]) -> ProtoResult<Message> {
         let mut decoder = BinDecoder::new(buffer);
         Message::read(&mut decoder)
@@ -623,6 +835,83 @@ impl Message {
         Ok(buffer)
     }
 
+    /// Pads the message with an EDNS padding option, RFC 7830
+    ///
+    /// After this the total length of the encoded message is a multiple of `block_size`. This should
+    ///  be done after all other changes to the message, any SIG0 or TSIG added afterwards will
+    ///  change the length, as will changes to the message. An EDNS record is added if there is none.
+    pub fn pad_to(&mut self, block_size: usize) -> ProtoResult<&mut Self> {
+        if block_size == 0 {
+            return Err("padding block size must not be zero".into());
+        }
+
+        // the padding option itself takes space, so measure with an empty one
+        self.edns_mut().set_option(EdnsOption::Padding(vec![]));
+        let len = self.to_vec()?.len();
+
+        let padding = (block_size - len % block_size) % block_size;
+        self.edns_mut()
+            .set_option(EdnsOption::Padding(vec![0; padding]));
+        Ok(self)
+    }
+
+    /// Encodes the Message into a buffer of at most `max_size` bytes
+    ///
+    /// Records which do not fit are left out, and the message is marked as truncated. Records are
+    ///  never split, and once one has been left out none of the following records are added.
+    ///  The question and the EDNS record, if any, are always included. This is for responses over
+    ///  UDP, where `max_size` is 512 or the EDNS max payload of the request.
+    pub fn to_vec_truncated(&self, max_size: u16) -> ProtoResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(max_size as usize);
+        {
+            let mut encoder = BinEncoder::new(&mut buffer);
+            encoder.set_max_size(max_size);
+            self.emit(&mut encoder)?;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Returns an upper bound of the length of the encoded message, without encoding it
+    ///
+    /// This is cheaper than `to_vec`, e.g. to decide whether a response needs to be truncated.
+    ///  Query and record names which repeat the end of an earlier query or record name are counted
+    ///  as compressed, like they are when the message is encoded. Names in record data are
+    ///  counted uncompressed, so the estimate is never less than the encoded length.
+    pub fn estimated_len(&self) -> usize {
+        let mut names = NameEstimate::default();
+
+        // the header
+        let mut len = 12;
+        for query in &self.queries {
+            len += names.len(query.name(), len) + 4;
+        }
+
+        let mut record_len = |len: usize, record: &Record| {
+            // type, class, ttl and rdata length
+            len + names.len(record.name(), len) + 10 + estimated_rdata_len(record.rdata())
+        };
+
+        len = self
+            .answers
+            .iter()
+            .chain(&self.name_servers)
+            .chain(&self.additionals)
+            .fold(len, &mut record_len);
+
+        if let Some(ref edns) = self.edns {
+            // the root name, type, class, ttl and rdata length of the OPT record
+            len += 11;
+            len += edns
+                .options()
+                .iter()
+                .map(|(_, option)| 4 + option.len() as usize)
+                .sum::<usize>();
+        }
+
+        self.sig0.iter().fold(len, record_len)
+    }
+
     /// Finalize the message prior to sending.
     ///
     /// Subsequent to calling this, the Message should not change.
@@ -640,6 +929,8 @@ impl Message {
                 // SIG0's are special, and come at the very end of the message
                 #[cfg(feature = "dnssec")]
                 RecordType::DNSSEC(DNSSECRecordType::SIG) => self.add_sig0(fin),
+                #[cfg(feature = "dnssec")]
+                RecordType::DNSSEC(DNSSECRecordType::TSIG) => self.add_tsig(fin),
                 _ => self.add_additional(fin),
             };
         }
@@ -648,6 +939,99 @@ impl Message {
     }
 }
 
+impl fmt::Display for Message {
+    /// Formats the message in the style of `dig`
+    ///
+    /// ```text
+    /// ;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 4096
+    /// ;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 1
+    ///
+    /// ;; OPT PSEUDOSECTION:
+    /// ; EDNS: version: 0, flags: do; udp: 4096
+    ///
+    /// ;; QUESTION SECTION:
+    /// ;www.example.com.    IN    A
+    ///
+    /// ;; ANSWER SECTION:
+    /// www.example.com.    86400    IN    A    93.184.216.34
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            ";; ->>HEADER<<- opcode: {}, status: {}, id: {}",
+            format!("{:?}", self.op_code()).to_uppercase(),
+            format!("{:?}", self.response_code()).to_uppercase(),
+            self.id()
+        )?;
+
+        f.write_str(";; flags:")?;
+        let flags = [
+            (self.message_type() == MessageType::Response, "qr"),
+            (self.authoritative(), "aa"),
+            (self.truncated(), "tc"),
+            (self.recursion_desired(), "rd"),
+            (self.recursion_available(), "ra"),
+            (self.authentic_data(), "ad"),
+            (self.checking_disabled(), "cd"),
+        ];
+        for &(_, flag) in flags.iter().filter(|&&(is_set, _)| is_set) {
+            write!(f, " {}", flag)?;
+        }
+
+        // the OPT and any SIG0 or TSIG are additional records on the wire
+        let additional_count = self.additionals.len() + self.edns.iter().count() + self.sig0.len();
+        writeln!(
+            f,
+            "; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}",
+            self.queries.len(),
+            self.answers.len(),
+            self.name_servers.len(),
+            additional_count
+        )?;
+
+        if let Some(ref edns) = self.edns {
+            writeln!(
+                f,
+                "\n;; OPT PSEUDOSECTION:\n; EDNS: version: {}, flags:{}; udp: {}",
+                edns.version(),
+                if edns.dnssec_ok() { " do" } else { "" },
+                edns.max_payload()
+            )?;
+        }
+
+        if !self.queries.is_empty() {
+            f.write_str("\n;; QUESTION SECTION:\n")?;
+            for query in &self.queries {
+                writeln!(
+                    f,
+                    ";{}\t{}\t{}",
+                    query.name(),
+                    query.query_class(),
+                    query.query_type()
+                )?;
+            }
+        }
+
+        let sections = [
+            ("ANSWER", &self.answers),
+            ("AUTHORITY", &self.name_servers),
+            ("ADDITIONAL", &self.additionals),
+        ];
+        for &(section, records) in sections.iter().filter(|(_, records)| !records.is_empty()) {
+            writeln!(f, "\n;; {} SECTION:", section)?;
+            for record in records {
+                writeln!(f, "{}", record)?;
+            }
+        }
+
+        for record in &self.sig0 {
+            writeln!(f, "\n;; {} PSEUDOSECTION:\n{}", record.rr_type(), record)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Deref for Message {
     type Target = Header;
 
@@ -672,8 +1056,28 @@ pub trait MessageFinalizer: Send + Sync + 'static {
     ///
     /// A vector to append to the additionals section of the message, sorted in the order as they should appear in the message.
     fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>>;
+
+    /// Returns true if the message should be finalized before it is sent, by default only updates
+    fn should_finalize_message(&self, message: &Message) -> bool {
+        message.op_code() == OpCode::Update
+    }
+
+    /// Returns the verifier for the responses to a finalized message, if they are to be verified
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message after it was finalized, as it is sent
+    fn response_verifier(&self, _message: &Message) -> Option<MessageVerifier> {
+        None
+    }
 }
 
+/// Verifies the response to a finalized message, e.g. its TSIG
+///
+/// The arguments are the response as received, before it is decoded, and the current time. A
+///  response that fails verification must not be passed on to the requestor.
+pub type MessageVerifier = Arc<dyn Fn(&[u8], u32) -> ProtoResult<()> + Send + Sync>;
+
 /// A MessageFinalizer which does nothing
 ///
 /// *WARNING* This should only be used in None context, it will panic in all cases where finalize is called.
@@ -703,6 +1107,19 @@ pub fn count_was_truncated(result: ProtoResult<usize>) -> ProtoResult<(usize, bo
     })
 }
 
+/// Emits the records, unless an earlier section was already truncated
+fn emit_unless_truncated<E: EmitAndCount>(
+    records: &mut E,
+    was_truncated: bool,
+    encoder: &mut BinEncoder,
+) -> ProtoResult<(usize, bool)> {
+    if was_truncated {
+        Ok((0, true))
+    } else {
+        count_was_truncated(records.emit(encoder))
+    }
+}
+
 /// A trait that defines types which can be emitted as a set, with the associated count returned.
 pub trait EmitAndCount {
     /// Emit self to the encoder and return the count of items
@@ -715,6 +1132,70 @@ impl<'e, I: Iterator<Item = &'e E>, E: 'e + BinEncodable> EmitAndCount for I {
     }
 }
 
+/// The names of a message being estimated, which later names are compressed to
+#[derive(Default)]
+struct NameEstimate {
+    suffixes: HashSet<Vec<u8>>,
+}
+
+impl NameEstimate {
+    /// Returns the length of the name at `offset`, compressed to the earlier names
+    fn len(&mut self, name: &Name, offset: usize) -> usize {
+        let mut wire = Vec::with_capacity(name.len());
+        let mut starts = Vec::with_capacity(name.num_labels() as usize);
+        for label in name.iter() {
+            starts.push(wire.len());
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label);
+        }
+
+        for start in starts {
+            if self.suffixes.contains(&wire[start..]) {
+                return start + 2;
+            }
+
+            // like the encoder, only names below the maximum pointer offset are stored
+            if offset + wire.len() < 0x3FFF {
+                self.suffixes.insert(wire[start..].to_vec());
+            }
+        }
+
+        // the labels and the root
+        wire.len() + 1
+    }
+}
+
+/// Returns the uncompressed length of the name
+fn name_len(name: &Name) -> usize {
+    name.iter().map(|label| 1 + label.len()).sum::<usize>() + 1
+}
+
+/// Returns an upper bound of the encoded length of the rdata
+fn estimated_rdata_len(rdata: &RData) -> usize {
+    match *rdata {
+        RData::A(..) => 4,
+        RData::AAAA(..) => 16,
+        RData::ANAME(ref name)
+        | RData::CNAME(ref name)
+        | RData::NS(ref name)
+        | RData::PTR(ref name) => name_len(name),
+        RData::MX(ref mx) => 2 + name_len(mx.exchange()),
+        RData::SOA(ref soa) => name_len(soa.mname()) + name_len(soa.rname()) + 20,
+        RData::SRV(ref srv) => 6 + name_len(srv.target()),
+        RData::TXT(ref txt) => txt.iter().map(|data| 1 + data.len()).sum(),
+        _ => {
+            // names in these are not compressed, measure them
+            let mut bytes = Vec::new();
+            let mut encoder = BinEncoder::new(&mut bytes);
+            match rdata.emit(&mut encoder) {
+                Ok(()) => bytes.len(),
+                // this will fail again when the message is emitted, until then stay an upper bound
+                Err(_) => usize::from(u16::max_value()),
+            }
+        }
+    }
+}
+
 /// Emits the different sections of a message properly
 #[allow(clippy::too_many_arguments)]
 pub fn emit_message_parts<Q, A, N, D>(
@@ -737,15 +1218,25 @@ where
     let place = encoder.place::<Header>()?;
 
     let query_count = queries.emit(encoder)?;
-    // FIXME: need to do some on max records
-    //  return offset of last emitted record.
+
+    // the OPT record must be present in truncated responses as well, so make room for it
+    let edns = edns.map(Record::from);
+    let edns_len = match edns {
+        Some(ref edns) => edns.to_bytes()?.len(),
+        None => 0,
+    };
+    let max_size = encoder.max_size();
+    encoder.set_max_size(max_size.saturating_sub(edns_len as u16));
+
+    // once a record didn't fit, none of the following records are emitted, RFC 2181 section 9
     let answer_count = count_was_truncated(answers.emit(encoder))?;
-    let nameserver_count = count_was_truncated(name_servers.emit(encoder))?;
-    let mut additional_count = count_was_truncated(additionals.emit(encoder))?;
+    let nameserver_count = emit_unless_truncated(name_servers, answer_count.1, encoder)?;
+    let mut additional_count = emit_unless_truncated(additionals, nameserver_count.1, encoder)?;
 
-    if let Some(edns) = edns {
+    encoder.set_max_size(max_size);
+    if let Some(ref edns) = edns {
         // need to commit the error code
-        let count = count_was_truncated(encoder.emit_all(iter::once(&Record::from(edns))))?;
+        let count = count_was_truncated(encoder.emit_all(iter::once(edns)))?;
         additional_count.0 += count.0;
         additional_count.1 |= count.1;
     }
@@ -767,7 +1258,9 @@ where
         nameserver_count: nameserver_count.0,
         additional_count: additional_count.0,
     };
-    let was_truncated = answer_count.1 || nameserver_count.1 || additional_count.1;
+    // an explicitly truncated message, e.g. `Message::truncate()`, stays truncated
+    let was_truncated =
+        header.truncated() || answer_count.1 || nameserver_count.1 || additional_count.1;
 
     place.replace(encoder, update_header_counts(header, was_truncated, counts))?;
     Ok(())
@@ -796,11 +1289,7 @@ impl<'r> BinDecodable<'r> for Message {
         //  this could improve error detection while decoding.
 
         // get the questions
-        let count = header.query_count() as usize;
-        let mut queries = Vec::with_capacity(count);
-        for _ in 0..count {
-            queries.push(Query::read(decoder)?);
-        }
+        let queries = Self::read_queries(decoder, header.query_count() as usize)?;
 
         // get all counts before header moves
         let answer_count = header.answer_count() as usize;
@@ -932,3 +1421,511 @@ fn test_legit_message() {
 
     assert_eq!(message.id(), 4096);
 }
+
+#[test]
+#[rustfmt::skip]
+fn test_answer_count_mismatch() {
+    let buf: Vec<u8> = vec![
+  0x10,0x00,0x81,0x80, // id = 4096, response, op=query, recursion_desired, recursion_available, no_error
+  0x00,0x01,0x00,0x02, // 1 query, 2 answers,
+  0x00,0x00,0x00,0x00, // 0 namesservers, 0 additional record
+
+  0x03,b'w',b'w',b'w', // query --- www.example.com
+  0x07,b'e',b'x',b'a', //
+  b'm',b'p',b'l',b'e', //
+  0x03,b'c',b'o',b'm', //
+  0x00,                // 0 = endname
+  0x00,0x01,0x00,0x01, // ReordType = A, Class = IN
+
+  0xC0,0x0C,           // name pointer to www.example.com
+  0x00,0x01,0x00,0x01, // RecordType = A, Class = IN
+  0x00,0x00,0x00,0x02, // TTL = 2 seconds
+  0x00,0x04,           // record length = 4 (ipv4 address)
+  0x5D,0xB8,0xD8,0x22, // address = 93.184.216.34
+  ];
+
+    let mut decoder = BinDecoder::new(&buf);
+    match *Message::read(&mut decoder).unwrap_err().kind() {
+        ProtoErrorKind::IncorrectRecordCount { count: 2, read: 1 } => (),
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn test_from_vec_never_panics() {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::rr::rdata::{MX, SOA, SRV, TXT};
+
+    let name = Name::from_ascii("www.example.com.").unwrap();
+    let mut message = Message::new();
+    message
+        .set_id(4096)
+        .add_query(Query::query(name.clone(), RecordType::A));
+    for rdata in vec![
+        RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+        RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        RData::CNAME(name.clone()),
+        RData::MX(MX::new(10, name.clone())),
+        RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()])),
+        RData::SRV(SRV::new(1, 2, 53, name.clone())),
+    ] {
+        message.add_answer(Record::from_rdata(name.clone(), 86400, rdata));
+    }
+    message.add_name_server(Record::from_rdata(
+        name.clone(),
+        86400,
+        RData::SOA(SOA::new(name.clone(), name.clone(), 1, 2, 3, 4, 5)),
+    ));
+    let mut edns = Edns::new();
+    edns.set_max_payload(1232);
+    message.set_edns(edns);
+    let bytes = message.to_vec().unwrap();
+    assert!(Message::from_vec(&bytes).is_ok());
+
+    // every truncation
+    for len in 0..bytes.len() {
+        let _ = Message::from_vec(&bytes[..len]);
+    }
+
+    // random changes to the message, and random data after the header
+    let mut rng = StdRng::seed_from_u64(4096);
+    for _ in 0..10_000 {
+        let mut mutated = bytes.clone();
+        for _ in 0..rng.gen_range(1, 8) {
+            let idx = rng.gen_range(0, mutated.len());
+            mutated[idx] = rng.gen();
+        }
+        let len = rng.gen_range(0, mutated.len() + 1);
+        let _ = Message::from_vec(&mutated[..len]);
+
+        let mut random = bytes[..12].to_vec();
+        random.extend((0..rng.gen_range(0, 128)).map(|_| rng.gen::<u8>()));
+        let _ = Message::from_vec(&random);
+    }
+
+    // the counts of the header do not allocate for records which are not present
+    let header = [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+    assert!(Message::from_vec(&header).is_err());
+}
+
+#[test]
+fn test_display() {
+    use std::net::Ipv4Addr;
+
+    use crate::rr::{Name, RData};
+
+    let name = Name::from_ascii("www.example.com.").unwrap();
+    let mut message = Message::new();
+    message
+        .set_id(4096)
+        .set_message_type(MessageType::Response)
+        .set_recursion_desired(true)
+        .set_recursion_available(true)
+        .add_query(Query::query(name.clone(), RecordType::A))
+        .add_answer(Record::from_rdata(
+            name,
+            86400,
+            RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+        ));
+    message.edns_mut().set_dnssec_ok(true);
+    message.edns_mut().set_max_payload(4096);
+
+    let expect = "\
+;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 4096
+;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 1
+
+;; OPT PSEUDOSECTION:
+; EDNS: version: 0, flags: do; udp: 4096
+
+;; QUESTION SECTION:
+;www.example.com.\tIN\tA
+
+;; ANSWER SECTION:
+www.example.com.\t86400\tIN\tA\t93.184.216.34
+";
+    assert_eq!(message.to_string(), expect);
+}
+
+#[test]
+fn test_to_vec_truncated() {
+    use std::net::Ipv4Addr;
+
+    use crate::rr::{Name, RData};
+
+    let name = Name::from_ascii("www.example.com.").unwrap();
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Response)
+        .add_query(Query::query(name.clone(), RecordType::A));
+    for i in 0..100 {
+        message.add_answer(Record::from_rdata(
+            name.clone(),
+            86400,
+            RData::A(Ipv4Addr::new(192, 0, 2, i)),
+        ));
+    }
+    message.add_name_server(Record::from_rdata(
+        Name::from_ascii("example.com.").unwrap(),
+        86400,
+        RData::NS(Name::from_ascii("ns.example.com.").unwrap()),
+    ));
+
+    // header 12, question 21, and each answer with a label pointer 16
+    let bytes = message.to_vec_truncated(512).expect("failed to encode");
+    assert_eq!(bytes.len(), 12 + 21 + 29 * 16);
+
+    let truncated = Message::from_vec(&bytes).expect("failed to decode");
+    assert!(truncated.truncated());
+    assert_eq!(truncated.queries(), message.queries());
+    assert_eq!(truncated.answers(), &message.answers()[..29]);
+    assert!(truncated.name_servers().is_empty());
+
+    // the OPT record always fits
+    message.edns_mut().set_max_payload(512);
+    let bytes = message.to_vec_truncated(512).expect("failed to encode");
+    assert!(bytes.len() <= 512);
+
+    let truncated = Message::from_vec(&bytes).expect("failed to decode");
+    assert!(truncated.truncated());
+    assert!(truncated.edns().is_some());
+    assert_eq!(truncated.answers().len(), 29);
+
+    // nothing is truncated if it all fits
+    let bytes = message
+        .to_vec_truncated(u16::max_value())
+        .expect("failed to encode");
+    assert!(!Message::from_vec(&bytes).unwrap().truncated());
+
+    // an explicitly truncated message stays truncated, even though it fits
+    let bytes = message.truncate().to_vec().expect("failed to encode");
+    let truncated = Message::from_vec(&bytes).expect("failed to decode");
+    assert!(truncated.truncated());
+    assert!(truncated.answers().is_empty());
+}
+
+#[test]
+fn test_pad_to() {
+    use crate::rr::rdata::opt::EdnsCode;
+    use crate::rr::Name;
+
+    for name in &[
+        "www.example.com.",
+        "a.b.",
+        "a-much-longer-name.example.org.",
+    ] {
+        let mut message = Message::new();
+        message
+            .set_id(10)
+            .add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A));
+        message.pad_to(128).expect("failed to pad");
+
+        let bytes = message.to_vec().expect("failed to encode");
+        assert_eq!(bytes.len() % 128, 0);
+        assert!(!bytes.is_empty());
+
+        // the padding is kept when read
+        let read = Message::from_vec(&bytes).expect("failed to decode");
+        assert_eq!(read.queries(), message.queries());
+        assert_eq!(
+            read.edns().unwrap().option(EdnsCode::Padding),
+            message.edns().unwrap().option(EdnsCode::Padding)
+        );
+    }
+}
+
+#[test]
+fn test_all_records() {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    let name = Name::from_str("example.com.").unwrap();
+    let ns_name = Name::from_str("ns.example.com.").unwrap();
+    let answer = Record::from_rdata(name.clone(), 300, RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+    let name_server = Record::from_rdata(name.clone(), 86400, RData::NS(ns_name.clone()));
+    let additional = Record::from_rdata(ns_name, 86400, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+
+    let mut message = Message::new();
+    message
+        .add_answer(answer.clone())
+        .add_answer(answer.clone())
+        .add_name_server(name_server.clone())
+        .add_additional(additional.clone())
+        .edns_mut()
+        .set_max_payload(4096);
+
+    let all_records = message.all_records();
+    assert_eq!(all_records.len(), 4);
+
+    let sections: Vec<MessageSection> = all_records.clone().map(|(section, _)| section).collect();
+    assert_eq!(
+        sections,
+        vec![
+            MessageSection::Answer,
+            MessageSection::Answer,
+            MessageSection::Authority,
+            MessageSection::Additional,
+        ]
+    );
+
+    // the records are borrowed from the message
+    for ((_, record), expect) in all_records.zip(
+        message
+            .answers()
+            .iter()
+            .chain(message.name_servers())
+            .chain(message.additionals()),
+    ) {
+        assert!(std::ptr::eq(record, expect));
+    }
+
+    assert_eq!(
+        message
+            .all_records()
+            .filter(|(_, r)| r.rr_type() == RecordType::A)
+            .count(),
+        3
+    );
+    assert_eq!(Message::new().all_records().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "dnssec")]
+fn test_rrsets() {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use crate::rr::dnssec::rdata::SIG;
+    use crate::rr::dnssec::Algorithm;
+    use crate::rr::{Name, RData};
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let a1 = Record::from_rdata(
+        name.clone(),
+        86400,
+        RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+    );
+    let a2 = Record::from_rdata(
+        name.clone(),
+        86400,
+        RData::A(Ipv4Addr::new(93, 184, 216, 35)),
+    );
+    let mut rrsig = Record::with(
+        name.clone(),
+        RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+        86400,
+    );
+    rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+        RecordType::A,
+        Algorithm::RSASHA256,
+        3,
+        86400,
+        5,
+        0,
+        2,
+        Name::from_str("example.com.").unwrap(),
+        vec![0xDE, 0xAD, 0xBE, 0xEF],
+    ))));
+    // this rrsig doesn't cover anything in the message
+    let mut other_rrsig = rrsig.clone();
+    other_rrsig.set_name(Name::from_str("other.example.com.").unwrap());
+
+    let mut message = Message::new();
+    message
+        .add_answer(a1.clone())
+        .add_answer(rrsig.clone())
+        .add_answer(a2.clone())
+        .add_answer(other_rrsig);
+
+    let rrsets = message.rrsets();
+    assert_eq!(rrsets.len(), 1);
+
+    let rrset = &rrsets[0];
+    assert_eq!(rrset.name(), &name);
+    assert_eq!(rrset.record_type(), RecordType::A);
+    assert_eq!(
+        rrset.records_without_rrsigs().collect::<Vec<_>>(),
+        vec![&a1, &a2]
+    );
+    assert_eq!(rrset.rrsigs(), &[rrsig]);
+}
+
+#[test]
+fn test_estimated_len() {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use crate::rr::rdata::{MX, TXT};
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(name.clone(), RecordType::A))
+        .add_answer(Record::from_rdata(
+            name.clone(),
+            86400,
+            RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+        ))
+        .add_answer(Record::from_rdata(
+            name.clone(),
+            86400,
+            RData::AAAA(Ipv6Addr::new(
+                0x2606, 0x2800, 0x220, 1, 0x248, 0x1893, 0x25c8, 0x1946,
+            )),
+        ))
+        .add_answer(Record::from_rdata(
+            Name::from_str("mail.example.com.").unwrap(),
+            86400,
+            RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()])),
+        ))
+        .add_name_server(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            86400,
+            RData::NS(Name::from_str("a.iana-servers.net.").unwrap()),
+        ))
+        .add_additional(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            86400,
+            RData::MX(MX::new(10, Name::from_str("mail.example.com.").unwrap())),
+        ));
+
+    // the names of the rdata are compressed as well
+    let len = message.to_vec().unwrap().len();
+    let estimate = message.estimated_len();
+    assert!(estimate >= len);
+    assert!(estimate <= len + 32, "{} > {} + 32", estimate, len);
+
+    // without names in record data the estimate is exact
+    let mut message = Message::new();
+    message.add_query(Query::query(name.clone(), RecordType::A));
+    for i in 0..10 {
+        message.add_answer(Record::from_rdata(
+            Name::from_str(&format!("host{}.example.com.", i)).unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(93, 184, 216, i)),
+        ));
+    }
+    message.set_edns(Edns::new());
+    message
+        .edns_mut()
+        .set_option(EdnsOption::Padding(vec![0; 7]));
+    assert_eq!(message.estimated_len(), message.to_vec().unwrap().len());
+}
+
+#[test]
+fn test_matches_query() {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    let mut query = Message::new();
+    query.set_id(0x1234).add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+
+    let mut response = query.clone();
+    response.set_message_type(MessageType::Response);
+    assert!(response.matches_query(&query));
+
+    // names are compared case-insensitively
+    let mut response = Message::new();
+    response.set_id(0x1234).add_query(Query::query(
+        Name::from_str("WWW.Example.COM.").unwrap(),
+        RecordType::A,
+    ));
+    assert!(response.matches_query(&query));
+
+    // the id matches, but the name does not
+    let mut response = Message::new();
+    response.set_id(0x1234).add_query(Query::query(
+        Name::from_str("www.example.net.").unwrap(),
+        RecordType::A,
+    ));
+    assert!(!response.matches_query(&query));
+
+    // the type does not match
+    let mut response = Message::new();
+    response.set_id(0x1234).add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::AAAA,
+    ));
+    assert!(!response.matches_query(&query));
+
+    // the question is missing, e.g. in a FORMERR
+    let mut response = Message::new();
+    response
+        .set_id(0x1234)
+        .set_response_code(ResponseCode::FormErr);
+    assert!(response.matches_query(&query));
+
+    // but an answer needs the question, it could be spoofed with the id alone
+    let mut response = Message::new();
+    response
+        .set_id(0x1234)
+        .set_message_type(MessageType::Response)
+        .add_answer(Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        ));
+    assert!(!response.matches_query(&query));
+
+    // as does an error with answers
+    response.set_response_code(ResponseCode::ServFail);
+    assert!(!response.matches_query(&query));
+
+    // and a NOERROR without answers
+    let mut response = Message::new();
+    response
+        .set_id(0x1234)
+        .set_message_type(MessageType::Response);
+    assert!(!response.matches_query(&query));
+
+    // unless it is the response to an UPDATE
+    let mut update = query.clone();
+    update.set_op_code(OpCode::Update);
+    response.set_op_code(OpCode::Update);
+    assert!(response.matches_query(&update));
+    assert!(!response.matches_query(&query));
+
+    // another number of questions
+    let mut response = query.clone();
+    response.add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::AAAA,
+    ));
+    assert!(!response.matches_query(&query));
+
+    // the id does not match
+    let mut response = query.clone();
+    response.set_id(0x4321);
+    assert!(!response.matches_query(&query));
+}
+
+#[test]
+fn test_edns() {
+    let mut message = Message::new();
+    assert!(message.edns().is_none());
+    assert_eq!(message.max_payload(), 512);
+    assert!(!message.dnssec_ok());
+
+    // an OPT record added by hand is replaced by the EDNS section
+    message.add_additional(Record::from(&Edns::new()));
+
+    let mut edns = Edns::new();
+    edns.set_max_payload(1232);
+    edns.set_dnssec_ok(true);
+    message.set_edns(edns);
+    assert!(message.additionals().is_empty());
+
+    let bytes = message.to_vec().unwrap();
+    let read = Message::from_vec(&bytes).unwrap();
+    assert_eq!(read.header().additional_count(), 1);
+    assert_eq!(read.edns().map(Edns::max_payload), Some(1232));
+    assert_eq!(read.max_payload(), 1232);
+    assert!(read.dnssec_ok());
+}
@@ -25,6 +25,10 @@ use crate::rr::domain::Name;
 use crate::rr::record_type::RecordType;
 use crate::serialize::binary::*;
 
+/// The top bit of the class of mDNS questions, requesting a unicast response
+#[cfg(feature = "mdns")]
+const MDNS_UNICAST_RESPONSE: u16 = 1 << 15;
+
 /// Query struct for looking up resource records, basically a resource record without RDATA.
 ///
 /// [RFC 1035, DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION, November 1987](https://tools.ietf.org/html/rfc1035)
@@ -54,6 +58,8 @@ pub struct Query {
     name: Name,
     query_type: RecordType,
     query_class: DNSClass,
+    #[cfg(feature = "mdns")]
+    mdns_unicast_response: bool,
 }
 
 impl Default for Query {
@@ -63,6 +69,8 @@ impl Default for Query {
             name: Name::new(),
             query_type: RecordType::A,
             query_class: DNSClass::IN,
+            #[cfg(feature = "mdns")]
+            mdns_unicast_response: false,
         }
     }
 }
@@ -79,6 +87,8 @@ impl Query {
             name,
             query_type,
             query_class: DNSClass::IN,
+            #[cfg(feature = "mdns")]
+            mdns_unicast_response: false,
         }
     }
 
@@ -100,6 +110,14 @@ impl Query {
         self
     }
 
+    /// Changes mDNS unicast-response bit
+    /// See [RFC 6762](https://tools.ietf.org/html/rfc6762#section-5.4)
+    #[cfg(feature = "mdns")]
+    pub fn set_mdns_unicast_response(&mut self, flag: bool) -> &mut Self {
+        self.mdns_unicast_response = flag;
+        self
+    }
+
     /// ```text
     /// QNAME           a domain name represented as a sequence of labels, where
     ///                 each label consists of a length octet followed by that
@@ -129,14 +147,38 @@ impl Query {
     pub fn query_class(&self) -> DNSClass {
         self.query_class
     }
+
+    /// Returns if the mDNS unicast-response bit is set or not, i.e. the question is QU rather
+    ///  than QM
+    ///
+    /// ```text
+    /// To avoid large floods of potentially unnecessary responses in these
+    /// cases, Multicast DNS defines the top bit in the class field of a DNS
+    /// question as the unicast-response bit.
+    /// ```
+    #[cfg(feature = "mdns")]
+    pub fn mdns_unicast_response(&self) -> bool {
+        self.mdns_unicast_response
+    }
 }
 
 impl BinEncodable for Query {
     fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
         self.name.emit(encoder)?;
         self.query_type.emit(encoder)?;
+
+        #[cfg(not(feature = "mdns"))]
         self.query_class.emit(encoder)?;
 
+        #[cfg(feature = "mdns")]
+        {
+            if self.mdns_unicast_response {
+                encoder.emit_u16(u16::from(self.query_class) | MDNS_UNICAST_RESPONSE)?;
+            } else {
+                self.query_class.emit(encoder)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -145,12 +187,27 @@ impl<'r> BinDecodable<'r> for Query {
     fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
         let name = Name::read(decoder)?;
         let query_type = RecordType::read(decoder)?;
+
+        #[cfg(not(feature = "mdns"))]
         let query_class = DNSClass::read(decoder)?;
 
+        #[cfg(feature = "mdns")]
+        let (query_class, mdns_unicast_response) = {
+            let query_class_value =
+                decoder.read_u16()?.unverified(/*DNSClass::from_u16 will verify the value*/);
+
+            (
+                DNSClass::from_u16(query_class_value & !MDNS_UNICAST_RESPONSE)?,
+                query_class_value & MDNS_UNICAST_RESPONSE != 0,
+            )
+        };
+
         Ok(Query {
             name,
             query_type,
             query_class,
+            #[cfg(feature = "mdns")]
+            mdns_unicast_response,
         })
     }
 }
@@ -171,6 +228,8 @@ fn test_read_and_emit() {
         name: Name::from_ascii("WWW.example.com").unwrap(),
         query_type: RecordType::AAAA,
         query_class: DNSClass::IN,
+        #[cfg(feature = "mdns")]
+        mdns_unicast_response: false,
     };
 
     let mut byte_vec: Vec<u8> = Vec::with_capacity(512);
@@ -183,3 +242,28 @@ fn test_read_and_emit() {
     let got = Query::read(&mut decoder).unwrap();
     assert_eq!(got, expect);
 }
+
+#[cfg(feature = "mdns")]
+#[test]
+fn test_mdns_unicast_response_bit() {
+    let mut expect = Query::query(
+        Name::from_ascii("_http._tcp.local.").unwrap(),
+        RecordType::PTR,
+    );
+    expect.set_mdns_unicast_response(true);
+
+    let mut byte_vec: Vec<u8> = Vec::with_capacity(512);
+    {
+        let mut encoder = BinEncoder::new(&mut byte_vec);
+        expect.emit(&mut encoder).unwrap();
+    }
+
+    // the class is IN, with the top bit set
+    assert_eq!(&byte_vec[byte_vec.len() - 2..], &[0x80, 0x01]);
+
+    let mut decoder = BinDecoder::new(&byte_vec);
+    let got = Query::read(&mut decoder).unwrap();
+    assert_eq!(got.query_class(), DNSClass::IN);
+    assert!(got.mdns_unicast_response());
+    assert_eq!(got, expect);
+}
@@ -102,6 +102,9 @@ pub enum Algorithm {
     RSASHA256,
     /// RSA public key with SHA512 hash
     RSASHA512,
+    /// [rfc5933](https://tools.ietf.org/html/rfc5933), GOST R 34.10-2001, recognized but not
+    ///  supported for signing or verification
+    ECCGOST,
     /// [rfc6605](https://tools.ietf.org/html/rfc6605)
     ECDSAP256SHA256,
     /// [rfc6605](https://tools.ietf.org/html/rfc6605)
@@ -120,6 +123,7 @@ impl Algorithm {
             7 => Algorithm::RSASHA1NSEC3SHA1,
             8 => Algorithm::RSASHA256,
             10 => Algorithm::RSASHA512,
+            12 => Algorithm::ECCGOST,
             13 => Algorithm::ECDSAP256SHA256,
             14 => Algorithm::ECDSAP384SHA384,
             15 => Algorithm::ED25519,
@@ -134,10 +138,29 @@ impl Algorithm {
             Algorithm::RSASHA256 | Algorithm::ECDSAP256SHA256 | Algorithm::ED25519 => Some(32), // 256 bits
             Algorithm::ECDSAP384SHA384 => Some(48),
             Algorithm::RSASHA512 => Some(64), // 512 bites
+            Algorithm::ECCGOST => Some(32),   // GOST R 34.11-94, 256 bits
             Algorithm::Unknown(_) => None,
         }
     }
 
+    /// length in bytes of the public key of this algorithm, None if it is not fixed
+    ///
+    /// The ECDSA keys are the uncompressed points without the leading 0x04,
+    ///  [RFC 6605](https://tools.ietf.org/html/rfc6605#section-4), the Ed25519 key the encoded
+    ///  point, [RFC 8080](https://tools.ietf.org/html/rfc8080#section-3).
+    pub fn public_key_len(self) -> Option<usize> {
+        match self {
+            Algorithm::ECDSAP256SHA256 | Algorithm::ECCGOST => Some(64),
+            Algorithm::ECDSAP384SHA384 => Some(96),
+            Algorithm::ED25519 => Some(32),
+            Algorithm::RSASHA1
+            | Algorithm::RSASHA1NSEC3SHA1
+            | Algorithm::RSASHA256
+            | Algorithm::RSASHA512
+            | Algorithm::Unknown(_) => None,
+        }
+    }
+
     /// Convert to string form
     #[deprecated(note = "use as_str instead")]
     pub fn to_str(self) -> &'static str {
@@ -151,6 +174,7 @@ impl Algorithm {
             Algorithm::RSASHA256 => "RSASHA256",
             Algorithm::RSASHA1NSEC3SHA1 => "RSASHA1-NSEC3-SHA1",
             Algorithm::RSASHA512 => "RSASHA512",
+            Algorithm::ECCGOST => "ECC-GOST",
             Algorithm::ECDSAP256SHA256 => "ECDSAP256SHA256",
             Algorithm::ECDSAP384SHA384 => "ECDSAP384SHA384",
             Algorithm::ED25519 => "ED25519",
@@ -187,6 +211,7 @@ impl From<Algorithm> for u8 {
             Algorithm::RSASHA1NSEC3SHA1 => 7,
             Algorithm::RSASHA256 => 8,
             Algorithm::RSASHA512 => 10,
+            Algorithm::ECCGOST => 12,
             Algorithm::ECDSAP256SHA256 => 13,
             Algorithm::ECDSAP384SHA384 => 14,
             Algorithm::ED25519 => 15,
@@ -208,6 +233,7 @@ fn test_into() {
         Algorithm::RSASHA256,
         Algorithm::RSASHA1NSEC3SHA1,
         Algorithm::RSASHA512,
+        Algorithm::ECCGOST,
         Algorithm::ECDSAP256SHA256,
         Algorithm::ECDSAP384SHA384,
         Algorithm::ED25519,
@@ -226,6 +252,7 @@ fn test_order() {
         Algorithm::RSASHA256,
         Algorithm::RSASHA1NSEC3SHA1,
         Algorithm::RSASHA512,
+        Algorithm::ECCGOST,
         Algorithm::ECDSAP256SHA256,
         Algorithm::ECDSAP384SHA384,
         Algorithm::ED25519,
@@ -239,6 +266,7 @@ fn test_order() {
             Algorithm::RSASHA1NSEC3SHA1,
             Algorithm::RSASHA256,
             Algorithm::RSASHA512,
+            Algorithm::ECCGOST,
             Algorithm::ECDSAP256SHA256,
             Algorithm::ECDSAP384SHA384,
             Algorithm::ED25519,
@@ -248,3 +276,21 @@ fn test_order() {
         assert_eq!(got, expect);
     }
 }
+
+#[test]
+fn test_read_emit_ecc_gost() {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = BinEncoder::new(&mut bytes);
+        Algorithm::ECCGOST.emit(&mut encoder).unwrap();
+    }
+    assert_eq!(bytes, vec![12]);
+
+    let mut decoder = BinDecoder::new(&bytes);
+    assert_eq!(Algorithm::read(&mut decoder).unwrap(), Algorithm::ECCGOST);
+    assert_eq!(Algorithm::ECCGOST.to_string(), "ECC-GOST");
+
+    // recognized, but not supported
+    assert!(!super::SupportedAlgorithms::all().has(Algorithm::ECCGOST));
+    assert!(super::PublicKeyEnum::from_public_bytes(&[0; 64], Algorithm::ECCGOST).is_err());
+}
@@ -0,0 +1,110 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The DNSSec algorithms accepted by a validator
+
+use crate::rr::dnssec::{Algorithm, SupportedAlgorithms};
+
+/// The algorithms of the signatures a validator accepts
+///
+/// RRSIGs of disallowed algorithms are ignored. Records which are signed only with disallowed
+///  algorithms are `Validity::Bogus` by default, with `BogusReason::DisallowedAlgorithm`.
+///
+/// With `set_disallowed_insecure(true)` they are `Validity::Insecure` instead if the trust
+///  anchors or the validated DS rrset of their zone, or of one of its ancestors, only list
+///  disallowed algorithms, as if the validator did not implement the algorithms, see
+///  [RFC 4035](https://tools.ietf.org/html/rfc4035#section-5.2)
+///
+/// ```text
+///    If the validator does not support any of the algorithms listed in an
+///    authenticated DS RRset, then the resolver has no supported
+///    authentication path leading from the parent to the child.  The
+///    resolver should treat this case as it would the case of an
+///    authenticated NSEC RRset proving that no DS RRset exists, as
+///    described above.
+/// ```
+///
+/// Records of zones which are signed with allowed algorithms stay `Validity::Bogus`, their RRSIGs
+///  of disallowed algorithms may be forged.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AlgorithmPolicy {
+    allowed: SupportedAlgorithms,
+    disallowed_insecure: bool,
+}
+
+impl AlgorithmPolicy {
+    /// All algorithms are allowed
+    pub fn new() -> Self {
+        Self::from_allowed(SupportedAlgorithms::all())
+    }
+
+    /// Only the specified algorithms are allowed
+    pub fn from_allowed(allowed: SupportedAlgorithms) -> Self {
+        AlgorithmPolicy {
+            allowed,
+            disallowed_insecure: false,
+        }
+    }
+
+    /// The minimum algorithm, and all algorithms which are ordered after it, are allowed
+    ///
+    /// e.g. with a minimum of `Algorithm::RSASHA256` the SHA-1 algorithms are disallowed
+    pub fn with_minimum(minimum: Algorithm) -> Self {
+        let allowed = SupportedAlgorithms::all()
+            .iter()
+            .filter(|algorithm| *algorithm >= minimum)
+            .collect::<Vec<_>>();
+
+        Self::from_allowed(SupportedAlgorithms::from_vec(&allowed))
+    }
+
+    /// Returns true if signatures of the algorithm are accepted
+    pub fn is_allowed(&self, algorithm: Algorithm) -> bool {
+        self.allowed.has(algorithm)
+    }
+
+    /// The allowed algorithms
+    pub fn allowed(&self) -> SupportedAlgorithms {
+        self.allowed
+    }
+
+    /// Records signed only with disallowed algorithms, in a zone whose chain of trust only lists
+    ///  disallowed algorithms, are `Validity::Insecure` if true, otherwise they are
+    ///  `Validity::Bogus`, the default
+    pub fn set_disallowed_insecure(&mut self, disallowed_insecure: bool) -> &mut Self {
+        self.disallowed_insecure = disallowed_insecure;
+        self
+    }
+
+    /// Returns true if records signed only with disallowed algorithms may be `Validity::Insecure`
+    pub fn disallowed_insecure(&self) -> bool {
+        self.disallowed_insecure
+    }
+}
+
+impl Default for AlgorithmPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_with_minimum() {
+    let policy = AlgorithmPolicy::with_minimum(Algorithm::RSASHA256);
+
+    assert!(!policy.is_allowed(Algorithm::RSASHA1));
+    assert!(!policy.is_allowed(Algorithm::RSASHA1NSEC3SHA1));
+    assert!(policy.is_allowed(Algorithm::RSASHA256));
+    assert!(policy.is_allowed(Algorithm::RSASHA512));
+    assert!(policy.is_allowed(Algorithm::ECDSAP256SHA256));
+    assert!(policy.is_allowed(Algorithm::ECDSAP384SHA384));
+    assert!(policy.is_allowed(Algorithm::ED25519));
+    assert!(!policy.is_allowed(Algorithm::Unknown(200)));
+    assert!(!policy.disallowed_insecure());
+
+    assert!(AlgorithmPolicy::new().is_allowed(Algorithm::RSASHA1));
+}
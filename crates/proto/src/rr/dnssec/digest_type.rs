@@ -145,7 +145,7 @@ impl From<Algorithm> for DigestType {
             Algorithm::ECDSAP384SHA384 => DigestType::SHA384,
             Algorithm::ED25519 => DigestType::ED25519,
 
-            Algorithm::Unknown(_) => DigestType::SHA512,
+            Algorithm::ECCGOST | Algorithm::Unknown(_) => DigestType::SHA512,
         }
     }
 }
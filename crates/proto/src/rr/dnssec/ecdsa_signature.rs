@@ -0,0 +1,210 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Conversions of ECDSA signatures between the DNSSec and the ASN.1 DER formats
+//!
+//! ```text
+//! RFC 6605                    ECDSA for DNSSEC                  April 2012
+//!
+//!   The ECDSA signature is the combination of two non-negative integers,
+//!   called "r" and "s" in FIPS 186-3.  The two integers, each of which is
+//!   formatted as a simple octet string, are combined into a single longer
+//!   octet string for DNSSEC as the concatenation "r | s".  (Conversion of
+//!   the integers to bit strings is described in Section C.2 of FIPS
+//!   186-3.)  For P-256, each integer MUST be encoded as 32 octets; for
+//!   P-384, each integer MUST be encoded as 48 octets.
+//! ```
+//!
+//! OpenSSL expects, and produces, the DER encoding of the `Ecdsa-Sig-Value` of
+//!  [RFC 3279](https://tools.ietf.org/html/rfc3279#section-2.2.3) instead.
+
+use crate::error::*;
+use crate::rr::dnssec::Algorithm;
+
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+
+/// The length of each of r and s in the DNSSec format of signatures of the algorithm
+fn component_len(algorithm: Algorithm) -> ProtoResult<usize> {
+    match algorithm {
+        Algorithm::ECDSAP256SHA256 => Ok(32),
+        Algorithm::ECDSAP384SHA384 => Ok(48),
+        _ => Err(format!("not an ECDSA algorithm: {}", algorithm).into()),
+    }
+}
+
+/// Converts the DNSSec format of an ECDSA signature, "r | s", to ASN.1 DER
+///
+/// # Arguments
+///
+/// * `algorithm` - the algorithm of the signature, ECDSAP256SHA256 or ECDSAP384SHA384
+/// * `signature` - r and s, each of the fixed size of the curve of the algorithm
+pub fn ecdsa_signature_to_der(algorithm: Algorithm, signature: &[u8]) -> ProtoResult<Vec<u8>> {
+    let len = component_len(algorithm)?;
+    if signature.len() != len * 2 {
+        return Err(format!(
+            "expected {} byte signature for {}: {}",
+            len * 2,
+            algorithm,
+            signature.len()
+        )
+        .into());
+    }
+
+    let (r, s) = signature.split_at(len);
+
+    // the content is at most 2 * (2 + 49) bytes, so the lengths are always in the short form
+    let mut der = vec![SEQUENCE, 0];
+    emit_integer(&mut der, r);
+    emit_integer(&mut der, s);
+    der[1] = (der.len() - 2) as u8;
+
+    Ok(der)
+}
+
+/// Converts an ASN.1 DER ECDSA signature to the DNSSec format, "r | s"
+///
+/// # Arguments
+///
+/// * `algorithm` - the algorithm of the signature, ECDSAP256SHA256 or ECDSAP384SHA384
+/// * `der` - the DER encoded `Ecdsa-Sig-Value`, a sequence of the integers r and s
+///
+/// # Return
+///
+/// r and s, each padded to the fixed size of the curve of the algorithm
+pub fn ecdsa_signature_from_der(algorithm: Algorithm, der: &[u8]) -> ProtoResult<Vec<u8>> {
+    let len = component_len(algorithm)?;
+
+    if der.len() < 2 || der[0] != SEQUENCE || der[1] as usize != der.len() - 2 {
+        return Err("invalid ECDSA signature: expected a DER sequence".into());
+    }
+    let content = &der[2..];
+
+    let mut signature = Vec::with_capacity(len * 2);
+    let rest = read_integer(&mut signature, len, content)?;
+    let rest = read_integer(&mut signature, len, rest)?;
+    if !rest.is_empty() {
+        return Err("invalid ECDSA signature: trailing data".into());
+    }
+
+    Ok(signature)
+}
+
+/// Emits the unsigned big endian integer as a DER integer, which is minimal and signed
+fn emit_integer(der: &mut Vec<u8>, int: &[u8]) {
+    let start = int.iter().position(|b| *b != 0).unwrap_or(int.len() - 1);
+    let int = &int[start..];
+
+    der.push(INTEGER);
+    if int[0] > 0x7f {
+        // the high bit is the sign
+        der.push(int.len() as u8 + 1);
+        der.push(0);
+    } else {
+        der.push(int.len() as u8);
+    }
+    der.extend_from_slice(int);
+}
+
+/// Reads a DER integer, appending it to the signature padded to `len` bytes, returns the rest of
+///  the DER
+fn read_integer<'a>(signature: &mut Vec<u8>, len: usize, der: &'a [u8]) -> ProtoResult<&'a [u8]> {
+    if der.len() < 2 || der[0] != INTEGER || der[1] as usize > der.len() - 2 {
+        return Err("invalid ECDSA signature: expected a DER integer".into());
+    }
+    let (int, rest) = der[2..].split_at(der[1] as usize);
+
+    match int.first() {
+        None => return Err("invalid ECDSA signature: empty integer".into()),
+        Some(b) if *b > 0x7f => return Err("invalid ECDSA signature: negative integer".into()),
+        Some(_) => (),
+    }
+
+    let start = int.iter().position(|b| *b != 0).unwrap_or(int.len());
+    let int = &int[start..];
+    if int.len() > len {
+        return Err(format!("invalid ECDSA signature: integer exceeds {} bytes", len).into());
+    }
+
+    signature.resize(signature.len() + len - int.len(), 0);
+    signature.extend_from_slice(int);
+
+    Ok(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_integer() {
+        fn test_case(source: &[u8], expected_data: &[u8]) {
+            let mut output = Vec::<u8>::new();
+            emit_integer(&mut output, source);
+            assert_eq!(output[0], INTEGER);
+            assert_eq!(output[1], expected_data.len() as u8);
+            assert_eq!(&output[2..], expected_data);
+        }
+        test_case(&[0x00], &[0x00]);
+        test_case(&[0x00, 0x00], &[0x00]);
+        test_case(&[0x7f], &[0x7f]);
+        test_case(&[0x80], &[0x00, 0x80]);
+        test_case(&[0x00, 0x80], &[0x00, 0x80]);
+        test_case(&[0x00, 0x00, 0x80], &[0x00, 0x80]);
+        test_case(&[0x7f, 0x00, 0x80], &[0x7f, 0x00, 0x80]);
+        test_case(&[0x00, 0x7f, 0x00, 0x80], &[0x7f, 0x00, 0x80]);
+        test_case(&[0x80, 0x00, 0x80], &[0x00, 0x80, 0x00, 0x80]);
+        test_case(&[0xff, 0x00, 0x80], &[0x00, 0xff, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn test_der_round_trip() {
+        for &(algorithm, len) in &[
+            (Algorithm::ECDSAP256SHA256, 32),
+            (Algorithm::ECDSAP384SHA384, 48),
+        ] {
+            // r with the high bit set, s with leading zeros
+            let mut signature = vec![0xff; len];
+            signature.extend(vec![0; len - 1]);
+            signature.push(0x01);
+
+            let der = ecdsa_signature_to_der(algorithm, &signature).unwrap();
+            assert_eq!(der[0], SEQUENCE);
+            assert_eq!(&der[2..5], &[INTEGER, len as u8 + 1, 0]);
+            assert_eq!(&der[der.len() - 3..], &[INTEGER, 1, 0x01]);
+
+            assert_eq!(
+                ecdsa_signature_from_der(algorithm, &der).unwrap(),
+                signature
+            );
+        }
+    }
+
+    #[test]
+    fn test_der_lengths() {
+        let signature = vec![0x01; 64];
+        assert!(ecdsa_signature_to_der(Algorithm::ECDSAP256SHA256, &signature).is_ok());
+        assert!(ecdsa_signature_to_der(Algorithm::ECDSAP384SHA384, &signature).is_err());
+        assert!(ecdsa_signature_to_der(Algorithm::ED25519, &signature).is_err());
+        assert!(ecdsa_signature_to_der(Algorithm::ECDSAP256SHA256, &signature[1..]).is_err());
+
+        // a P-384 sized integer doesn't fit P-256
+        let der = ecdsa_signature_to_der(Algorithm::ECDSAP384SHA384, &[0x01; 96]).unwrap();
+        assert!(ecdsa_signature_from_der(Algorithm::ECDSAP256SHA256, &der).is_err());
+        assert!(ecdsa_signature_from_der(Algorithm::ECDSAP384SHA384, &der).is_ok());
+
+        let der = ecdsa_signature_to_der(Algorithm::ECDSAP256SHA256, &signature).unwrap();
+        assert!(
+            ecdsa_signature_from_der(Algorithm::ECDSAP256SHA256, &der[..der.len() - 1]).is_err()
+        );
+
+        let mut trailing = der.clone();
+        trailing.push(0);
+        trailing[1] += 1;
+        assert!(ecdsa_signature_from_der(Algorithm::ECDSAP256SHA256, &trailing).is_err());
+    }
+}
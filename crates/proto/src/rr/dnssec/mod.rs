@@ -17,28 +17,40 @@
 //! dns security extension related modules
 
 mod algorithm;
+mod algorithm_policy;
 mod digest_type;
 #[cfg(any(feature = "openssl", feature = "ring"))]
 mod ec_public_key;
+mod ecdsa_signature;
 mod nsec3;
 pub mod public_key;
 pub mod rdata;
 #[cfg(any(feature = "openssl", feature = "ring"))]
 mod rsa_public_key;
 mod supported_algorithm;
-mod trust_anchor;
 pub mod tbs;
+mod timestamp;
+mod trust_anchor;
+#[cfg(any(feature = "openssl", feature = "ring"))]
+pub mod tsig;
+mod validity;
 mod verifier;
 
 pub use self::algorithm::Algorithm;
+pub use self::algorithm_policy::AlgorithmPolicy;
 pub use self::digest_type::DigestType;
-pub use self::nsec3::Nsec3HashAlgorithm;
+pub use self::ecdsa_signature::{ecdsa_signature_from_der, ecdsa_signature_to_der};
+pub use self::nsec3::{hashed_owner_name, owner_name_hash, Nsec3HashAlgorithm};
 pub use self::public_key::PublicKey;
 pub use self::public_key::PublicKeyBuf;
 pub use self::public_key::PublicKeyEnum;
 pub use self::supported_algorithm::SupportedAlgorithms;
 pub use self::tbs::TBS;
-pub use self::trust_anchor::TrustAnchor;
+pub use self::timestamp::{format_timestamp, parse_timestamp};
+pub use self::trust_anchor::{TrustAnchor, TrustAnchors};
+#[cfg(any(feature = "openssl", feature = "ring"))]
+pub use self::tsig::{TsigSigner, TsigVerifier};
+pub use self::validity::{BogusReason, Validity};
 pub use self::verifier::Verifier;
 
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
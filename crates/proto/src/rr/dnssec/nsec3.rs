@@ -18,8 +18,8 @@
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use super::{Digest, DigestType};
 use crate::error::*;
-#[cfg(any(feature = "openssl", feature = "ring"))]
 use crate::rr::Name;
+use crate::serialize::base32hex;
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use crate::serialize::binary::{BinEncodable, BinEncoder};
 
@@ -174,6 +174,20 @@ impl Nsec3HashAlgorithm {
     }
 }
 
+/// Returns the owner name of an NSEC3 record, the base32 encoded hash prepended to the zone name
+///
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3)
+pub fn hashed_owner_name(hash: &[u8], zone: &Name) -> ProtoResult<Name> {
+    let label = base32hex::encode(hash).to_ascii_lowercase();
+    Ok(Name::from_ascii(label)?.append_domain(zone))
+}
+
+/// Returns the hash from the owner name of an NSEC3 record, the reverse of `hashed_owner_name`
+pub fn owner_name_hash(owner: &Name) -> Option<Vec<u8>> {
+    let label = owner.iter().next()?;
+    base32hex::decode(::std::str::from_utf8(label).ok()?).ok()
+}
+
 impl From<Nsec3HashAlgorithm> for u8 {
     fn from(a: Nsec3HashAlgorithm) -> u8 {
         match a {
@@ -286,16 +300,33 @@ fn test_known_hashes() {
     );
 }
 
+#[test]
+fn test_hashed_owner_name() {
+    let zone = Name::from_ascii("example.").unwrap();
+    let hash = base32hex::decode("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom").unwrap();
+
+    let owner = hashed_owner_name(&hash, &zone).unwrap();
+    assert_eq!(
+        owner,
+        Name::from_ascii("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.").unwrap()
+    );
+    assert_eq!(owner_name_hash(&owner), Some(hash.clone()));
+
+    // the encoding is case insensitive, like names
+    let owner = Name::from_ascii("0P9MHAVEQVM6T7VBL5LOP2U3T2RP3TOM.example.").unwrap();
+    assert_eq!(owner_name_hash(&owner), Some(hash));
+
+    assert_eq!(owner_name_hash(&zone), None);
+}
+
 #[cfg(test)]
 #[cfg(any(feature = "openssl", feature = "ring"))]
 fn hash_with_base32(name: &str) -> String {
-    use data_encoding::BASE32_DNSSEC;
-
     // NSEC3PARAM 1 0 12 aabbccdd
     let known_name = Name::parse(name, Some(&Name::new())).unwrap();
     let known_salt = [0xAAu8, 0xBBu8, 0xCCu8, 0xDDu8];
     let hash = Nsec3HashAlgorithm::SHA1
         .hash(&known_salt, &known_name, 12)
         .unwrap();
-    BASE32_DNSSEC.encode(hash.as_ref())
+    base32hex::encode(hash.as_ref()).to_ascii_lowercase()
 }
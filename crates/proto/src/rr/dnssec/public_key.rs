@@ -29,7 +29,7 @@ use ring::signature::{self, ED25519_PUBLIC_KEY_LEN};
 use crate::error::*;
 use crate::rr::dnssec::Algorithm;
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
-use crate::rr::dnssec::DigestType;
+use crate::rr::dnssec::{ecdsa_signature_to_der, DigestType};
 
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use crate::rr::dnssec::ec_public_key::ECPublicKey;
@@ -147,50 +147,19 @@ impl<'k> Ec<'k> {
     }
 }
 
-#[cfg(all(not(feature = "ring"), feature = "openssl"))]
-fn asn1_emit_integer(output: &mut Vec<u8>, int: &[u8]) {
-    assert!(!int.is_empty());
-    output.push(0x02); // INTEGER
-    if int[0] > 0x7f {
-        output.push((int.len() + 1) as u8);
-        output.push(0x00); // MSB must be zero
-        output.extend(int);
-        return;
-    }
-    // Trim leading zeros
-    let mut pos = 0;
-    while pos < int.len() {
-        if int[pos] == 0 {
-            if pos == int.len() - 1 {
-                break;
-            }
-            pos += 1;
-            continue;
-        }
-        if int[pos] > 0x7f {
-            // We need to leave one 0x00 to make MSB zero
-            pos -= 1;
-        }
-        break;
-    }
-    let int_output = &int[pos..];
-    output.push(int_output.len() as u8);
-    output.extend(int_output);
-}
 /// Convert raw DNSSEC ECDSA signature to ASN.1 DER format
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
+#[deprecated(note = "see `ecdsa_signature_to_der`")]
 pub fn dnssec_ecdsa_signature_to_der(signature: &[u8]) -> ProtoResult<Vec<u8>> {
-    if signature.is_empty() || signature.len() & 1 != 0 || signature.len() > 127 {
-        return Err("invalid signature length".into());
-    }
-    let part_len = signature.len() / 2;
-    // ASN.1 SEQUENCE: 0x30 [LENGTH]
-    let mut signature_asn1 = vec![0x30, 0x00];
-    asn1_emit_integer(&mut signature_asn1, &signature[..part_len]);
-    asn1_emit_integer(&mut signature_asn1, &signature[part_len..]);
-    signature_asn1[1] = (signature_asn1.len() - 2) as u8;
-    Ok(signature_asn1)
+    let algorithm = match signature.len() {
+        64 => Algorithm::ECDSAP256SHA256,
+        96 => Algorithm::ECDSAP384SHA384,
+        _ => return Err("invalid signature length".into()),
+    };
+
+    ecdsa_signature_to_der(algorithm, signature)
 }
+
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
 impl<'k> PublicKey for Ec<'k> {
     fn public_bytes(&self) -> &[u8] {
@@ -198,7 +167,7 @@ impl<'k> PublicKey for Ec<'k> {
     }
 
     fn verify(&self, algorithm: Algorithm, message: &[u8], signature: &[u8]) -> ProtoResult<()> {
-        let signature_asn1 = dnssec_ecdsa_signature_to_der(signature)?;
+        let signature_asn1 = ecdsa_signature_to_der(algorithm, signature)?;
         verify_with_pkey(&self.pkey, algorithm, message, &signature_asn1)
     }
 }
@@ -393,9 +362,9 @@ impl<'k> PublicKey for Rsa<'k> {
         let alg = match algorithm {
             Algorithm::RSASHA256 => &signature::RSA_PKCS1_2048_8192_SHA256,
             Algorithm::RSASHA512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-            Algorithm::RSASHA1 => &signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY,
-            Algorithm::RSASHA1NSEC3SHA1 => {
-                return Err("*ring* doesn't support RSASHA1NSEC3SHA1 yet".into())
+            // RSASHA1-NSEC3-SHA1 only differs from RSASHA1 in signaling NSEC3 support
+            Algorithm::RSASHA1 | Algorithm::RSASHA1NSEC3SHA1 => {
+                &signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY
             }
             _ => unreachable!("non-RSA algorithm passed to RSA verify()"),
         };
@@ -502,32 +471,3 @@ impl PublicKey for PublicKeyBuf {
         public_key.verify(algorithm, message, signature)
     }
 }
-
-#[cfg(all(not(feature = "ring"), feature = "openssl"))]
-#[cfg(test)]
-mod tests {
-    #[cfg(feature = "openssl")]
-    #[test]
-    fn test_asn1_emit_integer() {
-        fn test_case(source: &[u8], expected_data: &[u8]) {
-            use crate::rr::dnssec::public_key::asn1_emit_integer;
-
-            let mut output = Vec::<u8>::new();
-            asn1_emit_integer(&mut output, source);
-            assert_eq!(output[0], 0x02);
-            assert_eq!(output[1], expected_data.len() as u8);
-            assert_eq!(&output[2..], expected_data);
-        }
-        test_case(&[0x00], &[0x00]);
-        test_case(&[0x00, 0x00], &[0x00]);
-        test_case(&[0x7f], &[0x7f]);
-        test_case(&[0x80], &[0x00, 0x80]);
-        test_case(&[0x00, 0x80], &[0x00, 0x80]);
-        test_case(&[0x00, 0x00, 0x80], &[0x00, 0x80]);
-        test_case(&[0x7f, 0x00, 0x80], &[0x7f, 0x00, 0x80]);
-        test_case(&[0x00, 0x7f, 0x00, 0x80], &[0x7f, 0x00, 0x80]);
-        test_case(&[0x80, 0x00, 0x80], &[0x00, 0x80, 0x00, 0x80]);
-        test_case(&[0xff, 0x00, 0x80], &[0x00, 0xff, 0x00, 0x80]);
-    }
-
-}
@@ -20,9 +20,7 @@ use crate::error::*;
 use crate::rr::dnssec::{Algorithm, Digest, DigestType};
 use crate::rr::record_data::RData;
 use crate::rr::Name;
-use crate::serialize::binary::{
-    BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict, RestrictedMath,
-};
+use crate::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict};
 
 /// [RFC 4034](https://tools.ietf.org/html/rfc4034#section-2), DNSSEC Resource Records, March 2005
 ///
@@ -189,6 +187,24 @@ impl DNSKEY {
         &self.public_key
     }
 
+    /// Checks that the public key has the length required by the algorithm, see
+    ///  `Algorithm::public_key_len`, e.g. to detect a corrupt trust anchor
+    ///
+    /// Keys of algorithms without a fixed length, like RSA, are not checked.
+    pub fn validate_key_length(&self) -> ProtoResult<()> {
+        match self.algorithm.public_key_len() {
+            Some(expected) if expected != self.public_key.len() => {
+                Err(ProtoErrorKind::DnsKeyPublicKeyLength {
+                    algorithm: self.algorithm,
+                    len: self.public_key.len(),
+                    expected,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Creates a message digest for this DNSKEY record.
     ///
     /// ```text
@@ -324,6 +340,15 @@ impl From<DNSKEY> for RData {
 
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<DNSKEY> {
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
+
+    decoder.read_bounded(rdata_length, read_fields)
+}
+
+/// Reads the fields of the DNSKEY, the decoder is bounded to its rdata
+fn read_fields(decoder: &mut BinDecoder) -> ProtoResult<DNSKEY> {
     let flags: u16 = decoder.read_u16()?.unverified(/*used as a bitfield, this is safe*/);
 
     //    Bits 0-6 and 8-14 are reserved: these bits MUST have value 0 upon
@@ -349,24 +374,19 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
 
     let algorithm: Algorithm = Algorithm::read(decoder)?;
 
-    // the public key is the left-over bytes minus 4 for the first fields
-    //   this sub is safe, as the first 4 fields must have been in the rdata, otherwise there would have been
-    //   an earlier return.
-    let key_len = rdata_length
-        .map(|u| u as usize)
-        .checked_sub(4)
-        .map_err(|_| ProtoError::from("invalid rdata length in DNSKEY"))?
-        .unverified(/*used only as length safely*/);
+    // the public key is the left-over bytes of the rdata, after the first 4 octets of the fields
+    let key_len = decoder.remaining();
     let public_key: Vec<u8> =
         decoder.read_vec(key_len)?.unverified(/*the byte array will fail in usage if invalid*/);
 
-    Ok(DNSKEY::new(
-        zone_key,
-        secure_entry_point,
-        revoke,
-        algorithm,
-        public_key,
-    ))
+    let dnskey = DNSKEY::new(zone_key, secure_entry_point, revoke, algorithm, public_key);
+
+    // the key is still returned, it fails when it is used
+    if let Err(e) = dnskey.validate_key_length() {
+        warn!("invalid DNSKEY: {}", e);
+    }
+
+    Ok(dnskey)
 }
 
 /// Write the RData from the given Decoder
@@ -396,6 +416,10 @@ mod tests {
     #[test]
     #[cfg(any(feature = "openssl", feature = "ring"))]
     pub fn test() {
+        use crate::rr::dnssec::rdata::DNSSECRecordType;
+        use crate::rr::RecordType;
+        use crate::test_util::assert_wire_roundtrip;
+
         let rdata = DNSKEY::new(
             true,
             true,
@@ -404,20 +428,11 @@ mod tests {
             vec![0, 1, 2, 3, 4, 5, 6, 7],
         );
 
-        let mut bytes = Vec::new();
-        let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
-        assert!(emit(&mut encoder, &rdata).is_ok());
-        let bytes = encoder.into_bytes();
-
-        println!("bytes: {:?}", bytes);
-
-        let mut decoder: BinDecoder = BinDecoder::new(bytes);
-        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16));
-        assert!(
-            read_rdata.is_ok(),
-            format!("error decoding: {:?}", read_rdata.unwrap_err())
+        let bytes = assert_wire_roundtrip(
+            &RData::from(rdata.clone()),
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
         );
-        assert_eq!(rdata, read_rdata.unwrap());
+        assert_eq!(bytes, &[0x01, 0x01, 3, 8, 0, 1, 2, 3, 4, 5, 6, 7]);
         assert!(
             rdata
                 .to_digest(
@@ -427,6 +442,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_bounded_to_rdata() {
+        // the public key ends with the rdata, not with the buffer
+        let bytes = [1, 1, 3, 8, 0xAA, 0xBB, 0xCC, 0xDD];
+        let mut decoder = BinDecoder::new(&bytes);
+        let rdata = read(&mut decoder, Restrict::new(6)).expect("failed to read DNSKEY");
+        assert_eq!(rdata.public_key(), &[0xAA, 0xBB]);
+        assert_eq!(decoder.index(), 6);
+
+        // the fields alone exceed the rdata
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(read(&mut decoder, Restrict::new(3)).is_err());
+    }
+
+    #[test]
+    fn test_validate_key_length() {
+        let public_key = vec![0xAB; 32];
+        let rdata = DNSKEY::new(true, true, false, Algorithm::ED25519, public_key.clone());
+        assert!(rdata.validate_key_length().is_ok());
+
+        // the truncated key is still read
+        let rdata = DNSKEY::new(
+            true,
+            true,
+            false,
+            Algorithm::ED25519,
+            public_key[..31].to_vec(),
+        );
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).unwrap();
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(read_rdata, rdata);
+
+        match *read_rdata.validate_key_length().unwrap_err().kind() {
+            ProtoErrorKind::DnsKeyPublicKeyLength {
+                algorithm: Algorithm::ED25519,
+                len: 31,
+                expected: 32,
+            } => (),
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+
+        // RSA keys have no fixed length
+        let rdata = DNSKEY::new(true, true, false, Algorithm::RSASHA256, public_key);
+        assert!(rdata.validate_key_length().is_ok());
+    }
+
     #[test]
     fn test_calculate_key_tag_checksum() {
         let test_text = "The quick brown fox jumps over the lazy dog";
@@ -16,11 +16,15 @@
 
 //! pointer record from parent zone to child zone for dnskey proof
 
+use std::fmt;
+
 use crate::error::*;
 use crate::rr::dnssec::{Algorithm, DigestType};
 use crate::serialize::binary::*;
+use crate::serialize::hex;
 
 use crate::rr::dnssec::rdata::DNSKEY;
+use crate::rr::record_data::RData;
 use crate::rr::Name;
 
 /// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-5)
@@ -184,25 +188,46 @@ impl DS {
     }
 }
 
+impl From<DS> for RData {
+    fn from(ds: DS) -> RData {
+        RData::DNSSEC(super::DNSSECRData::DS(ds))
+    }
+}
+
+impl fmt::Display for DS {
+    /// Formats the DS in the presentation format, e.g. `12345 8 2 <digest>`, with the digest in
+    ///  uppercase hex
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag,
+            u8::from(self.algorithm),
+            u8::from(self.digest_type),
+            hex::encode(&self.digest)
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<DS> {
-    let start_idx = decoder.index();
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
 
-    let key_tag: u16 = decoder.read_u16()?.unverified(/*key_tag is valid as any u16*/);
-    let algorithm: Algorithm = Algorithm::read(decoder)?;
-    let digest_type: DigestType =
-        DigestType::from_u8(decoder.read_u8()?.unverified(/*DigestType is verified as safe*/))?;
+    decoder.read_bounded(rdata_length, |decoder| {
+        let key_tag: u16 = decoder.read_u16()?.unverified(/*key_tag is valid as any u16*/);
+        let algorithm: Algorithm = Algorithm::read(decoder)?;
+        let digest_type: DigestType =
+            DigestType::from_u8(decoder.read_u8()?.unverified(/*DigestType is verified as safe*/))?;
 
-    let bytes_read = decoder.index() - start_idx;
-    let left: usize = rdata_length
-        .map(|u| u as usize)
-        .checked_sub(bytes_read)
-        .map_err(|_| ProtoError::from("invalid rdata length in DS"))?
-        .unverified(/*used only as length safely*/);
-    let digest =
-        decoder.read_vec(left)?.unverified(/*the byte array will fail in usage if invalid*/);
+        // the digest is the rest of the rdata
+        let left = decoder.remaining();
+        let digest =
+            decoder.read_vec(left)?.unverified(/*the byte array will fail in usage if invalid*/);
 
-    Ok(DS::new(key_tag, algorithm, digest_type, digest))
+        Ok(DS::new(key_tag, algorithm, digest_type, digest))
+    })
 }
 
 /// Write the RData from the given Decoder
@@ -240,6 +265,18 @@ pub fn test() {
     assert_eq!(rdata, read_rdata.unwrap());
 }
 
+#[test]
+pub fn test_display() {
+    let rdata = DS::new(
+        12345,
+        Algorithm::RSASHA256,
+        DigestType::SHA256,
+        vec![0x0a, 0xbc, 0xde, 0xf0],
+    );
+
+    assert_eq!(rdata.to_string(), "12345 8 2 0ABCDEF0");
+}
+
 #[test]
 #[cfg(any(feature = "openssl", feature = "ring"))]
 pub fn test_covers() {
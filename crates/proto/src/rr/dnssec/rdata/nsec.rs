@@ -16,9 +16,9 @@
 
 //! negative cache proof for non-existence
 
-use super::nsec3;
 use crate::error::*;
 use crate::rr::dnssec::rdata::DNSSECRecordType;
+use crate::rr::rdata::type_bit_map;
 use crate::rr::{Name, RecordType};
 use crate::serialize::binary::*;
 
@@ -131,17 +131,19 @@ impl NSEC {
 
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<NSEC> {
-    let start_idx = decoder.index();
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
 
-    let next_domain_name = Name::read(decoder)?;
+    decoder.read_bounded(rdata_length, |decoder| {
+        let next_domain_name = Name::read(decoder)?;
 
-    let bit_map_len = rdata_length
-        .map(|u| u as usize)
-        .checked_sub(decoder.index() - start_idx)
-        .map_err(|_| ProtoError::from("invalid rdata length in NSEC"))?;
-    let record_types = nsec3::decode_type_bit_maps(decoder, bit_map_len)?;
+        // the type bit maps are the rest of the rdata
+        let bit_map_len = Restrict::new(decoder.remaining());
+        let record_types = type_bit_map::read(decoder, bit_map_len)?;
 
-    Ok(NSEC::new(next_domain_name, record_types))
+        Ok(NSEC::new(next_domain_name, record_types))
+    })
 }
 
 /// [RFC 6840](https://tools.ietf.org/html/rfc6840#section-6)
@@ -157,7 +159,7 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
 pub fn emit(encoder: &mut BinEncoder, rdata: &NSEC) -> ProtoResult<()> {
     encoder.with_canonical_names(|encoder| {
         rdata.next_domain_name().emit(encoder)?;
-        nsec3::encode_bit_maps(encoder, rdata.type_bit_maps())
+        type_bit_map::emit(encoder, rdata.type_bit_maps())
     })
 }
 
@@ -172,7 +172,7 @@ pub fn read(decoder: &mut BinDecoder) -> ProtoResult<NSEC3PARAM> {
     let salt_len: usize = decoder
         .read_u8()?
         .map(|u| u as usize)
-        .verify_unwrap(|salt_len| *salt_len <= decoder.len())
+        .verify_unwrap(|salt_len| *salt_len <= decoder.remaining())
         .map_err(|_| ProtoError::from("salt_len exceeds buffer length"))?;
     let salt: Vec<u8> = decoder.read_vec(salt_len)?.unverified(/*valid as any array of u8*/);
 
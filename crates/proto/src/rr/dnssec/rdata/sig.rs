@@ -451,8 +451,15 @@ impl SIG {
 
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<SIG> {
-    let start_idx = decoder.index();
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
 
+    decoder.read_bounded(rdata_length, read_fields)
+}
+
+/// Reads the fields of the SIG, the decoder is bounded to its rdata
+fn read_fields(decoder: &mut BinDecoder) -> ProtoResult<SIG> {
     // TODO should we verify here? or elsewhere...
     let type_covered = RecordType::read(decoder)?;
     let algorithm = Algorithm::read(decoder)?;
@@ -464,12 +471,8 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
     let key_tag = decoder.read_u16()?.unverified(/*valid as any u16*/);
     let signer_name = Name::read(decoder)?;
 
-    // read the signature, this will vary buy key size
-    let sig_len = rdata_length
-        .map(|u| u as usize)
-        .checked_sub(decoder.index() - start_idx)
-        .map_err(|_| ProtoError::from("invalid rdata length in SIG"))?
-        .unverified(/*used only as length safely*/);
+    // read the signature, this will vary buy key size, it is the rest of the rdata
+    let sig_len = decoder.remaining();
     let sig = decoder
         .read_vec(sig_len)?
         .unverified(/*will fail in usage if invalid*/);
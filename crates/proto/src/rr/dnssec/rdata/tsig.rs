@@ -0,0 +1,454 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TSIG for secret key authentication of transactions
+
+#[cfg(all(not(feature = "ring"), feature = "openssl"))]
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+#[cfg(feature = "ring")]
+use ring::hmac;
+
+use crate::error::*;
+use crate::rr::Name;
+use crate::serialize::binary::*;
+
+/// [RFC 8945](https://tools.ietf.org/html/rfc8945#section-4.2)
+///
+/// ```text
+/// 4.2.  TSIG Record Format
+///
+///    The fields of the TSIG RR are described below.  All multi-octet
+///    integers in the record are sent in network byte order (see
+///    Section 2.3.2 of [RFC1035]).
+///
+///    NAME:  The name of the key used, in domain name syntax.  The name
+///       should reflect the names of the hosts and uniquely identify the
+///       key among a set of keys these two hosts may share at any given
+///       time.
+///
+///    TYPE:  This MUST be TSIG (250: Transaction SIGnature).
+///
+///    CLASS:  This MUST be ANY.
+///
+///    TTL:  This MUST be 0.
+///
+///    RDLENGTH:  (variable)
+///
+///    RDATA:  The RDATA for a TSIG RR consists of a number of fields,
+///       described below:
+///
+///                           1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///       0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      /                         Algorithm Name                        /
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      |                                                               |
+///      |          Time Signed          +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      |                               |            Fudge              |
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      |          MAC Size             |                               /
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+             MAC               /
+///      /                                                               /
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      |          Original ID          |            Error              |
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///      |          Other Len            |                               /
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+           Other Data          /
+///      /                                                               /
+///      +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+///    The contents of the RDATA fields are:
+///
+///    Algorithm Name:
+///       an octet sequence identifying the TSIG algorithm in the domain
+///       name syntax.  (Allowed names are listed in Table 3.)  The name is
+///       stored in the DNS name wire format as described in [RFC1034].  As
+///       per [RFC3597], this name MUST NOT be compressed.
+///
+///    Time Signed:
+///       an unsigned 48-bit integer containing the time the message was
+///       signed as seconds since 00:00 on 1970-01-01 UTC, ignoring leap
+///       seconds.
+///
+///    Fudge:
+///       an unsigned 16-bit integer specifying the allowed time difference
+///       in seconds permitted in the Time Signed field.
+///
+///    MAC Size:
+///       an unsigned 16-bit integer giving the length of the MAC field in
+///       octets.  Truncation is indicated by a MAC Size less than the size
+///       of the keyed hash produced by the algorithm specified by the
+///       Algorithm Name.
+///
+///    MAC:
+///       a sequence of octets whose contents are defined by the TSIG
+///       algorithm used, possibly truncated as specified by the MAC Size.
+///
+///    Original ID:
+///       an unsigned 16-bit integer holding the message ID of the original
+///       request message.
+///
+///    Error:
+///       in responses, an unsigned 16-bit integer containing the extended
+///       RCODE covering TSIG processing.  In requests, this MUST be zero.
+///
+///    Other Len:
+///       an unsigned 16-bit integer specifying the length of the Other Data
+///       field in octets.
+///
+///    Other Data:
+///       additional data relevant to the TSIG record.  In responses, this
+///       will be empty (i.e., Other Len will be zero) unless the content of
+///       the Error field is BADTIME, in which case it will be a 48-bit
+///       unsigned integer containing the server's current time.
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TSIG {
+    algorithm: TsigAlgorithm,
+    time: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    oid: u16,
+    error: u16,
+    other: Vec<u8>,
+}
+
+impl TSIG {
+    /// Constructs a new TSIG
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the MAC algorithm used for the signature
+    /// * `time` - seconds since the unix epoch, this must fit in 48 bits
+    /// * `fudge` - the allowed difference in seconds from `time`
+    /// * `mac` - the MAC over the message and TSIG variables
+    /// * `oid` - the id of the original request
+    /// * `error` - the extended rcode, zero in requests
+    /// * `other` - other data, the server time for BADTIME errors
+    pub fn new(
+        algorithm: TsigAlgorithm,
+        time: u64,
+        fudge: u16,
+        mac: Vec<u8>,
+        oid: u16,
+        error: u16,
+        other: Vec<u8>,
+    ) -> Self {
+        TSIG {
+            algorithm,
+            time,
+            fudge,
+            mac,
+            oid,
+            error,
+            other,
+        }
+    }
+
+    /// The MAC algorithm used for the signature
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// Time the message was signed, in seconds since the unix epoch
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Allowed difference in seconds between `time` and the time of verification
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// The MAC, possibly truncated
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    /// The message id of the original request
+    pub fn oid(&self) -> u16 {
+        self.oid
+    }
+
+    /// The extended rcode for TSIG processing, zero in requests
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// Other data, in BADTIME responses this is the server's current time
+    pub fn other(&self) -> &[u8] {
+        &self.other
+    }
+}
+
+/// [RFC 8945](https://tools.ietf.org/html/rfc8945#section-6)
+///
+/// ```text
+/// 6.  Algorithms and Identifiers
+///
+///    The only message digest algorithm specified in the first version of
+///    these specifications [RFC2845] was "HMAC-MD5" (see [RFC1321] and
+///    [RFC2104]).  Although a review of its security some years ago
+///    [RFC6151] concluded that "it may not be urgent to remove HMAC-MD5
+///    from the existing protocols", with the availability of more secure
+///    alternatives the opportunity has been taken to make the
+///    implementation of this algorithm optional.
+///
+///    [RFC4635] added mandatory support in TSIG for SHA-1 [FIPS180-4]
+///    [RFC3174].  SHA-1 collisions have been demonstrated, so the
+///    MD5 security considerations described in Section 2 of [RFC6151]
+///    apply to SHA-1 in a similar manner.
+///
+///    The use of SHA-1 in TSIG is therefore NOT RECOMMENDED.
+///
+///    +==========================+================+=================+
+///    | Algorithm Name           | Implementation | Use             |
+///    +==========================+================+=================+
+///    | HMAC-MD5.SIG-ALG.REG.INT | MAY            | MUST NOT        |
+///    | hmac-sha1                | MUST           | NOT RECOMMENDED |
+///    | hmac-sha224              | MAY            | MAY             |
+///    | hmac-sha256              | MUST           | RECOMMENDED     |
+///    | hmac-sha384              | MAY            | MAY             |
+///    | hmac-sha512              | MAY            | MAY             |
+///    +--------------------------+----------------+-----------------+
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum TsigAlgorithm {
+    /// HMAC-MD5.SIG-ALG.REG.INT, not supported with ring
+    HmacMd5,
+    /// hmac-sha1
+    HmacSha1,
+    /// hmac-sha224, not supported with ring
+    HmacSha224,
+    /// hmac-sha256
+    HmacSha256,
+    /// hmac-sha384
+    HmacSha384,
+    /// hmac-sha512
+    HmacSha512,
+    /// Any other algorithm, MACs can not be computed for these
+    Unknown(Name),
+}
+
+impl TsigAlgorithm {
+    /// Returns the well-known algorithm for the name, or `Unknown`.
+    ///
+    /// Algorithm names are compared case-insensitively.
+    pub fn from_name(name: Name) -> Self {
+        match &*name.to_lowercase().to_ascii() {
+            "hmac-md5.sig-alg.reg.int." => TsigAlgorithm::HmacMd5,
+            "hmac-sha1." => TsigAlgorithm::HmacSha1,
+            "hmac-sha224." => TsigAlgorithm::HmacSha224,
+            "hmac-sha256." => TsigAlgorithm::HmacSha256,
+            "hmac-sha384." => TsigAlgorithm::HmacSha384,
+            "hmac-sha512." => TsigAlgorithm::HmacSha512,
+            _ => TsigAlgorithm::Unknown(name),
+        }
+    }
+
+    /// Returns the name used to identify this algorithm in the TSIG record
+    pub fn to_name(&self) -> Name {
+        let name = match *self {
+            TsigAlgorithm::HmacMd5 => "HMAC-MD5.SIG-ALG.REG.INT.",
+            TsigAlgorithm::HmacSha1 => "hmac-sha1.",
+            TsigAlgorithm::HmacSha224 => "hmac-sha224.",
+            TsigAlgorithm::HmacSha256 => "hmac-sha256.",
+            TsigAlgorithm::HmacSha384 => "hmac-sha384.",
+            TsigAlgorithm::HmacSha512 => "hmac-sha512.",
+            TsigAlgorithm::Unknown(ref name) => return name.clone(),
+        };
+
+        Name::from_ascii(name).expect("well-known tsig algorithm names are valid")
+    }
+
+    /// Computes the MAC over the message with the given key
+    ///
+    /// # Return
+    ///
+    /// An `UnknownTsigAlgorithm` error, which should be reported as BADKEY, if the algorithm is
+    ///  not known or not supported by the crypto library in use.
+    #[cfg(feature = "ring")]
+    pub fn mac(&self, key: &[u8], message: &[u8]) -> ProtoResult<Vec<u8>> {
+        let key = hmac::Key::new(self.to_ring_hmac_alg()?, key);
+        Ok(hmac::sign(&key, message).as_ref().to_vec())
+    }
+
+    /// Computes the MAC over the message with the given key
+    ///
+    /// # Return
+    ///
+    /// An `UnknownTsigAlgorithm` error, which should be reported as BADKEY, if the algorithm is
+    ///  not known or not supported by the crypto library in use.
+    #[cfg(all(not(feature = "ring"), feature = "openssl"))]
+    pub fn mac(&self, key: &[u8], message: &[u8]) -> ProtoResult<Vec<u8>> {
+        let key = PKey::hmac(key)?;
+        let mut signer = Signer::new(self.to_openssl_digest()?, &key)?;
+        signer.update(message)?;
+        signer.sign_to_vec().map_err(Into::into)
+    }
+
+    #[cfg(feature = "ring")]
+    fn to_ring_hmac_alg(&self) -> ProtoResult<hmac::Algorithm> {
+        match *self {
+            TsigAlgorithm::HmacSha1 => Ok(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY),
+            TsigAlgorithm::HmacSha256 => Ok(hmac::HMAC_SHA256),
+            TsigAlgorithm::HmacSha384 => Ok(hmac::HMAC_SHA384),
+            TsigAlgorithm::HmacSha512 => Ok(hmac::HMAC_SHA512),
+            _ => Err(ProtoErrorKind::UnknownTsigAlgorithm(self.to_name()).into()),
+        }
+    }
+
+    #[cfg(all(not(feature = "ring"), feature = "openssl"))]
+    fn to_openssl_digest(&self) -> ProtoResult<MessageDigest> {
+        match *self {
+            TsigAlgorithm::HmacMd5 => Ok(MessageDigest::md5()),
+            TsigAlgorithm::HmacSha1 => Ok(MessageDigest::sha1()),
+            TsigAlgorithm::HmacSha224 => Ok(MessageDigest::sha224()),
+            TsigAlgorithm::HmacSha256 => Ok(MessageDigest::sha256()),
+            TsigAlgorithm::HmacSha384 => Ok(MessageDigest::sha384()),
+            TsigAlgorithm::HmacSha512 => Ok(MessageDigest::sha512()),
+            TsigAlgorithm::Unknown(ref name) => {
+                Err(ProtoErrorKind::UnknownTsigAlgorithm(name.clone()).into())
+            }
+        }
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<TSIG> {
+    let start_idx = decoder.index();
+
+    let algorithm = TsigAlgorithm::from_name(Name::read(decoder)?);
+    let time = decoder.read_u48()?.unverified(/*any 48-bit time is valid*/);
+    let fudge = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let mac_size = decoder.read_u16()?.unverified(/*used only as a length safely*/);
+    let mac = decoder
+        .read_vec(mac_size as usize)?
+        .unverified(/*the mac is checked during verification*/);
+    let oid = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let error = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let other_len = decoder.read_u16()?.unverified(/*used only as a length safely*/);
+    let other = decoder
+        .read_vec(other_len as usize)?
+        .unverified(/*opaque data*/);
+
+    let read = decoder.index() - start_idx;
+    rdata_length
+        .map(|u| u as usize)
+        .verify_unwrap(|rdata_length| read == *rdata_length)
+        .map_err(|rdata_length| {
+            ProtoError::from(ProtoErrorKind::IncorrectRDataLengthRead {
+                read,
+                len: rdata_length,
+            })
+        })?;
+
+    Ok(TSIG::new(algorithm, time, fudge, mac, oid, error, other))
+}
+
+/// Write the RData from the given Encoder
+pub fn emit(encoder: &mut BinEncoder, tsig: &TSIG) -> ProtoResult<()> {
+    tsig.algorithm.to_name().emit_as_canonical(encoder, true)?;
+    encoder.emit_u48(tsig.time)?;
+    encoder.emit_u16(tsig.fudge)?;
+    encoder.emit_u16(tsig.mac.len() as u16)?;
+    encoder.emit_vec(&tsig.mac)?;
+    encoder.emit_u16(tsig.oid)?;
+    encoder.emit_u16(tsig.error)?;
+    encoder.emit_u16(tsig.other.len() as u16)?;
+    encoder.emit_vec(&tsig.other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        for alg in &[
+            TsigAlgorithm::HmacMd5,
+            TsigAlgorithm::HmacSha1,
+            TsigAlgorithm::HmacSha224,
+            TsigAlgorithm::HmacSha256,
+            TsigAlgorithm::HmacSha384,
+            TsigAlgorithm::HmacSha512,
+        ] {
+            assert_eq!(TsigAlgorithm::from_name(alg.to_name()), *alg);
+        }
+
+        assert_eq!(
+            TsigAlgorithm::from_name(Name::from_ascii("HMAC-SHA256.").unwrap()),
+            TsigAlgorithm::HmacSha256
+        );
+
+        let unknown = Name::from_ascii("gss-tsig.").unwrap();
+        assert_eq!(
+            TsigAlgorithm::from_name(unknown.clone()),
+            TsigAlgorithm::Unknown(unknown)
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            1_577_836_800,
+            300,
+            vec![0xDE, 0xAD, 0xBE, 0xEF],
+            0x1234,
+            0,
+            vec![],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit TSIG");
+        let bytes = encoder.into_bytes();
+
+        // hmac-sha256. is 13 octets, followed by 6 for the time
+        assert_eq!(&bytes[13..19], &[0, 0, 0x5E, 0x0B, 0xE1, 0x00]);
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_rdata =
+            read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read TSIG");
+        assert_eq!(read_rdata, rdata);
+    }
+
+    // RFC 2202 and RFC 4231, test case 2
+    #[cfg(any(feature = "ring", feature = "openssl"))]
+    #[test]
+    fn test_mac() {
+        let key = b"Jefe";
+        let message = b"what do ya want for nothing?";
+
+        assert_eq!(
+            TsigAlgorithm::HmacSha1.mac(key, message).unwrap(),
+            vec![
+                0xef, 0xfc, 0xdf, 0x6a, 0xe5, 0xeb, 0x2f, 0xa2, 0xd2, 0x74, 0x16, 0xd5, 0xf1, 0x84,
+                0xdf, 0x9c, 0x25, 0x9a, 0x7c, 0x79
+            ]
+        );
+        assert_eq!(
+            TsigAlgorithm::HmacSha256.mac(key, message).unwrap(),
+            vec![
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43
+            ]
+        );
+    }
+
+    #[cfg(any(feature = "ring", feature = "openssl"))]
+    #[test]
+    fn test_mac_unknown() {
+        let unknown = TsigAlgorithm::Unknown(Name::from_ascii("gss-tsig.").unwrap());
+        match *unknown.mac(b"key", b"message").unwrap_err().kind() {
+            ProtoErrorKind::UnknownTsigAlgorithm(ref name) => assert_eq!(*name, unknown.to_name()),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+}
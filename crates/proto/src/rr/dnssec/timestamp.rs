@@ -0,0 +1,176 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Conversion of the signature times of RRSIGs to and from their presentation format
+
+use crate::error::*;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Days from 0000-03-01 to 1970-01-01 in the proleptic Gregorian calendar
+const DAYS_TO_EPOCH: i64 = 719_468;
+/// Days in 400 years, after which the Gregorian calendar repeats
+const DAYS_PER_ERA: i64 = 146_097;
+
+/// Parses a signature expiration or inception time in the `YYYYMMDDHHmmSS` form in UTC, into
+///  seconds since 1 January 1970 00:00:00 UTC
+///
+/// Times past 2106 don't fit into 32 bits, they are taken modulo 2^32 as the serial number
+///  arithmetic of [RFC 4034](https://tools.ietf.org/html/rfc4034#section-3.1.5) expects. A second
+///  of 60 is a leap second, which the seconds since the epoch don't count, it is the first second
+///  of the next minute.
+pub fn parse_timestamp(timestamp: &str) -> ProtoResult<u32> {
+    let invalid = || ProtoError::from(format!("invalid timestamp: {}", timestamp));
+    if timestamp.len() != 14 || !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let field = |range: ::std::ops::Range<usize>| -> i64 {
+        timestamp[range].parse().expect("digits are checked above")
+    };
+
+    let (year, month, day) = (field(0..4), field(4..6), field(6..8));
+    let (hour, minute, second) = (field(8..10), field(10..12), field(12..14));
+
+    if year < 1970
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+
+    let seconds =
+        days_from_epoch(year, month, day) * SECONDS_PER_DAY + (hour * 60 + minute) * 60 + second;
+
+    Ok(seconds as u32)
+}
+
+/// Formats seconds since 1 January 1970 00:00:00 UTC as a signature expiration or inception time
+///  in the `YYYYMMDDHHmmSS` form in UTC
+pub fn format_timestamp(timestamp: u32) -> String {
+    let timestamp = i64::from(timestamp);
+    let (year, month, day) = date_from_epoch(timestamp / SECONDS_PER_DAY);
+    let seconds = timestamp % SECONDS_PER_DAY;
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the date, for years from 1970 on
+fn days_from_epoch(year: i64, month: i64, day: i64) -> i64 {
+    // count the years from March, so that the leap day is the last of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * DAYS_PER_ERA + day_of_era - DAYS_TO_EPOCH
+}
+
+/// The year, month and day of the days since 1970-01-01, the inverse of `days_from_epoch`
+fn date_from_epoch(days: i64) -> (i64, i64, i64) {
+    let days = days + DAYS_TO_EPOCH;
+    let era = days / DAYS_PER_ERA;
+    let day_of_era = days - era * DAYS_PER_ERA;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (year_of_era * 365 + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(parse_timestamp("20230101000000").unwrap(), 1_672_531_200);
+        assert_eq!(format_timestamp(1_672_531_200), "20230101000000");
+
+        // RFC 4034, section 3.3, before 2038 where signed 32 bit times end
+        assert_eq!(parse_timestamp("20030322173103").unwrap(), 1_048_354_263);
+        assert_eq!(format_timestamp(1_048_354_263), "20030322173103");
+        assert_eq!(parse_timestamp("20380119031407").unwrap(), 2_147_483_647);
+        assert_eq!(format_timestamp(2_147_483_647), "20380119031407");
+
+        // unsigned 32 bit times go on until 2106
+        assert_eq!(format_timestamp(2_147_483_648), "20380119031408");
+        assert_eq!(parse_timestamp("21060207062815").unwrap(), u32::max_value());
+        assert_eq!(format_timestamp(u32::max_value()), "21060207062815");
+
+        assert_eq!(format_timestamp(0), "19700101000000");
+        for &timestamp in &[
+            "19700101000000",
+            "19991231235959",
+            "20000229120000",
+            "20000301000000",
+            "20240229235959",
+            "21000301000000",
+        ] {
+            assert_eq!(
+                format_timestamp(parse_timestamp(timestamp).unwrap()),
+                timestamp
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        // leap years
+        assert_eq!(parse_timestamp("20000229000000").unwrap(), 951_782_400);
+        assert_eq!(parse_timestamp("20000301000000").unwrap(), 951_868_800);
+        assert_eq!(parse_timestamp("20240229235959").unwrap(), 1_709_251_199);
+        assert!(parse_timestamp("19000229000000").is_err());
+        assert!(parse_timestamp("20230229000000").is_err());
+        assert!(parse_timestamp("21000229000000").is_err());
+
+        // the leap second is the first second of the next day
+        assert_eq!(parse_timestamp("20161231235960").unwrap(), 1_483_228_800);
+
+        // past 32 bits the time wraps around
+        assert_eq!(parse_timestamp("21060207062816").unwrap(), 0);
+
+        assert!(parse_timestamp("19691231235959").is_err());
+        assert!(parse_timestamp("20231301000000").is_err());
+        assert!(parse_timestamp("20230431000000").is_err());
+        assert!(parse_timestamp("20230100000000").is_err());
+        assert!(parse_timestamp("20230101240000").is_err());
+        assert!(parse_timestamp("20230101006000").is_err());
+        assert!(parse_timestamp("2023010100000a").is_err());
+        assert!(parse_timestamp("+0230101000000").is_err());
+        assert!(parse_timestamp("1672531200").is_err());
+        assert!(parse_timestamp("").is_err());
+    }
+}
@@ -16,9 +16,15 @@
 
 //! Allows for the root trust_anchor to either be added to or replaced for dns_sec validation.
 
+use std::collections::BTreeMap;
 use std::default::Default;
+use std::str::FromStr;
 
-use crate::rr::dnssec::PublicKey;
+use crate::error::*;
+use crate::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, DNSKEY, DS, SIG};
+use crate::rr::dnssec::{Algorithm, DigestType, PublicKey, Verifier};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+use crate::serialize::hex;
 
 const ROOT_ANCHOR_ORIG: &[u8] = include_bytes!("roots/19036.rsa");
 const ROOT_ANCHOR_2018: &[u8] = include_bytes!("roots/20326.rsa");
@@ -69,6 +75,304 @@ impl TrustAnchor {
     pub fn get(&self, idx: usize) -> &[u8] {
         &self.pkeys[idx]
     }
+
+    /// Returns true if there are no trusted keys
+    pub fn is_empty(&self) -> bool {
+        self.pkeys.is_empty()
+    }
+}
+
+/// The add hold-down time of RFC 5011, 30 days in seconds
+pub const ADD_HOLD_DOWN: u64 = 30 * 24 * 60 * 60;
+
+/// Trust anchors, the DS and DNSKEY records trusted as the start of a chain of trust, by the name
+///  of their zone
+///
+/// The DNSKEY trust anchors are updated by the automated rollovers of
+///  [RFC 5011](https://tools.ietf.org/html/rfc5011), see `update_dnskeys`.
+#[derive(Clone, Debug)]
+pub struct TrustAnchors {
+    ds: BTreeMap<Name, Vec<DS>>,
+    dnskeys: BTreeMap<Name, Vec<DNSKEY>>,
+    /// new keys waiting for the hold-down time, with the time they were first seen
+    pending: BTreeMap<Name, Vec<(DNSKEY, u64)>>,
+    /// revoked keys, with the revoke bit cleared, which are never trusted again
+    revoked: BTreeMap<Name, Vec<DNSKEY>>,
+    add_hold_down: u64,
+}
+
+impl Default for TrustAnchors {
+    fn default() -> Self {
+        TrustAnchors {
+            ds: BTreeMap::new(),
+            dnskeys: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            revoked: BTreeMap::new(),
+            add_hold_down: ADD_HOLD_DOWN,
+        }
+    }
+}
+
+impl TrustAnchors {
+    /// Creates a new empty set of trust anchors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the time in seconds a new key must be present before it is trusted, by default
+    ///  `ADD_HOLD_DOWN`
+    pub fn set_add_hold_down(&mut self, add_hold_down: u64) -> &mut Self {
+        self.add_hold_down = add_hold_down;
+        self
+    }
+
+    /// Returns true if there are no trust anchors
+    pub fn is_empty(&self) -> bool {
+        self.ds.is_empty() && self.dnskeys.is_empty()
+    }
+
+    /// Adds the DS as a trust anchor of the zone
+    pub fn insert_ds(&mut self, zone: Name, ds: DS) {
+        let anchors = self.ds.entry(zone).or_default();
+        if !anchors.contains(&ds) {
+            anchors.push(ds);
+        }
+    }
+
+    /// Adds a DS record in the presentation format as a trust anchor, returning the zone
+    ///
+    /// ```text
+    /// <owner> [<TTL>] [<class>] DS <key tag> <algorithm> <digest type> <digest>
+    /// ```
+    ///
+    /// The owner must be a fully qualified name, e.g. `.` for the root, the digest is in hex and may
+    ///  contain whitespace.
+    pub fn insert_ds_str(&mut self, line: &str) -> ProtoResult<Name> {
+        let mut parts = line.split_whitespace();
+        let zone = parts
+            .next()
+            .ok_or_else(|| ProtoError::from(ProtoErrorKind::Message("DS owner not specified")))?;
+        let zone = Name::parse(zone, None)?;
+        if !zone.is_fqdn() {
+            return Err(
+                ProtoErrorKind::Msg(format!("DS owner is not fully qualified: {}", zone)).into(),
+            );
+        }
+
+        // the optional TTL and class, in either order
+        loop {
+            match parts.next() {
+                Some(part) if part.eq_ignore_ascii_case("DS") => break,
+                Some(part) if part.parse::<u32>().is_ok() => (),
+                Some(part) => {
+                    DNSClass::from_str(&part.to_uppercase())?;
+                }
+                None => return Err(ProtoErrorKind::Message("DS type not specified").into()),
+            }
+        }
+
+        let key_tag = parse_ds_field(parts.next(), "key tag")?;
+        let algorithm = parse_ds_field(parts.next(), "algorithm")?;
+        let digest_type = parse_ds_field(parts.next(), "digest type")?;
+        let digest = hex::decode(&parts.collect::<String>())?;
+        if digest.is_empty() {
+            return Err(ProtoErrorKind::Message("DS digest not specified").into());
+        }
+
+        let ds = DS::new(
+            key_tag,
+            Algorithm::from_u8(algorithm),
+            DigestType::from_u8(digest_type)?,
+            digest,
+        );
+        self.insert_ds(zone.clone(), ds);
+        Ok(zone)
+    }
+
+    /// Adds the DNSKEY as a trust anchor of the zone
+    pub fn insert_dnskey(&mut self, zone: Name, dnskey: DNSKEY) {
+        let anchors = self.dnskeys.entry(zone).or_default();
+        if !anchors.contains(&dnskey) {
+            anchors.push(dnskey);
+        }
+    }
+
+    /// The DS trust anchors of the zone
+    pub fn ds(&self, zone: &Name) -> &[DS] {
+        self.ds.get(zone).map_or(&[], Vec::as_slice)
+    }
+
+    /// The DNSKEY trust anchors of the zone
+    pub fn dnskeys(&self, zone: &Name) -> &[DNSKEY] {
+        self.dnskeys.get(zone).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns true if the name is in a zone, or below a zone, with trust anchors
+    pub fn is_anchored(&self, name: &Name) -> bool {
+        self.ds
+            .keys()
+            .chain(self.dnskeys.keys())
+            .any(|zone| zone.zone_of(name))
+    }
+
+    /// Returns true if the DNSKEY of the zone is a trust anchor, or is covered by a DS trust anchor
+    ///
+    /// DS trust anchors are only checked with the openssl or ring features.
+    pub fn is_trusted(&self, zone: &Name, dnskey: &DNSKEY) -> bool {
+        self.dnskeys(zone).contains(dnskey)
+            || self
+                .ds(zone)
+                .iter()
+                .any(|ds| ds.covers(zone, dnskey).unwrap_or(false))
+    }
+
+    /// Updates the DNSKEY trust anchors of the zone from its apex DNSKEY rrset, following the
+    ///  [RFC 5011](https://tools.ietf.org/html/rfc5011#section-4) key states
+    ///
+    /// ```text
+    ///    AddPend:  The key has been seen by the resolver, has its SEP bit set,
+    ///       and has been included in a validated DNSKEY RRSet.  There is a
+    ///       hold-down time for the key before it can be used as a trust point.
+    ///
+    ///    Valid:  The key has been seen by the resolver and has been included in a
+    ///       validated DNSKEY RRSet for more than the hold-down time.
+    ///
+    ///    Revoked:  This is the state a key moves to once the resolver sees a
+    ///       REVOKE bit on the key in a validated DNSKEY RRSet.
+    /// ```
+    ///
+    /// The rrset is only used if one of the RRSIGs is by a trusted key of the zone, otherwise
+    ///  nothing is updated. New SEP keys are pending until they have been present for the add
+    ///  hold-down time, pending keys which are missing from the rrset are forgotten. Keys with the
+    ///  REVOKE bit which are trusted or pending, and which signed the rrset themselves, are
+    ///  removed and are not trusted again. Removing a revoked key which is covered by a DS trust
+    ///  anchor removes the DS.
+    ///
+    /// Signatures are only verified with the openssl or ring features.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - the zone of the DNSKEY rrset
+    /// * `dns_class` - the class of the DNSKEY rrset, generally IN
+    /// * `records` - the DNSKEY rrset
+    /// * `rrsigs` - the RRSIGs of the rrset, they must be within their validity period
+    /// * `now` - the current time in seconds since the Unix epoch
+    pub fn update_dnskeys(
+        &mut self,
+        zone: &Name,
+        dns_class: DNSClass,
+        records: &[Record],
+        rrsigs: &[SIG],
+        now: u64,
+    ) {
+        let dnskeys = records
+            .iter()
+            .filter_map(|record| match *record.rdata() {
+                RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => Some(dnskey),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let is_signed_by = |dnskey: &DNSKEY| {
+            rrsigs.iter().any(|sig| {
+                sig.type_covered() == RecordType::DNSSEC(DNSSECRecordType::DNSKEY)
+                    && sig.signer_name() == zone
+                    && sig.algorithm() == dnskey.algorithm()
+                    && dnskey.calculate_key_tag().ok() == Some(sig.key_tag())
+                    && dnskey.verify_rrsig(zone, dns_class, sig, records).is_ok()
+            })
+        };
+
+        if !dnskeys
+            .iter()
+            .any(|dnskey| !dnskey.revoke() && self.is_trusted(zone, dnskey) && is_signed_by(dnskey))
+        {
+            debug!("DNSKEY rrset is not signed by a trust anchor: {}", zone);
+            return;
+        }
+
+        for dnskey in dnskeys.iter().filter(|dnskey| dnskey.revoke()) {
+            let unrevoked = DNSKEY::new(
+                dnskey.zone_key(),
+                dnskey.secure_entry_point(),
+                false,
+                dnskey.algorithm(),
+                dnskey.public_key().to_vec(),
+            );
+
+            let pending = self
+                .pending
+                .get(zone)
+                .into_iter()
+                .flatten()
+                .any(|(key, _)| *key == unrevoked);
+            if !pending && !self.is_trusted(zone, &unrevoked) {
+                continue;
+            }
+
+            // RFC 5011 section 2.1, the revoked key must sign the rrset itself
+            if !is_signed_by(dnskey) {
+                debug!("revoked key is not self-signed {}: {:?}", zone, unrevoked);
+                continue;
+            }
+
+            debug!("revoked trust anchor {}: {:?}", zone, unrevoked);
+            if let Some(keys) = self.dnskeys.get_mut(zone) {
+                keys.retain(|key| *key != unrevoked);
+            }
+            if let Some(ds) = self.ds.get_mut(zone) {
+                ds.retain(|ds| !ds.covers(zone, &unrevoked).unwrap_or(false));
+            }
+            if let Some(keys) = self.pending.get_mut(zone) {
+                keys.retain(|(key, _)| *key != unrevoked);
+            }
+            self.revoked
+                .entry(zone.clone())
+                .or_default()
+                .push(unrevoked);
+        }
+
+        let valid = dnskeys
+            .into_iter()
+            .filter(|dnskey| dnskey.secure_entry_point() && !dnskey.revoke())
+            .filter(|dnskey| {
+                !self
+                    .revoked
+                    .get(zone)
+                    .into_iter()
+                    .flatten()
+                    .any(|key| key == *dnskey)
+            })
+            .filter(|dnskey| !self.is_trusted(zone, dnskey))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // pending keys which are no longer present are forgotten
+        let mut pending = self.pending.remove(zone).unwrap_or_default();
+        pending.retain(|(key, _)| valid.contains(key));
+
+        for dnskey in valid {
+            match pending.iter().position(|(key, _)| *key == dnskey) {
+                Some(i) if now.saturating_sub(pending[i].1) >= self.add_hold_down => {
+                    debug!("trusted after the hold-down {}: {:?}", zone, dnskey);
+                    pending.remove(i);
+                    self.insert_dnskey(zone.clone(), dnskey);
+                }
+                Some(_) => (),
+                None => pending.push((dnskey, now)),
+            }
+        }
+
+        if !pending.is_empty() {
+            self.pending.insert(zone.clone(), pending);
+        }
+    }
+}
+
+/// Parses a numeric field of a DS record in the presentation format
+fn parse_ds_field<T: FromStr>(part: Option<&str>, field: &str) -> ProtoResult<T> {
+    let part = part.ok_or_else(|| ProtoErrorKind::Msg(format!("DS {} not specified", field)))?;
+    part.parse()
+        .map_err(|_| ProtoErrorKind::Msg(format!("invalid DS {}: {}", field, part)).into())
 }
 
 #[test]
@@ -77,3 +381,248 @@ fn test_kjqmt7v() {
     assert_eq!(trust.get(0), ROOT_ANCHOR_ORIG);
     assert!(trust.contains_dnskey_bytes(ROOT_ANCHOR_ORIG));
 }
+
+#[test]
+fn test_trust_anchors_root_ds() {
+    let mut anchors = TrustAnchors::new();
+    assert!(anchors.is_empty());
+    assert!(!anchors.is_anchored(&Name::root()));
+
+    // the DS of the 2017 root KSK, as published by IANA
+    let zone = anchors
+        .insert_ds_str(
+            ". 172800 IN DS 20326 8 2 \
+             E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+        )
+        .expect("failed to parse DS");
+    assert_eq!(zone, Name::root());
+
+    let ds = anchors.ds(&Name::root());
+    assert_eq!(ds.len(), 1);
+    assert_eq!(ds[0].key_tag(), 20326);
+    assert_eq!(*ds[0].algorithm(), Algorithm::RSASHA256);
+    assert_eq!(ds[0].digest_type(), DigestType::SHA256);
+    assert!(anchors.ds(&Name::parse("com.", None).unwrap()).is_empty());
+    assert!(anchors.is_anchored(&Name::parse("www.example.com.", None).unwrap()));
+
+    // the public key of the root KSK is covered by the DS
+    let ksk = DNSKEY::new(
+        true,
+        true,
+        false,
+        Algorithm::RSASHA256,
+        ROOT_ANCHOR_2018.to_vec(),
+    );
+    assert_eq!(ksk.calculate_key_tag().unwrap(), 20326);
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    assert!(anchors.is_trusted(&Name::root(), &ksk));
+    assert!(!anchors.is_trusted(&Name::parse("com.", None).unwrap(), &ksk));
+
+    anchors.insert_dnskey(Name::parse("example.com.", None).unwrap(), ksk.clone());
+    assert!(anchors.is_trusted(&Name::parse("EXAMPLE.com.", None).unwrap(), &ksk));
+
+    assert!(anchors.insert_ds_str(". IN DS 20326 8 2").is_err());
+    assert!(anchors.insert_ds_str(". IN DNSKEY 257 3 8 AwEAAa").is_err());
+    assert!(anchors
+        .insert_ds_str("example.com IN DS 20326 8 2 E06D44B80B8F1D39")
+        .is_err());
+    assert!(anchors
+        .ds(&Name::parse("example.com.", None).unwrap())
+        .is_empty());
+}
+
+/// An Ed25519 key pair and its DNSKEY
+#[cfg(all(test, feature = "ring"))]
+fn ed25519_key(secure_entry_point: bool) -> (ring::signature::Ed25519KeyPair, DNSKEY) {
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let dnskey = DNSKEY::new(
+        true,
+        secure_entry_point,
+        false,
+        Algorithm::ED25519,
+        pair.public_key().as_ref().to_vec(),
+    );
+
+    (pair, dnskey)
+}
+
+/// The DNSKEY with the REVOKE bit set
+#[cfg(all(test, feature = "ring"))]
+fn revoked(dnskey: &DNSKEY) -> DNSKEY {
+    DNSKEY::new(
+        dnskey.zone_key(),
+        dnskey.secure_entry_point(),
+        true,
+        dnskey.algorithm(),
+        dnskey.public_key().to_vec(),
+    )
+}
+
+/// The DNSKEY rrset of the keys, and its RRSIGs by the signers
+#[cfg(all(test, feature = "ring"))]
+fn signed_dnskeys(
+    zone: &Name,
+    dnskeys: &[&DNSKEY],
+    signers: &[(&ring::signature::Ed25519KeyPair, &DNSKEY)],
+) -> (Vec<Record>, Vec<SIG>) {
+    use crate::rr::dnssec::tbs;
+
+    let records = dnskeys
+        .iter()
+        .map(|dnskey| {
+            Record::from_rdata(
+                zone.clone(),
+                3600,
+                RData::DNSSEC(DNSSECRData::DNSKEY((*dnskey).clone())),
+            )
+        })
+        .collect::<Vec<_>>();
+    let rrsigs = signers
+        .iter()
+        .map(|(pair, dnskey)| {
+            let unsigned = SIG::new(
+                RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+                Algorithm::ED25519,
+                zone.num_labels(),
+                3600,
+                1_893_456_000, // 2030-01-01
+                946_684_800,   // 2000-01-01
+                dnskey.calculate_key_tag().unwrap(),
+                zone.clone(),
+                vec![],
+            );
+            let tbs = tbs::rrset_tbs_with_sig(zone, DNSClass::IN, &unsigned, &records).unwrap();
+            unsigned.set_sig(pair.sign(tbs.as_ref()).as_ref().to_vec())
+        })
+        .collect();
+
+    (records, rrsigs)
+}
+
+#[test]
+#[cfg(feature = "ring")]
+fn test_trust_anchors_rollover() {
+    let zone = Name::parse("example.com.", None).unwrap();
+    let (old_pair, old_key) = ed25519_key(true);
+    let (new_pair, new_key) = ed25519_key(true);
+    let (_, zsk) = ed25519_key(false);
+
+    let mut anchors = TrustAnchors::new();
+    anchors.insert_dnskey(zone.clone(), old_key.clone());
+
+    // the clock, in seconds
+    let start = 1_000_000_000;
+
+    // an rrset which is not signed by a trusted key is not used
+    let (rrset, rrsigs) = signed_dnskeys(&zone, &[&old_key, &new_key], &[(&new_pair, &new_key)]);
+    anchors.update_dnskeys(&zone, DNSClass::IN, &rrset, &rrsigs, start);
+    anchors.update_dnskeys(&zone, DNSClass::IN, &rrset, &rrsigs, start + ADD_HOLD_DOWN);
+    assert!(!anchors.is_trusted(&zone, &new_key));
+
+    // add: the new key is pending
+    let (rrset, rrsigs) =
+        signed_dnskeys(&zone, &[&old_key, &new_key, &zsk], &[(&old_pair, &old_key)]);
+    anchors.update_dnskeys(&zone, DNSClass::IN, &rrset, &rrsigs, start);
+    assert!(anchors.is_trusted(&zone, &old_key));
+    assert!(!anchors.is_trusted(&zone, &new_key));
+
+    // hold-down: still pending until the hold-down time passed
+    anchors.update_dnskeys(
+        &zone,
+        DNSClass::IN,
+        &rrset,
+        &rrsigs,
+        start + ADD_HOLD_DOWN - 1,
+    );
+    assert!(!anchors.is_trusted(&zone, &new_key));
+
+    // trusted
+    anchors.update_dnskeys(&zone, DNSClass::IN, &rrset, &rrsigs, start + ADD_HOLD_DOWN);
+    assert!(anchors.is_trusted(&zone, &new_key));
+    assert!(
+        !anchors.is_trusted(&zone, &zsk),
+        "only SEP keys are trust anchors"
+    );
+
+    // a revoked key which does not sign the rrset itself stays trusted
+    let revoked_key = revoked(&old_key);
+    let (rrset, rrsigs) = signed_dnskeys(
+        &zone,
+        &[&revoked_key, &new_key, &zsk],
+        &[(&new_pair, &new_key)],
+    );
+    anchors.update_dnskeys(
+        &zone,
+        DNSClass::IN,
+        &rrset,
+        &rrsigs,
+        start + ADD_HOLD_DOWN + 1,
+    );
+    assert!(anchors.is_trusted(&zone, &old_key));
+
+    // the old key is revoked, and never trusted again
+    let (rrset, rrsigs) = signed_dnskeys(
+        &zone,
+        &[&revoked_key, &new_key, &zsk],
+        &[(&new_pair, &new_key), (&old_pair, &revoked_key)],
+    );
+    anchors.update_dnskeys(
+        &zone,
+        DNSClass::IN,
+        &rrset,
+        &rrsigs,
+        start + ADD_HOLD_DOWN + 1,
+    );
+    assert!(!anchors.is_trusted(&zone, &old_key));
+    assert!(anchors.is_trusted(&zone, &new_key));
+
+    let (rrset, rrsigs) = signed_dnskeys(
+        &zone,
+        &[&old_key, &new_key],
+        &[(&new_pair, &new_key), (&old_pair, &old_key)],
+    );
+    anchors.update_dnskeys(
+        &zone,
+        DNSClass::IN,
+        &rrset,
+        &rrsigs,
+        start + 3 * ADD_HOLD_DOWN,
+    );
+    anchors.update_dnskeys(
+        &zone,
+        DNSClass::IN,
+        &rrset,
+        &rrsigs,
+        start + 5 * ADD_HOLD_DOWN,
+    );
+    assert!(!anchors.is_trusted(&zone, &old_key));
+}
+
+#[test]
+#[cfg(feature = "ring")]
+fn test_trust_anchors_pending_removed() {
+    let zone = Name::parse("example.com.", None).unwrap();
+    let (old_pair, old_key) = ed25519_key(true);
+    let (_, new_key) = ed25519_key(true);
+    let signers = [(&old_pair, &old_key)];
+
+    let mut anchors = TrustAnchors::new();
+    anchors.set_add_hold_down(60);
+    anchors.insert_dnskey(zone.clone(), old_key.clone());
+
+    let (both, both_rrsigs) = signed_dnskeys(&zone, &[&old_key, &new_key], &signers);
+    let (old, old_rrsigs) = signed_dnskeys(&zone, &[&old_key], &signers);
+
+    anchors.update_dnskeys(&zone, DNSClass::IN, &both, &both_rrsigs, 0);
+
+    // the new key disappears before the hold-down passed, the hold-down starts again
+    anchors.update_dnskeys(&zone, DNSClass::IN, &old, &old_rrsigs, 30);
+    anchors.update_dnskeys(&zone, DNSClass::IN, &both, &both_rrsigs, 60);
+    assert!(!anchors.is_trusted(&zone, &new_key));
+    anchors.update_dnskeys(&zone, DNSClass::IN, &both, &both_rrsigs, 120);
+    assert!(anchors.is_trusted(&zone, &new_key));
+}
@@ -0,0 +1,599 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Signing and verification of messages with TSIG, RFC 8945
+
+use std::sync::Arc;
+
+use crate::error::*;
+use crate::op::{Header, Message, MessageFinalizer, MessageVerifier, Query, ResponseCode};
+use crate::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, TsigAlgorithm, TSIG};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+use crate::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, EncodeMode};
+
+/// The fudge recommended by RFC 8945, in seconds
+pub const DEFAULT_FUDGE: u16 = 300;
+
+/// The offset of the additional count in the header
+const ADDITIONAL_COUNT_OFFSET: usize = 10;
+
+/// Signs messages with a TSIG shared secret
+///
+/// As a `MessageFinalizer` this will append the TSIG record to all outgoing requests, and verify
+///  the TSIG records of their responses.
+#[derive(Clone)]
+pub struct TsigSigner {
+    key_name: Name,
+    algorithm: TsigAlgorithm,
+    key: Vec<u8>,
+    fudge: u16,
+}
+
+impl TsigSigner {
+    /// Constructs a new signer
+    ///
+    /// # Arguments
+    ///
+    /// * `key_name` - the name of the shared key, this is the owner name of the TSIG record
+    /// * `algorithm` - the MAC algorithm to use
+    /// * `key` - the shared secret
+    /// * `fudge` - allowed difference in seconds between the time signed and time of verification
+    pub fn new(key_name: Name, algorithm: TsigAlgorithm, key: Vec<u8>, fudge: u16) -> Self {
+        TsigSigner {
+            key_name,
+            algorithm,
+            key,
+            fudge,
+        }
+    }
+
+    /// The name of the shared key
+    pub fn key_name(&self) -> &Name {
+        &self.key_name
+    }
+
+    /// The MAC algorithm
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// Allowed difference in seconds between the time signed and time of verification
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// Creates the TSIG record for the message, which must be the last additional record
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message to sign, any SIG0 or TSIG records already present are not signed
+    /// * `request_mac` - when signing a response, the MAC of the TSIG of the request
+    /// * `time_signed` - the current time, in seconds since the unix epoch
+    pub fn sign_message(
+        &self,
+        message: &Message,
+        request_mac: Option<&[u8]>,
+        time_signed: u64,
+    ) -> ProtoResult<Record> {
+        let mut buf: Vec<u8> = Vec::with_capacity(512);
+        {
+            // signing mode leaves off the SIG0 and TSIG records, and adjusts the additional count
+            let mut encoder = BinEncoder::with_mode(&mut buf, EncodeMode::Signing);
+            message.emit(&mut encoder)?;
+        }
+
+        self.sign_bytes(&buf, request_mac, time_signed)
+    }
+
+    /// Creates the TSIG record for the encoded message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the encoded message, without the TSIG record
+    /// * `request_mac` - when signing a response, the MAC of the TSIG of the request
+    /// * `time_signed` - the current time, in seconds since the unix epoch
+    pub fn sign_bytes(
+        &self,
+        message: &[u8],
+        request_mac: Option<&[u8]>,
+        time_signed: u64,
+    ) -> ProtoResult<Record> {
+        let pre_tsig = TSIG::new(
+            self.algorithm.clone(),
+            time_signed,
+            self.fudge,
+            Vec::new(),
+            read_id(message)?,
+            0,
+            Vec::new(),
+        );
+
+        let tbs = message_tbs(message, &self.key_name, &pre_tsig, request_mac)?;
+        let mac = self.algorithm.mac(&self.key, &tbs)?;
+
+        let tsig = TSIG::new(
+            pre_tsig.algorithm().clone(),
+            pre_tsig.time(),
+            pre_tsig.fudge(),
+            mac,
+            pre_tsig.oid(),
+            pre_tsig.error(),
+            Vec::new(),
+        );
+
+        Ok(tsig_record(self.key_name.clone(), tsig))
+    }
+
+    /// Signs the message that was emitted to the encoder, by emitting its TSIG record after it
+    ///
+    /// This is for messages which are not available as a `Message`, e.g. the responses of the
+    ///  server. The additional count of the emitted header is incremented for the TSIG record.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder` - the encoder the message was emitted to, it must end at the current offset
+    /// * `start` - the offset of the start of the message
+    /// * `request_mac` - when signing a response, the MAC of the TSIG of the request
+    /// * `time_signed` - the current time, in seconds since the unix epoch
+    pub fn sign_emitted(
+        &self,
+        encoder: &mut BinEncoder,
+        start: usize,
+        request_mac: Option<&[u8]>,
+        time_signed: u64,
+    ) -> ProtoResult<()> {
+        let message = encoder.slice_of(start, encoder.offset()).to_vec();
+        let record = self.sign_bytes(&message, request_mac, time_signed)?;
+        record.emit(encoder)?;
+
+        let end = encoder.offset();
+        let additional_count = read_additional_count(&message)?
+            .checked_add(1)
+            .ok_or("additional count exceeded by TSIG")?;
+        encoder.set_offset(start + ADDITIONAL_COUNT_OFFSET);
+        encoder.emit_u16(additional_count)?;
+        encoder.set_offset(end);
+
+        Ok(())
+    }
+
+    /// The encoded length of the TSIG records of this signer
+    ///
+    /// This is to be reserved when the size of a message to be signed is limited.
+    pub fn record_len(&self) -> ProtoResult<usize> {
+        let record = self.sign_bytes(&[0; 12], None, 0)?;
+        Ok(record.to_bytes()?.len())
+    }
+
+    /// Returns a verifier for messages signed with the same key
+    pub fn to_verifier(&self) -> TsigVerifier {
+        TsigVerifier::new(
+            self.key_name.clone(),
+            self.algorithm.clone(),
+            self.key.clone(),
+        )
+    }
+}
+
+impl MessageFinalizer for TsigSigner {
+    fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>> {
+        debug!("signing message with tsig: {:?}", message);
+        Ok(vec![self.sign_message(
+            message,
+            None,
+            u64::from(current_time),
+        )?])
+    }
+
+    /// All messages are signed, not only updates
+    fn should_finalize_message(&self, _message: &Message) -> bool {
+        true
+    }
+
+    /// Responses must be signed with the same key, and cover the MAC of the request
+    fn response_verifier(&self, message: &Message) -> Option<MessageVerifier> {
+        let request_mac = match message.tsig().map(Record::rdata) {
+            Some(RData::DNSSEC(DNSSECRData::TSIG(ref tsig))) => tsig.mac().to_vec(),
+            _ => return None,
+        };
+
+        let verifier = self.to_verifier();
+        Some(Arc::new(move |response: &[u8], current_time: u32| {
+            verifier
+                .verify_bytes(response, Some(&request_mac), u64::from(current_time))
+                .map(|_| ())
+        }))
+    }
+}
+
+/// Verifies messages against a TSIG shared secret
+#[derive(Clone)]
+pub struct TsigVerifier {
+    key_name: Name,
+    algorithm: TsigAlgorithm,
+    key: Vec<u8>,
+}
+
+impl TsigVerifier {
+    /// Constructs a new verifier
+    ///
+    /// # Arguments
+    ///
+    /// * `key_name` - the name of the shared key, messages signed with other keys are rejected
+    /// * `algorithm` - the MAC algorithm of the key
+    /// * `key` - the shared secret
+    pub fn new(key_name: Name, algorithm: TsigAlgorithm, key: Vec<u8>) -> Self {
+        TsigVerifier {
+            key_name,
+            algorithm,
+            key,
+        }
+    }
+
+    /// The name of the shared key
+    pub fn key_name(&self) -> &Name {
+        &self.key_name
+    }
+
+    /// Verifies the TSIG record of the message
+    ///
+    /// The message is encoded for verification, see `verify_bytes`.
+    pub fn verify_message(
+        &self,
+        message: &Message,
+        request_mac: Option<&[u8]>,
+        current_time: u64,
+    ) -> ProtoResult<Vec<u8>> {
+        self.verify_bytes(&message.to_vec()?, request_mac, current_time)
+    }
+
+    /// Verifies the TSIG record of the encoded message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message as received, the TSIG record must be the last additional record
+    /// * `request_mac` - when verifying a response, the MAC of the TSIG of the request
+    /// * `current_time` - the current time, in seconds since the unix epoch
+    ///
+    /// # Return
+    ///
+    /// The verified MAC, to be used when signing or verifying the response. On failure a
+    ///  `TsigVerificationFailed` error with BADKEY, BADSIG or BADTIME, or a `FormErr` for a
+    ///  missing or malformed TSIG record.
+    pub fn verify_bytes(
+        &self,
+        message: &[u8],
+        request_mac: Option<&[u8]>,
+        current_time: u64,
+    ) -> ProtoResult<Vec<u8>> {
+        let (tsig_start, record) = read_tsig(message)?;
+        let tsig = match *record.rdata() {
+            RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => tsig,
+            _ => return Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::FormErr).into()),
+        };
+
+        if *record.name() != self.key_name || *tsig.algorithm() != self.algorithm {
+            return Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADKEY).into());
+        }
+
+        // the original message, with the original id and without the TSIG record
+        let mut original = message[..tsig_start].to_vec();
+        original[..2].copy_from_slice(&tsig.oid().to_be_bytes());
+        let additional_count = read_additional_count(&original)? - 1;
+        original[ADDITIONAL_COUNT_OFFSET..ADDITIONAL_COUNT_OFFSET + 2]
+            .copy_from_slice(&additional_count.to_be_bytes());
+
+        let tbs = message_tbs(&original, record.name(), tsig, request_mac)?;
+        let mac = self.algorithm.mac(&self.key, &tbs)?;
+
+        // truncated MACs must be at least half of the full MAC, and no less than 10 octets
+        let min_len = ::std::cmp::max(10, mac.len() / 2);
+        if tsig.mac().len() > mac.len() || tsig.mac().len() < min_len {
+            return Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::FormErr).into());
+        }
+
+        if !constant_time_eq(tsig.mac(), &mac[..tsig.mac().len()]) {
+            return Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADSIG).into());
+        }
+
+        // the time is only checked once the MAC is known to be authentic
+        let time_diff = if current_time > tsig.time() {
+            current_time - tsig.time()
+        } else {
+            tsig.time() - current_time
+        };
+        if time_diff > u64::from(tsig.fudge()) {
+            return Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADTIME).into());
+        }
+
+        Ok(tsig.mac().to_vec())
+    }
+}
+
+/// Returns the unsigned TSIG record for the error response to a request which failed verification
+///
+/// Per RFC 8945 section 5.2, the TSIG record carries the error, and the MAC is empty, as the key
+///  can not be trusted.
+///
+/// # Arguments
+///
+/// * `request` - the TSIG record of the request
+/// * `error` - the TSIG error, BADKEY, BADSIG or BADTIME
+/// * `current_time` - the current time, in seconds since the unix epoch
+pub fn error_record(request: &Record, error: ResponseCode, current_time: u64) -> Option<Record> {
+    let tsig = match *request.rdata() {
+        RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => tsig,
+        _ => return None,
+    };
+
+    let tsig = TSIG::new(
+        tsig.algorithm().clone(),
+        current_time,
+        tsig.fudge(),
+        Vec::new(),
+        tsig.oid(),
+        error.into(),
+        Vec::new(),
+    );
+    Some(tsig_record(request.name().clone(), tsig))
+}
+
+fn tsig_record(key_name: Name, tsig: TSIG) -> Record {
+    let mut record = Record::from_rdata(key_name, 0, RData::DNSSEC(DNSSECRData::TSIG(tsig)));
+    record.set_dns_class(DNSClass::ANY);
+    record
+}
+
+fn read_id(message: &[u8]) -> ProtoResult<u16> {
+    match message {
+        [high, low, ..] => Ok(u16::from_be_bytes([*high, *low])),
+        _ => Err("message is shorter than its header".into()),
+    }
+}
+
+fn read_additional_count(message: &[u8]) -> ProtoResult<u16> {
+    match message.get(ADDITIONAL_COUNT_OFFSET..ADDITIONAL_COUNT_OFFSET + 2) {
+        Some(count) => Ok(u16::from_be_bytes([count[0], count[1]])),
+        None => Err("message is shorter than its header".into()),
+    }
+}
+
+/// Returns the offset of the TSIG record in the message, and the record itself
+///
+/// The records of the message are decoded to find the last one, which must be the TSIG record.
+fn read_tsig(message: &[u8]) -> ProtoResult<(usize, Record)> {
+    let form_err = || {
+        ProtoError::from(ProtoErrorKind::TsigVerificationFailed(
+            ResponseCode::FormErr,
+        ))
+    };
+
+    let mut decoder = BinDecoder::new(message);
+    let header = Header::read(&mut decoder)?;
+    for _ in 0..header.query_count() {
+        Query::read(&mut decoder)?;
+    }
+
+    let record_count = usize::from(header.answer_count())
+        + usize::from(header.name_server_count())
+        + usize::from(header.additional_count());
+    if header.additional_count() == 0 {
+        return Err(form_err());
+    }
+    for _ in 1..record_count {
+        Record::read(&mut decoder)?;
+    }
+
+    let start = decoder.index();
+    let record = Record::read(&mut decoder)?;
+    if record.rr_type() != RecordType::DNSSEC(DNSSECRecordType::TSIG) || !decoder.is_empty() {
+        return Err(form_err());
+    }
+
+    Ok((start, record))
+}
+
+/// Returns the data to be signed, per RFC 8945 section 4.3.3
+///
+/// This is the request MAC (for responses), followed by the message without the TSIG record,
+///  followed by the TSIG variables. The MAC field itself is never included.
+fn message_tbs(
+    message: &[u8],
+    key_name: &Name,
+    tsig: &TSIG,
+    request_mac: Option<&[u8]>,
+) -> ProtoResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::with_capacity(message.len() + 128);
+    if let Some(request_mac) = request_mac {
+        let mut encoder = BinEncoder::new(&mut buf);
+        encoder.emit_u16(request_mac.len() as u16)?;
+        encoder.emit_vec(request_mac)?;
+    }
+
+    buf.extend_from_slice(message);
+
+    let mut variables_buf: Vec<u8> = Vec::with_capacity(64);
+    {
+        // TSIG variables, names are in canonical form
+        let mut encoder = BinEncoder::new(&mut variables_buf);
+        key_name
+            .to_lowercase()
+            .emit_as_canonical(&mut encoder, true)?;
+        DNSClass::ANY.emit(&mut encoder)?;
+        encoder.emit_u32(0)?; // TTL
+        tsig.algorithm()
+            .to_name()
+            .to_lowercase()
+            .emit_as_canonical(&mut encoder, true)?;
+        encoder.emit_u48(tsig.time())?;
+        encoder.emit_u16(tsig.fudge())?;
+        encoder.emit_u16(tsig.error())?;
+        encoder.emit_u16(tsig.other().len() as u16)?;
+        encoder.emit_vec(tsig.other())?;
+    }
+    buf.extend_from_slice(&variables_buf);
+
+    Ok(buf)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op::Query;
+    use crate::rr::RecordType;
+
+    fn signer() -> TsigSigner {
+        TsigSigner::new(
+            Name::from_ascii("tsig-key.example.com.").unwrap(),
+            TsigAlgorithm::HmacSha256,
+            b"not so secret shared key".to_vec(),
+            DEFAULT_FUDGE,
+        )
+    }
+
+    fn query() -> Message {
+        let mut message = Message::new();
+        message.set_id(0x1234).add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = signer();
+        let mut message = query();
+        message.finalize(&signer, 1_577_836_800).unwrap();
+        assert!(message.tsig().is_some());
+
+        let message = Message::from_vec(&message.to_vec().unwrap()).unwrap();
+        let mac = signer
+            .to_verifier()
+            .verify_message(&message, None, 1_577_836_900)
+            .expect("failed to verify");
+        assert_eq!(mac.len(), 32);
+    }
+
+    #[test]
+    fn test_verify_bad_time() {
+        let signer = signer();
+        let mut message = query();
+        message.finalize(&signer, 1_577_836_800).unwrap();
+
+        match *signer
+            .to_verifier()
+            .verify_message(&message, None, 1_577_836_800 + 301)
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADTIME) => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn test_verify_wrong_key() {
+        let mut message = query();
+        message.finalize(&signer(), 1_577_836_800).unwrap();
+
+        let verifier = TsigVerifier::new(
+            Name::from_ascii("tsig-key.example.com.").unwrap(),
+            TsigAlgorithm::HmacSha256,
+            b"some other key".to_vec(),
+        );
+        match *verifier
+            .verify_message(&message, None, 1_577_836_800)
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADSIG) => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn test_sign_response_with_request_mac() {
+        let signer = signer();
+        let verifier = signer.to_verifier();
+        let mut request = query();
+        request.finalize(&signer, 1_577_836_800).unwrap();
+        let request_mac = verifier
+            .verify_message(&request, None, 1_577_836_800)
+            .unwrap();
+
+        let mut response = query();
+        let tsig = signer
+            .sign_message(&response, Some(&request_mac), 1_577_836_801)
+            .unwrap();
+        response.add_tsig(tsig);
+
+        assert!(verifier
+            .verify_message(&response, Some(&request_mac), 1_577_836_801)
+            .is_ok());
+        assert!(verifier
+            .verify_message(&response, None, 1_577_836_801)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sign_emitted() {
+        let signer = signer();
+        let response = query();
+
+        let mut buf = Vec::new();
+        let unsigned_len;
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            response.emit(&mut encoder).unwrap();
+            unsigned_len = encoder.offset();
+            signer
+                .sign_emitted(&mut encoder, 0, Some(&[1, 2, 3]), 1_577_836_800)
+                .unwrap();
+        }
+        assert!(buf.len() - unsigned_len <= signer.record_len().unwrap());
+
+        let signed = Message::from_vec(&buf).unwrap();
+        assert!(signed.tsig().is_some());
+        assert!(signer
+            .to_verifier()
+            .verify_bytes(&buf, Some(&[1, 2, 3]), 1_577_836_800)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_response_verifier() {
+        let signer = signer();
+        let mut request = query();
+        request.finalize(&signer, 1_577_836_800).unwrap();
+        let request_mac = signer
+            .to_verifier()
+            .verify_message(&request, None, 1_577_836_800)
+            .unwrap();
+        let verify_response = signer.response_verifier(&request).expect("no verifier");
+
+        let mut response = query();
+        response.add_tsig(
+            signer
+                .sign_message(&response, Some(&request_mac), 1_577_836_801)
+                .unwrap(),
+        );
+        assert!(verify_response(&response.to_vec().unwrap(), 1_577_836_801).is_ok());
+
+        // not signed for this request
+        let mut response = query();
+        response.finalize(&signer, 1_577_836_801).unwrap();
+        assert!(verify_response(&response.to_vec().unwrap(), 1_577_836_801).is_err());
+
+        // not signed at all
+        assert!(verify_response(&query().to_vec().unwrap(), 1_577_836_801).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The result of DNSSec validation of a response
+
+use std::fmt;
+
+/// The security status of a validated response, see
+///  [RFC 4035](https://tools.ietf.org/html/rfc4035#section-4.3)
+///
+/// ```text
+/// 4.3.  Determining Security Status of Data
+///
+///    A security-aware resolver MUST be able to determine whether it should
+///    expect a particular RRset to be signed.  More precisely, a
+///    security-aware resolver must be able to distinguish between four
+///    cases:
+///
+///    Secure: An RRset for which the resolver is able to build a chain of
+///       signed DNSKEY and DS RRs from a trusted security anchor to the
+///       RRset.  In this case, the RRset should be signed and is subject to
+///       signature validation, as described above.
+///
+///    Insecure: An RRset for which the resolver knows that it has no chain
+///       of signed DNSKEY and DS RRs from any trusted starting point to the
+///       RRset.  This can occur when the target RRset lies in an unsigned
+///       zone or in a descendent of an unsigned zone.  In this case, the
+///       RRset may or may not be signed, but the resolver will not be able
+///       to verify the signature.
+///
+///    Bogus: An RRset for which the resolver believes that it ought to be
+///       able to establish a chain of trust but for which it is unable to
+///       do so, either due to signatures that for some reason fail to
+///       validate or due to missing data that the relevant DNSSEC RRs
+///       indicate should be present.  This case may indicate an attack but
+///       may also indicate a configuration error or some form of data
+///       corruption.
+///
+///    Indeterminate: An RRset for which the resolver is not able to
+///       determine whether the RRset should be signed, as the resolver is
+///       not able to obtain the necessary DNSSEC RRs.  This can occur when
+///       the security-aware resolver is not able to contact security-aware
+///       name servers for the relevant zones.
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Validity {
+    /// There is a chain of trust from a trust anchor to the records
+    Secure,
+    /// There is signed proof of an unsigned delegation between a trust anchor and the records
+    Insecure,
+    /// The records should be signed, but could not be validated
+    Bogus(BogusReason),
+    /// There is no trust anchor for the records
+    Indeterminate,
+}
+
+impl Validity {
+    /// Returns true if the records were validated to a trust anchor
+    pub fn is_secure(self) -> bool {
+        self == Validity::Secure
+    }
+
+    /// Returns true if the records failed validation
+    pub fn is_bogus(self) -> bool {
+        self.bogus_reason().is_some()
+    }
+
+    /// The reason the records failed validation, if they are bogus
+    pub fn bogus_reason(self) -> Option<BogusReason> {
+        if let Validity::Bogus(reason) = self {
+            Some(reason)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Validity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Validity::Secure => f.write_str("secure"),
+            Validity::Insecure => f.write_str("insecure"),
+            Validity::Bogus(reason) => write!(f, "bogus: {}", reason),
+            Validity::Indeterminate => f.write_str("indeterminate"),
+        }
+    }
+}
+
+/// The reason records are `Validity::Bogus`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum BogusReason {
+    /// The records are not signed
+    MissingSignature,
+    /// None of the signatures is within its validity period, i.e. they have expired or are not
+    ///  yet valid
+    SignatureExpired,
+    /// There is no DNSKEY of the signer with the key tag and algorithm of the signatures
+    MissingKey,
+    /// The signatures do not verify with the DNSKEYs of the signer
+    InvalidSignature,
+    /// The DNSKEYs are not trust anchors, and are not covered by a DS record of the parent zone
+    BrokenChain,
+    /// The nonexistence of the records is not proven by NSEC or NSEC3 records
+    InvalidDenial,
+    /// The records are only signed with algorithms which are not allowed by the `AlgorithmPolicy`
+    ///  of the validator
+    DisallowedAlgorithm,
+}
+
+impl fmt::Display for BogusReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match *self {
+            BogusReason::MissingSignature => "missing signature",
+            BogusReason::SignatureExpired => "signature expired",
+            BogusReason::MissingKey => "missing key",
+            BogusReason::InvalidSignature => "invalid signature",
+            BogusReason::BrokenChain => "broken chain of trust",
+            BogusReason::InvalidDenial => "invalid denial of existence",
+            BogusReason::DisallowedAlgorithm => "disallowed algorithm",
+        };
+
+        f.write_str(reason)
+    }
+}
@@ -1,10 +1,11 @@
 //! Verifier is a structure for performing many of the signing processes of the DNSSec specification
 
 use crate::error::*;
-use crate::rr::dnssec::rdata::{DNSKEY, KEY, SIG};
+use crate::op::Message;
+use crate::rr::dnssec::rdata::{DNSSECRData, DNSKEY, KEY, SIG};
 use crate::rr::dnssec::Algorithm;
 use crate::rr::dnssec::{tbs, PublicKey, PublicKeyEnum};
-use crate::rr::{DNSClass, Name, Record};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
 use crate::serialize::binary::BinEncodable;
 
 /// Types which are able to verify DNS based signatures
@@ -50,6 +51,42 @@ pub trait Verifier {
         tbs::message_tbs(message, sig0).and_then(|tbs| self.verify(tbs.as_ref(), signature))
     }
 
+    /// Verifies the SIG(0) record at the end of the message, [RFC 2931](https://tools.ietf.org/html/rfc2931#section-3)
+    ///
+    /// The message must have exactly one SIG(0), of the algorithm of this key, which is valid at
+    ///  `current_time`. The signature covers the SIG(0) RDATA and the message without the SIG(0),
+    ///  see `tbs::message_tbs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the signed message, e.g. a received request
+    /// * `current_time` - the epoch seconds to check the inception and expiration against
+    fn verify_sig0(&self, message: &Message, current_time: u32) -> ProtoResult<()> {
+        let sig0 = match message.sig0() {
+            [sig0] => sig0,
+            [] => return Err("message has no SIG(0)".into()),
+            _ => return Err("message has more than one SIG(0)".into()),
+        };
+
+        let sig = match *sig0.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => sig,
+            _ => return Err("SIG(0) record is not a SIG".into()),
+        };
+
+        // a type covered of 0 is what makes this a SIG(0) rather than a SIG over an RRset
+        if sig.type_covered() != RecordType::ZERO {
+            return Err("SIG does not cover a message".into());
+        }
+        if sig.algorithm() != self.algorithm() {
+            return Err("SIG(0) algorithm does not match the key".into());
+        }
+        if current_time < sig.sig_inception() || sig.sig_expiration() < current_time {
+            return Err("SIG(0) is not valid at the current time".into());
+        }
+
+        self.verify_message(message, sig.sig(), sig)
+    }
+
     /// Verifies an RRSig with the associated key, e.g. DNSKEY
     ///
     /// # Arguments
@@ -89,3 +126,200 @@ impl Verifier for KEY {
         PublicKeyEnum::from_public_bytes(self.public_key(), self.algorithm())
     }
 }
+
+#[cfg(any(feature = "openssl", feature = "ring"))]
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::rr::{RData, RecordType};
+
+    // the keys are RFC 3110 encoded 2048 bit RSA public keys, exponent 65537, the signatures are of
+    //  www.example.net. 3600 IN A 192.0.2.91, signed with an independent implementation
+
+    const RSASHA512_PUBLIC_KEY: &[u8] = &[
+        0x03, 0x01, 0x00, 0x01, 0xd4, 0xfb, 0x9d, 0x0e, 0x72, 0x92, 0xc3, 0xf1, 0xee, 0xa0, 0x3b,
+        0x29, 0x83, 0x8a, 0x75, 0xec, 0xe1, 0x04, 0xb2, 0x1c, 0x76, 0xb9, 0x64, 0x96, 0x50, 0x4a,
+        0x62, 0x3c, 0x31, 0x5f, 0x30, 0xe5, 0x73, 0x5b, 0x7f, 0x4b, 0xae, 0x30, 0x9f, 0x49, 0xb9,
+        0x85, 0xfe, 0x82, 0x36, 0x71, 0x6e, 0x6a, 0x2c, 0xc8, 0x78, 0xf8, 0xe6, 0x3d, 0xf3, 0xec,
+        0xbd, 0xa3, 0xc3, 0xc5, 0xb9, 0x45, 0x8f, 0x73, 0x12, 0x14, 0xf9, 0x21, 0xac, 0xa9, 0x70,
+        0x6e, 0xa9, 0xb5, 0xf1, 0xf3, 0x7e, 0x6a, 0x5c, 0x0f, 0x0c, 0x62, 0x19, 0x0c, 0xf2, 0xe0,
+        0xc2, 0x00, 0x10, 0x8e, 0x8a, 0x19, 0x97, 0x2e, 0x90, 0x35, 0x03, 0x52, 0xcb, 0x14, 0x4d,
+        0x3d, 0xa3, 0xc1, 0x86, 0xf3, 0x5a, 0x48, 0x09, 0x43, 0x3f, 0xb1, 0x87, 0x33, 0x3f, 0x69,
+        0xde, 0xaf, 0x44, 0xc4, 0x2c, 0x13, 0x25, 0x8e, 0x4e, 0x17, 0xc0, 0x44, 0x9f, 0x80, 0x98,
+        0xad, 0x78, 0x87, 0x47, 0xf3, 0xdb, 0xed, 0xbd, 0xe7, 0x32, 0xfb, 0x17, 0x1a, 0x58, 0xf2,
+        0x30, 0x4a, 0x71, 0x34, 0xe2, 0x9c, 0x6a, 0x53, 0xfd, 0xae, 0xb3, 0x5e, 0xd3, 0xba, 0x45,
+        0xf6, 0x6c, 0x5a, 0x05, 0xb4, 0x76, 0x2a, 0x62, 0xa8, 0x8a, 0x16, 0x05, 0xc9, 0xd7, 0x8c,
+        0xd2, 0xf2, 0x54, 0x43, 0xf6, 0xb9, 0x75, 0x53, 0x18, 0x83, 0x08, 0x48, 0x5e, 0x0b, 0xaf,
+        0x77, 0x78, 0xaf, 0xc0, 0x78, 0x5e, 0xd6, 0x4b, 0xd5, 0x33, 0x27, 0x74, 0xea, 0x83, 0x1a,
+        0x3d, 0x77, 0x23, 0x65, 0x2d, 0xaf, 0x56, 0xa0, 0xd4, 0x33, 0xf6, 0x5c, 0xce, 0x0b, 0x6f,
+        0xd8, 0xdf, 0x99, 0xac, 0xb0, 0x99, 0x9a, 0x2f, 0x3b, 0x74, 0x4a, 0x88, 0x77, 0x10, 0x7e,
+        0xfd, 0xfe, 0xef, 0xe3, 0x3c, 0x4a, 0x15, 0xa4, 0xbf, 0x47, 0xa6, 0xcc, 0xe2, 0x21, 0x61,
+        0x8c, 0xe2, 0xca, 0x44, 0x39,
+    ];
+
+    const RSASHA512_SIGNATURE: &[u8] = &[
+        0x44, 0x99, 0x14, 0xfc, 0xc2, 0xa7, 0x7c, 0x47, 0x71, 0x27, 0x99, 0xa4, 0x76, 0x43, 0x9e,
+        0x58, 0x85, 0x34, 0x02, 0x08, 0xaf, 0x9c, 0xcd, 0x67, 0x53, 0x20, 0xdc, 0x54, 0x29, 0x21,
+        0xa9, 0x8f, 0x41, 0x82, 0x62, 0x34, 0x51, 0x73, 0x96, 0x07, 0xa9, 0x65, 0x76, 0xc2, 0xa9,
+        0xc2, 0xfe, 0xd1, 0x0e, 0x45, 0xdb, 0x2f, 0xb2, 0x09, 0xb2, 0x3d, 0xea, 0xe8, 0x20, 0x2e,
+        0xcc, 0x3e, 0x5e, 0xac, 0x26, 0x67, 0xc2, 0xde, 0xe5, 0xe2, 0x61, 0xce, 0x8b, 0x18, 0xce,
+        0x9b, 0x28, 0xcd, 0xff, 0xfb, 0x31, 0x0e, 0xe2, 0x64, 0x88, 0x73, 0x34, 0x43, 0x4d, 0xbf,
+        0x67, 0x1b, 0xe6, 0x3d, 0xbb, 0x3e, 0xa9, 0x7d, 0x15, 0xdf, 0xbf, 0xc0, 0x3c, 0xa2, 0x3d,
+        0x38, 0xc1, 0x76, 0xfb, 0xa1, 0x26, 0xda, 0x8d, 0x4f, 0x79, 0xbd, 0x15, 0xa4, 0xdf, 0xfb,
+        0xf2, 0x58, 0xe5, 0x39, 0x08, 0xe7, 0x27, 0xb1, 0x69, 0xeb, 0xb9, 0x33, 0xe9, 0x10, 0xeb,
+        0xb2, 0xe6, 0x66, 0x8d, 0x87, 0x6c, 0x74, 0xf2, 0x3c, 0xaf, 0x79, 0x8a, 0x27, 0x95, 0x3b,
+        0xf3, 0xb7, 0x18, 0x3e, 0xe4, 0xbe, 0x77, 0x28, 0x57, 0x21, 0xbd, 0xb3, 0xd0, 0x24, 0xf2,
+        0xe4, 0x05, 0x6c, 0x21, 0x17, 0xf5, 0xcb, 0xa9, 0xee, 0x98, 0x89, 0x7b, 0x83, 0x4a, 0x98,
+        0x16, 0x89, 0xf4, 0x07, 0x8f, 0xf4, 0xf7, 0x7a, 0x33, 0x28, 0x49, 0xac, 0x85, 0xfd, 0x17,
+        0x73, 0x84, 0xdb, 0x81, 0xc6, 0xfb, 0xa4, 0x76, 0xdb, 0x40, 0x02, 0x2f, 0x27, 0x59, 0xfb,
+        0xfe, 0xc6, 0x56, 0x68, 0xfd, 0x72, 0x52, 0x2a, 0x24, 0xad, 0x60, 0xfb, 0x11, 0x7f, 0xe7,
+        0xd3, 0xc0, 0xd3, 0x7e, 0x7a, 0xd2, 0xe4, 0xa3, 0x73, 0xfa, 0xd8, 0x86, 0xd5, 0xf6, 0xbe,
+        0x30, 0xf6, 0x5f, 0x22, 0xa9, 0xf0, 0x54, 0x03, 0x5b, 0x28, 0xf7, 0x7f, 0xba, 0x84, 0xf4,
+        0x20,
+    ];
+
+    const RSASHA1NSEC3SHA1_PUBLIC_KEY: &[u8] = &[
+        0x03, 0x01, 0x00, 0x01, 0xbd, 0xa9, 0x49, 0x06, 0xdd, 0x3c, 0x94, 0x1d, 0xf7, 0xf7, 0x5b,
+        0x2d, 0x3d, 0xe1, 0x42, 0x59, 0xa5, 0xfb, 0xe8, 0x53, 0x2f, 0x10, 0xad, 0xc1, 0x73, 0xac,
+        0xff, 0x3b, 0x8f, 0xf8, 0xcc, 0x7e, 0x26, 0xfb, 0x44, 0x95, 0xc0, 0xea, 0x3c, 0x96, 0xd0,
+        0x17, 0x7e, 0xef, 0x13, 0x0a, 0x01, 0xf8, 0x61, 0xd0, 0x6e, 0xfe, 0x93, 0x5e, 0x9c, 0x38,
+        0xde, 0x76, 0xfd, 0xc9, 0xa1, 0x8d, 0x7f, 0xcd, 0x24, 0x04, 0xe9, 0x5f, 0xe2, 0x63, 0xc1,
+        0xe8, 0xbd, 0xf7, 0xac, 0x92, 0x4e, 0x42, 0x76, 0x13, 0xd8, 0xcb, 0x10, 0x1a, 0xfc, 0xc2,
+        0xba, 0xeb, 0x6e, 0x29, 0x5c, 0x44, 0xec, 0x72, 0x73, 0x8e, 0x8f, 0xa6, 0x68, 0x96, 0xa8,
+        0x73, 0x0f, 0x43, 0x8b, 0x01, 0x4c, 0x5e, 0x2a, 0x73, 0x5f, 0x7d, 0xca, 0x46, 0x5c, 0x6c,
+        0xa5, 0xff, 0xdb, 0xd0, 0xc5, 0x27, 0xbd, 0xce, 0x20, 0x37, 0xab, 0x57, 0xff, 0xaa, 0x01,
+        0x92, 0x5d, 0xcd, 0xda, 0xf5, 0x55, 0xe5, 0x7f, 0x74, 0xb1, 0x22, 0xd5, 0xa5, 0xa0, 0x3b,
+        0x4f, 0x13, 0x13, 0x41, 0x1c, 0xc5, 0xc1, 0xd9, 0x6a, 0x85, 0x3a, 0x22, 0x00, 0x2f, 0x19,
+        0x16, 0x69, 0x8e, 0x12, 0xac, 0xd0, 0x45, 0x63, 0x03, 0x07, 0x6d, 0x21, 0x8a, 0x01, 0x7a,
+        0x32, 0x6a, 0xe2, 0xa3, 0x26, 0x0e, 0xaf, 0x89, 0x0e, 0x0e, 0xd7, 0x78, 0x4f, 0x9b, 0x42,
+        0x6f, 0x34, 0x44, 0x47, 0x6d, 0x13, 0x67, 0x60, 0xb8, 0x6e, 0x5d, 0x84, 0x15, 0xc3, 0x7c,
+        0x70, 0x09, 0x42, 0x0f, 0x7d, 0xd1, 0xc4, 0xc5, 0x1b, 0x69, 0xa5, 0xa6, 0x39, 0x39, 0x34,
+        0xdc, 0x7d, 0x7f, 0x1b, 0xe9, 0xc0, 0x03, 0x9b, 0x3b, 0x39, 0x9b, 0x21, 0x0e, 0x5c, 0x62,
+        0x9b, 0x45, 0x01, 0x96, 0xff, 0xe6, 0xd6, 0x7d, 0xb2, 0x4c, 0x5b, 0xe7, 0x0f, 0x8c, 0x94,
+        0xe2, 0xe6, 0x75, 0x6a, 0x21,
+    ];
+
+    const RSASHA1NSEC3SHA1_SIGNATURE: &[u8] = &[
+        0x5c, 0xd1, 0xcd, 0x97, 0x52, 0x78, 0xc6, 0x6d, 0x05, 0xb4, 0x21, 0x55, 0x87, 0xf1, 0xd3,
+        0x26, 0x54, 0xf7, 0x70, 0xb8, 0x13, 0x3b, 0xb7, 0x7e, 0xd4, 0x9c, 0x88, 0x3f, 0xbc, 0x95,
+        0x9a, 0x52, 0xda, 0x0b, 0x6c, 0xac, 0x50, 0x85, 0xb8, 0xb1, 0x5a, 0xd5, 0x19, 0x8e, 0x36,
+        0x5c, 0xba, 0x04, 0x21, 0x14, 0xd6, 0xfe, 0x5d, 0xae, 0xdd, 0x41, 0xd0, 0xc1, 0xd8, 0x82,
+        0x0e, 0xdf, 0xc4, 0x48, 0x7a, 0x95, 0x4f, 0x9e, 0x73, 0x7a, 0x11, 0x2a, 0x50, 0xcf, 0xee,
+        0x91, 0xfc, 0xd9, 0x20, 0xbd, 0xf4, 0xd3, 0xab, 0xae, 0x68, 0x2f, 0xc9, 0x32, 0xbc, 0x38,
+        0xf8, 0x27, 0x95, 0x1b, 0xd7, 0xa8, 0x85, 0x80, 0xd4, 0x06, 0x11, 0xe0, 0x06, 0xd5, 0xd0,
+        0x7e, 0x9a, 0xeb, 0xc0, 0x47, 0x7d, 0xe4, 0xb3, 0x71, 0xe1, 0x4e, 0x0c, 0x16, 0xa1, 0xa9,
+        0x4e, 0x5e, 0x5d, 0xe9, 0x48, 0x47, 0x2d, 0x1e, 0xac, 0x15, 0x2f, 0xef, 0x2f, 0xf4, 0x56,
+        0x98, 0x03, 0x9e, 0x5f, 0x09, 0xe3, 0xda, 0x5a, 0x2f, 0x8d, 0x79, 0x97, 0x89, 0x56, 0xf0,
+        0xd5, 0xda, 0xaf, 0x13, 0xb4, 0x2e, 0xfa, 0x6d, 0x36, 0x62, 0xc5, 0xe5, 0x4c, 0x04, 0x6c,
+        0x46, 0x66, 0x79, 0x2e, 0xaa, 0x25, 0x00, 0x5c, 0x51, 0x31, 0x68, 0x34, 0x90, 0x5a, 0xf6,
+        0xe9, 0x1b, 0xe6, 0x4c, 0x68, 0x53, 0xa8, 0xeb, 0x6d, 0x39, 0xb7, 0x07, 0x3e, 0xa1, 0x7c,
+        0x05, 0x89, 0xc2, 0x50, 0x22, 0xd2, 0x9f, 0xab, 0xc4, 0x32, 0xcc, 0xff, 0x5c, 0x99, 0x9a,
+        0x84, 0xb1, 0x7f, 0xca, 0x95, 0xbd, 0x5f, 0xcb, 0xd5, 0x93, 0x23, 0x68, 0x87, 0x48, 0x39,
+        0x1e, 0x7f, 0x20, 0xe5, 0x53, 0x55, 0x2a, 0x0e, 0xf6, 0x1c, 0xc5, 0xfe, 0x35, 0xc8, 0xc5,
+        0xc8, 0x08, 0xce, 0x82, 0x0c, 0x66, 0xcc, 0x84, 0x59, 0x04, 0x32, 0x57, 0x3f, 0xe2, 0x3a,
+        0x7f,
+    ];
+
+    fn verify_rrsig(algorithm: Algorithm, key_tag: u16, public_key: &[u8], signature: &[u8]) {
+        let dnskey = DNSKEY::new(true, false, false, algorithm, public_key.to_vec());
+        assert_eq!(dnskey.calculate_key_tag().unwrap(), key_tag);
+
+        let name = Name::parse("www.example.net.", None).unwrap();
+        let sig = SIG::new(
+            RecordType::A,
+            algorithm,
+            3,
+            3600,
+            1_893_456_000, // 2030-01-01
+            946_684_800,   // 2000-01-01
+            key_tag,
+            Name::parse("example.net.", None).unwrap(),
+            signature.to_vec(),
+        );
+
+        let record = Record::from_rdata(name.clone(), 3600, RData::A(Ipv4Addr::new(192, 0, 2, 91)));
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, &sig, &[record])
+            .is_ok());
+
+        let record = Record::from_rdata(name.clone(), 3600, RData::A(Ipv4Addr::new(192, 0, 2, 92)));
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, &sig, &[record])
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_rrsig_rsasha512() {
+        verify_rrsig(
+            Algorithm::RSASHA512,
+            32202,
+            RSASHA512_PUBLIC_KEY,
+            RSASHA512_SIGNATURE,
+        );
+    }
+
+    #[test]
+    fn test_verify_rrsig_rsasha1nsec3sha1() {
+        verify_rrsig(
+            Algorithm::RSASHA1NSEC3SHA1,
+            26913,
+            RSASHA1NSEC3SHA1_PUBLIC_KEY,
+            RSASHA1NSEC3SHA1_SIGNATURE,
+        );
+    }
+
+    /// The P-256 public key of example.net. in RFC 6605 section 6.1
+    const ECDSAP256SHA256_PUBLIC_KEY: &[u8] = &[
+        0x1a, 0x88, 0xc8, 0x86, 0x15, 0xd4, 0x37, 0xfb, 0xb8, 0xbf, 0x9e, 0x19, 0x42, 0xa1, 0x92,
+        0x9f, 0x28, 0x56, 0x27, 0x06, 0xae, 0x6c, 0x2b, 0xd3, 0x99, 0xe7, 0xb1, 0xbf, 0xb6, 0xd1,
+        0xe9, 0xe7, 0x5b, 0x92, 0xb4, 0xaa, 0x42, 0x91, 0x7a, 0xe1, 0xc6, 0x1b, 0x70, 0x1e, 0xf0,
+        0x35, 0xc3, 0xfe, 0x7b, 0xe3, 0x00, 0x9c, 0xba, 0xfe, 0x5a, 0x2f, 0x71, 0x31, 0x6c, 0x90,
+        0x2d, 0xcf, 0x0d, 0x00,
+    ];
+
+    /// The signature of www.example.net. 3600 IN A 192.0.2.1 in RFC 6605 section 6.1
+    const ECDSAP256SHA256_SIGNATURE: &[u8] = &[
+        0xab, 0x1e, 0xb0, 0x2d, 0x8a, 0xa6, 0x87, 0xe9, 0x7d, 0xa0, 0x22, 0x93, 0x37, 0xaa, 0x88,
+        0x73, 0xe6, 0xf0, 0xeb, 0x26, 0xbe, 0x28, 0x9f, 0x28, 0x33, 0x3d, 0x18, 0x3f, 0x5d, 0x3b,
+        0x7a, 0x95, 0xc0, 0xc8, 0x69, 0xad, 0xfb, 0x74, 0x8d, 0xae, 0xe3, 0xc5, 0x28, 0x6e, 0xed,
+        0x66, 0x82, 0xc1, 0x2e, 0x55, 0x33, 0x18, 0x6b, 0xac, 0xed, 0x9c, 0x26, 0xc1, 0x67, 0xa9,
+        0xeb, 0xae, 0x95, 0x0b,
+    ];
+
+    #[test]
+    fn test_verify_rrsig_ecdsap256sha256() {
+        let dnskey = DNSKEY::new(
+            true,
+            true,
+            false,
+            Algorithm::ECDSAP256SHA256,
+            ECDSAP256SHA256_PUBLIC_KEY.to_vec(),
+        );
+        assert_eq!(dnskey.calculate_key_tag().unwrap(), 55648);
+
+        let name = Name::parse("www.example.net.", None).unwrap();
+        let sig = SIG::new(
+            RecordType::A,
+            Algorithm::ECDSAP256SHA256,
+            3,
+            3600,
+            1_284_026_679, // 20100909100439
+            1_281_607_479, // 20100812100439
+            55648,
+            Name::parse("example.net.", None).unwrap(),
+            ECDSAP256SHA256_SIGNATURE.to_vec(),
+        );
+
+        let record = Record::from_rdata(name.clone(), 3600, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, &sig, &[record])
+            .is_ok());
+
+        let record = Record::from_rdata(name.clone(), 3600, RData::A(Ipv4Addr::new(192, 0, 2, 2)));
+        assert!(dnskey
+            .verify_rrsig(&name, DNSClass::IN, &sig, &[record])
+            .is_err());
+    }
+}
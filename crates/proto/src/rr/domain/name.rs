@@ -10,6 +10,7 @@
 use std::borrow::Borrow;
 use std::char;
 use std::cmp::{Ordering, PartialEq};
+use std::collections::HashSet;
 use std::fmt::{self, Write};
 use std::hash::{Hash, Hasher};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -20,9 +21,9 @@ use std::str::FromStr;
 use crate::error::*;
 use crate::rr::domain::label::{CaseInsensitive, CaseSensitive, IntoLabel, Label, LabelCmp};
 use crate::rr::domain::usage::LOCALHOST as LOCALHOST_usage;
+use crate::serialize::binary::*;
 #[cfg(feature = "serde-config")]
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use crate::serialize::binary::*;
 
 /// Them should be through references. As a workaround the Strings are all Rc as well as the array
 #[derive(Clone, Default, Debug, Eq)]
@@ -120,9 +121,11 @@ impl Name {
 
     /// Creates a new Name from the specified labels
     ///
+    /// Each label must be between 1 and 63 octets, and the name at most 255 octets on the wire.
+    ///
     /// # Arguments
     ///
-    /// * `labels` - vector of items which will be stored as Strings.
+    /// * `labels` - vector of items which will be stored as Strings, or raw label bytes.
     ///
     /// # Examples
     ///
@@ -138,6 +141,10 @@ impl Name {
     /// let from_labels = Name::from_labels(vec!["bad chars".as_bytes(), "example".as_bytes(), "com".as_bytes()]).unwrap();
     /// assert_eq!(from_labels[0].as_bytes(), "bad chars".as_bytes());
     ///
+    /// // Raw label bytes are not converted at all
+    /// let from_labels = Name::from_labels(vec![vec![0xFF_u8], b"example".to_vec()]).unwrap();
+    /// assert_eq!(from_labels[0].as_bytes(), &[0xFF]);
+    ///
     /// let root = Name::from_labels(Vec::<&str>::new()).unwrap();
     /// assert!(root.is_root());
     /// ```
@@ -153,12 +160,20 @@ impl Name {
         let labels: Vec<_> = labels.into_iter().map(Result::unwrap).collect();
         let errors: Vec<_> = errors.into_iter().map(Result::unwrap_err).collect();
 
-        if labels.len() > 255 {
-            return Err("labels exceed maximum length of 255".into());
-        };
         if !errors.is_empty() {
             return Err(format!("error converting some labels: {:?}", errors).into());
         };
+        if labels.iter().any(|label| label.is_empty()) {
+            return Err("labels must not be empty".into());
+        };
+
+        // each label has a length octet, and the name ends with the root label
+        let length = labels
+            .iter()
+            .fold(1, |length, label| length + label.len() + 1);
+        if length > 255 {
+            return Err(ProtoErrorKind::DomainNameTooLong(length).into());
+        };
 
         Ok(Name {
             is_fqdn: true,
@@ -446,7 +461,7 @@ impl Name {
     /// assert_eq!(bytes_name, name);
     ///
     /// let bytes_name = Name::from_labels(vec!["bad.char".as_bytes(), "example".as_bytes(), "com".as_bytes()]).unwrap();
-    /// let name = Name::from_ascii("bad\\056char.example.com.").unwrap();
+    /// let name = Name::from_ascii("bad\\046char.example.com.").unwrap();
     ///
     /// assert_eq!(bytes_name, name);
     /// ```
@@ -504,6 +519,8 @@ impl Name {
     fn from_encoded_str<E: LabelEnc>(local: &str, origin: Option<&Self>) -> ProtoResult<Self> {
         let mut name = Name::new();
         let mut label = String::new();
+        // labels with escaped octets are taken as raw bytes, see RFC 1035 section 5.1
+        let mut escaped: Option<Vec<u8>> = None;
 
         let mut state = ParseState::Label;
 
@@ -519,56 +536,72 @@ impl Name {
             match state {
                 ParseState::Label => match ch {
                     '.' => {
-                        name.labels.push(E::to_label(&label)?);
+                        name.labels
+                            .push(Self::to_label::<E>(&label, escaped.take())?);
                         label.clear();
                     }
-                    '\\' => state = ParseState::Escape1,
-                    ch if !ch.is_control() && !ch.is_whitespace() => label.push(ch),
+                    '\\' => {
+                        if escaped.is_none() {
+                            escaped = Some(label.as_bytes().to_vec());
+                        }
+                        state = ParseState::Escape1;
+                    }
+                    ch if !ch.is_control() && !ch.is_whitespace() => {
+                        if let Some(ref mut bytes) = escaped {
+                            let mut buf = [0u8; 4];
+                            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        label.push(ch)
+                    }
                     _ => return Err(format!("unrecognized char: {}", ch).into()),
                 },
                 ParseState::Escape1 => {
-                    if ch.is_numeric() {
-                        state =
-                            ParseState::Escape2(ch.to_digit(8).ok_or_else(|| {
-                                ProtoError::from(format!("illegal char: {}", ch))
-                            })?);
+                    if let Some(d) = ch.to_digit(10) {
+                        state = ParseState::Escape2(d);
                     } else {
                         // it's a single escaped char
+                        if let Some(ref mut bytes) = escaped {
+                            let mut buf = [0u8; 4];
+                            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
                         label.push(ch);
                         state = ParseState::Label;
                     }
                 }
                 ParseState::Escape2(i) => {
-                    if ch.is_numeric() {
-                        state = ParseState::Escape3(
-                            i,
-                            ch.to_digit(8)
-                                .ok_or_else(|| ProtoError::from(format!("illegal char: {}", ch)))?,
-                        );
-                    } else {
-                        return Err(ProtoError::from(format!("unrecognized char: {}", ch)))?;
-                    }
+                    let ii = ch
+                        .to_digit(10)
+                        .ok_or_else(|| ProtoError::from(format!("unrecognized char: {}", ch)))?;
+                    state = ParseState::Escape3(i, ii);
                 }
                 ParseState::Escape3(i, ii) => {
-                    if ch.is_numeric() {
-                        // octal conversion
-                        let val: u32 = (i * 8 * 8)
-                            + (ii * 8)
-                            + ch.to_digit(8)
-                                .ok_or_else(|| ProtoError::from(format!("illegal char: {}", ch)))?;
-                        let new: char = char::from_u32(val)
-                            .ok_or_else(|| ProtoError::from(format!("illegal char: {}", ch)))?;
-                        label.push(new);
-                        state = ParseState::Label;
-                    } else {
-                        return Err(format!("unrecognized char: {}", ch))?;
+                    // decimal conversion, \DDD
+                    let val: u32 = (i * 100)
+                        + (ii * 10)
+                        + ch.to_digit(10).ok_or_else(|| {
+                            ProtoError::from(format!("unrecognized char: {}", ch))
+                        })?;
+                    if val > 0xFF {
+                        return Err(format!("escaped octet out of range: {}", val).into());
+                    }
+
+                    if let Some(ref mut bytes) = escaped {
+                        bytes.push(val as u8);
                     }
+                    label.push(char::from(val as u8));
+                    state = ParseState::Label;
                 }
             }
         }
 
+        match state {
+            ParseState::Label => (),
+            _ => return Err(format!("incomplete escape: {}", local).into()),
+        }
+
         if !label.is_empty() {
-            name.labels.push(E::to_label(&label)?);
+            name.labels
+                .push(Self::to_label::<E>(&label, escaped.take())?);
         }
 
         if local.ends_with('.') {
@@ -580,6 +613,14 @@ impl Name {
         Ok(name)
     }
 
+    /// Labels with escapes are taken as is, otherwise the label is encoded by `E`
+    fn to_label<E: LabelEnc>(label: &str, escaped: Option<Vec<u8>>) -> ProtoResult<Label> {
+        match escaped {
+            Some(bytes) => Label::from_raw_bytes(&bytes),
+            None => E::to_label(label),
+        }
+    }
+
     /// Emits the canonical version of the name to the encoder.
     ///
     /// In canonical form, there will be no pointers written to the encoder (i.e. no compression).
@@ -693,6 +734,15 @@ impl Name {
         self.cmp_with_f::<CaseSensitive>(other)
     }
 
+    /// Canonical DNSSec comparison, labels are compared from the root, ignoring case
+    ///
+    /// This is the order of NSEC chains and of the records of signed RRSets, see
+    ///  [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6.1). It is also the `Ord` of
+    ///  `Name`, this makes the dependence on the canonical order explicit.
+    pub fn cmp_canonical(&self, other: &Self) -> Ordering {
+        self.cmp_with_f::<CaseInsensitive>(other)
+    }
+
     /// Compares the Names, in a case sensitive manner
     pub fn eq_case(&self, other: &Self) -> bool {
         self.cmp_with_f::<CaseSensitive>(other) == Ordering::Equal
@@ -839,6 +889,10 @@ impl<'a> Iterator for LabelIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(Borrow::borrow)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
 impl<'a> ExactSizeIterator for LabelIter<'a> {}
@@ -954,6 +1008,153 @@ impl Hash for Name {
     }
 }
 
+/// Builds names sharing the labels of their parents with the names built before
+///
+/// Labels are reference counted, a parent interned once, e.g. `example.com.`, is shared by all of
+///  the names built below it, e.g. `a.example.com.` and `b.example.com.`. For many names below a
+///  few zones this saves an allocation per label of the parents, i.e. 16 bytes and the label for
+///  each.
+///
+/// The case of the names is preserved, `EXAMPLE.com.` and `example.com.` are interned separately.
+#[derive(Debug, Default)]
+pub struct NameInterner {
+    parents: HashSet<ExactName>,
+}
+
+impl NameInterner {
+    /// Creates an interner without any names
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the name with the labels of its parents shared with the names interned before
+    ///
+    /// The parents of the name are interned as well, the name itself only once it is the parent of
+    ///  another name.
+    pub fn intern(&mut self, name: &Name) -> Name {
+        let labels = &name.labels;
+        let with_labels = |labels: Vec<Label>| Name {
+            is_fqdn: name.is_fqdn,
+            labels,
+        };
+
+        // the name itself or the longest parent already interned, the root otherwise
+        let mut tail = with_labels(Vec::new());
+        let mut tail_start = labels.len();
+        for start in 0..labels.len() {
+            let parent = (name.is_fqdn, &labels[start..]);
+            if let Some(interned) = self.parents.get(&parent as &dyn ExactKey) {
+                tail = interned.0.clone();
+                tail_start = start;
+                break;
+            }
+        }
+
+        // the parents below it, from the shortest on
+        for start in (1..tail_start).rev() {
+            tail = with_labels(prepend(&labels[start], &tail));
+            self.parents.insert(ExactName(tail.clone()));
+        }
+
+        if tail_start == 0 {
+            tail
+        } else {
+            with_labels(prepend(&labels[0], &tail))
+        }
+    }
+
+    /// The number of interned parents
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Returns true if no parents are interned
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+fn prepend(label: &Label, name: &Name) -> Vec<Label> {
+    let mut labels = Vec::with_capacity(name.labels.len() + 1);
+    labels.push(label.clone());
+    labels.extend(name.labels.iter().cloned());
+    labels
+}
+
+/// A name compared and hashed case sensitively
+#[derive(Debug)]
+struct ExactName(Name);
+
+/// The labels of a name compared and hashed case sensitively, the parents of a name are looked
+///  up by their labels, without building a name for each of them
+trait ExactKey {
+    fn is_fqdn(&self) -> bool;
+    fn labels(&self) -> &[Label];
+}
+
+impl ExactKey for ExactName {
+    fn is_fqdn(&self) -> bool {
+        self.0.is_fqdn
+    }
+
+    fn labels(&self) -> &[Label] {
+        &self.0.labels
+    }
+}
+
+impl ExactKey for (bool, &[Label]) {
+    fn is_fqdn(&self) -> bool {
+        self.0
+    }
+
+    fn labels(&self) -> &[Label] {
+        self.1
+    }
+}
+
+impl<'k> Borrow<dyn ExactKey + 'k> for ExactName {
+    fn borrow(&self) -> &(dyn ExactKey + 'k) {
+        self
+    }
+}
+
+impl<'k> PartialEq for dyn ExactKey + 'k {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_fqdn() == other.is_fqdn()
+            && self.labels().len() == other.labels().len()
+            && self
+                .labels()
+                .iter()
+                .zip(other.labels())
+                .all(|(l, r)| l.as_bytes() == r.as_bytes())
+    }
+}
+
+impl<'k> Eq for dyn ExactKey + 'k {}
+
+impl<'k> Hash for dyn ExactKey + 'k {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.is_fqdn().hash(state);
+        for label in self.labels() {
+            label.as_bytes().hash(state);
+        }
+    }
+}
+
+impl PartialEq for ExactName {
+    fn eq(&self, other: &Self) -> bool {
+        Borrow::<dyn ExactKey>::borrow(self) == Borrow::<dyn ExactKey>::borrow(other)
+    }
+}
+
+impl Eq for ExactName {}
+
+impl Hash for ExactName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Borrow::<dyn ExactKey>::borrow(self).hash(state)
+    }
+}
+
 enum ParseState {
     Label,
     Escape1,
@@ -1009,6 +1210,9 @@ fn read_inner<'r>(decoder: &mut BinDecoder<'r>, max_idx: Option<usize>) -> Proto
 
         state = match state {
             LabelParseState::LabelLengthOrPointer => {
+                // later names may point here
+                decoder.store_label_start();
+
                 // determine what the next label is
                 match decoder
                     .peek()
@@ -1073,8 +1277,17 @@ fn read_inner<'r>(decoder: &mut BinDecoder<'r>, max_idx: Option<usize>) -> Proto
                         })
                     })?;
 
-                let mut pointer = decoder.clone(location);
-                let pointed = read_inner(&mut pointer, Some(name_start))?;
+                // a pointer into the middle of a label would parse its data as labels
+                if !decoder.is_label_start(location) {
+                    return Err(ProtoErrorKind::PointerNotToLabel {
+                        idx: pointer_location,
+                        ptr: location,
+                    }
+                    .into());
+                }
+
+                let pointed =
+                    decoder.read_at(location, |pointer| read_inner(pointer, Some(name_start)))?;
 
                 for l in &*pointed.labels {
                     if !l.is_empty() {
@@ -1162,7 +1375,7 @@ impl Ord for Name {
     ///            \200.z.example
     /// ```
     fn cmp(&self, other: &Self) -> Ordering {
-        self.cmp_with_f::<CaseInsensitive>(other)
+        self.cmp_canonical(other)
     }
 }
 
@@ -1350,6 +1563,60 @@ mod tests {
         assert!(Name::read(&mut d).is_err());
     }
 
+    #[test]
+    fn test_forward_pointer() {
+        // www.example.com. followed by a pointer to the name after it
+        let bytes = b"\x03www\x07example\x03com\x00\xC0\x1B\x00";
+        let mut d = BinDecoder::new(bytes);
+        Name::read(&mut d).unwrap();
+
+        match *Name::read(&mut d).unwrap_err().kind() {
+            ProtoErrorKind::PointerNotPriorToLabel { idx: 17, ptr: 27 } => (),
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_pointer_into_label() {
+        // www.example.com., then pointers to the labels, and into the label data of "example"
+        let bytes = b"\x03www\x07example\x03com\x00\xC0\x04\xC0\x0C\xC0\x07";
+        let mut d = BinDecoder::new(bytes);
+        Name::read(&mut d).unwrap();
+
+        assert_eq!(
+            Name::read(&mut d).unwrap(),
+            Name::from_ascii("example.com.").unwrap()
+        );
+        assert_eq!(
+            Name::read(&mut d).unwrap(),
+            Name::from_ascii("com.").unwrap()
+        );
+
+        // "ple" would otherwise be read as a label of length 'p'
+        match *Name::read(&mut d).unwrap_err().kind() {
+            ProtoErrorKind::PointerNotToLabel { idx: 21, ptr: 7 } => (),
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_pointer_to_pointer() {
+        // a pointer may point to an earlier pointer, which is the end of a name
+        let bytes = b"\x07example\x00\x03www\xC0\x00\xC0\x09\xC0\x0D";
+        let mut d = BinDecoder::new(bytes);
+        Name::read(&mut d).unwrap();
+        Name::read(&mut d).unwrap();
+
+        assert_eq!(
+            Name::read(&mut d).unwrap(),
+            Name::from_ascii("www.example.").unwrap()
+        );
+        assert_eq!(
+            Name::read(&mut d).unwrap(),
+            Name::from_ascii("example.").unwrap()
+        );
+    }
+
     #[test]
     fn test_bin_overlap_enforced() {
         let mut bytes = Vec::with_capacity(512);
@@ -1393,6 +1660,16 @@ mod tests {
         assert!(zone.base_name().base_name().base_name().is_root());
     }
 
+    #[test]
+    fn test_iter_len() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut iter = name.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(Name::root().iter().len(), 0);
+    }
+
     #[test]
     fn test_zone_of() {
         let zone = Name::from_str("example.com").unwrap();
@@ -1478,6 +1755,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cmp_canonical() {
+        let mut names = vec![
+            Name::from_ascii("a.example.com.").unwrap(),
+            Name::from_ascii("*.example.com.").unwrap(),
+            Name::from_ascii("example.com.").unwrap(),
+            Name::from_ascii("B.Example.com.").unwrap(),
+            Name::from_labels(vec![&[1u8] as &[u8], b"example", b"com"]).unwrap(),
+            Name::from_ascii("a.b.example.com.").unwrap(),
+        ];
+        names.sort_by(Name::cmp_canonical);
+
+        let expected = vec![
+            Name::from_ascii("example.com.").unwrap(),
+            Name::from_labels(vec![&[1u8] as &[u8], b"example", b"com"]).unwrap(),
+            Name::from_ascii("*.example.com.").unwrap(),
+            Name::from_ascii("a.example.com.").unwrap(),
+            Name::from_ascii("b.example.com.").unwrap(),
+            Name::from_ascii("a.b.example.com.").unwrap(),
+        ];
+        assert_eq!(names, expected);
+
+        assert_eq!(
+            Name::from_ascii("A.Example.com.")
+                .unwrap()
+                .cmp_canonical(&Name::from_ascii("a.example.COM.").unwrap()),
+            Ordering::Equal
+        );
+    }
+
     #[test]
     fn test_cmp_ignore_case() {
         let comparisons: Vec<(Name, Name)> = vec![
@@ -1497,6 +1804,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_to_lowercase() {
+        let name = Name::from_ascii("ExAmPle.COM").unwrap();
+        let lower = name.to_lowercase();
+
+        assert_eq!(lower.to_string(), "example.com");
+        assert!(!lower.is_fqdn());
+        assert!(lower.eq_case(&Name::from_ascii("example.com").unwrap()));
+        // the original is unchanged
+        assert_eq!(name.to_string(), "ExAmPle.COM");
+
+        let fqdn = Name::from_ascii("WWW.example.com.").unwrap().to_lowercase();
+        assert!(fqdn.is_fqdn());
+        assert_eq!(fqdn.to_string(), "www.example.com.");
+
+        // only ASCII letters are lowercased
+        let name = Name::from_labels(vec![&b"A\xC4"[..], &b"_Z9"[..]])
+            .unwrap()
+            .to_lowercase();
+        let labels: Vec<&[u8]> = name.iter().collect();
+        assert_eq!(labels, vec![&b"a\xC4"[..], &b"_z9"[..]]);
+    }
+
     #[test]
     fn test_from_ipv4() {
         let ip = IpAddr::V4(Ipv4Addr::new(26, 3, 0, 103));
@@ -1516,6 +1846,20 @@ mod tests {
         assert_eq!(Into::<Name>::into(ip), name);
     }
 
+    #[test]
+    fn test_reverse_names() {
+        let name = Name::from(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+        assert!(name.is_fqdn());
+        assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.");
+
+        let name = Name::from(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x1)));
+        assert!(name.is_fqdn());
+        assert_eq!(
+            name.to_string(),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+        );
+    }
+
     #[test]
     fn test_from_str() {
         assert_eq!(
@@ -1528,6 +1872,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_labels_bytes() {
+        let labels = vec![vec![0x2A, 0xFF], b"example".to_vec(), b"com".to_vec()];
+        let name = Name::from_labels(labels).unwrap();
+
+        assert_eq!(name.num_labels(), 3);
+        assert_eq!(name[0].as_bytes(), &[0x2A, 0xFF]);
+        assert!(name.is_fqdn());
+
+        // the longest possible name, 255 octets on the wire
+        let labels = vec![
+            vec![b'a'; 63],
+            vec![b'b'; 63],
+            vec![b'c'; 63],
+            vec![b'd'; 61],
+        ];
+        assert_eq!(Name::from_labels(labels).unwrap().len(), 254);
+    }
+
+    #[test]
+    fn test_from_labels_invalid() {
+        assert!(Name::from_labels(vec![vec![b'a'; 64]]).is_err());
+        assert!(Name::from_labels(vec![b"www".to_vec(), vec![], b"com".to_vec()]).is_err());
+
+        let labels: Vec<Vec<u8>> = (0..4).map(|_| vec![b'a'; 63]).collect();
+        match *Name::from_labels(labels).unwrap_err().kind() {
+            ProtoErrorKind::DomainNameTooLong(257) => (),
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
     #[test]
     fn test_fqdn() {
         assert!(Name::root().is_fqdn());
@@ -1642,4 +2017,88 @@ mod tests {
         Name::from_str_relaxed("mid_dle.example.com").expect("failed in the middle");
         Name::from_str_relaxed("end_.example.com").expect("failed at the end");
     }
+
+    #[test]
+    fn test_escaped_round_trip() {
+        let name = Name::from_ascii("a\\.b.example.com.").unwrap();
+        assert_eq!(name.num_labels(), 3);
+        assert_eq!(name[0].as_bytes(), b"a.b");
+        assert_eq!(name.to_string(), "a\\.b.example.com.");
+        assert_eq!(Name::from_str(&name.to_string()).unwrap(), name);
+
+        let name = Name::from_ascii("bell\\007.example.com.").unwrap();
+        assert_eq!(name[0].as_bytes(), b"bell\x07");
+        assert_eq!(name.to_string(), "bell\\007.example.com.");
+        assert_eq!(name.to_ascii(), "bell\\007.example.com.");
+        assert_eq!(Name::from_str(&name.to_string()).unwrap(), name);
+
+        assert!(Name::from_ascii("bad\\256.example.com.").is_err());
+        assert!(Name::from_ascii("bad\\07").is_err());
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut interner = NameInterner::new();
+        let a = interner.intern(&Name::from_ascii("a.example.com.").unwrap());
+        let b = interner.intern(&Name::from_ascii("b.example.com.").unwrap());
+        assert_eq!(a, Name::from_ascii("a.example.com.").unwrap());
+        assert_eq!(b, Name::from_ascii("b.example.com.").unwrap());
+        assert!(a.is_fqdn());
+
+        // the example.com. tail is the same
+        assert_eq!(interner.len(), 2);
+        assert_eq!(a[1].as_bytes().as_ptr(), b[1].as_bytes().as_ptr());
+        assert_eq!(a[2].as_bytes().as_ptr(), b[2].as_bytes().as_ptr());
+        assert_ne!(a[0].as_bytes().as_ptr(), b[0].as_bytes().as_ptr());
+
+        // and shared with a name below another level
+        let c = interner.intern(&Name::from_ascii("c.b.example.com.").unwrap());
+        assert_eq!(c.to_string(), "c.b.example.com.");
+        assert_eq!(interner.len(), 3);
+        assert_eq!(c[2].as_bytes().as_ptr(), a[1].as_bytes().as_ptr());
+
+        // the case is preserved
+        let upper = interner.intern(&Name::from_ascii("d.EXAMPLE.com.").unwrap());
+        assert_eq!(upper.to_string(), "d.EXAMPLE.com.");
+        assert_ne!(upper[1].as_bytes().as_ptr(), a[1].as_bytes().as_ptr());
+        assert_eq!(upper[2].as_bytes().as_ptr(), a[2].as_bytes().as_ptr());
+
+        // names that aren't fully qualified aren't parents of the ones that are
+        let relative = interner.intern(&Name::from_ascii("e.example.com").unwrap());
+        assert!(!relative.is_fqdn());
+        assert_ne!(relative[1].as_bytes().as_ptr(), a[1].as_bytes().as_ptr());
+
+        assert!(interner.intern(&Name::root()).is_root());
+        // an interned parent is shared as a whole
+        let parent = interner.intern(&Name::from_ascii("example.com.").unwrap());
+        assert_eq!(parent[0].as_bytes().as_ptr(), a[1].as_bytes().as_ptr());
+        assert_eq!(parent[1].as_bytes().as_ptr(), a[2].as_bytes().as_ptr());
+    }
+
+    #[test]
+    fn test_interner_memory() {
+        use std::collections::HashSet;
+
+        // the bytes allocated for the labels, each with the two reference counts
+        fn label_bytes(names: &[Name]) -> usize {
+            let labels: HashSet<(*const u8, usize)> = names
+                .iter()
+                .flat_map(|name| name.iter())
+                .map(|label| (label.as_ptr(), label.len()))
+                .collect();
+            labels.iter().map(|&(_, len)| 16 + len).sum()
+        }
+
+        let names: Vec<Name> = (0..1000)
+            .map(|i| Name::from_ascii(format!("host{}.zone.example.com.", i)).unwrap())
+            .collect();
+        let mut interner = NameInterner::new();
+        let interned: Vec<Name> = names.iter().map(|name| interner.intern(name)).collect();
+        assert_eq!(names, interned);
+
+        // the 3 parent labels are allocated once instead of once per name
+        let plain = label_bytes(&names);
+        let shared = label_bytes(&interned);
+        assert_eq!(plain - shared, 999 * (16 * 3 + 4 + 7 + 3));
+    }
 }
@@ -46,7 +46,22 @@ use crate::error::*;
 use crate::serialize::binary::*;
 
 /// Read the RData from the given Decoder
-pub fn read(decoder: &mut BinDecoder) -> ProtoResult<Ipv4Addr> {
+///
+/// The `rdata_length` must be exactly 4 octets, anything else is an error. If the decoder is not
+///  strict, octets after the first 4 are skipped instead.
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<Ipv4Addr> {
+    rdata_length
+        .map(|u| u as usize)
+        .verify_unwrap(|rdata_length| {
+            *rdata_length == 4 || (!decoder.is_strict() && *rdata_length > 4)
+        })
+        .map_err(|rdata_length| {
+            ProtoError::from(ProtoErrorKind::IncorrectRDataLengthRead {
+                read: rdata_length,
+                len: 4,
+            })
+        })?;
+
     Ok(Ipv4Addr::new(
         decoder.pop()?.unverified(/*valid as any u8*/),
         decoder.pop()?.unverified(/*valid as any u8*/),
@@ -91,11 +106,40 @@ mod mytests {
 
     #[test]
     fn test_parse() {
-        test_read_data_set(get_data(), |ref mut d| read(d));
+        test_read_data_set(get_data(), |ref mut d| read(d, Restrict::new(4)));
     }
 
     #[test]
     fn test_write_to() {
         test_emit_data_set(get_data(), |ref mut e, d| emit(e, d));
     }
+
+    #[test]
+    fn test_read_wrong_length() {
+        let short = vec![192_u8, 0, 2];
+        let mut decoder = BinDecoder::new(&short);
+        assert!(read(&mut decoder, Restrict::new(3)).is_err());
+
+        let long = vec![192_u8, 0, 2, 1, 0];
+        let mut decoder = BinDecoder::new(&long);
+        assert!(read(&mut decoder, Restrict::new(5)).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let address = Ipv4Addr::from_str("192.0.2.1").unwrap();
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = BinEncoder::new(&mut bytes);
+            emit(&mut encoder, address).expect("failed to emit A");
+        }
+        assert_eq!(bytes, vec![192, 0, 2, 1]);
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_address =
+            read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read A");
+        assert_eq!(read_address, address);
+        assert!(decoder.is_empty());
+    }
 }
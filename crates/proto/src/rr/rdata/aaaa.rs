@@ -34,12 +34,27 @@
 
 use std::net::Ipv6Addr;
 
-use crate::serialize::binary::*;
 use crate::error::*;
+use crate::serialize::binary::*;
 
 /// Read the RData from the given Decoder
+///
+/// The `rdata_length` must be exactly 16 octets, anything else is an error. If the decoder is not
+///  strict, octets after the first 16 are skipped instead.
 #[allow(clippy::many_single_char_names)]
-pub fn read(decoder: &mut BinDecoder) -> ProtoResult<Ipv6Addr> {
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<Ipv6Addr> {
+    rdata_length
+        .map(|u| u as usize)
+        .verify_unwrap(|rdata_length| {
+            *rdata_length == 16 || (!decoder.is_strict() && *rdata_length > 16)
+        })
+        .map_err(|rdata_length| {
+            ProtoError::from(ProtoErrorKind::IncorrectRDataLengthRead {
+                read: rdata_length,
+                len: 16,
+            })
+        })?;
+
     let a: u16 = decoder.read_u16()?.unverified(/*valid as any u16*/);
     let b: u16 = decoder.read_u16()?.unverified(/*valid as any u16*/);
     let c: u16 = decoder.read_u16()?.unverified(/*valid as any u16*/);
@@ -126,11 +141,40 @@ mod tests {
 
     #[test]
     fn test_read() {
-        test_read_data_set(get_data(), |ref mut d| read(d));
+        test_read_data_set(get_data(), |ref mut d| read(d, Restrict::new(16)));
     }
 
     #[test]
     fn test_emit() {
         test_emit_data_set(get_data(), |e, d| emit(e, &d));
     }
+
+    #[test]
+    fn test_read_wrong_length() {
+        let short = vec![0_u8; 15];
+        let mut decoder = BinDecoder::new(&short);
+        assert!(read(&mut decoder, Restrict::new(15)).is_err());
+
+        let long = vec![0_u8; 17];
+        let mut decoder = BinDecoder::new(&long);
+        assert!(read(&mut decoder, Restrict::new(17)).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_localhost() {
+        let localhost = Ipv6Addr::from_str("::1").unwrap();
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = BinEncoder::new(&mut bytes);
+            emit(&mut encoder, &localhost).expect("failed to emit AAAA");
+        }
+        assert_eq!(bytes.len(), 16);
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_localhost =
+            read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read AAAA");
+        assert_eq!(read_localhost, localhost);
+        assert!(decoder.is_empty());
+    }
 }
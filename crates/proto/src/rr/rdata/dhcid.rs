@@ -0,0 +1,130 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DHCID records for DHCP client identifiers
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.1)
+///
+/// ```text
+/// 3.1.  DHCID RDATA Format
+///
+///    The RDATA section of a DHCID RR in transmission contains RDLENGTH
+///    octets of binary data.  The format of this data and its
+///    interpretation by DHCP servers and clients are described below.
+///    DNS software should consider the RDATA section to be opaque.  DHCP
+///    clients or servers use the DHCID RR to associate a DHCP client's
+///    identity with a DNS name, so that multiple DHCP clients and servers
+///    may deterministically perform dynamic DNS updates to the same zone.
+///
+/// 3.3.  The DHCID RR Identifier Type Codes
+///
+///    The DHCID RR Identifier Type Code specifies what data from the DHCP
+///    option was used as input into the SHA-256 hash function.  The
+///    identifier type code is a 2-octet unsigned integer in network byte
+///    order.
+///
+/// 3.4.  The DHCID RR Digest Type Code
+///
+///    The DHCID RR Digest Type Code is an identifier for the digest
+///    algorithm used.  The digest is calculated over an identifier and the
+///    canonical FQDN as described in the next section.
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DHCID {
+    identifier_type: u16,
+    digest_type: u8,
+    digest: Vec<u8>,
+}
+
+impl DHCID {
+    /// Creates a new DHCID record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `identifier_type` - which DHCP option was used as input to the digest.
+    /// * `digest_type` - the digest algorithm, 1 for SHA-256.
+    /// * `digest` - the digest of the identifier and FQDN. This will NOT be checked.
+    pub fn new(identifier_type: u16, digest_type: u8, digest: Vec<u8>) -> Self {
+        DHCID {
+            identifier_type,
+            digest_type,
+            digest,
+        }
+    }
+
+    /// The identifier type code, e.g. `0x0000` for the htype and chaddr from a DHCPv4 message
+    pub fn identifier_type(&self) -> u16 {
+        self.identifier_type
+    }
+
+    /// The digest type code, `1` is SHA-256
+    pub fn digest_type(&self) -> u8 {
+        self.digest_type
+    }
+
+    /// The digest of the client identifier and the FQDN
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+/// Read the RData from the given decoder.
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<DHCID> {
+    let identifier_type = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let digest_type = decoder.read_u8()?.unverified(/*any u8 is valid*/);
+    let digest_len = rdata_length
+        .map(|l| l as usize)
+        .checked_sub(3)
+        .map_err(|_| ProtoError::from("invalid rdata length in DHCID"))?
+        .unverified(/*used only as a length safely*/);
+    let digest = decoder.read_vec(digest_len)?.unverified(/*the digest is opaque*/);
+    Ok(DHCID::new(identifier_type, digest_type, digest))
+}
+
+/// Write the RData using the given encoder.
+pub fn emit(encoder: &mut BinEncoder, dhcid: &DHCID) -> ProtoResult<()> {
+    encoder.emit_u16(dhcid.identifier_type())?;
+    encoder.emit_u8(dhcid.digest_type())?;
+    encoder.emit_vec(dhcid.digest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_encode_decode(rdata: DHCID, result: &[u8]) {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit DHCID");
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes, &result);
+
+        let mut decoder = BinDecoder::new(result);
+        let read_rdata =
+            read(&mut decoder, Restrict::new(result.len() as u16)).expect("failed to read DHCID");
+        assert_eq!(read_rdata, rdata)
+    }
+
+    #[test]
+    fn test_encode_decode_dhcid() {
+        test_encode_decode(DHCID::new(0, 1, vec![]), &[0, 0, 1]);
+        test_encode_decode(
+            DHCID::new(2, 1, vec![0xDE, 0xAD, 0xBE, 0xEF]),
+            &[0, 2, 1, 0xDE, 0xAD, 0xBE, 0xEF],
+        );
+    }
+
+    #[test]
+    fn test_read_too_short() {
+        let bytes = [0, 1];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16)).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HINFO record for storing host information
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 1035, DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION, November 1987](https://tools.ietf.org/html/rfc1035#section-3.3.2)
+///
+/// ```text
+/// 3.3.2. HINFO RDATA format
+///
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                      CPU                      /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///     /                       OS                      /
+///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+/// where:
+///
+/// CPU             A <character-string> which specifies the CPU type.
+///
+/// OS              A <character-string> which specifies the operating
+///                 system type.
+///
+/// Standard values for CPU and OS can be found in [RFC-1010].
+///
+/// HINFO records are used to acquire general information about a host.  The
+/// main use is for protocols such as FTP that can use special procedures
+/// when talking between machines or operating systems of the same type.
+/// ```
+///
+/// HINFO is also the synthesized answer to ANY queries of
+///  [RFC 8482](https://tools.ietf.org/html/rfc8482#section-4.2), see `HINFO::rfc8482()`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct HINFO {
+    cpu: Box<[u8]>,
+    os: Box<[u8]>,
+}
+
+impl HINFO {
+    /// Creates a new HINFO record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `cpu` - A <character-string> which specifies the CPU type.
+    /// * `os` - A <character-string> which specifies the operating system type.
+    ///
+    /// # Return value
+    ///
+    /// The new HINFO record data.
+    pub fn new(cpu: Box<[u8]>, os: Box<[u8]>) -> Self {
+        HINFO { cpu, os }
+    }
+
+    /// The synthesized response to ANY queries, [RFC 8482](https://tools.ietf.org/html/rfc8482#section-4.2)
+    ///
+    /// ```text
+    /// In this case, a single HINFO resource record SHOULD be returned.  The
+    /// CPU field of the HINFO RDATA SHOULD be set to "RFC8482".  The OS
+    /// field of the HINFO RDATA SHOULD be set to the null string to
+    /// minimize the size of the response.
+    /// ```
+    pub fn rfc8482() -> Self {
+        Self::new(
+            b"RFC8482".to_vec().into_boxed_slice(),
+            Vec::new().into_boxed_slice(),
+        )
+    }
+
+    /// A <character-string> which specifies the CPU type.
+    pub fn cpu(&self) -> &[u8] {
+        &self.cpu
+    }
+
+    /// A <character-string> which specifies the operating system type.
+    pub fn os(&self) -> &[u8] {
+        &self.os
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder) -> ProtoResult<HINFO> {
+    Ok(HINFO::new(
+        decoder.read_character_data()?.unverified(/*any chardata*/).to_vec().into_boxed_slice(),
+        decoder.read_character_data()?.unverified(/*any chardata*/).to_vec().into_boxed_slice(),
+    ))
+}
+
+/// Write the RData using the given encoder
+pub fn emit(encoder: &mut BinEncoder, hinfo: &HINFO) -> ProtoResult<()> {
+    encoder.emit_character_data(&hinfo.cpu)?;
+    encoder.emit_character_data(&hinfo.os)?;
+
+    Ok(())
+}
+
+#[test]
+fn test() {
+    let rdata = HINFO::new(
+        b"x86_64".to_vec().into_boxed_slice(),
+        b"Linux".to_vec().into_boxed_slice(),
+    );
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    assert!(emit(&mut encoder, &rdata).is_ok());
+    let bytes = encoder.into_bytes();
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    assert_eq!(read(&mut decoder).unwrap(), rdata);
+
+    // the RFC 8482 response has an empty OS
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    assert!(emit(&mut encoder, &HINFO::rfc8482()).is_ok());
+    assert_eq!(encoder.into_bytes(), b"\x07RFC8482\x00");
+}
@@ -22,6 +22,8 @@
 pub mod a;
 pub mod aaaa;
 pub mod caa;
+pub mod dhcid;
+pub mod hinfo;
 pub mod mx;
 pub mod name;
 pub mod naptr;
@@ -31,10 +33,16 @@ pub mod opt;
 pub mod soa;
 pub mod srv;
 pub mod sshfp;
+pub mod svcb;
+pub mod tkey;
 pub mod tlsa;
 pub mod txt;
+pub mod type_bit_map;
+pub mod uri;
 
 pub use self::caa::CAA;
+pub use self::dhcid::DHCID;
+pub use self::hinfo::HINFO;
 pub use self::mx::MX;
 pub use self::naptr::NAPTR;
 pub use self::null::NULL;
@@ -43,5 +51,8 @@ pub use self::opt::OPT;
 pub use self::soa::SOA;
 pub use self::srv::SRV;
 pub use self::sshfp::SSHFP;
+pub use self::svcb::SVCB;
+pub use self::tkey::TKEY;
 pub use self::tlsa::TLSA;
 pub use self::txt::TXT;
+pub use self::uri::URI;
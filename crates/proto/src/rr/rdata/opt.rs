@@ -18,11 +18,15 @@
 
 use std::collections::HashMap;
 
+use linked_hash_map::LinkedHashMap;
+
 use crate::error::*;
+use crate::op::Edns;
+use crate::rr::Record;
 use crate::serialize::binary::*;
 
 #[cfg(feature = "dnssec")]
-use crate::rr::dnssec::SupportedAlgorithms;
+use crate::rr::dnssec::{BogusReason, SupportedAlgorithms};
 
 /// The OPT record type is used for ExtendedDNS records.
 ///
@@ -160,9 +164,12 @@ use crate::rr::dnssec::SupportedAlgorithms;
 ///       Set to zero by senders and ignored by receivers, unless modified
 ///       in a subsequent specification.
 /// ```
-#[derive(Default, Debug, PartialEq, Eq, Clone)]
+#[derive(Default, Debug, Clone)]
 pub struct OPT {
-    options: HashMap<EdnsCode, EdnsOption>,
+    // the options in the order they were inserted or read, which is the order they are emitted in
+    options: LinkedHashMap<EdnsCode, EdnsOption>,
+    // a message may carry multiple extended errors, RFC 8914 section 2
+    extended_errors: Vec<EdnsOption>,
 }
 
 impl OPT {
@@ -175,36 +182,143 @@ impl OPT {
     /// # Return value
     ///
     /// The newly created OPT data
-    pub fn new(options: HashMap<EdnsCode, EdnsOption>) -> OPT {
-        OPT { options }
+    pub fn new(mut options: HashMap<EdnsCode, EdnsOption>) -> OPT {
+        let extended_errors = options
+            .remove(&EdnsCode::ExtendedError)
+            .into_iter()
+            .collect();
+        OPT {
+            options: options.into_iter().collect(),
+            extended_errors,
+        }
     }
 
-    /// The entire map of options
-    pub fn options(&self) -> &HashMap<EdnsCode, EdnsOption> {
+    /// The entire map of options, except for the extended errors, see `extended_errors()`
+    pub fn options(&self) -> &LinkedHashMap<EdnsCode, EdnsOption> {
         &self.options
     }
 
-    /// Get a single option based on the code
+    /// Get a single option based on the code, the first of the extended errors
     pub fn get(&self, code: EdnsCode) -> Option<&EdnsOption> {
-        self.options.get(&code)
+        match code {
+            EdnsCode::ExtendedError => self.extended_errors.first(),
+            _ => self.options.get(&code),
+        }
+    }
+
+    /// All of the `EdnsOption::ExtendedError`s, in the order they were inserted or read
+    pub fn extended_errors(&self) -> &[EdnsOption] {
+        &self.extended_errors
     }
 
     /// Insert a new option, the key is derived from the `EdnsOption`
+    ///
+    /// Options are emitted in the order they were first inserted, replacing an option keeps its place.
+    ///  Extended errors are added to the others, unless the same one was already inserted, and are
+    ///  emitted after all of the other options.
     pub fn insert(&mut self, option: EdnsOption) {
-        self.options.insert((&option).into(), option);
+        let code = EdnsCode::from(&option);
+        if code == EdnsCode::ExtendedError {
+            if !self.extended_errors.contains(&option) {
+                self.extended_errors.push(option);
+            }
+        } else if let Some(replaced) = self.options.get_mut(&code) {
+            *replaced = option;
+        } else {
+            self.options.insert(code, option);
+        }
+    }
+
+    /// The options in the order they are emitted in
+    pub fn iter(&self) -> impl Iterator<Item = (&EdnsCode, &EdnsOption)> {
+        self.options.iter().chain(
+            self.extended_errors
+                .iter()
+                .map(|option| (&EdnsCode::ExtendedError, option)),
+        )
+    }
+}
+
+impl PartialEq for OPT {
+    /// The order of the options doesn't change their meaning, it is ignored
+    fn eq(&self, other: &Self) -> bool {
+        self.options.len() == other.options.len()
+            && self
+                .options
+                .iter()
+                .all(|(code, option)| other.options.get(code) == Some(option))
+            && self.extended_errors == other.extended_errors
+    }
+}
+
+impl Eq for OPT {}
+
+/// A builder for OPT records with multiple options
+///
+/// The options are emitted in the order they are added, e.g.
+///
+/// ```
+/// use trust_dns_proto::rr::rdata::opt::{EdnsOption, OptBuilder};
+///
+/// let mut builder = OptBuilder::new();
+/// builder
+///     .udp_payload_size(1232)
+///     .dnssec_ok(true)
+///     .add_option(EdnsOption::Unknown(10, vec![0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE]))
+///     .add_option(EdnsOption::Padding(vec![0; 16]));
+/// let record = builder.build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OptBuilder {
+    edns: Edns,
+}
+
+impl OptBuilder {
+    /// Constructs a new builder, for a payload size of 512 without DNSSEC
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The maximum UDP payload size the sender can receive, values lower than 512 are treated as 512
+    pub fn udp_payload_size(&mut self, udp_payload_size: u16) -> &mut Self {
+        self.edns.set_max_payload(udp_payload_size);
+        self
+    }
+
+    /// Set to true if the sender accepts DNSSEC records
+    pub fn dnssec_ok(&mut self, dnssec_ok: bool) -> &mut Self {
+        self.edns.set_dnssec_ok(dnssec_ok);
+        self
+    }
+
+    /// Adds the option after the ones already added, an option with the same code is replaced in place
+    pub fn add_option(&mut self, option: EdnsOption) -> &mut Self {
+        self.edns.set_option(option);
+        self
+    }
+
+    /// Constructs the OPT record, with the payload size and flags in its class and TTL and the
+    ///  options as its `RData::OPT`
+    pub fn build(self) -> Record {
+        Record::from(&self.edns)
     }
 }
 
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<OPT> {
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
+
+    decoder.read_bounded(rdata_length, |decoder| read_options(decoder, rdata_length))
+}
+
+/// Reads the options of the OPT, the decoder is bounded to its rdata of `rdata_length` bytes
+fn read_options(decoder: &mut BinDecoder, rdata_length: usize) -> ProtoResult<OPT> {
     let mut state: OptReadState = OptReadState::ReadCode;
-    let mut options: HashMap<EdnsCode, EdnsOption> = HashMap::new();
-    let start_idx = decoder.index();
+    let mut options = OPT::default();
 
-    // There is no unsafe direct use of the rdata length after this point
-    let rdata_length =
-        rdata_length.map(|u| u as usize).unverified(/*rdata length usage is bounded*/);
-    while rdata_length > decoder.index() - start_idx {
+    while decoder.remaining() > 0 {
         match state {
             OptReadState::ReadCode => {
                 state = OptReadState::Code {
@@ -219,13 +333,19 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
                     .map(|u| u as usize)
                     .verify_unwrap(|u| *u <= rdata_length)
                     .map_err(|_| ProtoError::from("OPT value length exceeds rdata length"))?;
-                state = OptReadState::Data {
-                    code,
-                    length,
-                    // TODO: this can be replaced with decoder.read_vec(), right?
-                    //  the current version allows for malformed opt to be skipped...
-                    collected: Vec::<u8>::with_capacity(length),
-                };
+                if length == 0 {
+                    // there is no data to collect for an empty option
+                    options.insert((code, &[][..]).into());
+                    state = OptReadState::ReadCode;
+                } else {
+                    state = OptReadState::Data {
+                        code,
+                        length,
+                        // TODO: this can be replaced with decoder.read_vec(), right?
+                        //  the current version allows for malformed opt to be skipped...
+                        collected: Vec::<u8>::with_capacity(length.min(decoder.remaining())),
+                    };
+                }
             }
             OptReadState::Data {
                 code,
@@ -235,7 +355,7 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
                 // TODO: can this be replaced by read_slice()?
                 collected.push(decoder.pop()?.unverified(/*byte array is safe*/));
                 if length == collected.len() {
-                    options.insert(code, (code, &collected as &[u8]).into());
+                    options.insert((code, &collected[..]).into());
                     state = OptReadState::ReadCode;
                 } else {
                     state = OptReadState::Data {
@@ -252,16 +372,16 @@ pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResul
         // there was some problem parsing the data for the options, ignoring them
         // TODO: should we ignore all of the EDNS data in this case?
         warn!("incomplete or poorly formatted EDNS options: {:?}", state);
-        options.clear();
+        options = OPT::default();
     }
 
     // the record data is stored as unstructured data, the expectation is that this will be processed after initial parsing.
-    Ok(OPT::new(options))
+    Ok(options)
 }
 
 /// Write the RData from the given Decoder
 pub fn emit(encoder: &mut BinEncoder, opt: &OPT) -> ProtoResult<()> {
-    for (edns_code, edns_option) in opt.options().iter() {
+    for (edns_code, edns_option) in opt.iter() {
         encoder.emit_u16(u16::from(*edns_code))?;
         encoder.emit_u16(edns_option.len())?;
         edns_option.emit(encoder)?
@@ -306,7 +426,7 @@ pub enum EdnsCode {
     /// [RFC 6975, NSEC3 Hash Understood](https://tools.ietf.org/html/rfc6975)
     N3U,
 
-    /// [edns-client-subnet, Optional](https://tools.ietf.org/html/draft-vandergaast-edns-client-subnet-02)
+    /// [RFC 7871, Client Subnet, Optional](https://tools.ietf.org/html/rfc7871)
     Subnet,
 
     /// [RFC 7314, EDNS EXPIRE, Optional](https://tools.ietf.org/html/rfc7314)
@@ -324,6 +444,9 @@ pub enum EdnsCode {
     /// [draft-ietf-dnsop-edns-chain-query](https://tools.ietf.org/html/draft-ietf-dnsop-edns-chain-query-07)
     Chain,
 
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ExtendedError,
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16),
 }
@@ -346,6 +469,7 @@ impl From<u16> for EdnsCode {
             11 => EdnsCode::Keepalive,
             12 => EdnsCode::Padding,
             13 => EdnsCode::Chain,
+            15 => EdnsCode::ExtendedError,
             _ => EdnsCode::Unknown(value),
         }
     }
@@ -368,6 +492,7 @@ impl From<EdnsCode> for u16 {
             EdnsCode::Keepalive => 11,
             EdnsCode::Padding => 12,
             EdnsCode::Chain => 13,
+            EdnsCode::ExtendedError => 15,
             EdnsCode::Unknown(value) => value,
         }
     }
@@ -392,6 +517,37 @@ pub enum EdnsOption {
     #[cfg(feature = "dnssec")]
     N3U(SupportedAlgorithms),
 
+    /// [RFC 7830, The EDNS(0) Padding Option](https://tools.ietf.org/html/rfc7830)
+    ///
+    /// The padding should be all zeros.
+    Padding(Vec<u8>),
+
+    /// [RFC 7871, Client Subnet in DNS Queries](https://tools.ietf.org/html/rfc7871)
+    ///
+    /// The address is truncated to the source prefix length when encoded and decoded, the
+    ///  trailing bits of the last octet are zeroed.
+    ClientSubnet {
+        /// The address family, 1 for IPv4 and 2 for IPv6
+        family: u16,
+        /// The number of leading bits of the address that are significant
+        source_prefix: u8,
+        /// The number of leading bits of the address that the response covers, 0 in queries
+        scope_prefix: u8,
+        /// The octets of the address, in network byte order
+        address: Vec<u8>,
+    },
+
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ///
+    /// Additional information about the cause of an error, e.g. the reason a validating resolver
+    ///  responds with SERVFAIL, see `From<BogusReason>`.
+    ExtendedError {
+        /// The INFO-CODE from the IANA registry, e.g. 6 for DNSSEC Bogus
+        info_code: u16,
+        /// The EXTRA-TEXT for humans, may be empty
+        extra_text: String,
+    },
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -404,6 +560,13 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.len(),
+            EdnsOption::Padding(ref padding) => padding.len() as u16,
+            EdnsOption::ClientSubnet {
+                source_prefix,
+                ref address,
+                ..
+            } => 4 + subnet_octets(source_prefix, address) as u16,
+            EdnsOption::ExtendedError { ref extra_text, .. } => 2 + extra_text.len() as u16,
             EdnsOption::Unknown(_, ref data) => data.len() as u16, // TODO: should we verify?
         }
     }
@@ -415,6 +578,8 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.is_empty(),
+            EdnsOption::Padding(ref padding) => padding.is_empty(),
+            EdnsOption::ClientSubnet { .. } | EdnsOption::ExtendedError { .. } => false,
             EdnsOption::Unknown(_, ref data) => data.is_empty(),
         }
     }
@@ -427,6 +592,25 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.emit(encoder),
+            EdnsOption::Padding(ref padding) => encoder.emit_vec(padding),
+            EdnsOption::ClientSubnet {
+                family,
+                source_prefix,
+                scope_prefix,
+                ref address,
+            } => {
+                encoder.emit_u16(family)?;
+                encoder.emit(source_prefix)?;
+                encoder.emit(scope_prefix)?;
+                encoder.emit_vec(&subnet_address(source_prefix, address))
+            }
+            EdnsOption::ExtendedError {
+                info_code,
+                ref extra_text,
+            } => {
+                encoder.emit_u16(info_code)?;
+                encoder.emit_vec(extra_text.as_bytes())
+            }
             EdnsOption::Unknown(_, ref data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -442,11 +626,51 @@ impl<'a> From<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DHU => EdnsOption::DHU(value.1.into()),
             #[cfg(feature = "dnssec")]
             EdnsCode::N3U => EdnsOption::N3U(value.1.into()),
+            EdnsCode::Padding => EdnsOption::Padding(value.1.to_vec()),
+            // a malformed client subnet is kept as it is
+            EdnsCode::Subnet if value.1.len() >= 4 => {
+                let data = value.1;
+                let source_prefix = data[2];
+                EdnsOption::ClientSubnet {
+                    family: u16::from(data[0]) << 8 | u16::from(data[1]),
+                    source_prefix,
+                    scope_prefix: data[3],
+                    address: subnet_address(source_prefix, &data[4..]),
+                }
+            }
+            // as is a malformed extended error
+            EdnsCode::ExtendedError if value.1.len() >= 2 => {
+                let data = value.1;
+                EdnsOption::ExtendedError {
+                    info_code: u16::from(data[0]) << 8 | u16::from(data[1]),
+                    extra_text: String::from_utf8_lossy(&data[2..]).into_owned(),
+                }
+            }
             _ => EdnsOption::Unknown(value.0.into(), value.1.to_vec()),
         }
     }
 }
 
+/// The number of octets of the address that are covered by the prefix
+fn subnet_octets(source_prefix: u8, address: &[u8]) -> usize {
+    let prefix_octets = (usize::from(source_prefix) + 7) >> 3;
+    prefix_octets.min(address.len())
+}
+
+/// Truncates the address to the prefix, and zeroes the trailing bits of the last octet
+fn subnet_address(source_prefix: u8, address: &[u8]) -> Vec<u8> {
+    let octets = subnet_octets(source_prefix, address);
+    let mut address = address[..octets].to_vec();
+
+    // only the last octet of the prefix has trailing bits, not the last of a shorter address
+    let trailing_bits = octets * 8 - (source_prefix as usize).min(octets * 8);
+    if let Some(last) = address.last_mut() {
+        *last &= 0xFF << trailing_bits;
+    }
+
+    address
+}
+
 impl<'a> From<&'a EdnsOption> for Vec<u8> {
     fn from(value: &'a EdnsOption) -> Vec<u8> {
         match *value {
@@ -454,6 +678,30 @@ impl<'a> From<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.into(),
+            EdnsOption::Padding(ref padding) => padding.clone(),
+            EdnsOption::ClientSubnet {
+                family,
+                source_prefix,
+                scope_prefix,
+                ref address,
+            } => {
+                let mut data = vec![
+                    (family >> 8) as u8,
+                    family as u8,
+                    source_prefix,
+                    scope_prefix,
+                ];
+                data.extend_from_slice(&subnet_address(source_prefix, address));
+                data
+            }
+            EdnsOption::ExtendedError {
+                info_code,
+                ref extra_text,
+            } => {
+                let mut data = vec![(info_code >> 8) as u8, info_code as u8];
+                data.extend_from_slice(extra_text.as_bytes());
+                data
+            }
             EdnsOption::Unknown(_, ref data) => data.clone(), // gah, clone needed or make a crazy api.
         }
     }
@@ -468,11 +716,35 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DHU(..) => EdnsCode::DHU,
             #[cfg(feature = "dnssec")]
             EdnsOption::N3U(..) => EdnsCode::N3U,
+            EdnsOption::Padding(..) => EdnsCode::Padding,
+            EdnsOption::ClientSubnet { .. } => EdnsCode::Subnet,
+            EdnsOption::ExtendedError { .. } => EdnsCode::ExtendedError,
             EdnsOption::Unknown(code, _) => code.into(),
         }
     }
 }
 
+/// The extended error for responses which failed validation, the text is the `BogusReason`
+#[cfg(feature = "dnssec")]
+impl From<BogusReason> for EdnsOption {
+    fn from(reason: BogusReason) -> EdnsOption {
+        // the INFO-CODEs of RFC 8914, section 4
+        let info_code = match reason {
+            BogusReason::DisallowedAlgorithm => 1,
+            BogusReason::InvalidSignature | BogusReason::BrokenChain => 6,
+            BogusReason::SignatureExpired => 7,
+            BogusReason::MissingKey => 9,
+            BogusReason::MissingSignature => 10,
+            BogusReason::InvalidDenial => 12,
+        };
+
+        EdnsOption::ExtendedError {
+            info_code,
+            extra_text: reason.to_string(),
+        }
+    }
+}
+
 #[test]
 #[cfg(feature = "dnssec")]
 pub fn test() {
@@ -494,3 +766,221 @@ pub fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_read_padding() {
+    // a zero length option, followed by padding, followed by another option
+    let bytes = [0, 3, 0, 0, 0, 12, 0, 4, 0, 0, 0, 0, 0, 10, 0, 2, 0xCA, 0xFE];
+
+    let mut decoder: BinDecoder = BinDecoder::new(&bytes);
+    let read_rdata =
+        read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read OPT");
+
+    assert_eq!(read_rdata.options().len(), 3);
+    assert_eq!(
+        read_rdata.get(EdnsCode::NSID),
+        Some(&EdnsOption::Unknown(3, vec![]))
+    );
+    assert_eq!(
+        read_rdata.get(EdnsCode::Padding),
+        Some(&EdnsOption::Padding(vec![0; 4]))
+    );
+    assert_eq!(
+        read_rdata.get(EdnsCode::Cookie),
+        Some(&EdnsOption::Unknown(10, vec![0xCA, 0xFE]))
+    );
+}
+
+#[test]
+fn test_client_subnet() {
+    let mut rdata = OPT::default();
+    rdata.insert(EdnsOption::ClientSubnet {
+        family: 1,
+        source_prefix: 24,
+        scope_prefix: 0,
+        address: vec![192, 0, 2, 1],
+    });
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    emit(&mut encoder, &rdata).expect("failed to emit OPT");
+    let bytes = encoder.into_bytes();
+
+    // code, length, family, source and scope prefix, and only 3 octets of the address
+    assert_eq!(bytes, &[0, 8, 0, 7, 0, 1, 24, 0, 192, 0, 2]);
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata =
+        read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read OPT");
+    assert_eq!(
+        read_rdata.get(EdnsCode::Subnet),
+        Some(&EdnsOption::ClientSubnet {
+            family: 1,
+            source_prefix: 24,
+            scope_prefix: 0,
+            address: vec![192, 0, 2],
+        })
+    );
+}
+
+#[test]
+fn test_client_subnet_trailing_bits() {
+    let subnet = EdnsOption::ClientSubnet {
+        family: 2,
+        source_prefix: 20,
+        scope_prefix: 0,
+        address: vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    };
+
+    assert_eq!(subnet.len(), 7);
+    assert_eq!(
+        Vec::<u8>::from(&subnet),
+        vec![0, 2, 20, 0, 0x20, 0x01, 0x00]
+    );
+}
+
+#[test]
+fn test_extended_error() {
+    let mut rdata = OPT::default();
+    rdata.insert(EdnsOption::ExtendedError {
+        info_code: 6,
+        extra_text: "DNSSEC Bogus".to_string(),
+    });
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    emit(&mut encoder, &rdata).expect("failed to emit OPT");
+    let bytes = encoder.into_bytes();
+
+    // code, length, info code and the text
+    assert_eq!(&bytes[..6], &[0, 15, 0, 14, 0, 6]);
+    assert_eq!(&bytes[6..], b"DNSSEC Bogus");
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata =
+        read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read OPT");
+    assert_eq!(
+        read_rdata.get(EdnsCode::ExtendedError),
+        Some(&EdnsOption::ExtendedError {
+            info_code: 6,
+            extra_text: "DNSSEC Bogus".to_string(),
+        })
+    );
+
+    // the text is optional, but not the info code
+    let empty = EdnsOption::from((EdnsCode::ExtendedError, &[0, 22][..]));
+    assert_eq!(
+        empty,
+        EdnsOption::ExtendedError {
+            info_code: 22,
+            extra_text: String::new(),
+        }
+    );
+    assert_eq!(
+        EdnsOption::from((EdnsCode::ExtendedError, &[6][..])),
+        EdnsOption::Unknown(15, vec![6])
+    );
+}
+
+#[test]
+fn test_extended_errors() {
+    let bogus = EdnsOption::ExtendedError {
+        info_code: 6,
+        extra_text: "DNSSEC Bogus".to_string(),
+    };
+    let stale = EdnsOption::ExtendedError {
+        info_code: 3,
+        extra_text: String::new(),
+    };
+
+    let mut rdata = OPT::default();
+    rdata.insert(bogus.clone());
+    rdata.insert(EdnsOption::Padding(vec![0; 2]));
+    rdata.insert(stale.clone());
+    rdata.insert(bogus.clone());
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    emit(&mut encoder, &rdata).expect("failed to emit OPT");
+    let bytes = encoder.into_bytes();
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata =
+        read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read OPT");
+    assert_eq!(read_rdata.extended_errors(), &[bogus.clone(), stale][..]);
+    assert_eq!(read_rdata.get(EdnsCode::ExtendedError), Some(&bogus));
+}
+
+#[test]
+#[cfg(feature = "dnssec")]
+fn test_extended_error_from_bogus() {
+    assert_eq!(
+        EdnsOption::from(BogusReason::InvalidSignature),
+        EdnsOption::ExtendedError {
+            info_code: 6,
+            extra_text: "invalid signature".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_builder() {
+    use crate::rr::{RData, RecordType};
+
+    let cookie = EdnsOption::Unknown(10, vec![0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE]);
+    let mut builder = OptBuilder::new();
+    builder
+        .udp_payload_size(1232)
+        .dnssec_ok(true)
+        .add_option(EdnsOption::Unknown(3, vec![]))
+        .add_option(cookie.clone())
+        .add_option(EdnsOption::Padding(vec![0; 6]));
+    let record = builder.build();
+
+    assert_eq!(record.rr_type(), RecordType::OPT);
+    let edns = Edns::from(&record);
+    assert_eq!(edns.max_payload(), 1232);
+    assert!(edns.dnssec_ok());
+
+    let rdata = match *record.rdata() {
+        RData::OPT(ref rdata) => rdata,
+        ref rdata => panic!("not an OPT: {:?}", rdata),
+    };
+    let mut bytes = Vec::new();
+    emit(&mut BinEncoder::new(&mut bytes), rdata).expect("failed to emit OPT");
+
+    // the NSID, cookie and padding in the order they were added
+    let mut expected = vec![0, 3, 0, 0, 0, 10, 0, 8];
+    expected.extend_from_slice(&[0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE, 0xCA, 0xFE]);
+    expected.extend_from_slice(&[0, 12, 0, 6, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(bytes, expected);
+
+    // the cookie and the padding decode back, in the same order
+    let mut decoder = BinDecoder::new(&bytes);
+    let read_rdata =
+        read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read OPT");
+    assert_eq!(read_rdata.get(EdnsCode::Cookie), Some(&cookie));
+    assert_eq!(
+        read_rdata.get(EdnsCode::Padding),
+        Some(&EdnsOption::Padding(vec![0; 6]))
+    );
+    let codes: Vec<EdnsCode> = read_rdata.iter().map(|(code, _)| *code).collect();
+    assert_eq!(
+        codes,
+        vec![EdnsCode::NSID, EdnsCode::Cookie, EdnsCode::Padding]
+    );
+
+    // replacing an option keeps its place
+    let mut builder = OptBuilder::new();
+    builder
+        .add_option(EdnsOption::Unknown(10, vec![1]))
+        .add_option(EdnsOption::Unknown(3, vec![]))
+        .add_option(cookie.clone());
+    let record = builder.build();
+    let rdata = match *record.rdata() {
+        RData::OPT(ref rdata) => rdata,
+        ref rdata => panic!("not an OPT: {:?}", rdata),
+    };
+    let options: Vec<&EdnsOption> = rdata.iter().map(|(_, option)| option).collect();
+    assert_eq!(options, vec![&cookie, &EdnsOption::Unknown(3, vec![])]);
+}
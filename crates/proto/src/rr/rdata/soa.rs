@@ -282,3 +282,49 @@ fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_canonical() {
+    use std::str::FromStr;
+
+    use crate::rr::RData;
+
+    let rdata = SOA::new(
+        Name::from_ascii("NS1.example.com.").unwrap(),
+        Name::from_str("hostmaster.example.com.").unwrap(),
+        2019060101,
+        7200,
+        3600,
+        1_209_600,
+        300,
+    );
+    assert_eq!(
+        RData::SOA(rdata.clone()).to_string(),
+        "NS1.example.com. hostmaster.example.com. 2019060101 7200 3600 1209600 300"
+    );
+
+    // the names share example.com., which is only compressed in the non canonical form
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    emit(&mut encoder, &rdata).expect("failed to emit");
+    let compressed_len = encoder.into_bytes().len();
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    encoder
+        .with_canonical_names(|encoder| emit(encoder, &rdata))
+        .expect("failed to emit");
+    let bytes = encoder.into_bytes();
+    assert_eq!(bytes.len(), 17 + 24 + 20);
+    assert_eq!(compressed_len, 17 + 13 + 20);
+    assert_eq!(&bytes[..17], b"\x03ns1\x07example\x03com\x00");
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata = read(&mut decoder).expect("failed to read");
+    assert_eq!(read_rdata, rdata);
+    assert_eq!(read_rdata.serial(), 2019060101);
+    assert_eq!(read_rdata.refresh(), 7200);
+    assert_eq!(read_rdata.retry(), 3600);
+    assert_eq!(read_rdata.expire(), 1_209_600);
+    assert_eq!(read_rdata.minimum(), 300);
+}
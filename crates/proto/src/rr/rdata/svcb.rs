@@ -0,0 +1,485 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! SVCB and HTTPS records for service binding, the endpoints and parameters of a service
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::error::*;
+use crate::rr::domain::Name;
+use crate::serialize::binary::*;
+
+/// [RFC 9460, Service Binding and Parameter Specification via the DNS](https://tools.ietf.org/html/rfc9460#section-2.2)
+///
+/// ```text
+/// 2.2.  RDATA Wire Format
+///
+///    The RDATA for the SVCB RR consists of:
+///
+///    *  a 2-octet field for SvcPriority as an integer in network byte
+///       order.
+///
+///    *  the uncompressed, fully qualified TargetName, represented as a
+///       sequence of length-prefixed labels per Section 3.1 of [RFC1035].
+///
+///    *  the SvcParams, consuming the remainder of the record (so smaller
+///       than 65535 octets and constrained by the RDATA and DNS message
+///       sizes).
+///
+///    When the list of SvcParams is non-empty, it contains a series of
+///    SvcParamKey=SvcParamValue pairs, represented as:
+///
+///    *  a 2-octet field containing the SvcParamKey as an integer in
+///       network byte order.  (See Section 14.3.2 for the defined values.)
+///
+///    *  a 2-octet field containing the length of the SvcParamValue as an
+///       integer between 0 and 65535 in network byte order.
+///
+///    *  an octet string of this length whose contents are the SvcParamValue
+///       in a format determined by the SvcParamKey.
+/// ```
+///
+/// HTTPS records have the same format, RFC 9460 section 9.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SVCB {
+    svc_priority: u16,
+    target_name: Name,
+    svc_params: Vec<(SvcParamKey, Vec<u8>)>,
+}
+
+impl SVCB {
+    /// Creates a new SVCB record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `svc_priority` - 0 for AliasMode, otherwise the priority of this ServiceMode record.
+    /// * `target_name` - the name of the alias or the service endpoint, the root for the owner.
+    /// * `svc_params` - the keys and raw wire format values of the parameters, in any order.
+    ///
+    /// The parameters are emitted in the ascending order of their keys, each key may only be used
+    ///  once.
+    pub fn new(
+        svc_priority: u16,
+        target_name: Name,
+        svc_params: Vec<(SvcParamKey, Vec<u8>)>,
+    ) -> Self {
+        SVCB {
+            svc_priority,
+            target_name,
+            svc_params,
+        }
+    }
+
+    /// The priority of the record, 0 for AliasMode
+    pub fn svc_priority(&self) -> u16 {
+        self.svc_priority
+    }
+
+    /// Returns true if this is an AliasMode record, which only aliases the owner to the target name
+    pub fn is_alias_mode(&self) -> bool {
+        self.svc_priority == 0
+    }
+
+    /// The name of the alias, or of the service endpoint
+    pub fn target_name(&self) -> &Name {
+        &self.target_name
+    }
+
+    /// The keys and raw values of all parameters, in the order of the record
+    pub fn svc_params(&self) -> &[(SvcParamKey, Vec<u8>)] {
+        &self.svc_params
+    }
+
+    /// The raw value of the parameter with the key
+    pub fn svc_param(&self, key: SvcParamKey) -> Option<&[u8]> {
+        self.svc_params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// The keys of the parameters which clients must support to use this endpoint
+    pub fn mandatory(&self) -> Vec<u16> {
+        self.svc_param(SvcParamKey::Mandatory)
+            .map(|value| {
+                value
+                    .chunks_exact(2)
+                    .map(|key| u16::from(key[0]) << 8 | u16::from(key[1]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The ALPN protocol identifiers supported by the endpoint, e.g. `h2`
+    ///
+    /// None if there is no `alpn` parameter, or if its value is not a sequence of length-prefixed
+    ///  identifiers.
+    pub fn alpn(&self) -> Option<Vec<String>> {
+        let mut value = self.svc_param(SvcParamKey::Alpn)?;
+        let mut alpn = Vec::new();
+        while let Some((&len, rest)) = value.split_first() {
+            if usize::from(len) > rest.len() {
+                return None;
+            }
+
+            let (id, rest) = rest.split_at(usize::from(len));
+            alpn.push(String::from_utf8_lossy(id).into_owned());
+            value = rest;
+        }
+
+        Some(alpn)
+    }
+
+    /// The alternative port of the endpoint
+    pub fn port(&self) -> Option<u16> {
+        match self.svc_param(SvcParamKey::Port)? {
+            [high, low] => Some(u16::from(*high) << 8 | u16::from(*low)),
+            _ => None,
+        }
+    }
+
+    /// IPv4 addresses which clients may use to reach the endpoint before resolving the target name
+    pub fn ipv4hint(&self) -> Vec<Ipv4Addr> {
+        self.svc_param(SvcParamKey::Ipv4Hint)
+            .map(|value| {
+                value
+                    .chunks_exact(4)
+                    .map(|a| Ipv4Addr::new(a[0], a[1], a[2], a[3]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// IPv6 addresses which clients may use to reach the endpoint before resolving the target name
+    pub fn ipv6hint(&self) -> Vec<Ipv6Addr> {
+        self.svc_param(SvcParamKey::Ipv6Hint)
+            .map(|value| {
+                value
+                    .chunks_exact(16)
+                    .map(|a| {
+                        let mut octets = [0u8; 16];
+                        octets.copy_from_slice(a);
+                        Ipv6Addr::from(octets)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// [RFC 9460](https://tools.ietf.org/html/rfc9460#section-14.3.2) the keys of SvcParams
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SvcParamKey {
+    /// Mandatory keys in this RR
+    Mandatory,
+    /// Additional supported protocols
+    Alpn,
+    /// No support for the default protocol
+    NoDefaultAlpn,
+    /// Port for alternative endpoint
+    Port,
+    /// IPv4 address hints
+    Ipv4Hint,
+    /// TLS Encrypted ClientHello configuration
+    Ech,
+    /// IPv6 address hints
+    Ipv6Hint,
+    /// Unknown, used to deal with unknown or unsupported keys
+    Unknown(u16),
+}
+
+impl SvcParamKey {
+    /// Checks the wire format of the value of known keys
+    fn is_valid_value(self, value: &[u8]) -> bool {
+        match self {
+            SvcParamKey::Mandatory => {
+                !value.is_empty() && value.chunks_exact(2).remainder().is_empty()
+            }
+            SvcParamKey::Alpn => {
+                // a non-empty sequence of non-empty, length-prefixed identifiers
+                let mut ids = value;
+                while let Some((&len, rest)) = ids.split_first() {
+                    if len == 0 || usize::from(len) > rest.len() {
+                        return false;
+                    }
+                    ids = &rest[usize::from(len)..];
+                }
+                !value.is_empty()
+            }
+            SvcParamKey::NoDefaultAlpn => value.is_empty(),
+            SvcParamKey::Port => value.len() == 2,
+            SvcParamKey::Ipv4Hint => {
+                !value.is_empty() && value.chunks_exact(4).remainder().is_empty()
+            }
+            SvcParamKey::Ipv6Hint => {
+                !value.is_empty() && value.chunks_exact(16).remainder().is_empty()
+            }
+            SvcParamKey::Ech | SvcParamKey::Unknown(_) => true,
+        }
+    }
+}
+
+impl From<u16> for SvcParamKey {
+    fn from(key: u16) -> Self {
+        match key {
+            0 => SvcParamKey::Mandatory,
+            1 => SvcParamKey::Alpn,
+            2 => SvcParamKey::NoDefaultAlpn,
+            3 => SvcParamKey::Port,
+            4 => SvcParamKey::Ipv4Hint,
+            5 => SvcParamKey::Ech,
+            6 => SvcParamKey::Ipv6Hint,
+            _ => SvcParamKey::Unknown(key),
+        }
+    }
+}
+
+impl From<SvcParamKey> for u16 {
+    fn from(key: SvcParamKey) -> u16 {
+        match key {
+            SvcParamKey::Mandatory => 0,
+            SvcParamKey::Alpn => 1,
+            SvcParamKey::NoDefaultAlpn => 2,
+            SvcParamKey::Port => 3,
+            SvcParamKey::Ipv4Hint => 4,
+            SvcParamKey::Ech => 5,
+            SvcParamKey::Ipv6Hint => 6,
+            SvcParamKey::Unknown(key) => key,
+        }
+    }
+}
+
+/// Read the RData from the given decoder.
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<SVCB> {
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
+
+    decoder.read_bounded(rdata_length, read_fields)
+}
+
+/// Reads the fields of the SVCB, the decoder is bounded to its rdata
+fn read_fields(decoder: &mut BinDecoder) -> ProtoResult<SVCB> {
+    let svc_priority = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let target_name = Name::read(decoder)?;
+
+    let mut svc_params: Vec<(SvcParamKey, Vec<u8>)> = Vec::new();
+    while decoder.remaining() > 0 {
+        let key = decoder.read_u16()?.unverified(/*checked against the previous key*/);
+        verify_key_order(
+            svc_params.last().map(|(previous, _)| u16::from(*previous)),
+            key,
+        )?;
+
+        let key = SvcParamKey::from(key);
+        let len = decoder.read_u16()?.unverified(/*bounded by the decoder*/);
+        let value = decoder
+            .read_vec(len as usize)?
+            .verify_unwrap(|value| key.is_valid_value(value))
+            .map_err(|_| ProtoErrorKind::Msg(format!("invalid SVCB value for {:?}", key)))?;
+        svc_params.push((key, value));
+    }
+
+    Ok(SVCB::new(svc_priority, target_name, svc_params))
+}
+
+/// Write the RData using the given encoder.
+pub fn emit(encoder: &mut BinEncoder, svcb: &SVCB) -> ProtoResult<()> {
+    encoder.emit_u16(svcb.svc_priority())?;
+    // the target name must not be compressed, RFC 9460 section 2.2
+    encoder.with_canonical_names(|encoder| svcb.target_name().emit(encoder))?;
+
+    // the keys must be ascending, RFC 9460 section 2.2
+    let mut svc_params = svcb.svc_params().iter().collect::<Vec<_>>();
+    svc_params.sort_by_key(|(key, _)| u16::from(*key));
+
+    let mut previous = None;
+    for (key, value) in svc_params {
+        verify_key_order(previous, u16::from(*key))?;
+        previous = Some(u16::from(*key));
+
+        if value.len() > 0xFFFF {
+            return Err(
+                ProtoErrorKind::Msg(format!("SVCB value for {:?} is too long", key)).into(),
+            );
+        }
+
+        encoder.emit_u16((*key).into())?;
+        encoder.emit_u16(value.len() as u16)?;
+        encoder.emit_vec(value)?;
+    }
+
+    Ok(())
+}
+
+/// Verifies the key is greater than the previous one, so the keys are ascending and unique
+fn verify_key_order(previous: Option<u16>, key: u16) -> ProtoResult<()> {
+    match previous {
+        Some(previous) if key <= previous => {
+            Err(ProtoErrorKind::SvcParamKeyNotAscending { key, previous }.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn service_mode() -> SVCB {
+        SVCB::new(
+            1,
+            Name::from_str("svc.example.net.").unwrap(),
+            vec![
+                (SvcParamKey::Mandatory, vec![0, 1, 0, 4]),
+                (SvcParamKey::Alpn, b"\x02h2\x05h3-29".to_vec()),
+                (SvcParamKey::Port, vec![0x20, 0xFB]),
+                (SvcParamKey::Ipv4Hint, vec![192, 0, 2, 1, 192, 0, 2, 2]),
+                (
+                    SvcParamKey::Ipv6Hint,
+                    Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1)
+                        .octets()
+                        .to_vec(),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_service_mode_params() {
+        let svcb = service_mode();
+        assert!(!svcb.is_alias_mode());
+
+        assert_eq!(svcb.mandatory(), vec![1, 4]);
+        assert_eq!(
+            svcb.alpn(),
+            Some(vec!["h2".to_string(), "h3-29".to_string()])
+        );
+        assert_eq!(svcb.port(), Some(8443));
+        assert_eq!(
+            svcb.ipv4hint(),
+            vec![Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2)]
+        );
+        assert_eq!(
+            svcb.ipv6hint(),
+            vec![Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_missing_params() {
+        let svcb = SVCB::new(0, Name::from_str("foo.example.com.").unwrap(), vec![]);
+        assert!(svcb.is_alias_mode());
+
+        assert!(svcb.mandatory().is_empty());
+        assert_eq!(svcb.alpn(), None);
+        assert_eq!(svcb.port(), None);
+        assert!(svcb.ipv4hint().is_empty());
+        assert!(svcb.ipv6hint().is_empty());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let svcb = service_mode();
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &svcb).expect("failed to emit SVCB");
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_svcb =
+            read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read SVCB");
+        assert_eq!(read_svcb, svcb);
+    }
+
+    #[test]
+    fn test_emit_sorted() {
+        let svcb = service_mode();
+        let mut unsorted = svcb.svc_params().to_vec();
+        unsorted.reverse();
+        let unsorted = SVCB::new(1, svcb.target_name().clone(), unsorted);
+
+        let mut bytes = Vec::new();
+        emit(&mut BinEncoder::new(&mut bytes), &svcb).expect("failed to emit SVCB");
+        let mut unsorted_bytes = Vec::new();
+        emit(&mut BinEncoder::new(&mut unsorted_bytes), &unsorted).expect("failed to emit SVCB");
+        assert_eq!(unsorted_bytes, bytes);
+
+        // round trips to the sorted params
+        let mut decoder = BinDecoder::new(&unsorted_bytes);
+        let read_svcb = read(&mut decoder, Restrict::new(unsorted_bytes.len() as u16))
+            .expect("failed to read SVCB");
+        assert_eq!(read_svcb, svcb);
+    }
+
+    #[test]
+    fn test_emit_duplicate_keys() {
+        let svcb = SVCB::new(
+            1,
+            Name::root(),
+            vec![
+                (SvcParamKey::Port, vec![0x01, 0xBB]),
+                (SvcParamKey::Alpn, b"\x02h2".to_vec()),
+                (SvcParamKey::Port, vec![0x20, 0xFB]),
+            ],
+        );
+
+        let mut bytes = Vec::new();
+        let error = emit(&mut BinEncoder::new(&mut bytes), &svcb).unwrap_err();
+        assert_eq!(
+            *error.kind(),
+            ProtoErrorKind::SvcParamKeyNotAscending {
+                key: 3,
+                previous: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_key_order() {
+        // port then alpn
+        let unordered = b"\x00\x01\x00\x00\x03\x00\x02\x01\xBB\x00\x01\x00\x03\x02h2";
+        // port twice
+        let duplicate = b"\x00\x01\x00\x00\x03\x00\x02\x01\xBB\x00\x03\x00\x02\x20\xFB";
+
+        for &(bytes, key, previous) in &[(&unordered[..], 1, 3), (&duplicate[..], 3, 3)] {
+            let mut decoder = BinDecoder::new(bytes);
+            let error = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap_err();
+            assert_eq!(
+                *error.kind(),
+                ProtoErrorKind::SvcParamKeyNotAscending { key, previous }
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_invalid_values() {
+        let invalid = [
+            // port of three octets
+            b"\x00\x01\x00\x00\x03\x00\x03\x01\xBB\x00".to_vec(),
+            // alpn identifier longer than the value
+            b"\x00\x01\x00\x00\x01\x00\x03\x05h2".to_vec(),
+            // ipv4hint of a partial address
+            b"\x00\x01\x00\x00\x04\x00\x03\xC0\x00\x02".to_vec(),
+            // no-default-alpn with a value
+            b"\x00\x01\x00\x00\x02\x00\x01\x00".to_vec(),
+        ];
+
+        for bytes in &invalid {
+            let mut decoder = BinDecoder::new(bytes);
+            assert!(read(&mut decoder, Restrict::new(bytes.len() as u16)).is_err());
+        }
+
+        // a value beyond the end of the rdata
+        let bytes = b"\x00\x01\x00\x00\x03\x00\x02\x01\xBB";
+        let mut decoder = BinDecoder::new(bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16 - 1)).is_err());
+    }
+}
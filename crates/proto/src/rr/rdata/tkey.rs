@@ -0,0 +1,234 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TKEY records for establishing shared secret keys
+
+use crate::error::*;
+use crate::rr::domain::Name;
+use crate::serialize::binary::*;
+
+/// [RFC 2930](https://tools.ietf.org/html/rfc2930#section-2)
+///
+/// ```text
+/// 2. The TKEY Resource Record
+///
+///    The TKEY resource record (RR) has the structure given below.  Its RR
+///    type code is 249.
+///
+///       Field       Type         Comment
+///       -----       ----         -------
+///
+///       NAME         domain      see description below
+///       TTYPE        u_int16_t   TKEY = 249
+///       CLASS        u_int16_t   ignored, SHOULD be 255 (ANY)
+///       TTL          u_int32_t   ignored, SHOULD be zero
+///       RDLEN        u_int16_t   size of RDATA
+///       RDATA:
+///            Algorithm:   domain
+///            Inception:   u_int32_t
+///            Expiration:  u_int32_t
+///            Mode:        u_int16_t
+///            Error:       u_int16_t
+///            Key Size:    u_int16_t
+///            Key Data:    octet-stream
+///            Other Size:  u_int16_t
+///            Other Data:  octet-stream  undefined by this specification
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TKEY {
+    algorithm: Name,
+    inception: u32,
+    expiration: u32,
+    mode: u16,
+    error: u16,
+    key_data: Vec<u8>,
+    other_data: Vec<u8>,
+}
+
+impl TKEY {
+    /// The mode value for server assigned keying
+    pub const MODE_SERVER_ASSIGNMENT: u16 = 1;
+    /// The mode value for Diffie-Hellman exchanged keying
+    pub const MODE_DIFFIE_HELLMAN: u16 = 2;
+    /// The mode value for GSS-API negotiation
+    pub const MODE_GSS_API: u16 = 3;
+    /// The mode value for resolver assigned keying
+    pub const MODE_RESOLVER_ASSIGNMENT: u16 = 4;
+    /// The mode value for key deletion
+    pub const MODE_KEY_DELETION: u16 = 5;
+
+    /// Constructs a new TKEY
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - name of the algorithm the key is for, e.g. `gss-tsig.`
+    /// * `inception` - start of the key validity period, in seconds since the unix epoch
+    /// * `expiration` - end of the key validity period, in seconds since the unix epoch
+    /// * `mode` - the keying mode, see the `MODE_*` constants
+    /// * `error` - the extended rcode, zero in queries
+    /// * `key_data` - the keying material, interpreted according to `mode`
+    /// * `other_data` - reserved for future use
+    pub fn new(
+        algorithm: Name,
+        inception: u32,
+        expiration: u32,
+        mode: u16,
+        error: u16,
+        key_data: Vec<u8>,
+        other_data: Vec<u8>,
+    ) -> Self {
+        TKEY {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key_data,
+            other_data,
+        }
+    }
+
+    /// The name of the algorithm the key is for
+    pub fn algorithm(&self) -> &Name {
+        &self.algorithm
+    }
+
+    /// Start of the key validity period, in seconds since the unix epoch
+    pub fn inception(&self) -> u32 {
+        self.inception
+    }
+
+    /// End of the key validity period, in seconds since the unix epoch
+    pub fn expiration(&self) -> u32 {
+        self.expiration
+    }
+
+    /// The keying mode, see the `MODE_*` constants
+    pub fn mode(&self) -> u16 {
+        self.mode
+    }
+
+    /// The extended rcode, zero in queries
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// The keying material
+    pub fn key_data(&self) -> &[u8] {
+        &self.key_data
+    }
+
+    /// Other data, undefined by RFC 2930
+    pub fn other_data(&self) -> &[u8] {
+        &self.other_data
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<TKEY> {
+    let start_idx = decoder.index();
+
+    let algorithm = Name::read(decoder)?;
+    let inception = decoder.read_u32()?.unverified(/*any u32 is valid*/);
+    let expiration = decoder.read_u32()?.unverified(/*any u32 is valid*/);
+    let mode = decoder.read_u16()?.unverified(/*unknown modes are left to the caller*/);
+    let error = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let key_size = decoder.read_u16()?.unverified(/*used only as a length safely*/);
+    let key_data = decoder
+        .read_vec(key_size as usize)?
+        .unverified(/*interpretation depends on the mode*/);
+    let other_size = decoder.read_u16()?.unverified(/*used only as a length safely*/);
+    let other_data = decoder
+        .read_vec(other_size as usize)?
+        .unverified(/*opaque data*/);
+
+    let read = decoder.index() - start_idx;
+    rdata_length
+        .map(|u| u as usize)
+        .verify_unwrap(|rdata_length| read == *rdata_length)
+        .map_err(|rdata_length| {
+            ProtoError::from(ProtoErrorKind::IncorrectRDataLengthRead {
+                read,
+                len: rdata_length,
+            })
+        })?;
+
+    Ok(TKEY::new(
+        algorithm, inception, expiration, mode, error, key_data, other_data,
+    ))
+}
+
+/// Write the RData from the given Encoder
+pub fn emit(encoder: &mut BinEncoder, tkey: &TKEY) -> ProtoResult<()> {
+    // the algorithm name must not be compressed
+    tkey.algorithm.emit_as_canonical(encoder, true)?;
+    encoder.emit_u32(tkey.inception)?;
+    encoder.emit_u32(tkey.expiration)?;
+    encoder.emit_u16(tkey.mode)?;
+    encoder.emit_u16(tkey.error)?;
+    encoder.emit_u16(tkey.key_data.len() as u16)?;
+    encoder.emit_vec(&tkey.key_data)?;
+    encoder.emit_u16(tkey.other_data.len() as u16)?;
+    encoder.emit_vec(&tkey.other_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_gss_api() {
+        let algorithm = Name::from_ascii("gss-tsig.").unwrap();
+        let rdata = TKEY::new(
+            algorithm.clone(),
+            1_577_836_800,
+            1_577_923_200,
+            TKEY::MODE_GSS_API,
+            0,
+            vec![0x60, 0x82, 0x01, 0x02],
+            vec![],
+        );
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = BinEncoder::new(&mut bytes);
+            // a prior use of the name must not lead to a pointer in the rdata
+            algorithm.emit(&mut encoder).unwrap();
+            emit(&mut encoder, &rdata).expect("failed to emit TKEY");
+        }
+
+        let rdata_bytes = &bytes[10..];
+        assert_eq!(&rdata_bytes[..10], b"\x08gss-tsig\x00");
+        assert_eq!(&rdata_bytes[18..20], &[0, 3]);
+
+        let mut decoder = BinDecoder::new(rdata_bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(rdata_bytes.len() as u16))
+            .expect("failed to read TKEY");
+        assert_eq!(read_rdata, rdata);
+    }
+
+    #[test]
+    fn test_read_wrong_length() {
+        let rdata = TKEY::new(
+            Name::root(),
+            0,
+            0,
+            TKEY::MODE_KEY_DELETION,
+            0,
+            vec![],
+            vec![],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit TKEY");
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = BinDecoder::new(bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16 + 1)).is_err());
+    }
+}
@@ -59,6 +59,23 @@ impl TXT {
         }
     }
 
+    /// Creates a new TXT record data from arbitrary bytes
+    ///
+    /// Unlike `new`, the strings are not required to be valid UTF-8, e.g. for binary data.
+    ///
+    /// # Arguments
+    ///
+    /// * `txt_data` - the set of byte strings which make up the txt_data.
+    pub fn from_bytes(txt_data: Vec<&[u8]>) -> TXT {
+        TXT {
+            txt_data: txt_data
+                .into_iter()
+                .map(|s| s.to_vec().into_boxed_slice())
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+        }
+    }
+
     /// ```text
     /// TXT-DATA        One or more <character-string>s.
     /// ```
@@ -74,17 +91,19 @@ impl TXT {
 
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<TXT> {
-    let data_len = decoder.len();
-    let mut strings = Vec::with_capacity(1);
-
-    // no unsafe usage of rdata length after this point
-    let rdata_length = rdata_length.map(|u| u as usize).unverified(/*used as a higher bound, safely*/);
-    while data_len - decoder.len() < rdata_length {
-        let string = decoder.read_character_data()?.unverified(/*any data should be validate in TXT usage*/);
-        strings.push(string.to_vec().into_boxed_slice());
-    }
-    Ok(TXT {
-        txt_data: strings.into_boxed_slice(),
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
+
+    decoder.read_bounded(rdata_length, |decoder| {
+        let mut strings = Vec::with_capacity(1);
+        while decoder.remaining() > 0 {
+            let string = decoder.read_character_data()?.unverified(/*any data should be validate in TXT usage*/);
+            strings.push(string.to_vec().into_boxed_slice());
+        }
+        Ok(TXT {
+            txt_data: strings.into_boxed_slice(),
+        })
     })
 }
 
@@ -116,3 +135,18 @@ fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_binary() {
+    let rdata = TXT::from_bytes(vec![b"a\x00b", &[0xFF, 0x80, b'"']]);
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    assert!(emit(&mut encoder, &rdata).is_ok());
+    let bytes = encoder.into_bytes();
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read");
+    assert_eq!(rdata, read_rdata);
+    assert_eq!(&*read_rdata.txt_data()[0], b"a\x00b");
+}
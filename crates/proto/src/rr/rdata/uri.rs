@@ -0,0 +1,149 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! URI records for publishing mappings from hostnames to URIs
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 7553](https://tools.ietf.org/html/rfc7553#section-4.5)
+///
+/// ```text
+/// 4.5.  URI RDATA Wire Format
+///
+///    The RDATA for a URI RR consists of a 2-octet Priority field, a
+///    2-octet Weight field, and a variable-length Target field.
+///
+///    Priority and Weight are unsigned integers in network byte order.
+///
+///    The remaining data in the RDATA contains the Target field.  The
+///    Target field contains the URI as a sequence of octets (without the
+///    enclosing double-quote characters used in the presentation format).
+///
+///    The length of the Target field MUST be greater than zero.
+///
+///                         1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |          Priority             |          Weight               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    /                                                               /
+///    /                             Target                            /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct URI {
+    priority: u16,
+    weight: u16,
+    target: String,
+}
+
+impl URI {
+    /// Creates a new URI record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - lower values are preferred, as for SRV records.
+    /// * `weight` - the relative weight among targets of the same priority.
+    /// * `target` - the URI, as specified in RFC 3986. This will NOT be checked.
+    pub fn new(priority: u16, weight: u16, target: String) -> Self {
+        URI {
+            priority,
+            weight,
+            target,
+        }
+    }
+
+    /// The priority of the target URI, clients must use the lowest-numbered target they can reach
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// The weight for selecting among targets of the same priority, larger weights are selected
+    ///  more often
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// The target URI
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+/// Read the RData from the given decoder.
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<URI> {
+    let rdata_length = rdata_length
+        .map(usize::from)
+        .unverified(/*checked against the buffer by read_bounded*/);
+
+    decoder.read_bounded(rdata_length, |decoder| {
+        let priority = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+        let weight = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+
+        // the target is the rest of the rdata
+        if decoder.is_empty() {
+            return Err("URI target must not be empty".into());
+        }
+        let target_len = decoder.remaining();
+        let target = decoder.read_vec(target_len)?.unverified(/*checked as utf8 below*/);
+        let target = String::from_utf8(target).map_err(|e| e.utf8_error())?;
+        Ok(URI::new(priority, weight, target))
+    })
+}
+
+/// Write the RData using the given encoder.
+pub fn emit(encoder: &mut BinEncoder, uri: &URI) -> ProtoResult<()> {
+    if uri.target().is_empty() {
+        return Err("URI target must not be empty".into());
+    }
+
+    encoder.emit_u16(uri.priority())?;
+    encoder.emit_u16(uri.weight())?;
+    encoder.emit_vec(uri.target().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_encode_decode(rdata: URI, result: &[u8]) {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit URI");
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes, &result);
+
+        let mut decoder = BinDecoder::new(result);
+        let read_rdata =
+            read(&mut decoder, Restrict::new(result.len() as u16)).expect("failed to read URI");
+        assert_eq!(read_rdata, rdata)
+    }
+
+    #[test]
+    fn test_encode_decode_uri() {
+        test_encode_decode(
+            URI::new(10, 1, "ftp://ftp1.example.com/public".to_string()),
+            b"\x00\x0A\x00\x01ftp://ftp1.example.com/public",
+        );
+    }
+
+    #[test]
+    fn test_empty_target() {
+        let bytes = [0, 10, 0, 1];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16)).is_err());
+
+        let mut bytes = Vec::new();
+        assert!(emit(
+            &mut BinEncoder::new(&mut bytes),
+            &URI::new(10, 1, String::new())
+        )
+        .is_err());
+    }
+}
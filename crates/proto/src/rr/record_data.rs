@@ -19,14 +19,19 @@
 use std::cmp::Ordering;
 #[cfg(test)]
 use std::convert::From;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use super::domain::Name;
 use super::rdata;
-use super::rdata::{CAA, MX, NAPTR, NULL, OPENPGPKEY, OPT, SOA, SRV, SSHFP, TLSA, TXT};
+use super::rdata::{
+    CAA, DHCID, HINFO, MX, NAPTR, NULL, OPENPGPKEY, OPT, SOA, SRV, SSHFP, SVCB, TKEY, TLSA, TXT,
+    URI,
+};
 use super::record_type::RecordType;
 use crate::error::*;
 use crate::serialize::binary::*;
+use crate::serialize::hex;
 
 #[cfg(feature = "dnssec")]
 use super::dnssec::rdata::DNSSECRData;
@@ -165,6 +170,53 @@ pub enum RData {
     /// ```
     CNAME(Name),
 
+    /// [RFC 4701, A DNS Resource Record (RR) for Encoding DHCP Information, October 2006](https://tools.ietf.org/html/rfc4701#section-3)
+    ///
+    /// ```text
+    /// 3.1.  DHCID RDATA Format
+    ///
+    ///    The RDATA section of a DHCID RR in transmission contains RDLENGTH
+    ///    octets of binary data.  The format of this data and its
+    ///    interpretation by DHCP servers and clients are described below.
+    ///    DNS software should consider the RDATA section to be opaque.
+    ///
+    ///    < 2 octets >    Identifier type code
+    ///    < 1 octet >     Digest type code
+    ///    < n octets >    Digest (length depends on digest type)
+    /// ```
+    DHCID(DHCID),
+
+    /// [RFC 6672, DNAME Redirection in the DNS, June 2012](https://tools.ietf.org/html/rfc6672#section-2.1)
+    ///
+    /// ```text
+    /// 2.1.  Format
+    ///
+    ///    The DNAME RR has mnemonic DNAME and type code 39 (decimal).  It is
+    ///    not class-sensitive.
+    ///
+    ///    Its format is identical to that of the CNAME RR, and its RDATA
+    ///    consists of a single field:
+    ///
+    ///    <target>      A <domain-name> which specifies the target of the
+    ///                  redirection.
+    /// ```
+    DNAME(Name),
+
+    /// [RFC 9460, Service Binding and Parameter Specification via the DNS](https://tools.ietf.org/html/rfc9460#section-9)
+    ///
+    /// ```text
+    /// 9.  Using Service Bindings with HTTP
+    ///
+    ///    The use of any protocol with SVCB requires a protocol-specific
+    ///    mapping specification.  This section specifies the mapping for the
+    ///    "http" and "https" URI schemes [HTTP].
+    ///
+    ///    To enable special handling for HTTP use cases, the HTTPS RR type is
+    ///    defined as a SVCB-compatible RR type, specific to the "https" and
+    ///    "http" schemes.
+    /// ```
+    HTTPS(SVCB),
+
     /// ```text
     /// 3.3.9. MX RDATA format
     ///
@@ -190,6 +242,24 @@ pub enum RData {
     /// ```
     MX(MX),
 
+    /// ```text
+    /// 3.3.2. HINFO RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                      CPU                      /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                       OS                      /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// CPU             A <character-string> which specifies the CPU type.
+    ///
+    /// OS              A <character-string> which specifies the operating
+    ///                 system type.
+    /// ```
+    HINFO(HINFO),
+
     /// [RFC 3403 DDDS DNS Database, October 2002](https://tools.ietf.org/html/rfc3403#section-4)
     ///
     /// ```text
@@ -544,6 +614,43 @@ pub enum RData {
     /// [RFC 7479](https://tools.ietf.org/html/rfc7479).
     SSHFP(SSHFP),
 
+    /// [RFC 9460, Service Binding and Parameter Specification via the DNS](https://tools.ietf.org/html/rfc9460#section-2.2)
+    ///
+    /// ```text
+    /// 2.2.  RDATA Wire Format
+    ///
+    ///    The RDATA for the SVCB RR consists of:
+    ///
+    ///    *  a 2-octet field for SvcPriority as an integer in network byte
+    ///       order.
+    ///
+    ///    *  the uncompressed, fully qualified TargetName, represented as a
+    ///       sequence of length-prefixed labels per Section 3.1 of [RFC1035].
+    ///
+    ///    *  the SvcParams, consuming the remainder of the record (so smaller
+    ///       than 65535 octets and constrained by the RDATA and DNS message
+    ///       sizes).
+    /// ```
+    SVCB(SVCB),
+
+    /// [RFC 2930, Secret Key Establishment for DNS](https://tools.ietf.org/html/rfc2930#section-2)
+    ///
+    /// ```text
+    ///       Field       Type         Comment
+    ///       -----       ----         -------
+    ///
+    ///       Algorithm:   domain
+    ///       Inception:   u_int32_t
+    ///       Expiration:  u_int32_t
+    ///       Mode:        u_int16_t
+    ///       Error:       u_int16_t
+    ///       Key Size:    u_int16_t
+    ///       Key Data:    octet-stream
+    ///       Other Size:  u_int16_t
+    ///       Other Data:  octet-stream  undefined by this specification
+    /// ```
+    TKEY(TKEY),
+
     /// [RFC 6698, DNS-Based Authentication for TLS](https://tools.ietf.org/html/rfc6698#section-2.1)
     ///
     /// ```text
@@ -575,6 +682,22 @@ pub enum RData {
     /// ```
     TXT(TXT),
 
+    /// [RFC 7553, The Uniform Resource Identifier (URI) DNS Resource Record, June 2015](https://tools.ietf.org/html/rfc7553#section-4.5)
+    ///
+    /// ```text
+    /// 4.5.  URI RDATA Wire Format
+    ///
+    ///    The RDATA for a URI RR consists of a 2-octet Priority field, a
+    ///    2-octet Weight field, and a variable-length Target field.
+    ///
+    ///    Priority and Weight are unsigned integers in network byte order.
+    ///
+    ///    The remaining data in the RDATA contains the Target field.  The
+    ///    Target field contains the URI as a sequence of octets (without the
+    ///    enclosing double-quote characters used in the presentation format).
+    /// ```
+    URI(URI),
+
     /// A DNSSEC- or SIG(0)- specific record. See `DNSSECRData` for details.
     ///
     /// These types are in `DNSSECRData` to make them easy to disable when
@@ -612,23 +735,26 @@ impl RData {
         record_type: RecordType,
         rdata_length: Restrict<u16>,
     ) -> ProtoResult<Self> {
-        let start_idx = decoder.index();
+        // no rdata is read beyond its length, nor allocated for more data than there is
+        let len = rdata_length
+            .map(usize::from)
+            .unverified(/*checked against the buffer by read_bounded*/);
 
-        let result = match record_type {
+        decoder.read_bounded(len, |decoder| match record_type {
             RecordType::A => {
                 debug!("reading A");
-                rdata::a::read(decoder).map(RData::A)
+                rdata::a::read(decoder, rdata_length).map(RData::A)
             }
             RecordType::AAAA => {
                 debug!("reading AAAA");
-                rdata::aaaa::read(decoder).map(RData::AAAA)
+                rdata::aaaa::read(decoder, rdata_length).map(RData::AAAA)
             }
             RecordType::ANAME => {
                 debug!("reading ANAME");
                 rdata::name::read(decoder).map(RData::ANAME)
             }
             rt @ RecordType::ANY | rt @ RecordType::AXFR | rt @ RecordType::IXFR => {
-                return Err(ProtoErrorKind::UnknownRecordTypeValue(rt.into()).into());
+                Err(ProtoErrorKind::UnknownRecordTypeValue(rt.into()).into())
             }
             RecordType::CAA => {
                 debug!("reading CAA");
@@ -638,9 +764,25 @@ impl RData {
                 debug!("reading CNAME");
                 rdata::name::read(decoder).map(RData::CNAME)
             }
+            RecordType::DNAME => {
+                debug!("reading DNAME");
+                rdata::name::read(decoder).map(RData::DNAME)
+            }
+            RecordType::HINFO => {
+                debug!("reading HINFO");
+                rdata::hinfo::read(decoder).map(RData::HINFO)
+            }
+            RecordType::HTTPS => {
+                debug!("reading HTTPS");
+                rdata::svcb::read(decoder, rdata_length).map(RData::HTTPS)
+            }
+            RecordType::DHCID => {
+                debug!("reading DHCID");
+                rdata::dhcid::read(decoder, rdata_length).map(RData::DHCID)
+            }
             RecordType::ZERO => {
                 debug!("reading EMPTY");
-                return Ok(RData::ZERO);
+                Ok(RData::ZERO)
             }
             RecordType::MX => {
                 debug!("reading MX");
@@ -682,6 +824,14 @@ impl RData {
                 debug!("reading SSHFP");
                 rdata::sshfp::read(decoder, rdata_length).map(RData::SSHFP)
             }
+            RecordType::SVCB => {
+                debug!("reading SVCB");
+                rdata::svcb::read(decoder, rdata_length).map(RData::SVCB)
+            }
+            RecordType::TKEY => {
+                debug!("reading TKEY");
+                rdata::tkey::read(decoder, rdata_length).map(RData::TKEY)
+            }
             RecordType::TLSA => {
                 debug!("reading TLSA");
                 rdata::tlsa::read(decoder, rdata_length).map(RData::TLSA)
@@ -690,6 +840,10 @@ impl RData {
                 debug!("reading TXT");
                 rdata::txt::read(decoder, rdata_length).map(RData::TXT)
             }
+            RecordType::URI => {
+                debug!("reading URI");
+                rdata::uri::read(decoder, rdata_length).map(RData::URI)
+            }
             #[cfg(feature = "dnssec")]
             RecordType::DNSSEC(record_type) => {
                 DNSSECRData::read(decoder, record_type, rdata_length).map(RData::DNSSEC)
@@ -698,21 +852,7 @@ impl RData {
                 debug!("reading Unknown");
                 rdata::null::read(decoder, rdata_length).map(|rdata| RData::Unknown { code, rdata })
             }
-        };
-
-        // we should have read rdata_length, but we did not
-        let read = decoder.index() - start_idx;
-        rdata_length
-            .map(|u| u as usize)
-            .verify_unwrap(|rdata_length| read == *rdata_length)
-            .map_err(|rdata_length| {
-                ProtoError::from(ProtoErrorKind::IncorrectRDataLengthRead {
-                    read,
-                    len: rdata_length,
-                })
-            })?;
-
-        result
+        })
     }
 
     /// [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6), DNSSEC Resource Records, March 2005
@@ -796,7 +936,14 @@ impl RData {
             RData::CNAME(ref name) | RData::NS(ref name) | RData::PTR(ref name) => {
                 rdata::name::emit(encoder, name)
             }
+            RData::DHCID(ref dhcid) => rdata::dhcid::emit(encoder, dhcid),
+            // the target is never compressed, RFC 3597 section 4, but lowercased for rfc4034
+            RData::DNAME(ref name) => {
+                let lowercase = encoder.is_canonical_names();
+                encoder.with_canonical_names(|encoder| name.emit_with_lowercase(encoder, lowercase))
+            }
             RData::ZERO => Ok(()),
+            RData::HINFO(ref hinfo) => rdata::hinfo::emit(encoder, hinfo),
             // to_lowercase for rfc4034 and rfc6840
             RData::MX(ref mx) => rdata::mx::emit(encoder, mx),
             RData::NAPTR(ref naptr) => {
@@ -816,10 +963,13 @@ impl RData {
             RData::SSHFP(ref sshfp) => {
                 encoder.with_canonical_names(|encoder| rdata::sshfp::emit(encoder, sshfp))
             }
+            RData::HTTPS(ref svcb) | RData::SVCB(ref svcb) => rdata::svcb::emit(encoder, svcb),
+            RData::TKEY(ref tkey) => rdata::tkey::emit(encoder, tkey),
             RData::TLSA(ref tlsa) => {
                 encoder.with_canonical_names(|encoder| rdata::tlsa::emit(encoder, tlsa))
             }
             RData::TXT(ref txt) => rdata::txt::emit(encoder, txt),
+            RData::URI(ref uri) => rdata::uri::emit(encoder, uri),
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => encoder.with_canonical_names(|encoder| rdata.emit(encoder)),
             RData::Unknown { ref rdata, .. } => rdata::null::emit(encoder, rdata),
@@ -834,6 +984,10 @@ impl RData {
             RData::ANAME(..) => RecordType::ANAME,
             RData::CAA(..) => RecordType::CAA,
             RData::CNAME(..) => RecordType::CNAME,
+            RData::DHCID(..) => RecordType::DHCID,
+            RData::DNAME(..) => RecordType::DNAME,
+            RData::HINFO(..) => RecordType::HINFO,
+            RData::HTTPS(..) => RecordType::HTTPS,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
             RData::NS(..) => RecordType::NS,
@@ -844,8 +998,11 @@ impl RData {
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::SSHFP(..) => RecordType::SSHFP,
+            RData::SVCB(..) => RecordType::SVCB,
+            RData::TKEY(..) => RecordType::TKEY,
             RData::TLSA(..) => RecordType::TLSA,
             RData::TXT(..) => RecordType::TXT,
+            RData::URI(..) => RecordType::URI,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => RecordType::DNSSEC(DNSSECRData::to_record_type(rdata)),
             RData::Unknown { code, .. } => RecordType::Unknown(code),
@@ -863,6 +1020,135 @@ impl RData {
     }
 }
 
+impl fmt::Display for RData {
+    /// Formats the RData in the presentation format of master files, RFC 1035 section 5.1
+    ///
+    /// Types without a supported presentation format use the generic `\# <length> <hex>` format of
+    ///  [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RData::A(ref address) => write!(f, "{}", address),
+            RData::AAAA(ref address) => write!(f, "{}", address),
+            RData::ANAME(ref name)
+            | RData::CNAME(ref name)
+            | RData::DNAME(ref name)
+            | RData::NS(ref name)
+            | RData::PTR(ref name) => write!(f, "{}", name),
+            RData::CAA(..) => {
+                // flags, tag length, tag and the remaining value, see RFC 6844 section 5.1.1
+                let bytes = self.to_bytes();
+                let tag_end = bytes
+                    .get(1)
+                    .map_or(usize::max_value(), |len| 2 + *len as usize);
+                if tag_end > bytes.len() {
+                    return fmt_generic(f, &bytes);
+                }
+
+                write!(
+                    f,
+                    "{} {} ",
+                    bytes[0],
+                    String::from_utf8_lossy(&bytes[2..tag_end])
+                )?;
+                fmt_character_string(f, &bytes[tag_end..])
+            }
+            RData::HINFO(ref hinfo) => {
+                fmt_character_string(f, hinfo.cpu())?;
+                f.write_str(" ")?;
+                fmt_character_string(f, hinfo.os())
+            }
+            RData::MX(ref mx) => write!(f, "{} {}", mx.preference(), mx.exchange()),
+            RData::NAPTR(ref naptr) => {
+                write!(f, "{} {} ", naptr.order(), naptr.preference())?;
+                fmt_character_string(f, naptr.flags())?;
+                f.write_str(" ")?;
+                fmt_character_string(f, naptr.services())?;
+                f.write_str(" ")?;
+                fmt_character_string(f, naptr.regexp())?;
+                write!(f, " {}", naptr.replacement())
+            }
+            RData::SOA(ref soa) => write!(
+                f,
+                "{} {} {} {} {} {} {}",
+                soa.mname(),
+                soa.rname(),
+                soa.serial(),
+                soa.refresh(),
+                soa.retry(),
+                soa.expire(),
+                soa.minimum()
+            ),
+            RData::SRV(ref srv) => write!(
+                f,
+                "{} {} {} {}",
+                srv.priority(),
+                srv.weight(),
+                srv.port(),
+                srv.target()
+            ),
+            RData::SSHFP(ref sshfp) => {
+                let algorithm: u8 = sshfp.algorithm().into();
+                let fingerprint_type: u8 = sshfp.fingerprint_type().into();
+                write!(f, "{} {} ", algorithm, fingerprint_type)?;
+                fmt_hex(f, sshfp.fingerprint())
+            }
+            RData::TLSA(ref tlsa) => {
+                write!(
+                    f,
+                    "{} {} {} ",
+                    u8::from(*tlsa.cert_usage()),
+                    u8::from(*tlsa.selector()),
+                    u8::from(*tlsa.matching())
+                )?;
+                fmt_hex(f, tlsa.cert_data())
+            }
+            RData::TXT(ref txt) => {
+                for (i, data) in txt.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    fmt_character_string(f, data)?;
+                }
+                Ok(())
+            }
+            RData::URI(ref uri) => {
+                write!(f, "{} {} ", uri.priority(), uri.weight())?;
+                fmt_character_string(f, uri.target().as_bytes())
+            }
+            #[cfg(feature = "dnssec")]
+            RData::DNSSEC(DNSSECRData::DS(ref ds)) => write!(f, "{}", ds),
+            _ => fmt_generic(f, &self.to_bytes()),
+        }
+    }
+}
+
+/// Writes the data in the generic format for unknown types, RFC 3597 section 5
+fn fmt_generic(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    write!(f, "\\# {}", data.len())?;
+    if !data.is_empty() {
+        f.write_str(" ")?;
+        fmt_hex(f, data)?;
+    }
+    Ok(())
+}
+
+/// Writes the data as a quoted character-string, escaping quotes, backslashes and non-printable octets
+fn fmt_character_string(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    for &b in data {
+        match b {
+            b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+            0x20..=0x7E => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{:03}", b)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+fn fmt_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    f.write_str(&hex::encode(data))
+}
+
 impl PartialOrd<RData> for RData {
     fn partial_cmp(&self, other: &RData) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -903,6 +1189,7 @@ mod tests {
 
     use super::*;
     use crate::rr::domain::Name;
+    use crate::rr::rdata::svcb::SvcParamKey;
     use crate::rr::rdata::{MX, SOA, SRV, TXT};
     use crate::serialize::binary::bin_tests::test_emit_data_set;
     #[allow(clippy::useless_attribute)]
@@ -984,6 +1271,14 @@ mod tests {
                     b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0,
                 ],
             ),
+            (
+                RData::HTTPS(SVCB::new(
+                    1,
+                    Name::root(),
+                    vec![(SvcParamKey::Alpn, b"\x02h2".to_vec())],
+                )),
+                vec![0x00, 0x01, 0, 0x00, 0x01, 0x00, 0x03, 2, b'h', b'2'],
+            ),
         ]
     }
 
@@ -1079,6 +1374,10 @@ mod tests {
             RData::ANAME(..) => RecordType::ANAME,
             RData::CAA(..) => RecordType::CAA,
             RData::CNAME(..) => RecordType::CNAME,
+            RData::DHCID(..) => RecordType::DHCID,
+            RData::DNAME(..) => RecordType::DNAME,
+            RData::HINFO(..) => RecordType::HINFO,
+            RData::HTTPS(..) => RecordType::HTTPS,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
             RData::NS(..) => RecordType::NS,
@@ -1089,8 +1388,11 @@ mod tests {
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::SSHFP(..) => RecordType::SSHFP,
+            RData::SVCB(..) => RecordType::SVCB,
+            RData::TKEY(..) => RecordType::TKEY,
             RData::TLSA(..) => RecordType::TLSA,
             RData::TXT(..) => RecordType::TXT,
+            RData::URI(..) => RecordType::URI,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => RecordType::DNSSEC(rdata.to_record_type()),
             RData::Unknown { code, .. } => RecordType::Unknown(code),
@@ -1102,4 +1404,85 @@ mod tests {
     fn test_write_to() {
         test_emit_data_set(get_data(), |e, d| d.emit(e));
     }
+
+    #[test]
+    fn test_display() {
+        use crate::rr::rdata::{CAA, HINFO, NULL, URI};
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let display = [
+            (RData::CNAME(name.clone()), "www.example.com."),
+            (RData::MX(MX::new(10, name.clone())), "10 www.example.com."),
+            (
+                RData::SOA(SOA::new(
+                    Name::from_str("ns.example.com.").unwrap(),
+                    Name::from_str("hostmaster.example.com.").unwrap(),
+                    2019010101,
+                    7200,
+                    3600,
+                    1_209_600,
+                    300,
+                )),
+                "ns.example.com. hostmaster.example.com. 2019010101 7200 3600 1209600 300",
+            ),
+            (
+                RData::SRV(SRV::new(1, 2, 443, name.clone())),
+                "1 2 443 www.example.com.",
+            ),
+            (
+                RData::TXT(TXT::new(vec![
+                    "v=spf1 -all".to_string(),
+                    "say \"hi\"\n".to_string(),
+                ])),
+                "\"v=spf1 -all\" \"say \\\"hi\\\"\\010\"",
+            ),
+            (
+                RData::TXT(TXT::from_bytes(vec![b"a\x00b", &[0xFF]])),
+                "\"a\\000b\" \"\\255\"",
+            ),
+            (
+                RData::CAA(CAA::new_issue(
+                    false,
+                    Some(Name::parse("ca.example.net", None).unwrap()),
+                    vec![],
+                )),
+                "0 issue \"ca.example.net\"",
+            ),
+            (
+                RData::URI(URI::new(10, 1, "ftp://ftp1.example.com/public".to_string())),
+                "10 1 \"ftp://ftp1.example.com/public\"",
+            ),
+            (
+                RData::NULL(NULL::with(vec![0xDE, 0xAD, 0xBE, 0xEF])),
+                "\\# 4 DEADBEEF",
+            ),
+            (RData::HINFO(HINFO::rfc8482()), "\"RFC8482\" \"\""),
+        ];
+
+        for (rdata, expect) in display.iter() {
+            assert_eq!(rdata.to_string(), *expect);
+        }
+    }
+
+    #[test]
+    fn test_dname_not_compressed() {
+        let name = Name::from_str("Example.com.").unwrap();
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = BinEncoder::new(&mut bytes);
+            name.emit(&mut encoder).unwrap();
+            RData::DNAME(name.clone()).emit(&mut encoder).unwrap();
+            RData::CNAME(name.clone()).emit(&mut encoder).unwrap();
+        }
+
+        // the DNAME target is written out in full, with its case, while the CNAME is a pointer
+        assert_eq!(&bytes[13..26], &bytes[..13]);
+        assert_eq!(&bytes[26..], &[0xC0, 0x00]);
+
+        let mut decoder = BinDecoder::new(&bytes[13..26]);
+        assert_eq!(
+            RData::read(&mut decoder, RecordType::DNAME, Restrict::new(13)).unwrap(),
+            RData::DNAME(name)
+        );
+    }
 }
@@ -55,9 +55,15 @@ pub enum RecordType {
     //  CERT,       //	37	RFC 4398	Certificate record
     /// RFC 1035[1] Canonical name record
     CNAME,
-    //  DHCID,      //	49	RFC 4701	DHCP identifier
-    //  DNAME,      //	39	RFC 2672	Delegation Name
+    /// RFC 4701 DHCP identifier
+    DHCID,
+    /// RFC 6672 Delegation name
+    DNAME,
+    /// RFC 1035[1] Host information
+    HINFO,
     //  HIP,        //	55	RFC 5205	Host Identity Protocol
+    /// RFC 9460 HTTPS service binding
+    HTTPS,
     //  IPSECKEY,   //	45	RFC 4025	IPsec Key
     /// RFC 1996 Incremental Zone Transfer
     IXFR,
@@ -84,13 +90,17 @@ pub enum RecordType {
     SRV,
     /// RFC 4255 SSH Public Key Fingerprint
     SSHFP,
+    /// RFC 9460 General purpose service binding
+    SVCB,
     //  TA,         //	32768	N/A	DNSSEC Trust Authorities
-    //  TKEY,       //	249	RFC 2930	Secret key record
+    /// RFC 2930 Secret key record
+    TKEY,
     /// RFC 6698 TLSA certificate association
     TLSA,
-    //  TSIG,       //	250	RFC 2845	Transaction Signature
     /// RFC 1035[1] Text record
     TXT,
+    /// RFC 7553 Uniform Resource Identifier
+    URI,
 
     /// A DNSSEC- or SIG(0)- specific record type.
     ///
@@ -133,6 +143,33 @@ impl RecordType {
             _ => false,
         }
     }
+
+    /// Returns true if this is a type of DNSSEC, RFC 4034, RFC 5155 and RFC 7344
+    ///
+    /// These are DNSKEY, RRSIG, DS, NSEC, NSEC3, NSEC3PARAM, CDS and CDNSKEY. KEY, SIG and TSIG
+    ///  are not, they secure transactions. This does not depend on the `dnssec` feature.
+    pub fn is_dnssec(self) -> bool {
+        match self {
+            #[cfg(feature = "dnssec")]
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY)
+            | RecordType::DNSSEC(DNSSECRecordType::DS)
+            | RecordType::DNSSEC(DNSSECRecordType::NSEC)
+            | RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+            | RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM)
+            | RecordType::DNSSEC(DNSSECRecordType::RRSIG) => true,
+            // CDS and CDNSKEY have no variants yet
+            RecordType::Unknown(59) | RecordType::Unknown(60) => true,
+            // without the dnssec feature the other types are unknown too
+            #[cfg(not(feature = "dnssec"))]
+            RecordType::Unknown(43)
+            | RecordType::Unknown(46)
+            | RecordType::Unknown(47)
+            | RecordType::Unknown(48)
+            | RecordType::Unknown(50)
+            | RecordType::Unknown(51) => true,
+            _ => false,
+        }
+    }
 }
 
 impl FromStr for RecordType {
@@ -155,6 +192,10 @@ impl FromStr for RecordType {
             "ANAME" => Ok(RecordType::ANAME),
             "CAA" => Ok(RecordType::CAA),
             "CNAME" => Ok(RecordType::CNAME),
+            "DHCID" => Ok(RecordType::DHCID),
+            "DNAME" => Ok(RecordType::DNAME),
+            "HINFO" => Ok(RecordType::HINFO),
+            "HTTPS" => Ok(RecordType::HTTPS),
             "NULL" => Ok(RecordType::NULL),
             "MX" => Ok(RecordType::MX),
             "NAPTR" => Ok(RecordType::NAPTR),
@@ -164,10 +205,13 @@ impl FromStr for RecordType {
             "SOA" => Ok(RecordType::SOA),
             "SRV" => Ok(RecordType::SRV),
             "SSHFP" => Ok(RecordType::SSHFP),
+            "SVCB" => Ok(RecordType::SVCB),
             "TLSA" => Ok(RecordType::TLSA),
             "TXT" => Ok(RecordType::TXT),
+            "URI" => Ok(RecordType::URI),
             "ANY" | "*" => Ok(RecordType::ANY),
             "AXFR" => Ok(RecordType::AXFR),
+            "IXFR" => Ok(RecordType::IXFR),
             _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
         }
     }
@@ -190,8 +234,13 @@ impl From<u16> for RecordType {
             65305 => RecordType::ANAME,
             255 => RecordType::ANY,
             252 => RecordType::AXFR,
+            251 => RecordType::IXFR,
             257 => RecordType::CAA,
             5 => RecordType::CNAME,
+            49 => RecordType::DHCID,
+            39 => RecordType::DNAME,
+            13 => RecordType::HINFO,
+            65 => RecordType::HTTPS,
             0 => RecordType::ZERO,
             15 => RecordType::MX,
             35 => RecordType::NAPTR,
@@ -203,8 +252,11 @@ impl From<u16> for RecordType {
             6 => RecordType::SOA,
             33 => RecordType::SRV,
             44 => RecordType::SSHFP,
+            64 => RecordType::SVCB,
+            249 => RecordType::TKEY,
             52 => RecordType::TLSA,
             16 => RecordType::TXT,
+            256 => RecordType::URI,
             #[cfg(feature = "dnssec")]
             48/*DNSKEY*/ |
             43/*DS*/ |
@@ -213,7 +265,8 @@ impl From<u16> for RecordType {
             50/*NSEC3*/|
             51/*NSEC3PARAM*/|
             46/*RRSIG*/|
-            24/*SIG*/ => RecordType::DNSSEC(DNSSECRecordType::from(value)),
+            24/*SIG*/|
+            250/*TSIG*/ => RecordType::DNSSEC(DNSSECRecordType::from(value)),
             // all unknown record types
             _ => RecordType::Unknown(value),
         }
@@ -261,6 +314,10 @@ impl From<RecordType> for &'static str {
             RecordType::AXFR => "AXFR",
             RecordType::CAA => "CAA",
             RecordType::CNAME => "CNAME",
+            RecordType::DHCID => "DHCID",
+            RecordType::DNAME => "DNAME",
+            RecordType::HINFO => "HINFO",
+            RecordType::HTTPS => "HTTPS",
             RecordType::ZERO => "",
             RecordType::IXFR => "IXFR",
             RecordType::MX => "MX",
@@ -273,8 +330,11 @@ impl From<RecordType> for &'static str {
             RecordType::SOA => "SOA",
             RecordType::SRV => "SRV",
             RecordType::SSHFP => "SSHFP",
+            RecordType::SVCB => "SVCB",
+            RecordType::TKEY => "TKEY",
             RecordType::TLSA => "TLSA",
             RecordType::TXT => "TXT",
+            RecordType::URI => "URI",
             #[cfg(feature = "dnssec")]
             RecordType::DNSSEC(rt) => rt.into(),
             RecordType::Unknown(_) => "Unknown",
@@ -302,6 +362,10 @@ impl From<RecordType> for u16 {
             RecordType::AXFR => 252,
             RecordType::CAA => 257,
             RecordType::CNAME => 5,
+            RecordType::DHCID => 49,
+            RecordType::DNAME => 39,
+            RecordType::HINFO => 13,
+            RecordType::HTTPS => 65,
             RecordType::ZERO => 0,
             RecordType::IXFR => 251,
             RecordType::MX => 15,
@@ -314,8 +378,11 @@ impl From<RecordType> for u16 {
             RecordType::SOA => 6,
             RecordType::SRV => 33,
             RecordType::SSHFP => 44,
+            RecordType::SVCB => 64,
+            RecordType::TKEY => 249,
             RecordType::TLSA => 52,
             RecordType::TXT => 16,
+            RecordType::URI => 256,
             #[cfg(feature = "dnssec")]
             RecordType::DNSSEC(rt) => rt.into(),
             RecordType::Unknown(code) => code,
@@ -383,3 +450,28 @@ fn test_order() {
 
     assert_eq!(ordered, unordered);
 }
+
+#[test]
+fn test_is_dnssec() {
+    for code in &[43, 46, 47, 48, 50, 51, 59, 60] {
+        let rtype = RecordType::from(*code);
+        assert!(rtype.is_dnssec(), "{} should be DNSSEC", rtype);
+    }
+
+    for rtype in &[RecordType::A, RecordType::SOA, RecordType::OPT] {
+        assert!(!rtype.is_dnssec(), "{} should not be DNSSEC", rtype);
+    }
+
+    // SIG(0), KEY and TSIG secure transactions, not zone data
+    for code in &[24, 25, 250] {
+        assert!(!RecordType::from(*code).is_dnssec());
+    }
+}
+
+#[test]
+fn test_zone_transfer_types() {
+    for rtype in &[RecordType::AXFR, RecordType::IXFR] {
+        assert_eq!(RecordType::from(u16::from(*rtype)), *rtype);
+        assert_eq!(RecordType::from_str(&rtype.to_string()).unwrap(), *rtype);
+    }
+}
@@ -17,9 +17,12 @@
 //! resource record implementation
 
 use std::cmp::Ordering;
+use std::fmt;
 
 use crate::error::*;
 use crate::rr::dns_class::DNSClass;
+#[cfg(feature = "dnssec")]
+use crate::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType};
 use crate::rr::rdata::NULL;
 #[allow(deprecated)]
 use crate::rr::IntoRecordSet;
@@ -215,6 +218,17 @@ impl Record {
         self.ttl
     }
 
+    /// Returns a copy of the record with the TTL reduced by the time it was cached
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed_secs` - the seconds since the record was cached, the TTL is clamped at zero
+    pub fn with_remaining_ttl(&self, elapsed_secs: u32) -> Record {
+        let mut record = self.clone();
+        record.set_ttl(self.ttl.saturating_sub(elapsed_secs));
+        record
+    }
+
     /// Returns the Record Data, i.e. the record information
     pub fn rdata(&self) -> &RData {
         &self.rdata
@@ -229,6 +243,18 @@ impl Record {
     pub fn unwrap_rdata(self) -> RData {
         self.rdata
     }
+
+    /// Returns true if the other Record is the same member of an RRset as this one
+    ///
+    /// The name (case insensitive), type, class and rdata are compared, the TTL is not. During
+    ///  TTL transitions records in an RRset may differ only by TTL, these should be grouped together,
+    ///  e.g. for canonical ordering or caching.
+    pub fn same_rrset(&self, other: &Self) -> bool {
+        self.name_labels == other.name_labels
+            && self.rr_type == other.rr_type
+            && self.dns_class == other.dns_class
+            && self.rdata == other.rdata
+    }
 }
 
 #[allow(deprecated)]
@@ -240,6 +266,15 @@ impl IntoRecordSet for Record {
 
 impl BinEncodable for Record {
     fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        // the rdata is written in the format of its own type, which must be that of the record
+        if !self.is_rdata_of_type() {
+            return Err(ProtoErrorKind::UnexpectedRData {
+                record_type: self.rr_type,
+                rdata_type: self.rdata.to_record_type(),
+            }
+            .into());
+        }
+
         self.name_labels.emit(encoder)?;
         self.rr_type.emit(encoder)?;
         self.dns_class.emit(encoder)?;
@@ -254,7 +289,9 @@ impl BinEncodable for Record {
 
         // get the length written
         let len = encoder.len_since_place(&place);
-        assert!(len <= u16::max_value() as usize);
+        if len > u16::max_value() as usize {
+            return Err(format!("rdata length exceeds u16::MAX: {}", len).into());
+        }
 
         // replace the location with the length
         place.replace(encoder, len as u16)?;
@@ -262,6 +299,26 @@ impl BinEncodable for Record {
     }
 }
 
+impl Record {
+    /// Returns true if the RData has the format of the type of the record
+    fn is_rdata_of_type(&self) -> bool {
+        match self.rdata {
+            // empty rdata, as in updates, RFC 2136, has no type of its own
+            RData::ZERO => true,
+            RData::NULL(ref null) if null.anything().map_or(true, Vec::is_empty) => true,
+            // RRSIG records share the format of SIG records, RFC 4034
+            #[cfg(feature = "dnssec")]
+            RData::DNSSEC(DNSSECRData::SIG(..)) => {
+                self.rr_type == RecordType::DNSSEC(DNSSECRecordType::SIG)
+                    || self.rr_type == RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+            }
+            // the variants of unknown types, and of types without rdata of their own, e.g. HINFO,
+            //  map to the value of the type
+            ref rdata => u16::from(rdata.to_record_type()) == u16::from(self.rr_type),
+        }
+    }
+}
+
 impl<'r> BinDecodable<'r> for Record {
     /// parse a resource record line example:
     ///  WARNING: the record_bytes is 100% consumed and destroyed in this parsing process
@@ -303,9 +360,12 @@ impl<'r> BinDecodable<'r> for Record {
         //                octets of the RDATA field.
         let rd_length: u16 = decoder
             .read_u16()?
-            .verify_unwrap(|u| (*u as usize) <= decoder.len())
-            .map_err(|_| {
-                ProtoError::from("rdata length too large for remaining bytes, need: {} remain: {}")
+            .verify_unwrap(|u| (*u as usize) <= decoder.remaining())
+            .map_err(|len| {
+                ProtoError::from(ProtoErrorKind::RDataLengthExceedsBuffer {
+                    len: len as usize,
+                    remaining: decoder.remaining(),
+                })
             })?;
 
         // this is to handle updates, RFC 2136, which uses 0 to indicate certain aspects of
@@ -317,7 +377,8 @@ impl<'r> BinDecodable<'r> for Record {
             //                resource.  The format of this information varies
             //                according to the TYPE and CLASS of the resource record.
             // Adding restrict to the rdata length because it's used for many calculations later
-            //  and must be validated before hand
+            //  and must be validated before hand. The rdata parser is bounded to RDLENGTH octets,
+            //  and must consume all of them.
             RData::read(decoder, record_type, Restrict::new(rd_length))?
         };
 
@@ -352,6 +413,21 @@ impl PartialEq for Record {
     }
 }
 
+impl fmt::Display for Record {
+    /// Formats the record as a line of a master file, the fields are separated by tabs
+    ///
+    /// ```text
+    /// www.example.com.    86400    IN    A    93.184.216.34
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.name_labels, self.ttl, self.dns_class, self.rr_type, self.rdata
+        )
+    }
+}
+
 /// returns the value of the compare if the items are greater or lesser, but continues on equal
 macro_rules! compare_or_equal {
     ($x:ident, $y:ident, $z:ident) => {
@@ -448,6 +524,246 @@ mod tests {
         assert_eq!(got, record);
     }
 
+    #[test]
+    fn test_emit_wire_format() {
+        let record = Record::from_rdata(
+            Name::from_str("a.").unwrap(),
+            300,
+            RData::A(Ipv4Addr::new(192, 168, 0, 1)),
+        );
+
+        let mut vec_bytes: Vec<u8> = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut vec_bytes);
+            record.emit(&mut encoder).unwrap();
+        }
+
+        assert_eq!(
+            vec_bytes,
+            vec![
+                1, b'a', 0, // name
+                0, 1, // type
+                0, 1, // class
+                0, 0, 1, 44, // ttl
+                0, 4, // rdlength
+                192, 168, 0, 1, // rdata
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emit_unexpected_rdata() {
+        let mut record = Record::from_rdata(
+            Name::from_str("a.").unwrap(),
+            300,
+            RData::A(Ipv4Addr::new(192, 168, 0, 1)),
+        );
+        record.set_rr_type(RecordType::AAAA);
+
+        let mut vec_bytes: Vec<u8> = Vec::with_capacity(512);
+        match *record
+            .emit(&mut BinEncoder::new(&mut vec_bytes))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::UnexpectedRData {
+                record_type: RecordType::AAAA,
+                rdata_type: RecordType::A,
+            } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+        assert!(vec_bytes.is_empty());
+
+        // empty rdata, of update messages, has no type of its own
+        record.set_rdata(RData::NULL(NULL::new()));
+        record
+            .emit(&mut BinEncoder::new(&mut vec_bytes))
+            .expect("failed to emit empty rdata");
+
+        // but NULL rdata with content is only of the NULL type
+        record.set_rdata(RData::NULL(NULL::with(vec![192, 168, 0, 1])));
+        let mut vec_bytes: Vec<u8> = Vec::with_capacity(512);
+        match *record
+            .emit(&mut BinEncoder::new(&mut vec_bytes))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::UnexpectedRData {
+                record_type: RecordType::AAAA,
+                rdata_type: RecordType::NULL,
+            } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        record.set_rr_type(RecordType::NULL);
+        record
+            .emit(&mut BinEncoder::new(&mut vec_bytes))
+            .expect("failed to emit NULL rdata");
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_emit_unexpected_rdata_dnskey() {
+        let mut record = Record::from_rdata(
+            Name::from_str("a.").unwrap(),
+            300,
+            RData::A(Ipv4Addr::new(192, 168, 0, 1)),
+        );
+        record.set_rr_type(RecordType::DNSSEC(DNSSECRecordType::DNSKEY));
+
+        let mut vec_bytes: Vec<u8> = Vec::with_capacity(512);
+        match *record
+            .emit(&mut BinEncoder::new(&mut vec_bytes))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::UnexpectedRData { rdata_type, .. } => {
+                assert_eq!(rdata_type, RecordType::A)
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn test_read_rdata_length_mismatch() {
+        // a CNAME record claiming four octets of rdata, for a name of three octets
+        let bytes = [1, b'a', 0, 0, 5, 0, 1, 0, 0, 1, 44, 0, 4, 1, b'a', 0, 0];
+        let mut decoder = BinDecoder::new(&bytes);
+
+        match *Record::read(&mut decoder).unwrap_err().kind() {
+            ProtoErrorKind::IncorrectRDataLengthRead { read: 3, len: 4 } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        // the length of A records is checked before the address is read, the rdata length is
+        //  reported as read against the four octets of an address
+        let bytes = [1, b'a', 0, 0, 1, 0, 1, 0, 0, 1, 44, 0, 5, 192, 168, 0, 1, 0];
+        let mut decoder = BinDecoder::new(&bytes);
+
+        match *Record::read(&mut decoder).unwrap_err().kind() {
+            ProtoErrorKind::IncorrectRDataLengthRead { read: 5, len: 4 } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn test_read_rdata_beyond_length() {
+        // a CNAME record claiming two octets of rdata, for a name of three octets
+        let bytes = [1, b'a', 0, 0, 5, 0, 1, 0, 0, 1, 44, 0, 2, 1, b'a', 0, 0];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(Record::read(&mut decoder).is_err());
+
+        // the URI target ends with the rdata
+        let bytes = [
+            1, b'a', 0, 1, 0, 0, 1, 0, 0, 1, 44, 0, 5, 0, 10, 0, 1, b'x', b'y', b'z',
+        ];
+        let mut decoder = BinDecoder::new(&bytes);
+        let record = Record::read(&mut decoder).expect("failed to read URI");
+        match *record.rdata() {
+            RData::URI(ref uri) => assert_eq!(uri.target(), "x"),
+            ref rdata => panic!("unexpected rdata: {:?}", rdata),
+        }
+        assert_eq!(decoder.len(), 2);
+    }
+
+    #[test]
+    fn test_read_rdata_trailing_bytes() {
+        // an A record with five octets of rdata, one more than the address
+        let bytes = [
+            1, b'a', 0, 0, 1, 0, 1, 0, 0, 1, 44, 0, 5, 192, 168, 0, 1, 0xFF, 0,
+        ];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(decoder.is_strict());
+        assert!(Record::read(&mut decoder).is_err());
+
+        let mut decoder = BinDecoder::new(&bytes);
+        decoder.set_strict(false);
+        let record = Record::read(&mut decoder).expect("failed to read A");
+        assert_eq!(*record.rdata(), RData::A(Ipv4Addr::new(192, 168, 0, 1)));
+        assert_eq!(decoder.len(), 1);
+
+        // a CNAME record with an octet after the name
+        let bytes = [1, b'a', 0, 0, 5, 0, 1, 0, 0, 1, 44, 0, 4, 1, b'b', 0, 0xFF];
+        let mut decoder = BinDecoder::new(&bytes);
+        decoder.set_strict(false);
+        let record = Record::read(&mut decoder).expect("failed to read CNAME");
+        assert_eq!(
+            *record.rdata(),
+            RData::CNAME(Name::from_ascii("b.").unwrap())
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_read_rdata_length_exceeds_buffer() {
+        // a DNSKEY record claiming 60000 octets of rdata, with only a few left in the buffer
+        let bytes = [
+            1, b'a', 0, 0, 48, 0, 1, 0, 0, 1, 44, 0xEA, 0x60, 1, 0, 3, 8, 1, 2, 3,
+        ];
+        let mut decoder = BinDecoder::new(&bytes);
+
+        match *Record::read(&mut decoder).unwrap_err().kind() {
+            ProtoErrorKind::RDataLengthExceedsBuffer {
+                len: 60000,
+                remaining: 7,
+            } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        // also when the rdata is read directly
+        let mut decoder = BinDecoder::new(&bytes[13..]);
+        let record_type = RecordType::from(48);
+        match *RData::read(&mut decoder, record_type, Restrict::new(60000))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::RDataLengthExceedsBuffer {
+                len: 60000,
+                remaining: 7,
+            } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "dnssec")]
+    fn test_emit_and_read_dnskey() {
+        use crate::rr::dnssec::rdata::{DNSSECRData, DNSKEY};
+        use crate::rr::dnssec::Algorithm;
+
+        let record = Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RData::DNSSEC(DNSSECRData::DNSKEY(DNSKEY::new(
+                true,
+                true,
+                false,
+                Algorithm::RSASHA256,
+                vec![0x03, 0x01, 0x00, 0x01, 0xAB, 0xCD, 0xEF],
+            ))),
+        );
+
+        let mut vec_bytes: Vec<u8> = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut vec_bytes);
+            record.emit(&mut encoder).unwrap();
+        }
+
+        // flags, protocol and algorithm, followed by the key
+        let rdata_len = 4 + 7;
+        assert_eq!(
+            &vec_bytes[vec_bytes.len() - rdata_len - 2..vec_bytes.len() - rdata_len],
+            &[0, rdata_len as u8]
+        );
+
+        let mut decoder = BinDecoder::new(&vec_bytes);
+        let got = Record::read(&mut decoder).unwrap();
+
+        assert!(decoder.is_empty());
+        assert_eq!(got, record);
+        assert_eq!(got.ttl(), 3600);
+    }
+
     #[test]
     fn test_order() {
         let mut record = Record::new();
@@ -483,4 +799,64 @@ mod tests {
             assert_eq!(r.cmp(g), Ordering::Less);
         }
     }
+
+    #[test]
+    fn test_display() {
+        let mut record = Record::new();
+        record
+            .set_name(Name::from_str("www.example.com.").unwrap())
+            .set_rr_type(RecordType::A)
+            .set_dns_class(DNSClass::IN)
+            .set_ttl(86400)
+            .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+
+        assert_eq!(
+            record.to_string(),
+            "www.example.com.\t86400\tIN\tA\t93.184.216.34"
+        );
+    }
+
+    #[test]
+    fn test_same_rrset() {
+        let mut record = Record::new();
+        record
+            .set_name(Name::from_str("www.example.com").unwrap())
+            .set_rr_type(RecordType::A)
+            .set_dns_class(DNSClass::IN)
+            .set_ttl(5)
+            .set_rdata(RData::A(Ipv4Addr::new(192, 168, 0, 1)));
+
+        let mut other_ttl = record.clone();
+        other_ttl.set_ttl(3600);
+        assert!(record.same_rrset(&other_ttl));
+
+        let mut other_case = other_ttl.clone();
+        other_case.set_name(Name::from_str("WWW.Example.COM").unwrap());
+        assert!(record.same_rrset(&other_case));
+
+        let mut other_class = record.clone();
+        other_class.set_dns_class(DNSClass::CH);
+        assert!(!record.same_rrset(&other_class));
+
+        let mut other_rdata = record.clone();
+        other_rdata.set_rdata(RData::A(Ipv4Addr::new(192, 168, 0, 2)));
+        assert!(!record.same_rrset(&other_rdata));
+    }
+
+    #[test]
+    fn test_with_remaining_ttl() {
+        let record = Record::from_rdata(
+            Name::from_str("www.example.com").unwrap(),
+            60,
+            RData::A(Ipv4Addr::new(192, 168, 0, 1)),
+        );
+
+        let cached = record.with_remaining_ttl(10);
+        assert_eq!(cached.ttl(), 50);
+        assert!(cached.same_rrset(&record));
+        assert_eq!(cached.rdata(), record.rdata());
+
+        assert_eq!(record.with_remaining_ttl(60).ttl(), 0);
+        assert_eq!(record.with_remaining_ttl(3600).ttl(), 0);
+    }
 }
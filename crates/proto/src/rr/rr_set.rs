@@ -116,6 +116,17 @@ impl RecordSet {
         }
     }
 
+    /// Sets the TTL of the set and of every record to the minimum TTL of the records
+    ///
+    /// The records of an RRSet, e.g. from multiple sources or zone transfers, may have different
+    ///  TTLs, in which case [RFC 2181](https://tools.ietf.org/html/rfc2181#section-5.2) recommends
+    ///  using the lowest. The TTL of an empty set is not changed.
+    pub fn normalize_ttl(&mut self) {
+        if let Some(ttl) = self.records.iter().map(Record::ttl).min() {
+            self.set_ttl(ttl);
+        }
+    }
+
     /// Returns the time-to-live for the record.
     ///
     /// # Return value
@@ -931,4 +942,28 @@ mod test {
             }
         }));
     }
+
+    #[test]
+    fn test_normalize_ttl() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut rr_set = RecordSet::new(&name, RecordType::A, 0);
+
+        rr_set.normalize_ttl();
+        assert_eq!(rr_set.ttl(), 0);
+
+        for (ttl, octet) in &[(300, 1), (200, 2), (400, 3)] {
+            let record = Record::from_rdata(
+                name.clone(),
+                *ttl,
+                RData::A(Ipv4Addr::new(192, 0, 2, *octet)),
+            );
+            assert!(rr_set.insert(record, 0));
+        }
+        assert_eq!(rr_set.ttl(), 400);
+
+        rr_set.normalize_ttl();
+        assert_eq!(rr_set.ttl(), 200);
+        assert_eq!(rr_set.records_without_rrsigs().count(), 3);
+        assert!(rr_set.records_without_rrsigs().all(|r| r.ttl() == 200));
+    }
 }
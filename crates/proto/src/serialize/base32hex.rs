@@ -0,0 +1,120 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Base32 encoding with the extended hex alphabet, of the hashed owner names of NSEC3 records
+//!
+//! [RFC 4648](https://tools.ietf.org/html/rfc4648#section-7) defines the alphabet, `0-9A-V`,
+//!  which preserves the sort order of the encoded data. The owner names of
+//!  [RFC 5155](https://tools.ietf.org/html/rfc5155#section-3) omit the padding.
+
+use crate::error::*;
+
+const SYMBOLS: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// Encodes the data with the uppercase alphabet, without padding
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len() * 8 / 5 + 1);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for &b in data {
+        buffer = buffer << 8 | u16::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(SYMBOLS[usize::from(buffer >> bits & 0x1F)] as char);
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(SYMBOLS[usize::from(buffer << (5 - bits) & 0x1F)] as char);
+    }
+
+    encoded
+}
+
+/// Decodes data in upper or lower case, with or without padding
+///
+/// # Errors
+///
+/// If the data contains characters outside of the alphabet, or its length or trailing bits are not
+///  those of encoded data.
+pub fn decode(data: &str) -> ProtoResult<Vec<u8>> {
+    let data = data.trim_end_matches('=');
+
+    let mut decoded = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for c in data.chars() {
+        let value = c
+            .to_digit(32)
+            .ok_or_else(|| ProtoErrorKind::Msg(format!("invalid base32hex character: {}", c)))?;
+
+        buffer = buffer << 5 | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+
+    // a complete symbol may not be left over, and the bits of a partial one must be zero
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return Err(ProtoErrorKind::Message("invalid base32hex length").into());
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "CO"),
+            ("fo", "CPNG"),
+            ("foo", "CPNMU"),
+            ("foob", "CPNMUOG"),
+            ("fooba", "CPNMUOJ1"),
+            ("foobar", "CPNMUOJ1E8"),
+        ];
+
+        for &(data, encoded) in &vectors {
+            assert_eq!(encode(data.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), data.as_bytes());
+        }
+
+        // with padding, and in lowercase
+        assert_eq!(decode("CPNMUOJ1E8======").unwrap(), b"foobar");
+        assert_eq!(decode("cpnmuoj1e8").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn test_sha1_round_trip() {
+        let hash: Vec<u8> = (0..20).map(|i| i * 13).collect();
+        let encoded = encode(&hash);
+        assert_eq!(encoded.len(), 32);
+        assert_eq!(decode(&encoded).unwrap(), hash);
+
+        // H(example) of RFC 5155, appendix A
+        let hash = decode("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom").unwrap();
+        assert_eq!(hash.len(), 20);
+        assert_eq!(encode(&hash), "0P9MHAVEQVM6T7VBL5LOP2U3T2RP3TOM");
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode("CPNMW").is_err());
+        assert!(decode("CPN-").is_err());
+        // lengths that leave a complete symbol, or non-zero bits
+        assert!(decode("C").is_err());
+        assert!(decode("CPN").is_err());
+        assert!(decode("CP").is_err());
+    }
+}
@@ -27,6 +27,12 @@ use crate::serialize::binary::Restrict;
 pub struct BinDecoder<'a> {
     buffer: &'a [u8],
     index: usize,
+    /// the index up to which reads are allowed, see `read_bounded`
+    end: usize,
+    /// if bytes left unread within the bound of `read_bounded` are an error
+    strict: bool,
+    /// sorted offsets of the labels read so far, the only valid targets of name pointers
+    label_starts: Vec<u16>,
 }
 
 impl<'a> BinDecoder<'a> {
@@ -36,12 +42,32 @@ impl<'a> BinDecoder<'a> {
     ///
     /// * `buffer` - buffer from which all data will be read
     pub fn new(buffer: &'a [u8]) -> Self {
-        BinDecoder { buffer, index: 0 }
+        BinDecoder {
+            buffer,
+            index: 0,
+            end: buffer.len(),
+            strict: true,
+            label_starts: Vec::new(),
+        }
+    }
+
+    /// Sets if the decoder is strict, which it is by default
+    ///
+    /// A strict decoder fails `read_bounded` if the read leaves bytes within the bound, e.g.
+    ///  RDATA padded beyond what its record type needs. Otherwise these bytes are skipped.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns `true` if the decoder is strict, see `set_strict`
+    pub fn is_strict(&self) -> bool {
+        self.strict
     }
 
     /// Pop one byte from the buffer
     pub fn pop(&mut self) -> ProtoResult<Restrict<u8>> {
-        if self.index < self.buffer.len() {
+        if self.index < self.end {
             let byte = self.buffer[self.index];
             self.index += 1;
             Ok(Restrict::new(byte))
@@ -50,7 +76,7 @@ impl<'a> BinDecoder<'a> {
         }
     }
 
-    /// Returns the number of bytes in the buffer
+    /// Returns the number of bytes remaining in the buffer, after the current index
     ///
     /// ```
     /// use trust_dns_proto::serialize::binary::BinDecoder;
@@ -62,7 +88,15 @@ impl<'a> BinDecoder<'a> {
     /// assert_eq!(decoder.len(), 1);
     /// ```
     pub fn len(&self) -> usize {
-        self.buffer.len().saturating_sub(self.index)
+        self.remaining()
+    }
+
+    /// Returns the number of bytes left to read, up to the bound of `read_bounded`
+    ///
+    /// Within the bound of an RDATA this is the part of the RDATA not read yet, readers use it
+    ///  rather than computing it from the RDATA length and the index.
+    pub fn remaining(&self) -> usize {
+        self.end.saturating_sub(self.index)
     }
 
     /// Returns `true` if the buffer is empty
@@ -70,9 +104,11 @@ impl<'a> BinDecoder<'a> {
         self.len() == 0
     }
 
-    /// Peed one byte forward, without moving the current index forward
+    /// Peek one byte forward, without moving the current index forward
+    ///
+    /// Returns `None` at the end of the buffer
     pub fn peek(&self) -> Option<Restrict<u8>> {
-        if self.index < self.buffer.len() {
+        if self.index < self.end {
             Some(Restrict::new(self.buffer[self.index]))
         } else {
             None
@@ -85,14 +121,96 @@ impl<'a> BinDecoder<'a> {
     }
 
     /// This is a pretty efficient clone, as the buffer is never cloned, and only the index is set
-    ///  to the value passed in
+    ///  to the value passed in, the offsets of the labels read so far are copied
+    ///
+    /// The clone may read the whole buffer, the bound of `read_bounded` does not apply to it.
     pub fn clone(&self, index_at: u16) -> BinDecoder<'a> {
         BinDecoder {
             buffer: self.buffer,
             index: index_at as usize,
+            end: self.buffer.len(),
+            strict: self.strict,
+            label_starts: self.label_starts.clone(),
         }
     }
 
+    /// Reads with the decoder bounded to the next `len` bytes, e.g. to the RDATA of a record
+    ///
+    /// Within `read`, the decoder behaves as if the buffer ended after `len` bytes, so that reads
+    ///  past the bound fail, and `len()` returns the bytes left up to it. The bounds of nested calls
+    ///  must be within the outer bound.
+    ///
+    /// # Errors
+    ///
+    /// `RDataLengthExceedsBuffer` if there are less than `len` bytes left, the error of `read` if it
+    ///  fails, and `IncorrectRDataLengthRead` if it did not read exactly `len` bytes. If the decoder
+    ///  is not strict, bytes left unread after a successful `read` are skipped instead.
+    pub fn read_bounded<T, F>(&mut self, len: usize, read: F) -> ProtoResult<T>
+    where
+        F: FnOnce(&mut Self) -> ProtoResult<T>,
+    {
+        let remaining = self.remaining();
+        if len > remaining {
+            return Err(ProtoErrorKind::RDataLengthExceedsBuffer { len, remaining }.into());
+        }
+
+        let start = self.index;
+        let end = self.end;
+        self.end = start + len;
+        let result = read(self);
+        self.end = end;
+        let value = result?;
+
+        let read = self.index - start;
+        if read != len {
+            if self.strict {
+                return Err(ProtoErrorKind::IncorrectRDataLengthRead { read, len }.into());
+            }
+
+            debug!("skipping {} trailing bytes", len - read);
+            self.index = start + len;
+        }
+
+        Ok(value)
+    }
+
+    /// Reads at the index, then returns to the current one, e.g. for following name pointers
+    ///
+    /// Unlike on a `clone`, the offsets of the labels read so far are shared, not copied. The bound
+    ///  of `read_bounded` does not apply, e.g. to pointers out of the RDATA.
+    pub(crate) fn read_at<T, F>(&mut self, index_at: u16, read: F) -> ProtoResult<T>
+    where
+        F: FnOnce(&mut Self) -> ProtoResult<T>,
+    {
+        let index = self.index;
+        let end = self.end;
+        self.index = index_at as usize;
+        self.end = self.buffer.len();
+        let result = read(self);
+        self.index = index;
+        self.end = end;
+
+        result
+    }
+
+    /// Stores the current index as the start of a label, pointers in names may only point there
+    pub(crate) fn store_label_start(&mut self) {
+        // pointers have 14 bits for the offset
+        if self.index > 0x3FFF {
+            return;
+        }
+
+        let index = self.index as u16;
+        if let Err(pos) = self.label_starts.binary_search(&index) {
+            self.label_starts.insert(pos, index);
+        }
+    }
+
+    /// Returns true if a label starts at the index, see `store_label_start`
+    pub(crate) fn is_label_start(&self, index: u16) -> bool {
+        self.label_starts.binary_search(&index).is_ok()
+    }
+
     /// Reads a String from the buffer
     ///
     /// ```text
@@ -161,7 +279,7 @@ impl<'a> BinDecoder<'a> {
             .index
             .checked_add(len)
             .ok_or_else(|| ProtoError::from("invalid length for slice"))?;
-        if end > self.buffer.len() {
+        if end > self.end {
             return Err("buffer exhausted".into());
         }
         let slice: &'a [u8] = &self.buffer[self.index..end];
@@ -192,7 +310,9 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the u16 from the buffer
     pub fn read_u16(&mut self) -> ProtoResult<Restrict<u16>> {
-        Ok(self.read_slice(2)?.map(|s| u16::from_be_bytes([s[0], s[1]])))
+        Ok(self
+            .read_slice(2)?
+            .map(|s| u16::from_be_bytes([s[0], s[1]])))
     }
 
     /// Reads the next four bytes into i32.
@@ -204,7 +324,9 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the i32 from the buffer
     pub fn read_i32(&mut self) -> ProtoResult<Restrict<i32>> {
-        Ok(self.read_slice(4)?.map(|s| i32::from_be_bytes([s[0], s[1], s[2], s[3]])))
+        Ok(self
+            .read_slice(4)?
+            .map(|s| i32::from_be_bytes([s[0], s[1], s[2], s[3]])))
     }
 
     /// Reads the next four bytes into u32.
@@ -216,11 +338,20 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the u32 from the buffer
     pub fn read_u32(&mut self) -> ProtoResult<Restrict<u32>> {
-        Ok(self.read_slice(4)?.map(|s| u32::from_be_bytes([s[0], s[1], s[2], s[3]])))
+        Ok(self
+            .read_slice(4)?
+            .map(|s| u32::from_be_bytes([s[0], s[1], s[2], s[3]])))
+    }
+
+    /// Reads the next six bytes, in network byte order, into a u64 for the TSIG and TKEY times.
+    pub fn read_u48(&mut self) -> ProtoResult<Restrict<u64>> {
+        Ok(self
+            .read_slice(6)?
+            .map(|s| u64::from_be_bytes([0, 0, s[0], s[1], s[2], s[3], s[4], s[5]])))
     }
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -230,13 +361,13 @@ mod tests {
         let mut decoder = BinDecoder::new(deadbeef);
 
         let read = decoder.read_slice(4).expect("failed to read dead");
-        assert_eq!(read, "dead");
+        assert_eq!(read.unverified(), b"dead");
 
         let read = decoder.read_slice(2).expect("failed to read be");
-        assert_eq!(read, "be");
+        assert_eq!(read.unverified(), b"be");
 
         let read = decoder.read_slice(0).expect("failed to read nothing");
-        assert_eq!(read, "");
+        assert_eq!(read.unverified(), b"");
 
         // this should fail
         assert!(decoder.read_slice(3).is_err());
@@ -247,20 +378,156 @@ mod tests {
         let deadbeef = b"deadbeef";
         let mut decoder = BinDecoder::new(deadbeef);
 
-        decoder.read_slice_from(4).expect("failed to read dead");
+        decoder.read_slice(4).expect("failed to read dead");
         let read = decoder.slice_from(0).expect("failed to get slice");
-        assert_eq!(read, "dead");
+        assert_eq!(read, b"dead");
 
         decoder.read_slice(2).expect("failed to read be");
         let read = decoder.slice_from(4).expect("failed to get slice");
-        assert_eq!(read, "be");
+        assert_eq!(read, b"be");
 
         decoder.read_slice(0).expect("failed to read nothing");
         let read = decoder.slice_from(4).expect("failed to get slice");
-        assert_eq!(read, "be");
+        assert_eq!(read, b"be");
+
+        // nothing was read since the current index
+        let read = decoder.slice_from(6).expect("failed to get slice");
+        assert_eq!(read, b"");
 
         // this should fail
-        assert!(decoder.slice_from(6).is_err());
+        assert!(decoder.slice_from(7).is_err());
         assert!(decoder.slice_from(10).is_err());
     }
+
+    #[test]
+    fn test_peek_and_index() {
+        let bytes = [1, 2, 0, 3];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert_eq!(decoder.index(), 0);
+        assert_eq!(decoder.len(), 4);
+
+        // peeking does not consume
+        assert_eq!(decoder.peek().map(Restrict::unverified), Some(1));
+        assert_eq!(decoder.index(), 0);
+        assert_eq!(decoder.read_u8().unwrap().unverified(), 1);
+        assert_eq!(decoder.index(), 1);
+
+        assert_eq!(decoder.read_u16().unwrap().unverified(), 0x0200);
+        assert_eq!(decoder.index(), 3);
+        assert_eq!(decoder.len(), 1);
+        assert_eq!(decoder.peek().map(Restrict::unverified), Some(3));
+
+        decoder.pop().unwrap();
+        assert_eq!(decoder.index(), 4);
+        assert!(decoder.is_empty());
+        assert!(decoder.peek().is_none());
+        assert!(decoder.pop().is_err());
+        assert_eq!(decoder.index(), 4);
+    }
+
+    #[test]
+    fn test_read_bounded() {
+        let bytes = [1, 2, 3, 4, 5];
+        let mut decoder = BinDecoder::new(&bytes);
+        decoder.read_u8().unwrap();
+
+        let read = decoder
+            .read_bounded(3, |decoder| {
+                assert_eq!(decoder.remaining(), 3);
+                let read = decoder.read_vec(2)?;
+                assert_eq!(decoder.remaining(), 1);
+
+                // reads past the bound fail
+                assert!(decoder.read_u16().is_err());
+                assert!(decoder.read_slice(2).is_err());
+                decoder.read_u8()?;
+                assert!(decoder.is_empty());
+                assert!(decoder.peek().is_none());
+
+                Ok(read.unverified())
+            })
+            .expect("bounded read failed");
+        assert_eq!(read, vec![2, 3]);
+
+        // the bound is lifted afterwards
+        assert_eq!(decoder.index(), 4);
+        assert_eq!(decoder.remaining(), 1);
+        assert_eq!(decoder.read_u8().unwrap().unverified(), 5);
+    }
+
+    #[test]
+    fn test_read_at() {
+        let bytes = [1, 2, 3, 4, 5];
+        let mut decoder = BinDecoder::new(&bytes);
+        decoder.read_u8().unwrap();
+
+        let read = decoder
+            .read_bounded(2, |decoder| {
+                // the read may go past the bound, and back before the current index
+                let read = decoder.read_at(0, |decoder| decoder.read_vec(5))?;
+
+                // and returns to the current index, within the bound
+                assert_eq!(decoder.index(), 1);
+                assert_eq!(decoder.remaining(), 2);
+                decoder.read_u16()?;
+
+                Ok(read.unverified())
+            })
+            .expect("read at failed");
+        assert_eq!(read, bytes.to_vec());
+    }
+
+    #[test]
+    fn test_read_bounded_length() {
+        let bytes = [1, 2, 3, 4, 5];
+
+        // reading less than the bound
+        let mut decoder = BinDecoder::new(&bytes);
+        match *decoder
+            .read_bounded(3, |decoder| decoder.read_u16().map(Restrict::unverified))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::IncorrectRDataLengthRead { read: 2, len: 3 } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        // the error of the read is returned, not the length read up to it
+        let mut decoder = BinDecoder::new(&bytes);
+        let result: ProtoResult<()> = decoder.read_bounded(3, |decoder| {
+            decoder.read_u8()?;
+            Err("invalid data".into())
+        });
+        match *result.unwrap_err().kind() {
+            ProtoErrorKind::Message("invalid data") => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        // reading more than the bound
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(decoder
+            .read_bounded(3, |decoder| decoder.read_u32())
+            .is_err());
+
+        // a bound beyond the buffer
+        let mut decoder = BinDecoder::new(&bytes);
+        match *decoder
+            .read_bounded(6, |decoder| decoder.read_u8().map(Restrict::unverified))
+            .unwrap_err()
+            .kind()
+        {
+            ProtoErrorKind::RDataLengthExceedsBuffer {
+                len: 6,
+                remaining: 5,
+            } => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+
+        // nested bounds are within the outer bound
+        let mut decoder = BinDecoder::new(&bytes);
+        let nested = decoder.read_bounded(2, |decoder| {
+            decoder.read_bounded(3, |decoder| decoder.read_vec(3))
+        });
+        assert!(nested.is_err());
+    }
 }
@@ -20,6 +20,9 @@ use crate::error::{ProtoErrorKind, ProtoResult};
 use super::BinEncodable;
 use crate::op::Header;
 
+/// The largest value which can be written by `BinEncoder::emit_u48`
+const MAX_U48: u64 = 0x0000_FFFF_FFFF_FFFF;
+
 // this is private to make sure there is no accidental access to the inner buffer.
 mod private {
     use crate::error::{ProtoErrorKind, ProtoResult};
@@ -43,6 +46,11 @@ mod private {
             self.max_size = max as usize;
         }
 
+        /// returns the maximum size of the buffer
+        pub fn max_size(&self) -> usize {
+            self.max_size
+        }
+
         /// returns an error if the maximum buffer size would be exceeded with the addition number of elements
         ///
         /// and reserves the additional space in the buffer
@@ -110,6 +118,17 @@ impl<'a> BinEncoder<'a> {
         Self::with_offset(buf, 0, mode)
     }
 
+    /// Create a new encoder which fails writes beyond the maximum size, see `set_max_size`
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - the maximum length of the buffer, e.g. the payload size of the requester
+    pub fn with_max_size(buf: &'a mut Vec<u8>, max_size: u16) -> Self {
+        let mut encoder = Self::new(buf);
+        encoder.set_max_size(max_size);
+        encoder
+    }
+
     /// Begins the encoder at the given offset
     ///
     /// This is used for pointers. If this encoder is starting at some point further in
@@ -145,6 +164,11 @@ impl<'a> BinEncoder<'a> {
         self.buffer.set_max_size(max);
     }
 
+    /// Returns the maximum size of the buffer
+    pub fn max_size(&self) -> u16 {
+        self.buffer.max_size() as u16
+    }
+
     /// Returns a reference to the internal buffer
     pub fn into_bytes(self) -> &'a Vec<u8> {
         self.buffer.into_bytes()
@@ -323,6 +347,19 @@ impl<'a> BinEncoder<'a> {
         self.write_slice(&data.to_be_bytes())
     }
 
+    /// Writes the low 48 bits of a u64 in network byte order to the buffer
+    ///
+    /// # Return
+    ///
+    /// An error if the value does not fit in 48 bits
+    pub fn emit_u48(&mut self, data: u64) -> ProtoResult<()> {
+        if data > MAX_U48 {
+            return Err(format!("value exceeds 48 bits: {}", data).into());
+        }
+
+        self.write_slice(&data.to_be_bytes()[2..])
+    }
+
     fn write_slice(&mut self, data: &[u8]) -> ProtoResult<()> {
         // replacement case, the necessary space should have been reserved already...
         if self.offset < self.buffer.len() {
@@ -492,7 +529,9 @@ pub struct Rollback {
 
 impl Rollback {
     pub fn rollback(self, encoder: &mut BinEncoder) {
-        encoder.set_offset(self.rollback_index)
+        // drop anything partially written, and any name pointers into it
+        encoder.set_offset(self.rollback_index);
+        encoder.trim();
     }
 }
 
@@ -569,6 +608,38 @@ mod tests {
         assert_eq!(written, 4);
     }
 
+    #[test]
+    fn test_u48() {
+        for value in &[0, 1_577_836_800, MAX_U48] {
+            let mut buf = vec![];
+            {
+                let mut encoder = BinEncoder::new(&mut buf);
+                encoder.emit_u48(*value).expect("failed to emit u48");
+            }
+            assert_eq!(buf.len(), 6);
+
+            let mut decoder = BinDecoder::new(&buf);
+            let read = decoder.read_u48().expect("failed to read u48").unverified();
+            assert_eq!(read, *value);
+            assert!(decoder.is_empty());
+        }
+
+        let mut buf = vec![];
+        let mut encoder = BinEncoder::new(&mut buf);
+        encoder
+            .emit_u48(0x0000_8000_0000_0001)
+            .expect("failed to emit u48");
+        assert_eq!(encoder.into_bytes(), &[0x80, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_u48_overflow() {
+        let mut buf = vec![];
+        let mut encoder = BinEncoder::new(&mut buf);
+        assert!(encoder.emit_u48(MAX_U48 + 1).is_err());
+        assert!(encoder.is_empty());
+    }
+
     #[test]
     fn test_max_size() {
         let mut buf = vec![];
@@ -602,6 +673,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_max_size_rollback() {
+        let mut buf = vec![];
+        let mut encoder = BinEncoder::new(&mut buf);
+
+        encoder.set_max_size(5);
+        let data: &[u16] = &[1, 2, 3];
+        let error = encoder.emit_all(data.iter()).unwrap_err();
+
+        match *error.kind() {
+            ProtoErrorKind::NotAllRecordsWritten { count: 2 } => (),
+            _ => panic!(),
+        }
+
+        // nothing of the third element remains
+        assert_eq!(encoder.into_bytes(), &vec![0, 1, 0, 2]);
+    }
+
     #[test]
     fn test_max_size_place() {
         let mut buf = vec![];
@@ -618,4 +707,31 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_with_max_size_records() {
+        use crate::rr::{Name, RData, Record};
+        use std::net::Ipv4Addr;
+
+        // each record is 15 bytes: the root name, type, class, ttl, rdata length and address
+        let record = Record::from_rdata(
+            Name::root(),
+            300,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        );
+
+        let mut buf = vec![];
+        let mut encoder = BinEncoder::with_max_size(&mut buf, 30);
+        assert_eq!(encoder.max_size(), 30);
+
+        record.emit(&mut encoder).expect("failed to write");
+        record.emit(&mut encoder).expect("failed to write");
+        assert_eq!(encoder.len(), 30);
+
+        match *record.emit(&mut encoder).unwrap_err().kind() {
+            ProtoErrorKind::MaxBufferSizeExceeded(30) => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+        assert!(encoder.len() <= 30);
+    }
 }
@@ -0,0 +1,100 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Hex encoding of binary data in presentation formats
+//!
+//! This is the format of e.g. DS digests, NSEC3 salts and the generic rdata of RFC 3597.
+
+use crate::error::*;
+
+const SYMBOLS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Encodes the data as uppercase hex, without any separators
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len() * 2);
+    for b in data {
+        encoded.push(SYMBOLS[usize::from(b >> 4)] as char);
+        encoded.push(SYMBOLS[usize::from(b & 0x0F)] as char);
+    }
+    encoded
+}
+
+/// Decodes hex, in upper or lower case, ignoring any whitespace
+///
+/// `-` decodes to no data, it is the presentation of an empty NSEC3 salt, see
+///  [RFC 5155](https://tools.ietf.org/html/rfc5155#section-3.3).
+///
+/// # Errors
+///
+/// If the data contains non-hex characters, or an odd number of hex digits.
+pub fn decode(data: &str) -> ProtoResult<Vec<u8>> {
+    if data.trim() == "-" {
+        return Ok(vec![]);
+    }
+
+    let mut decoded = Vec::with_capacity(data.len() / 2);
+    let mut high: Option<u8> = None;
+    for c in data.chars().filter(|c| !c.is_whitespace()) {
+        let digit = c
+            .to_digit(16)
+            .ok_or_else(|| ProtoErrorKind::Msg(format!("invalid hex character: {}", c)))?
+            as u8;
+
+        match high.take() {
+            Some(high) => decoded.push(high << 4 | digit),
+            None => high = Some(digit),
+        }
+    }
+
+    if high.is_some() {
+        return Err(ProtoErrorKind::Message("odd number of hex digits").into());
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let encoded = encode(&data);
+        assert_eq!(encoded.len(), 512);
+        assert!(encoded.starts_with("000102"));
+        assert!(encoded.ends_with("FDFEFF"));
+        assert_eq!(decode(&encoded).unwrap(), data);
+
+        assert_eq!(encode(&[]), "");
+        assert!(decode("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_whitespace_and_case() {
+        assert_eq!(
+            decode("2bb183AF 5f22\t588179\r\nA5").unwrap(),
+            vec![0x2B, 0xB1, 0x83, 0xAF, 0x5F, 0x22, 0x58, 0x81, 0x79, 0xA5]
+        );
+        assert_eq!(decode(" AABB ").unwrap(), vec![0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode("ABC").is_err());
+        assert!(decode("A B C").is_err());
+        assert!(decode("AG").is_err());
+        assert!(decode("0x00").is_err());
+        assert!(decode("--").is_err());
+    }
+
+    #[test]
+    fn test_decode_empty_salt() {
+        assert!(decode("-").unwrap().is_empty());
+        assert!(decode(" - ").unwrap().is_empty());
+    }
+}
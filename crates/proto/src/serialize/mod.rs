@@ -16,4 +16,7 @@
 
 //! Contains serialization libraries for `binary` and text, `txt`.
 
+pub mod base32hex;
 pub mod binary;
+pub mod hex;
+pub mod tcp;
@@ -0,0 +1,140 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Length prefixed framing of messages for DNS over TCP
+//!
+//! [RFC 1035, section 4.2.2](https://tools.ietf.org/html/rfc1035#section-4.2.2)
+//!
+//! ```text
+//! 4.2.2. TCP usage
+//!
+//! Messages sent over TCP connections use server port 53 (decimal).  The
+//! message is prefixed with a two byte length field which gives the message
+//! length, excluding the two byte length field.  This length field allows
+//! the low-level processing to assemble a complete message before beginning
+//! to parse it.
+//! ```
+
+use std::io::{Read, Write};
+
+use crate::error::*;
+use crate::op::Message;
+
+/// Reads one length prefixed message from the reader
+///
+/// This blocks until the length and all of the bytes of the message have been read, or the reader
+///  ends, in which case an error is returned.
+pub fn read_tcp_message<R: Read>(reader: &mut R) -> ProtoResult<Message> {
+    let mut len_bytes = [0u8; 2];
+    reader.read_exact(&mut len_bytes)?;
+    let len = u16::from_be_bytes(len_bytes) as usize;
+
+    // read_exact continues reading after partial reads, until the buffer is full
+    let mut buffer = vec![0u8; len];
+    reader.read_exact(&mut buffer)?;
+
+    Message::from_vec(&buffer)
+}
+
+/// Writes the message to the writer, prefixed with its length
+///
+/// This blocks until the whole message has been written and the writer has been flushed.
+pub fn write_tcp_message<W: Write>(writer: &mut W, message: &Message) -> ProtoResult<()> {
+    let bytes = message.to_vec()?;
+    if bytes.len() > u16::max_value() as usize {
+        return Err(ProtoErrorKind::MaxBufferSizeExceeded(bytes.len()).into());
+    }
+
+    let mut buffer = Vec::with_capacity(2 + bytes.len());
+    buffer.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(&bytes);
+
+    // write_all continues writing after partial writes
+    writer.write_all(&buffer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::op::Query;
+    use crate::rr::{Name, RData, Record, RecordType};
+
+    /// Returns at most one byte per read
+    struct TrickleReader<R: Read>(R);
+
+    impl<R: Read> Read for TrickleReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = ::std::cmp::min(1, buf.len());
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    /// Accepts at most three bytes per write
+    struct TrickleWriter(Vec<u8>);
+
+    impl Write for TrickleWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let len = ::std::cmp::min(3, buf.len());
+            self.0.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn message(id: u16) -> Message {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let mut message = Message::new();
+        message
+            .set_id(id)
+            .add_query(Query::query(name.clone(), RecordType::A))
+            .add_answer(Record::from_rdata(
+                name,
+                86400,
+                RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+            ));
+        message
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let mut writer = TrickleWriter(Vec::new());
+        write_tcp_message(&mut writer, &message(1)).expect("failed to write");
+        write_tcp_message(&mut writer, &message(2)).expect("failed to write");
+
+        let bytes = writer.0;
+        let len = message(1).to_vec().unwrap().len();
+        assert_eq!(bytes.len(), 2 * (2 + len));
+        assert_eq!(&bytes[..2], &(len as u16).to_be_bytes());
+
+        let mut reader = TrickleReader(Cursor::new(bytes));
+        let first = read_tcp_message(&mut reader).expect("failed to read");
+        let second = read_tcp_message(&mut reader).expect("failed to read");
+        assert_eq!(first.id(), 1);
+        assert_eq!(second.id(), 2);
+        assert_eq!(first.answers(), message(1).answers());
+
+        // no more messages
+        assert!(read_tcp_message(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let mut bytes = Vec::new();
+        write_tcp_message(&mut bytes, &message(1)).expect("failed to write");
+        bytes.pop();
+
+        assert!(read_tcp_message(&mut Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Utilities for testing the binary encoding of record data, enabled with the `test-util` feature
+
+use std::fmt::Write;
+
+use crate::rr::{RData, RecordType};
+use crate::serialize::binary::{BinDecoder, BinEncoder, Restrict};
+
+/// Emits the RData, reads it back as the `record_type` and asserts that it is equal to the original
+///
+/// Returns the emitted bytes, e.g. to compare them with the expected wire format.
+///
+/// # Panics
+///
+/// If the RData can't be emitted or read, or if it is read back differently or from fewer bytes
+///  than were emitted, with the emitted bytes in hex in the panic message.
+pub fn assert_wire_roundtrip(rdata: &RData, record_type: RecordType) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = BinEncoder::new(&mut bytes);
+    if let Err(err) = rdata.emit(&mut encoder) {
+        panic!("failed to emit {}: {}\n{:?}", record_type, err, rdata);
+    }
+
+    assert!(
+        bytes.len() <= usize::from(u16::max_value()),
+        "{} rdata is too long: {} bytes",
+        record_type,
+        bytes.len()
+    );
+
+    let mut decoder = BinDecoder::new(&bytes);
+    let read = match RData::read(&mut decoder, record_type, Restrict::new(bytes.len() as u16)) {
+        Ok(read) => read,
+        Err(err) => panic!(
+            "failed to read {}: {}\n{:?}\n{}",
+            record_type,
+            err,
+            rdata,
+            hex_dump(&bytes)
+        ),
+    };
+
+    assert!(
+        read == *rdata && decoder.index() == bytes.len(),
+        "{} didn't roundtrip, {} of {} bytes were read\nemitted: {:?}\nread:    {:?}\n{}",
+        record_type,
+        decoder.index(),
+        bytes.len(),
+        rdata,
+        read,
+        hex_dump(&bytes)
+    );
+
+    bytes
+}
+
+/// Formats the bytes as hex, 16 per line after their offset
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        write!(dump, "{:04x}:", line * 16).expect("writing to a String can't fail");
+        for b in chunk {
+            write!(dump, " {:02x}", b).expect("writing to a String can't fail");
+        }
+        dump.push('\n');
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::rr::rdata::MX;
+    use crate::rr::Name;
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = assert_wire_roundtrip(&RData::A(Ipv4Addr::new(192, 0, 2, 1)), RecordType::A);
+        assert_eq!(bytes, &[192, 0, 2, 1]);
+
+        let mx = MX::new(10, Name::from_ascii("mail.example.com.").unwrap());
+        let bytes = assert_wire_roundtrip(&RData::MX(mx), RecordType::MX);
+        assert_eq!(&bytes[..2], &[0, 10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "didn't roundtrip")]
+    fn test_roundtrip_mismatch() {
+        // the same bytes are read back as another type
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        assert_wire_roundtrip(&RData::CNAME(name), RecordType::NS);
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let bytes: Vec<u8> = (0..18).collect();
+        assert_eq!(
+            hex_dump(&bytes),
+            "0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0010: 10 11\n"
+        );
+    }
+}
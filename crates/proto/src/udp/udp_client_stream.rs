@@ -17,9 +17,11 @@ use tokio_timer::Timeout;
 
 use crate::error::ProtoError;
 use crate::op::message::NoopMessageFinalizer;
-use crate::op::{Message, MessageFinalizer, OpCode};
+use crate::op::{Message, MessageFinalizer, MessageVerifier};
 use crate::udp::udp_stream::{NextRandomUdpSocket, UdpSocket};
-use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, SerialMessage};
+use crate::xfer::{
+    DnsRequest, DnsRequestSender, DnsResponse, OsRandSource, RandSource, SerialMessage,
+};
 
 /// A UDP client stream of DNS binary packets
 ///
@@ -35,6 +37,7 @@ where
     timeout: Duration,
     is_shutdown: bool,
     signer: Option<Arc<MF>>,
+    rand_source: Arc<dyn RandSource>,
     marker: PhantomData<S>,
 }
 
@@ -82,6 +85,7 @@ impl<S: Send, MF: MessageFinalizer> UdpClientStream<S, MF> {
             name_server: Some(name_server),
             timeout,
             signer,
+            rand_source: Arc::new(OsRandSource),
             marker: PhantomData::<S>,
         }
     }
@@ -93,14 +97,6 @@ impl<S: Send, MF: MessageFinalizer> Display for UdpClientStream<S, MF> {
     }
 }
 
-/// creates random query_id, each socket is unique, no need for global uniqueness
-fn random_query_id() -> u16 {
-    use rand::distributions::{Distribution, Standard};
-    let mut rand = rand::thread_rng();
-
-    Standard.sample(&mut rand)
-}
-
 impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
     for UdpClientStream<S, MF>
 {
@@ -113,7 +109,7 @@ impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
 
         // associated the ID for this request, b/c this connection is unique to socket port, the ID
         //   does not need to be globally unique
-        message.set_id(random_query_id());
+        message.set_id(self.rand_source.next_u16());
 
         let now = match SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -134,8 +130,9 @@ impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
         let now = now as u32;
 
         // TODO: move this logic into Message::finalize?
-        if let OpCode::Update = message.op_code() {
-            if let Some(ref signer) = self.signer {
+        let mut verifier = None;
+        if let Some(ref signer) = self.signer {
+            if signer.should_finalize_message(&message) {
                 if let Err(e) = message.finalize::<MF>(signer.borrow(), now) {
                     debug!("could not sign message: {}", e);
                     return UdpResponse(Timeout::new(
@@ -143,6 +140,8 @@ impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
                         self.timeout,
                     ));
                 }
+
+                verifier = signer.response_verifier(&message);
             }
         }
 
@@ -156,10 +155,16 @@ impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
             }
         };
 
-        let message_id = message.id();
+        // only the id, op code and question are needed to match the response
+        let mut query = Message::new();
+        query
+            .set_id(message.id())
+            .set_op_code(message.op_code())
+            .add_queries(message.queries().iter().cloned());
         let message = SerialMessage::new(bytes, self.name_server);
+        let expected = ExpectedResponse { query, verifier };
 
-        UdpResponse::new(message, message_id, self.timeout)
+        UdpResponse::new(message, Arc::new(expected), self.timeout)
     }
 
     fn error_response(err: ProtoError) -> Self::DnsResponseFuture {
@@ -202,10 +207,10 @@ impl<S> UdpResponse<S> {
     /// # Arguments
     ///
     /// * `request` - Serialized message being sent
-    /// * `message_id` - Id of the message that was encoded in the serial message
-    fn new(request: SerialMessage, message_id: u16, timeout: Duration) -> Self {
+    /// * `query` - the id, question and response verifier of the message in the serial message
+    fn new(request: SerialMessage, query: Arc<ExpectedResponse>, timeout: Duration) -> Self {
         UdpResponse(Timeout::new(
-            SingleUseUdpSocket::StartSend(Some(request), message_id),
+            SingleUseUdpSocket::StartSend(Some(request), query),
             timeout,
         ))
     }
@@ -229,9 +234,18 @@ where
     name_server: Option<SocketAddr>,
     timeout: Duration,
     signer: Option<Arc<MF>>,
+    rand_source: Arc<dyn RandSource>,
     marker: PhantomData<S>,
 }
 
+impl<S: Send, MF: MessageFinalizer> UdpClientConnect<S, MF> {
+    /// Uses the source for the ids of the queries, instead of `OsRandSource`
+    pub fn with_rand_source(mut self, rand_source: Arc<dyn RandSource>) -> Self {
+        self.rand_source = rand_source;
+        self
+    }
+}
+
 impl<S: Send, MF: MessageFinalizer> Future for UdpClientConnect<S, MF> {
     type Item = UdpClientStream<S, MF>;
     type Error = ProtoError;
@@ -245,16 +259,28 @@ impl<S: Send, MF: MessageFinalizer> Future for UdpClientConnect<S, MF> {
             is_shutdown: false,
             timeout: self.timeout,
             signer: self.signer.take(),
+            rand_source: self.rand_source.clone(),
             marker: PhantomData,
         }))
     }
 }
 
+/// The request a response must match, and its verifier
+struct ExpectedResponse {
+    // only the id and the question of the request
+    query: Message,
+    verifier: Option<MessageVerifier>,
+}
+
 enum SingleUseUdpSocket<S> {
-    StartSend(Option<SerialMessage>, u16),
-    Connect(Option<SerialMessage>, NextRandomUdpSocket<S>, u16),
-    Send(Option<SerialMessage>, Option<S>, u16),
-    AwaitResponse(Option<SerialMessage>, S, u16),
+    StartSend(Option<SerialMessage>, Arc<ExpectedResponse>),
+    Connect(
+        Option<SerialMessage>,
+        NextRandomUdpSocket<S>,
+        Arc<ExpectedResponse>,
+    ),
+    Send(Option<SerialMessage>, Option<S>, Arc<ExpectedResponse>),
+    AwaitResponse(Option<SerialMessage>, S, Arc<ExpectedResponse>),
     Response(Option<Message>),
     Errored(Option<ProtoError>),
 }
@@ -266,23 +292,27 @@ impl<S: UdpSocket> Future for SingleUseUdpSocket<S> {
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         loop {
             *self = match *self {
-                SingleUseUdpSocket::StartSend(ref mut msg, msg_id) => {
+                SingleUseUdpSocket::StartSend(ref mut msg, ref query) => {
                     // get a new socket to use
                     let msg = msg.take();
                     let name_server = msg
                         .as_ref()
                         .expect("SingleUseUdpSocket::StartSend invalid state: msg")
                         .addr();
-                    SingleUseUdpSocket::Connect(msg, NextRandomUdpSocket::new(&name_server), msg_id)
+                    SingleUseUdpSocket::Connect(
+                        msg,
+                        NextRandomUdpSocket::new(&name_server),
+                        query.clone(),
+                    )
                 }
-                SingleUseUdpSocket::Connect(ref mut msg, ref mut future_socket, msg_id) => {
+                SingleUseUdpSocket::Connect(ref mut msg, ref mut future_socket, ref query) => {
                     let socket = try_ready!(future_socket.poll());
                     // TODO: connect the socket here on merge into master
 
                     // send the message, and then await the response
-                    SingleUseUdpSocket::Send(msg.take(), Some(socket), msg_id)
+                    SingleUseUdpSocket::Send(msg.take(), Some(socket), query.clone())
                 }
-                SingleUseUdpSocket::Send(ref mut msg, ref mut socket, msg_id) => {
+                SingleUseUdpSocket::Send(ref mut msg, ref mut socket, ref query) => {
                     try_ready!(socket
                         .as_mut()
                         .expect("SingleUseUdpSocket::Send invalid state: socket1")
@@ -301,10 +331,10 @@ impl<S: UdpSocket> Future for SingleUseUdpSocket<S> {
                         socket
                             .take()
                             .expect("SingleUseUdpSocket::Send invalid state: socket2"),
-                        msg_id,
+                        query.clone(),
                     )
                 }
-                SingleUseUdpSocket::AwaitResponse(ref mut request, ref mut socket, msg_id) => {
+                SingleUseUdpSocket::AwaitResponse(ref mut request, ref mut socket, ref query) => {
                     // TODO: consider making this heap based? need to verify it matches EDNS settings
                     let mut buf = [0u8; 2048];
 
@@ -328,18 +358,32 @@ impl<S: UdpSocket> Future for SingleUseUdpSocket<S> {
                         continue;
                     }
 
-                    // TODO: match query strings from request and response?
-
                     match response.to_message() {
                         Ok(message) => {
-                            if msg_id == message.id() {
+                            if message.matches_query(&query.query) {
+                                // a response failing verification is dropped, as a spoofed one
+                                if let Some(ref verifier) = query.verifier {
+                                    let now = SystemTime::now()
+                                        .duration_since(UNIX_EPOCH)
+                                        .map_or(0, |now| now.as_secs() as u32);
+
+                                    if let Err(e) = verifier(response.bytes(), now) {
+                                        warn!(
+                                            "dropped message id: {} failing verification: {}",
+                                            message.id(),
+                                            e
+                                        );
+                                        continue;
+                                    }
+                                }
+
                                 debug!("received message id: {}", message.id());
                                 SingleUseUdpSocket::Response(Some(message))
                             } else {
-                                // on wrong id, attempted poison?
+                                // on wrong id or question, attempted poison?
                                 warn!(
-                                    "expected message id: {} got: {}, dropped",
-                                    msg_id,
+                                    "expected message id: {} and its question, got: {}, dropped",
+                                    query.query.id(),
                                     message.id()
                                 );
 
@@ -351,7 +395,8 @@ impl<S: UdpSocket> Future for SingleUseUdpSocket<S> {
                             // on errors deserializing, continue
                             warn!(
                                 "dropped malformed message waiting for id: {} err: {}",
-                                msg_id, e
+                                query.query.id(),
+                                e
                             );
                             //SingleUseUdpSocket::AwaitResponse(msg.take(), socket.take(), msg_id)
                             continue;
@@ -518,3 +563,129 @@ fn udp_client_stream_test(server_addr: IpAddr) {
 
     assert!(worked_once);
 }
+
+#[test]
+fn test_udp_client_stream_rand_source() {
+    use crate::op::{MessageType, Query};
+    use crate::rr::{Name, RecordType};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Mutex;
+    use tokio::runtime::current_thread::Runtime;
+
+    let server = std::net::UdpSocket::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+    server
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let server_addr = server.local_addr().unwrap();
+
+    // answers the requests with themselves, and returns their ids
+    let server_handle = std::thread::Builder::new()
+        .name("test_udp_client_stream_rand_source:server".to_string())
+        .spawn(move || {
+            let mut buffer = [0_u8; 512];
+            let mut ids = Vec::new();
+
+            for _ in 0..3 {
+                let (len, addr) = server.recv_from(&mut buffer).expect("receive failed");
+                let mut message = Message::from_vec(&buffer[0..len]).expect("failed parse");
+                message.set_message_type(MessageType::Response);
+                server
+                    .send_to(&message.to_vec().unwrap(), addr)
+                    .expect("send failed");
+
+                ids.push(message.id());
+            }
+
+            ids
+        })
+        .unwrap();
+
+    let mut io_loop = Runtime::new().unwrap();
+    let stream = UdpClientStream::with_timeout(server_addr, Duration::from_secs(5))
+        .with_rand_source(Arc::new(Mutex::new(StdRng::seed_from_u64(42))));
+    let mut stream: UdpClientStream<tokio_udp::UdpSocket> = io_loop.block_on(stream).unwrap();
+
+    let mut query = Message::new();
+    query.add_query(Query::query(
+        Name::from_ascii("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    for _ in 0..3 {
+        let response_future =
+            stream.send_message(DnsRequest::new(query.clone(), Default::default()));
+        io_loop.block_on(response_future).expect("no response");
+    }
+
+    // the same seed results in the same ids
+    let expected = Mutex::new(StdRng::seed_from_u64(42));
+    let expected: Vec<u16> = (0..3).map(|_| expected.next_u16()).collect();
+    assert_eq!(
+        server_handle.join().expect("server thread failed"),
+        expected
+    );
+}
+
+#[test]
+fn test_udp_client_stream_question_mismatch() {
+    use crate::op::{MessageType, Query};
+    use crate::rr::rdata::NULL;
+    use crate::rr::{Name, RData, Record, RecordType};
+    use tokio::runtime::current_thread::Runtime;
+
+    let server = std::net::UdpSocket::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+    server
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let server_addr = server.local_addr().unwrap();
+
+    // answers the request with the id, but another question, before the actual response
+    let server_handle = std::thread::Builder::new()
+        .name("test_udp_client_stream_question_mismatch:server".to_string())
+        .spawn(move || {
+            let mut buffer = [0_u8; 512];
+            let (len, addr) = server.recv_from(&mut buffer).expect("receive failed");
+            let request = Message::from_vec(&buffer[0..len]).expect("failed parse");
+
+            for (name, bytes) in &[
+                ("www.example.net.", b"spoofed"),
+                ("www.example.com.", b"genuine"),
+            ] {
+                let name = Name::from_ascii(name).unwrap();
+                let mut message = Message::new();
+                message
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .add_query(Query::query(name.clone(), RecordType::NULL))
+                    .add_answer(Record::from_rdata(
+                        name,
+                        0,
+                        RData::NULL(NULL::with(bytes.to_vec())),
+                    ));
+                server
+                    .send_to(&message.to_vec().unwrap(), addr)
+                    .expect("send failed");
+            }
+        })
+        .unwrap();
+
+    let mut io_loop = Runtime::new().unwrap();
+    let stream = UdpClientStream::with_timeout(server_addr, Duration::from_secs(5));
+    let mut stream: UdpClientStream<tokio_udp::UdpSocket> = io_loop.block_on(stream).unwrap();
+
+    let mut query = Message::new();
+    query.add_query(Query::query(
+        Name::from_ascii("WWW.example.com.").unwrap(),
+        RecordType::NULL,
+    ));
+    let response = io_loop
+        .block_on(stream.send_message(DnsRequest::new(query, Default::default())))
+        .expect("no response");
+    server_handle.join().expect("server thread failed");
+
+    let response = Message::from(response);
+    match *response.answers()[0].rdata() {
+        RData::NULL(ref null) => assert_eq!(null.anything(), Some(&b"genuine".to_vec())),
+        ref rdata => panic!("unexpected rdata: {:?}", rdata),
+    }
+}
@@ -10,7 +10,6 @@
 use futures::sync::mpsc::UnboundedSender;
 use futures::sync::oneshot;
 use futures::{Future, IntoFuture};
-use rand;
 
 use crate::error::*;
 use crate::op::{Message, MessageType, OpCode, Query};
@@ -133,13 +132,9 @@ pub trait DnsHandle: 'static + Clone + Send {
         // build the message
         let mut message: Message = Message::new();
 
-        // TODO: This is not the final ID, it's actually set in the poll method of DNS future
-        //  should we just remove this?
-        let id: u16 = rand::random();
-
+        // the id is assigned from the RandSource of the connection when the message is sent
         message.add_query(query);
         message
-            .set_id(id)
             .set_message_type(MessageType::Query)
             .set_op_code(OpCode::Query)
             .set_recursion_desired(true);
@@ -17,16 +17,14 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use futures::stream::Stream;
 use futures::sync::oneshot;
 use futures::{task, Async, Future, Poll};
-use rand;
-use rand::distributions::{Distribution, Standard};
 use smallvec::SmallVec;
 use tokio_timer::Delay;
 
 use crate::error::*;
-use crate::op::{Message, MessageFinalizer, OpCode};
+use crate::op::{Message, MessageFinalizer, MessageVerifier};
 use crate::xfer::{
     ignore_send, DnsClientStream, DnsRequest, DnsRequestOptions, DnsRequestSender, DnsResponse,
-    SerialMessage,
+    OsRandSource, RandSource, SerialMessage,
 };
 use crate::DnsStreamHandle;
 
@@ -35,7 +33,8 @@ const QOS_MAX_RECEIVE_MSGS: usize = 100; // max number of messages to receive fr
 struct ActiveRequest {
     // the completion is the channel for a response to the original request
     completion: oneshot::Sender<Result<DnsResponse, ProtoError>>,
-    request_id: u16,
+    // the id, op code and question of the request, see `Message::matches_query`
+    query: Message,
     request_options: DnsRequestOptions,
     // most requests pass a single Message response directly through to the completion
     //  this small vec will have no allocations, unless the requests is a DNS-SD request
@@ -43,22 +42,32 @@ struct ActiveRequest {
     // TODO: change the completion above to a Stream, and don't hold messages...
     responses: SmallVec<[Message; 1]>,
     timeout: Delay,
+    // verifies the responses, if the request was finalized, e.g. with a TSIG
+    verifier: Option<MessageVerifier>,
 }
 
 impl ActiveRequest {
     fn new(
         completion: oneshot::Sender<Result<DnsResponse, ProtoError>>,
-        request_id: u16,
+        request: &Message,
         request_options: DnsRequestOptions,
         timeout: Delay,
+        verifier: Option<MessageVerifier>,
     ) -> Self {
+        let mut query = Message::new();
+        query
+            .set_id(request.id())
+            .set_op_code(request.op_code())
+            .add_queries(request.queries().iter().cloned());
+
         ActiveRequest {
             completion,
-            request_id,
+            query,
             request_options,
             // request,
             responses: SmallVec::new(),
             timeout,
+            verifier,
         }
     }
 
@@ -79,7 +88,12 @@ impl ActiveRequest {
 
     /// the request id of the message that was sent
     fn request_id(&self) -> u16 {
-        self.request_id
+        self.query.id()
+    }
+
+    /// Returns true if the message is a response to the request, see `Message::matches_query`
+    fn is_response(&self, message: &Message) -> bool {
+        message.matches_query(&self.query)
     }
 
     /// the request options from the message that was sent
@@ -87,6 +101,14 @@ impl ActiveRequest {
         &self.request_options
     }
 
+    /// Verifies the response as received, if the request was finalized with a verifier
+    fn verify_response(&self, response: &[u8], current_time: u32) -> ProtoResult<()> {
+        match self.verifier {
+            Some(ref verifier) => verifier(response, current_time),
+            None => Ok(()),
+        }
+    }
+
     /// Sends an error
     fn complete_with_error(self, error: ProtoError) {
         ignore_send(self.completion.send(Err(error)));
@@ -122,6 +144,7 @@ where
     stream_handle: D,
     active_requests: HashMap<u16, ActiveRequest>,
     signer: Option<Arc<MF>>,
+    rand_source: Arc<dyn RandSource>,
     is_shutdown: bool,
 }
 
@@ -174,6 +197,7 @@ where
             stream_handle: Some(stream_handle),
             timeout_duration,
             signer,
+            rand_source: Arc::new(OsRandSource),
         }
     }
 
@@ -216,10 +240,8 @@ where
 
     /// creates random query_id, validates against all active queries
     fn next_random_query_id(&self) -> Async<u16> {
-        let mut rand = rand::thread_rng();
-
         for _ in 0..100 {
-            let id: u16 = Standard.sample(&mut rand); // the range is [0 ... u16::max]
+            let id: u16 = self.rand_source.next_u16(); // the range is [0 ... u16::max]
 
             if !self.active_requests.contains_key(&id) {
                 return Async::Ready(id);
@@ -266,6 +288,20 @@ where
     stream_handle: Option<Box<dyn DnsStreamHandle>>,
     timeout_duration: Duration,
     signer: Option<Arc<MF>>,
+    rand_source: Arc<dyn RandSource>,
+}
+
+impl<F, S, MF> DnsMultiplexerConnect<F, S, MF>
+where
+    F: Future<Item = S, Error = ProtoError> + Send + 'static,
+    S: Stream<Item = SerialMessage, Error = ProtoError>,
+    MF: MessageFinalizer + Send + Sync + 'static,
+{
+    /// Uses the source for the ids of the queries, instead of `OsRandSource`
+    pub fn with_rand_source(mut self, rand_source: Arc<dyn RandSource>) -> Self {
+        self.rand_source = rand_source;
+        self
+    }
 }
 
 impl<F, S, MF> Future for DnsMultiplexerConnect<F, S, MF>
@@ -289,6 +325,7 @@ where
                 .expect("must not poll after complete"),
             active_requests: HashMap::new(),
             signer: self.signer.clone(),
+            rand_source: self.rand_source.clone(),
             is_shutdown: false,
         }))
     }
@@ -340,13 +377,24 @@ where
         // TODO: truncates u64 to u32, error on overflow?
         let now = now as u32;
 
-        // update messages need to be signed.
-        if let OpCode::Update = request.op_code() {
-            if let Some(ref signer) = self.signer {
+        // padding must come before signing, as the signature covers it
+        if let Some(block_size) = request_options.pad_to {
+            if let Err(e) = request.pad_to(block_size) {
+                debug!("could not pad message: {}", e);
+                return DnsMultiplexerSerialResponseInner::Err(Some(e)).into();
+            }
+        }
+
+        // update messages need to be signed, other finalizers may sign all messages
+        let mut verifier = None;
+        if let Some(ref signer) = self.signer {
+            if signer.should_finalize_message(&request) {
                 if let Err(e) = request.finalize::<MF>(signer.borrow(), now) {
                     debug!("could not sign message: {}", e);
                     return DnsMultiplexerSerialResponseInner::Err(Some(e)).into();
                 }
+
+                verifier = signer.response_verifier(&request);
             }
         }
 
@@ -356,7 +404,8 @@ where
         let (complete, receiver) = oneshot::channel();
 
         // send the message
-        let active_request = ActiveRequest::new(complete, request.id(), request_options, timeout);
+        let active_request =
+            ActiveRequest::new(complete, &request, request_options, timeout, verifier);
 
         match request.to_vec() {
             Ok(buffer) => {
@@ -429,6 +478,27 @@ where
                     match buffer.to_message() {
                         Ok(message) => match self.active_requests.entry(message.id()) {
                             Entry::Occupied(mut request_entry) => {
+                                // on wrong question, attempted poison?
+                                if !request_entry.get().is_response(&message) {
+                                    warn!(
+                                        "expected the question of message id: {}, dropped",
+                                        message.id()
+                                    );
+                                    continue;
+                                }
+
+                                // a response failing verification fails the request
+                                let now = SystemTime::now()
+                                    .duration_since(UNIX_EPOCH)
+                                    .map_or(0, |now| now.as_secs() as u32);
+                                if let Err(e) =
+                                    request_entry.get().verify_response(buffer.bytes(), now)
+                                {
+                                    warn!("response failed verification: {}", e);
+                                    request_entry.remove().complete_with_error(e);
+                                    continue;
+                                }
+
                                 // first add the response to the active_requests responses
                                 let complete = {
                                     let active_request = request_entry.get_mut();
@@ -524,3 +594,110 @@ impl Future for DnsMultiplexerSerialResponseInner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+
+    use tokio::runtime::current_thread::Runtime;
+    use tokio_tcp::TcpStream as TokioTcpStream;
+
+    use super::*;
+    use crate::op::{MessageType, NoopMessageFinalizer, Query, ResponseCode};
+    use crate::rr::rdata::NULL;
+    use crate::rr::{Name, RData, Record, RecordType};
+    use crate::tcp::TcpClientStream;
+    use crate::xfer::{BufDnsRequestStreamHandle, DnsExchange, DnsHandle};
+
+    /// Sends the responses to the request of a TCP connection, with the id of the request
+    fn respond(server: TcpListener, responses: Vec<Message>) {
+        let (mut socket, _) = server.accept().expect("accept failed");
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let mut len_bytes = [0_u8; 2];
+        socket.read_exact(&mut len_bytes).expect("receive failed");
+        let mut buffer = vec![0_u8; usize::from(u16::from_be_bytes(len_bytes))];
+        socket.read_exact(&mut buffer).expect("receive failed");
+        let request = Message::from_vec(&buffer).expect("failed parse");
+
+        for mut response in responses {
+            let bytes = response.set_id(request.id()).to_vec().unwrap();
+            socket
+                .write_all(&(bytes.len() as u16).to_be_bytes())
+                .expect("send failed");
+            socket.write_all(&bytes).expect("send failed");
+        }
+    }
+
+    fn message(name: &str, response_code: ResponseCode, rdata: Option<&[u8]>) -> Message {
+        let name = Name::from_ascii(name).unwrap();
+        let mut message = Message::new();
+        message
+            .set_message_type(MessageType::Response)
+            .set_response_code(response_code);
+        if let Some(rdata) = rdata {
+            message
+                .add_query(Query::query(name.clone(), RecordType::NULL))
+                .add_answer(Record::from_rdata(
+                    name,
+                    0,
+                    RData::NULL(NULL::with(rdata.to_vec())),
+                ));
+        }
+        message
+    }
+
+    fn send_query(responses: Vec<Message>) -> Message {
+        let server = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let server_handle = std::thread::Builder::new()
+            .name("dns_multiplexer:server".to_string())
+            .spawn(move || respond(server, responses))
+            .unwrap();
+
+        let mut io_loop = Runtime::new().unwrap();
+        let (stream, sender) = TcpClientStream::<TokioTcpStream>::new(server_addr);
+        let multiplexer = DnsMultiplexer::<_, NoopMessageFinalizer>::new(stream, sender, None);
+        let (exchange, handle) = DnsExchange::connect(multiplexer);
+        io_loop.spawn(exchange.and_then(|exchange| exchange).map_err(|_| ()));
+
+        let mut query = Message::new();
+        query.add_query(Query::query(
+            Name::from_ascii("WWW.example.com.").unwrap(),
+            RecordType::NULL,
+        ));
+        let response = io_loop
+            .block_on(BufDnsRequestStreamHandle::new(handle).send(query))
+            .expect("no response");
+        server_handle.join().expect("server thread failed");
+
+        Message::from(response)
+    }
+
+    #[test]
+    fn test_question_mismatch() {
+        let response = send_query(vec![
+            message("www.example.net.", ResponseCode::NoError, Some(b"spoofed")),
+            message("www.example.com.", ResponseCode::NoError, Some(b"genuine")),
+        ]);
+
+        match *response.answers()[0].rdata() {
+            RData::NULL(ref null) => assert_eq!(null.anything(), Some(&b"genuine".to_vec())),
+            ref rdata => panic!("unexpected rdata: {:?}", rdata),
+        }
+    }
+
+    #[test]
+    fn test_no_question() {
+        let response = send_query(vec![message(
+            "www.example.com.",
+            ResponseCode::FormErr,
+            None,
+        )]);
+
+        assert_eq!(response.response_code(), ResponseCode::FormErr);
+    }
+}
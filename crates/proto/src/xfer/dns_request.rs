@@ -18,6 +18,10 @@ pub struct DnsRequestOptions {
     ///
     /// Setting this option will cause the underlying protocol to await the timeout, and then return all Responses.
     pub expects_multiple_responses: bool,
+    /// When set, requests are padded with the EDNS padding option to a multiple of this many bytes
+    ///
+    /// See [RFC 7830](https://tools.ietf.org/html/rfc7830), this is intended for encrypted transports.
+    pub pad_to: Option<usize>,
     // /// If set, then the request will terminate early if all types have been received
     // pub expected_record_types: Option<SmallVec<[RecordType; 2]>>,
     // TODO: add EDNS options here?
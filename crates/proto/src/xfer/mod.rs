@@ -18,6 +18,7 @@ pub mod dns_handle;
 pub mod dns_multiplexer;
 pub mod dns_request;
 pub mod dns_response;
+mod rand_source;
 pub mod retry_dns_handle;
 #[cfg(feature = "dnssec")]
 pub mod secure_dns_handle;
@@ -30,7 +31,8 @@ pub use self::dns_multiplexer::{
 };
 pub use self::dns_request::{DnsRequest, DnsRequestOptions};
 pub use self::dns_response::DnsResponse;
-pub use self::retry_dns_handle::RetryDnsHandle;
+pub use self::rand_source::{OsRandSource, RandSource};
+pub use self::retry_dns_handle::{RetryDnsHandle, RetryPolicy};
 #[cfg(feature = "dnssec")]
 pub use self::secure_dns_handle::SecureDnsHandle;
 pub use self::serial_message::SerialMessage;
@@ -0,0 +1,68 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Sources of the random numbers used for query ids
+
+use std::sync::Mutex;
+
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+
+/// A source of random numbers, e.g. for the ids of queries
+///
+/// Query ids must be unpredictable to protect against spoofed responses, the default is
+///  `OsRandSource`. Any `RngCore` can be used by wrapping it in a `Mutex`, which is useful for
+///  reproducible ids in tests:
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+///
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use trust_dns_proto::xfer::RandSource;
+///
+/// let rand_source: Arc<dyn RandSource> = Arc::new(Mutex::new(StdRng::seed_from_u64(42)));
+/// let id = rand_source.next_u16();
+/// ```
+pub trait RandSource: Send + Sync {
+    /// Returns a random number
+    fn next_u16(&self) -> u16;
+}
+
+/// Random numbers from the operating system, e.g. `getrandom` on Linux
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsRandSource;
+
+impl RandSource for OsRandSource {
+    fn next_u16(&self) -> u16 {
+        OsRng.gen()
+    }
+}
+
+impl<R: RngCore + Send> RandSource for Mutex<R> {
+    fn next_u16(&self) -> u16 {
+        self.lock().expect("RandSource poisoned").gen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_seeded_is_reproducible() {
+        let first = Mutex::new(StdRng::seed_from_u64(42));
+        let second = Mutex::new(StdRng::seed_from_u64(42));
+
+        let first: Vec<u16> = (0..10).map(|_| first.next_u16()).collect();
+        let second: Vec<u16> = (0..10).map(|_| second.next_u16()).collect();
+        assert_eq!(first, second);
+    }
+}
@@ -7,12 +7,54 @@
 
 //! `RetryDnsHandle` allows for DnsQueries to be reattempted on failure
 
-use futures::{Future, Poll};
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll};
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+use tokio_timer::Delay;
 
 use crate::error::ProtoError;
 use crate::xfer::{DnsRequest, DnsResponse};
 use crate::DnsHandle;
 
+/// The delay before each of the retries of a `RetryDnsHandle`
+///
+/// [RFC 1035](https://tools.ietf.org/html/rfc1035#section-4.2.1) recommends to back off when
+///  retransmitting queries, rather than to flood the servers with them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub enum RetryPolicy {
+    /// The same delay before each retry, the default is to retry immediately
+    Fixed(Duration),
+    /// The delay doubles with each retry
+    Exponential {
+        /// The delay before the first retry
+        initial: Duration,
+        /// The maximum delay before any retry
+        max: Duration,
+    },
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry, starting from `0` for the first retry
+    pub fn delay(&self, retry: usize) -> Duration {
+        match *self {
+            RetryPolicy::Fixed(delay) => delay,
+            RetryPolicy::Exponential { initial, max } => {
+                let factor = 1u32 << retry.min(31);
+                initial.checked_mul(factor).unwrap_or(max).min(max)
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::Fixed(Duration::from_secs(0))
+    }
+}
+
 /// Can be used to reattempt a queries if they fail
 ///
 /// *note* Current value of this is not clear, it may be removed
@@ -21,6 +63,7 @@ use crate::DnsHandle;
 pub struct RetryDnsHandle<H: DnsHandle> {
     handle: H,
     attempts: usize,
+    policy: RetryPolicy,
 }
 
 impl<H: DnsHandle> RetryDnsHandle<H> {
@@ -31,7 +74,24 @@ impl<H: DnsHandle> RetryDnsHandle<H> {
     /// * `handle` - handle to the dns connection
     /// * `attempts` - number of attempts before failing
     pub fn new(handle: H, attempts: usize) -> Self {
-        RetryDnsHandle { handle, attempts }
+        Self::with_policy(handle, attempts, RetryPolicy::default())
+    }
+
+    /// Creates a new Client handler for reattempting requests on failures, after a delay.
+    ///
+    /// Retries with a delay require a tokio timer.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - handle to the dns connection
+    /// * `attempts` - number of attempts before failing
+    /// * `policy` - the delay before each of the retries
+    pub fn with_policy(handle: H, attempts: usize, policy: RetryPolicy) -> Self {
+        RetryDnsHandle {
+            handle,
+            attempts,
+            policy,
+        }
     }
 }
 
@@ -52,7 +112,10 @@ where
             request,
             handle: self.handle.clone(),
             future,
+            delay: None,
             remaining_attempts: self.attempts,
+            retries: 0,
+            policy: self.policy,
         })
     }
 }
@@ -62,7 +125,10 @@ struct RetrySendFuture<H: DnsHandle> {
     request: DnsRequest,
     handle: H,
     future: <H as DnsHandle>::Response,
+    delay: Option<Delay>,
     remaining_attempts: usize,
+    retries: usize,
+    policy: RetryPolicy,
 }
 
 impl<H: DnsHandle> Future for RetrySendFuture<H> {
@@ -73,6 +139,18 @@ impl<H: DnsHandle> Future for RetrySendFuture<H> {
         // loop over the future, on errors, spawn a new future
         //  on ready and not ready return.
         loop {
+            if let Some(ref mut delay) = self.delay {
+                if let Async::NotReady = delay.poll()? {
+                    return Ok(Async::NotReady);
+                }
+            }
+
+            if self.delay.take().is_some() {
+                // FIXME: if the "sent" Message is part of the error result,
+                //  then we can just reuse it... and no clone necessary
+                self.future = self.handle.send(self.request.clone());
+            }
+
             match self.future.poll() {
                 r @ Ok(_) => return r,
                 Err(e) => {
@@ -81,9 +159,15 @@ impl<H: DnsHandle> Future for RetrySendFuture<H> {
                     }
 
                     self.remaining_attempts -= 1;
-                    // FIXME: if the "sent" Message is part of the error result,
-                    //  then we can just reuse it... and no clone necessary
-                    self.future = self.handle.send(self.request.clone());
+                    let delay = self.policy.delay(self.retries);
+                    self.retries += 1;
+
+                    debug!("retrying after {:?} on error: {}", delay, e);
+                    if delay == Duration::from_secs(0) {
+                        self.future = self.handle.send(self.request.clone());
+                    } else {
+                        self.delay = Some(Delay::new(Instant::now() + delay));
+                    }
                 }
             }
         }
@@ -97,6 +181,9 @@ mod test {
     use futures::*;
     use crate::op::*;
     use std::cell::Cell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::runtime::current_thread::Runtime;
     use DnsHandle;
 
     #[derive(Clone)]
@@ -151,4 +238,57 @@ mod test {
         let test1 = Message::new();
         assert!(client.send(test1).wait().is_err());
     }
+
+    #[derive(Clone)]
+    struct TimeoutClient {
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl DnsHandle for TimeoutClient {
+        type Response = Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+        fn send<R: Into<DnsRequest>>(&mut self, _: R) -> Self::Response {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            Box::new(failed(ProtoErrorKind::Timeout.into()))
+        }
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mut handle = RetryDnsHandle::with_policy(
+            TimeoutClient {
+                attempts: attempts.clone(),
+            },
+            2,
+            RetryPolicy::Exponential {
+                initial: Duration::from_millis(10),
+                max: Duration::from_millis(15),
+            },
+        );
+
+        let start = Instant::now();
+        let mut io_loop = Runtime::new().unwrap();
+        let error = io_loop
+            .block_on(handle.send(Message::new()))
+            .expect_err("should have timed out");
+
+        assert_eq!(*error.kind(), ProtoErrorKind::Timeout);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= Duration::from_millis(25));
+    }
+
+    #[test]
+    fn test_exponential_delay() {
+        let policy = RetryPolicy::Exponential {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+        };
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(4), Duration::from_secs(1));
+        assert_eq!(policy.delay(usize::max_value()), Duration::from_secs(1));
+    }
 }
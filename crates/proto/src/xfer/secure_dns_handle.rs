@@ -8,9 +8,10 @@
 //! The `SecureDnsHandle` is used to validate all DNS responses for correct DNSSec signatures.
 
 use std::clone::Clone;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures::*;
 
@@ -19,11 +20,20 @@ use crate::op::{OpCode, Query};
 use crate::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, DNSKEY, SIG};
 #[cfg(feature = "dnssec")]
 use crate::rr::dnssec::Verifier;
-use crate::rr::dnssec::{Algorithm, SupportedAlgorithms, TrustAnchor};
+use crate::rr::dnssec::{
+    Algorithm, AlgorithmPolicy, BogusReason, SupportedAlgorithms, TrustAnchor, TrustAnchors,
+    Validity,
+};
 use crate::rr::rdata::opt::EdnsOption;
 use crate::rr::{DNSClass, Name, RData, Record, RecordType};
-use crate::xfer::{DnsRequest, DnsRequestOptions, DnsResponse};
 use crate::xfer::dns_handle::DnsHandle;
+use crate::xfer::{DnsRequest, DnsRequestOptions, DnsResponse};
+
+/// The maximum number of cached results of the DS queries of `verify_insecure`
+const MAX_INSECURE_PROOFS: usize = 1024;
+
+/// The results of the DS queries of `verify_insecure`, by zone, with the time they expire
+type InsecureProofs = HashMap<Name, (bool, Instant)>;
 
 #[derive(Debug)]
 struct Rrset {
@@ -46,9 +56,11 @@ where
 {
     handle: H,
     trust_anchor: Arc<TrustAnchor>,
+    trust_anchors: Arc<RwLock<TrustAnchors>>,
+    insecure_proofs: Arc<Mutex<InsecureProofs>>,
     request_depth: usize,
     minimum_key_len: usize,
-    minimum_algorithm: Algorithm, // used to prevent down grade attacks...
+    algorithm_policy: AlgorithmPolicy, // used to prevent down grade attacks...
 }
 
 impl<H> SecureDnsHandle<H>
@@ -76,12 +88,56 @@ where
         SecureDnsHandle {
             handle,
             trust_anchor: Arc::new(trust_anchor),
+            trust_anchors: Arc::new(RwLock::new(TrustAnchors::new())),
+            insecure_proofs: Arc::new(Mutex::new(HashMap::new())),
+            request_depth: 0,
+            minimum_key_len: 0,
+            algorithm_policy: AlgorithmPolicy::default(),
+        }
+    }
+
+    /// Create a new SecureDnsHandle wrapping the specified handle.
+    ///
+    /// The chains of trust start at the DS and DNSKEY trust anchors of their zones, the compiled in
+    ///  TrustAnchor default trusted keys are not used.
+    ///
+    /// # Arguments
+    /// * `handle` - handle to use for all connections to a remote server.
+    /// * `trust_anchors` - DS and DNSKEY records that will be trusted in their zones, the DNSKEY
+    ///   trust anchors are updated by the rollovers of validated DNSKEY rrsets, see
+    ///   `TrustAnchors::update_dnskeys`.
+    pub fn with_trust_anchors(handle: H, trust_anchors: TrustAnchors) -> SecureDnsHandle<H> {
+        SecureDnsHandle {
+            handle,
+            trust_anchor: Arc::new(TrustAnchor::new()),
+            trust_anchors: Arc::new(RwLock::new(trust_anchors)),
+            insecure_proofs: Arc::new(Mutex::new(HashMap::new())),
             request_depth: 0,
             minimum_key_len: 0,
-            minimum_algorithm: Algorithm::RSASHA256,
+            algorithm_policy: AlgorithmPolicy::default(),
         }
     }
 
+    /// The current trust anchors, including the DNSKEYs added and revoked by rollovers since the
+    ///  handle was created
+    pub fn trust_anchors(&self) -> TrustAnchors {
+        self.read_trust_anchors().clone()
+    }
+
+    fn read_trust_anchors(&self) -> RwLockReadGuard<'_, TrustAnchors> {
+        self.trust_anchors
+            .read()
+            .expect("trust anchors lock poisoned")
+    }
+
+    /// Sets the algorithms of the signatures which are accepted, by default all are
+    ///
+    /// # Arguments
+    /// * `algorithm_policy` - the allowed algorithms, see `AlgorithmPolicy`
+    pub fn set_algorithm_policy(&mut self, algorithm_policy: AlgorithmPolicy) {
+        self.algorithm_policy = algorithm_policy;
+    }
+
     /// An internal function used to clone the handle, but maintain some information back to the
     ///  original handle, such as the request_depth such that infinite recursion does
     ///  not occur.
@@ -89,9 +145,11 @@ where
         SecureDnsHandle {
             handle: self.handle.clone(),
             trust_anchor: Arc::clone(&self.trust_anchor),
+            trust_anchors: Arc::clone(&self.trust_anchors),
+            insecure_proofs: Arc::clone(&self.insecure_proofs),
             request_depth: self.request_depth + 1,
             minimum_key_len: self.minimum_key_len,
-            minimum_algorithm: self.minimum_algorithm,
+            algorithm_policy: self.algorithm_policy,
         }
     }
 }
@@ -122,88 +180,518 @@ impl<H: DnsHandle> DnsHandle for SecureDnsHandle<H> {
                 .cloned()
                 .expect("no queries in request");
             let handle: SecureDnsHandle<H> = self.clone_with_context();
+            let dns_class = prepare_request(&mut request);
 
-            // TODO: cache response of the server about understood algorithms
-            #[cfg(feature = "dnssec")]
-            {
-                let edns = request.edns_mut();
-
-                edns.set_dnssec_ok(true);
+            return Box::new(self.handle.send(request).and_then(move |message_response| {
+                verify_response(&handle, query, dns_class, message_response)
+            }));
+        }
 
-                // send along the algorithms which are supported by this handle
-                let mut algorithms = SupportedAlgorithms::new();
-                #[cfg(feature = "ring")]
-                {
-                    algorithms.set(Algorithm::ED25519);
-                }
-                algorithms.set(Algorithm::ECDSAP256SHA256);
-                algorithms.set(Algorithm::ECDSAP384SHA384);
-                algorithms.set(Algorithm::RSASHA256);
+        Box::new(self.handle.send(request))
+    }
+}
 
-                let dau = EdnsOption::DAU(algorithms);
-                let dhu = EdnsOption::DHU(algorithms);
+impl<H> SecureDnsHandle<H>
+where
+    H: DnsHandle + 'static,
+{
+    /// Sends the request, returning the response along with its `Validity`
+    ///
+    /// Unlike `send()`, a response which fails validation is not an error, it is returned
+    ///  unvalidated and `Validity::Bogus` with the reason of the failure. An unsigned response is
+    ///  `Validity::Insecure` if DS queries prove an unsigned delegation to its zone, and
+    ///  `Validity::Indeterminate` if there is no trust anchor for it. A response signed only with
+    ///  algorithms disallowed by the `AlgorithmPolicy` is `Validity::Bogus`, unless the policy
+    ///  allows it to be `Validity::Insecure` and its chain of trust only lists disallowed
+    ///  algorithms. A `Validity::Secure` response is filtered down to the
+    ///  validated records, as with `send()`.
+    ///
+    /// Responses to operations other than Query are not validated, and are
+    ///  `Validity::Indeterminate`.
+    pub fn send_with_validity<R: Into<DnsRequest>>(
+        &mut self,
+        request: R,
+    ) -> Box<dyn Future<Item = (DnsResponse, Validity), Error = ProtoError> + Send> {
+        let mut request = request.into();
 
-                edns.set_option(dau);
-                edns.set_option(dhu);
-            }
+        if self.request_depth > 20 {
+            return Box::new(failed(ProtoError::from("exceeded max validation depth")));
+        }
 
-            request.set_authentic_data(true);
-            request.set_checking_disabled(false);
-            let dns_class = request
+        if let OpCode::Query = request.op_code() {
+            let query = request
                 .queries()
                 .first()
-                .map_or(DNSClass::IN, Query::query_class);
-
-            return Box::new(
-                self.handle
-                    .send(request)
-                    .and_then(move |message_response| {
-                        // group the record sets by name and type
-                        //  each rrset type needs to validated independently
-                        debug!("validating message_response: {}", message_response.id());
-                        verify_rrsets(&handle, message_response, dns_class)
+                .cloned()
+                .expect("no queries in request");
+            let handle: SecureDnsHandle<H> = self.clone_with_context();
+            let insecure_handle: SecureDnsHandle<H> = self.clone();
+            let dns_class = prepare_request(&mut request);
+
+            return Box::new(self.handle.send(request).and_then(move |message_response| {
+                verify_response(&handle, query, dns_class, message_response.clone())
+                    .map(|verified_message| (verified_message, Validity::Secure))
+                    .or_else(move |e| failed_validity(insecure_handle, message_response, e))
+            }));
+        }
+
+        Box::new(
+            self.handle
+                .send(request)
+                .map(|response| (response, Validity::Indeterminate)),
+        )
+    }
+
+    /// Returns true if there are trust anchors for the name
+    fn is_anchored(&self, name: &Name) -> bool {
+        !self.trust_anchor.is_empty() || self.read_trust_anchors().is_anchored(name)
+    }
+}
+
+/// Requests DNSSec records and validation, returning the class of the query
+fn prepare_request(request: &mut DnsRequest) -> DNSClass {
+    // TODO: cache response of the server about understood algorithms
+    #[cfg(feature = "dnssec")]
+    {
+        let edns = request.edns_mut();
+
+        edns.set_dnssec_ok(true);
+
+        // send along the algorithms which are supported by this handle, the AlgorithmPolicy is
+        //  applied to the signatures of the response, so that disallowed signatures are not
+        //  mistaken for missing ones
+        let mut algorithms = SupportedAlgorithms::new();
+        #[cfg(feature = "ring")]
+        {
+            algorithms.set(Algorithm::ED25519);
+        }
+        algorithms.set(Algorithm::ECDSAP256SHA256);
+        algorithms.set(Algorithm::ECDSAP384SHA384);
+        algorithms.set(Algorithm::RSASHA256);
+        algorithms.set(Algorithm::RSASHA512);
+        algorithms.set(Algorithm::RSASHA1);
+        algorithms.set(Algorithm::RSASHA1NSEC3SHA1);
+
+        let dau = EdnsOption::DAU(algorithms);
+        let dhu = EdnsOption::DHU(algorithms);
+
+        edns.set_option(dau);
+        edns.set_option(dhu);
+    }
+
+    request.set_authentic_data(true);
+    request.set_checking_disabled(false);
+    request
+        .queries()
+        .first()
+        .map_or(DNSClass::IN, Query::query_class)
+}
+
+/// Validates all the rrsets of the response to the query, and the denial of existence of
+///  responses without answers
+fn verify_response<H: DnsHandle>(
+    handle: &SecureDnsHandle<H>,
+    query: Query,
+    dns_class: DNSClass,
+    message_response: DnsResponse,
+) -> Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send> {
+    // group the record sets by name and type
+    //  each rrset type needs to validated independently
+    debug!("validating message_response: {}", message_response.id());
+
+    // the answers expanded from a wildcard, i.e. with more labels than their RRSIGs at the same
+    //  owner, with the labels of the wildcard and its zone
+    let answers = message_response.answers();
+    let mut wildcards = answers
+        .iter()
+        .filter_map(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some((rrsig, sig)),
+            _ => None,
+        })
+        .flat_map(|(rrsig, sig)| {
+            answers
+                .iter()
+                .filter(move |rr| {
+                    rr.name() == rrsig.name()
+                        && rr.rr_type() == sig.type_covered()
+                        && rr.name().num_labels() > sig.num_labels()
+                })
+                .map(move |rr| {
+                    (
+                        rr.name().clone(),
+                        sig.type_covered(),
+                        sig.num_labels(),
+                        sig.signer_name().clone(),
+                    )
+                })
+        })
+        .collect::<Vec<_>>();
+    wildcards.dedup();
+
+    Box::new(
+        verify_rrsets(handle, message_response, dns_class).and_then(move |verified_message| {
+            // a wildcard answer requires the proof that there was no exact match
+            for (name, record_type, num_labels, zone) in &wildcards {
+                let is_answer = verified_message
+                    .answers()
+                    .iter()
+                    .any(|rr| rr.name() == name && rr.rr_type() == *record_type);
+                let proofs = verified_message
+                    .name_servers()
+                    .iter()
+                    .filter(|rr| {
+                        is_dnssec(rr, DNSSECRecordType::NSEC)
+                            || is_dnssec(rr, DNSSECRecordType::NSEC3)
                     })
-                    .and_then(move |verified_message| {
-                        // at this point all of the message is verified.
-                        //  This is where NSEC (and possibly NSEC3) validation occurs
-                        // As of now, only NSEC is supported.
-                        if verified_message.answers().is_empty() {
-                            // get SOA name
-                            let soa_name = if let Some(soa_name) = verified_message
-                                .name_servers()
-                                .iter()
-                                // there should only be one
-                                .find(|rr| rr.record_type() == RecordType::SOA)
-                                .map(Record::name)
-                            {
-                                soa_name
-                            } else {
-                                return Err(ProtoError::from(
-                                    "could not validate negative response missing SOA",
-                                ));
-                            };
-
-                            let nsecs = verified_message
-                                .name_servers()
-                                .iter()
-                                .filter(|rr| is_dnssec(rr, DNSSECRecordType::NSEC))
-                                .collect::<Vec<_>>();
-
-                            if !verify_nsec(&query, soa_name, nsecs.as_slice()) {
-                                // TODO change this to remove the NSECs, like we do for the others?
-                                return Err(ProtoError::from(
-                                    "could not validate negative response with NSEC",
-                                ));
-                            }
+                    .collect::<Vec<_>>();
+
+                if is_answer && !verify_wildcard(name, *num_labels, zone, proofs.as_slice()) {
+                    return Err(bogus(name, *record_type, BogusReason::InvalidDenial));
+                }
+            }
+
+            // at this point all of the message is verified.
+            //  This is where NSEC and NSEC3 validation occurs
+            if verified_message.answers().is_empty() {
+                // get SOA name
+                let soa_name = if let Some(soa_name) = verified_message
+                    .name_servers()
+                    .iter()
+                    // there should only be one
+                    .find(|rr| rr.record_type() == RecordType::SOA)
+                    .map(Record::name)
+                {
+                    soa_name
+                } else {
+                    return Err(bogus(
+                        query.name(),
+                        query.query_type(),
+                        BogusReason::InvalidDenial,
+                    ));
+                };
+
+                let nsecs = verified_message
+                    .name_servers()
+                    .iter()
+                    .filter(|rr| is_dnssec(rr, DNSSECRecordType::NSEC))
+                    .collect::<Vec<_>>();
+
+                // zones are either signed with NSEC or with NSEC3
+                #[cfg(any(feature = "openssl", feature = "ring"))]
+                {
+                    let nsec3s = verified_message
+                        .name_servers()
+                        .iter()
+                        .filter(|rr| is_dnssec(rr, DNSSECRecordType::NSEC3))
+                        .collect::<Vec<_>>();
+
+                    if nsecs.is_empty() && !nsec3s.is_empty() {
+                        if !verify_nsec3(&query, soa_name, nsec3s.as_slice()) {
+                            return Err(bogus(
+                                query.name(),
+                                query.query_type(),
+                                BogusReason::InvalidDenial,
+                            ));
                         }
 
-                        Ok(verified_message)
-                    }),
-            );
+                        return Ok(verified_message);
+                    }
+                }
+
+                if !verify_nsec(&query, soa_name, nsecs.as_slice()) {
+                    // TODO change this to remove the NSECs, like we do for the others?
+                    return Err(bogus(
+                        query.name(),
+                        query.query_type(),
+                        BogusReason::InvalidDenial,
+                    ));
+                }
+            }
+
+            Ok(verified_message)
+        }),
+    )
+}
+
+/// The error of an rrset which failed validation
+fn bogus(name: &Name, record_type: RecordType, reason: BogusReason) -> ProtoError {
+    ProtoErrorKind::Bogus {
+        name: name.clone(),
+        record_type,
+        reason,
+    }
+    .into()
+}
+
+/// Determines the validity of a response from the error of its validation
+///
+/// Errors which are not validation failures, e.g. failed queries for DNSKEYs, are returned.
+fn failed_validity<H: DnsHandle>(
+    handle: SecureDnsHandle<H>,
+    message_response: DnsResponse,
+    error: ProtoError,
+) -> Box<dyn Future<Item = (DnsResponse, Validity), Error = ProtoError> + Send> {
+    let validity = match *error.kind() {
+        ProtoErrorKind::Bogus {
+            reason: BogusReason::DisallowedAlgorithm,
+            ref name,
+            ..
+        } if handle.algorithm_policy.disallowed_insecure() => {
+            return Box::new(verify_disallowed(&handle, name).map(move |insecure| {
+                if insecure {
+                    (message_response, Validity::Insecure)
+                } else {
+                    (
+                        message_response,
+                        Validity::Bogus(BogusReason::DisallowedAlgorithm),
+                    )
+                }
+            }));
         }
+        ProtoErrorKind::Bogus { reason, .. } => Validity::Bogus(reason),
+        ProtoErrorKind::RrsigsNotPresent { ref name, .. } if handle.is_anchored(name) => {
+            return Box::new(verify_insecure(&handle, name).map(move |insecure| {
+                if insecure {
+                    (message_response, Validity::Insecure)
+                } else {
+                    (
+                        message_response,
+                        Validity::Bogus(BogusReason::MissingSignature),
+                    )
+                }
+            }));
+        }
+        ProtoErrorKind::RrsigsNotPresent { .. } => Validity::Indeterminate,
+        _ => return Box::new(failed(error)),
+    };
 
-        Box::new(self.handle.send(request))
+    Box::new(finished((message_response, validity)))
+}
+
+/// Returns true if there is a validated proof of an unsigned delegation at the name or one of its
+///  ancestors, i.e. an NSEC or NSEC3 record of the parent zone with the NS type, but without the
+///  DS and SOA types
+///
+/// ```text
+/// RFC 4035             DNSSEC Protocol Modifications            March 2005
+///
+/// 5.2.  Authenticating Referrals
+///
+///    If the validator does not have a DS RR for the child zone,
+///    the validator MUST check whether the DS RRset is absent
+///    ...
+///    If the resolver accepts the NSEC RR as a valid proof that no DS RRset
+///    exists, the child zone is unsigned and the resolver has no chain of
+///    trust to the child zone.
+/// ```
+///
+/// The results of the DS queries are cached for the smallest TTL of their responses.
+fn verify_insecure<H: DnsHandle>(
+    handle: &SecureDnsHandle<H>,
+    name: &Name,
+) -> Box<dyn Future<Item = bool, Error = ProtoError> + Send> {
+    let mut zones = (1..=name.num_labels())
+        .map(|num_labels| name.trim_to(num_labels as usize))
+        .collect::<Vec<_>>();
+
+    // the zones with a cached result are not queried again
+    {
+        let insecure_proofs = handle
+            .insecure_proofs
+            .lock()
+            .expect("insecure proofs lock poisoned");
+        let now = Instant::now();
+        let cached = |zone: &Name| {
+            insecure_proofs
+                .get(zone)
+                .filter(|&&(_, valid_until)| now < valid_until)
+                .map(|&(proof, _)| proof)
+        };
+
+        if zones.iter().any(|zone| cached(zone) == Some(true)) {
+            return Box::new(finished(true));
+        }
+        zones.retain(|zone| cached(zone).is_none());
+    }
+
+    let proofs = zones
+        .into_iter()
+        .map(|zone| {
+            let insecure_proofs = Arc::clone(&handle.insecure_proofs);
+            let mut handle = handle.clone();
+            handle
+                .lookup(
+                    Query::query(zone.clone(), RecordType::DNSSEC(DNSSECRecordType::DS)),
+                    DnsRequestOptions::default(),
+                )
+                .map(move |ds_message| {
+                    let proof = is_insecure_delegation(&zone, &ds_message);
+                    cache_insecure_proof(&insecure_proofs, zone, proof, &ds_message);
+                    proof
+                })
+                .or_else(|_| Ok(false))
+        })
+        .collect::<Vec<_>>();
+
+    Box::new(future::join_all(proofs).map(|proofs| proofs.into_iter().any(|proof| proof)))
+}
+
+/// Caches the result of the DS query of the zone for the smallest TTL of the response
+fn cache_insecure_proof(
+    insecure_proofs: &Mutex<InsecureProofs>,
+    zone: Name,
+    proof: bool,
+    ds_message: &DnsResponse,
+) {
+    let ttl = ds_message
+        .answers()
+        .iter()
+        .chain(ds_message.name_servers())
+        .map(Record::ttl)
+        .min()
+        .unwrap_or(0);
+    if ttl == 0 {
+        return;
+    }
+
+    let mut insecure_proofs = insecure_proofs
+        .lock()
+        .expect("insecure proofs lock poisoned");
+
+    // the cache only saves queries, when it is full it starts over
+    if insecure_proofs.len() >= MAX_INSECURE_PROOFS {
+        insecure_proofs.clear();
     }
+
+    let valid_until = Instant::now() + Duration::from_secs(u64::from(ttl));
+    insecure_proofs.insert(zone, (proof, valid_until));
+}
+
+/// Returns true if the trust anchors or the validated DS rrset of the name, or of one of its
+///  ancestors, only list algorithms which are disallowed by the `AlgorithmPolicy`
+///
+/// The validator then has no supported chain of trust to the name, which is insecure, see
+///  `AlgorithmPolicy`. Records which are only signed with disallowed algorithms, but are in a
+///  zone with allowed algorithms, may be forged and are not insecure.
+fn verify_disallowed<H: DnsHandle>(
+    handle: &SecureDnsHandle<H>,
+    name: &Name,
+) -> Box<dyn Future<Item = bool, Error = ProtoError> + Send> {
+    let algorithm_policy = handle.algorithm_policy;
+    let only_disallowed = move |algorithms: &[Algorithm]| {
+        !algorithms.is_empty()
+            && algorithms
+                .iter()
+                .all(|algorithm| !algorithm_policy.is_allowed(*algorithm))
+    };
+
+    let zones = (1..=name.num_labels())
+        .map(|num_labels| name.trim_to(num_labels as usize))
+        .collect::<Vec<_>>();
+
+    {
+        let trust_anchors = handle.read_trust_anchors();
+        let anchored_disallowed = zones.iter().any(|zone| {
+            let algorithms = trust_anchors
+                .ds(zone)
+                .iter()
+                .map(|ds| *ds.algorithm())
+                .chain(trust_anchors.dnskeys(zone).iter().map(DNSKEY::algorithm))
+                .collect::<Vec<_>>();
+            only_disallowed(&algorithms)
+        });
+        if anchored_disallowed {
+            return Box::new(finished(true));
+        }
+    }
+
+    let proofs = zones
+        .into_iter()
+        .map(|zone| {
+            let mut handle = handle.clone();
+            handle
+                .lookup(
+                    Query::query(zone, RecordType::DNSSEC(DNSSECRecordType::DS)),
+                    DnsRequestOptions::default(),
+                )
+                .map(move |ds_message| {
+                    let algorithms = ds_message
+                        .answers()
+                        .iter()
+                        .filter_map(|rr| rr.rdata().as_dnssec().and_then(DNSSECRData::as_ds))
+                        .map(|ds| *ds.algorithm())
+                        .collect::<Vec<_>>();
+                    only_disallowed(&algorithms)
+                })
+                .or_else(|_| Ok(false))
+        })
+        .collect::<Vec<_>>();
+
+    Box::new(future::join_all(proofs).map(|proofs| proofs.into_iter().any(|proof| proof)))
+}
+
+/// Returns true if the validated response to a DS query proves the zone is an unsigned delegation
+fn is_insecure_delegation(zone: &Name, ds_message: &DnsResponse) -> bool {
+    if ds_message
+        .answers()
+        .iter()
+        .any(|rr| is_dnssec(rr, DNSSECRecordType::DS))
+    {
+        return false;
+    }
+
+    let is_delegation = |types: &[RecordType]| {
+        types.contains(&RecordType::NS)
+            && !types.contains(&RecordType::DNSSEC(DNSSECRecordType::DS))
+            && !types.contains(&RecordType::SOA)
+    };
+
+    let is_nsec_delegation = ds_message.name_servers().iter().any(|rr| {
+        rr.name() == zone
+            && rr
+                .rdata()
+                .as_dnssec()
+                .and_then(DNSSECRData::as_nsec)
+                .into_iter()
+                .any(|nsec| is_delegation(nsec.type_bit_maps()))
+    });
+    if is_nsec_delegation {
+        return true;
+    }
+
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    {
+        // the NSEC3 records are of the parent zone, the signer of the DS record
+        let nsec3s = ds_message
+            .name_servers()
+            .iter()
+            .filter(|rr| is_dnssec(rr, DNSSECRecordType::NSEC3))
+            .collect::<Vec<_>>();
+        let parent = match ds_message
+            .name_servers()
+            .iter()
+            .find(|rr| rr.record_type() == RecordType::SOA)
+        {
+            Some(soa) => soa.name(),
+            None => return false,
+        };
+        let nsec3s = match Nsec3Records::new(parent, nsec3s.as_slice()) {
+            Some(nsec3s) => nsec3s,
+            None => return false,
+        };
+
+        // without an NSEC3 record for the delegation, it must be in an opt-out span
+        match nsec3s.matching(zone) {
+            Some(rdata) => is_delegation(rdata.type_bit_maps()),
+            None => nsec3s
+                .closest_encloser(parent, zone)
+                .into_iter()
+                .any(|(_, next_closer)| next_closer.opt_out()),
+        }
+    }
+
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    false
 }
 
 /// A future to verify all RRSets in a returned Message.
@@ -434,14 +922,29 @@ where
         }
     }
 
+    // the RRSIGs of a DNSKEY rrset are checked again for the rollovers of the trust anchors
+    let dnskey_sigs = if let RecordType::DNSSEC(DNSSECRecordType::DNSKEY) = rrset.record_type {
+        rrsigs
+            .iter()
+            .filter_map(|rrsig| match *rrsig.rdata() {
+                RData::DNSSEC(DNSSECRData::SIG(ref sig)) if is_current(sig) => Some(sig.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+
     // standard validation path
     Box::new(
         verify_default_rrset(&handle.clone_with_context(), rrset, rrsigs)
-            .and_then(|rrset|
+            .and_then(move |rrset|
           // POST validation
           match rrset.record_type {
-            RecordType::DNSSEC(DNSSECRecordType::DNSKEY) =>
-                verify_dnskey_rrset(handle, rrset),
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => {
+                update_trust_anchors(&handle, &rrset, &dnskey_sigs);
+                verify_dnskey_rrset(handle, rrset)
+            }
             // RecordType::DNSSEC(DNSSECRecordType::DS) => verify_ds_rrset(handle, name, record_type, record_class, rrset, rrsigs),
             _ => Box::new(finished(rrset)),
           })
@@ -452,11 +955,33 @@ where
     )
 }
 
+/// Updates the DNSKEY trust anchors of the zone with a DNSKEY rrset, see
+///  `TrustAnchors::update_dnskeys`, which only uses the rrset if it is signed by a trusted key
+fn update_trust_anchors<H>(handle: &SecureDnsHandle<H>, rrset: &Rrset, rrsigs: &[SIG])
+where
+    H: DnsHandle,
+{
+    if !handle.read_trust_anchors().is_anchored(&rrset.name) {
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+
+    handle
+        .trust_anchors
+        .write()
+        .expect("trust anchors lock poisoned")
+        .update_dnskeys(&rrset.name, rrset.record_class, &rrset.records, rrsigs, now);
+}
+
 /// Verifies a dnskey rrset
 ///
-/// This first checks to see if the key is in the set of trust_anchors. If so then it's returned
-///  as a success. Otherwise, a query is sent to get the DS record, and the DNSKEY is validated
-///  against the DS record.
+/// This first checks to see if the key is in the set of trust_anchors, or is covered by a DS
+///  trust anchor of the zone. If so then it's returned as a success. Otherwise, a query is sent
+///  to get the DS record, and the DNSKEY is validated against the DS record.
 fn verify_dnskey_rrset<H>(
     mut handle: SecureDnsHandle<H>,
     rrset: Rrset,
@@ -487,6 +1012,7 @@ where
                 if handle
                     .trust_anchor
                     .contains_dnskey_bytes(rdata.public_key())
+                    || handle.read_trust_anchors().is_trusted(&rrset.name, rdata)
                 {
                     debug!("in trust_anchor");
                     Some(i)
@@ -553,9 +1079,11 @@ where
                 debug!("validated dnskey: {}, {}", rrset.name, rrset.records.len());
                 Ok(rrset)
             } else {
-                Err(ProtoError::from(ProtoErrorKind::Message(
-                    "Could not validate all DNSKEYs",
-                )))
+                Err(bogus(
+                    &rrset.name,
+                    rrset.record_type,
+                    BogusReason::BrokenChain,
+                ))
             }
         });
 
@@ -642,6 +1170,42 @@ where
         rrset.name, rrset.record_type
     );
 
+    // signatures outside of their validity period are not used, RFC 4035 section 5.3.1
+    let has_rrsigs = !rrsigs.is_empty();
+    let rrsigs = rrsigs
+        .into_iter()
+        .filter(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => is_current(sig),
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    if has_rrsigs && rrsigs.is_empty() {
+        return Box::new(failed(bogus(
+            &rrset.name,
+            rrset.record_type,
+            BogusReason::SignatureExpired,
+        )));
+    }
+
+    // signatures of algorithms which are not allowed are not used either
+    let has_rrsigs = !rrsigs.is_empty();
+    let rrsigs = rrsigs
+        .into_iter()
+        .filter(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => {
+                handle.algorithm_policy.is_allowed(sig.algorithm())
+            }
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    if has_rrsigs && rrsigs.is_empty() {
+        return Box::new(failed(bogus(
+            &rrset.name,
+            rrset.record_type,
+            BogusReason::DisallowedAlgorithm,
+        )));
+    }
+
     // Special case for self-signed DNSKEYS, validate with itself...
     if rrsigs
         .iter()
@@ -690,7 +1254,11 @@ where
                     })
                     .next()
                     .ok_or_else(|| {
-                        ProtoError::from(ProtoErrorKind::Message("self-signed dnskey is invalid"))
+                        bogus(
+                            &rrset.name,
+                            rrset.record_type,
+                            BogusReason::InvalidSignature,
+                        )
                     }),
             )
             .map(move |rrset| Arc::try_unwrap(rrset).expect("unable to unwrap Arc")),
@@ -706,40 +1274,64 @@ where
     //         susceptible until that algorithm is removed as an option.
     //        dns over TLS will mitigate this.
     //  TODO: strip RRSIGS to accepted algorithms and make algorithms configurable.
-    let verifications = rrsigs.into_iter()
-                            // this filter is technically unnecessary, can probably remove it...
-                            .filter(|rrsig| is_dnssec(rrsig, DNSSECRecordType::RRSIG))
-                            .map(|rrsig|
-                              if let RData::DNSSEC(DNSSECRData::SIG(sig)) = rrsig.unwrap_rdata() {
-                                // setting up the context explicitly.
-                                sig
-                              } else {
-                                panic!("expected a SIG here");
-                              }
-                            )
-                            .map(|sig| {
-                              let rrset = Arc::clone(&rrset);
-                              let mut handle = handle.clone_with_context();
-
-                              handle.lookup(Query::query(sig.signer_name().clone(), RecordType::DNSSEC(DNSSECRecordType::DNSKEY)),
-                              DnsRequestOptions::default())
-                                    .and_then(move |message|
-                                      // DNSKEYs are validated by the inner query
-                                      message.answers()
-                                             .iter()
-                                             .filter(|r| is_dnssec(r, DNSSECRecordType::DNSKEY))
-                                             .find(|r|
-                                               if let RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) = *r.rdata() {
-                                                 verify_rrset_with_dnskey(dnskey, &sig, &rrset).is_ok()
-                                               } else {
-                                                 panic!("expected a DNSKEY here: {:?}", r.rdata());
-                                               }
-                                             )
-                                             .map(|_| rrset)
-                                             .ok_or_else(|| ProtoError::from(ProtoErrorKind::Message("validation failed")))
-                                    )
-                            })
-                            .collect::<Vec<_>>();
+    let verifications = rrsigs
+        .into_iter()
+        // this filter is technically unnecessary, can probably remove it...
+        .filter(|rrsig| is_dnssec(rrsig, DNSSECRecordType::RRSIG))
+        .map(|rrsig| {
+            if let RData::DNSSEC(DNSSECRData::SIG(sig)) = rrsig.unwrap_rdata() {
+                // setting up the context explicitly.
+                sig
+            } else {
+                panic!("expected a SIG here");
+            }
+        })
+        .map(|sig| {
+            let rrset = Arc::clone(&rrset);
+            let mut handle = handle.clone_with_context();
+
+            handle
+                .lookup(
+                    Query::query(
+                        sig.signer_name().clone(),
+                        RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+                    ),
+                    DnsRequestOptions::default(),
+                )
+                .and_then(move |message| {
+                    // DNSKEYs are validated by the inner query
+                    let mut dnskeys = message
+                        .answers()
+                        .iter()
+                        .filter(|r| is_dnssec(r, DNSSECRecordType::DNSKEY))
+                        .map(|r| {
+                            if let RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) = *r.rdata() {
+                                dnskey
+                            } else {
+                                panic!("expected a DNSKEY here: {:?}", r.rdata());
+                            }
+                        });
+
+                    if dnskeys
+                        .clone()
+                        .any(|dnskey| verify_rrset_with_dnskey(dnskey, &sig, &rrset).is_ok())
+                    {
+                        return Ok(rrset);
+                    }
+
+                    // a key of the signature which does not verify, or no key of the signature at all
+                    let reason = if dnskeys.any(|dnskey| {
+                        dnskey.algorithm() == sig.algorithm()
+                            && dnskey.calculate_key_tag().ok() == Some(sig.key_tag())
+                    }) {
+                        BogusReason::InvalidSignature
+                    } else {
+                        BogusReason::MissingKey
+                    };
+                    Err(bogus(&rrset.name, rrset.record_type, reason))
+                })
+        })
+        .collect::<Vec<_>>();
 
     // if there are no available verifications, then we are in a failed state.
     if verifications.is_empty() {
@@ -760,6 +1352,60 @@ where
     Box::new(select)
 }
 
+/// Returns true if the current time is within the validity period of the SIG
+fn is_current(sig: &SIG) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() as u32)
+        .unwrap_or(0);
+
+    is_current_at(sig, now)
+}
+
+/// Returns true if the time is within the validity period of the SIG
+///
+/// The times are compared with the serial number arithmetic of
+///  [RFC 1982](https://tools.ietf.org/html/rfc1982), as required by
+///  [RFC 4034](https://tools.ietf.org/html/rfc4034#section-3.1.5), so that they keep working
+///  when the 32 bit times wrap around.
+fn is_current_at(sig: &SIG, now: u32) -> bool {
+    // a <= b, if b is at most 2^31 - 1 after a
+    let serial_le = |a: u32, b: u32| b.wrapping_sub(a) < 0x8000_0000;
+
+    serial_le(sig.sig_inception(), now) && serial_le(now, sig.sig_expiration())
+}
+
+#[test]
+fn test_is_current_at() {
+    let sig = |inception: u32, expiration: u32| {
+        SIG::new(
+            RecordType::A,
+            Algorithm::RSASHA256,
+            2,
+            3600,
+            expiration,
+            inception,
+            0,
+            Name::root(),
+            vec![],
+        )
+    };
+
+    let now = 1_000_000_000;
+    assert!(is_current_at(&sig(now - 60, now + 60), now));
+    assert!(is_current_at(&sig(now, now), now));
+    assert!(!is_current_at(&sig(now - 60, now - 1), now));
+    assert!(!is_current_at(&sig(now + 1, now + 60), now));
+
+    // the times wrap around after 2106
+    assert!(is_current_at(&sig(u32::max_value() - 60, 60), 0));
+    assert!(is_current_at(
+        &sig(u32::max_value() - 60, 60),
+        u32::max_value()
+    ));
+    assert!(!is_current_at(&sig(u32::max_value() - 60, 60), 61));
+}
+
 /// Verifies the given SIG of the RRSET with the DNSKEY.
 #[cfg(feature = "dnssec")]
 fn verify_rrset_with_dnskey(dnskey: &DNSKEY, sig: &SIG, rrset: &Rrset) -> ProtoResult<()> {
@@ -898,3 +1544,466 @@ pub fn verify_nsec(query: &Query, soa_name: &Name, nsecs: &[&Record]) -> bool {
         verify_nsec_coverage(&wildcard)
     }
 }
+
+/// Verifies that an answer expanded from a wildcard was not an exact match
+///
+/// ```text
+/// RFC 4035             DNSSEC Protocol Modifications            March 2005
+///
+/// 5.3.4.  Authenticating A Wildcard Expanded RRset Positive Response
+///
+///    If the number of labels in an RRset's owner name is greater than the
+///    Labels field of the covering RRSIG RR, then the RRset and its
+///    covering RRSIG RR were created as a result of wildcard expansion.
+///    Once the validator has verified the signature, as described in
+///    Section 5.3, it must take additional steps to verify the non-
+///    existence of an exact match or closer wildcard match for the query.
+///    Section 5.4 discusses these steps.
+/// ```
+///
+/// An NSEC record of the zone must cover the name. With NSEC3, a record of the zone must cover
+///  the next closer name, one label longer than the closest encloser of the wildcard, see
+///  [RFC 5155](https://tools.ietf.org/html/rfc5155#section-8.8).
+///
+/// # Arguments
+///
+/// * `name` - the owner name of the answer
+/// * `num_labels` - the labels of the RRSIG of the answer, i.e. of the wildcard
+/// * `zone` - the signer of the answer
+/// * `proofs` - the NSEC or NSEC3 records of the response
+fn verify_wildcard(name: &Name, num_labels: u8, zone: &Name, proofs: &[&Record]) -> bool {
+    let covers_name = proofs.iter().any(|nsec| {
+        nsec.rdata()
+            .as_dnssec()
+            .and_then(DNSSECRData::as_nsec)
+            .into_iter()
+            .any(|rdata| {
+                let next = rdata.next_domain_name();
+                // the name must be strictly greater than the owner, an exact match is not allowed
+                zone.zone_of(nsec.name())
+                    && nsec.name() < name
+                    && (name < next || next <= nsec.name())
+            })
+    });
+    if covers_name {
+        return true;
+    }
+
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    {
+        let next_closer = name.trim_to(num_labels as usize + 1);
+        Nsec3Records::new(zone, proofs)
+            .and_then(|nsec3s| nsec3s.covering(&next_closer))
+            .is_some()
+    }
+
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    {
+        let _ = num_labels;
+        false
+    }
+}
+
+#[test]
+fn test_verify_wildcard() {
+    use crate::rr::dnssec::rdata::NSEC;
+
+    let nsec = |owner: &str, next: &str| -> Record {
+        let rdata = NSEC::new(
+            Name::from_ascii(next).unwrap(),
+            vec![RecordType::MX, RecordType::DNSSEC(DNSSECRecordType::RRSIG)],
+        );
+        Record::from_rdata(
+            Name::from_ascii(owner).unwrap(),
+            3600,
+            RData::DNSSEC(DNSSECRData::NSEC(rdata)),
+        )
+    };
+    let example = Name::from_ascii("example.").unwrap();
+
+    // RFC 4035 appendix B.6, a.z.w.example. MX expanded from *.w.example.
+    let name = Name::from_ascii("a.z.w.example.").unwrap();
+    let proof = nsec("x.y.w.example.", "xx.example.");
+    assert!(verify_wildcard(&name, 2, &example, &[&proof]));
+
+    // the proof must be of the zone, and must not match the name
+    let other = Name::from_ascii("other.").unwrap();
+    assert!(!verify_wildcard(&name, 2, &other, &[&proof]));
+    let exact = nsec("a.z.w.example.", "xx.example.");
+    assert!(!verify_wildcard(&name, 2, &example, &[&exact]));
+    let before = nsec("x.w.example.", "x.y.w.example.");
+    assert!(!verify_wildcard(&name, 2, &example, &[&before]));
+    assert!(!verify_wildcard(&name, 2, &example, &[]));
+}
+
+#[test]
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn test_verify_wildcard_nsec3() {
+    use crate::rr::dnssec::owner_name_hash;
+    use crate::rr::dnssec::rdata::NSEC3;
+    use crate::rr::dnssec::Nsec3HashAlgorithm;
+
+    let nsec3 = |owner: &str, next: &str| -> Record {
+        let next = owner_name_hash(&Name::from_ascii(next).unwrap()).unwrap();
+        let rdata = NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            true,
+            12,
+            vec![0xAA, 0xBB, 0xCC, 0xDD],
+            next,
+            vec![RecordType::A, RecordType::DNSSEC(DNSSECRecordType::RRSIG)],
+        );
+        Record::from_rdata(
+            Name::from_ascii(owner).unwrap(),
+            3600,
+            RData::DNSSEC(DNSSECRData::NSEC3(rdata)),
+        )
+    };
+    let example = Name::from_ascii("example.").unwrap();
+
+    // RFC 5155 appendix B.4, a.z.w.example. MX expanded from *.w.example.
+    //  the proof covers the next closer name z.w.example.
+    let name = Name::from_ascii("a.z.w.example.").unwrap();
+    let proof = nsec3(
+        "q04jkcevqvmu85r014c7dkba38o0ji5r.example.",
+        "r53bq7cc2uvmubfu5ocmm6pers9tk9en",
+    );
+    assert!(verify_wildcard(&name, 2, &example, &[&proof]));
+
+    // which is not the next closer name of a wildcard at z.w.example.
+    assert!(!verify_wildcard(&name, 3, &example, &[&proof]));
+}
+
+/// The NSEC3 records of a zone in a response, by the hashes of their owner names
+#[cfg(any(feature = "openssl", feature = "ring"))]
+struct Nsec3Records<'a> {
+    records: Vec<(Vec<u8>, &'a crate::rr::dnssec::rdata::NSEC3)>,
+}
+
+#[cfg(any(feature = "openssl", feature = "ring"))]
+impl<'a> Nsec3Records<'a> {
+    /// The NSEC3 records of the zone, None if there are none, or if they don't all use the same
+    ///  hash algorithm, salt and iterations
+    fn new(zone: &Name, records: &[&'a Record]) -> Option<Self> {
+        use crate::rr::dnssec::owner_name_hash;
+
+        // the hashes from the owner names, only records of the zone are considered
+        let records: Vec<_> = records
+            .iter()
+            .filter(|nsec3| nsec3.name().base_name() == *zone)
+            .filter_map(|nsec3| {
+                let rdata = nsec3.rdata().as_dnssec().and_then(DNSSECRData::as_nsec3)?;
+                Some((owner_name_hash(nsec3.name())?, rdata))
+            })
+            .collect();
+
+        let (_, first) = records.first()?;
+        if records.iter().any(|(_, rdata)| {
+            rdata.hash_algorithm() != first.hash_algorithm()
+                || rdata.salt() != first.salt()
+                || rdata.iterations() != first.iterations()
+        }) {
+            return None;
+        }
+
+        Some(Nsec3Records { records })
+    }
+
+    fn hash(&self, name: &Name) -> Option<Vec<u8>> {
+        let (_, rdata) = self.records[0];
+        rdata
+            .hash_algorithm()
+            .hash(rdata.salt(), name, rdata.iterations())
+            .ok()
+            .map(|digest| digest.as_ref().to_vec())
+    }
+
+    /// The record with the hash of the name as its owner
+    fn matching(&self, name: &Name) -> Option<&'a crate::rr::dnssec::rdata::NSEC3> {
+        let hash = self.hash(name)?;
+        self.records
+            .iter()
+            .find(|(owner, _)| *owner == hash)
+            .map(|(_, rdata)| *rdata)
+    }
+
+    /// The record whose owner and next hashed owner name are on either side of the hash of the name
+    fn covering(&self, name: &Name) -> Option<&'a crate::rr::dnssec::rdata::NSEC3> {
+        let hash = self.hash(name)?;
+        self.records
+            .iter()
+            .find(|(owner, rdata)| {
+                let next = rdata.next_hashed_owner_name();
+                if owner.as_slice() < next {
+                    *owner < hash && hash.as_slice() < next
+                } else {
+                    // this is the last record, and wraps to the beginning of the zone
+                    *owner < hash || hash.as_slice() < next
+                }
+            })
+            .map(|(_, rdata)| *rdata)
+    }
+
+    /// The closest encloser proof for the name, returns the closest encloser and the record
+    ///  covering the next closer name
+    ///
+    /// ```text
+    /// RFC 5155                         NSEC3                        March 2008
+    ///
+    /// 8.3.  Closest Encloser Proof
+    ///
+    ///    For some NSEC3 responses, namely Name Error and Wildcard responses,
+    ///    the validator MUST verify the closest encloser proof for the QNAME
+    ///    ...
+    ///    Once the closest encloser has been discovered, the validator MUST
+    ///    check that the NSEC3 RR that has the closest encloser as the original
+    ///    owner name is from the proper zone.  The DNAME type bit must not be
+    ///    set and the NS type bit may only be set if the SOA type bit is set.
+    ///    If this is not the case, it would be an indication that an attacker
+    ///    is using them to falsely deny the existence of RRs for which the
+    ///    server is not authoritative.
+    /// ```
+    fn closest_encloser(
+        &self,
+        zone: &Name,
+        name: &Name,
+    ) -> Option<(Name, &'a crate::rr::dnssec::rdata::NSEC3)> {
+        if !zone.zone_of(name) {
+            return None;
+        }
+
+        // the closest encloser is the nearest ancestor with an NSEC3 record
+        let (closest_encloser, rdata) = (zone.num_labels()..name.num_labels())
+            .rev()
+            .map(|num_labels| name.trim_to(num_labels as usize))
+            .filter_map(|ancestor| Some((ancestor.clone(), self.matching(&ancestor)?)))
+            .next()?;
+
+        let types = rdata.type_bit_maps();
+        if types.contains(&RecordType::DNAME)
+            || (types.contains(&RecordType::NS) && !types.contains(&RecordType::SOA))
+        {
+            return None;
+        }
+
+        // the next closer name, one label longer, does not exist
+        let next_closer = name.trim_to(closest_encloser.num_labels() as usize + 1);
+        let covering = self.covering(&next_closer)?;
+
+        Some((closest_encloser, covering))
+    }
+}
+
+/// ```text
+/// RFC 5155                         NSEC3                        March 2008
+///
+/// 8.4.  Validating Name Error Responses
+///
+///    A validator MUST verify that there is a closest encloser proof for
+///    QNAME present in the response and that there is an NSEC3 RR that
+///    covers the wildcard at the closest encloser (i.e., the name formed by
+///    prepending the asterisk label to the closest encloser).
+///
+/// 8.5.  Validating No Data Responses, QTYPE is not DS
+///
+///    The validator MUST verify that an NSEC3 RR that matches QNAME is
+///    present and that both the QTYPE and the CNAME type are not set in its
+///    Type Bit Maps field.
+///
+/// 8.6.  Validating No Data Responses, QTYPE is DS
+///
+///    If there is an NSEC3 RR that matches QNAME, the validator MUST check
+///    that both the QTYPE and the CNAME type are not set in its Type Bit
+///    Maps field.
+///
+///    If there is no such NSEC3 RR, then the validator MUST verify that a
+///    closest provable encloser proof for QNAME is present in the response,
+///    and that the NSEC3 RR that covers the "next closer" name has the Opt-
+///    Out bit set.
+///
+/// 8.7.  Validating Wildcard No Data Responses
+///
+///    The validator MUST verify a closest encloser proof for QNAME and MUST
+///    find an NSEC3 RR present in the response that matches the wildcard
+///    name generated by prepending the asterisk label to the closest
+///    encloser.  Furthermore, the bit corresponding to the QTYPE MUST NOT
+///    be set in the wildcard matching NSEC3 RR.
+/// ```
+#[cfg(any(feature = "openssl", feature = "ring"))]
+#[doc(hidden)]
+pub fn verify_nsec3(query: &Query, soa_name: &Name, nsec3s: &[&Record]) -> bool {
+    let nsec3s = match Nsec3Records::new(soa_name, nsec3s) {
+        Some(nsec3s) => nsec3s,
+        None => return false,
+    };
+
+    // the name exists, but not with the type
+    if let Some(rdata) = nsec3s.matching(query.name()) {
+        return !rdata.type_bit_maps().contains(&query.query_type())
+            && !rdata.type_bit_maps().contains(&RecordType::CNAME);
+    }
+
+    let (closest_encloser, next_closer) = match nsec3s.closest_encloser(soa_name, query.name()) {
+        Some(proof) => proof,
+        None => return false,
+    };
+
+    // an unsigned delegation in an opt-out span, there is no NSEC3 record for it
+    if query.query_type() == RecordType::DNSSEC(DNSSECRecordType::DS) && next_closer.opt_out() {
+        return true;
+    }
+
+    // either there is no wildcard at the closest encloser, or it doesn't have the type
+    let wildcard = query
+        .name()
+        .trim_to(closest_encloser.num_labels() as usize + 1)
+        .into_wildcard();
+    match nsec3s.matching(&wildcard) {
+        Some(rdata) => !rdata.type_bit_maps().contains(&query.query_type()),
+        None => nsec3s.covering(&wildcard).is_some(),
+    }
+}
+
+#[test]
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn test_verify_nsec3() {
+    use crate::rr::dnssec::rdata::NSEC3;
+    use crate::rr::dnssec::{owner_name_hash, Nsec3HashAlgorithm};
+
+    // the example zone of RFC 5155 appendix A, NSEC3PARAM 1 0 12 aabbccdd
+    let nsec3 = |owner: &str, next: &str, types: &[RecordType]| -> Record {
+        let owner = Name::from_ascii(owner).unwrap();
+        let next = owner_name_hash(&Name::from_ascii(next).unwrap()).unwrap();
+        let rdata = NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            true,
+            12,
+            vec![0xAA, 0xBB, 0xCC, 0xDD],
+            next,
+            types.to_vec(),
+        );
+        Record::from_rdata(owner, 3600, RData::DNSSEC(DNSSECRData::NSEC3(rdata)))
+    };
+    let example = Name::from_ascii("example.").unwrap();
+
+    // appendix B.1, name error for a.c.x.w.example.
+    let apex = nsec3(
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.",
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+        &[RecordType::NS, RecordType::SOA, RecordType::MX],
+    );
+    let x_w = nsec3(
+        "b4um86eghhds6nea196smvmlo4ors995.example.",
+        "gjeqe526plbf1g8mklp59enfd789njgi",
+        &[RecordType::MX],
+    );
+    let a = nsec3(
+        "35mthgpgcu1qg68fab165klnsnk3dpvl.example.",
+        "b4um86eghhds6nea196smvmlo4ors995",
+        &[RecordType::NS],
+    );
+
+    let query = Query::query(Name::from_ascii("a.c.x.w.example.").unwrap(), RecordType::A);
+    assert!(verify_nsec3(&query, &example, &[&apex, &x_w, &a]));
+
+    // the next closer name, and the wildcard, must both be covered
+    assert!(!verify_nsec3(&query, &example, &[&x_w, &a]));
+    assert!(!verify_nsec3(&query, &example, &[&apex, &x_w]));
+    assert!(!verify_nsec3(&query, &example, &[&apex, &a]));
+
+    // appendix B.2, no data for ns1.example. MX
+    let ns1 = nsec3(
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr.example.",
+        "2vptu5timamqttgl4luu9kg21e0aor3s",
+        &[RecordType::A],
+    );
+
+    let query = Query::query(Name::from_ascii("ns1.example.").unwrap(), RecordType::MX);
+    assert!(verify_nsec3(&query, &example, &[&ns1]));
+
+    let query = Query::query(Name::from_ascii("ns1.example.").unwrap(), RecordType::A);
+    assert!(!verify_nsec3(&query, &example, &[&ns1]));
+}
+
+#[test]
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn test_verify_nsec3_opt_out() {
+    use crate::op::Message;
+    use crate::rr::dnssec::rdata::NSEC3;
+    use crate::rr::dnssec::{owner_name_hash, Nsec3HashAlgorithm};
+    use crate::rr::rdata::SOA;
+
+    // the example zone of RFC 5155 appendix A, NSEC3PARAM 1 0 12 aabbccdd
+    let nsec3 = |owner: &str, opt_out: bool, next: &str, types: &[RecordType]| -> Record {
+        let owner = Name::from_ascii(owner).unwrap();
+        let next = owner_name_hash(&Name::from_ascii(next).unwrap()).unwrap();
+        let rdata = NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            opt_out,
+            12,
+            vec![0xAA, 0xBB, 0xCC, 0xDD],
+            next,
+            types.to_vec(),
+        );
+        Record::from_rdata(owner, 3600, RData::DNSSEC(DNSSECRData::NSEC3(rdata)))
+    };
+    let example = Name::from_ascii("example.").unwrap();
+    let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+
+    // appendix B.3, the unsigned delegation c.example. is in the opt-out span of a.example.
+    let apex = nsec3(
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.",
+        true,
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+        &[RecordType::NS, RecordType::SOA, RecordType::MX],
+    );
+    let a = |opt_out: bool| {
+        nsec3(
+            "35mthgpgcu1qg68fab165klnsnk3dpvl.example.",
+            opt_out,
+            "b4um86eghhds6nea196smvmlo4ors995",
+            &[RecordType::NS, ds],
+        )
+    };
+
+    let c = Name::from_ascii("c.example.").unwrap();
+    let query = Query::query(c.clone(), ds);
+    assert!(verify_nsec3(&query, &example, &[&apex, &a(true)]));
+    assert!(!verify_nsec3(&query, &example, &[&apex, &a(false)]));
+
+    // only the nonexistence of DS records is proven by opt-out
+    let query = Query::query(c.clone(), RecordType::A);
+    assert!(!verify_nsec3(&query, &example, &[&apex, &a(true)]));
+
+    let soa = Record::from_rdata(
+        example.clone(),
+        3600,
+        RData::SOA(SOA::new(
+            Name::from_ascii("ns1.example.").unwrap(),
+            Name::from_ascii("bugs.x.w.example.").unwrap(),
+            1,
+            3600,
+            300,
+            3_600_000,
+            3600,
+        )),
+    );
+    let ds_message = |a: Record| {
+        let mut message = Message::new();
+        message.add_name_servers(vec![soa.clone(), apex.clone(), a]);
+        DnsResponse::from(message)
+    };
+    assert!(is_insecure_delegation(&c, &ds_message(a(true))));
+    assert!(!is_insecure_delegation(&c, &ds_message(a(false))));
+
+    // the delegation a.example. can't be the closest encloser of a name below it, even though
+    //  the name is covered by this record, which covers all of the hashes
+    let everything = nsec3(
+        "b4um86eghhds6nea196smvmlo4ors995.example.",
+        true,
+        "b4um86eghhds6nea196smvmlo4ors995",
+        &[RecordType::MX],
+    );
+    let query = Query::query(Name::from_ascii("z.a.example.").unwrap(), RecordType::A);
+    assert!(!verify_nsec3(&query, &example, &[&a(true), &everything]));
+}
@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
 
 use futures::{future, sync::mpsc, Async, Future, Poll, Stream};
@@ -9,8 +10,9 @@ use proto::{
     xfer::{DnsRequestOptions, RetryDnsHandle},
 };
 
-use config::{ResolverConfig, ResolverOpts};
+use config::{LookupIpStrategy, ResolverConfig, ResolverOpts};
 use dns_lru::{self, DnsLru};
+use fcrdns::FcrdnsFuture;
 use hosts::Hosts;
 use lookup::{Lookup, LookupEither, LookupFuture};
 use lookup_ip::LookupIpFuture;
@@ -32,6 +34,7 @@ use super::Request;
 pub(super) fn task(
     config: ResolverConfig,
     options: ResolverOpts,
+    conn_provider: StandardConnection,
     lru: Arc<Mutex<DnsLru>>,
     request_rx: mpsc::UnboundedReceiver<Request>,
 ) -> impl Future<Item = (), Error = ()> {
@@ -39,9 +42,14 @@ pub(super) fn task(
         debug!("trust-dns resolver running");
 
         let pool =
-            NameServerPool::<ConnectionHandle, StandardConnection>::from_config(&config, &options);
+            NameServerPool::<ConnectionHandle, StandardConnection>::from_config_with_provider(
+                &config,
+                &options,
+                conn_provider,
+            );
         let either;
-        let client = RetryDnsHandle::new(pool.clone(), options.attempts);
+        let client =
+            RetryDnsHandle::with_policy(pool.clone(), options.attempts, options.retry_policy);
         if options.validate {
             #[cfg(feature = "dnssec")]
             {
@@ -96,7 +104,12 @@ impl Task {
         LookupFuture::lookup(names, record_type, options, self.client_cache.clone())
     }
 
-    fn lookup_ip(&self, maybe_name: ProtoResult<Name>, maybe_ip: Option<RData>) -> LookupIpFuture {
+    fn lookup_ip(
+        &self,
+        maybe_name: ProtoResult<Name>,
+        maybe_ip: Option<RData>,
+        strategy: Option<LookupIpStrategy>,
+    ) -> LookupIpFuture {
         let mut finally_ip_addr: Option<Record> = None;
 
         // if host is a ip address, return directly.
@@ -135,7 +148,7 @@ impl Task {
 
         LookupIpFuture::lookup(
             names,
-            self.options.ip_strategy,
+            strategy.unwrap_or(self.options.ip_strategy),
             self.client_cache.clone(),
             DnsRequestOptions::default(),
             hosts,
@@ -143,6 +156,10 @@ impl Task {
         )
     }
 
+    fn fcrdns(&self, ip: IpAddr) -> FcrdnsFuture {
+        FcrdnsFuture::verify(ip, DnsRequestOptions::default(), self.client_cache.clone())
+    }
+
     fn push_name(name: Name, names: &mut Vec<Name>) {
         if !names.contains(&name) {
             names.push(name);
@@ -221,13 +238,18 @@ impl Future for Task {
                 Some(Request::Ip {
                     maybe_name,
                     maybe_ip,
+                    strategy,
                     tx,
                 }) => {
-                    let future = self.lookup_ip(maybe_name, maybe_ip);
+                    let future = self.lookup_ip(maybe_name, maybe_ip, strategy);
                     // tx.send() will return an error if the oneshot was canceled, but
                     // we don't actually care, so just drop the future.
                     let _ = tx.send(future);
                 }
+                Some(Request::Fcrdns { ip, tx }) => {
+                    let future = self.fcrdns(ip);
+                    let _ = tx.send(future);
+                }
             }
         }
     }
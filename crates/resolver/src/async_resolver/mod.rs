@@ -18,13 +18,15 @@ use futures::{
 use proto::error::ProtoResult;
 use proto::rr::domain::TryParseIp;
 use proto::rr::{IntoName, Name, RData, RecordType};
-use proto::xfer::DnsRequestOptions;
+use proto::xfer::{DnsRequestOptions, RandSource};
 
-use config::{ResolverConfig, ResolverOpts};
+use config::{LookupIpStrategy, ResolverConfig, ResolverOpts};
 use dns_lru::{self, DnsLru};
 use error::*;
+use fcrdns::FcrdnsFuture;
 use lookup::{self, LookupFuture};
-use lookup_ip::LookupIpFuture;
+use lookup_ip::{LookupIpFuture, ResolveAddrsFuture};
+use name_server::StandardConnection;
 
 mod background;
 
@@ -78,6 +80,12 @@ pub type BackgroundLookupIp = Background<LookupIpFuture>;
 /// Future returned by lookup requests to the background task.
 pub type BackgroundLookup<F = LookupFuture> = Background<LookupFuture, F>;
 
+/// Future returned by `resolve_addrs` requests to the background task.
+pub type BackgroundResolveAddrs = Background<LookupIpFuture, ResolveAddrsFuture>;
+
+/// Future returned by FCrDNS requests to the background task.
+pub type BackgroundFcrdns = Background<FcrdnsFuture>;
+
 /// Type alias for the complex inner part of a `Background` future.
 type BgInner<T, F, G> = future::Either<BgSend<F, G>, future::FutureResult<T, ResolveError>>;
 
@@ -103,8 +111,14 @@ enum Request {
     Ip {
         maybe_name: ProtoResult<Name>,
         maybe_ip: Option<RData>,
+        strategy: Option<LookupIpStrategy>,
         tx: oneshot::Sender<LookupIpFuture>,
     },
+    /// Requests forward-confirmed reverse DNS of an IP address.
+    Fcrdns {
+        ip: IpAddr,
+        tx: oneshot::Sender<FcrdnsFuture>,
+    },
 }
 
 macro_rules! lookup_fn {
@@ -161,7 +175,33 @@ impl AsyncResolver {
         let lru = DnsLru::new(options.cache_size, dns_lru::TtlConfig::from_opts(&options));
         let lru = Arc::new(Mutex::new(lru));
 
-        Self::with_cache(config, options, lru)
+        Self::with_cache(config, options, StandardConnection::default(), lru)
+    }
+
+    /// Construct a new `AsyncResolver` which draws the ids of its queries from `rand_source`.
+    ///
+    /// This is useful for reproducible query ids in tests, see `RandSource`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - configuration, name_servers, etc. for the Resolver
+    /// * `options` - basic lookup options for the resolver
+    /// * `rand_source` - the source of the query ids, instead of `OsRandSource`
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the new `AsyncResolver` and a future that drives the
+    /// background task that runs resolutions for the `AsyncResolver`.
+    pub fn with_rand_source(
+        config: ResolverConfig,
+        options: ResolverOpts,
+        rand_source: Arc<dyn RandSource>,
+    ) -> (Self, impl Future<Item = (), Error = ()>) {
+        let lru = DnsLru::new(options.cache_size, dns_lru::TtlConfig::from_opts(&options));
+        let lru = Arc::new(Mutex::new(lru));
+        let conn_provider = StandardConnection::with_rand_source(rand_source);
+
+        Self::with_cache(config, options, conn_provider, lru)
     }
 
     /// Construct a new `AsyncResolver` with the associated Client and configuration.
@@ -170,6 +210,7 @@ impl AsyncResolver {
     ///
     /// * `config` - configuration, name_servers, etc. for the Resolver
     /// * `options` - basic lookup options for the resolver
+    /// * `conn_provider` - creates the connections to the name servers
     /// * `lru` - the cache to be used with the resolver
     ///
     /// # Returns
@@ -181,10 +222,11 @@ impl AsyncResolver {
     pub(crate) fn with_cache(
         config: ResolverConfig,
         options: ResolverOpts,
+        conn_provider: StandardConnection,
         lru: Arc<Mutex<DnsLru>>,
     ) -> (Self, impl Future<Item = (), Error = ()>) {
         let (request_tx, request_rx) = mpsc::unbounded();
-        let background = background::task(config, options, lru, request_rx);
+        let background = background::task(config, options, conn_provider, lru, request_rx);
         let handle = Self { request_tx };
         (handle, background)
     }
@@ -261,6 +303,7 @@ impl AsyncResolver {
         let request = Request::Ip {
             maybe_name: host.into_name(),
             maybe_ip,
+            strategy: None,
             tx,
         };
 
@@ -275,6 +318,33 @@ impl AsyncResolver {
         BackgroundLookupIp::from(f)
     }
 
+    /// Performs concurrent A and AAAA lookups of the hostname, for connecting to it.
+    ///
+    /// Regardless of the configured `LookupIpStrategy`, both are always looked up. The addresses
+    /// alternate between IPv6 and IPv4, starting with IPv6, the order in which connections should
+    /// be attempted according to Happy Eyeballs, see [`lookup_ip::happy_eyeballs_order`].
+    ///
+    /// # Arguments
+    /// * `host` - string hostname, if this is an invalid hostname, an error will be returned.
+    pub fn resolve_addrs<N: IntoName + TryParseIp>(&self, host: N) -> BackgroundResolveAddrs {
+        let (tx, rx) = oneshot::channel();
+        let maybe_ip = host.try_parse_ip();
+        let request = Request::Ip {
+            maybe_name: host.into_name(),
+            maybe_ip,
+            strategy: Some(LookupIpStrategy::Ipv4AndIpv6),
+            tx,
+        };
+
+        if self.request_tx.unbounded_send(request).is_err() {
+            return ResolveErrorKind::Message("background resolver gone, this is a bug").into();
+        }
+        let f: BgSend<LookupIpFuture, ResolveAddrsFuture> = rx
+            .map_err(Self::oneshot_canceled as fn(oneshot::Canceled) -> ResolveError)
+            .and_then(ResolveAddrsFuture::from);
+        BackgroundResolveAddrs::from(f)
+    }
+
     /// Performs a DNS lookup for an SRV record for the specified service type and protocol at the given name.
     ///
     /// This is a convenience method over `lookup_srv`, it combines the service, protocol and name into a single name: `_service._protocol.name`.
@@ -305,6 +375,24 @@ impl AsyncResolver {
         self.inner_lookup(name, RecordType::SRV, DnsRequestOptions::default())
     }
 
+    /// Performs forward-confirmed reverse DNS, FCrDNS, of the address.
+    ///
+    /// This looks up the names of the PTR records of the address, and then the A or AAAA records
+    /// of each of the names. The result is `true` if any of the names resolves to the address
+    /// again. Addresses without PTR records are not confirmed, i.e. the result is `false`.
+    pub fn verify_fcrdns(&self, ip: IpAddr) -> BackgroundFcrdns {
+        let (tx, rx) = oneshot::channel();
+        let request = Request::Fcrdns { ip, tx };
+
+        if self.request_tx.unbounded_send(request).is_err() {
+            return ResolveErrorKind::Message("background resolver gone, this is a bug").into();
+        }
+        let f: BgSend<FcrdnsFuture, FcrdnsFuture> = rx
+            .map_err(Self::oneshot_canceled as fn(oneshot::Canceled) -> ResolveError)
+            .and_then(FcrdnsFuture::from);
+        BackgroundFcrdns::from(f)
+    }
+
     lookup_fn!(
         reverse_lookup,
         lookup::ReverseLookupFuture,
@@ -384,7 +472,7 @@ mod tests {
     use self::tokio::runtime::current_thread::Runtime;
     use proto::xfer::DnsRequest;
 
-    use config::{LookupIpStrategy, NameServerConfig};
+    use config::NameServerConfig;
 
     use super::*;
 
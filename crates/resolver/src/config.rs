@@ -11,6 +11,7 @@ use std::ops::{Deref, DerefMut};
 use std::time::Duration;
 
 use proto::rr::Name;
+pub use proto::xfer::RetryPolicy;
 
 /// Configuration for the upstream nameservers to use for resolution
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -251,6 +252,12 @@ pub struct NameServerConfig {
     pub protocol: Protocol,
     /// SPKI name, only relevant for TLS connections
     pub tls_dns_name: Option<String>,
+    /// Accept a certificate of the server for another name than `tls_dns_name`, off by default
+    ///
+    /// *WARNING* the server is then not authenticated. Only dns-over-native-tls supports this, the
+    ///  other TLS implementations always validate the name.
+    #[cfg_attr(feature = "serde-config", serde(default))]
+    pub tls_accept_invalid_hostnames: bool,
 }
 
 /// A set of name_servers to associate with a [`ResolverConfig`].
@@ -282,11 +289,13 @@ impl NameServerConfigGroup {
                 socket_addr: SocketAddr::new(*ip, port),
                 protocol: Protocol::Udp,
                 tls_dns_name: None,
+                tls_accept_invalid_hostnames: false,
             };
             let tcp = NameServerConfig {
                 socket_addr: SocketAddr::new(*ip, port),
                 protocol: Protocol::Tcp,
                 tls_dns_name: None,
+                tls_accept_invalid_hostnames: false,
             };
 
             name_servers.push(udp);
@@ -312,6 +321,7 @@ impl NameServerConfigGroup {
                 socket_addr: SocketAddr::new(*ip, port),
                 protocol,
                 tls_dns_name: Some(tls_dns_name.clone()),
+                tls_accept_invalid_hostnames: false,
             };
 
             name_servers.push(config);
@@ -509,6 +519,8 @@ pub struct ResolverOpts {
     pub timeout: Duration,
     /// Number of attempts before giving up. Defaults to 2
     pub attempts: usize,
+    /// The delay before each retry of a failed request. Defaults to retrying immediately
+    pub retry_policy: RetryPolicy,
     /// Rotate through the resource records in the response (if there is more than one for a given name)
     pub(crate) rotate: bool,
     /// Validate the names in the response, not implemented don't really see the point unless you need to support
@@ -548,9 +560,21 @@ pub struct ResolverOpts {
     ///
     /// [`MAX_TTL`]: ../dns_lru/const.MAX_TTL.html
     pub negative_max_ttl: Option<Duration>,
+    /// Optional duration for serving stale records, [RFC 8767](https://tools.ietf.org/html/rfc8767)
+    ///
+    /// If this is set, positive responses are kept in the cache for `serve_stale` after they
+    /// expired. When a query for them fails, the expired records are returned with a TTL of 30
+    /// seconds, and the lookup is marked stale. Otherwise, this is disabled.
+    pub serve_stale: Option<Duration>,
+    /// Optional percentage of the TTL in which cached records are refreshed before they expire
+    ///
+    /// If this is set, a lookup answered from the cache in the last `prefetch_percent` of the TTL,
+    /// e.g. 10 for the last 10%, triggers a query in the background refreshing the cache, while
+    /// the cached records are returned. Otherwise, this is disabled.
+    pub prefetch_percent: Option<u8>,
     /// Default is to distrust negative responses from upstream nameservers
     ///
-    /// Currently only SERVFAIL responses are continued on, this may be expanded to include NXDOMAIN or NoError/Empty responses
+    /// Currently only SERVFAIL and REFUSED responses are continued on, this may be expanded to include NXDOMAIN or NoError/Empty responses
     pub distrust_nx_responses: bool,
     /// Concurrent requests where more than one Nameserver is registered, the default is 2
     ///
@@ -567,6 +591,7 @@ impl Default for ResolverOpts {
             ndots: 1,
             timeout: Duration::from_secs(5),
             attempts: 2,
+            retry_policy: RetryPolicy::default(),
             rotate: false,
             check_names: true,
             edns0: false,
@@ -578,6 +603,8 @@ impl Default for ResolverOpts {
             negative_min_ttl: None,
             positive_max_ttl: None,
             negative_max_ttl: None,
+            serve_stale: None,
+            prefetch_percent: None,
             distrust_nx_responses: true,
             num_concurrent_reqs: 2,
         }
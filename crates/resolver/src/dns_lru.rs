@@ -22,11 +22,17 @@ use lru_cache::LruCache;
 ///   Setting this to a value of 1 day, in seconds
 pub const MAX_TTL: u32 = 86400_u32;
 
+/// The TTL of stale records, as recommended by https://tools.ietf.org/html/rfc8767#section-4
+const STALE_TTL: u32 = 30;
+
 #[derive(Debug)]
 struct LruValue {
     // In the None case, this represents an NXDomain
     lookup: Option<Lookup>,
+    cached_at: Instant,
     valid_until: Instant,
+    /// true once a refresh of this set of ips was triggered
+    prefetched: bool,
 }
 
 impl LruValue {
@@ -34,6 +40,36 @@ impl LruValue {
     fn is_current(&self, now: Instant) -> bool {
         now <= self.valid_until
     }
+
+    /// Returns true if this set of ips expired, but may still be served stale
+    fn is_stale(&self, now: Instant, max_stale: Duration) -> bool {
+        !self.is_current(now) && now <= self.valid_until + max_stale
+    }
+
+    /// Returns true if this set of ips is in the last `percent` of its TTL
+    fn is_near_expiry(&self, now: Instant, percent: u8) -> bool {
+        let ttl = self.valid_until - self.cached_at;
+        let remaining = ttl * u32::from(percent.min(100)) / 100;
+        self.is_current(now) && now + remaining >= self.valid_until
+    }
+
+    /// Returns the lookup, with TTLs reflecting the time it has been cached
+    fn lookup(&self, now: Instant) -> Option<Lookup> {
+        let elapsed = if now > self.cached_at {
+            now - self.cached_at
+        } else {
+            Duration::from_secs(0)
+        };
+        let elapsed_secs = elapsed.as_secs().min(u64::from(u32::max_value())) as u32;
+
+        self.lookup.as_ref().map(|lookup| {
+            if elapsed_secs == 0 {
+                lookup.clone()
+            } else {
+                lookup.with_remaining_ttl(elapsed_secs)
+            }
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -75,6 +111,11 @@ pub(crate) struct DnsLru {
     ///
     /// [`MAX_TTL`]: const.MAX_TTL.html
     negative_max_ttl: Duration,
+    /// How long expired positive responses are kept to be served stale, if at all.
+    serve_stale: Option<Duration>,
+    /// The percentage of the TTL before expiry in which positive responses are refreshed, if at
+    /// all.
+    prefetch_percent: Option<u8>,
 }
 
 /// The time-to-live, TTL, configuration for use by the cache.
@@ -106,6 +147,16 @@ pub(crate) struct TtlConfig {
     /// `NXDOMAIN` responses with TTLs over `negative_max_ttl` will use
     /// `negative_max_ttl` instead.
     pub negative_max_ttl: Option<Duration>,
+    /// An optional duration for serving expired positive responses when they can not be
+    /// refreshed.
+    ///
+    /// Expired entries are kept in the cache for `serve_stale` after their TTL.
+    pub serve_stale: Option<Duration>,
+    /// An optional percentage of the TTL, in which positive responses are refreshed before they
+    /// expire.
+    ///
+    /// Entries accessed in the last `prefetch_percent` of their TTL are refreshed once.
+    pub prefetch_percent: Option<u8>,
 }
 
 impl TtlConfig {
@@ -115,6 +166,8 @@ impl TtlConfig {
             negative_min_ttl: opts.negative_min_ttl,
             positive_max_ttl: opts.positive_max_ttl,
             negative_max_ttl: opts.negative_max_ttl,
+            serve_stale: opts.serve_stale,
+            prefetch_percent: opts.prefetch_percent,
         }
     }
 }
@@ -126,6 +179,8 @@ impl DnsLru {
             negative_min_ttl,
             positive_max_ttl,
             negative_max_ttl,
+            serve_stale,
+            prefetch_percent,
         } = ttl_cfg;
         let cache = LruCache::new(capacity);
         Self {
@@ -136,6 +191,8 @@ impl DnsLru {
                 .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
             negative_max_ttl: negative_max_ttl
                 .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
+            serve_stale,
+            prefetch_percent,
         }
     }
 
@@ -168,7 +225,9 @@ impl DnsLru {
             query,
             LruValue {
                 lookup: Some(lookup.clone()),
+                cached_at: now,
                 valid_until,
+                prefetched: false,
             },
         );
 
@@ -190,7 +249,9 @@ impl DnsLru {
             query,
             LruValue {
                 lookup: Some(lookup.clone()),
+                cached_at: now,
                 valid_until,
+                prefetched: false,
             },
         );
 
@@ -216,7 +277,9 @@ impl DnsLru {
             query.clone(),
             LruValue {
                 lookup: None,
+                cached_at: now,
                 valid_until,
+                prefetched: false,
             },
         );
 
@@ -226,12 +289,17 @@ impl DnsLru {
     /// This needs to be mut b/c it's an LRU, meaning the ordering of elements will potentially change on retrieval...
     pub(crate) fn get(&mut self, query: &Query, now: Instant) -> Option<Lookup> {
         let mut out_of_date = false;
+        let serve_stale = self.serve_stale;
         let lookup = self.cache.get_mut(query).and_then(|value| {
             if value.is_current(now) {
                 out_of_date = false;
-                value.lookup.clone()
+                value.lookup(now)
             } else {
-                out_of_date = true;
+                // expired records are kept for serving them stale
+                out_of_date = match serve_stale {
+                    Some(max_stale) => !value.is_stale(now, max_stale),
+                    None => true,
+                };
                 None
             }
         });
@@ -245,6 +313,47 @@ impl DnsLru {
 
         lookup
     }
+
+    /// Returns true, once, if the positive response is close enough to expiry to be refreshed
+    ///
+    /// The entry is expected to still be served, after the refresh it is replaced.
+    pub(crate) fn prefetch(&mut self, query: &Query, now: Instant) -> bool {
+        let percent = match self.prefetch_percent {
+            Some(percent) => percent,
+            None => return false,
+        };
+
+        match self.cache.get_mut(query) {
+            Some(ref mut value)
+                if value.lookup.is_some()
+                    && !value.prefetched
+                    && value.is_near_expiry(now, percent) =>
+            {
+                value.prefetched = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the expired records of a positive response, if they may be served stale
+    ///
+    /// This is for answering a query whose refresh failed, see
+    ///  [RFC 8767](https://tools.ietf.org/html/rfc8767). The records have a TTL of 30 seconds,
+    ///  and the lookup is marked stale.
+    pub(crate) fn get_stale(&mut self, query: &Query, now: Instant) -> Option<Lookup> {
+        let max_stale = self.serve_stale?;
+        let value = self.cache.get_mut(query)?;
+
+        if value.is_stale(now, max_stale) {
+            value
+                .lookup
+                .as_ref()
+                .map(|lookup| lookup.to_stale(STALE_TTL, now))
+        } else {
+            None
+        }
+    }
 }
 
 // see also the lookup_tests.rs in integration-tests crate
@@ -268,7 +377,9 @@ mod tests {
 
         let value = LruValue {
             lookup: None,
+            cached_at: now,
             valid_until: future,
+            prefetched: false,
         };
 
         assert!(value.is_current(now));
@@ -578,4 +689,74 @@ mod tests {
         let rc_ips = lru.get(&query, now + Duration::from_secs(3));
         assert!(rc_ips.is_none());
     }
+
+    #[test]
+    fn test_get_reduces_ttl() {
+        let now = Instant::now();
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let ips_ttl = vec![(
+            Record::from_rdata(name.clone(), 60, RData::A(Ipv4Addr::new(127, 0, 0, 1))),
+            60,
+        )];
+
+        // the minimum TTL keeps the records cached after they expired
+        let ttls = TtlConfig {
+            positive_min_ttl: Some(Duration::from_secs(120)),
+            ..Default::default()
+        };
+        let mut lru = DnsLru::new(1, ttls);
+        lru.insert(query.clone(), ips_ttl, now);
+
+        let lookup = lru.get(&query, now + Duration::from_secs(10)).unwrap();
+        assert_eq!(lookup.records()[0].ttl(), 50);
+
+        let lookup = lru.get(&query, now + Duration::from_secs(90)).unwrap();
+        assert_eq!(lookup.records()[0].ttl(), 0);
+    }
+
+    #[test]
+    fn test_prefetch_near_expiry() {
+        let now = Instant::now();
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let ips_ttl = vec![(
+            Record::from_rdata(name.clone(), 100, RData::A(Ipv4Addr::new(127, 0, 0, 1))),
+            100,
+        )];
+
+        let ttls = TtlConfig {
+            prefetch_percent: Some(10),
+            ..Default::default()
+        };
+        let mut lru = DnsLru::new(1, ttls);
+        lru.insert(query.clone(), ips_ttl.clone(), now);
+
+        // before the last 10 seconds of the TTL
+        assert!(!lru.prefetch(&query, now));
+        assert!(!lru.prefetch(&query, now + Duration::from_secs(89)));
+
+        // the refresh is only triggered once
+        assert!(lru.prefetch(&query, now + Duration::from_secs(90)));
+        assert!(!lru.prefetch(&query, now + Duration::from_secs(95)));
+
+        // the refreshed entry may be prefetched again
+        lru.insert(query.clone(), ips_ttl, now + Duration::from_secs(95));
+        assert!(!lru.prefetch(&query, now + Duration::from_secs(96)));
+        assert!(lru.prefetch(&query, now + Duration::from_secs(185)));
+
+        // disabled
+        let mut lru = DnsLru::new(1, TtlConfig::default());
+        lru.insert(
+            query.clone(),
+            vec![(
+                Record::from_rdata(name, 100, RData::A(Ipv4Addr::new(127, 0, 0, 1))),
+                100,
+            )],
+            now,
+        );
+        assert!(!lru.prefetch(&query, now + Duration::from_secs(99)));
+    }
 }
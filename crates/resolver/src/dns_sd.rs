@@ -40,6 +40,7 @@ impl DnsSdHandle for AsyncResolver {
     fn list_services<N: IntoName>(&self, name: N) -> ListServicesFuture {
         let options = DnsRequestOptions {
             expects_multiple_responses: true,
+            ..DnsRequestOptions::default()
         };
 
         let name: Name = match name.into_name() {
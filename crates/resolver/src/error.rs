@@ -10,7 +10,10 @@
 use failure::{Backtrace, Context, Fail};
 use std::{fmt, io, sync, time::Instant};
 use proto::error::{ProtoError, ProtoErrorKind};
-use proto::op::Query;
+use proto::op::{Query, ResponseCode};
+#[cfg(feature = "dnssec")]
+use proto::rr::rdata::opt::EdnsOption;
+use proto::rr::Name;
 
 /// An alias for results returned by functions of this crate
 pub type ResolveResult<T> = ::std::result::Result<T, ResolveError>;
@@ -36,6 +39,24 @@ pub enum ResolveErrorKind {
         valid_until: Option<Instant>
     },
 
+    /// A CNAME chain led back to a name which was already in the chain
+    #[fail(display = "CNAME loop at {}", name)]
+    CnameLoop {
+        /// The name which was reached a second time
+        name: Name,
+    },
+
+    /// A CNAME chain was longer than the resolver follows
+    #[fail(display = "CNAME chain too long at {}", name)]
+    CnameChainTooLong {
+        /// The name at which the chain was no longer followed
+        name: Name,
+    },
+
+    /// The query was answered, or could only be answered, with an error response code
+    #[fail(display = "DNS Error: {}", _0)]
+    ResponseCode(ResponseCode),
+
     // foreign
     /// An error got returned from IO
     #[fail(display = "io error")]
@@ -60,6 +81,9 @@ impl Clone for ResolveErrorKind {
                 query: query.clone(),
                 valid_until,
             },
+            CnameLoop { ref name } => CnameLoop { name: name.clone() },
+            CnameChainTooLong { ref name } => CnameChainTooLong { name: name.clone() },
+            ResponseCode(code) => ResponseCode(code),
 
             // foreign
             Io => Io,
@@ -80,6 +104,16 @@ impl ResolveError {
     pub fn kind(&self) -> &ResolveErrorKind {
         self.inner.get_context()
     }
+
+    /// The Extended DNS Error, RFC 8914, for a SERVFAIL caused by this error, i.e. the reason the
+    ///  records failed DNSSec validation
+    #[cfg(feature = "dnssec")]
+    pub fn extended_error(&self) -> Option<EdnsOption> {
+        self.inner
+            .cause()?
+            .downcast_ref::<ProtoError>()?
+            .extended_error()
+    }
 }
 
 impl Clone for ResolveError {
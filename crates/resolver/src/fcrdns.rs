@@ -0,0 +1,121 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Forward-confirmed reverse DNS, FCrDNS, of an IP address
+//!
+//! The names of the PTR records of an address are only trustworthy if one of them resolves to the
+//! address again, otherwise the owner of the reverse zone can claim any name.
+
+use std::net::IpAddr;
+
+use futures::{future, Future, Poll};
+
+use proto::rr::{Name, RData, RecordType};
+use proto::xfer::{DnsHandle, DnsRequestOptions};
+
+use error::*;
+use lookup::{Lookup, LookupFuture};
+use lookup_state::CachingClient;
+
+/// The maximum number of PTR names which are looked up, the others are ignored
+///
+/// The reverse zone is not trusted, so it must not be able to cause any number of lookups.
+pub const MAX_PTR_NAMES: usize = 10;
+
+/// The result of forward-confirmed reverse DNS, `true` if the address was confirmed
+#[must_use = "futures do nothing unless polled"]
+pub struct FcrdnsFuture(Box<dyn Future<Item = bool, Error = ResolveError> + Send>);
+
+impl FcrdnsFuture {
+    /// Confirms the reverse DNS of the address
+    ///
+    /// This looks up the names of the PTR records of the address, and then the A or AAAA records,
+    /// for IPv4 or IPv6 addresses, of all of the names. The address is confirmed if any of the names
+    /// resolves to it. Names without records of the address family, names which fail to resolve,
+    /// and addresses without PTR records, are not errors, they only fail the confirmation. Only the
+    /// first `MAX_PTR_NAMES` names are looked up.
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - the address to confirm
+    /// * `options` - options for all of the lookups
+    /// * `client_cache` - cache with a connection to use for performing all lookups
+    #[doc(hidden)]
+    pub fn verify<C: DnsHandle + 'static>(
+        ip: IpAddr,
+        options: DnsRequestOptions,
+        client_cache: CachingClient<C>,
+    ) -> Self {
+        let record_type = match ip {
+            IpAddr::V4(..) => RecordType::A,
+            IpAddr::V6(..) => RecordType::AAAA,
+        };
+
+        let ptr_lookup = LookupFuture::lookup(
+            vec![Name::from(ip)],
+            RecordType::PTR,
+            options.clone(),
+            client_cache.clone(),
+        )
+        .then(|lookup| no_records_as(lookup.map(|lookup| ptr_names(&lookup)), vec![]));
+
+        let future = ptr_lookup
+            .and_then(move |names| {
+                let forward_lookups = names.into_iter().take(MAX_PTR_NAMES).map(move |name| {
+                    let names = vec![name];
+                    LookupFuture::lookup(names, record_type, options.clone(), client_cache.clone())
+                        .then(move |lookup| match lookup {
+                            Ok(lookup) => Ok(has_ip(&lookup, ip)),
+                            // one name failing must not fail the others
+                            Err(e) => {
+                                debug!("forward lookup of PTR name failed: {}", e);
+                                Ok(false)
+                            }
+                        })
+                });
+
+                future::join_all(forward_lookups)
+            })
+            .map(|confirmed| confirmed.into_iter().any(|confirmed| confirmed));
+
+        FcrdnsFuture(Box::new(future))
+    }
+}
+
+impl Future for FcrdnsFuture {
+    type Item = bool;
+    type Error = ResolveError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.0.poll()
+    }
+}
+
+fn ptr_names(lookup: &Lookup) -> Vec<Name> {
+    lookup
+        .iter()
+        .filter_map(|rdata| match *rdata {
+            RData::PTR(ref name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_ip(lookup: &Lookup, ip: IpAddr) -> bool {
+    lookup.iter().any(|rdata| rdata.to_ip_addr() == Some(ip))
+}
+
+/// A lookup without records is a negative answer, and not an error here
+fn no_records_as<T>(result: ResolveResult<T>, none: T) -> ResolveResult<T> {
+    if let Err(ref e) = result {
+        if let ResolveErrorKind::NoRecordsFound { .. } = *e.kind() {
+            return Ok(none);
+        }
+    }
+
+    result
+}
@@ -219,6 +219,7 @@ pub mod config;
 mod dns_lru;
 pub mod dns_sd;
 pub mod error;
+pub mod fcrdns;
 mod hosts;
 #[cfg(feature = "dns-over-https")]
 mod https;
@@ -227,6 +228,7 @@ pub mod lookup_ip;
 pub mod lookup_state;
 #[doc(hidden)]
 pub mod name_server;
+pub mod recursor;
 #[cfg(feature = "tokio")]
 mod resolver;
 pub mod system_conf;
@@ -236,7 +238,10 @@ mod tls;
 // reexports from proto
 pub use self::proto::rr::{IntoName, Name, TryParseIp};
 
-pub use async_resolver::{AsyncResolver, Background, BackgroundLookup, BackgroundLookupIp};
+pub use async_resolver::{
+    AsyncResolver, Background, BackgroundFcrdns, BackgroundLookup, BackgroundLookupIp,
+    BackgroundResolveAddrs,
+};
 pub use hosts::Hosts;
 #[cfg(feature = "tokio")]
 pub use resolver::Resolver;
@@ -18,6 +18,8 @@ use futures::{future, Async, Future, Poll};
 
 use proto::error::ProtoError;
 use proto::op::Query;
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::Validity;
 use proto::rr::rdata;
 use proto::rr::{Name, RData, Record, RecordType};
 use proto::xfer::{DnsRequest, DnsRequestOptions, DnsResponse};
@@ -39,6 +41,9 @@ pub struct Lookup {
     query: Query,
     records: Arc<Vec<Record>>,
     valid_until: Instant,
+    stale: bool,
+    #[cfg(feature = "dnssec")]
+    validity: Validity,
 }
 
 impl Lookup {
@@ -55,6 +60,9 @@ impl Lookup {
             query,
             records,
             valid_until,
+            stale: false,
+            #[cfg(feature = "dnssec")]
+            validity: Validity::Indeterminate,
         }
     }
 
@@ -68,6 +76,9 @@ impl Lookup {
             query,
             records,
             valid_until,
+            stale: false,
+            #[cfg(feature = "dnssec")]
+            validity: Validity::Indeterminate,
         }
     }
 
@@ -91,6 +102,25 @@ impl Lookup {
         self.valid_until
     }
 
+    /// Returns true if the records had expired, and were served from the cache because they could
+    ///  not be refreshed, see `ResolverOpts::serve_stale`
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Returns the DNSSec validity of the records, `Validity::Secure` if they were validated by a
+    ///  resolver with `ResolverOpts::validate`, `Validity::Indeterminate` otherwise
+    #[cfg(feature = "dnssec")]
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    /// Sets the validity of the records
+    #[cfg(feature = "dnssec")]
+    pub(crate) fn with_validity(self, validity: Validity) -> Self {
+        Lookup { validity, ..self }
+    }
+
     #[doc(hidden)]
     pub fn is_empty(&self) -> bool {
         self.records.is_empty()
@@ -113,7 +143,52 @@ impl Lookup {
 
         // Choose the sooner deadline of the two lookups.
         let valid_until = min(self.valid_until(), other.valid_until());
-        Self::new_with_deadline(self.query.clone(), Arc::new(records), valid_until)
+        Lookup {
+            stale: self.stale || other.stale,
+            #[cfg(feature = "dnssec")]
+            validity: if self.validity == other.validity {
+                self.validity
+            } else {
+                Validity::Indeterminate
+            },
+            ..Self::new_with_deadline(self.query.clone(), Arc::new(records), valid_until)
+        }
+    }
+
+    /// Clones the records with the TTLs reduced by the seconds elapsed since they were cached
+    pub(crate) fn with_remaining_ttl(&self, elapsed_secs: u32) -> Self {
+        let records = self
+            .records
+            .iter()
+            .map(|record| record.with_remaining_ttl(elapsed_secs))
+            .collect();
+
+        Lookup {
+            #[cfg(feature = "dnssec")]
+            validity: self.validity,
+            ..Self::new_with_deadline(self.query.clone(), Arc::new(records), self.valid_until)
+        }
+    }
+
+    /// Clones the expired records with the TTL for serving stale data, marking the lookup stale
+    pub(crate) fn to_stale(&self, stale_ttl: u32, now: Instant) -> Self {
+        let records = self
+            .records
+            .iter()
+            .map(|record| {
+                let mut record = record.clone();
+                record.set_ttl(stale_ttl);
+                record
+            })
+            .collect();
+
+        let valid_until = now + Duration::from_secs(u64::from(stale_ttl));
+        Lookup {
+            stale: true,
+            #[cfg(feature = "dnssec")]
+            validity: self.validity,
+            ..Self::new_with_deadline(self.query.clone(), Arc::new(records), valid_until)
+        }
     }
 }
 
@@ -534,13 +609,15 @@ pub mod tests {
     use std::net::{IpAddr, Ipv4Addr};
     use std::str::FromStr;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     use futures::{future, Future};
+    use tokio::runtime::current_thread::Runtime;
 
     use proto::error::{ProtoErrorKind, ProtoResult};
     use proto::op::Message;
     use proto::rr::{Name, RData, Record, RecordType};
-    use proto::xfer::{DnsRequest, DnsRequestOptions};
+    use proto::xfer::{DnsRequest, DnsRequestOptions, RetryPolicy};
 
     use super::*;
 
@@ -577,6 +654,10 @@ pub mod tests {
         Err(ProtoErrorKind::Io.into())
     }
 
+    pub fn timeout() -> ProtoResult<DnsResponse> {
+        Err(ProtoErrorKind::Timeout.into())
+    }
+
     pub fn mock(messages: Vec<ProtoResult<DnsResponse>>) -> MockDnsHandle {
         MockDnsHandle {
             messages: Arc::new(Mutex::new(messages)),
@@ -631,6 +712,30 @@ pub mod tests {
         .is_err());
     }
 
+    #[test]
+    fn test_retry_timeout() {
+        let handle = mock(vec![v4_message(), timeout(), timeout(), timeout()]);
+        let client = RetryDnsHandle::with_policy(
+            handle.clone(),
+            2,
+            RetryPolicy::Fixed(Duration::from_millis(1)),
+        );
+
+        let mut io_loop = Runtime::new().unwrap();
+        let error = io_loop
+            .block_on(LookupFuture::lookup(
+                vec![Name::root()],
+                RecordType::A,
+                DnsRequestOptions::default(),
+                CachingClient::new(0, client),
+            ))
+            .unwrap_err();
+
+        assert_eq!(*error.kind(), ResolveErrorKind::Timeout);
+        // the first attempt and two retries, the last response was never requested
+        assert_eq!(handle.messages.lock().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_empty_no_response() {
         assert_eq!(
@@ -650,6 +755,36 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_reverse_lookup() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let query_name = Name::from(ip);
+        assert_eq!(query_name.to_string(), "1.2.0.192.in-addr.arpa.");
+
+        let mut message = Message::new();
+        message.insert_answers(vec![Record::from_rdata(
+            query_name.clone(),
+            86400,
+            RData::PTR(Name::from_str("www.example.com.").unwrap()),
+        )]);
+
+        let lookup = ReverseLookup::from(
+            LookupFuture::lookup(
+                vec![query_name],
+                RecordType::PTR,
+                DnsRequestOptions::default(),
+                CachingClient::new(0, mock(vec![Ok(message.into())])),
+            )
+            .wait()
+            .unwrap(),
+        );
+
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<Name>>(),
+            vec![Name::from_str("www.example.com.").unwrap()]
+        );
+    }
+
     #[test]
     fn test_lookup_into_iter_arc() {
         let mut lookup = LookupIntoIter {
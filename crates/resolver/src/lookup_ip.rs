@@ -236,6 +236,50 @@ where
         }
     }
 }
+
+/// The Future returned from [`AsyncResolver::resolve_addrs`], the addresses of a lookup of both A
+///  and AAAA records, in the order of [`happy_eyeballs_order`].
+pub struct ResolveAddrsFuture<C = LookupEither<ConnectionHandle, StandardConnection>>(
+    LookupIpFuture<C>,
+)
+where
+    C: DnsHandle + 'static;
+
+impl<C: DnsHandle + 'static> From<LookupIpFuture<C>> for ResolveAddrsFuture<C> {
+    fn from(lookup: LookupIpFuture<C>) -> Self {
+        ResolveAddrsFuture(lookup)
+    }
+}
+
+impl<C: DnsHandle + 'static> Future for ResolveAddrsFuture<C> {
+    type Item = Vec<IpAddr>;
+    type Error = ResolveError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let lookup = try_ready!(self.0.poll());
+        Ok(Async::Ready(happy_eyeballs_order(lookup.iter())))
+    }
+}
+
+/// Orders the addresses for connection attempts, alternating between IPv6 and IPv4 addresses,
+///  starting with IPv6
+///
+/// This is the interleaving of [RFC 8305](https://tools.ietf.org/html/rfc8305#section-4), with a
+///  "First Address Family Count" of one. The order within each family is kept, and the remaining
+///  addresses of one family follow once the other one is exhausted.
+pub fn happy_eyeballs_order<I: IntoIterator<Item = IpAddr>>(addrs: I) -> Vec<IpAddr> {
+    let (v6, v4): (Vec<IpAddr>, Vec<IpAddr>) = addrs.into_iter().partition(IpAddr::is_ipv6);
+
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => return ordered,
+            (v6, v4) => ordered.extend(v6.into_iter().chain(v4)),
+        }
+    }
+}
 /// returns a new future for lookup
 fn strategic_lookup<C: DnsHandle + 'static>(
     name: Name,
@@ -477,6 +521,67 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_happy_eyeballs_order() {
+        let v4 = |i| IpAddr::V4(Ipv4Addr::new(127, 0, 0, i));
+        let v6 = |i| IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, i));
+
+        assert_eq!(
+            happy_eyeballs_order(vec![v4(1), v4(2), v6(1), v6(2), v6(3)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            happy_eyeballs_order(vec![v4(1), v6(1), v4(2), v4(3)]),
+            vec![v6(1), v4(1), v4(2), v4(3)]
+        );
+        assert_eq!(happy_eyeballs_order(vec![v4(1), v4(2)]), vec![v4(1), v4(2)]);
+        assert!(happy_eyeballs_order(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_addrs() {
+        fn message(rdatas: Vec<RData>) -> ProtoResult<DnsResponse> {
+            let mut message = Message::new();
+            message.insert_answers(
+                rdatas
+                    .into_iter()
+                    .map(|rdata| Record::from_rdata(Name::root(), 86400, rdata))
+                    .collect(),
+            );
+            Ok(message.into())
+        }
+
+        let v4 = message(vec![
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            RData::A(Ipv4Addr::new(127, 0, 0, 2)),
+        ]);
+        let v6 = message(vec![
+            RData::AAAA(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+            RData::AAAA(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 2)),
+            RData::AAAA(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 3)),
+        ]);
+
+        let lookup = LookupIpFuture::lookup(
+            vec![Name::root()],
+            LookupIpStrategy::Ipv4AndIpv6,
+            CachingClient::new(0, mock(vec![v6, v4])),
+            Default::default(),
+            None,
+            None,
+        );
+
+        assert_eq!(
+            ResolveAddrsFuture::from(lookup).wait().unwrap(),
+            vec![
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 2)),
+                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 3)),
+            ]
+        );
+    }
+
     #[test]
     fn test_ipv4_only_strategy() {
         assert_eq!(
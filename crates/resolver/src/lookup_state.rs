@@ -8,7 +8,6 @@
 //! Caching related functionality for the Resolver.
 
 use std::borrow::Cow;
-use std::cell::RefCell;
 use std::mem;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::{Arc, Mutex, TryLockError};
@@ -17,23 +16,23 @@ use std::time::Instant;
 use futures::{future, task, Async, Future, Poll};
 
 use proto::op::{Message, Query, ResponseCode};
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::Validity;
 use proto::rr::domain::usage::{
     ResolverUsage, DEFAULT, INVALID, IN_ADDR_ARPA_127, IP6_ARPA_1, LOCAL,
     LOCALHOST as LOCALHOST_usage,
 };
 use proto::rr::{DNSClass, Name, RData, Record, RecordType};
 use proto::xfer::{DnsHandle, DnsRequestOptions, DnsResponse};
+use tokio_executor::{DefaultExecutor, Executor};
 
 use dns_lru;
 use dns_lru::DnsLru;
 use error::*;
 use lookup::Lookup;
 
-const MAX_QUERY_DEPTH: u8 = 7; // arbitrarily chosen number...
-
-task_local! {
-    static QUERY_DEPTH: RefCell<u8> = RefCell::new(0)
-}
+/// The most CNAMEs which are followed for a single lookup, within and across responses
+const MAX_CNAME_CHAIN: usize = 16;
 
 lazy_static! {
     static ref LOCALHOST: RData = RData::PTR(Name::from_ascii("localhost.").unwrap());
@@ -69,6 +68,17 @@ impl<C: DnsHandle + 'static> CachingClient<C> {
         &mut self,
         query: Query,
         options: DnsRequestOptions,
+    ) -> Box<dyn Future<Item = Lookup, Error = ResolveError> + Send> {
+        let cname_chain = vec![query.name().clone()];
+        self.lookup_chained(query, options, cname_chain)
+    }
+
+    /// Perform a lookup for a name which was reached through the CNAMEs of `cname_chain`
+    fn lookup_chained(
+        &mut self,
+        query: Query,
+        options: DnsRequestOptions,
+        cname_chain: Vec<Name>,
     ) -> Box<dyn Future<Item = Lookup, Error = ResolveError> + Send> {
         // see https://tools.ietf.org/html/rfc6761
         //
@@ -125,12 +135,26 @@ impl<C: DnsHandle + 'static> CachingClient<C> {
             }
         }
 
-        Box::new(QueryState::lookup(
+        let lookup = QueryState::lookup_chained(
             query,
             options,
             &mut self.client,
             self.lru.clone(),
-        ))
+            cname_chain,
+        );
+
+        // a validating client only returns validated records, which are also the only ones cached
+        #[cfg(feature = "dnssec")]
+        let lookup = {
+            let validity = if self.client.is_verifying_dnssec() {
+                Validity::Secure
+            } else {
+                Validity::Indeterminate
+            };
+            lookup.map(move |lookup| lookup.with_validity(validity))
+        };
+
+        Box::new(lookup)
     }
 }
 
@@ -138,6 +162,9 @@ struct FromCache {
     query: Query,
     options: DnsRequestOptions,
     cache: Arc<Mutex<DnsLru>>,
+    cname_chain: Vec<Name>,
+    /// true when the cached lookup should be refreshed in the background
+    prefetch: bool,
 }
 
 impl Future for FromCache {
@@ -156,7 +183,13 @@ impl Future for FromCache {
             Err(TryLockError::Poisoned(poison)) => {
                 Err(ResolveErrorKind::Msg(format!("poisoned: {}", poison)).into())
             }
-            Ok(mut lru) => Ok(Async::Ready(lru.get(&self.query, Instant::now()))),
+            Ok(mut lru) => {
+                let now = Instant::now();
+                let lookup = lru.get(&self.query, now);
+                self.prefetch = lookup.is_some() && lru.prefetch(&self.query, now);
+
+                Ok(Async::Ready(lookup))
+            }
         }
     }
 }
@@ -170,6 +203,8 @@ struct QueryFuture<C: DnsHandle + 'static> {
     dnssec: bool, // TODO: move to DnsRequestOptions?
     options: DnsRequestOptions,
     client: CachingClient<C>,
+    /// the names which CNAMEs have been followed through, starting at the name of the first query
+    cname_chain: Vec<Name>,
 }
 
 enum Records {
@@ -184,21 +219,17 @@ enum Records {
     },
     /// Already cached, chained queries
     Chained { cached: Lookup, min_ttl: u32 },
+    /// Expired records of the cache, served because the query failed
+    Stale(Lookup),
 }
 
 impl<C: DnsHandle + 'static> QueryFuture<C> {
-    fn next_query(&mut self, query: Query, cname_ttl: u32, message: DnsResponse) -> Records {
-        if QUERY_DEPTH.with(|c| *c.borrow() >= MAX_QUERY_DEPTH) {
-            // TODO: This should return an error
-            self.handle_nxdomain(message, true)
-        } else {
-            // tracking the depth of our queries, to prevent infinite CNAME recursion
-            QUERY_DEPTH.with(|c| *c.borrow_mut() += 1);
-
-            Records::CnameChain {
-                next: self.client.lookup(query, self.options.clone()),
-                min_ttl: cname_ttl,
-            }
+    fn next_query(&mut self, query: Query, cname_ttl: u32, cname_chain: Vec<Name>) -> Records {
+        Records::CnameChain {
+            next: self
+                .client
+                .lookup_chained(query, self.options.clone(), cname_chain),
+            min_ttl: cname_ttl,
         }
     }
 
@@ -206,6 +237,9 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
         // initial ttl is what CNAMES for min usage
         const INITIAL_TTL: u32 = dns_lru::MAX_TTL;
 
+        // the CNAMEs followed in this response are added to the chain, to detect loops
+        let mut cname_chain = self.cname_chain.clone();
+
         // seek out CNAMES, this is only performed if the query is not a CNAME, ANY, or SRV
         let (search_name, cname_ttl, was_cname) = {
             // this will only search for CNAMEs if the request was not meant to be for one of the triggers for recursion
@@ -217,18 +251,31 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                     //   this works by folding the last CNAME found into the final folded result.
                     //   it assumes that the CNAMEs are in chained order in the DnsResponse Message...
                     // For SRV, the name added for the search becomes the target name.
+                    // A DNAME is followed as the CNAME that it synthesizes for the search name.
                     //
                     // TODO: should this include the additionals?
-                    response.messages().flat_map(Message::answers).fold(
+                    response.messages().flat_map(Message::answers).try_fold(
                         (Cow::Borrowed(self.query.name()), INITIAL_TTL, false),
-                        |(search_name, cname_ttl, was_cname), r| {
+                        |(search_name, cname_ttl, was_cname), r| -> ResolveResult<_> {
                             match *r.rdata() {
                                 RData::CNAME(ref cname) => {
                                     // take the minimum TTL of the cname_ttl and the next record in the chain
                                     let ttl = cname_ttl.min(r.ttl());
                                     debug_assert_eq!(r.rr_type(), RecordType::CNAME);
                                     if search_name.as_ref() == r.name() {
-                                        return (Cow::Owned(cname.clone()), ttl, true);
+                                        follow_cname(&mut cname_chain, cname)?;
+                                        return Ok((Cow::Owned(cname.clone()), ttl, true));
+                                    }
+                                }
+                                RData::DNAME(ref target) => {
+                                    // the synthesized CNAME has the TTL of the DNAME, RFC 6672
+                                    let ttl = cname_ttl.min(r.ttl());
+                                    debug_assert_eq!(r.rr_type(), RecordType::DNAME);
+                                    if let Some(cname) =
+                                        dname_substitution(search_name.as_ref(), r.name(), target)?
+                                    {
+                                        follow_cname(&mut cname_chain, &cname)?;
+                                        return Ok((Cow::Owned(cname), ttl, true));
                                     }
                                 }
                                 RData::SRV(ref srv) => {
@@ -237,14 +284,14 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                                     debug_assert_eq!(r.rr_type(), RecordType::SRV);
 
                                     // the search name becomes the srv.target
-                                    return (Cow::Owned(srv.target().clone()), ttl, true);
+                                    return Ok((Cow::Owned(srv.target().clone()), ttl, true));
                                 }
                                 _ => (),
                             }
 
-                            (search_name, cname_ttl, was_cname)
+                            Ok((search_name, cname_ttl, was_cname))
                         },
-                    )
+                    )?
                 };
 
             // take all answers. // TODO: following CNAMES?
@@ -302,9 +349,11 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
         // It was a CNAME, but not included in the request...
         if was_cname {
             let next_query = Query::query(search_name, self.query.query_type());
-            Ok(Async::Ready(
-                self.next_query(next_query, cname_ttl, response),
-            ))
+            Ok(Async::Ready(self.next_query(
+                next_query,
+                cname_ttl,
+                cname_chain,
+            )))
         } else {
             // TODO: review See https://tools.ietf.org/html/rfc2308 for NoData section
             // Note on DNSSec, in secure_client_handle, if verify_nsec fails then the request fails.
@@ -313,6 +362,25 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
         }
     }
 
+    /// Falls back to the expired records of the cache when the query failed, if they may be
+    ///  served stale, see https://tools.ietf.org/html/rfc8767
+    fn serve_stale(&self, error: ResolveError) -> Poll<Records, ResolveError> {
+        let stale = match self.cache.lock() {
+            Ok(mut lru) => lru.get_stale(&self.query, Instant::now()),
+            Err(poison) => {
+                return Err(ResolveErrorKind::Msg(format!("poisoned: {}", poison)).into())
+            }
+        };
+
+        match stale {
+            Some(stale) => {
+                debug!("serving stale records for {}: {}", self.query, error);
+                Ok(Async::Ready(Records::Stale(stale)))
+            }
+            None => Err(error),
+        }
+    }
+
     /// See https://tools.ietf.org/html/rfc2308
     ///
     /// For now we will regard NXDomain to strictly mean the query failed
@@ -348,6 +416,50 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
     }
 }
 
+/// Adds the target of a CNAME to the names of the chain which have been followed
+///
+/// # Errors
+///
+/// If the target is already in the chain, or the chain has reached `MAX_CNAME_CHAIN` CNAMEs
+fn follow_cname(cname_chain: &mut Vec<Name>, target: &Name) -> ResolveResult<()> {
+    if cname_chain.contains(target) {
+        return Err(ResolveErrorKind::CnameLoop {
+            name: target.clone(),
+        }
+        .into());
+    }
+
+    // the first name of the chain is the name of the query, not a CNAME
+    if cname_chain.len() > MAX_CNAME_CHAIN {
+        return Err(ResolveErrorKind::CnameChainTooLong {
+            name: target.clone(),
+        }
+        .into());
+    }
+
+    cname_chain.push(target.clone());
+    Ok(())
+}
+
+/// Replaces the owner of a DNAME at the end of `name` with the DNAME target, RFC 6672 section 2.2
+///
+/// The DNAME only redirects names below its owner, `None` is returned for any other name.
+///
+/// # Errors
+///
+/// YXDOMAIN, if the substituted name is longer than 255 octets, RFC 6672 section 2.2
+fn dname_substitution(name: &Name, owner: &Name, target: &Name) -> ResolveResult<Option<Name>> {
+    let name_labels = name.iter().len();
+    if name_labels <= owner.iter().len() || !owner.zone_of(name) {
+        return Ok(None);
+    }
+
+    let prefix = name.iter().take(name_labels - owner.iter().len());
+    Name::from_labels(prefix.chain(target.iter()))
+        .map(Some)
+        .map_err(|_| ResolveErrorKind::ResponseCode(ResponseCode::YXDomain).into())
+}
+
 impl<C: DnsHandle + 'static> Future for QueryFuture<C> {
     type Item = Records;
     type Error = ResolveError;
@@ -363,11 +475,13 @@ impl<C: DnsHandle + 'static> Future for QueryFuture<C> {
                         message, false, /* false b/c DNSSec should not cache NXDomain */
                     ))),
                     ResponseCode::NoError => self.handle_noerror(message),
-                    r => Err(ResolveErrorKind::Msg(format!("DNS Error: {}", r)).into()),
+                    r => {
+                        self.serve_stale(ResolveErrorKind::Msg(format!("DNS Error: {}", r)).into())
+                    }
                 }
             }
             Ok(Async::NotReady) => Ok(Async::NotReady),
-            Err(err) => Err(err.into()),
+            Err(err) => self.serve_stale(err.into()),
         }
     }
 }
@@ -412,6 +526,8 @@ impl Future for InsertCache {
                         ttl,
                         Instant::now(),
                     ))),
+                    // the stale records remain in the cache as they were
+                    Records::Stale(lookup) => Ok(Async::Ready(lookup)),
                     Records::NoData { ttl: Some(ttl) } => {
                         Err(lru.negative(query, ttl, Instant::now()))
                     }
@@ -448,17 +564,49 @@ impl<C: DnsHandle + 'static> QueryState<C> {
         options: DnsRequestOptions,
         client: &mut C,
         cache: Arc<Mutex<DnsLru>>,
+    ) -> QueryState<C> {
+        let cname_chain = vec![query.name().clone()];
+        Self::lookup_chained(query, options, client, cache, cname_chain)
+    }
+
+    /// A lookup for the next name of a CNAME chain, see `CachingClient::lookup_chained`
+    fn lookup_chained(
+        query: Query,
+        options: DnsRequestOptions,
+        client: &mut C,
+        cache: Arc<Mutex<DnsLru>>,
+        cname_chain: Vec<Name>,
     ) -> QueryState<C> {
         QueryState::FromCache(
             FromCache {
                 query,
                 options,
                 cache,
+                cname_chain,
+                prefetch: false,
             },
             client.clone(),
         )
     }
 
+    /// Refreshes the cached lookup for the query in the background, see `ResolverOpts::prefetch_percent`
+    fn prefetch(
+        query: Query,
+        options: DnsRequestOptions,
+        client: &mut C,
+        cache: Arc<Mutex<DnsLru>>,
+    ) {
+        let mut refresh = Self::lookup(query, options, client, cache);
+        refresh.query_after_cache();
+
+        let refresh = refresh
+            .map(|_| ())
+            .map_err(|e| debug!("prefetch failed: {}", e));
+        if let Err(e) = DefaultExecutor::current().spawn(Box::new(refresh)) {
+            debug!("could not spawn prefetch: {}", e);
+        }
+    }
+
     /// Query after a failed cache lookup
     ///
     /// # Panics
@@ -473,6 +621,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
                 let cache = from_cache.cache;
                 let query = from_cache.query;
                 let options = from_cache.options;
+                let cname_chain = from_cache.cname_chain;
                 let message_future = client.lookup(query.clone(), options.clone());
                 mem::replace(
                     self,
@@ -483,6 +632,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
                         dnssec: client.is_verifying_dnssec(),
                         options,
                         client: CachingClient::with_cache(cache, client),
+                        cname_chain,
                     }),
                 );
             }
@@ -506,6 +656,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
                 dnssec: _d,
                 options: _o,
                 client: _c,
+                cname_chain: _cc,
             }) => {
                 mem::replace(
                     self,
@@ -528,6 +679,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
                 dnssec: _d,
                 options: _o,
                 client: _c,
+                cname_chain: _cc,
             }) => {
                 match rdatas {
                     // There are Cnames to lookup
@@ -577,11 +729,22 @@ impl<C: DnsHandle + 'static> Future for QueryState<C> {
         // first transition any polling that is needed (mutable refs...)
         let records: Option<Records>;
         match *self {
-            QueryState::FromCache(ref mut from_cache, ..) => {
+            QueryState::FromCache(ref mut from_cache, ref mut client) => {
                 match from_cache.poll() {
                     // need to query since it wasn't in the cache
                     Ok(Async::Ready(None)) => (), // handled below
-                    Ok(Async::Ready(Some(ips))) => return Ok(Async::Ready(ips)),
+                    Ok(Async::Ready(Some(ips))) => {
+                        if from_cache.prefetch {
+                            Self::prefetch(
+                                from_cache.query.clone(),
+                                from_cache.options.clone(),
+                                client,
+                                from_cache.cache.clone(),
+                            );
+                        }
+
+                        return Ok(Async::Ready(ips));
+                    }
                     Ok(Async::NotReady) => return Ok(Async::NotReady),
                     Err(error) => return Err(error),
                 };
@@ -663,6 +826,8 @@ mod tests {
     use proto::op::{Message, Query};
     use proto::rr::rdata::SRV;
     use proto::rr::{Name, Record};
+    #[cfg(feature = "dnssec")]
+    use proto::xfer::DnsRequest;
 
     use super::*;
     use lookup_ip::tests::*;
@@ -928,6 +1093,193 @@ mod tests {
     //     );
     // }
 
+    fn cname_record(name: &str, cname: &str) -> Record {
+        Record::from_rdata(
+            Name::from_str(name).unwrap(),
+            86400,
+            RData::CNAME(Name::from_str(cname).unwrap()),
+        )
+    }
+
+    fn answers(records: Vec<Record>) -> ProtoResult<DnsResponse> {
+        let mut message = Message::new();
+        message.insert_answers(records);
+        Ok(message.into())
+    }
+
+    #[test]
+    fn test_cname_chain_across_responses() {
+        let cache = Arc::new(Mutex::new(DnsLru::new(3, dns_lru::TtlConfig::default())));
+
+        // each response only has the next CNAME of the chain, the mock pops from the back
+        let mut client = mock(vec![
+            error(),
+            answers(vec![Record::from_rdata(
+                Name::from_str("actual.example.com.").unwrap(),
+                86400,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            )]),
+            answers(vec![cname_record(
+                "alias.example.com.",
+                "actual.example.com.",
+            )]),
+            answers(vec![cname_record("www.example.com.", "alias.example.com.")]),
+        ]);
+
+        let ips = QueryState::lookup(
+            Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        )
+        .wait()
+        .expect("lookup failed");
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_cname_loop() {
+        let cache = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
+        let mut client = mock(vec![
+            error(),
+            answers(vec![cname_record("www.example.com.", "www.example.com.")]),
+        ]);
+
+        let err = QueryState::lookup(
+            Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        )
+        .wait()
+        .expect_err("lookup should have failed");
+
+        assert_eq!(
+            *err.kind(),
+            ResolveErrorKind::CnameLoop {
+                name: Name::from_str("www.example.com.").unwrap()
+            }
+        );
+
+        // the loop is also detected when it spans responses
+        let mut client = mock(vec![
+            error(),
+            answers(vec![cname_record("alias.example.com.", "www.example.com.")]),
+            answers(vec![cname_record("www.example.com.", "alias.example.com.")]),
+        ]);
+
+        let err = QueryState::lookup(
+            Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        )
+        .wait()
+        .expect_err("lookup should have failed");
+
+        assert_eq!(
+            *err.kind(),
+            ResolveErrorKind::CnameLoop {
+                name: Name::from_str("www.example.com.").unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn test_cname_chain_too_long() {
+        let mut cname_chain = vec![Name::from_str("www.example.com.").unwrap()];
+        for i in 0..MAX_CNAME_CHAIN {
+            let target = Name::from_str(&format!("{}.example.com.", i)).unwrap();
+            follow_cname(&mut cname_chain, &target).expect("chain is not too long yet");
+        }
+
+        let target = Name::from_str("last.example.com.").unwrap();
+        assert_eq!(
+            *follow_cname(&mut cname_chain, &target).unwrap_err().kind(),
+            ResolveErrorKind::CnameChainTooLong { name: target }
+        );
+    }
+
+    pub fn dname_message() -> ProtoResult<DnsResponse> {
+        let mut message = Message::new();
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            86400,
+            RData::DNAME(Name::from_str("example.net.").unwrap()),
+        )]);
+        Ok(message.into())
+    }
+
+    #[test]
+    fn test_dname_query() {
+        let cache = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
+
+        // the second query must be for the rewritten name, only that record is accepted
+        let mut message = Message::new();
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("www.example.net.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        )]);
+        let mut client = mock(vec![error(), Ok(message.into()), dname_message()]);
+
+        let ips = QueryState::lookup(
+            Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        )
+        .wait()
+        .expect("lookup failed");
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_dname_substitution() {
+        let owner = Name::from_str("example.com.").unwrap();
+        let target = Name::from_str("example.net.").unwrap();
+
+        assert_eq!(
+            dname_substitution(
+                &Name::from_str("a.b.example.com.").unwrap(),
+                &owner,
+                &target
+            )
+            .unwrap(),
+            Some(Name::from_str("a.b.example.net.").unwrap())
+        );
+
+        // the owner itself, and names outside of it, are not redirected
+        assert_eq!(dname_substitution(&owner, &owner, &target).unwrap(), None);
+        assert_eq!(
+            dname_substitution(
+                &Name::from_str("www.example.org.").unwrap(),
+                &owner,
+                &target
+            )
+            .unwrap(),
+            None
+        );
+
+        // the substituted name would be more than 255 octets
+        let label = "a".repeat(60);
+        let name = Name::from_labels(vec![&label, &label, &label, "example", "com"]).unwrap();
+        let target = Name::from_labels(vec![&label, &label, "net"]).unwrap();
+        let error = dname_substitution(&name, &owner, &target).unwrap_err();
+        assert_eq!(
+            *error.kind(),
+            ResolveErrorKind::ResponseCode(ResponseCode::YXDomain)
+        );
+    }
+
     fn cname_ttl_test(first: u32, second: u32) {
         let lru = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
         // expecting no queries to be performed
@@ -941,6 +1293,7 @@ mod tests {
             dnssec: false,
             options: Default::default(),
             client,
+            cname_chain: vec![Name::from_str("ttl.example.com.").unwrap()],
         };
 
         let mut message = Message::new();
@@ -1109,4 +1462,229 @@ mod tests {
             .wait()
             .is_ok());
     }
+
+    /// A cache holding a record for `www.example.com.` which expired 10 seconds ago
+    fn expired_cache(serve_stale: Option<Duration>) -> (Arc<Mutex<DnsLru>>, Query) {
+        let ttls = dns_lru::TtlConfig {
+            serve_stale,
+            ..Default::default()
+        };
+        let cache = Arc::new(Mutex::new(DnsLru::new(1, ttls)));
+
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+        cache.lock().unwrap().insert(
+            query.clone(),
+            vec![(
+                Record::from_rdata(
+                    query.name().clone(),
+                    60,
+                    RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+                ),
+                60,
+            )],
+            Instant::now() - Duration::from_secs(70),
+        );
+
+        (cache, query)
+    }
+
+    #[test]
+    fn test_serve_stale() {
+        let (cache, query) = expired_cache(Some(Duration::from_secs(3600)));
+        let mut client = mock(vec![error()]);
+
+        let lookup = QueryState::lookup(query.clone(), Default::default(), &mut client, cache)
+            .wait()
+            .expect("stale records should be served");
+
+        assert!(lookup.is_stale());
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+        assert_eq!(lookup.record_iter().next().unwrap().ttl(), 30);
+    }
+
+    #[test]
+    fn test_serve_stale_prefers_fresh() {
+        let (cache, query) = expired_cache(Some(Duration::from_secs(3600)));
+        let mut message = Message::new();
+        message.add_answer(Record::from_rdata(
+            query.name().clone(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 2)),
+        ));
+        let mut client = mock(vec![Ok(message.into())]);
+
+        let lookup = QueryState::lookup(query.clone(), Default::default(), &mut client, cache)
+            .wait()
+            .unwrap();
+
+        assert!(!lookup.is_stale());
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+        assert_eq!(lookup.record_iter().next().unwrap().ttl(), 86400);
+    }
+
+    #[test]
+    fn test_serve_stale_limits() {
+        // not enabled
+        let (cache, query) = expired_cache(None);
+        let mut client = mock(vec![error()]);
+        assert!(
+            QueryState::lookup(query.clone(), Default::default(), &mut client, cache)
+                .wait()
+                .is_err()
+        );
+
+        // expired for longer than the stale records are kept
+        let (cache, query) = expired_cache(Some(Duration::from_secs(5)));
+        let mut client = mock(vec![error()]);
+        assert!(
+            QueryState::lookup(query.clone(), Default::default(), &mut client, cache)
+                .wait()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_prefetch() {
+        use tokio::runtime::current_thread::Runtime;
+
+        // cached for the given seconds with a TTL of 100 seconds, prefetched in the last 10
+        let lookup_cached_for = |cached_secs: u64| -> Vec<RData> {
+            let ttls = dns_lru::TtlConfig {
+                prefetch_percent: Some(10),
+                ..Default::default()
+            };
+            let cache = Arc::new(Mutex::new(DnsLru::new(1, ttls)));
+
+            let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+            cache.lock().unwrap().insert(
+                query.clone(),
+                vec![(
+                    Record::from_rdata(
+                        query.name().clone(),
+                        100,
+                        RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+                    ),
+                    100,
+                )],
+                Instant::now() - Duration::from_secs(cached_secs),
+            );
+
+            let mut message = Message::new();
+            message.add_answer(Record::from_rdata(
+                query.name().clone(),
+                100,
+                RData::A(Ipv4Addr::new(127, 0, 0, 2)),
+            ));
+            let mut client = mock(vec![Ok(message.into())]);
+
+            // the cached records are returned, while the refresh runs in the background
+            let mut io_loop = Runtime::new().unwrap();
+            let lookup = io_loop
+                .block_on(QueryState::lookup(
+                    query.clone(),
+                    Default::default(),
+                    &mut client,
+                    cache.clone(),
+                ))
+                .unwrap();
+            assert_eq!(
+                lookup.iter().cloned().collect::<Vec<_>>(),
+                vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+            );
+            io_loop.run().unwrap();
+
+            let cached = cache.lock().unwrap().get(&query, Instant::now()).unwrap();
+            cached.iter().cloned().collect()
+        };
+
+        assert_eq!(
+            lookup_cached_for(89),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+        assert_eq!(
+            lookup_cached_for(91),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+    }
+
+    /// A client which claims to validate its responses
+    #[cfg(feature = "dnssec")]
+    #[derive(Clone)]
+    struct ValidatingHandle(MockDnsHandle);
+
+    #[cfg(feature = "dnssec")]
+    impl DnsHandle for ValidatingHandle {
+        type Response = <MockDnsHandle as DnsHandle>::Response;
+
+        fn is_verifying_dnssec(&self) -> bool {
+            true
+        }
+
+        fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+            self.0.send(request)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "dnssec")]
+    fn test_validity() {
+        let lookup = CachingClient::new(1, mock(vec![v4_message()]))
+            .lookup(Query::new(), Default::default())
+            .wait()
+            .unwrap();
+        assert_eq!(lookup.validity(), Validity::Indeterminate);
+
+        let mut client = CachingClient::new(1, ValidatingHandle(mock(vec![v4_message()])));
+        let lookup = client
+            .lookup(Query::new(), Default::default())
+            .wait()
+            .unwrap();
+        assert_eq!(lookup.validity(), Validity::Secure);
+
+        // from the cache
+        let lookup = client
+            .lookup(Query::new(), Default::default())
+            .wait()
+            .unwrap();
+        assert_eq!(lookup.validity(), Validity::Secure);
+
+        // localhost is answered by the resolver itself
+        let query = Query::query(Name::from_ascii("localhost.").unwrap(), RecordType::A);
+        let lookup = client.lookup(query, Default::default()).wait().unwrap();
+        assert_eq!(lookup.validity(), Validity::Indeterminate);
+    }
+
+    #[test]
+    #[cfg(feature = "dnssec")]
+    fn test_extended_error() {
+        use proto::error::ProtoErrorKind;
+        use proto::rr::dnssec::BogusReason;
+        use proto::rr::rdata::opt::EdnsOption;
+
+        let bogus = ProtoError::from(ProtoErrorKind::Bogus {
+            name: Name::from_ascii("www.example.com.").unwrap(),
+            record_type: RecordType::A,
+            reason: BogusReason::InvalidSignature,
+        });
+        let bogus = CachingClient::new(1, ValidatingHandle(mock(vec![Err(bogus)])))
+            .lookup(Query::new(), Default::default())
+            .wait()
+            .unwrap_err();
+        assert_eq!(
+            bogus.extended_error(),
+            Some(EdnsOption::from(BogusReason::InvalidSignature))
+        );
+
+        let failed = CachingClient::new(1, mock(vec![error()]))
+            .lookup(Query::new(), Default::default())
+            .wait()
+            .unwrap_err();
+        assert_eq!(failed.extended_error(), None);
+    }
 }
@@ -22,7 +22,7 @@ use proto::tcp::TcpClientStream;
 use proto::udp::{UdpClientStream, UdpResponse};
 use proto::xfer::{
     self, BufDnsRequestStreamHandle, DnsExchange, DnsHandle, DnsMultiplexer,
-    DnsMultiplexerSerialResponse, DnsRequest, DnsResponse,
+    DnsMultiplexerSerialResponse, DnsRequest, DnsResponse, OsRandSource, RandSource,
 };
 #[cfg(feature = "dns-over-https")]
 use trust_dns_https;
@@ -40,7 +40,22 @@ pub trait ConnectionProvider: 'static + Clone + Send + Sync {
 
 /// Standard connection implements the default mechanism for creating new Connections
 #[derive(Clone)]
-pub struct StandardConnection;
+pub struct StandardConnection {
+    rand_source: Arc<dyn RandSource>,
+}
+
+impl StandardConnection {
+    /// Uses the source for the ids of the queries, instead of `OsRandSource`
+    pub fn with_rand_source(rand_source: Arc<dyn RandSource>) -> Self {
+        StandardConnection { rand_source }
+    }
+}
+
+impl Default for StandardConnection {
+    fn default() -> Self {
+        Self::with_rand_source(Arc::new(OsRandSource))
+    }
+}
 
 impl ConnectionProvider for StandardConnection {
     type ConnHandle = ConnectionHandle;
@@ -52,36 +67,37 @@ impl ConnectionProvider for StandardConnection {
         config: &NameServerConfig,
         options: &ResolverOpts,
     ) -> Self::ConnHandle {
-        let dns_handle = match config.protocol {
-            Protocol::Udp => ConnectionHandleInner::Connect(Some(ConnectionHandleConnect::Udp {
+        let connect = match config.protocol {
+            Protocol::Udp => ConnectionHandleConnect::Udp {
                 socket_addr: config.socket_addr,
                 timeout: options.timeout,
-            })),
-            Protocol::Tcp => ConnectionHandleInner::Connect(Some(ConnectionHandleConnect::Tcp {
+            },
+            Protocol::Tcp => ConnectionHandleConnect::Tcp {
                 socket_addr: config.socket_addr,
                 timeout: options.timeout,
-            })),
+            },
             #[cfg(feature = "dns-over-tls")]
-            Protocol::Tls => ConnectionHandleInner::Connect(Some(ConnectionHandleConnect::Tls {
+            Protocol::Tls => ConnectionHandleConnect::Tls {
                 socket_addr: config.socket_addr,
                 timeout: options.timeout,
                 tls_dns_name: config.tls_dns_name.clone().unwrap_or_default(),
-            })),
+                tls_accept_invalid_hostnames: config.tls_accept_invalid_hostnames,
+            },
             #[cfg(feature = "dns-over-https")]
-            Protocol::Https => {
-                ConnectionHandleInner::Connect(Some(ConnectionHandleConnect::Https {
-                    socket_addr: config.socket_addr,
-                    timeout: options.timeout,
-                    tls_dns_name: config.tls_dns_name.clone().unwrap_or_default(),
-                }))
-            }
+            Protocol::Https => ConnectionHandleConnect::Https {
+                socket_addr: config.socket_addr,
+                timeout: options.timeout,
+                tls_dns_name: config.tls_dns_name.clone().unwrap_or_default(),
+            },
             #[cfg(feature = "mdns")]
-            Protocol::Mdns => ConnectionHandleInner::Connect(Some(ConnectionHandleConnect::Mdns {
+            Protocol::Mdns => ConnectionHandleConnect::Mdns {
                 socket_addr: config.socket_addr,
                 timeout: options.timeout,
-            })),
+            },
         };
 
+        let dns_handle = ConnectionHandleInner::Connect(self.rand_source.clone(), Some(connect));
+
         ConnectionHandle(Arc::new(Mutex::new(dns_handle)))
     }
 }
@@ -102,6 +118,7 @@ pub(crate) enum ConnectionHandleConnect {
         socket_addr: SocketAddr,
         timeout: Duration,
         tls_dns_name: String,
+        tls_accept_invalid_hostnames: bool,
     },
     #[cfg(feature = "dns-over-https")]
     Https {
@@ -119,7 +136,10 @@ pub(crate) enum ConnectionHandleConnect {
 impl ConnectionHandleConnect {
     /// Establishes the connection, this is allowed to perform network operations,
     ///   such as tokio::spawns of background tasks, etc.
-    fn connect(self) -> Result<ConnectionHandleConnected, proto::error::ProtoError> {
+    fn connect(
+        self,
+        rand_source: Arc<dyn RandSource>,
+    ) -> Result<ConnectionHandleConnected, proto::error::ProtoError> {
         use self::ConnectionHandleConnect::*;
 
         debug!("connecting: {:?}", self);
@@ -128,7 +148,8 @@ impl ConnectionHandleConnect {
                 socket_addr,
                 timeout,
             } => {
-                let stream = UdpClientStream::<TokioUdpSocket>::with_timeout(socket_addr, timeout);
+                let stream = UdpClientStream::<TokioUdpSocket>::with_timeout(socket_addr, timeout)
+                    .with_rand_source(rand_source);
                 let (stream, handle) = DnsExchange::connect(stream);
 
                 let stream = stream.and_then(|stream| stream).map_err(|e| {
@@ -151,7 +172,8 @@ impl ConnectionHandleConnect {
                     handle,
                     timeout,
                     NoopMessageFinalizer::new(),
-                );
+                )
+                .with_rand_source(rand_source);
 
                 let (stream, handle) = DnsExchange::connect(dns_conn);
                 let stream = stream.and_then(|stream| stream).map_err(|e| {
@@ -167,14 +189,20 @@ impl ConnectionHandleConnect {
                 socket_addr,
                 timeout,
                 tls_dns_name,
+                tls_accept_invalid_hostnames,
             } => {
-                let (stream, handle) = ::tls::new_tls_stream(socket_addr, tls_dns_name);
+                let (stream, handle) = ::tls::new_tls_stream(
+                    socket_addr,
+                    tls_dns_name,
+                    tls_accept_invalid_hostnames,
+                );
                 let dns_conn = DnsMultiplexer::with_timeout(
                     stream,
                     Box::new(handle),
                     timeout,
                     NoopMessageFinalizer::new(),
-                );
+                )
+                .with_rand_source(rand_source);
 
                 let (stream, handle) = DnsExchange::connect(dns_conn);
                 let stream = stream.and_then(|stream| stream).map_err(|e| {
@@ -214,7 +242,8 @@ impl ConnectionHandleConnect {
                     handle,
                     timeout,
                     NoopMessageFinalizer::new(),
-                );
+                )
+                .with_rand_source(rand_source);
 
                 let (stream, handle) = DnsExchange::connect(dns_conn);
                 let stream = stream.and_then(|stream| stream).map_err(|e| {
@@ -259,7 +288,7 @@ impl DnsHandle for ConnectionHandleConnected {
 
 /// Allows us to wrap a connection that is either pending or already connected
 enum ConnectionHandleInner {
-    Connect(Option<ConnectionHandleConnect>),
+    Connect(Arc<dyn RandSource>, Option<ConnectionHandleConnect>),
     Connected(ConnectionHandleConnected),
 }
 
@@ -269,9 +298,10 @@ impl ConnectionHandleInner {
             let connected: Result<ConnectionHandleConnected, proto::error::ProtoError> = match self
             {
                 // still need to connect, drop through
-                ConnectionHandleInner::Connect(conn) => {
-                    conn.take().expect("already connected?").connect()
-                }
+                ConnectionHandleInner::Connect(rand_source, conn) => conn
+                    .take()
+                    .expect("already connected?")
+                    .connect(rand_source.clone()),
                 ConnectionHandleInner::Connected(conn) => return conn.send(request),
             };
 
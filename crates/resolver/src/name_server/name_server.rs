@@ -44,7 +44,7 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> Debug for NameServer<C
 
 impl NameServer<ConnectionHandle, StandardConnection> {
     pub fn new(config: NameServerConfig, options: ResolverOpts) -> Self {
-        Self::new_with_provider(config, options, StandardConnection)
+        Self::new_with_provider(config, options, StandardConnection::default())
     }
 }
 
@@ -143,11 +143,18 @@ where
                     //   see https://github.com/bluejekyll/trust-dns/issues/606
                     //   TODO: there are probably other return codes from the server we may want to
                     //    retry on. We may also want to evaluate NoError responses that lack records as errors as well
+                    //   a server that refuses the query may not serve the zone, or not this client
                     if distrust_nx_responses {
-                        if let ResponseCode::ServFail = response.response_code() {
-                            let note = "Nameserver responded with SERVFAIL";
-                            debug!("{}", note);
-                            return future::err(ProtoError::from(note));
+                        match response.response_code() {
+                            ResponseCode::ServFail | ResponseCode::Refused => {
+                                let note = format!(
+                                    "Nameserver responded with {}",
+                                    response.response_code()
+                                );
+                                debug!("{}", note);
+                                return future::err(ProtoError::from(note));
+                            }
+                            _ => (),
                         }
                     }
 
@@ -233,6 +240,7 @@ where
         socket_addr: *MDNS_IPV4,
         protocol: Protocol::Mdns,
         tls_dns_name: None,
+        tls_accept_invalid_hostnames: false,
     };
     NameServer::new_with_provider(config, options, conn_provider)
 }
@@ -262,6 +270,7 @@ mod tests {
             socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         };
         let mut io_loop = Runtime::new().unwrap();
         let name_server = future::lazy(|| {
@@ -291,6 +300,7 @@ mod tests {
             socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 252)), 252),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         };
         let mut io_loop = Runtime::new().unwrap();
         let name_server =
@@ -18,7 +18,7 @@ use proto::xfer::{DnsHandle, DnsRequest, DnsResponse};
 use config::{ResolverConfig, ResolverOpts};
 #[cfg(feature = "mdns")]
 use name_server;
-use name_server::{ConnectionHandle, ConnectionProvider, NameServer, StandardConnection};
+use name_server::{ConnectionProvider, NameServer};
 
 /// A pool of NameServers
 ///
@@ -34,12 +34,6 @@ pub struct NameServerPool<C: DnsHandle + 'static, P: ConnectionProvider<ConnHand
     conn_provider: P,
 }
 
-impl NameServerPool<ConnectionHandle, StandardConnection> {
-    pub(crate) fn from_config(config: &ResolverConfig, options: &ResolverOpts) -> Self {
-        Self::from_config_with_provider(config, options, StandardConnection)
-    }
-}
-
 impl<C: DnsHandle + 'static, P: ConnectionProvider<ConnHandle = C> + 'static> NameServerPool<C, P> {
     pub(crate) fn from_config_with_provider(
         config: &ResolverConfig,
@@ -387,6 +381,7 @@ mod tests {
     use proto::xfer::{DnsHandle, DnsRequestOptions};
 
     use super::*;
+    use name_server::StandardConnection;
     use config::NameServerConfig;
     use config::Protocol;
 
@@ -398,12 +393,14 @@ mod tests {
             socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 252)), 253),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         };
 
         let config2 = NameServerConfig {
             socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         };
 
         let mut resolver_config = ResolverConfig::new();
@@ -411,9 +408,10 @@ mod tests {
         resolver_config.add_name_server(config2);
 
         let mut io_loop = Runtime::new().unwrap();
-        let mut pool = NameServerPool::<_, StandardConnection>::from_config(
+        let mut pool = NameServerPool::<_, StandardConnection>::from_config_with_provider(
             &resolver_config,
             &ResolverOpts::default(),
+            StandardConnection::default(),
         );
 
         let name = Name::parse("www.example.com.", None).unwrap();
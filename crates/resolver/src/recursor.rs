@@ -0,0 +1,1013 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Iterative resolution, following the delegations from the root zone down to the authoritative
+//! servers of a name
+//!
+//! Unlike the stub resolution of the `AsyncResolver`, which asks the configured name servers to
+//! recurse, the `Recursor` queries the servers of each zone itself. With qname minimization,
+//! [RFC 7816](https://tools.ietf.org/html/rfc7816), every server only learns one more label of the
+//! name than the zone it is authoritative for.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Loop};
+use futures::{Future, Poll};
+use lru_cache::LruCache;
+
+use proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use proto::rr::{Name, RData, Record, RecordType};
+use proto::xfer::{DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse};
+
+use dns_lru::DnsLru;
+use error::*;
+use lookup::Lookup;
+
+/// The most queries which are sent for the resolution of a single query
+const MAX_QUERIES: usize = 32;
+
+/// The number of zones whose servers are remembered
+const DELEGATION_CACHE_SIZE: usize = 1024;
+
+/// The servers of the root zone, as published by IANA in
+/// [named.root](https://www.internic.net/domain/named.root)
+pub const ROOT_HINTS: [(&str, Ipv4Addr, Ipv6Addr); 13] = [
+    (
+        "a.root-servers.net.",
+        Ipv4Addr::new(198, 41, 0, 4),
+        Ipv6Addr::new(0x2001, 0x503, 0xba3e, 0, 0, 0, 0x2, 0x30),
+    ),
+    (
+        "b.root-servers.net.",
+        Ipv4Addr::new(170, 247, 170, 2),
+        Ipv6Addr::new(0x2801, 0x1b8, 0x10, 0, 0, 0, 0, 0xb),
+    ),
+    (
+        "c.root-servers.net.",
+        Ipv4Addr::new(192, 33, 4, 12),
+        Ipv6Addr::new(0x2001, 0x500, 0x2, 0, 0, 0, 0, 0xc),
+    ),
+    (
+        "d.root-servers.net.",
+        Ipv4Addr::new(199, 7, 91, 13),
+        Ipv6Addr::new(0x2001, 0x500, 0x2d, 0, 0, 0, 0, 0xd),
+    ),
+    (
+        "e.root-servers.net.",
+        Ipv4Addr::new(192, 203, 230, 10),
+        Ipv6Addr::new(0x2001, 0x500, 0xa8, 0, 0, 0, 0, 0xe),
+    ),
+    (
+        "f.root-servers.net.",
+        Ipv4Addr::new(192, 5, 5, 241),
+        Ipv6Addr::new(0x2001, 0x500, 0x2f, 0, 0, 0, 0, 0xf),
+    ),
+    (
+        "g.root-servers.net.",
+        Ipv4Addr::new(192, 112, 36, 4),
+        Ipv6Addr::new(0x2001, 0x500, 0x12, 0, 0, 0, 0, 0xd0d),
+    ),
+    (
+        "h.root-servers.net.",
+        Ipv4Addr::new(198, 97, 190, 53),
+        Ipv6Addr::new(0x2001, 0x500, 0x1, 0, 0, 0, 0, 0x53),
+    ),
+    (
+        "i.root-servers.net.",
+        Ipv4Addr::new(192, 36, 148, 17),
+        Ipv6Addr::new(0x2001, 0x7fe, 0, 0, 0, 0, 0, 0x53),
+    ),
+    (
+        "j.root-servers.net.",
+        Ipv4Addr::new(192, 58, 128, 30),
+        Ipv6Addr::new(0x2001, 0x503, 0xc27, 0, 0, 0, 0x2, 0x30),
+    ),
+    (
+        "k.root-servers.net.",
+        Ipv4Addr::new(193, 0, 14, 129),
+        Ipv6Addr::new(0x2001, 0x7fd, 0, 0, 0, 0, 0, 0x1),
+    ),
+    (
+        "l.root-servers.net.",
+        Ipv4Addr::new(199, 7, 83, 42),
+        Ipv6Addr::new(0x2001, 0x500, 0x9f, 0, 0, 0, 0, 0x42),
+    ),
+    (
+        "m.root-servers.net.",
+        Ipv4Addr::new(202, 12, 27, 33),
+        Ipv6Addr::new(0x2001, 0xdc3, 0, 0, 0, 0, 0, 0x35),
+    ),
+];
+
+/// Returns the addresses of the `ROOT_HINTS` on port 53, the IPv4 addresses first
+pub fn root_hints() -> Vec<SocketAddr> {
+    let ipv4 = ROOT_HINTS.iter().map(|&(_, ip, _)| IpAddr::V4(ip));
+    let ipv6 = ROOT_HINTS.iter().map(|&(_, _, ip)| IpAddr::V6(ip));
+
+    ipv4.chain(ipv6).map(|ip| SocketAddr::new(ip, 53)).collect()
+}
+
+/// An iterative resolver, following referrals from the root servers to the authoritative servers
+///
+/// The servers are queried without requesting recursion, in turn until one of the servers of each
+/// zone answers.
+/// Only delegations with glue records for the servers of the child zone are followed, and CNAMEs
+/// are returned like any other answer, they are not followed.
+///
+/// The servers of delegated zones are cached for the TTL of their NS records, resolutions of names
+/// within such a zone start at its servers instead of the root servers.
+pub struct Recursor<F> {
+    roots: Vec<SocketAddr>,
+    connect: Arc<F>,
+    qname_minimization: bool,
+    delegations: Arc<Mutex<LruCache<Name, Delegation>>>,
+}
+
+/// The cached servers of a zone
+struct Delegation {
+    servers: Vec<SocketAddr>,
+    valid_until: Instant,
+}
+
+impl<C, F> Recursor<F>
+where
+    C: DnsHandle + 'static,
+    F: Fn(SocketAddr) -> C + Send + Sync + 'static,
+{
+    /// Creates a new recursor
+    ///
+    /// # Arguments
+    ///
+    /// * `roots` - the addresses of the servers of the root zone, the root hints
+    /// * `connect` - returns a connection to the server of the address, the servers of delegated
+    ///   zones are connected to on port 53
+    pub fn new(roots: Vec<SocketAddr>, connect: F) -> Self {
+        Recursor {
+            roots,
+            connect: Arc::new(connect),
+            qname_minimization: false,
+            delegations: Arc::new(Mutex::new(LruCache::new(DELEGATION_CACHE_SIZE))),
+        }
+    }
+
+    /// Creates a new recursor, starting the resolutions at the servers of the `ROOT_HINTS`
+    pub fn with_root_hints(connect: F) -> Self {
+        Self::new(root_hints(), connect)
+    }
+
+    /// Minimize the names sent to the servers, [RFC 7816](https://tools.ietf.org/html/rfc7816)
+    ///
+    /// The servers of each zone are asked for the NS records of the name with one more label than
+    /// the zone, and only the servers of the last zone are sent the query. A response without any
+    /// records for such a name, i.e. an empty non-terminal, proceeds with the next label.
+    pub fn set_qname_minimization(&mut self, qname_minimization: bool) -> &mut Self {
+        self.qname_minimization = qname_minimization;
+        self
+    }
+
+    /// Resolves the query, starting from the closest cached zone of the name, or the root servers
+    pub fn resolve(&self, query: Query) -> RecursorFuture {
+        let start = match self.cached_zone(query.name(), Instant::now()) {
+            Some(zone) => zone,
+            None if self.roots.is_empty() => {
+                return RecursorFuture(Box::new(future::err(
+                    ResolveErrorKind::Message("no root servers to query").into(),
+                )))
+            }
+            None => Zone {
+                name: Name::root(),
+                servers: self.roots.clone(),
+                probed_labels: 0,
+                ttl: u32::max_value(),
+            },
+        };
+
+        let connect = self.connect.clone();
+        let delegations = self.delegations.clone();
+        let qname_minimization = self.qname_minimization;
+
+        let resolution = future::loop_fn((start, 0), move |(zone, queries)| {
+            if queries >= MAX_QUERIES {
+                return future::Either::A(future::err(
+                    ResolveErrorKind::Msg(format!(
+                        "resolution of {} exceeded {} queries",
+                        query.name(),
+                        MAX_QUERIES
+                    ))
+                    .into(),
+                ));
+            }
+
+            let sent = zone.next_query(&query, qname_minimization);
+            let query = query.clone();
+            let delegations = delegations.clone();
+
+            future::Either::B(
+                send_to_servers(&connect, zone.servers.clone(), sent.clone()).and_then(
+                    move |response| {
+                        zone.next(&query, &sent, &response).map(|next| match next {
+                            Loop::Break(lookup) => Loop::Break(lookup),
+                            Loop::Continue(next) => {
+                                if next.name != zone.name {
+                                    cache_zone(&delegations, &next, Instant::now());
+                                }
+                                Loop::Continue((next, queries + 1))
+                            }
+                        })
+                    },
+                ),
+            )
+        });
+
+        RecursorFuture(Box::new(resolution))
+    }
+
+    /// Returns the closest zone containing the name whose servers are cached
+    fn cached_zone(&self, name: &Name, now: Instant) -> Option<Zone> {
+        let mut delegations = self.delegations.lock().expect("delegations lock poisoned");
+
+        let mut zone = name.clone();
+        while !zone.is_root() {
+            let expired = match delegations.get_mut(&zone) {
+                Some(delegation) if delegation.valid_until > now => {
+                    let ttl = delegation.valid_until.duration_since(now).as_secs();
+                    return Some(Zone {
+                        probed_labels: zone.num_labels(),
+                        name: zone,
+                        servers: delegation.servers.clone(),
+                        ttl: ttl.min(u64::from(u32::max_value())) as u32,
+                    });
+                }
+                Some(_) => true,
+                None => false,
+            };
+
+            if expired {
+                delegations.remove(&zone);
+            }
+            zone = zone.base_name();
+        }
+
+        None
+    }
+}
+
+/// Remembers the servers of the zone for the TTL of its NS records
+fn cache_zone(delegations: &Mutex<LruCache<Name, Delegation>>, zone: &Zone, now: Instant) {
+    if zone.name.is_root() || zone.ttl == 0 {
+        return;
+    }
+
+    let delegation = Delegation {
+        servers: zone.servers.clone(),
+        valid_until: now + Duration::from_secs(u64::from(zone.ttl)),
+    };
+    delegations
+        .lock()
+        .expect("delegations lock poisoned")
+        .insert(zone.name.clone(), delegation);
+}
+
+/// Sends the query to the servers of a zone in turn, until one of them answers it
+///
+/// A server which fails, or responds with another error than NXDOMAIN, is skipped. The response, or
+///  the error, of the last server is returned.
+fn send_to_servers<C, F>(
+    connect: &Arc<F>,
+    servers: Vec<SocketAddr>,
+    query: Query,
+) -> Box<dyn Future<Item = DnsResponse, Error = ResolveError> + Send>
+where
+    C: DnsHandle + 'static,
+    F: Fn(SocketAddr) -> C + Send + Sync + 'static,
+{
+    if servers.is_empty() {
+        return Box::new(future::err(
+            ResolveErrorKind::Msg(format!("no servers to query for {}", query.name())).into(),
+        ));
+    }
+
+    let connect = connect.clone();
+    Box::new(future::loop_fn(0, move |index| {
+        let server = servers[index];
+        let is_last = index + 1 == servers.len();
+
+        connect(server)
+            .send(request(query.clone()))
+            .then(move |result| match result {
+                Ok(ref response) if !is_last && !is_answer(response) => {
+                    debug!("{} responded {}", server, response.response_code());
+                    Ok(Loop::Continue(index + 1))
+                }
+                Err(ref e) if !is_last => {
+                    debug!("{} failed: {}", server, e);
+                    Ok(Loop::Continue(index + 1))
+                }
+                Ok(response) => Ok(Loop::Break(response)),
+                Err(e) => Err(e.into()),
+            })
+    }))
+}
+
+/// Returns true if the server answered the query, the response is not an error of the server
+fn is_answer(response: &DnsResponse) -> bool {
+    let code = response.response_code();
+    code == ResponseCode::NoError || code == ResponseCode::NXDomain
+}
+
+/// The result of an iterative resolution
+#[must_use = "futures do nothing unless polled"]
+pub struct RecursorFuture(Box<dyn Future<Item = Lookup, Error = ResolveError> + Send>);
+
+impl Future for RecursorFuture {
+    type Item = Lookup;
+    type Error = ResolveError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.0.poll()
+    }
+}
+
+/// Builds a query message, which does not request recursion
+fn request(query: Query) -> DnsRequest {
+    let mut message = Message::new();
+    message
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(false)
+        .add_query(query);
+
+    DnsRequest::new(message, DnsRequestOptions::default())
+}
+
+/// The zone whose servers are queried next
+#[derive(Clone, Debug)]
+struct Zone {
+    name: Name,
+    servers: Vec<SocketAddr>,
+    /// the number of labels of the name which were already sent to the servers of this zone
+    probed_labels: u8,
+    /// the TTL of the NS records of the zone
+    ttl: u32,
+}
+
+impl Zone {
+    /// Returns the query for the servers of this zone, the minimized query or the query itself
+    fn next_query(&self, query: &Query, qname_minimization: bool) -> Query {
+        if !qname_minimization || self.probed_labels + 1 >= query.name().num_labels() {
+            return query.clone();
+        }
+
+        // only one more label than the servers already know, RFC 7816 section 2
+        let name = query.name().trim_to(usize::from(self.probed_labels) + 1);
+        let mut minimized = Query::query(name, RecordType::NS);
+        minimized.set_query_class(query.query_class());
+        minimized
+    }
+
+    /// Evaluates the response to the query sent to the servers of this zone
+    ///
+    /// # Returns
+    ///
+    /// The zone to query next, or the answers to the query
+    fn next(
+        &self,
+        query: &Query,
+        sent: &Query,
+        response: &DnsResponse,
+    ) -> ResolveResult<Loop<Lookup, Zone>> {
+        match response.response_code() {
+            ResponseCode::NoError => (),
+            // the name does not exist, if any name above it does not, RFC 8020
+            ResponseCode::NXDomain => return Err(DnsLru::nx_error(query.clone(), None)),
+            code => return Err(ResolveErrorKind::ResponseCode(code).into()),
+        }
+
+        if response.answers().is_empty() {
+            if let Some(referral) = self.referral(query.name(), response)? {
+                return Ok(Loop::Continue(referral));
+            }
+        }
+
+        if sent == query {
+            let records = self.in_bailiwick(response.answers());
+            if records.is_empty() {
+                return Err(DnsLru::nx_error(query.clone(), None));
+            }
+
+            let valid_until = Instant::now() + Duration::from_secs(u64::from(min_ttl(&records)));
+            return Ok(Loop::Break(Lookup::new_with_deadline(
+                query.clone(),
+                Arc::new(records),
+                valid_until,
+            )));
+        }
+
+        // the servers of this zone are authoritative for the probed name as well, it is either
+        //  the apex of a zone they serve, or it has no records of its own
+        let delegation = response
+            .answers()
+            .iter()
+            .filter(|r| r.rr_type() == RecordType::NS && r.name() == sent.name())
+            .cloned()
+            .collect::<Vec<_>>();
+        let servers = glue(&delegation, &self.in_bailiwick(response.additionals()));
+
+        if delegation.is_empty() || servers.is_empty() {
+            return Ok(Loop::Continue(Zone {
+                probed_labels: sent.name().num_labels(),
+                ..self.clone()
+            }));
+        }
+
+        Ok(Loop::Continue(Zone {
+            name: sent.name().clone(),
+            servers,
+            probed_labels: sent.name().num_labels(),
+            ttl: min_ttl(&delegation),
+        }))
+    }
+
+    /// Returns the zone below this one containing the name, if the response delegates to it
+    ///
+    /// # Errors
+    ///
+    /// If the response contains no glue for the servers of the delegated zone
+    fn referral(&self, name: &Name, response: &DnsResponse) -> ResolveResult<Option<Zone>> {
+        let delegation = response
+            .name_servers()
+            .iter()
+            .filter(|r| {
+                r.rr_type() == RecordType::NS
+                    && r.name() != &self.name
+                    && self.name.zone_of(r.name())
+                    && r.name().zone_of(name)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let zone = match delegation.first() {
+            Some(record) => record.name().clone(),
+            None => return Ok(None),
+        };
+
+        let servers = glue(&delegation, &self.in_bailiwick(response.additionals()));
+        if servers.is_empty() {
+            return Err(
+                ResolveErrorKind::Msg(format!("no glue for the servers of {}", zone)).into(),
+            );
+        }
+
+        Ok(Some(Zone {
+            probed_labels: zone.num_labels(),
+            name: zone,
+            servers,
+            ttl: min_ttl(&delegation),
+        }))
+    }
+
+    /// Returns the records the servers of this zone are authoritative for, the others are dropped
+    fn in_bailiwick(&self, records: &[Record]) -> Vec<Record> {
+        records
+            .iter()
+            .filter(|r| {
+                let in_bailiwick = is_in_bailiwick(&self.name, r.name());
+                if !in_bailiwick {
+                    debug!("dropping {} from the servers of {}", r.name(), self.name);
+                }
+                in_bailiwick
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns true if the servers of the zone are authoritative for the name of a record
+///
+/// Records of names outside of the zone of the responding servers must not be trusted, they could
+///  poison the cache with addresses for names of other zones.
+pub fn is_in_bailiwick(zone: &Name, record_name: &Name) -> bool {
+    zone.zone_of(record_name)
+}
+
+/// Returns the lowest TTL of the records
+fn min_ttl(records: &[Record]) -> u32 {
+    records.iter().map(Record::ttl).min().unwrap_or(0)
+}
+
+/// A name server of a delegated zone, with its addresses from the referral
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReferralServer {
+    name: Name,
+    zone: Name,
+    addresses: Vec<IpAddr>,
+}
+
+impl ReferralServer {
+    /// The name of the server, the target of the NS record
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// The delegated zone, the owner of the NS record
+    pub fn zone(&self) -> &Name {
+        &self.zone
+    }
+
+    /// The A and AAAA records of the server in the additional section, the glue
+    pub fn addresses(&self) -> &[IpAddr] {
+        &self.addresses
+    }
+
+    /// The name of the server is within the delegated zone
+    ///
+    /// The servers of the parent zone can only know the addresses of such servers from the glue,
+    ///  the addresses of other servers are not theirs to provide.
+    pub fn is_in_bailiwick(&self) -> bool {
+        is_in_bailiwick(&self.zone, &self.name)
+    }
+
+    /// There is no glue for the server, its addresses need to be resolved separately
+    pub fn needs_resolution(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+/// Returns the name servers of the zones delegated to in the referral, paired with their glue
+///
+/// The servers are those of the NS records in the authority section, in their order, and the glue
+///  are the A and AAAA records of their names in the additional section.
+pub fn referral_servers(referral: &Message) -> Vec<ReferralServer> {
+    servers_with_glue(referral.name_servers(), referral.additionals())
+}
+
+fn servers_with_glue(name_servers: &[Record], additionals: &[Record]) -> Vec<ReferralServer> {
+    let mut servers: Vec<ReferralServer> = Vec::new();
+
+    for record in name_servers {
+        let target = match *record.rdata() {
+            RData::NS(ref target) => target,
+            _ => continue,
+        };
+        if servers
+            .iter()
+            .any(|s| &s.name == target && &s.zone == record.name())
+        {
+            continue;
+        }
+
+        let addresses = additionals
+            .iter()
+            .filter(|r| r.name() == target)
+            .filter_map(|r| match *r.rdata() {
+                RData::A(ip) => Some(IpAddr::V4(ip)),
+                RData::AAAA(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            })
+            .collect();
+
+        servers.push(ReferralServer {
+            name: target.clone(),
+            zone: record.name().clone(),
+            addresses,
+        });
+    }
+
+    servers
+}
+
+/// Returns the addresses of the additional records for the targets of the NS records
+fn glue(name_servers: &[Record], additionals: &[Record]) -> Vec<SocketAddr> {
+    servers_with_glue(name_servers, additionals)
+        .iter()
+        .flat_map(|server| server.addresses().iter())
+        .map(|ip| SocketAddr::new(*ip, 53))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    use proto::error::ProtoError;
+
+    use super::*;
+
+    type Seen = Arc<Mutex<Vec<(IpAddr, Query)>>>;
+
+    const ROOT: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const COM: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+    const EXAMPLE_COM: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 3);
+    /// A server which does not respond
+    const UNREACHABLE: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 4);
+    /// A server which refuses to answer
+    const LAME: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 5);
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn referral(zone: &str, server: &str, glue: Ipv4Addr) -> Message {
+        let mut message = Message::new();
+        message.set_message_type(MessageType::Response);
+        message.add_name_server(Record::from_rdata(
+            name(zone),
+            86400,
+            RData::NS(name(server)),
+        ));
+        message.add_additional(Record::from_rdata(name(server), 86400, RData::A(glue)));
+        message
+    }
+
+    /// The servers of the root, com. and example.com. zones, where a.example.com. is an empty
+    ///  non-terminal
+    fn respond(server: IpAddr, query: &Query) -> Message {
+        match server {
+            IpAddr::V4(ROOT) => referral("com.", "a.gtld-servers.net.", COM),
+            IpAddr::V4(COM) => referral("example.com.", "ns.example.com.", EXAMPLE_COM),
+            IpAddr::V4(EXAMPLE_COM) => {
+                let mut message = Message::new();
+                message.set_message_type(MessageType::Response);
+
+                if query.name() == &name("www.a.example.com.") {
+                    message.add_answer(Record::from_rdata(
+                        query.name().clone(),
+                        300,
+                        RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+                    ));
+                } else if query.name() == &name("poison.example.com.") {
+                    message
+                        .add_answer(Record::from_rdata(
+                            query.name().clone(),
+                            300,
+                            RData::CNAME(name("www.evil.com.")),
+                        ))
+                        .add_answer(Record::from_rdata(
+                            name("www.evil.com."),
+                            300,
+                            RData::A(Ipv4Addr::new(203, 0, 113, 66)),
+                        ));
+                } else if query.name() != &name("a.example.com.") {
+                    message.set_response_code(ResponseCode::NXDomain);
+                }
+
+                message
+            }
+            IpAddr::V4(LAME) => {
+                let mut message = Message::new();
+                message
+                    .set_message_type(MessageType::Response)
+                    .set_response_code(ResponseCode::Refused);
+                message
+            }
+            _ => panic!("unexpected server {}", server),
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockServer {
+        addr: SocketAddr,
+        seen: Seen,
+    }
+
+    impl DnsHandle for MockServer {
+        type Response = Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+        fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+            let request = request.into();
+            assert!(!request.recursion_desired());
+
+            let query = request.queries()[0].clone();
+            self.seen
+                .lock()
+                .unwrap()
+                .push((self.addr.ip(), query.clone()));
+            if self.addr.ip() == IpAddr::V4(UNREACHABLE) {
+                return Box::new(future::err(ProtoError::from("unreachable")));
+            }
+            Box::new(future::ok(respond(self.addr.ip(), &query).into()))
+        }
+    }
+
+    fn recursor(seen: &Seen) -> Recursor<impl Fn(SocketAddr) -> MockServer> {
+        recursor_with_roots(seen, &[ROOT])
+    }
+
+    fn recursor_with_roots(
+        seen: &Seen,
+        roots: &[Ipv4Addr],
+    ) -> Recursor<impl Fn(SocketAddr) -> MockServer> {
+        let seen = seen.clone();
+        let roots = roots
+            .iter()
+            .map(|&ip| SocketAddr::new(IpAddr::V4(ip), 53))
+            .collect();
+        Recursor::new(roots, move |addr| MockServer {
+            addr,
+            seen: seen.clone(),
+        })
+    }
+
+    fn seen_by(seen: &Seen, server: Ipv4Addr) -> Vec<(Name, RecordType)> {
+        seen.lock()
+            .unwrap()
+            .iter()
+            .filter(|&&(ip, _)| ip == IpAddr::V4(server))
+            .map(|(_, query)| (query.name().clone(), query.query_type()))
+            .collect()
+    }
+
+    #[test]
+    fn test_qname_minimization() {
+        let seen = Seen::default();
+        let mut recursor = recursor(&seen);
+        recursor.set_qname_minimization(true);
+
+        let lookup = recursor
+            .resolve(Query::query(name("www.a.example.com."), RecordType::A))
+            .wait()
+            .expect("resolution failed");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+
+        assert_eq!(seen_by(&seen, ROOT), vec![(name("com."), RecordType::NS)]);
+        assert_eq!(
+            seen_by(&seen, COM),
+            vec![(name("example.com."), RecordType::NS)]
+        );
+        // the empty non-terminal a.example.com. has no records, the resolution proceeds
+        assert_eq!(
+            seen_by(&seen, EXAMPLE_COM),
+            vec![
+                (name("a.example.com."), RecordType::NS),
+                (name("www.a.example.com."), RecordType::A),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_qname() {
+        let seen = Seen::default();
+        let recursor = recursor(&seen);
+
+        let lookup = recursor
+            .resolve(Query::query(name("www.a.example.com."), RecordType::A))
+            .wait()
+            .expect("resolution failed");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+
+        for &server in &[ROOT, COM, EXAMPLE_COM] {
+            assert_eq!(
+                seen_by(&seen, server),
+                vec![(name("www.a.example.com."), RecordType::A)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_nxdomain() {
+        let seen = Seen::default();
+        let mut recursor = recursor(&seen);
+        recursor.set_qname_minimization(true);
+
+        let error = recursor
+            .resolve(Query::query(name("www.b.example.com."), RecordType::A))
+            .wait()
+            .unwrap_err();
+        match *error.kind() {
+            ResolveErrorKind::NoRecordsFound { ref query, .. } => {
+                assert_eq!(query.name(), &name("www.b.example.com."))
+            }
+            ref kind => panic!("expected NoRecordsFound, got {:?}", kind),
+        }
+
+        // the resolution stops at the non-existent b.example.com.
+        assert_eq!(
+            seen_by(&seen, EXAMPLE_COM),
+            vec![(name("b.example.com."), RecordType::NS)]
+        );
+    }
+
+    #[test]
+    fn test_failing_servers() {
+        let seen = Seen::default();
+        let recursor = recursor_with_roots(&seen, &[UNREACHABLE, LAME, ROOT]);
+
+        let lookup = recursor
+            .resolve(Query::query(name("www.a.example.com."), RecordType::A))
+            .wait()
+            .expect("resolution failed");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+
+        // the other servers of the zone are asked in turn
+        for &server in &[UNREACHABLE, LAME, ROOT] {
+            assert_eq!(
+                seen_by(&seen, server),
+                vec![(name("www.a.example.com."), RecordType::A)]
+            );
+        }
+
+        // the error of the last server is returned
+        let recursor = recursor_with_roots(&seen, &[UNREACHABLE, LAME]);
+        let error = recursor
+            .resolve(Query::query(name("www.a.example.com."), RecordType::A))
+            .wait()
+            .unwrap_err();
+        assert_eq!(
+            *error.kind(),
+            ResolveErrorKind::ResponseCode(ResponseCode::Refused)
+        );
+    }
+
+    #[test]
+    fn test_cached_delegations() {
+        let seen = Seen::default();
+        let recursor = recursor(&seen);
+        let query = Query::query(name("www.a.example.com."), RecordType::A);
+
+        recursor
+            .resolve(query.clone())
+            .wait()
+            .expect("resolution failed");
+        seen.lock().unwrap().clear();
+
+        // the servers of example.com. are known, neither the root nor com. are asked again
+        let lookup = recursor.resolve(query).wait().expect("resolution failed");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+        assert!(seen_by(&seen, ROOT).is_empty());
+        assert!(seen_by(&seen, COM).is_empty());
+        assert_eq!(
+            seen_by(&seen, EXAMPLE_COM),
+            vec![(name("www.a.example.com."), RecordType::A)]
+        );
+
+        let now = Instant::now();
+        let zone = recursor
+            .cached_zone(&name("mail.example.com."), now)
+            .expect("example.com. not cached");
+        assert_eq!(zone.name, name("example.com."));
+        assert_eq!(
+            zone.servers,
+            vec![SocketAddr::new(IpAddr::V4(EXAMPLE_COM), 53)]
+        );
+
+        // the delegations expire with the TTL of the NS records
+        let expired = now + Duration::from_secs(86401);
+        assert!(recursor
+            .cached_zone(&name("mail.example.com."), expired)
+            .is_none());
+    }
+
+    #[test]
+    fn test_root_hints() {
+        let roots = root_hints();
+        assert_eq!(roots.len(), 26);
+        assert!(roots.iter().all(|addr| addr.port() == 53));
+        assert_eq!(
+            roots[0],
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(198, 41, 0, 4)), 53)
+        );
+        assert!(ROOT_HINTS
+            .iter()
+            .all(|&(server, _, _)| name(server).base_name() == name("root-servers.net.")));
+    }
+
+    #[test]
+    fn test_referral_servers() {
+        let mut message = referral("example.com.", "ns1.example.com.", EXAMPLE_COM);
+        message
+            .add_additional(Record::from_rdata(
+                name("ns1.example.com."),
+                86400,
+                RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)),
+            ))
+            .add_name_server(Record::from_rdata(
+                name("example.com."),
+                86400,
+                RData::NS(name("ns.example.net.")),
+            ))
+            .add_additional(Record::from_rdata(
+                name("ns.example.net."),
+                86400,
+                RData::A(Ipv4Addr::new(192, 0, 2, 53)),
+            ))
+            .add_name_server(Record::from_rdata(
+                name("example.com."),
+                86400,
+                RData::NS(name("ns.example.org.")),
+            ));
+
+        let servers = referral_servers(&message);
+        assert_eq!(servers.len(), 3);
+
+        assert_eq!(servers[0].name(), &name("ns1.example.com."));
+        assert_eq!(servers[0].zone(), &name("example.com."));
+        assert_eq!(
+            servers[0].addresses(),
+            &[
+                IpAddr::V4(EXAMPLE_COM),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)),
+            ]
+        );
+        assert!(servers[0].is_in_bailiwick());
+        assert!(!servers[0].needs_resolution());
+
+        assert_eq!(servers[1].name(), &name("ns.example.net."));
+        assert_eq!(
+            servers[1].addresses(),
+            &[IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53))]
+        );
+        assert!(!servers[1].is_in_bailiwick());
+        assert!(!servers[1].needs_resolution());
+
+        assert_eq!(servers[2].name(), &name("ns.example.org."));
+        assert!(servers[2].addresses().is_empty());
+        assert!(!servers[2].is_in_bailiwick());
+        assert!(servers[2].needs_resolution());
+    }
+
+    #[test]
+    fn test_is_in_bailiwick() {
+        assert!(is_in_bailiwick(
+            &name("example.com."),
+            &name("www.example.com.")
+        ));
+        assert!(is_in_bailiwick(
+            &name("example.com."),
+            &name("example.com.")
+        ));
+        assert!(is_in_bailiwick(&Name::root(), &name("www.evil.com.")));
+        assert!(!is_in_bailiwick(
+            &name("example.com."),
+            &name("www.evil.com.")
+        ));
+        assert!(!is_in_bailiwick(&name("example.com."), &name("com.")));
+    }
+
+    #[test]
+    fn test_out_of_bailiwick_answer() {
+        let seen = Seen::default();
+        let recursor = recursor(&seen);
+
+        let lookup = recursor
+            .resolve(Query::query(name("poison.example.com."), RecordType::A))
+            .wait()
+            .expect("resolution failed");
+
+        // the servers of example.com. are not authoritative for the address of www.evil.com.
+        assert_eq!(
+            lookup.record_iter().map(Record::name).collect::<Vec<_>>(),
+            vec![&name("poison.example.com.")]
+        );
+    }
+
+    #[test]
+    fn test_out_of_bailiwick_glue() {
+        let com = Zone {
+            name: name("com."),
+            servers: vec![SocketAddr::new(IpAddr::V4(COM), 53)],
+            probed_labels: 1,
+            ttl: 86400,
+        };
+
+        // glue for the name server of example.com. in another zone
+        let mut message = referral("example.com.", "ns.evil.net.", EXAMPLE_COM);
+        message.add_additional(Record::from_rdata(
+            name("ns.example.com."),
+            86400,
+            RData::A(Ipv4Addr::new(203, 0, 113, 66)),
+        ));
+        assert!(com
+            .referral(&name("www.example.com."), &message.into())
+            .is_err());
+
+        let message = referral("example.com.", "ns.example.com.", EXAMPLE_COM);
+        let zone = com
+            .referral(&name("www.example.com."), &message.into())
+            .expect("referral failed")
+            .expect("no referral");
+        assert_eq!(
+            zone.servers,
+            vec![SocketAddr::new(IpAddr::V4(EXAMPLE_COM), 53)]
+        );
+    }
+}
@@ -132,6 +132,19 @@ impl Resolver {
         self.runtime.lock()?.block_on(lookup)
     }
 
+    /// Performs A and AAAA lookups of the hostname, for connecting to it.
+    ///
+    /// The addresses alternate between IPv6 and IPv4, starting with IPv6, see
+    /// [`AsyncResolver::resolve_addrs`].
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - string hostname, if this is an invalid hostname, an error will be returned.
+    pub fn resolve_addrs(&self, host: &str) -> ResolveResult<Vec<IpAddr>> {
+        let lookup = self.async_resolver.resolve_addrs(host);
+        self.runtime.lock()?.block_on(lookup)
+    }
+
     /// Performs a DNS lookup for an SRV record for the specified service type and protocol at the given name.
     ///
     /// This is a convenience method over `lookup_srv`, it combines the service, protocol and name into a single name: `_service._protocol.name`.
@@ -159,6 +172,15 @@ impl Resolver {
         self.runtime.lock()?.block_on(lookup)
     }
 
+    /// Performs forward-confirmed reverse DNS, FCrDNS, of the address.
+    ///
+    /// The result is `true` if any of the names of the PTR records of the address resolves to the
+    /// address again, see [`AsyncResolver::verify_fcrdns`].
+    pub fn verify_fcrdns(&self, ip: IpAddr) -> ResolveResult<bool> {
+        let verify = self.async_resolver.verify_fcrdns(ip);
+        self.runtime.lock()?.block_on(verify)
+    }
+
     lookup_fn!(reverse_lookup, lookup::ReverseLookup, IpAddr);
     lookup_fn!(ipv4_lookup, lookup::Ipv4Lookup);
     lookup_fn!(ipv6_lookup, lookup::Ipv6Lookup);
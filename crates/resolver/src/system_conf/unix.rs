@@ -64,11 +64,13 @@ fn into_resolver_config(
             socket_addr: SocketAddr::new(ip.into(), DEFAULT_PORT),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         });
         nameservers.push(NameServerConfig {
             socket_addr: SocketAddr::new(ip.into(), DEFAULT_PORT),
             protocol: Protocol::Tcp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         });
     }
     if nameservers.is_empty() {
@@ -115,11 +117,13 @@ mod tests {
                 socket_addr: addr,
                 protocol: Protocol::Udp,
                 tls_dns_name: None,
+                tls_accept_invalid_hostnames: false,
             },
             NameServerConfig {
                 socket_addr: addr,
                 protocol: Protocol::Tcp,
                 tls_dns_name: None,
+                tls_accept_invalid_hostnames: false,
             },
         ]
     }
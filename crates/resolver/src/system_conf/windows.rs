@@ -32,11 +32,13 @@ fn get_name_servers() -> ResolveResult<Vec<NameServerConfig>> {
             socket_addr,
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         });
         name_servers.push(NameServerConfig {
             socket_addr,
             protocol: Protocol::Tcp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         });
     }
     Ok(name_servers)
@@ -19,10 +19,12 @@ use proto::BufDnsStreamHandle;
 pub(crate) fn new_tls_stream(
     socket_addr: SocketAddr,
     dns_name: String,
+    accept_invalid_hostnames: bool,
 ) -> (
     Box<dyn Future<Item = TlsClientStream, Error = ProtoError> + Send>,
     BufDnsStreamHandle,
 ) {
-    let tls_builder = TlsClientStreamBuilder::new();
+    let mut tls_builder = TlsClientStreamBuilder::new();
+    tls_builder.danger_accept_invalid_hostnames(accept_invalid_hostnames);
     tls_builder.build(socket_addr, dns_name)
 }
@@ -19,10 +19,18 @@ use proto::BufDnsStreamHandle;
 pub(crate) fn new_tls_stream(
     socket_addr: SocketAddr,
     dns_name: String,
+    accept_invalid_hostnames: bool,
 ) -> (
     Box<dyn Future<Item = TlsClientStream, Error = ProtoError> + Send>,
     BufDnsStreamHandle,
 ) {
+    if accept_invalid_hostnames {
+        warn!(
+            "invalid hostnames are not accepted with dns-over-openssl, validating {}",
+            dns_name
+        );
+    }
+
     let tls_builder = TlsClientStreamBuilder::new();
     tls_builder.build(socket_addr, dns_name)
 }
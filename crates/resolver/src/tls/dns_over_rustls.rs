@@ -39,10 +39,18 @@ lazy_static! {
 pub(crate) fn new_tls_stream(
     socket_addr: SocketAddr,
     dns_name: String,
+    accept_invalid_hostnames: bool,
 ) -> (
     Box<dyn Future<Item = TlsClientStream, Error = ProtoError> + Send>,
     BufDnsStreamHandle,
 ) {
+    if accept_invalid_hostnames {
+        warn!(
+            "invalid hostnames are not accepted with dns-over-rustls, validating {}",
+            dns_name
+        );
+    }
+
     let (stream, handle) = tls_client_connect(socket_addr, dns_name, CLIENT_CONFIG.clone());
     (Box::new(stream), handle)
 }
@@ -7,6 +7,8 @@
 
 //! All authority related types
 
+use std::net::IpAddr;
+
 use futures::Future;
 
 use trust_dns::op::LowerQuery;
@@ -29,6 +31,13 @@ pub trait Authority: Send {
     /// Return true if AXFR is allowed
     fn is_axfr_allowed(&self) -> bool;
 
+    /// Return true if zone transfers, AXFR and IXFR, to the peer are allowed
+    ///
+    /// By default transfers to any peer are allowed if AXFR is allowed.
+    fn is_transfer_allowed(&self, _peer: IpAddr) -> bool {
+        self.is_axfr_allowed()
+    }
+
     /// Perform a dynamic update of a zone
     fn update(&mut self, update: &MessageRequest) -> UpdateResult<bool>;
 
@@ -7,6 +7,8 @@
 
 //! All authority related types
 
+use std::net::IpAddr;
+
 use futures::{future, Future, Poll};
 
 use trust_dns::op::LowerQuery;
@@ -24,6 +26,9 @@ pub trait AuthorityObject: Send + Sync {
     /// Return true if AXFR is allowed
     fn is_axfr_allowed(&self) -> bool;
 
+    /// Return true if zone transfers, AXFR and IXFR, to the peer are allowed
+    fn is_transfer_allowed(&self, peer: IpAddr) -> bool;
+
     /// Perform a dynamic update of a zone
     fn update(&mut self, update: &MessageRequest) -> UpdateResult<bool>;
 
@@ -161,6 +166,11 @@ where
         Authority::is_axfr_allowed(self)
     }
 
+    /// Return true if zone transfers, AXFR and IXFR, to the peer are allowed
+    fn is_transfer_allowed(&self, peer: IpAddr) -> bool {
+        Authority::is_transfer_allowed(self, peer)
+    }
+
     /// Perform a dynamic update of a zone
     fn update(&mut self, update: &MessageRequest) -> UpdateResult<bool> {
         Authority::update(self, update)
@@ -19,23 +19,41 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::io;
+use std::iter;
+use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::{Arc, RwLock};
+#[cfg(feature = "dnssec")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use futures::{Async, Future, Poll};
 
-use server::{Request, RequestHandler, ResponseHandler};
+#[cfg(feature = "dnssec")]
+use proto::error::ProtoErrorKind;
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::tsig;
+#[cfg(feature = "dnssec")]
+use server::TsigResponseHandle;
+use server::{Protocol, Request, RequestHandler, ResponseHandler};
 use trust_dns::op::{Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode};
-use trust_dns::rr::dnssec::{Algorithm, SupportedAlgorithms};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TsigSigner;
+use trust_dns::rr::dnssec::{Algorithm, AlgorithmPolicy, SupportedAlgorithms};
 use trust_dns::rr::rdata::opt::{EdnsCode, EdnsOption};
-use trust_dns::rr::{LowerName, RecordType};
+use trust_dns::rr::rdata::TXT;
+use trust_dns::rr::{DNSClass, LowerName, RData, Record, RecordType};
 
 use authority::{AuthLookup, MessageRequest, MessageResponse, MessageResponseBuilder, ZoneType};
-use authority::{AuthorityObject, BoxedLookupFuture, LookupError, LookupObject};
+use authority::{AuthorityObject, BoxedLookupFuture, LookupError, LookupObject, LookupRecords};
 
 /// Set of authorities, zones, available to this server.
-#[derive(Default)]
 pub struct Catalog {
     authorities: HashMap<LowerName, Arc<RwLock<Box<dyn AuthorityObject>>>>,
+    version: Option<String>,
+    identity: Option<String>,
+    algorithm_policy: AlgorithmPolicy,
+    #[cfg(feature = "dnssec")]
+    tsig_keys: HashMap<LowerName, Arc<TsigSigner>>,
 }
 
 fn send_response<R: ResponseHandler>(
@@ -43,27 +61,19 @@ fn send_response<R: ResponseHandler>(
     mut response: MessageResponse,
     response_handle: R,
 ) -> io::Result<()> {
-    if let Some(mut resp_edns) = response_edns {
-        // set edns DAU and DHU
-        // send along the algorithms which are supported by this authority
-        let mut algorithms = SupportedAlgorithms::new();
-        algorithms.set(Algorithm::RSASHA256);
-        algorithms.set(Algorithm::ECDSAP256SHA256);
-        algorithms.set(Algorithm::ECDSAP384SHA384);
-        algorithms.set(Algorithm::ED25519);
-
-        let dau = EdnsOption::DAU(algorithms);
-        let dhu = EdnsOption::DHU(algorithms);
-
-        resp_edns.set_option(dau);
-        resp_edns.set_option(dhu);
-
+    if let Some(resp_edns) = response_edns {
         response.set_edns(resp_edns);
     }
 
     response_handle.send_response(response)
 }
 
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RequestHandler for Catalog {
     type ResponseFuture = HandleRequest;
 
@@ -78,6 +88,24 @@ impl RequestHandler for Catalog {
         request: Request,
         response_handle: R,
     ) -> Self::ResponseFuture {
+        // signed requests are only handled once verified, their responses are signed
+        #[cfg(feature = "dnssec")]
+        {
+            if request.message.tsig().is_some() {
+                return self.handle_tsig_request(request, response_handle);
+            }
+        }
+
+        self.handle_verified_request(request, response_handle)
+    }
+}
+
+impl Catalog {
+    fn handle_verified_request<R: ResponseHandler>(
+        &self,
+        request: Request,
+        response_handle: R,
+    ) -> HandleRequest {
         let request_message = request.message;
         trace!("request: {:?}", request_message);
 
@@ -98,6 +126,12 @@ impl RequestHandler for Catalog {
             resp_edns.set_max_payload(req_edns.max_payload().max(512));
             resp_edns.set_version(our_version);
 
+            // set edns DAU and DHU
+            // send along the algorithms which are allowed by the policy of this authority
+            let algorithms = self.algorithm_policy.allowed();
+            resp_edns.set_option(EdnsOption::DAU(algorithms));
+            resp_edns.set_option(EdnsOption::DHU(algorithms));
+
             if req_edns.version() > our_version {
                 warn!(
                     "request edns version greater than {}: {}",
@@ -124,6 +158,28 @@ impl RequestHandler for Catalog {
             MessageType::Query => match request_message.op_code() {
                 OpCode::Query => {
                     debug!("query received: {}", request_message.id());
+                    if let Some(response_header) =
+                        self.deny_transfer(&request_message, request.src, request.protocol)
+                    {
+                        let response =
+                            MessageResponseBuilder::new(Some(request_message.raw_queries()));
+                        let result = send_response(
+                            response_edns,
+                            response.build_no_records(response_header),
+                            response_handle,
+                        );
+                        return HandleRequest::result(result);
+                    }
+
+                    let is_chaos = request_message
+                        .queries()
+                        .first()
+                        .map_or(false, |query| query.query_class() == DNSClass::CH);
+                    if is_chaos {
+                        let result = self.chaos(&request_message, response_edns, response_handle);
+                        return HandleRequest::result(result);
+                    }
+
                     let lookup = self.lookup(request_message, response_edns, response_handle);
                     HandleRequest::lookup(lookup)
                 }
@@ -201,9 +257,101 @@ impl Catalog {
     pub fn new() -> Self {
         Catalog {
             authorities: HashMap::new(),
+            version: None,
+            identity: None,
+            algorithm_policy: AlgorithmPolicy::with_minimum(Algorithm::RSASHA256),
+            #[cfg(feature = "dnssec")]
+            tsig_keys: HashMap::new(),
         }
     }
 
+    /// The algorithms which are sent in the DAU and DHU EDNS options of the responses, by default
+    ///  RSASHA256 and all stronger algorithms, see `AlgorithmPolicy`
+    pub fn set_algorithm_policy(&mut self, algorithm_policy: AlgorithmPolicy) {
+        self.algorithm_policy = algorithm_policy;
+    }
+
+    /// Adds a TSIG key, requests signed with it are verified and their responses signed
+    ///
+    /// Requests signed with any other key are answered with NOTAUTH and a BADKEY TSIG error.
+    #[cfg(feature = "dnssec")]
+    pub fn add_tsig_key(&mut self, signer: TsigSigner) {
+        self.tsig_keys
+            .insert(LowerName::from(signer.key_name()), Arc::new(signer));
+    }
+
+    /// Verifies the TSIG of the request, and handles it with a handle signing the responses
+    ///
+    /// Per RFC 8945 section 5.2, a request failing verification is answered with NOTAUTH and the
+    ///  TSIG error, or FORMERR for a malformed TSIG record.
+    #[cfg(feature = "dnssec")]
+    fn handle_tsig_request<R: ResponseHandler>(
+        &self,
+        request: Request,
+        response_handle: R,
+    ) -> HandleRequest {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+
+        let tsig = request
+            .message
+            .tsig()
+            .cloned()
+            .expect("handle_tsig_request requires a TSIG");
+        let signer = self.tsig_keys.get(&LowerName::from(tsig.name()));
+        let verified = match (signer, request.message.tsig_signed_bytes()) {
+            (Some(signer), Some(signed)) => signer.to_verifier().verify_bytes(signed, None, now),
+            _ => Err(ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADKEY).into()),
+        };
+
+        match verified {
+            Ok(request_mac) => {
+                let signer = signer.expect("verified without a key").clone();
+                let response_handle = TsigResponseHandle::new(response_handle, signer, request_mac);
+                self.handle_verified_request(request, response_handle)
+            }
+            Err(e) => {
+                warn!(
+                    "request {} failed TSIG verification: {}",
+                    request.message.id(),
+                    e
+                );
+                let error = match *e.kind() {
+                    ProtoErrorKind::TsigVerificationFailed(error) => error,
+                    _ => ResponseCode::FormErr,
+                };
+
+                let mut response = MessageResponseBuilder::new(Some(request.message.raw_queries()));
+                let response_code = if error == ResponseCode::FormErr {
+                    ResponseCode::FormErr
+                } else {
+                    response.sig0(tsig::error_record(&tsig, error, now).into_iter().collect());
+                    ResponseCode::NotAuth
+                };
+
+                let result = response_handle.send_response(response.error_msg(
+                    request.message.id(),
+                    request.message.op_code(),
+                    response_code,
+                ));
+                HandleRequest::result(result)
+            }
+        }
+    }
+
+    /// The version answered to `version.bind.` and `version.server.` CH TXT queries, these are
+    ///  refused if None
+    pub fn set_version(&mut self, version: Option<String>) {
+        self.version = version;
+    }
+
+    /// The identity of this server answered to `id.server.` and `hostname.bind.` CH TXT queries,
+    ///  these are refused if None
+    pub fn set_identity(&mut self, identity: Option<String>) {
+        self.identity = identity;
+    }
+
     /// Insert or update a zone authority
     ///
     /// # Arguments
@@ -431,17 +579,154 @@ impl Catalog {
         )
     }
 
+    /// Checks the zone transfers, AXFR and IXFR, of the request
+    ///
+    /// Transfers are only allowed over TCP, to the peers the authority of the zone allows, see
+    ///  `Authority::is_transfer_allowed`. AXFR over UDP isn't defined,
+    ///  [RFC 5936](https://tools.ietf.org/html/rfc5936#section-4.2), and is refused, the response to
+    ///  IXFR over UDP is truncated, for the client to retry over TCP.
+    ///
+    /// # Return value
+    ///
+    /// The header of the response if a transfer is denied, None otherwise
+    fn deny_transfer(
+        &self,
+        request: &MessageRequest,
+        src: SocketAddr,
+        protocol: Protocol,
+    ) -> Option<Header> {
+        let mut response_header = Header::new();
+        response_header.set_id(request.id());
+        response_header.set_op_code(request.op_code());
+        response_header.set_message_type(MessageType::Response);
+
+        for query in request.queries() {
+            let query_type = query.query_type();
+            if query_type != RecordType::AXFR && query_type != RecordType::IXFR {
+                continue;
+            }
+
+            if protocol == Protocol::Udp {
+                warn!("{} over UDP from {}: {}", query_type, src, query.name());
+                if query_type == RecordType::IXFR {
+                    response_header.set_truncated(true);
+                } else {
+                    response_header.set_response_code(ResponseCode::Refused);
+                }
+                return Some(response_header);
+            }
+
+            // without an authority the query is answered as usual
+            let is_allowed = self.find(query.name()).map_or(true, |authority| {
+                authority
+                    .read()
+                    .unwrap() // poison errors should panic
+                    .is_transfer_allowed(src.ip())
+            });
+
+            if !is_allowed {
+                warn!("{} refused to {}: {}", query_type, src, query.name());
+                response_header.set_response_code(ResponseCode::Refused);
+                return Some(response_header);
+            }
+        }
+
+        None
+    }
+
+    /// Answers the CHAOS class query of the request, for the version or identity of this server
+    ///
+    /// The version is answered to `version.bind.` and `version.server.` TXT queries, the identity to
+    ///  `id.server.`, [RFC 4892](https://tools.ietf.org/html/rfc4892), and `hostname.bind.`. All
+    ///  other CHAOS queries are refused.
+    fn chaos<R: ResponseHandler>(
+        &self,
+        request: &MessageRequest,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let response = MessageResponseBuilder::new(Some(request.raw_queries()));
+        let mut response_header = Header::new();
+        response_header.set_id(request.id());
+        response_header.set_op_code(request.op_code());
+        response_header.set_message_type(MessageType::Response);
+
+        let query = request.queries().first();
+        let text = query.and_then(|query| match query.query_type() {
+            RecordType::TXT | RecordType::ANY => self.chaos_text(query.name()),
+            _ => None,
+        });
+
+        let (query, text) = match (query, text) {
+            (Some(query), Some(text)) => (query, text),
+            _ => {
+                debug!("refused CH query: {:?}", query.map(LowerQuery::original));
+                response_header.set_response_code(ResponseCode::Refused);
+                return send_response(
+                    response_edns,
+                    response.build_no_records(response_header),
+                    response_handle,
+                );
+            }
+        };
+
+        let mut answer = Record::from_rdata(
+            query.original().name().clone(),
+            0,
+            RData::TXT(TXT::new(vec![text.to_string()])),
+        );
+        answer.set_dns_class(DNSClass::CH);
+        response_header.set_authoritative(true);
+
+        let answers = Box::new(iter::once(&answer)) as Box<dyn Iterator<Item = &Record> + Send>;
+        let none = || Box::new(iter::empty()) as Box<dyn Iterator<Item = &Record> + Send>;
+        send_response(
+            response_edns,
+            response.build(response_header, answers, none(), none(), none()),
+            response_handle,
+        )
+    }
+
+    /// The version or identity for the CHAOS name, if it is set
+    fn chaos_text(&self, name: &LowerName) -> Option<&str> {
+        let is = |chaos_name: &str| LowerName::from_str(chaos_name).map_or(false, |n| n == *name);
+
+        let text = if is("version.bind.") || is("version.server.") {
+            &self.version
+        } else if is("id.server.") || is("hostname.bind.") {
+            &self.identity
+        } else {
+            return None;
+        };
+
+        text.as_ref().map(String::as_str)
+    }
+
     /// Recursively searches the catalog for a matching authority
     pub fn find(&self, name: &LowerName) -> Option<&Arc<RwLock<Box<dyn AuthorityObject>>>> {
         debug!("searching authorities for: {}", name);
-        self.authorities.get(name).or_else(|| {
-            if !name.is_root() {
-                let name = name.base_name();
-                self.find(&name)
-            } else {
-                None
+        self.closest_zone(name)
+            .and_then(|zone| self.authorities.get(&zone))
+    }
+
+    /// Returns the closest enclosing zone of the name, i.e. the longest origin of the authorities
+    ///  which is a suffix of the name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name to find the zone of, e.g. the name of a query
+    pub fn closest_zone(&self, name: &LowerName) -> Option<LowerName> {
+        let mut zone = name.clone();
+        loop {
+            if self.authorities.contains_key(&zone) {
+                return Some(zone);
+            }
+
+            if zone.is_root() {
+                return None;
             }
-        })
+            zone = zone.base_name();
+        }
     }
 }
 
@@ -656,10 +941,18 @@ impl<R: ResponseHandler> Future for AuthorityLookup<R> {
             &self.authority
         ));
 
-        let records = sections.answers;
-        let soa = sections.soa;
-        let ns = sections.ns;
-        let additionals = sections.additionals;
+        let mut records = sections.answers;
+        let mut soa = sections.soa;
+        let mut ns = sections.ns;
+        let mut additionals = sections.additionals;
+
+        if !self.request_params.is_dnssec {
+            let query_type = self.request_params.query.query_type();
+            records = SectionRecords::without_dnssec(records, query_type);
+            soa = SectionRecords::without_dnssec(soa, query_type);
+            ns = SectionRecords::without_dnssec(ns, query_type);
+            additionals = SectionRecords::without_dnssec(additionals, query_type);
+        }
 
         let response_params = self
             .response_params
@@ -697,6 +990,70 @@ struct LookupSections {
     additionals: Box<dyn LookupObject>,
 }
 
+/// A section of a response built from copies of the looked up records
+struct SectionRecords(Vec<Record>);
+
+impl SectionRecords {
+    /// The SOA for the authority section of a negative response
+    ///
+    /// The TTL of the SOA, and of its RRSIGs, is the minimum of the SOA TTL and the SOA MINIMUM field,
+    ///  this is how long the negative response may be cached, RFC 2308 section 3.
+    fn negative_soa(soa: &dyn LookupObject) -> Self {
+        let minimum = soa
+            .iter()
+            .filter_map(|record| record.rdata().as_soa())
+            .map(|soa| soa.minimum())
+            .next();
+
+        let records = soa
+            .iter()
+            .cloned()
+            .map(|mut record| {
+                if let Some(minimum) = minimum {
+                    let ttl = record.ttl().min(minimum);
+                    record.set_ttl(ttl);
+                }
+                record
+            })
+            .collect();
+
+        SectionRecords(records)
+    }
+
+    /// A section of a response to a client that did not set the DO bit
+    ///
+    /// DNSSEC records are only included if the query was for their type, RFC 4035 section 3.2.1.
+    fn without_dnssec(
+        section: Box<dyn LookupObject>,
+        query_type: RecordType,
+    ) -> Box<dyn LookupObject> {
+        let keep =
+            |record: &&Record| !record.rr_type().is_dnssec() || record.rr_type() == query_type;
+
+        // most sections have nothing to strip
+        if section.iter().all(|record| keep(&record)) {
+            return section;
+        }
+
+        let records = section.iter().filter(keep).cloned().collect();
+        Box::new(SectionRecords(records))
+    }
+}
+
+impl LookupObject for SectionRecords {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Record> + Send + 'a> {
+        Box::new(self.0.iter())
+    }
+
+    fn take_additionals(&mut self) -> Option<Box<dyn LookupObject>> {
+        None
+    }
+}
+
 #[must_use = "futures do nothing unless polled"]
 enum AuthOrResolve {
     AuthorityLookupState(AuthorityLookupState),
@@ -799,6 +1156,26 @@ impl AuthorityLookupState {
                                 ns: Some(ns),
                             }
                         }
+                        // the name is delegated to another zone, refer to its name servers, RFC 1034 section 4.3.2
+                        Err(LookupError::Referral(mut referral)) => {
+                            response_params
+                                .response_header
+                                .set_response_code(ResponseCode::NoError);
+                            response_params.response_header.set_authoritative(false);
+
+                            // the glue is returned in the additional section
+                            let glue = referral.take_additionals();
+                            let records =
+                                Box::new(AuthLookup::answers(LookupRecords::default(), glue))
+                                    as Box<dyn LookupObject>;
+                            let soa = Box::new(AuthLookup::default()) as Box<dyn LookupObject>;
+
+                            AuthorityLookupState::Complete {
+                                records: Some(records),
+                                soa: Some(soa),
+                                ns: Some(referral as Box<dyn LookupObject>),
+                            }
+                        }
                         // in the not found case it's standard to return the SOA in the authority section
                         //   if the name is in this zone, etc.
                         // see https://tools.ietf.org/html/rfc2308 for proper response construct
@@ -886,7 +1263,9 @@ impl AuthorityLookupState {
                 AuthorityLookupState::NxLookupSoa { soa_lookup, nsecs } => {
                     let soa = match soa_lookup.poll() {
                         Ok(Async::NotReady) => return Ok(Async::NotReady),
-                        Ok(Async::Ready(soa)) => soa,
+                        Ok(Async::Ready(soa)) => {
+                            Box::new(SectionRecords::negative_soa(&*soa)) as Box<dyn LookupObject>
+                        }
                         Err(e) => {
                             warn!("failed to lookup soa: {}", e);
                             Box::new(AuthLookup::default()) as Box<dyn LookupObject>
@@ -948,9 +1327,25 @@ impl ResolveLookupState {
                 //
                 // On Errors, the transition depends on the type of error.
                 ResolveLookupState::Records { record_lookup } => {
-                    let records = try_ready!(record_lookup
-                        .poll()
-                        .map_err(|e| error!("error resolving: {}", e)));
+                    let records = match record_lookup.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(records)) => records,
+                        // the client is told why the resolution failed, if the records failed
+                        //  validation, RFC 8914
+                        Err(e) => {
+                            error!("error resolving: {}", e);
+                            response_params
+                                .response_header
+                                .set_response_code(ResponseCode::ServFail);
+                            if let (Some(edns), Some(extended_error)) =
+                                (response_params.response_edns.as_mut(), e.extended_error())
+                            {
+                                Arc::make_mut(edns).set_option(extended_error);
+                            }
+
+                            Box::new(AuthLookup::default()) as Box<dyn LookupObject>
+                        }
+                    };
                     // need to clone the result codes...
 
                     response_params.response_header.set_authoritative(false);
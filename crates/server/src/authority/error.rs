@@ -12,10 +12,15 @@ use std::io;
 #[cfg(feature = "trust-dns-resolver")]
 use failure::{Compat, Fail};
 
+#[cfg(feature = "trust-dns-resolver")]
+use proto::error::ProtoError;
 use trust_dns::op::ResponseCode;
+use trust_dns::rr::rdata::opt::EdnsOption;
 #[cfg(feature = "trust-dns-resolver")]
 use trust_dns_resolver::error::ResolveError;
 
+use authority::AuthLookup;
+
 // TODO: should this implement Failure?
 /// A query could not be fulfilled
 #[derive(Debug, EnumAsInner)]
@@ -24,6 +29,8 @@ pub enum LookupError {
     NameExists,
     /// There was an error performing the lookup
     ResponseCode(ResponseCode),
+    /// The name is in a delegated zone, the lookup contains the NS records of the delegation and any glue
+    Referral(Box<AuthLookup>),
     /// Resolve Error
     #[cfg(feature = "trust-dns-resolver")]
     ResolveError(Compat<ResolveError>),
@@ -53,6 +60,14 @@ impl LookupError {
         }
     }
 
+    /// The name is in a delegated zone
+    pub fn is_referral(&self) -> bool {
+        match *self {
+            LookupError::Referral(_) => true,
+            _ => false,
+        }
+    }
+
     /// This is a non-existent domain name
     pub fn is_refused(&self) -> bool {
         match *self {
@@ -60,6 +75,20 @@ impl LookupError {
             _ => false,
         }
     }
+
+    /// The Extended DNS Error, RFC 8914, sent along with the SERVFAIL for this error, i.e. the
+    ///  reason the resolved records failed DNSSec validation
+    pub fn extended_error(&self) -> Option<EdnsOption> {
+        match self {
+            #[cfg(feature = "trust-dns-resolver")]
+            LookupError::ResolveError(e) => e
+                .get_ref()
+                .cause()?
+                .downcast_ref::<ProtoError>()?
+                .extended_error(),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for LookupError {
@@ -67,6 +96,7 @@ impl fmt::Display for LookupError {
         match self {
             LookupError::NameExists => write!(f, "NameExists"),
             LookupError::ResponseCode(rc) => write!(f, "response_code: {}", rc),
+            LookupError::Referral(_) => write!(f, "Referral"),
             #[cfg(feature = "trust-dns-resolver")]
             LookupError::ResolveError(e) => write!(f, "resolve_error: {}", e),
             LookupError::Io(e) => write!(f, "io: {}", e),
@@ -79,6 +109,7 @@ impl error::Error for LookupError {
         match self {
             LookupError::NameExists => "record type not found at name, but others exist",
             LookupError::ResponseCode(_rc) => "an response code other than NoError returned",
+            LookupError::Referral(_) => "the name is in a delegated zone",
             #[cfg(feature = "trust-dns-resolver")]
             LookupError::ResolveError(_e) => "the resolver encountered an error",
             LookupError::Io(_e) => "there was an underlying IO error during search",
@@ -89,6 +120,7 @@ impl error::Error for LookupError {
         match self {
             LookupError::NameExists => None,
             LookupError::ResponseCode(_rc) => None,
+            LookupError::Referral(_) => None,
             #[cfg(feature = "trust-dns-resolver")]
             LookupError::ResolveError(e) => e.source(),
             LookupError::Io(e) => e.source(),
@@ -8,7 +8,11 @@
 use proto::error::*;
 use proto::op::message::EmitAndCount;
 use proto::op::{message, Edns, Header, Message, MessageType, OpCode, ResponseCode};
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::rdata::DNSSECRecordType;
 use proto::rr::Record;
+#[cfg(feature = "dnssec")]
+use proto::rr::RecordType;
 use proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
 use trust_dns::op::LowerQuery;
 
@@ -22,6 +26,7 @@ pub struct MessageRequest {
     additionals: Vec<Record>,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    tsig_signed: Option<Box<[u8]>>,
 }
 
 impl MessageRequest {
@@ -150,6 +155,21 @@ impl MessageRequest {
         &self.sig0
     }
 
+    /// The TSIG record, if this request was authenticated with a shared secret
+    #[cfg(feature = "dnssec")]
+    pub fn tsig(&self) -> Option<&Record> {
+        self.sig0
+            .last()
+            .filter(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::TSIG))
+    }
+
+    /// The request as it was received, if it has a TSIG record
+    ///
+    /// The MAC of the TSIG covers the bytes from the client, which are verified as they are.
+    pub fn tsig_signed_bytes(&self) -> Option<&[u8]> {
+        self.tsig_signed.as_ref().map(AsRef::as_ref)
+    }
+
     /// # Return value
     ///
     /// the max payload value as it's defined in the EDNS section.
@@ -179,6 +199,8 @@ impl<'q> BinDecodable<'q> for MessageRequest {
     // TODO: generify this with Message?
     /// Reads a MessageRequest from the decoder
     fn read(decoder: &mut BinDecoder<'q>) -> ProtoResult<Self> {
+        #[cfg_attr(not(feature = "dnssec"), allow(unused))]
+        let start = decoder.index();
         let header = Header::read(decoder)?;
 
         // TODO/FIXME: return just header, and in the case of the rest of message getting an error.
@@ -197,6 +219,18 @@ impl<'q> BinDecodable<'q> for MessageRequest {
         let (name_servers, _, _) = Message::read_records(decoder, name_server_count, false)?;
         let (additionals, edns, sig0) = Message::read_records(decoder, additional_count, true)?;
 
+        // only TSIG needs the original bytes, SIG0 is verified against the canonical form
+        #[cfg(feature = "dnssec")]
+        let tsig_signed = if sig0.last().map(Record::rr_type)
+            == Some(RecordType::DNSSEC(DNSSECRecordType::TSIG))
+        {
+            Some(decoder.slice_from(start)?.to_vec().into_boxed_slice())
+        } else {
+            None
+        };
+        #[cfg(not(feature = "dnssec"))]
+        let tsig_signed = None;
+
         Ok(MessageRequest {
             header,
             queries,
@@ -205,6 +239,7 @@ impl<'q> BinDecodable<'q> for MessageRequest {
             additionals,
             sig0,
             edns,
+            tsig_signed,
         })
     }
 }
@@ -5,9 +5,16 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+#[cfg(feature = "dnssec")]
+use std::fmt;
+#[cfg(feature = "dnssec")]
+use std::sync::Arc;
+
 use proto::error::*;
 use proto::op::message::EmitAndCount;
 use proto::op::{message, Edns, Header, MessageType, OpCode, ResponseCode};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TsigSigner;
 use trust_dns::rr::Record;
 use trust_dns::serialize::binary::BinEncoder;
 
@@ -37,6 +44,28 @@ pub struct MessageResponse<
     additionals: D,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    #[cfg(feature = "dnssec")]
+    tsig: Option<TsigResponse>,
+}
+
+/// The key and request MAC for signing a response with TSIG
+#[cfg(feature = "dnssec")]
+#[derive(Clone)]
+struct TsigResponse {
+    signer: Arc<TsigSigner>,
+    request_mac: Arc<[u8]>,
+    time_signed: u64,
+}
+
+#[cfg(feature = "dnssec")]
+impl fmt::Debug for TsigResponse {
+    // the key is not to be logged
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TsigResponse")
+            .field("key_name", self.signer.key_name())
+            .field("time_signed", &self.time_signed)
+            .finish()
+    }
 }
 
 enum EmptyOrQueries<'q> {
@@ -79,11 +108,65 @@ where
         self
     }
 
+    /// Signs the response with TSIG when it is emitted
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - the key which verified the request
+    /// * `request_mac` - the MAC of the TSIG of the request
+    /// * `time_signed` - the current time, in seconds since the unix epoch
+    #[cfg(feature = "dnssec")]
+    pub fn set_tsig_signer(
+        &mut self,
+        signer: Arc<TsigSigner>,
+        request_mac: Arc<[u8]>,
+        time_signed: u64,
+    ) -> &mut Self {
+        self.tsig = Some(TsigResponse {
+            signer,
+            request_mac,
+            time_signed,
+        });
+        self
+    }
+
+    /// Drops all records and sets the TC bit, the client retries over TCP for the records
+    pub fn truncate(self) -> MessageResponse<'q, 'a> {
+        let mut header = self.header;
+        header.set_truncated(true);
+
+        MessageResponse {
+            header,
+            queries: self.queries,
+            answers: Box::new(None.into_iter()),
+            name_servers: Box::new(None.into_iter()),
+            soa: Box::new(None.into_iter()),
+            additionals: Box::new(None.into_iter()),
+            // a SIG(0) would no longer cover the message
+            sig0: Vec::new(),
+            edns: self.edns,
+            // TSIG signs the message as it is emitted
+            #[cfg(feature = "dnssec")]
+            tsig: self.tsig,
+        }
+    }
+
     /// Consumes self, and emits to the encoder.
     pub fn destructive_emit(mut self, encoder: &mut BinEncoder) -> ProtoResult<()> {
         // soa records are part of the nameserver section
         let mut name_servers = self.name_servers.chain(self.soa);
 
+        #[cfg(feature = "dnssec")]
+        let (start, max_size) = (encoder.offset(), encoder.max_size());
+        #[cfg(feature = "dnssec")]
+        {
+            if let Some(ref tsig) = self.tsig {
+                // the TSIG record must fit, a truncated response is still signed
+                let record_len = tsig.signer.record_len()?;
+                encoder.set_max_size(max_size.saturating_sub(record_len as u16));
+            }
+        }
+
         message::emit_message_parts(
             &self.header,
             &mut EmptyOrQueries::from(self.queries),
@@ -93,7 +176,22 @@ where
             self.edns.as_ref(),
             &self.sig0,
             encoder,
-        )
+        )?;
+
+        #[cfg(feature = "dnssec")]
+        {
+            if let Some(ref tsig) = self.tsig {
+                encoder.set_max_size(max_size);
+                tsig.signer.sign_emitted(
+                    encoder,
+                    start,
+                    Some(&tsig.request_mac),
+                    tsig.time_signed,
+                )?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -124,6 +222,12 @@ impl<'q> MessageResponseBuilder<'q> {
         self
     }
 
+    /// Associate SIG0 or TSIG records with the Response, e.g. the unsigned TSIG of an error
+    pub fn sig0(&mut self, sig0: Vec<Record>) -> &mut Self {
+        self.sig0 = Some(sig0);
+        self
+    }
+
     /// Constructs the new MessageResponse with associated Header
     ///
     /// # Arguments
@@ -156,6 +260,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: additionals.into_iter(),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 
@@ -170,6 +276,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 
@@ -201,6 +309,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 }
@@ -239,6 +349,8 @@ mod tests {
                 additionals: iter::once(&answer),
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -275,6 +387,8 @@ mod tests {
                 additionals: iter::repeat(&answer),
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -20,6 +20,7 @@ mod catalog;
 mod error;
 pub(crate) mod message_request;
 mod message_response;
+mod zone_diff;
 mod zone_type;
 
 pub use self::auth_lookup::{
@@ -31,4 +32,5 @@ pub use self::catalog::Catalog;
 pub use self::error::{LookupError, LookupResult};
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
+pub use self::zone_diff::zone_diff;
 pub use self::zone_type::ZoneType;
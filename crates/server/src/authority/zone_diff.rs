@@ -0,0 +1,149 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::collections::BTreeSet;
+
+use trust_dns::rr::{DNSClass, Name, RData, Record, RecordType};
+
+/// Computes the difference between two versions of a zone, as the deletions and the additions of
+///  an IXFR response, [RFC 1995](https://tools.ietf.org/html/rfc1995#section-4)
+///
+/// ```text
+///    If incremental zone transfer is available, one or more difference
+///    sequences is returned.  The list of difference sequences is preceded
+///    and followed by a copy of the server's current version of the SOA.
+///
+///    Each difference sequence represents one update to the zone (one SOA
+///    serial change) consisting of deleted RRs and added RRs.  The first RR
+///    of the deleted RRs is the older SOA RR and the first RR of the added
+///    RRs is the newer SOA RR.
+/// ```
+///
+/// The deletions start with the SOA of `old`, the additions with the SOA of `new`, the other SOA
+///  records are not compared. A record with a changed TTL is deleted and added again.
+///
+/// # Arguments
+///
+/// * `old` - the records of the zone at the older serial
+/// * `new` - the records of the zone at the newer serial
+///
+/// # Return
+///
+/// A pair of the records to delete and the records to add to go from `old` to `new`
+pub fn zone_diff(old: &[Record], new: &[Record]) -> (Vec<Record>, Vec<Record>) {
+    (difference(old, new), difference(new, old))
+}
+
+/// The SOA of `records` followed by the records which are not in `other`, in the order of `records`
+fn difference(records: &[Record], other: &[Record]) -> Vec<Record> {
+    let is_soa = |record: &&Record| record.rr_type() == RecordType::SOA;
+
+    let other: BTreeSet<RecordKey> = other
+        .iter()
+        .filter(|r| !is_soa(r))
+        .map(record_key)
+        .collect();
+
+    records
+        .iter()
+        .find(is_soa)
+        .into_iter()
+        .chain(
+            records
+                .iter()
+                .filter(|r| !is_soa(r) && !other.contains(&record_key(r))),
+        )
+        .cloned()
+        .collect()
+}
+
+/// The fields which identify a record in a zone transfer, unlike the equality of records the TTL
+///  is included
+type RecordKey<'r> = (&'r Name, RecordType, DNSClass, &'r RData, u32);
+
+fn record_key<'r>(record: &'r Record) -> RecordKey<'r> {
+    (
+        record.name(),
+        record.rr_type(),
+        record.dns_class(),
+        record.rdata(),
+        record.ttl(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use trust_dns::rr::rdata::SOA;
+    use trust_dns::rr::{Name, RData};
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn soa(serial: u32) -> Record {
+        let soa = SOA::new(
+            name("ns.example.com."),
+            name("root.example.com."),
+            serial,
+            28800,
+            7200,
+            604_800,
+            86400,
+        );
+
+        Record::from_rdata(name("example.com."), 86400, RData::SOA(soa))
+    }
+
+    fn a(owner: &str, ttl: u32, address: Ipv4Addr) -> Record {
+        Record::from_rdata(name(owner), ttl, RData::A(address))
+    }
+
+    #[test]
+    fn test_zone_diff() {
+        let old = vec![
+            soa(1),
+            a("www.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 1)),
+            a("ftp.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 2)),
+            a("mail.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 3)),
+        ];
+        let new = vec![
+            soa(2),
+            a("www.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 1)),
+            a("ftp.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 4)),
+            a("mail.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 3)),
+        ];
+
+        let (deletions, additions) = zone_diff(&old, &new);
+
+        assert_eq!(deletions, vec![soa(1), old[2].clone()]);
+        assert_eq!(additions, vec![soa(2), new[2].clone()]);
+    }
+
+    #[test]
+    fn test_zone_diff_ttl() {
+        let old = vec![
+            soa(1),
+            a("www.example.com.", 86400, Ipv4Addr::new(127, 0, 0, 1)),
+        ];
+        let new = vec![
+            soa(2),
+            a("www.example.com.", 3600, Ipv4Addr::new(127, 0, 0, 1)),
+        ];
+
+        let (deletions, additions) = zone_diff(&old, &new);
+
+        assert_eq!(deletions.len(), 2);
+        assert_eq!(deletions[1].ttl(), 86400);
+        assert_eq!(additions.len(), 2);
+        assert_eq!(additions[1].ttl(), 3600);
+    }
+}
@@ -14,13 +14,19 @@ use openssl::{pkey::PKey, stack::Stack, x509::X509};
 #[cfg(feature = "dns-over-rustls")]
 use rustls::{Certificate, PrivateKey};
 
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::tsig::DEFAULT_FUDGE;
 use trust_dns::error::ParseResult;
 use trust_dns::rr::dnssec::Algorithm;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TsigSigner;
 #[cfg(any(feature = "dns-over-tls", feature = "dnssec"))]
 use trust_dns::rr::dnssec::{KeyFormat, KeyPair, Private, Signer};
 #[cfg(feature = "dnssec")]
 use trust_dns::rr::domain::IntoName;
 use trust_dns::rr::domain::Name;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::TsigAlgorithm;
 
 /// Key pair configuration for DNSSec keys for signing a zone
 #[derive(Deserialize, PartialEq, Debug)]
@@ -90,6 +96,7 @@ impl KeyConfig {
             Some("key") => Ok(KeyFormat::Pem), // TODO: deprecate this...
             Some("pem") => Ok(KeyFormat::Pem),
             Some("pk8") => Ok(KeyFormat::Pkcs8),
+            Some("private") => Ok(KeyFormat::Bind),
             e => Err(ParseErrorKind::Msg(format!(
                 "extension not understood, '{:?}': {:?}",
                 e,
@@ -159,6 +166,72 @@ impl KeyConfig {
     }
 }
 
+/// Configuration for a TSIG key, shared with the clients whose requests are signed with it
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct TsigKeyConfig {
+    /// name of the key, e.g. transfer-key.example.com.
+    pub key_name: String,
+    /// the MAC algorithm, e.g. hmac-sha256
+    pub algorithm: String,
+    /// file path to the shared secret, the file holds the raw bytes of the secret
+    pub key_path: String,
+}
+
+impl TsigKeyConfig {
+    /// the name of the key, this must be fully qualified
+    pub fn key_name(&self) -> ParseResult<Name> {
+        let name = Name::parse(&self.key_name, None)?;
+        if !name.is_fqdn() {
+            return Err(format!("tsig key name is not fully qualified: {}", name).into());
+        }
+
+        Ok(name)
+    }
+
+    /// path to the shared secret, either relative to the zone directory, or explicit from the root
+    pub fn key_path(&self) -> &Path {
+        Path::new(&self.key_path)
+    }
+
+    /// MAC algorithm of the key, see `TsigAlgorithm` for the supported algorithms
+    #[cfg(feature = "dnssec")]
+    pub fn algorithm(&self) -> ParseResult<TsigAlgorithm> {
+        let name = Name::parse(&self.algorithm, Some(&Name::root()))?;
+        match TsigAlgorithm::from_name(name) {
+            TsigAlgorithm::Unknown(name) => {
+                Err(format!("unrecognized tsig algorithm {}", name).into())
+            }
+            algorithm => Ok(algorithm),
+        }
+    }
+
+    /// Tries to read the shared secret into a TsigSigner
+    ///
+    /// # Arguments
+    ///
+    /// * `zone_dir` - the directory relative paths are resolved against
+    #[cfg(feature = "dnssec")]
+    pub fn try_into_signer(&self, zone_dir: &Path) -> Result<TsigSigner, String> {
+        use std::fs;
+
+        let key_name = self
+            .key_name()
+            .map_err(|e| format!("error loading tsig key name: {}", e))?;
+        let algorithm = self
+            .algorithm()
+            .map_err(|e| format!("error loading tsig algorithm: {}", e))?;
+
+        let key_path = zone_dir.join(self.key_path());
+        let key = fs::read(&key_path)
+            .map_err(|e| format!("failed to read tsig key: {:?} msg: {}", key_path, e))?;
+        if key.is_empty() {
+            return Err(format!("tsig key is empty: {:?}", key_path));
+        }
+
+        Ok(TsigSigner::new(key_name, algorithm, key, DEFAULT_FUDGE))
+    }
+}
+
 /// Certificate format of the file being read
 #[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
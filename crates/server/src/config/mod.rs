@@ -11,7 +11,7 @@ pub mod dnssec;
 
 use std::fs::File;
 use std::io::Read;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -58,6 +58,13 @@ pub struct Config {
     zones: Vec<ZoneConfig>,
     /// Certificate to associate to TLS connections (currently the same is used for HTTPS and TLS)
     tls_cert: Option<dnssec::TlsCertConfig>,
+    /// Version answered to `version.bind.` CH TXT queries
+    version: Option<String>,
+    /// Identity answered to `id.server.` CH TXT queries
+    identity: Option<String>,
+    /// Keys for verifying TSIG signed requests, and signing their responses
+    #[serde(default)]
+    tsig_keys: Vec<dnssec::TsigKeyConfig>,
 }
 
 impl Config {
@@ -136,10 +143,25 @@ impl Config {
         &self.zones
     }
 
+    /// the version answered to `version.bind.` and `version.server.` CH TXT queries, refused if None
+    pub fn get_version(&self) -> Option<&str> {
+        self.version.as_ref().map(String::as_str)
+    }
+
+    /// the identity answered to `id.server.` and `hostname.bind.` CH TXT queries, refused if None
+    pub fn get_identity(&self) -> Option<&str> {
+        self.identity.as_ref().map(String::as_str)
+    }
+
     /// the tls certificate to use for accepting tls connections
     pub fn get_tls_cert(&self) -> Option<&dnssec::TlsCertConfig> {
         self.tls_cert.as_ref()
     }
+
+    /// the keys for verifying TSIG signed requests
+    pub fn get_tsig_keys(&self) -> &[dnssec::TsigKeyConfig] {
+        &self.tsig_keys
+    }
 }
 
 impl FromStr for Config {
@@ -163,6 +185,10 @@ pub struct ZoneConfig {
     pub allow_update: Option<bool>,
     /// Allow AXFR (TODO: need auth)
     pub allow_axfr: Option<bool>,
+    /// Restrict zone transfers, AXFR and IXFR, to these peers
+    pub transfer_peers: Option<Vec<IpAddr>>,
+    /// Answer ANY queries with the minimal response of RFC 8482
+    pub minimal_any: Option<bool>,
     /// Enable DnsSec TODO: should this move to StoreConfig?
     pub enable_dnssec: Option<bool>,
     /// Keys for use by the zone
@@ -200,6 +226,8 @@ impl ZoneConfig {
             file: Some(file),
             allow_update,
             allow_axfr,
+            transfer_peers: None,
+            minimal_any: None,
             enable_dnssec,
             keys,
             stores: None,
@@ -236,6 +264,16 @@ impl ZoneConfig {
         self.allow_axfr.unwrap_or(false)
     }
 
+    /// the peers zone transfers are restricted to, any peer is allowed if None
+    pub fn get_transfer_peers(&self) -> Option<&[IpAddr]> {
+        self.transfer_peers.as_ref().map(Vec::as_slice)
+    }
+
+    /// answer ANY queries with a single RRSet, instead of all the records at the name, RFC 8482
+    pub fn is_minimal_any(&self) -> bool {
+        self.minimal_any.unwrap_or(false)
+    }
+
     /// declare that this zone should be signed, see keys for configuration of the keys for signing
     pub fn is_dnssec_enabled(&self) -> bool {
         self.enable_dnssec.unwrap_or(false)
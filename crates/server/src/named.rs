@@ -65,6 +65,7 @@ use trust_dns_server::server::ServerFuture;
 use trust_dns_server::store::file::{FileAuthority, FileConfig};
 #[cfg(feature = "trust-dns-resolver")]
 use trust_dns_server::store::forwarder::ForwardAuthority;
+use trust_dns_server::store::in_memory::InMemoryAuthority;
 use trust_dns_server::store::sqlite::{SqliteAuthority, SqliteConfig};
 use trust_dns_server::store::StoreConfig;
 
@@ -81,6 +82,12 @@ fn load_zone(
     let zone_path: Option<String> = zone_config.file.clone();
     let zone_type: ZoneType = zone_config.get_zone_type();
     let is_axfr_allowed = zone_config.is_axfr_allowed();
+
+    // the options of the InMemoryAuthority of the file and sqlite stores
+    let configure = |authority: &mut InMemoryAuthority| {
+        authority.set_minimal_any(zone_config.is_minimal_any());
+        authority.set_transfer_peers(zone_config.get_transfer_peers().map(<[_]>::to_vec));
+    };
     let is_dnssec_enabled = zone_config.is_dnssec_enabled();
 
     if zone_config.is_update_allowed() {
@@ -102,7 +109,10 @@ fn load_zone(
                 Some(zone_dir),
                 config,
             )
-            .map(Box::new)?
+            .map(|mut authority| {
+                configure(&mut authority);
+                Box::new(authority)
+            })?
         }
         Some(StoreConfig::File(ref config)) => {
             if zone_path.is_some() {
@@ -115,7 +125,10 @@ fn load_zone(
                 Some(zone_dir),
                 config,
             )
-            .map(Box::new)?
+            .map(|mut authority| {
+                configure(&mut authority);
+                Box::new(authority)
+            })?
         }
         #[cfg(feature = "trust-dns-resolver")]
         Some(StoreConfig::Forward(ref config)) => {
@@ -155,7 +168,10 @@ fn load_zone(
                 Some(zone_dir),
                 &config,
             )
-            .map(Box::new)?
+            .map(|mut authority| {
+                configure(&mut authority);
+                Box::new(authority)
+            })?
         }
         None => {
             let config = FileConfig {
@@ -169,7 +185,10 @@ fn load_zone(
                 Some(zone_dir),
                 &config,
             )
-            .map(Box::new)?
+            .map(|mut authority| {
+                configure(&mut authority);
+                Box::new(authority)
+            })?
         }
     };
 
@@ -365,6 +384,23 @@ pub fn main() {
     let mut io_loop = Runtime::new().expect("error when creating tokio Runtime");
     let executor = io_loop.executor();
     let mut catalog: Catalog = Catalog::new();
+    catalog.set_version(config.get_version().map(str::to_string));
+    catalog.set_identity(config.get_identity().map(str::to_string));
+
+    #[cfg(feature = "dnssec")]
+    for tsig_key in config.get_tsig_keys() {
+        let signer = tsig_key
+            .try_into_signer(&zone_dir)
+            .unwrap_or_else(|e| panic!("could not load tsig key {}: {}", tsig_key.key_name, e));
+        catalog.add_tsig_key(signer);
+    }
+    #[cfg(not(feature = "dnssec"))]
+    {
+        if !config.get_tsig_keys().is_empty() {
+            panic!("tsig keys are configured, but the dnssec feature is not enabled");
+        }
+    }
+
     // configure our server based on the config_path
     for zone in config.get_zones() {
         let zone_name = zone
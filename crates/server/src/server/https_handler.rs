@@ -16,7 +16,7 @@ use tokio_io::{AsyncRead, AsyncWrite};
 use trust_dns_https::https_server;
 
 use authority::MessageResponse;
-use server::request_handler::RequestHandler;
+use server::request_handler::{Protocol, RequestHandler};
 use server::response_handler::ResponseHandler;
 use server::server_future;
 
@@ -56,6 +56,7 @@ where
                             server_future::handle_request(
                                 message,
                                 src_addr,
+                                Protocol::Tcp,
                                 handler.clone(),
                                 responder,
                             )
@@ -0,0 +1,140 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Handler for requests which are answered synchronously with a complete `Message`
+
+use std::io;
+
+use futures::future::{self, FutureResult};
+
+use proto::op::{Edns, Message, MessageType, ResponseCode};
+use proto::rr::Record;
+use proto::serialize::binary::BinEncodable;
+
+use authority::{MessageRequest, MessageResponseBuilder};
+use server::{Request, RequestHandler, ResponseHandler};
+
+/// Trait for handling requests as whole messages
+///
+/// This is simpler to implement than `RequestHandler`, for servers which can answer every request
+///  immediately. Use `MessageRequestHandler` to register it with a `ServerFuture`.
+pub trait MessageHandler: Send + 'static {
+    /// Returns the response to the request
+    ///
+    /// The id of the response is always set to the id of the request. The response must be a
+    ///  response to the queries of the request, the queries of the response are replaced with
+    ///  those.
+    fn handle_request(&self, request: &Message) -> Message;
+}
+
+/// Adapts a `MessageHandler` to a `RequestHandler`
+///
+/// This takes care of the OPT record: requests with an unsupported EDNS version are answered
+///  with `BADVERS`, responses to EDNS requests always include an OPT record, and responses to
+///  other requests never do. Responses sent over UDP that are too large for the client are
+///  truncated, and have the TC bit set.
+pub struct MessageRequestHandler<H: MessageHandler> {
+    handler: H,
+}
+
+impl<H: MessageHandler> MessageRequestHandler<H> {
+    /// Wraps the handler
+    pub fn new(handler: H) -> Self {
+        MessageRequestHandler { handler }
+    }
+
+    /// Returns the wrapped handler
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    fn respond<R: ResponseHandler>(
+        &self,
+        request: &MessageRequest,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let mut response = MessageResponseBuilder::new(Some(request.raw_queries()));
+
+        // TODO: what version are we?
+        let our_version = 0;
+        if let Some(req_edns) = request.edns() {
+            let mut resp_edns = Edns::new();
+            resp_edns.set_max_payload(req_edns.max_payload().max(512));
+            resp_edns.set_version(our_version);
+            response.edns(resp_edns);
+
+            if req_edns.version() > our_version {
+                warn!(
+                    "request edns version greater than {}: {}",
+                    our_version,
+                    req_edns.version()
+                );
+                return response_handle.send_response(response.error_msg(
+                    request.id(),
+                    request.op_code(),
+                    ResponseCode::BADVERS,
+                ));
+            }
+        }
+
+        // the handler takes a Message, which is most easily had by decoding the request again
+        let message = match request
+            .to_bytes()
+            .and_then(|bytes| Message::from_vec(&bytes))
+        {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("failed to convert request {}: {}", request.id(), e);
+                return response_handle.send_response(response.error_msg(
+                    request.id(),
+                    request.op_code(),
+                    ResponseCode::FormErr,
+                ));
+            }
+        };
+
+        let mut answer = self.handler.handle_request(&message);
+        answer
+            .set_id(request.id())
+            .set_message_type(MessageType::Response);
+
+        // the handler may choose the EDNS options, but only if the client supports them
+        if request.edns().is_some() {
+            if let Some(edns) = answer.edns() {
+                response.edns(edns.clone());
+            }
+        }
+
+        response_handle.send_response(response.build(
+            answer.header().clone(),
+            records(answer.answers()),
+            records(answer.name_servers()),
+            records(&[]),
+            records(answer.additionals()),
+        ))
+    }
+}
+
+fn records<'a>(records: &'a [Record]) -> Box<dyn Iterator<Item = &'a Record> + Send + 'a> {
+    Box::new(records.iter())
+}
+
+impl<H: MessageHandler> RequestHandler for MessageRequestHandler<H> {
+    type ResponseFuture = FutureResult<(), ()>;
+
+    fn handle_request<R: ResponseHandler>(
+        &self,
+        request: Request,
+        response_handle: R,
+    ) -> Self::ResponseFuture {
+        if let Err(e) = self.respond(&request.message, response_handle) {
+            error!("failed to send response to {}: {}", request.src, e);
+        }
+
+        future::ok(())
+    }
+}
@@ -9,12 +9,18 @@
 
 #[cfg(feature = "dns-over-https")]
 mod https_handler;
+mod message_handler;
+mod rate_limiter;
 mod request_handler;
 mod response_handler;
 mod server_future;
 mod timeout_stream;
 
-pub use self::request_handler::{Request, RequestHandler};
+pub use self::message_handler::{MessageHandler, MessageRequestHandler};
+pub use self::rate_limiter::{RateLimit, RateLimiter, TokenBucketRateLimiter};
+pub use self::request_handler::{Protocol, Request, RequestHandler};
+#[cfg(feature = "dnssec")]
+pub use self::response_handler::TsigResponseHandle;
 pub use self::response_handler::{ResponseHandle, ResponseHandler};
 pub use self::server_future::ServerFuture;
 pub use self::timeout_stream::TimeoutStream;
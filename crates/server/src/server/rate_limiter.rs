@@ -0,0 +1,244 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Response rate limiting, RRL, to mitigate the use of the server for amplification attacks
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use lru_cache::LruCache;
+use proto::op::Header;
+
+/// The prefix length of IPv4 clients sharing a limit, as in BIND
+const IPV4_PREFIX_LEN: u8 = 24;
+/// The prefix length of IPv6 clients sharing a limit, as in BIND
+const IPV6_PREFIX_LEN: u8 = 56;
+/// Once this many clients are tracked, the least recently seen one is forgotten for a new one
+const MAX_TRACKED: usize = 16_384;
+
+/// What to do with a response to a client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimit {
+    /// Send the response
+    Allow,
+    /// Send an empty response with the TC bit set, legitimate clients will retry over TCP
+    Truncate,
+    /// Don't send any response
+    Drop,
+}
+
+/// A limiter consulted before responses to UDP requests are sent
+///
+/// Responses over TCP aren't limited, the handshake already proves the client's address.
+pub trait RateLimiter: Send + Sync + 'static {
+    /// Decides on the response to send to the client
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - address of the client the response is for
+    /// * `response` - header of the response, e.g. for its response code
+    fn check(&self, client: IpAddr, response: &Header) -> RateLimit;
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    limited: u32,
+}
+
+/// A token bucket per client prefix and response code
+///
+/// Clients are grouped by their /24 IPv4 or /56 IPv6 prefix. Each group gets `burst` responses,
+///  refilled at `responses_per_second`. Responses past the limit are dropped, except for every
+///  `slip`th, which is truncated.
+pub struct TokenBucketRateLimiter {
+    responses_per_second: f64,
+    burst: f64,
+    slip: u32,
+    buckets: Mutex<LruCache<(IpAddr, u8), Bucket>>,
+}
+
+impl TokenBucketRateLimiter {
+    /// Creates a new limiter, every 2nd limited response is truncated
+    ///
+    /// # Arguments
+    ///
+    /// * `responses_per_second` - rate at which responses are allowed once the burst is used up
+    /// * `burst` - number of responses allowed at once
+    pub fn new(responses_per_second: u32, burst: u32) -> Self {
+        TokenBucketRateLimiter {
+            responses_per_second: f64::from(responses_per_second),
+            burst: f64::from(burst),
+            slip: 2,
+            buckets: Mutex::new(LruCache::new(MAX_TRACKED)),
+        }
+    }
+
+    /// Truncate every `slip`th limited response and drop the others, 0 drops all of them
+    pub fn set_slip(&mut self, slip: u32) {
+        self.slip = slip;
+    }
+
+    fn check_at(&self, client: IpAddr, response_code: u8, now: Instant) -> RateLimit {
+        let mut buckets = self.buckets.lock().expect("poisoned lock");
+
+        let key = (prefix(client), response_code);
+        if !buckets.contains_key(&key) {
+            let bucket = Bucket {
+                tokens: self.burst,
+                last: now,
+                limited: 0,
+            };
+            buckets.insert(key, bucket);
+        }
+        let bucket = buckets.get_mut(&key).expect("bucket was inserted");
+
+        if refill(bucket, self.responses_per_second, self.burst, now) >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RateLimit::Allow;
+        }
+
+        bucket.limited += 1;
+        if self.slip != 0 && bucket.limited >= self.slip {
+            bucket.limited = 0;
+            RateLimit::Truncate
+        } else {
+            RateLimit::Drop
+        }
+    }
+}
+
+impl RateLimiter for TokenBucketRateLimiter {
+    fn check(&self, client: IpAddr, response: &Header) -> RateLimit {
+        self.check_at(client, response.response_code(), Instant::now())
+    }
+}
+
+/// Adds the tokens accrued since the last refill, returns the tokens available
+///
+/// `now` may be before the last refill, if another thread read the clock later but took the lock
+///  first, then nothing accrued.
+fn refill(bucket: &mut Bucket, responses_per_second: f64, burst: f64, now: Instant) -> f64 {
+    if now <= bucket.last {
+        return bucket.tokens;
+    }
+
+    let elapsed = now.duration_since(bucket.last);
+    let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+
+    bucket.tokens = (bucket.tokens + elapsed * responses_per_second).min(burst);
+    bucket.last = now;
+    bucket.tokens
+}
+
+/// The client's address masked to the prefix sharing its limit
+fn prefix(client: IpAddr) -> IpAddr {
+    match client {
+        IpAddr::V4(ip) => {
+            let mask = !0u32 << (32 - IPV4_PREFIX_LEN);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = !0u128 << (128 - IPV6_PREFIX_LEN);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use proto::op::ResponseCode;
+
+    use super::*;
+
+    #[test]
+    fn test_limit_past_burst() {
+        let mut limiter = TokenBucketRateLimiter::new(1, 2);
+        limiter.set_slip(2);
+        let client = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        let results: Vec<_> = (0..6).map(|_| limiter.check_at(client, 0, now)).collect();
+        assert_eq!(
+            results,
+            vec![
+                RateLimit::Allow,
+                RateLimit::Allow,
+                RateLimit::Drop,
+                RateLimit::Truncate,
+                RateLimit::Drop,
+                RateLimit::Truncate,
+            ]
+        );
+
+        // a second later there is a token again
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.check_at(client, 0, later), RateLimit::Allow);
+        assert_eq!(limiter.check_at(client, 0, later), RateLimit::Drop);
+    }
+
+    #[test]
+    fn test_earlier_now() {
+        let limiter = TokenBucketRateLimiter::new(1, 1);
+        let client = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+
+        assert_eq!(limiter.check_at(client, 0, later), RateLimit::Allow);
+        // a thread which read the clock before the last refill accrues nothing
+        assert_eq!(limiter.check_at(client, 0, now), RateLimit::Drop);
+        assert_eq!(
+            limiter.check_at(client, 0, later + Duration::from_secs(1)),
+            RateLimit::Allow
+        );
+    }
+
+    #[test]
+    fn test_limit_keys() {
+        let mut limiter = TokenBucketRateLimiter::new(1, 1);
+        limiter.set_slip(0);
+        let now = Instant::now();
+
+        let nx_domain = u16::from(ResponseCode::NXDomain) as u8;
+        let client = IpAddr::from([192, 0, 2, 1]);
+        assert_eq!(limiter.check_at(client, 0, now), RateLimit::Allow);
+        assert_eq!(limiter.check_at(client, 0, now), RateLimit::Drop);
+        // the same prefix shares the limit
+        let neighbour = IpAddr::from([192, 0, 2, 200]);
+        assert_eq!(limiter.check_at(neighbour, 0, now), RateLimit::Drop);
+        // but another response code or prefix doesn't
+        assert_eq!(limiter.check_at(client, nx_domain, now), RateLimit::Allow);
+        let other = IpAddr::from([192, 0, 3, 1]);
+        assert_eq!(limiter.check_at(other, 0, now), RateLimit::Allow);
+
+        let client = IpAddr::from([0x2001, 0xdb8, 0, 0x100, 0, 0, 0, 1]);
+        let neighbour = IpAddr::from([0x2001, 0xdb8, 0, 0x1ff, 0, 0, 0, 2]);
+        assert_eq!(limiter.check_at(client, 0, now), RateLimit::Allow);
+        assert_eq!(limiter.check_at(neighbour, 0, now), RateLimit::Drop);
+    }
+
+    #[test]
+    fn test_max_tracked() {
+        let mut limiter = TokenBucketRateLimiter::new(1, 1);
+        limiter.set_slip(0);
+        let now = Instant::now();
+
+        let first = IpAddr::from([10, 0, 0, 1]);
+        assert_eq!(limiter.check_at(first, 0, now), RateLimit::Allow);
+        for i in 0..MAX_TRACKED as u32 {
+            let client = IpAddr::from(Ipv4Addr::from((11 << 24) + (i << 8)));
+            limiter.check_at(client, 0, now);
+        }
+
+        // the least recently seen client was forgotten, and the clients don't grow past the cap
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_TRACKED);
+        assert_eq!(limiter.check_at(first, 0, now), RateLimit::Allow);
+    }
+}
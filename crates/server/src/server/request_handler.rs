@@ -14,12 +14,23 @@ use futures::Future;
 use authority::MessageRequest;
 use server::ResponseHandler;
 
+/// The protocol a request was received on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// UDP
+    Udp,
+    /// TCP, or TLS and HTTPS over TCP
+    Tcp,
+}
+
 /// An incoming request to the DNS catalog
 pub struct Request {
     /// Message with the associated query or update data
     pub message: MessageRequest,
     /// Source address of the Client
     pub src: SocketAddr,
+    /// Protocol the request was received on
+    pub protocol: Protocol,
 }
 
 /// Trait for handling incoming requests, and providing a message response.
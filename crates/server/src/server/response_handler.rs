@@ -7,12 +7,19 @@
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
+#[cfg(feature = "dnssec")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TsigSigner;
 
 use proto::xfer::SerialMessage;
 use trust_dns::serialize::binary::BinEncoder;
 use trust_dns::BufStreamHandle;
 
 use authority::MessageResponse;
+use server::{RateLimit, RateLimiter};
 
 /// A handler for send a response to a client
 pub trait ResponseHandler: Clone + Send + 'static {
@@ -31,12 +38,31 @@ pub trait ResponseHandler: Clone + Send + 'static {
 pub struct ResponseHandle {
     dst: SocketAddr,
     stream_handle: BufStreamHandle,
+    max_size: Option<u16>,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
 }
 
 impl ResponseHandle {
     /// Returns a new `ResponseHandle` for sending a response message
     pub fn new(dst: SocketAddr, stream_handle: BufStreamHandle) -> Self {
-        ResponseHandle { dst, stream_handle }
+        ResponseHandle {
+            dst,
+            stream_handle,
+            max_size: None,
+            rate_limiter: None,
+        }
+    }
+
+    /// Limits the size of responses, larger responses are truncated and have the TC bit set
+    ///
+    /// This is the negotiated payload size for UDP, i.e. 512 or the EDNS max payload of the request.
+    pub fn set_max_size(&mut self, max_size: u16) {
+        self.max_size = Some(max_size);
+    }
+
+    /// Consults the limiter before sending the response, it may be truncated or dropped
+    pub fn set_rate_limiter(&mut self, rate_limiter: Arc<dyn RateLimiter>) {
+        self.rate_limiter = Some(rate_limiter);
     }
 }
 
@@ -44,7 +70,21 @@ impl ResponseHandler for ResponseHandle {
     /// Serializes and sends a message to to the wrapped handle
     ///
     /// self is consumed as only one message should ever be sent in response to a Request
-    fn send_response(&self, response: MessageResponse) -> io::Result<()> {
+    fn send_response(&self, mut response: MessageResponse) -> io::Result<()> {
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            match rate_limiter.check(self.dst.ip(), response.header()) {
+                RateLimit::Allow => (),
+                RateLimit::Truncate => {
+                    debug!("rate limited, truncating response to: {}", self.dst);
+                    response = response.truncate();
+                }
+                RateLimit::Drop => {
+                    debug!("rate limited, dropping response to: {}", self.dst);
+                    return Ok(());
+                }
+            }
+        }
+
         info!(
             "response: {} response_code: {}",
             response.header().id(),
@@ -52,7 +92,8 @@ impl ResponseHandler for ResponseHandle {
         );
         let mut buffer = Vec::with_capacity(512);
         let encode_result = {
-            let mut encoder: BinEncoder = BinEncoder::new(&mut buffer);
+            let mut encoder: BinEncoder =
+                BinEncoder::with_max_size(&mut buffer, self.max_size.unwrap_or(u16::max_value()));
             response.destructive_emit(&mut encoder)
         };
 
@@ -68,3 +109,43 @@ impl ResponseHandler for ResponseHandle {
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))
     }
 }
+
+/// A handler which signs the responses to a TSIG verified request, before sending them with the
+///  wrapped handler
+#[cfg(feature = "dnssec")]
+#[derive(Clone)]
+pub struct TsigResponseHandle<R: ResponseHandler> {
+    response_handle: R,
+    signer: Arc<TsigSigner>,
+    request_mac: Arc<[u8]>,
+}
+
+#[cfg(feature = "dnssec")]
+impl<R: ResponseHandler> TsigResponseHandle<R> {
+    /// Returns a new `TsigResponseHandle` signing with the key which verified the request
+    ///
+    /// # Arguments
+    ///
+    /// * `response_handle` - the handler to send the signed responses with
+    /// * `signer` - the key which verified the request
+    /// * `request_mac` - the verified MAC of the TSIG of the request
+    pub fn new(response_handle: R, signer: Arc<TsigSigner>, request_mac: Vec<u8>) -> Self {
+        TsigResponseHandle {
+            response_handle,
+            signer,
+            request_mac: request_mac.into(),
+        }
+    }
+}
+
+#[cfg(feature = "dnssec")]
+impl<R: ResponseHandler> ResponseHandler for TsigResponseHandle<R> {
+    fn send_response(&self, mut response: MessageResponse) -> io::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+
+        response.set_tsig_signer(self.signer.clone(), self.request_mac.clone(), now);
+        self.response_handle.send_response(response)
+    }
+}
@@ -29,13 +29,16 @@ use proto::BufStreamHandle;
 use trust_dns_openssl::tls_server::*;
 
 use authority::MessageRequest;
-use server::{Request, RequestHandler, ResponseHandle, ResponseHandler, TimeoutStream};
+use server::{
+    Protocol, RateLimiter, Request, RequestHandler, ResponseHandle, ResponseHandler, TimeoutStream,
+};
 
 // TODO, would be nice to have a Slab for buffers here...
 
 /// A Futures based implementation of a DNS server
 pub struct ServerFuture<T: RequestHandler> {
     handler: Arc<Mutex<T>>,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
 }
 
 impl<T: RequestHandler> ServerFuture<T> {
@@ -43,9 +46,15 @@ impl<T: RequestHandler> ServerFuture<T> {
     pub fn new(handler: T) -> ServerFuture<T> {
         ServerFuture {
             handler: Arc::new(Mutex::new(handler)),
+            rate_limiter: None,
         }
     }
 
+    /// Limits the responses to UDP requests, sockets registered afterwards use the limiter
+    pub fn set_rate_limiter<L: RateLimiter>(&mut self, rate_limiter: L) {
+        self.rate_limiter = Some(Arc::new(rate_limiter));
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&self, socket: tokio_udp::UdpSocket) {
         debug!("registered udp: {:?}", socket);
@@ -54,6 +63,7 @@ impl<T: RequestHandler> ServerFuture<T> {
         let (buf_stream, stream_handle) = UdpStream::with_bound(socket);
         //let request_stream = RequestStream::new(buf_stream, stream_handle);
         let handler = self.handler.clone();
+        let rate_limiter = self.rate_limiter.clone();
 
         // this spawns a ForEach future which handles all the requests into a Handler.
         tokio_executor::spawn(
@@ -62,7 +72,13 @@ impl<T: RequestHandler> ServerFuture<T> {
                 .for_each(move |message| {
                     let src_addr = message.addr();
                     debug!("received udp request from: {}", src_addr);
-                    self::handle_raw_request(message, handler.clone(), stream_handle.clone())
+                    self::handle_raw_request(
+                        message,
+                        Protocol::Udp,
+                        handler.clone(),
+                        stream_handle.clone(),
+                        rate_limiter.clone(),
+                    )
                 }),
         );
     }
@@ -119,8 +135,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                             .for_each(move |message| {
                                 self::handle_raw_request(
                                     message,
+                                    Protocol::Tcp,
                                     handler.clone(),
                                     stream_handle.clone(),
+                                    None,
                                 )
                             }),
                     );
@@ -222,8 +240,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                                     .for_each(move |message| {
                                         self::handle_raw_request(
                                             message,
+                                            Protocol::Tcp,
                                             handler.clone(),
                                             stream_handle.clone(),
+                                            None,
                                         )
                                     })
                                     .map_err(move |_| {
@@ -345,8 +365,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                                     .for_each(move |message| {
                                         self::handle_raw_request(
                                             message,
+                                            Protocol::Tcp,
                                             handler.clone(),
                                             stream_handle.clone(),
+                                            None,
                                         )
                                     })
                                     .map_err(move |_| {
@@ -470,11 +492,13 @@ impl<T: RequestHandler> ServerFuture<T> {
 
 pub(crate) fn handle_raw_request<T: RequestHandler>(
     message: SerialMessage,
+    protocol: Protocol,
     request_handler: Arc<Mutex<T>>,
     response_handler: BufStreamHandle,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
 ) -> HandleRawRequest<T::ResponseFuture> {
     let src_addr = message.addr();
-    let response_handler = ResponseHandle::new(message.addr(), response_handler);
+    let mut response_handler = ResponseHandle::new(message.addr(), response_handler);
 
     // TODO: rather than decoding the message here, this RequestStream should instead
     //       forward the request to another sender such that we could pull serialization off
@@ -483,8 +507,22 @@ pub(crate) fn handle_raw_request<T: RequestHandler>(
     let mut decoder = BinDecoder::new(message.bytes());
     match MessageRequest::read(&mut decoder) {
         Ok(message) => {
-            let handle_request =
-                self::handle_request(message, src_addr, request_handler, response_handler);
+            // responses over UDP must fit the size the client can receive
+            if protocol == Protocol::Udp {
+                response_handler.set_max_size(message.max_payload());
+
+                if let Some(rate_limiter) = rate_limiter {
+                    response_handler.set_rate_limiter(rate_limiter);
+                }
+            }
+
+            let handle_request = self::handle_request(
+                message,
+                src_addr,
+                protocol,
+                request_handler,
+                response_handler,
+            );
             HandleRawRequest::HandleRequest(handle_request)
         }
         Err(e) => HandleRawRequest::Result(e.into()),
@@ -494,12 +532,14 @@ pub(crate) fn handle_raw_request<T: RequestHandler>(
 pub(crate) fn handle_request<R: ResponseHandler, T: RequestHandler>(
     message: MessageRequest,
     src_addr: SocketAddr,
+    protocol: Protocol,
     request_handler: Arc<Mutex<T>>,
     response_handler: R,
 ) -> T::ResponseFuture {
     let request = Request {
         message,
         src: src_addr,
+        protocol,
     };
 
     info!(
@@ -8,6 +8,7 @@
 //! All authority related types
 
 use std::collections::BTreeMap;
+use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
@@ -82,7 +83,7 @@ impl FileAuthority {
         file.read_to_string(&mut buf)
             .map_err(|e| format!("failed to read {}: {:?}", zone_path.display(), e))?;
         let lexer = Lexer::new(&buf);
-        let (origin, records) = Parser::new()
+        let (origin, records) = Parser::with_path(zone_path.clone())
             .parse(lexer, Some(origin))
             .map_err(|e| format!("failed to parse {}: {:?}", zone_path.display(), e))?;
 
@@ -92,7 +93,10 @@ impl FileAuthority {
             records.len()
         );
 
-        FileAuthority::new(origin, records, zone_type, allow_axfr)
+        let authority = FileAuthority::new(origin, records, zone_type, allow_axfr)?;
+        authority.verify_glue()?;
+
+        Ok(authority)
     }
 
     /// Unwrap the InMemoryAuthority
@@ -129,6 +133,11 @@ impl Authority for FileAuthority {
         self.0.is_axfr_allowed()
     }
 
+    /// Return true if zone transfers to the peer are allowed
+    fn is_transfer_allowed(&self, peer: IpAddr) -> bool {
+        self.0.is_transfer_allowed(peer)
+    }
+
     /// Perform a dynamic update of a zone
     fn update(&mut self, _update: &MessageRequest) -> UpdateResult<bool> {
         use proto::op::ResponseCode;
@@ -245,7 +254,7 @@ mod tests {
     use authority::ZoneType;
     use std::net::Ipv4Addr;
     use std::str::FromStr;
-    use trust_dns::rr::RData;
+    use trust_dns::rr::{RData, Record};
 
     #[test]
     fn test_load_zone() {
@@ -281,4 +290,78 @@ mod tests {
             _ => panic!("wrong rdata type returned"),
         }
     }
+
+    #[test]
+    fn test_load_zone_missing_glue() {
+        let config = FileConfig {
+            zone_file_path: "tests/named_test_configs/missing_glue.zone".to_string(),
+        };
+        let error = FileAuthority::try_from_config(
+            Name::from_str("example.com.").unwrap(),
+            ZoneType::Master,
+            false,
+            None,
+            &config,
+        )
+        .err()
+        .expect("zone without glue loaded");
+
+        assert!(error.contains("ns.sub.example.com."), "{}", error);
+        // out-of-zone servers are not checked
+        assert!(!error.contains("ns.example.net."), "{}", error);
+    }
+
+    #[test]
+    fn test_load_zone_include() {
+        let config = FileConfig {
+            zone_file_path: "tests/named_test_configs/include.zone".to_string(),
+        };
+        let authority = FileAuthority::try_from_config(
+            Name::from_str("example.com.").unwrap(),
+            ZoneType::Master,
+            false,
+            None,
+            &config,
+        )
+        .expect("failed to load file");
+
+        let addresses = [
+            ("www.example.com.", Ipv4Addr::new(127, 0, 0, 1)),
+            ("sub.example.com.", Ipv4Addr::new(127, 0, 0, 2)),
+            ("www.sub.example.com.", Ipv4Addr::new(127, 0, 0, 3)),
+        ];
+        for (name, address) in &addresses {
+            let lookup = Authority::lookup(
+                &authority,
+                &LowerName::from_str(name).unwrap(),
+                RecordType::A,
+                false,
+                SupportedAlgorithms::new(),
+            )
+            .wait()
+            .expect("lookup failed");
+
+            let rdatas: Vec<&RData> = lookup.iter().map(Record::rdata).collect();
+            assert_eq!(rdatas, vec![&RData::A(*address)], "{}", name);
+        }
+        assert_eq!(authority.records().len(), 5);
+    }
+
+    #[test]
+    fn test_load_zone_include_loop() {
+        let config = FileConfig {
+            zone_file_path: "tests/named_test_configs/include_loop.zone".to_string(),
+        };
+        let error = FileAuthority::try_from_config(
+            Name::from_str("example.com.").unwrap(),
+            ZoneType::Master,
+            false,
+            None,
+            &config,
+        )
+        .err()
+        .expect("zone with an include loop loaded");
+
+        assert!(error.contains("$INCLUDE loop"), "{}", error);
+    }
 }
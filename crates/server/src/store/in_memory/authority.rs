@@ -9,6 +9,7 @@
 
 use std::borrow::Borrow;
 use std::collections::BTreeMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use futures::future::{self, Future, FutureResult, IntoFuture};
@@ -19,7 +20,8 @@ use trust_dns::rr::rdata::key::KEY;
 #[cfg(feature = "dnssec")]
 use trust_dns::rr::rdata::DNSSECRData;
 use trust_dns::rr::rdata::DNSSECRecordType;
-use trust_dns::rr::rdata::SOA;
+use trust_dns::rr::rdata::NSEC3PARAM;
+use trust_dns::rr::rdata::{HINFO, SOA};
 use trust_dns::rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
 
 use authority::{
@@ -37,12 +39,18 @@ pub struct InMemoryAuthority {
     records: BTreeMap<RrKey, Arc<RecordSet>>,
     zone_type: ZoneType,
     allow_axfr: bool,
+    transfer_peers: Option<Vec<IpAddr>>,
+    minimal_any: bool,
     // Private key mapped to the Record of the DNSKey
     //  TODO: these private_keys should be stored securely. Ideally, we have keys only stored per
     //   server instance, but that requires requesting updates from the parent zone, which may or
     //   may not support dynamic updates to register the new key... Trust-DNS will provide support
     //   for this, in some form, perhaps alternate root zones...
     secure_keys: Vec<Signer>,
+    nsec3_params: Option<NSEC3PARAM>,
+    // the hashes of the owners of the NSEC3 records, in order, for finding the covering record
+    #[cfg(feature = "dnssec")]
+    nsec3_owners: BTreeMap<Vec<u8>, LowerName>,
 }
 
 impl InMemoryAuthority {
@@ -78,6 +86,19 @@ impl InMemoryAuthority {
             .map(SOA::serial)
             .ok_or_else(|| format!("SOA record must be present: {}", origin))?;
 
+        let conflicts = cname_conflicts(&records);
+        if !conflicts.is_empty() {
+            let conflicts = conflicts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!(
+                "CNAME and other data at {} in zone: {}",
+                conflicts, origin
+            ));
+        }
+
         let iter = records.into_iter().map(|(_key, record)| record);
 
         // add soa to the records
@@ -110,12 +131,19 @@ impl InMemoryAuthority {
             records: BTreeMap::new(),
             zone_type,
             allow_axfr,
+            transfer_peers: None,
+            minimal_any: false,
             secure_keys: Vec::new(),
+            nsec3_params: None,
+            #[cfg(feature = "dnssec")]
+            nsec3_owners: BTreeMap::new(),
         }
     }
 
     /// Clears all records (including SOA, etc)
     pub fn clear(&mut self) {
+        #[cfg(feature = "dnssec")]
+        self.nsec3_owners.clear();
         self.records.clear()
     }
 
@@ -124,16 +152,106 @@ impl InMemoryAuthority {
         self.class
     }
 
+    /// Verifies that the name servers within the zone have address records, the glue
+    ///
+    /// Without the A or AAAA records of such a name server, which includes those of delegations to
+    ///  child zones, resolvers have no means to reach the server.
+    ///
+    /// # Return value
+    ///
+    /// An error naming all in-zone name servers without address records
+    pub fn verify_glue(&self) -> Result<(), String> {
+        let mut missing = Vec::new();
+
+        let name_servers = self
+            .records
+            .values()
+            .filter(|rrset| rrset.record_type() == RecordType::NS)
+            .flat_map(|rrset| rrset.records_without_rrsigs());
+        for record in name_servers {
+            let target = match *record.rdata() {
+                RData::NS(ref target) => LowerName::new(target),
+                _ => continue,
+            };
+
+            if !self.origin.zone_of(&target) || missing.contains(&target) {
+                continue;
+            }
+
+            let has_address = [RecordType::A, RecordType::AAAA].iter().any(|rr_type| {
+                self.records
+                    .contains_key(&RrKey::new(target.clone(), *rr_type))
+            });
+            if !has_address {
+                missing.push(target);
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let missing = missing
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(format!(
+            "missing glue for the name servers {} in zone: {}",
+            missing, self.origin
+        ))
+    }
+
     /// Enables AXFRs of all the zones records
     pub fn set_allow_axfr(&mut self, allow_axfr: bool) {
         self.allow_axfr = allow_axfr;
     }
 
+    /// Restricts zone transfers, AXFR and IXFR, to the peers, if AXFR is allowed
+    ///
+    /// With `None`, the default, transfers to any peer are allowed. Transfers are only ever
+    ///  allowed over TCP, see `Catalog`.
+    pub fn set_transfer_peers(&mut self, transfer_peers: Option<Vec<IpAddr>>) {
+        self.transfer_peers = transfer_peers;
+    }
+
+    /// The peers zone transfers are restricted to, see `set_transfer_peers()`
+    pub fn transfer_peers(&self) -> Option<&[IpAddr]> {
+        self.transfer_peers.as_ref().map(Vec::as_slice)
+    }
+
+    /// Answers ANY queries with a single RRSet instead of all the records at the name
+    ///
+    /// This is the minimal response of [RFC 8482](https://tools.ietf.org/html/rfc8482#section-4),
+    ///  a CNAME at the name is returned, otherwise an HINFO record is synthesized, see
+    ///  `HINFO::rfc8482()`. By default all the records at the name are returned.
+    pub fn set_minimal_any(&mut self, minimal_any: bool) {
+        self.minimal_any = minimal_any;
+    }
+
+    /// Returns true if ANY queries are answered with the minimal response, see `set_minimal_any()`
+    pub fn is_minimal_any(&self) -> bool {
+        self.minimal_any
+    }
+
     /// Retrieve the Signer, which contains the private keys, for this zone
     pub fn secure_keys(&self) -> &[Signer] {
         &self.secure_keys
     }
 
+    /// Uses NSEC3 records instead of NSEC records for authenticated denial of existence
+    ///
+    /// The NSEC3 records, and the NSEC3PARAM record, are generated with these parameters when the
+    ///  zone is secured, see `secure_zone()`. Opt-out is not supported, the flag is ignored.
+    pub fn set_nsec3_params(&mut self, nsec3_params: Option<NSEC3PARAM>) {
+        self.nsec3_params = nsec3_params;
+    }
+
+    /// The NSEC3 parameters if the zone is secured with NSEC3, see `set_nsec3_params()`
+    pub fn nsec3_params(&self) -> Option<&NSEC3PARAM> {
+        self.nsec3_params.as_ref()
+    }
+
     /// Get all the records
     pub fn records(&self) -> &BTreeMap<RrKey, Arc<RecordSet>> {
         &self.records
@@ -224,11 +342,22 @@ impl InMemoryAuthority {
         and_rrsigs: bool,
         supported_algorithms: SupportedAlgorithms,
     ) -> Option<Arc<RecordSet>> {
-        let wildcard = if name.is_wildcard() {
+        // wildcards only match names which don't exist, RFC 4592
+        if name.is_wildcard() || !self.origin.zone_of(name) || self.name_exists(name) {
             return None;
-        } else {
-            name.clone().into_wildcard()
-        };
+        }
+
+        // the wildcard is at the closest encloser, the nearest existing ancestor of the name
+        let query_name: &Name = name.borrow();
+        let closest_encloser = (self.origin.num_labels()..query_name.num_labels())
+            .rev()
+            .find(|num_labels| {
+                self.name_exists(&query_name.trim_to(*num_labels as usize).into())
+            })?;
+        let wildcard = query_name
+            .trim_to(closest_encloser as usize + 1)
+            .into_wildcard()
+            .into();
 
         self.inner_lookup(&wildcard, record_type, and_rrsigs, supported_algorithms)
             // we need to change the name to the query name in the result set since this was a wildcard
@@ -244,15 +373,131 @@ impl InMemoryAuthority {
                     new_answer.add_rdata(record.rdata().clone());
                 }
 
+                // the RRSIGs of the expansion have its owner name, RFC 4035 section 3.1.3.3
                 #[cfg(feature = "dnssec")]
                 for rrsig in _rrsigs {
-                    new_answer.insert_rrsig(rrsig.clone())
+                    let mut rrsig = rrsig.clone();
+                    rrsig.set_name(Name::from(name));
+                    new_answer.insert_rrsig(rrsig)
                 }
 
                 Arc::new(new_answer)
             })
     }
 
+    /// The minimal answer to an ANY query, RFC 8482
+    ///
+    /// The CNAME at the name, or a synthesized HINFO if there are any other records at the name
+    #[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
+    fn minimal_any_lookup(&self, name: &LowerName, is_secure: bool) -> Option<Arc<RecordSet>> {
+        let start_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::min_value()));
+        let end_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::max_value()));
+
+        let mut rr_sets = self
+            .records
+            .range(&start_range_key..&end_range_key)
+            .map(|(_key, rr_set)| rr_set);
+        let first = rr_sets.next()?;
+
+        // a CNAME is the only record at its name, and is returned as is
+        if first.record_type() == RecordType::CNAME {
+            return Some(first.clone());
+        }
+
+        let mut hinfo =
+            RecordSet::with_ttl(Name::from(name), RecordType::HINFO, self.minimum_ttl());
+        hinfo.add_rdata(RData::HINFO(HINFO::rfc8482()));
+
+        // the HINFO is synthesized on demand, so it needs to be signed before it is returned
+        #[cfg(feature = "dnssec")]
+        {
+            if is_secure {
+                Self::sign_rrset(&mut hinfo, self.secure_keys(), self.minimum_ttl())
+                    // rather than failing the request, we'll just warn
+                    .map_err(|e| warn!("failed to sign HINFO record: {}", e))
+                    .ok();
+            }
+        }
+
+        Some(Arc::new(hinfo))
+    }
+
+    /// True if there are records at the name, or below it
+    ///
+    /// Names below the name follow it in the canonical order of the records, so only the first
+    ///  record at or after the name needs to be checked.
+    fn name_exists(&self, name: &LowerName) -> bool {
+        self.records
+            .range(RrKey::new(name.clone(), RecordType::ZERO)..)
+            .next()
+            .map_or(false, |(key, _)| {
+                key.name() == name || name.zone_of(key.name())
+            })
+    }
+
+    /// Returns the NS records delegating the name to another zone, if any
+    ///
+    /// Names at or below a zone cut aren't part of this zone, except for the DS records at the
+    ///  cut. If there are several cuts above the name, the one closest to the origin is returned.
+    fn zone_cut(&self, name: &LowerName, record_type: RecordType) -> Option<&Arc<RecordSet>> {
+        let is_ds = record_type == RecordType::DNSSEC(DNSSECRecordType::DS);
+        if !self.origin.zone_of(name) {
+            return None;
+        }
+
+        // the ancestors of the name below the origin, the name itself is only a cut for non-DS
+        let mut ancestors = Vec::new();
+        let mut ancestor = if is_ds {
+            name.base_name()
+        } else {
+            name.clone()
+        };
+        while ancestor.num_labels() > self.origin.num_labels() {
+            let base_name = ancestor.base_name();
+            ancestors.push(ancestor);
+            ancestor = base_name;
+        }
+
+        // walk down from the origin, the first cut is the closest to it
+        ancestors
+            .into_iter()
+            .rev()
+            .find_map(|ancestor| self.records.get(&RrKey::new(ancestor, RecordType::NS)))
+    }
+
+    /// Builds the referral to the delegated zone, with the NS records and any glue
+    fn referral(
+        &self,
+        zone_cut: &Arc<RecordSet>,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> AuthLookup {
+        let glue = zone_cut
+            .records_without_rrsigs()
+            .filter_map(|record| record.rdata().as_ns())
+            .map(LowerName::from)
+            .flat_map(|ns| {
+                vec![
+                    RrKey::new(ns.clone(), RecordType::A),
+                    RrKey::new(ns, RecordType::AAAA),
+                ]
+            })
+            .filter_map(|key| self.records.get(&key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let glue = if glue.is_empty() {
+            None
+        } else {
+            Some(LookupRecords::many(is_secure, supported_algorithms, glue))
+        };
+
+        AuthLookup::answers(
+            LookupRecords::new(is_secure, supported_algorithms, zone_cut.clone()),
+            glue,
+        )
+    }
+
     /// Search for additional records to include in the response
     ///
     /// # Arguments
@@ -351,21 +596,6 @@ impl InMemoryAuthority {
     pub fn upsert(&mut self, record: Record, serial: u32) -> bool {
         assert_eq!(self.class, record.dns_class());
 
-        #[cfg(feature = "dnssec")]
-        fn is_nsec(upsert_type: RecordType, occupied_type: RecordType) -> bool {
-            // NSEC is always allowed
-            upsert_type == RecordType::DNSSEC(DNSSECRecordType::NSEC)
-                || upsert_type == RecordType::DNSSEC(DNSSECRecordType::NSEC3)
-                || occupied_type == RecordType::DNSSEC(DNSSECRecordType::NSEC)
-                || occupied_type == RecordType::DNSSEC(DNSSECRecordType::NSEC3)
-        }
-
-        #[cfg(not(feature = "dnssec"))]
-        fn is_nsec(_upsert_type: RecordType, _occupied_type: RecordType) -> bool {
-            // TODO: we should make the DNSSec RecordTypes always visible
-            false
-        }
-
         /// returns true if an only if the label can not cooccupy space with the checked type
         #[allow(clippy::nonminimal_bool)]
         fn label_does_not_allow_multiple(
@@ -389,7 +619,8 @@ impl InMemoryAuthority {
             .range(&start_range_key..&end_range_key)
             // remember CNAME can be the only record at a particular label
             .any(|(key, _)| {
-                !is_nsec(record.record_type(), key.record_type)
+                !may_coexist_with_cname(record.record_type())
+                    && !may_coexist_with_cname(key.record_type)
                     && label_does_not_allow_multiple(
                         record.record_type(),
                         key.record_type,
@@ -402,6 +633,17 @@ impl InMemoryAuthority {
             return false;
         }
 
+        #[cfg(feature = "dnssec")]
+        {
+            if record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC3) {
+                use proto::rr::dnssec::owner_name_hash;
+
+                if let Some(hash) = owner_name_hash(record.name()) {
+                    self.nsec3_owners.insert(hash, record.name().into());
+                }
+            }
+        }
+
         let rr_key = RrKey::new(record.name().into(), record.rr_type());
         let records: &mut Arc<RecordSet> = self
             .records
@@ -423,7 +665,7 @@ impl InMemoryAuthority {
     pub fn secure_zone(&mut self) -> DnsSecResult<()> {
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
-        self.nsec_zone();
+        self.nsec_zone()?;
 
         // need to resign any records at the current serial number and bump the number.
         // first bump the serial number on the SOA, so that it is resigned with the new serial.
@@ -439,28 +681,38 @@ impl InMemoryAuthority {
         Err("DNSSEC was not enabled during compilation.")
     }
 
-    /// Dummy implementation for when DNSSEC is disabled.
+    /// (Re)generates the NSEC records, or the NSEC3 records if there are NSEC3 parameters
     #[cfg(feature = "dnssec")]
-    fn nsec_zone(&mut self) {
+    fn nsec_zone(&mut self) -> DnsSecResult<()> {
         use trust_dns::rr::rdata::NSEC;
 
         // only create nsec records for secure zones
         if self.secure_keys.is_empty() {
-            return;
+            return Ok(());
         }
-        debug!("generating nsec records: {}", self.origin);
 
         // first remove all existing nsec records
         let delete_keys: Vec<RrKey> = self
             .records
             .keys()
-            .filter(|k| k.record_type == RecordType::DNSSEC(DNSSECRecordType::NSEC))
+            .filter(|k| match k.record_type {
+                RecordType::DNSSEC(DNSSECRecordType::NSEC)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => true,
+                _ => false,
+            })
             .cloned()
             .collect();
 
         for key in delete_keys {
             self.records.remove(&key);
         }
+        self.nsec3_owners.clear();
+
+        if let Some(nsec3_params) = self.nsec3_params.clone() {
+            return self.nsec3_zone(&nsec3_params);
+        }
+        debug!("generating nsec records: {}", self.origin);
 
         // now go through and generate the nsec records
         let ttl = self.minimum_ttl();
@@ -511,6 +763,193 @@ impl InMemoryAuthority {
             let upserted = self.upsert(record, serial);
             debug_assert!(upserted);
         }
+
+        Ok(())
+    }
+
+    /// Generates the NSEC3 records, and the NSEC3PARAM record at the origin, RFC 5155 section 7.1
+    #[cfg(feature = "dnssec")]
+    fn nsec3_zone(&mut self, nsec3_params: &NSEC3PARAM) -> DnsSecResult<()> {
+        use proto::rr::dnssec::hashed_owner_name;
+        use trust_dns::rr::rdata::NSEC3;
+
+        debug!("generating nsec3 records: {}", self.origin);
+
+        let ttl = self.minimum_ttl();
+        let serial = self.serial();
+        let origin: Name = self.origin.clone().into();
+
+        let nsec3param = NSEC3PARAM::new(
+            nsec3_params.hash_algorithm(),
+            false,
+            nsec3_params.iterations(),
+            nsec3_params.salt().to_vec(),
+        );
+        let nsec3param = Record::from_rdata(
+            origin.clone(),
+            ttl,
+            RData::DNSSEC(DNSSECRData::NSEC3PARAM(nsec3param)),
+        );
+        self.upsert(nsec3param, serial);
+
+        // all names with records, and the empty non-terminals above them, but not those which are
+        //  below a zone cut. Names below a cut follow it in the canonical order of the records.
+        let mut names: BTreeMap<LowerName, Vec<RecordType>> = BTreeMap::new();
+        let mut zone_cut: Option<&LowerName> = None;
+        for key in self.records.keys() {
+            if let Some(cut) = zone_cut {
+                if cut.zone_of(key.name()) {
+                    if cut != key.name() {
+                        continue;
+                    }
+                } else {
+                    zone_cut = None;
+                }
+            }
+            if key.record_type == RecordType::NS && *key.name() != self.origin {
+                zone_cut = Some(key.name());
+            }
+
+            names
+                .entry(key.name().clone())
+                .or_insert_with(Vec::new)
+                .push(key.record_type);
+
+            let mut name = key.name().base_name();
+            while name != self.origin && self.origin.zone_of(&name) {
+                names.entry(name.clone()).or_insert_with(Vec::new);
+                name = name.base_name();
+            }
+        }
+
+        let mut hashed_names = Vec::with_capacity(names.len());
+        for (name, mut types) in names {
+            // all records are signed, except the NS records of zone cuts, which are only signed
+            //  for their DS records
+            let is_zone_cut = name != self.origin && types.contains(&RecordType::NS);
+            if !types.is_empty()
+                && (!is_zone_cut || types.contains(&RecordType::DNSSEC(DNSSECRecordType::DS)))
+            {
+                types.push(RecordType::DNSSEC(DNSSECRecordType::RRSIG));
+            }
+
+            let hash = nsec3_params.hash_algorithm().hash(
+                nsec3_params.salt(),
+                &name.into(),
+                nsec3_params.iterations(),
+            )?;
+            hashed_names.push((hash.as_ref().to_vec(), types));
+        }
+        hashed_names.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut records: Vec<Record> = Vec::with_capacity(hashed_names.len());
+        for (i, (hash, types)) in hashed_names.iter().enumerate() {
+            // the last record refers to the first
+            let (next_hash, _) = &hashed_names[(i + 1) % hashed_names.len()];
+
+            let rdata = NSEC3::new(
+                nsec3_params.hash_algorithm(),
+                false,
+                nsec3_params.iterations(),
+                nsec3_params.salt().to_vec(),
+                next_hash.clone(),
+                types.clone(),
+            );
+            records.push(Record::from_rdata(
+                hashed_owner_name(hash, &origin)?,
+                ttl,
+                RData::DNSSEC(DNSSECRData::NSEC3(rdata)),
+            ));
+        }
+
+        for record in records {
+            let upserted = self.upsert(record, serial);
+            debug_assert!(upserted);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the NSEC3 records proving the name, or the record type at the name, doesn't exist
+    ///
+    /// For an existing name this is the NSEC3 record of the name, listing the record types at the
+    ///  name. Otherwise these are the closest encloser proof, and the NSEC3 record covering the
+    ///  wildcard at the closest encloser, RFC 5155 section 7.2.
+    #[cfg(feature = "dnssec")]
+    fn nsec3_records(
+        &self,
+        name: &LowerName,
+        nsec3_params: &NSEC3PARAM,
+    ) -> DnsSecResult<Vec<Arc<RecordSet>>> {
+        use proto::rr::dnssec::hashed_owner_name;
+
+        let origin: Name = self.origin.clone().into();
+        let nsec3_type = RecordType::DNSSEC(DNSSECRecordType::NSEC3);
+
+        let hash = |name: &Name| -> DnsSecResult<Vec<u8>> {
+            let hash = nsec3_params.hash_algorithm().hash(
+                nsec3_params.salt(),
+                name,
+                nsec3_params.iterations(),
+            )?;
+            Ok(hash.as_ref().to_vec())
+        };
+
+        let matching = |name: &Name| -> DnsSecResult<Option<&Arc<RecordSet>>> {
+            let owner = hashed_owner_name(&hash(name)?, &origin)?;
+            Ok(self.records.get(&RrKey::new(owner.into(), nsec3_type)))
+        };
+
+        // the closest hashed owner before the hash covers it, the last one wraps to the beginning
+        //  of the zone
+        let covering = |name: &Name| -> DnsSecResult<Option<&Arc<RecordSet>>> {
+            let hash = hash(name)?;
+            if self.nsec3_owners.contains_key(&hash) {
+                return Ok(None);
+            }
+
+            Ok(self
+                .nsec3_owners
+                .range(..hash)
+                .next_back()
+                .or_else(|| self.nsec3_owners.iter().next_back())
+                .and_then(|(_, owner)| self.records.get(&RrKey::new(owner.clone(), nsec3_type))))
+        };
+
+        let name: &Name = name.borrow();
+        if let Some(nsec3) = matching(name)? {
+            return Ok(vec![nsec3.clone()]);
+        }
+
+        // the closest encloser is the nearest ancestor with an NSEC3 record
+        for num_labels in (origin.num_labels()..name.num_labels()).rev() {
+            let closest_encloser = match matching(&name.trim_to(num_labels as usize))? {
+                Some(closest_encloser) => closest_encloser,
+                None => continue,
+            };
+
+            // the next closer name is one label longer, and must not exist
+            let next_closer = name.trim_to(num_labels as usize + 1);
+            let wildcard = next_closer.clone().into_wildcard();
+            let wildcard = match matching(&wildcard)? {
+                Some(wildcard) => Some(wildcard),
+                None => covering(&wildcard)?,
+            };
+
+            let mut proofs: Vec<Arc<RecordSet>> = Vec::with_capacity(3);
+            for proof in vec![Some(closest_encloser), covering(&next_closer)?, wildcard]
+                .into_iter()
+                .flatten()
+            {
+                if !proofs.contains(proof) {
+                    proofs.push(proof.clone());
+                }
+            }
+
+            return Ok(proofs);
+        }
+
+        Ok(vec![])
     }
 
     /// Signs an RecordSet, and stores the RRSIGs in the RecordSet
@@ -522,20 +961,18 @@ impl InMemoryAuthority {
     /// * `rr_set` - RecordSet to sign
     /// * `secure_keys` - Set of keys to use to sign the RecordSet, see `self.signers()`
     /// * `zone_ttl` - the zone TTL, see `self.minimum_ttl()`
-    /// * `zone_class` - DNSClass of the zone, see `self.zone_class()`
     #[cfg(feature = "dnssec")]
     fn sign_rrset(
         rr_set: &mut RecordSet,
         secure_keys: &[Signer],
         zone_ttl: u32,
-        zone_class: DNSClass,
     ) -> DnsSecResult<()> {
         use chrono::Utc;
-        use trust_dns::rr::dnssec::tbs;
-        use trust_dns::rr::rdata::SIG;
 
-        let inception = Utc::now();
+        let inception = Utc::now().timestamp() as u32;
 
+        // the signature covers the TTL, which must be the same for all records
+        rr_set.normalize_ttl();
         rr_set.clear_rrsigs();
 
         let rrsig_temp = Record::with(
@@ -544,6 +981,13 @@ impl InMemoryAuthority {
             zone_ttl,
         );
 
+        // TODO: this is a nasty clone... the issue is that the vec
+        //  from records is of Vec<&R>, but we really want &[R]
+        let records = rr_set
+            .records_without_rrsigs()
+            .cloned()
+            .collect::<Vec<Record>>();
+
         for signer in secure_keys {
             debug!(
                 "signing rr_set: {}, {} with: {}",
@@ -552,39 +996,8 @@ impl InMemoryAuthority {
                 signer.algorithm(),
             );
 
-            let expiration = inception + signer.sig_duration();
-
-            let tbs = tbs::rrset_tbs(
-                rr_set.name(),
-                zone_class,
-                rr_set.name().num_labels(),
-                rr_set.record_type(),
-                signer.algorithm(),
-                rr_set.ttl(),
-                expiration.timestamp() as u32,
-                inception.timestamp() as u32,
-                signer.calculate_key_tag()?,
-                signer.signer_name(),
-                // TODO: this is a nasty clone... the issue is that the vec
-                //  from records is of Vec<&R>, but we really want &[R]
-                &rr_set
-                    .records_without_rrsigs()
-                    .cloned()
-                    .collect::<Vec<Record>>(),
-            );
-
-            // TODO, maybe chain these with some ETL operations instead?
-            let tbs = match tbs {
-                Ok(tbs) => tbs,
-                Err(err) => {
-                    error!("could not serialize rrset to sign: {}", err);
-                    continue;
-                }
-            };
-
-            let signature = signer.sign(&tbs);
-            let signature = match signature {
-                Ok(signature) => signature,
+            let rdata = match signer.sign_rrset(&records, inception) {
+                Ok(rdata) => rdata,
                 Err(err) => {
                     error!("could not sign rrset: {}", err);
                     continue;
@@ -592,26 +1005,7 @@ impl InMemoryAuthority {
             };
 
             let mut rrsig = rrsig_temp.clone();
-            rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
-                // type_covered: RecordType,
-                rr_set.record_type(),
-                // algorithm: Algorithm,
-                signer.algorithm(),
-                // num_labels: u8,
-                rr_set.name().num_labels(),
-                // original_ttl: u32,
-                rr_set.ttl(),
-                // sig_expiration: u32,
-                expiration.timestamp() as u32,
-                // sig_inception: u32,
-                inception.timestamp() as u32,
-                // key_tag: u16,
-                signer.calculate_key_tag()?,
-                // signer_name: Name,
-                signer.signer_name().clone(),
-                // sig: Vec<u8>
-                signature,
-            ))));
+            rrsig.set_rdata(rdata);
 
             rr_set.insert_rrsig(rrsig);
         }
@@ -625,6 +1019,7 @@ impl InMemoryAuthority {
         debug!("signing zone: {}", self.origin);
 
         let minimum_ttl = self.minimum_ttl();
+        let origin = &self.origin;
         let secure_keys = &self.secure_keys;
         let records = &mut self.records;
 
@@ -633,11 +1028,27 @@ impl InMemoryAuthority {
             warn!("attempt to sign_zone for dnssec, but no keys available!")
         }
 
-        // sign all record_sets, as of 0.12.1 this includes DNSKEY
-        for rr_set_orig in records.values_mut() {
+        // sign all record_sets, as of 0.12.1 this includes DNSKEY, except the NS records of zone
+        //  cuts and the glue below them, which aren't authoritative, RFC 4035 section 2.2
+        let mut zone_cut: Option<&LowerName> = None;
+        for (key, rr_set_orig) in records.iter_mut() {
+            if let Some(cut) = zone_cut {
+                if cut.zone_of(key.name()) {
+                    if cut != key.name() {
+                        continue;
+                    }
+                } else {
+                    zone_cut = None;
+                }
+            }
+            if key.record_type == RecordType::NS && key.name() != origin {
+                zone_cut = Some(key.name());
+                continue;
+            }
+
             // because the rrset is an Arc, it must be cloned before mutated
             let rr_set = Arc::make_mut(rr_set_orig);
-            Self::sign_rrset(rr_set, secure_keys, minimum_ttl, self.class)?;
+            Self::sign_rrset(rr_set, secure_keys, minimum_ttl)?;
         }
 
         Ok(())
@@ -693,6 +1104,44 @@ fn maybe_next_name(
     }
 }
 
+/// Returns true if records of the type may share the name of a CNAME record
+///
+/// The signatures of the CNAME and the denial of existence records are the exceptions to the "no
+///  other data" rule of [RFC 1034](https://tools.ietf.org/html/rfc1034#section-3.6.2).
+#[cfg(feature = "dnssec")]
+fn may_coexist_with_cname(rr_type: RecordType) -> bool {
+    rr_type == RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+        || rr_type == RecordType::DNSSEC(DNSSECRecordType::NSEC)
+        || rr_type == RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn may_coexist_with_cname(_rr_type: RecordType) -> bool {
+    // TODO: we should make the DNSSec RecordTypes always visible
+    false
+}
+
+/// Returns the names with a CNAME record and other data
+fn cname_conflicts(records: &BTreeMap<RrKey, RecordSet>) -> Vec<&LowerName> {
+    records
+        .keys()
+        .filter(|key| key.record_type == RecordType::CNAME)
+        .filter(|cname| {
+            let start_range_key =
+                RrKey::new(cname.name.clone(), RecordType::Unknown(u16::min_value()));
+            let end_range_key =
+                RrKey::new(cname.name.clone(), RecordType::Unknown(u16::max_value()));
+
+            records
+                .range(start_range_key..end_range_key)
+                .any(|(key, _)| {
+                    key.record_type != RecordType::CNAME && !may_coexist_with_cname(key.record_type)
+                })
+        })
+        .map(|cname| &cname.name)
+        .collect()
+}
+
 impl Authority for InMemoryAuthority {
     type Lookup = AuthLookup;
     type LookupFuture = FutureResult<Self::Lookup, LookupError>;
@@ -707,6 +1156,15 @@ impl Authority for InMemoryAuthority {
         self.allow_axfr
     }
 
+    /// Return true if AXFR is allowed, and the peer is one of the transfer peers, if any
+    fn is_transfer_allowed(&self, peer: IpAddr) -> bool {
+        self.allow_axfr
+            && self
+                .transfer_peers
+                .as_ref()
+                .map_or(true, |peers| peers.contains(&peer))
+    }
+
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -794,9 +1252,25 @@ impl Authority for InMemoryAuthority {
         is_secure: bool,
         supported_algorithms: SupportedAlgorithms,
     ) -> Self::LookupFuture {
+        // the delegated zone is authoritative for these names, refer to its name servers
+        if query_type != RecordType::AXFR {
+            if let Some(zone_cut) = self.zone_cut(name, query_type) {
+                let referral = self.referral(zone_cut, is_secure, supported_algorithms);
+                return Err(LookupError::Referral(Box::new(referral))).into_future();
+            }
+        }
+
         // Collect the records from each rr_set
         let (result, additionals): (LookupResult<LookupRecords>, Option<LookupRecords>) =
             match query_type {
+                RecordType::ANY if self.minimal_any => {
+                    let answer = self
+                        .minimal_any_lookup(name, is_secure)
+                        .map_or(Err(LookupError::from(ResponseCode::NXDomain)), |rr_set| {
+                            Ok(LookupRecords::new(is_secure, supported_algorithms, rr_set))
+                        });
+                    (answer, None)
+                }
                 RecordType::AXFR | RecordType::ANY => {
                     let result = AnyRecords::new(
                         is_secure,
@@ -885,7 +1359,6 @@ impl Authority for InMemoryAuthority {
                                             &mut new_answer,
                                             self.secure_keys(),
                                             self.minimum_ttl(),
-                                            self.class(),
                                         )
                                         // rather than failing the request, we'll just warn
                                         .map_err(|e| warn!("failed to sign ANAME record: {}", e))
@@ -930,11 +1403,7 @@ impl Authority for InMemoryAuthority {
         // TODO: can we get rid of this?
         let result = match result {
             Err(LookupError::ResponseCode(ResponseCode::NXDomain)) => {
-                if self
-                    .records
-                    .keys()
-                    .any(|key| key.name() == name || name.zone_of(key.name()))
-                {
+                if self.name_exists(name) {
                     return Err(LookupError::NameExists).into_future();
                 } else {
                     return Err(LookupError::from(ResponseCode::NXDomain)).into_future();
@@ -1019,6 +1488,19 @@ impl Authority for InMemoryAuthority {
         is_secure: bool,
         supported_algorithms: SupportedAlgorithms,
     ) -> Self::LookupFuture {
+        if let Some(ref nsec3_params) = self.nsec3_params {
+            return future::result(
+                self.nsec3_records(name, nsec3_params)
+                    .map(|proofs| {
+                        LookupRecords::many(is_secure, supported_algorithms, proofs).into()
+                    })
+                    .map_err(|e| {
+                        warn!("failed to find nsec3 records for {}: {}", name, e);
+                        LookupError::from(ResponseCode::ServFail)
+                    }),
+            );
+        }
+
         fn is_nsec_rrset(rr_set: &RecordSet) -> bool {
             rr_set.record_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC)
         }
@@ -1160,7 +1642,7 @@ impl Authority for InMemoryAuthority {
     fn secure_zone(&mut self) -> DnsSecResult<()> {
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
-        self.nsec_zone();
+        self.nsec_zone()?;
 
         // need to resign any records at the current serial number and bump the number.
         // first bump the serial number on the SOA, so that it is resigned with the new serial.
@@ -1176,3 +1658,191 @@ impl Authority for InMemoryAuthority {
         Err("DNSSEC was not enabled during compilation.".into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use trust_dns::rr::rdata::TXT;
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn records(records: Vec<Record>) -> BTreeMap<RrKey, RecordSet> {
+        let soa = SOA::new(
+            name("ns.example.com."),
+            name("root.example.com."),
+            1,
+            28800,
+            7200,
+            604_800,
+            86400,
+        );
+
+        let soa = Record::from_rdata(name("example.com."), 86400, RData::SOA(soa));
+
+        let mut rrsets = BTreeMap::new();
+        for record in Some(soa).into_iter().chain(records) {
+            rrsets
+                .entry(RrKey::new(LowerName::new(record.name()), record.rr_type()))
+                .or_insert_with(|| RecordSet::new(record.name(), record.rr_type(), 1))
+                .insert(record, 1);
+        }
+
+        rrsets
+    }
+
+    #[test]
+    fn test_cname_and_other_data() {
+        let records = records(vec![
+            Record::from_rdata(
+                name("www.example.com."),
+                86400,
+                RData::CNAME(name("example.com.")),
+            ),
+            Record::from_rdata(
+                name("www.example.com."),
+                86400,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            ),
+            Record::from_rdata(
+                name("ftp.example.com."),
+                86400,
+                RData::CNAME(name("www.example.com.")),
+            ),
+        ]);
+
+        let error = InMemoryAuthority::new(name("example.com."), records, ZoneType::Master, false)
+            .err()
+            .expect("zone with a conflicting CNAME loaded");
+        assert!(error.contains("www.example.com."), "{}", error);
+        assert!(!error.contains("ftp.example.com."), "{}", error);
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_cname_and_rrsig() {
+        use trust_dns::rr::dnssec::Algorithm;
+        use trust_dns::rr::rdata::sig::SIG;
+
+        let sig = SIG::new(
+            RecordType::CNAME,
+            Algorithm::RSASHA256,
+            3,
+            86400,
+            5,
+            0,
+            2,
+            name("example.com."),
+            vec![],
+        );
+        let mut rrsig = Record::from_rdata(
+            name("www.example.com."),
+            86400,
+            RData::DNSSEC(DNSSECRData::SIG(sig)),
+        );
+        rrsig.set_rr_type(RecordType::DNSSEC(DNSSECRecordType::RRSIG));
+
+        let records = records(vec![
+            Record::from_rdata(
+                name("www.example.com."),
+                86400,
+                RData::CNAME(name("example.com.")),
+            ),
+            rrsig,
+        ]);
+
+        assert!(
+            InMemoryAuthority::new(name("example.com."), records, ZoneType::Master, false).is_ok()
+        );
+    }
+
+    fn any_authority() -> InMemoryAuthority {
+        let records = records(vec![
+            Record::from_rdata(
+                name("www.example.com."),
+                86400,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            ),
+            Record::from_rdata(
+                name("www.example.com."),
+                86400,
+                RData::TXT(TXT::new(vec!["www".to_string()])),
+            ),
+            Record::from_rdata(
+                name("alias.example.com."),
+                86400,
+                RData::CNAME(name("www.example.com.")),
+            ),
+        ]);
+
+        InMemoryAuthority::new(name("example.com."), records, ZoneType::Master, false)
+            .expect("failed to load zone")
+    }
+
+    fn any_lookup(authority: &InMemoryAuthority, query_name: &str) -> LookupResult<Vec<Record>> {
+        authority
+            .lookup(
+                &LowerName::from(name(query_name)),
+                RecordType::ANY,
+                false,
+                SupportedAlgorithms::new(),
+            )
+            .wait()
+            .map(|lookup| lookup.iter().cloned().collect())
+    }
+
+    #[test]
+    fn test_any_lookup() {
+        let authority = any_authority();
+        assert!(!authority.is_minimal_any());
+
+        let records = any_lookup(&authority, "www.example.com.").unwrap();
+        let mut record_types = records.iter().map(Record::rr_type).collect::<Vec<_>>();
+        record_types.sort();
+        assert_eq!(record_types, vec![RecordType::A, RecordType::TXT]);
+
+        let records = any_lookup(&authority, "alias.example.com.").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].rr_type(), RecordType::CNAME);
+    }
+
+    #[test]
+    fn test_minimal_any_lookup() {
+        use trust_dns::rr::rdata::HINFO;
+
+        let mut authority = any_authority();
+        authority.set_minimal_any(true);
+
+        let records = any_lookup(&authority, "www.example.com.").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(*records[0].name(), name("www.example.com."));
+        assert_eq!(records[0].ttl(), 86400);
+        assert_eq!(*records[0].rdata(), RData::HINFO(HINFO::rfc8482()));
+
+        // the CNAME is returned instead of the synthesized HINFO
+        let records = any_lookup(&authority, "alias.example.com.").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(*records[0].rdata(), RData::CNAME(name("www.example.com.")));
+
+        assert!(any_lookup(&authority, "none.example.com.")
+            .unwrap_err()
+            .is_nx_domain());
+
+        // other types are unaffected
+        let lookup = authority
+            .lookup(
+                &LowerName::from(name("www.example.com.")),
+                RecordType::TXT,
+                false,
+                SupportedAlgorithms::new(),
+            )
+            .wait()
+            .unwrap();
+        assert_eq!(lookup.iter().count(), 1);
+    }
+}
@@ -7,6 +7,7 @@
 
 //! All authority related types
 
+use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -844,6 +845,11 @@ impl Authority for SqliteAuthority {
         self.in_memory.is_axfr_allowed()
     }
 
+    /// Return true if zone transfers to the peer are allowed
+    fn is_transfer_allowed(&self, peer: IpAddr) -> bool {
+        self.in_memory.is_transfer_allowed(peer)
+    }
+
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
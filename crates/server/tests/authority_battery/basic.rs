@@ -6,7 +6,7 @@ use futures::future::Future;
 use trust_dns::op::{Message, Query};
 use trust_dns::rr::dnssec::SupportedAlgorithms;
 use trust_dns::rr::{Name, RData, Record, RecordType};
-use trust_dns_server::authority::{AuthLookup, Authority, MessageRequest};
+use trust_dns_server::authority::{AuthLookup, Authority, LookupError, MessageRequest};
 
 pub fn test_a_lookup<A: Authority<Lookup = AuthLookup>>(authority: A) {
     let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
@@ -539,6 +539,76 @@ pub fn test_wildcard_chain<A: Authority<Lookup = AuthLookup>>(authority: A) {
     assert_eq!(Ipv4Addr::new(127, 0, 0, 1), *a);
 }
 
+pub fn test_wildcard_closest_encloser<A: Authority<Lookup = AuthLookup>>(authority: A) {
+    // deep.wildcard.example.com. doesn't exist, so *.wildcard.example.com. matches below it
+    let query = Query::query(
+        Name::from_str("www.deep.wildcard.example.com.").unwrap(),
+        RecordType::CNAME,
+    );
+    let lookup = authority
+        .search(&query.into(), false, SupportedAlgorithms::new())
+        .wait()
+        .expect("lookup of www.deep.wildcard.example.com. failed");
+
+    let cname = lookup
+        .into_iter()
+        .next()
+        .expect("CNAME record not found in authority");
+    assert_eq!(
+        *cname.name(),
+        Name::from_str("www.deep.wildcard.example.com.").unwrap()
+    );
+    assert_eq!(
+        *cname.rdata().as_cname().expect("wrong rdata type returned"),
+        Name::from_str("www.example.com.").unwrap()
+    );
+
+    // the wildcard doesn't match names which exist, www.example.com. has no TXT records
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::TXT);
+    let lookup = authority
+        .search(&query.into(), false, SupportedAlgorithms::new())
+        .wait()
+        .unwrap_err();
+
+    assert!(lookup.is_name_exists());
+}
+
+pub fn test_referral<A: Authority<Lookup = AuthLookup>>(authority: A) {
+    // names at and below the zone cut are referred to the delegated zone
+    for name in &["sub.example.com.", "www.sub.example.com."] {
+        let query = Query::query(Name::from_str(name).unwrap(), RecordType::A);
+        let error = authority
+            .search(&query.into(), false, SupportedAlgorithms::new())
+            .wait()
+            .unwrap_err();
+
+        let mut referral = match error {
+            LookupError::Referral(referral) => referral,
+            e => panic!("expected a referral for {}: {}", name, e),
+        };
+
+        let glue = referral.take_additionals().expect("no glue in referral");
+        let ns = referral
+            .into_iter()
+            .next()
+            .expect("NS record not found in referral");
+        assert_eq!(*ns.name(), Name::from_str("sub.example.com.").unwrap());
+        assert_eq!(
+            *ns.rdata().as_ns().expect("Not an NS record"),
+            Name::from_str("ns.sub.example.com.").unwrap()
+        );
+
+        let a = glue
+            .into_iter()
+            .next()
+            .expect("A record not found")
+            .rdata()
+            .as_a()
+            .expect("Not an A record");
+        assert_eq!(Ipv4Addr::new(127, 0, 0, 4), *a);
+    }
+}
+
 pub fn test_srv<A: Authority<Lookup = AuthLookup>>(authority: A) {
     let query = Query::query(
         Name::from_str("server.example.com.").unwrap(),
@@ -627,6 +697,8 @@ macro_rules! basic_battery {
                     test_dots_in_name,
                     test_wildcard,
                     test_wildcard_chain,
+                    test_wildcard_closest_encloser,
+                    test_referral,
                     test_srv,
                 );
             }
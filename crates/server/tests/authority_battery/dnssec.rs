@@ -1,15 +1,17 @@
 #![cfg(feature = "dnssec")]
 
+use std::ops::DerefMut;
 use std::str::FromStr;
 
 use futures::Future;
 
 use trust_dns::op::Query;
-use trust_dns::proto::rr::dnssec::rdata::{DNSSECRecordType, DNSKEY};
+use trust_dns::proto::rr::dnssec::rdata::{DNSSECRecordType, DNSKEY, NSEC3PARAM};
 use trust_dns::proto::xfer;
-use trust_dns::rr::dnssec::{Algorithm, SupportedAlgorithms, Verifier};
+use trust_dns::rr::dnssec::{Algorithm, Nsec3HashAlgorithm, SupportedAlgorithms, Verifier};
 use trust_dns::rr::{DNSClass, Name, Record, RecordType};
 use trust_dns_server::authority::{AuthLookup, Authority};
+use trust_dns_server::store::in_memory::InMemoryAuthority;
 
 pub fn test_a_lookup<A: Authority<Lookup = AuthLookup>>(authority: A, keys: &[DNSKEY]) {
     let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
@@ -296,6 +298,218 @@ pub fn test_rfc_6975_supported_algorithms<A: Authority<Lookup = AuthLookup>>(
     }
 }
 
+fn secure_with_nsec3<A>(authority: &mut A)
+where
+    A: Authority<Lookup = AuthLookup> + DerefMut<Target = InMemoryAuthority>,
+{
+    authority.set_nsec3_params(Some(NSEC3PARAM::new(
+        Nsec3HashAlgorithm::SHA1,
+        false,
+        12,
+        vec![0xAA, 0xBB, 0xCC, 0xDD],
+    )));
+    Authority::secure_zone(authority).expect("failed to sign zone");
+}
+
+fn nsec3_records<A: Authority<Lookup = AuthLookup>>(
+    authority: &A,
+    name: &Name,
+) -> (Vec<Record>, Vec<Record>) {
+    let lookup = authority
+        .get_nsec_records(&name.clone().into(), true, SupportedAlgorithms::new())
+        .wait()
+        .unwrap();
+
+    let (nsec3_records, other_records): (Vec<_>, Vec<_>) = lookup
+        .into_iter()
+        .cloned()
+        .partition(|r| r.record_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC3));
+
+    // the NSEC records were replaced
+    assert!(other_records
+        .iter()
+        .all(|r| r.record_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG)));
+
+    (nsec3_records, other_records)
+}
+
+pub fn test_nsec3_nodata<A>(mut authority: A, keys: &[DNSKEY])
+where
+    A: Authority<Lookup = AuthLookup> + DerefMut<Target = InMemoryAuthority>,
+{
+    secure_with_nsec3(&mut authority);
+
+    // www has records, but not of the type TXT
+    let name = Name::from_str("www.example.com.").unwrap();
+    let (nsec3_records, rrsig_records) = nsec3_records(&authority, &name);
+
+    println!("nsec3_records: {:?}", nsec3_records);
+
+    // the matching record for www.example.com
+    assert_eq!(nsec3_records.len(), 1);
+    verify(&nsec3_records, &rrsig_records, keys);
+
+    let nsec3s: Vec<&Record> = nsec3_records.iter().collect();
+
+    let query = Query::query(name.clone(), RecordType::TXT);
+    assert!(xfer::secure_dns_handle::verify_nsec3(
+        &query,
+        &Name::from_str("example.com.").unwrap(),
+        &nsec3s
+    ));
+
+    // the A record exists, which can't be denied
+    let query = Query::query(name, RecordType::A);
+    assert!(!xfer::secure_dns_handle::verify_nsec3(
+        &query,
+        &Name::from_str("example.com.").unwrap(),
+        &nsec3s
+    ));
+
+    // the parameters are published at the apex
+    let query = Query::query(
+        Name::from_str("example.com.").unwrap(),
+        RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+    );
+    let lookup = authority
+        .search(&query.into(), false, SupportedAlgorithms::new())
+        .wait()
+        .unwrap();
+    let nsec3param = lookup.iter().next().expect("no NSEC3PARAM");
+    let nsec3param = nsec3param
+        .rdata()
+        .as_dnssec()
+        .and_then(|rdata| rdata.as_nsec3param())
+        .expect("not NSEC3PARAM");
+    assert_eq!(nsec3param.iterations(), 12);
+    assert_eq!(nsec3param.salt(), &[0xAA, 0xBB, 0xCC, 0xDD]);
+}
+
+pub fn test_nsec3_nxdomain<A>(mut authority: A, _keys: &[DNSKEY])
+where
+    A: Authority<Lookup = AuthLookup> + DerefMut<Target = InMemoryAuthority>,
+{
+    secure_with_nsec3(&mut authority);
+
+    for name in &[
+        "aaa.example.com.",
+        "zzz.example.com.",
+        "a.b.ccc.example.com.",
+    ] {
+        let name = Name::from_str(name).unwrap();
+        let (nsec3_records, _rrsig_records) = nsec3_records(&authority, &name);
+
+        println!("nsec3_records: {:?}", nsec3_records);
+
+        // the closest encloser, the next closer name and the wildcard, some may be the same
+        assert!(!nsec3_records.is_empty());
+        assert!(nsec3_records.len() <= 3);
+
+        let nsec3s: Vec<&Record> = nsec3_records.iter().collect();
+
+        let query = Query::query(name, RecordType::A);
+        assert!(xfer::secure_dns_handle::verify_nsec3(
+            &query,
+            &Name::from_str("example.com.").unwrap(),
+            &nsec3s
+        ));
+    }
+}
+
+pub fn test_nsec3_zone_cut<A>(mut authority: A, _keys: &[DNSKEY])
+where
+    A: Authority<Lookup = AuthLookup> + DerefMut<Target = InMemoryAuthority>,
+{
+    use trust_dns::proto::rr::dnssec::hashed_owner_name;
+    use trust_dns::proto::rr::dnssec::rdata::{DNSSECRData, DS};
+    use trust_dns::rr::dnssec::DigestType;
+    use trust_dns::rr::{RData, RrKey};
+
+    // a secure delegation, with a DS record, next to the insecure sub.example.com
+    let secure = Name::from_str("secure.example.com.").unwrap();
+    authority.upsert(
+        Record::from_rdata(
+            secure.clone(),
+            86400,
+            RData::NS(Name::from_str("ns.secure.example.com.").unwrap()),
+        ),
+        0,
+    );
+    authority.upsert(
+        Record::from_rdata(
+            secure,
+            86400,
+            RData::DNSSEC(DNSSECRData::DS(DS::new(
+                1,
+                Algorithm::RSASHA256,
+                DigestType::SHA256,
+                vec![0; 32],
+            ))),
+        ),
+        0,
+    );
+
+    secure_with_nsec3(&mut authority);
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let nsec3_types = |name: &str| {
+        let hash = Nsec3HashAlgorithm::SHA1
+            .hash(
+                &[0xAA, 0xBB, 0xCC, 0xDD],
+                &Name::from_str(name).unwrap(),
+                12,
+            )
+            .unwrap();
+        let owner = hashed_owner_name(hash.as_ref(), &origin).unwrap();
+        authority
+            .records()
+            .get(&RrKey::new(
+                owner.into(),
+                RecordType::DNSSEC(DNSSECRecordType::NSEC3),
+            ))
+            .and_then(|rrset| rrset.records_without_rrsigs().next())
+            .and_then(|record| record.rdata().as_dnssec())
+            .and_then(|rdata| rdata.as_nsec3())
+            .map(|nsec3| nsec3.type_bit_maps().to_vec())
+    };
+    let rrsig = RecordType::DNSSEC(DNSSECRecordType::RRSIG);
+
+    // the delegation is part of the zone, the glue below it is not
+    let sub = nsec3_types("sub.example.com.").expect("no NSEC3 for sub.example.com.");
+    assert!(nsec3_types("ns.sub.example.com.").is_none());
+    // names after the zone cut in canonical order are still in the zone
+    assert!(nsec3_types("this.has.dots.example.com.").is_some());
+    let www = nsec3_types("www.example.com.").expect("no NSEC3 for www.example.com.");
+
+    // the insecure delegation has no signed records, the secure one has its DS signed
+    assert!(sub.contains(&RecordType::NS));
+    assert!(!sub.contains(&rrsig));
+    let secure = nsec3_types("secure.example.com.").expect("no NSEC3 for secure.example.com.");
+    assert!(secure.contains(&rrsig));
+    assert!(www.contains(&rrsig));
+
+    // and neither the NS records of the delegations, nor the glue, are signed
+    let rrsigs = |name: &str, record_type: RecordType| {
+        authority
+            .records()
+            .get(&RrKey::new(
+                Name::from_str(name).unwrap().into(),
+                record_type,
+            ))
+            .map(|rrset| rrset.rrsigs().len())
+    };
+    assert_eq!(rrsigs("sub.example.com.", RecordType::NS), Some(0));
+    assert_eq!(rrsigs("ns.sub.example.com.", RecordType::A), Some(0));
+    assert_eq!(rrsigs("secure.example.com.", RecordType::NS), Some(0));
+    assert_ne!(
+        rrsigs(
+            "secure.example.com.",
+            RecordType::DNSSEC(DNSSECRecordType::DS)
+        ),
+        Some(0)
+    );
+}
+
 pub fn verify(records: &[Record], rrsig_records: &[Record], keys: &[DNSKEY]) {
     let record_name = records.first().unwrap().name();
     let record_type = records.first().unwrap().record_type();
@@ -443,6 +657,9 @@ macro_rules! dnssec_battery {
                     test_nsec_nxdomain_middle,
                     test_nsec_nxdomain_wraps_end,
                     test_rfc_6975_supported_algorithms,
+                    test_nsec3_nodata,
+                    test_nsec3_nxdomain,
+                    test_nsec3_zone_cut,
                 );
             }
         }
@@ -253,6 +253,63 @@ tls_listen_port = 8853
     );
 }
 
+#[test]
+fn test_parse_chaos() {
+    // defaults
+    let config: Config = "".parse().unwrap();
+
+    assert_eq!(config.get_version(), None);
+    assert_eq!(config.get_identity(), None);
+
+    let config: Config = "
+version = \"Trust-DNS\"
+identity = \"ns1.example.com\"
+  "
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.get_version(), Some("Trust-DNS"));
+    assert_eq!(config.get_identity(), Some("ns1.example.com"));
+}
+
+#[test]
+#[cfg(feature = "dnssec")]
+fn test_parse_tsig_keys() {
+    use trust_dns::rr::rdata::TsigAlgorithm;
+    use trust_dns::rr::Name;
+
+    // defaults
+    let config: Config = "".parse().unwrap();
+    assert!(config.get_tsig_keys().is_empty());
+
+    let config: Config = "
+[[tsig_keys]]
+key_name = \"transfer-key.example.com.\"
+algorithm = \"hmac-sha256\"
+key_path = \"transfer-key.secret\"
+
+[[tsig_keys]]
+key_name = \"transfer-key\"
+algorithm = \"hmac-sha3\"
+key_path = \"/etc/transfer-key.secret\"
+  "
+    .parse()
+    .unwrap();
+
+    let keys = config.get_tsig_keys();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(
+        keys[0].key_name().unwrap(),
+        Name::parse("transfer-key.example.com.", None).unwrap()
+    );
+    assert_eq!(keys[0].algorithm().unwrap(), TsigAlgorithm::HmacSha256);
+    assert_eq!(keys[0].key_path(), Path::new("transfer-key.secret"));
+
+    // not fully qualified, and unknown algorithm
+    assert!(keys[1].key_name().is_err());
+    assert!(keys[1].algorithm().is_err());
+}
+
 fn test_config(path: &str) {
     let path = PathBuf::from("tests/named_test_configs")
         .join(path)
@@ -496,3 +496,135 @@ a       A       127.0.0.1
 
     assert!(InMemoryAuthority::new(origin, records, ZoneType::Master, false).is_ok());
 }
+
+#[test]
+fn test_parse_record() {
+    let origin = Name::from_str("example.com.").unwrap();
+
+    for line in &[
+        "www.example.com. 3600 IN A 192.0.2.1",
+        "example.com. 3600 IN MX 10 mail.example.com.",
+    ] {
+        let record = Parser::parse_record(line, Some(&origin), None, None)
+            .unwrap_or_else(|e| panic!("failed to parse {}: {}", line, e));
+
+        // the fields of the Display are separated by tabs
+        assert_eq!(record.to_string(), line.replacen(" ", "\t", 4));
+        assert_eq!(
+            Parser::parse_record(&record.to_string(), None, None, None).unwrap(),
+            record
+        );
+    }
+
+    // the defaults apply to fields which are not specified
+    let record = Parser::parse_record(
+        "mail MX 20 mx",
+        Some(&origin),
+        Some(300),
+        Some(DNSClass::IN),
+    )
+    .expect("failed to parse MX");
+    assert_eq!(*record.name(), Name::from_str("mail.example.com.").unwrap());
+    assert_eq!(record.ttl(), 300);
+    assert_eq!(record.dns_class(), DNSClass::IN);
+    assert_eq!(
+        *record.rdata(),
+        RData::MX(rdata::MX::new(
+            20,
+            Name::from_str("mx.example.com.").unwrap()
+        ))
+    );
+
+    // without a default TTL, and more than one record
+    assert!(Parser::parse_record("www IN A 192.0.2.1", Some(&origin), None, None).is_err());
+    assert!(Parser::parse_record(
+        "www 60 IN A 192.0.2.1\nwww 60 IN A 192.0.2.2",
+        Some(&origin),
+        None,
+        None
+    )
+    .is_err());
+}
+
+#[test]
+fn test_parse_txt_octets() {
+    let origin = Name::from_str("example.com.").unwrap();
+
+    let record = Parser::parse_record(
+        r#"txt 3600 IN TXT "\255\000" "a""#,
+        Some(&origin),
+        None,
+        None,
+    )
+    .expect("failed to parse TXT");
+    match *record.rdata() {
+        RData::TXT(ref txt) => {
+            assert_eq!(&*txt.txt_data()[0], &[0xFF, 0x00]);
+            assert_eq!(&*txt.txt_data()[1], b"a");
+        }
+        ref rdata => panic!("expected TXT: {:?}", rdata),
+    }
+
+    // the octets are escaped in the Display
+    assert_eq!(
+        Parser::parse_record(&record.to_string(), None, None, None).unwrap(),
+        record
+    );
+}
+
+#[test]
+fn test_generate() {
+    let origin = Name::from_str("2.0.192.in-addr.arpa.").unwrap();
+    let lexer = Lexer::new(
+        r###"
+$TTL 3600
+@   IN  SOA     ns.example.com.   hostmaster.example.com. ( 1 7200 600 3600000 60 )
+$GENERATE 1-10 $ PTR host-${0,3,d}.example.com.
+$GENERATE 176-192/16 ${0,2,x} IN PTR hex-$.example.com.
+"###,
+    );
+
+    let (_, records) = Parser::new()
+        .parse(lexer, Some(origin.clone()))
+        .expect("failed to parse");
+
+    let ptrs: Vec<&RecordSet> = records
+        .values()
+        .filter(|rrset| rrset.record_type() == RecordType::PTR)
+        .collect();
+    assert_eq!(ptrs.len(), 12);
+
+    for i in 1..=10 {
+        let name = Name::from_str(&format!("{}.2.0.192.in-addr.arpa.", i)).unwrap();
+        let rrset = &records[&RrKey::new(name.into(), RecordType::PTR)];
+        let record = rrset.records_without_rrsigs().next().unwrap();
+
+        assert_eq!(record.ttl(), 3600);
+        assert_eq!(record.dns_class(), DNSClass::IN);
+        assert_eq!(
+            *record.rdata(),
+            RData::PTR(Name::from_str(&format!("host-{:03}.example.com.", i)).unwrap())
+        );
+    }
+
+    let name = Name::from_str("c0.2.0.192.in-addr.arpa.").unwrap();
+    let rrset = &records[&RrKey::new(name.into(), RecordType::PTR)];
+    assert_eq!(
+        *rrset.records_without_rrsigs().next().unwrap().rdata(),
+        RData::PTR(Name::from_str("hex-192.example.com.").unwrap())
+    );
+
+    // invalid ranges and modifiers
+    for generate in &[
+        "$GENERATE 10-1 $ PTR host-$.example.com.",
+        "$GENERATE 1-10/0 $ PTR host-$.example.com.",
+        "$GENERATE 1-10 $ PTR host-${0,3,q}.example.com.",
+        "$GENERATE 1-10 $ PTR host-${-2}.example.com.",
+        "$GENERATE 1-10 $ PTR host-${0,3.example.com.",
+        "$GENERATE 0-4294967295 $ PTR host-$.example.com.",
+        "$GENERATE 1-65537 $ PTR host-$.example.com.",
+    ] {
+        let lexer = Lexer::new(generate);
+        assert!(Parser::new().parse(lexer, Some(origin.clone())).is_err());
+    }
+}
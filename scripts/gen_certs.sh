@@ -2,7 +2,7 @@
 
 set -e
 
-OPENSSL=/usr/local/opt/openssl/bin/openssl
+OPENSSL=${OPENSSL:-/usr/local/opt/openssl/bin/openssl}
 
 trust_dns_dir=$(dirname $0)/..
 
@@ -18,6 +18,7 @@ cat <<-EOF > /tmp/ca.conf
 [req]
 prompt = no
 req_extensions = req_ext
+x509_extensions = req_ext
 distinguished_name = dn
 
 [dn]
@@ -39,7 +40,7 @@ EOF
 # CA
 echo "----> Generating CA <----"
 ${OPENSSL:?} genrsa -out ca.key 4096
-${OPENSSL:?} req -x509 -new -nodes -key ca.key -days 365 -out ca.pem -verify -config /tmp/ca.conf
+${OPENSSL:?} req -x509 -new -nodes -key ca.key -days 3650 -out ca.pem -config /tmp/ca.conf
 ${OPENSSL:?} x509 -in ca.pem -out ca.der -outform der  
 
 cat <<-EOF > /tmp/cert.conf
@@ -75,7 +76,7 @@ ${OPENSSL:?} req -new -nodes -key cert-key.pem -out cert.csr \
 ${OPENSSL:?} x509 -in ca.pem -inform pem -pubkey -noout > ca.pubkey
 
 echo "----> Signing Cert <----"
-${OPENSSL:?} x509 -req -days 365 -in cert.csr -CA ca.pem -CAkey ca.key  -set_serial 0x8771f7bdee982fa6 -out cert.pem -extfile /tmp/cert.conf -extensions req_ext
+${OPENSSL:?} x509 -req -days 3650 -in cert.csr -CA ca.pem -CAkey ca.key  -set_serial 0x8771f7bdee982fa6 -out cert.pem -extfile /tmp/cert.conf -extensions req_ext
 
 echo "----> Verifying Cert <----"
 ${OPENSSL:?} verify -CAfile ca.pem cert.pem
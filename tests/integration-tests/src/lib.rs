@@ -36,7 +36,7 @@ use trust_dns_proto::xfer::{
 use trust_dns_proto::StreamHandle;
 
 use trust_dns_server::authority::{Catalog, MessageRequest, MessageResponse};
-use trust_dns_server::server::{Request, RequestHandler, ResponseHandler};
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ResponseHandler};
 
 pub mod authority;
 pub mod mock_client;
@@ -150,6 +150,7 @@ impl Stream for TestClientStream {
                 let request = Request {
                     message,
                     src: src_addr,
+                    protocol: Protocol::Tcp,
                 };
 
                 dbg!("catalog handling request");
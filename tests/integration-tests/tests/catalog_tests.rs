@@ -1,6 +1,7 @@
 extern crate futures;
 extern crate trust_dns;
 extern crate trust_dns_integration;
+extern crate trust_dns_proto;
 extern crate trust_dns_server;
 
 use std::net::*;
@@ -14,6 +15,7 @@ use trust_dns::rr::*;
 use trust_dns::serialize::binary::{BinDecodable, BinEncodable};
 
 use trust_dns_server::authority::{Authority, Catalog, MessageRequest, ZoneType};
+use trust_dns_server::server::{Protocol, Request, RequestHandler};
 use trust_dns_server::store::in_memory::InMemoryAuthority;
 
 use trust_dns_integration::authority::create_example;
@@ -201,6 +203,40 @@ fn test_catalog_lookup() {
     );
 }
 
+#[test]
+fn test_catalog_response_id() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(example));
+
+    for op_code in &[OpCode::Query, OpCode::Notify] {
+        let mut question: Message = Message::new();
+        question
+            .set_id(0x1234)
+            .set_op_code(*op_code)
+            .add_query(Query::query(origin.clone().into(), RecordType::A));
+
+        let question_bytes = question.to_bytes().unwrap();
+        let request = Request {
+            message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+            src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+            protocol: Protocol::Udp,
+        };
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request(request, response_handler.clone())
+            .wait()
+            .unwrap();
+        let result = response_handler.into_message().wait().unwrap();
+
+        assert_eq!(result.id(), 0x1234);
+        assert_eq!(result.message_type(), MessageType::Response);
+    }
+}
+
 #[test]
 #[allow(clippy::unreadable_literal)]
 fn test_catalog_nx_soa() {
@@ -249,6 +285,62 @@ fn test_catalog_nx_soa() {
     );
 }
 
+#[test]
+#[allow(clippy::unreadable_literal)]
+fn test_catalog_negative_soa_ttl() {
+    let mut example = create_example();
+    let origin = example.origin().clone();
+
+    // the SOA TTL is longer than its MINIMUM
+    let soa = SOA::new(
+        Name::parse("sns.dns.icann.org.", None).unwrap(),
+        Name::parse("noc.dns.icann.org.", None).unwrap(),
+        2015082404,
+        7200,
+        3600,
+        1209600,
+        300,
+    );
+    example.upsert(
+        Record::from_rdata(origin.clone().into(), 86400, RData::SOA(soa.clone())),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(example));
+
+    // NXDOMAIN, and NODATA for www.example.com. which has no MX records
+    for (name, record_type, response_code) in &[
+        ("nx.example.com.", RecordType::A, ResponseCode::NXDomain),
+        ("www.example.com.", RecordType::MX, ResponseCode::NoError),
+    ] {
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(Name::parse(name, None).unwrap(), *record_type));
+
+        // temp request
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(question_req, None, response_handler.clone())
+            .wait()
+            .unwrap();
+        let result = response_handler.into_message().wait().unwrap();
+
+        assert_eq!(result.response_code(), *response_code);
+        assert!(result.authoritative());
+        assert!(result.answers().is_empty());
+
+        // exactly the SOA, for as long as the negative response may be cached
+        let ns: &[Record] = result.name_servers();
+        assert_eq!(ns.len(), 1);
+        assert_eq!(*ns[0].name(), Name::from(origin.clone()));
+        assert_eq!(*ns[0].rdata(), RData::SOA(soa.clone()));
+        assert_eq!(ns[0].ttl(), 300);
+    }
+}
+
 #[test]
 #[allow(clippy::unreadable_literal)]
 fn test_axfr() {
@@ -385,6 +477,161 @@ fn test_axfr() {
     assert_eq!(expected_set, answers);
 }
 
+#[test]
+fn test_referral() {
+    let mut example = create_example();
+    let origin = example.origin().clone();
+    let sub = Name::parse("sub.example.com.", None).unwrap();
+    let ns = Name::parse("ns.sub.example.com.", None).unwrap();
+    example.upsert(
+        Record::from_rdata(sub.clone(), 86400, RData::NS(ns.clone())),
+        0,
+    );
+    example.upsert(
+        Record::from_rdata(ns.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 53))),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(example));
+
+    let mut question: Message = Message::new();
+
+    let mut query: Query = Query::new();
+    query.set_name(Name::parse("www.sub.example.com.", None).unwrap());
+
+    question.add_query(query);
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(question_req, None, response_handler.clone())
+        .wait()
+        .unwrap();
+    let result = response_handler.into_message().wait().unwrap();
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.authoritative());
+    assert!(result.answers().is_empty());
+
+    let name_servers: &[Record] = result.name_servers();
+    assert_eq!(name_servers.len(), 1);
+    assert_eq!(*name_servers[0].name(), sub);
+    assert_eq!(*name_servers[0].rdata(), RData::NS(ns.clone()));
+
+    let additionals: &[Record] = result.additionals();
+    assert_eq!(additionals.len(), 1);
+    assert_eq!(*additionals[0].name(), ns);
+    assert_eq!(
+        *additionals[0].rdata(),
+        RData::A(Ipv4Addr::new(192, 0, 2, 53))
+    );
+}
+
+#[test]
+fn test_closest_zone() {
+    let mut catalog: Catalog = Catalog::new();
+    for zone in &["com.", "example.com.", "sub.example.com."] {
+        let origin = Name::parse(zone, None).unwrap();
+        catalog.upsert(
+            LowerName::new(&origin),
+            Box::new(InMemoryAuthority::empty(origin, ZoneType::Master, false)),
+        );
+    }
+
+    let closest_zone = |name: &str| {
+        catalog
+            .closest_zone(&LowerName::from(Name::parse(name, None).unwrap()))
+            .map(|zone| zone.to_string())
+    };
+
+    assert_eq!(
+        closest_zone("www.sub.example.com."),
+        Some("sub.example.com.".to_string())
+    );
+    assert_eq!(
+        closest_zone("sub.example.com."),
+        Some("sub.example.com.".to_string())
+    );
+    assert_eq!(
+        closest_zone("www.example.com."),
+        Some("example.com.".to_string())
+    );
+    assert_eq!(closest_zone("example.net."), None);
+    assert_eq!(closest_zone("com."), Some("com.".to_string()));
+}
+
+#[cfg(feature = "dnssec")]
+fn secure_lookup(name: &str, record_type: RecordType, dnssec_ok: bool) -> Message {
+    use trust_dns_integration::authority::create_secure_example;
+
+    let example = create_secure_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(example));
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(Name::parse(name, None).unwrap(), record_type));
+    if dnssec_ok {
+        question.edns_mut().set_dnssec_ok(true);
+    }
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(question_req, None, response_handler.clone())
+        .wait()
+        .unwrap();
+    response_handler.into_message().wait().unwrap()
+}
+
+#[cfg(feature = "dnssec")]
+fn has_dnssec_records(message: &Message) -> bool {
+    message
+        .answers()
+        .iter()
+        .chain(message.name_servers())
+        .chain(message.additionals())
+        .any(|record| record.rr_type().is_dnssec())
+}
+
+#[test]
+#[cfg(feature = "dnssec")]
+fn test_dnssec_records_without_do() {
+    // the zone is signed, clients with the DO bit get the RRSIGs
+    let result = secure_lookup("www.example.com.", RecordType::A, true);
+    assert!(result
+        .answers()
+        .iter()
+        .any(|record| record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG)));
+
+    let result = secure_lookup("www.example.com.", RecordType::A, false);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.answers().is_empty());
+    assert!(!has_dnssec_records(&result));
+
+    // no NSEC records or RRSIGs in negative responses
+    let result = secure_lookup("nx.example.com.", RecordType::A, false);
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(!result.name_servers().is_empty());
+    assert!(!has_dnssec_records(&result));
+
+    // unless the query is for a DNSSEC type
+    let dnskey = RecordType::DNSSEC(DNSSECRecordType::DNSKEY);
+    let result = secure_lookup("example.com.", dnskey, false);
+    assert!(!result.answers().is_empty());
+    assert!(result
+        .answers()
+        .iter()
+        .all(|record| record.rr_type() == dnskey));
+}
+
 #[test]
 fn test_axfr_refused() {
     let mut test = create_test();
@@ -419,6 +666,61 @@ fn test_axfr_refused() {
     assert!(result.additionals().is_empty());
 }
 
+#[test]
+fn test_transfer_protocol_and_peers() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+    test.set_transfer_peers(Some(vec![IpAddr::from([127, 0, 0, 1])]));
+
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(test));
+
+    let transfer = |query_type: RecordType, src: [u8; 4], protocol: Protocol| {
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(origin.clone().into(), query_type));
+
+        let question_bytes = question.to_bytes().unwrap();
+        let request = Request {
+            message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+            src: SocketAddr::from((src, 1234)),
+            protocol,
+        };
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request(request, response_handler.clone())
+            .wait()
+            .unwrap();
+        response_handler.into_message().wait().unwrap()
+    };
+
+    // AXFR over UDP is refused, even from an allowed peer
+    let result = transfer(RecordType::AXFR, [127, 0, 0, 1], Protocol::Udp);
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    // IXFR over UDP is truncated, to retry over TCP
+    let result = transfer(RecordType::IXFR, [127, 0, 0, 1], Protocol::Udp);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.truncated());
+    assert!(result.answers().is_empty());
+
+    // AXFR over TCP from an allowed peer
+    let result = transfer(RecordType::AXFR, [127, 0, 0, 1], Protocol::Tcp);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers = result.answers();
+    assert!(answers.len() > 2);
+    assert_eq!(answers.first().unwrap().rr_type(), RecordType::SOA);
+    assert_eq!(answers.last().unwrap().rr_type(), RecordType::SOA);
+
+    // AXFR over TCP from any other peer is refused
+    let result = transfer(RecordType::AXFR, [127, 0, 0, 2], Protocol::Tcp);
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+}
+
 // TODO: add this test
 // #[test]
 // fn test_truncated_returns_records() {
@@ -426,6 +728,103 @@ fn test_axfr_refused() {
 // }
 
 // TODO: these should be moved to the battery tests
+#[test]
+fn test_chaos_version() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(example));
+    catalog.set_version(Some("Trust-DNS 0.17".to_string()));
+
+    let chaos = |name: &str, query_type: RecordType| {
+        let mut query = Query::query(Name::from_str(name).unwrap(), query_type);
+        query.set_query_class(DNSClass::CH);
+        let mut question: Message = Message::new();
+        question.add_query(query);
+
+        let question_bytes = question.to_bytes().unwrap();
+        let request = Request {
+            message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+            src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+            protocol: Protocol::Udp,
+        };
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request(request, response_handler.clone())
+            .wait()
+            .unwrap();
+        response_handler.into_message().wait().unwrap()
+    };
+
+    let result = chaos("version.bind.", RecordType::TXT);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+    assert_eq!(result.answers().len(), 1);
+    let answer = &result.answers()[0];
+    assert_eq!(answer.name(), &Name::from_str("version.bind.").unwrap());
+    assert_eq!(answer.dns_class(), DNSClass::CH);
+    assert_eq!(
+        answer.rdata(),
+        &RData::TXT(TXT::new(vec!["Trust-DNS 0.17".to_string()]))
+    );
+
+    // the identity isn't set
+    let result = chaos("id.server.", RecordType::TXT);
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    // and no other CH queries are answered
+    let result = chaos("version.bind.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    let result = chaos("www.example.com.", RecordType::TXT);
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+}
+
+#[test]
+fn test_algorithm_policy_dau() {
+    use trust_dns::rr::dnssec::{Algorithm, AlgorithmPolicy, SupportedAlgorithms};
+    use trust_dns::rr::rdata::opt::{EdnsCode, EdnsOption};
+
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(example));
+    catalog.set_algorithm_policy(AlgorithmPolicy::with_minimum(Algorithm::ECDSAP256SHA256));
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    question.edns_mut().set_dnssec_ok(true);
+
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        protocol: Protocol::Udp,
+    };
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(request, response_handler.clone())
+        .wait()
+        .unwrap();
+    let result = response_handler.into_message().wait().unwrap();
+
+    let allowed = SupportedAlgorithms::from_vec(&[
+        Algorithm::ECDSAP256SHA256,
+        Algorithm::ECDSAP384SHA384,
+        Algorithm::ED25519,
+    ]);
+    let edns = result.edns().expect("no edns in response");
+    assert_eq!(edns.option(EdnsCode::DAU), Some(&EdnsOption::DAU(allowed)));
+    assert_eq!(edns.option(EdnsCode::DHU), Some(&EdnsOption::DHU(allowed)));
+}
+
 #[test]
 fn test_cname_additionals() {
     let example = create_example();
@@ -529,3 +928,59 @@ fn test_multiple_cname_additionals() {
         &RData::A(Ipv4Addr::new(93, 184, 216, 34))
     );
 }
+
+#[test]
+#[cfg(feature = "dnssec")]
+fn test_tsig_bad_key() {
+    use trust_dns::rr::dnssec::TsigSigner;
+    use trust_dns_proto::rr::dnssec::tsig::DEFAULT_FUDGE;
+
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let server_key = TsigSigner::new(
+        Name::from_str("transfer-key.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+        b"a secret shared by client and server".to_vec(),
+        DEFAULT_FUDGE,
+    );
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(example));
+    catalog.add_tsig_key(server_key);
+
+    let client_key = TsigSigner::new(
+        Name::from_str("other-key.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+        b"a secret shared by client and server".to_vec(),
+        DEFAULT_FUDGE,
+    );
+    let mut question: Message = Message::new();
+    question
+        .set_id(0x1234)
+        .add_query(Query::query(origin.into(), RecordType::A));
+    question.finalize(&client_key, 1_577_836_800).unwrap();
+
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        protocol: Protocol::Udp,
+    };
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(request, response_handler.clone())
+        .wait()
+        .unwrap();
+    let result = response_handler.into_message().wait().unwrap();
+
+    assert_eq!(result.response_code(), ResponseCode::NotAuth);
+    assert!(result.answers().is_empty());
+    match result.tsig().map(Record::rdata) {
+        Some(RData::DNSSEC(DNSSECRData::TSIG(ref tsig))) => {
+            assert_eq!(tsig.error(), u16::from(ResponseCode::BADKEY));
+            assert!(tsig.mac().is_empty());
+        }
+        tsig => panic!("expected an error TSIG: {:?}", tsig),
+    }
+}
@@ -190,6 +190,70 @@ where
     )
 }
 
+#[cfg(feature = "dnssec")]
+fn tsig_signer(key: &[u8]) -> trust_dns::rr::dnssec::TsigSigner {
+    use trust_dns::rr::dnssec::TsigSigner;
+    use trust_dns::rr::rdata::TsigAlgorithm;
+    use trust_dns_proto::rr::dnssec::tsig::DEFAULT_FUDGE;
+
+    TsigSigner::new(
+        Name::from_str("transfer-key.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+        key.to_vec(),
+        DEFAULT_FUDGE,
+    )
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_query_tsig_nonet() {
+    let authority = create_example();
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+    catalog.add_tsig_key(tsig_signer(b"a secret shared by client and server"));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, mut client) = ClientFuture::with_tsig_signer(
+        stream,
+        Box::new(sender),
+        std::time::Duration::from_secs(5),
+        Arc::new(tsig_signer(b"a secret shared by client and server")),
+    );
+    io_loop.spawn(bg);
+
+    // the responses are signed, and verified by the client
+    io_loop.block_on(test_query(&mut client)).unwrap();
+    io_loop.block_on(test_query(&mut client)).unwrap();
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_query_tsig_wrong_key_nonet() {
+    let authority = create_example();
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+    catalog.add_tsig_key(tsig_signer(b"a secret shared by client and server"));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, mut client) = ClientFuture::with_tsig_signer(
+        stream,
+        Box::new(sender),
+        std::time::Duration::from_secs(5),
+        Arc::new(tsig_signer(b"not the secret of the server")),
+    );
+    io_loop.spawn(bg);
+
+    // the NOTAUTH response carries an unsigned TSIG, which fails verification
+    let result = io_loop.block_on(client.query(
+        Name::from_str("www.example.com.").unwrap(),
+        DNSClass::IN,
+        RecordType::A,
+    ));
+    assert!(result.is_err());
+}
+
 #[test]
 fn test_notify() {
     let authority = create_example();
@@ -12,15 +12,19 @@ use std::sync::{Arc, Mutex};
 use futures::Future;
 use tokio::runtime::current_thread::Runtime;
 
+use trust_dns_proto::error::ProtoError;
 use trust_dns_proto::op::{NoopMessageFinalizer, Query};
+use trust_dns_proto::rr::rdata::SOA;
 use trust_dns_proto::rr::{DNSClass, Name, RData, Record, RecordType};
-use trust_dns_proto::xfer::{BufDnsRequestStreamHandle, DnsExchange, DnsMultiplexer};
+use trust_dns_proto::xfer::{BufDnsRequestStreamHandle, DnsExchange, DnsMultiplexer, DnsResponse};
 use trust_dns_resolver::config::LookupIpStrategy;
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::fcrdns::{FcrdnsFuture, MAX_PTR_NAMES};
 use trust_dns_resolver::lookup::{Lookup, LookupFuture};
 use trust_dns_resolver::lookup_ip::LookupIpFuture;
 use trust_dns_resolver::lookup_state::CachingClient;
 use trust_dns_resolver::Hosts;
-use trust_dns_server::authority::{Authority, Catalog};
+use trust_dns_server::authority::{Authority, Catalog, ZoneType};
 use trust_dns_server::store::in_memory::InMemoryAuthority;
 
 use trust_dns_integration::authority::create_example;
@@ -103,6 +107,141 @@ fn test_lookup_hosts() {
     assert_eq!(lookup.iter().next().unwrap(), Ipv4Addr::new(10, 0, 1, 104));
 }
 
+/// Reverse zone of 93.184.216.0/24, www.example.com. is 93.184.216.34
+fn create_reverse_example() -> InMemoryAuthority {
+    let origin = Name::from_str("216.184.93.in-addr.arpa.").unwrap();
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Master, false);
+    authority.upsert(
+        Record::from_rdata(
+            origin,
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("sns.dns.icann.org.").unwrap(),
+                Name::from_str("noc.dns.icann.org.").unwrap(),
+                2019060101,
+                7200,
+                3600,
+                1_209_600,
+                3600,
+            )),
+        ),
+        0,
+    );
+
+    // the first name does not exist, the second resolves to the address
+    for (octet, ptr) in &[
+        (34, "nowhere.example.com."),
+        (34, "www.example.com."),
+        (35, "www.example.com."),
+    ] {
+        let name = Name::from(Ipv4Addr::new(93, 184, 216, *octet));
+        let ptr = RData::PTR(Name::from_str(ptr).unwrap());
+        authority.upsert(Record::from_rdata(name, 86400, ptr), 0);
+    }
+
+    authority
+}
+
+fn fcrdns(ip: IpAddr) -> bool {
+    let mut catalog = Catalog::new();
+    let authority = create_example();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+    let authority = create_reverse_example();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let dns_conn = DnsMultiplexer::new(stream, Box::new(sender), NoopMessageFinalizer::new());
+
+    let (stream, handle) = DnsExchange::connect(dns_conn);
+    io_loop.spawn(stream.and_then(|stream| stream).map_err(|e| {
+        println!("error, udp connection shutting down: {}", e);
+    }));
+
+    let client = BufDnsRequestStreamHandle::new(handle);
+
+    let verify = FcrdnsFuture::verify(ip, Default::default(), CachingClient::new(0, client));
+    io_loop.block_on(verify).unwrap()
+}
+
+#[test]
+fn test_fcrdns_matching() {
+    assert!(fcrdns(Ipv4Addr::new(93, 184, 216, 34).into()));
+}
+
+#[test]
+fn test_fcrdns_mismatching() {
+    // www.example.com. is not 93.184.216.35
+    assert!(!fcrdns(Ipv4Addr::new(93, 184, 216, 35).into()));
+    // no PTR record
+    assert!(!fcrdns(Ipv4Addr::new(93, 184, 216, 36).into()));
+}
+
+fn mock_fcrdns(ptr_names: &[&str], mut responses: Vec<Result<DnsResponse, ProtoError>>) -> bool {
+    let ip = Ipv4Addr::new(93, 184, 216, 34);
+    let ptr_query = Query::query(Name::from(IpAddr::V4(ip)), RecordType::PTR);
+    let ptr_records = ptr_names
+        .iter()
+        .map(|name| {
+            let ptr = RData::PTR(Name::from_str(name).unwrap());
+            Record::from_rdata(Name::from(IpAddr::V4(ip)), 86400, ptr)
+        })
+        .collect();
+    let ptr_message = message(ptr_query, ptr_records, vec![], vec![]);
+
+    // the mock returns the responses from last to first, the PTR lookup is the first
+    responses.reverse();
+    responses.push(ptr_message.map(Into::into));
+    let client = MockClientHandle::mock(responses);
+
+    let verify = FcrdnsFuture::verify(ip.into(), Default::default(), CachingClient::new(0, client));
+    let mut io_loop = Runtime::new().unwrap();
+    io_loop.block_on(verify).unwrap()
+}
+
+#[test]
+fn test_fcrdns_error_is_no_match() {
+    let ip = Ipv4Addr::new(93, 184, 216, 34);
+    let www = Name::from_str("www.example.com.").unwrap();
+    let www_message = message(
+        Query::query(www.clone(), RecordType::A),
+        vec![v4_record(www, ip)],
+        vec![],
+        vec![],
+    );
+
+    // the lookup of the first name fails, the second name is confirmed
+    assert!(mock_fcrdns(
+        &["broken.example.com.", "www.example.com."],
+        vec![
+            error(ProtoError::from("failed to connect")),
+            www_message.map(Into::into),
+        ],
+    ));
+}
+
+#[test]
+fn test_fcrdns_max_ptr_names() {
+    let ip = Ipv4Addr::new(93, 184, 216, 34);
+    let names: Vec<String> = (0..=MAX_PTR_NAMES)
+        .map(|i| format!("host-{}.example.com.", i))
+        .collect();
+    let names: Vec<&str> = names.iter().map(AsRef::as_ref).collect();
+
+    let last = Name::from_str(names[MAX_PTR_NAMES]).unwrap();
+    let last_message = message(
+        Query::query(last.clone(), RecordType::A),
+        vec![v4_record(last, ip)],
+        vec![],
+        vec![],
+    );
+    let mut responses: Vec<_> = (0..MAX_PTR_NAMES).map(|_| empty()).collect();
+    responses.push(last_message.map(Into::into));
+
+    // only the last name would be confirmed, but it is not looked up
+    assert!(!mock_fcrdns(&names, responses));
+}
+
 fn create_ip_like_example() -> InMemoryAuthority {
     let mut authority = create_example();
     authority.upsert(
@@ -279,72 +418,42 @@ fn test_chained_cname_lookup() {
 #[test]
 fn test_max_chained_lookup_depth() {
     let resp_query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
-    let cname_record1 = cname_record(
-        Name::from_str("www.example.com.").unwrap(),
-        Name::from_str("cname2.example.com.").unwrap(),
-    );
-    let cname_record2 = cname_record(
-        Name::from_str("cname2.example.com.").unwrap(),
-        Name::from_str("cname3.example.com.").unwrap(),
-    );
-    let cname_record3 = cname_record(
-        Name::from_str("cname3.example.com.").unwrap(),
-        Name::from_str("cname4.example.com.").unwrap(),
-    );
-    let cname_record4 = cname_record(
-        Name::from_str("cname4.example.com.").unwrap(),
-        Name::from_str("cname5.example.com.").unwrap(),
-    );
-    let cname_record5 = cname_record(
-        Name::from_str("cname5.example.com.").unwrap(),
-        Name::from_str("cname6.example.com.").unwrap(),
-    );
-    let cname_record6 = cname_record(
-        Name::from_str("cname6.example.com.").unwrap(),
-        Name::from_str("cname7.example.com.").unwrap(),
-    );
-    let cname_record7 = cname_record(
-        Name::from_str("cname7.example.com.").unwrap(),
-        Name::from_str("cname8.example.com.").unwrap(),
-    );
-    let cname_record8 = cname_record(
-        Name::from_str("cname8.example.com.").unwrap(),
-        Name::from_str("cname9.example.com.").unwrap(),
-    );
-    let cname_record9 = cname_record(
-        Name::from_str("cname9.example.com.").unwrap(),
-        Name::from_str("v4.example.com.").unwrap(),
-    );
-    let v4_record = v4_record(
-        Name::from_str("v4.example.com.").unwrap(),
-        Ipv4Addr::new(93, 184, 216, 34),
-    );
 
-    // The first response should be a cname, the second will be the actual record
-    let message1 = message(resp_query.clone(), vec![cname_record1], vec![], vec![]);
-    let message2 = message(resp_query.clone(), vec![cname_record2], vec![], vec![]);
-    let message3 = message(resp_query.clone(), vec![cname_record3], vec![], vec![]);
-    let message4 = message(resp_query.clone(), vec![cname_record4], vec![], vec![]);
-    let message5 = message(resp_query.clone(), vec![cname_record5], vec![], vec![]);
-    let message6 = message(resp_query.clone(), vec![cname_record6], vec![], vec![]);
-    let message7 = message(resp_query.clone(), vec![cname_record7], vec![], vec![]);
-    let message8 = message(resp_query.clone(), vec![cname_record8], vec![], vec![]);
-    let message9 = message(resp_query.clone(), vec![cname_record9], vec![], vec![]);
-    let message10 = message(resp_query, vec![v4_record], vec![], vec![]);
+    // www.example.com. -> cname2.example.com. -> ... -> cname18.example.com. -> v4.example.com.
+    let mut names = vec![Name::from_str("www.example.com.").unwrap()];
+    names.extend((2..=18).map(|i| Name::from_str(&format!("cname{}.example.com.", i)).unwrap()));
+    names.push(Name::from_str("v4.example.com.").unwrap());
+
+    // every response is the next cname, the last one will be the actual record
+    let mut messages = names
+        .windows(2)
+        .map(|pair| {
+            message(
+                resp_query.clone(),
+                vec![cname_record(pair[0].clone(), pair[1].clone())],
+                vec![],
+                vec![],
+            )
+        })
+        .collect::<Vec<_>>();
+    messages.push(message(
+        resp_query,
+        vec![v4_record(
+            Name::from_str("v4.example.com.").unwrap(),
+            Ipv4Addr::new(93, 184, 216, 34),
+        )],
+        vec![],
+        vec![],
+    ));
 
     // the mock pops messages...
-    let client = MockClientHandle::mock(vec![
-        message10.map(Into::into),
-        message9.map(Into::into),
-        message8.map(Into::into),
-        message7.map(Into::into),
-        message6.map(Into::into),
-        message5.map(Into::into),
-        message4.map(Into::into),
-        message3.map(Into::into),
-        message2.map(Into::into),
-        message1.map(Into::into),
-    ]);
+    let client = MockClientHandle::mock(
+        messages
+            .into_iter()
+            .rev()
+            .map(|m| m.map(Into::into))
+            .collect(),
+    );
 
     let client = CachingClient::new(0, client);
     let lookup = LookupFuture::lookup(
@@ -357,12 +466,16 @@ fn test_max_chained_lookup_depth() {
     let mut io_loop = Runtime::new().unwrap();
 
     println!("performing max cname validation");
-    // TODO: validate exact error
-    assert!(io_loop.block_on(lookup).is_err());
+    assert_eq!(
+        *io_loop.block_on(lookup).unwrap_err().kind(),
+        ResolveErrorKind::CnameChainTooLong {
+            name: Name::from_str("cname18.example.com.").unwrap()
+        }
+    );
 
-    // This query should succeed, as the queue depth should reset to 0 on a failed request
+    // This query should succeed, the chain of a failed request is not carried over
     let lookup = LookupFuture::lookup(
-        vec![Name::from_str("cname9.example.com.").unwrap()],
+        vec![Name::from_str("cname18.example.com.").unwrap()],
         RecordType::A,
         Default::default(),
         client,
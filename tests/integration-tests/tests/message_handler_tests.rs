@@ -0,0 +1,146 @@
+extern crate futures;
+extern crate tokio;
+extern crate tokio_timer;
+extern crate tokio_udp;
+extern crate trust_dns;
+extern crate trust_dns_proto;
+extern crate trust_dns_server;
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket as StdUdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::{future, Future};
+use tokio::runtime::current_thread::Runtime;
+use tokio_timer::Delay;
+use tokio_udp::UdpSocket;
+
+use trust_dns::client::{Client, SyncClient};
+use trust_dns::udp::UdpClientConnection;
+use trust_dns_proto::op::{Message, MessageType, Query, ResponseCode};
+use trust_dns_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+
+use trust_dns_server::server::{MessageHandler, MessageRequestHandler};
+use trust_dns_server::ServerFuture;
+
+/// Answers every query with a fixed A record, or with many for `many.example.com.`
+struct FixedHandler;
+
+impl MessageHandler for FixedHandler {
+    fn handle_request(&self, request: &Message) -> Message {
+        let mut response = Message::new();
+        response.set_authoritative(true);
+
+        let query = request.queries().first().expect("no query").clone();
+        let count = if *query.name() == Name::from_str("many.example.com.").unwrap() {
+            100
+        } else {
+            1
+        };
+
+        for i in 0..count {
+            response.add_answer(Record::from_rdata(
+                query.name().clone(),
+                86400,
+                RData::A(Ipv4Addr::new(93, 184, 216, i as u8)),
+            ));
+        }
+
+        response.add_query(query);
+        response
+    }
+}
+
+fn server_thread_udp(udp_socket: UdpSocket, server_continue: Arc<AtomicBool>) {
+    let mut io_loop = Runtime::new().unwrap();
+    let server = ServerFuture::new(MessageRequestHandler::new(FixedHandler));
+    io_loop
+        .block_on::<Box<dyn Future<Item = (), Error = ()> + Send>>(Box::new(future::lazy(|| {
+            server.register_socket(udp_socket);
+            future::ok(())
+        })))
+        .unwrap();
+
+    while server_continue.load(Ordering::Relaxed) {
+        io_loop
+            .block_on(Delay::new(Instant::now() + Duration::from_millis(10)))
+            .unwrap();
+    }
+}
+
+fn with_udp_server<F: FnOnce(SocketAddr)>(client: F) {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+    let ipaddr = udp_socket.local_addr().unwrap();
+
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+    let server_thread = thread::Builder::new()
+        .name("test_message_handler:udp:server".to_string())
+        .spawn(move || server_thread_udp(udp_socket, server_continue2))
+        .unwrap();
+
+    client(ipaddr);
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_fixed_answer_udp() {
+    with_udp_server(|ipaddr| {
+        let conn = UdpClientConnection::new(ipaddr).unwrap();
+        let client = SyncClient::new(conn);
+        let response = client
+            .query(
+                &Name::from_str("www.example.com.").unwrap(),
+                DNSClass::IN,
+                RecordType::A,
+            )
+            .expect("query failed");
+
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.message_type(), MessageType::Response);
+        assert!(response.authoritative());
+        assert!(!response.truncated());
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(
+            *response.answers()[0].rdata(),
+            RData::A(Ipv4Addr::new(93, 184, 216, 0))
+        );
+
+        // the client sent EDNS, so the response has an OPT record
+        assert!(response.edns().is_some());
+    });
+}
+
+#[test]
+fn test_truncated_udp() {
+    with_udp_server(|ipaddr| {
+        // without EDNS, the response must fit in 512 bytes
+        let mut request = Message::new();
+        request.set_id(0xBEEF).add_query(Query::query(
+            Name::from_str("many.example.com.").unwrap(),
+            RecordType::A,
+        ));
+
+        let socket = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        socket.send_to(&request.to_vec().unwrap(), ipaddr).unwrap();
+
+        let mut buf = [0u8; 4096];
+        let (len, _) = socket.recv_from(&mut buf).expect("no response");
+        assert!(len <= 512);
+
+        let response = Message::from_vec(&buf[..len]).expect("failed to decode");
+        assert_eq!(response.id(), 0xBEEF);
+        assert!(response.truncated());
+        assert!(response.edns().is_none());
+        assert!(response.answers().len() < 100);
+    });
+}
@@ -71,6 +71,7 @@ fn mock_nameserver_on_send<O: OnSend>(
             socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 0),
             protocol: Protocol::Udp,
             tls_dns_name: None,
+            tls_accept_invalid_hostnames: false,
         },
         options,
         client,
@@ -334,6 +335,89 @@ fn test_distrust_nx_responses() {
     assert_eq!(response.answers()[0], v4_record);
 }
 
+#[test]
+fn test_failover_on_timeout() {
+    use trust_dns_proto::error::ProtoErrorKind;
+
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+    let v4_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+    let success_msg = message(query.clone(), vec![v4_record.clone()], vec![], vec![]);
+
+    let mut reactor = Runtime::new().unwrap();
+
+    // the first server times out, the second answers
+    let udp1_nameserver = mock_nameserver(vec![Err(ProtoErrorKind::Timeout.into())], options);
+    let udp2_nameserver = mock_nameserver(vec![success_msg.map(Into::into)], options);
+    let tcp_nameserver =
+        mock_nameserver(vec![Err(ProtoError::from("Forced Testing Error"))], options);
+
+    let mut pool = mock_nameserver_pool(
+        vec![udp1_nameserver, udp2_nameserver],
+        vec![tcp_nameserver],
+        None,
+        options,
+    );
+
+    let request = message(query, vec![], vec![], vec![]).unwrap();
+    let future = pool.send(request);
+
+    let response = reactor.block_on(future).unwrap();
+    assert_eq!(response.answers()[0], v4_record);
+}
+
+#[test]
+fn test_failover_on_refused() {
+    use trust_dns_proto::op::ResponseCode;
+
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+    let mut refused_message = message(query.clone(), vec![], vec![], vec![]).unwrap();
+    refused_message.set_response_code(ResponseCode::Refused);
+    let refused_message = Ok(refused_message);
+
+    let v4_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+    let success_msg = message(query.clone(), vec![v4_record.clone()], vec![], vec![]);
+
+    let mut reactor = Runtime::new().unwrap();
+
+    // the first server refuses, the second answers
+    let udp1_nameserver = mock_nameserver(vec![refused_message.clone().map(Into::into)], options);
+    let udp2_nameserver = mock_nameserver(vec![success_msg.map(Into::into)], options);
+    let tcp_nameserver =
+        mock_nameserver(vec![Err(ProtoError::from("Forced Testing Error"))], options);
+
+    let mut pool = mock_nameserver_pool(
+        vec![udp1_nameserver, udp2_nameserver],
+        vec![tcp_nameserver],
+        None,
+        options,
+    );
+
+    let request = message(query.clone(), vec![], vec![], vec![]).unwrap();
+    let future = pool.send(request);
+
+    let response = reactor.block_on(future).unwrap();
+    assert_eq!(response.answers()[0], v4_record);
+
+    // all of the servers refuse
+    let udp_nameserver = mock_nameserver(vec![refused_message.clone().map(Into::into)], options);
+    let tcp_nameserver = mock_nameserver(vec![refused_message.map(Into::into)], options);
+
+    let mut pool = mock_nameserver_pool(vec![udp_nameserver], vec![tcp_nameserver], None, options);
+
+    let request = message(query, vec![], vec![], vec![]).unwrap();
+    let future = pool.send(request);
+
+    assert!(reactor.block_on(future).is_err());
+}
+
 // === Concurrent requests ===
 
 #[derive(Clone)]
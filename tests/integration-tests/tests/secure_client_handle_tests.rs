@@ -1,6 +1,8 @@
 #![cfg(feature = "dnssec")]
 
+extern crate chrono;
 extern crate futures;
+extern crate openssl;
 extern crate tokio;
 extern crate tokio_tcp;
 extern crate tokio_udp;
@@ -11,8 +13,13 @@ extern crate trust_dns_server;
 
 use std::net::*;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+use chrono::Duration;
+use futures::{finished, Future};
+use openssl::pkey::Private;
+use openssl::rsa::Rsa;
 use tokio::runtime::current_thread::Runtime;
 use tokio_tcp::TcpStream as TokioTcpStream;
 use tokio_udp::UdpSocket as TokioUdpSocket;
@@ -20,18 +27,25 @@ use tokio_udp::UdpSocket as TokioUdpSocket;
 use trust_dns::client::{
     BasicClientHandle, ClientFuture, ClientHandle, MemoizeClientHandle, SecureClientHandle,
 };
-use trust_dns::op::ResponseCode;
-use trust_dns::rr::dnssec::TrustAnchor;
+use trust_dns::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns::rr::dnssec::{
+    Algorithm, AlgorithmPolicy, BogusReason, KeyPair, Nsec3HashAlgorithm, Signer, TrustAnchor,
+    TrustAnchors, Validity,
+};
+use trust_dns::rr::rdata::{NSEC3PARAM, SOA};
 use trust_dns::rr::Name;
-use trust_dns::rr::{DNSClass, RData, RecordType};
+use trust_dns::rr::{DNSClass, RData, Record, RecordType};
 use trust_dns::tcp::TcpClientStream;
 
+use trust_dns_proto::error::ProtoError;
+use trust_dns_proto::rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, SIG};
 use trust_dns_proto::udp::{UdpClientStream, UdpResponse};
-use trust_dns_proto::xfer::DnsMultiplexerSerialResponse;
+use trust_dns_proto::xfer::{DnsHandle, DnsMultiplexerSerialResponse, DnsRequest, DnsResponse};
 use trust_dns_proto::SecureDnsHandle;
-use trust_dns_server::authority::{Authority, Catalog};
+use trust_dns_server::authority::{Authority, Catalog, ZoneType};
+use trust_dns_server::store::in_memory::InMemoryAuthority;
 
-use trust_dns_integration::authority::create_secure_example;
+use trust_dns_integration::authority::{create_example, create_secure_example};
 use trust_dns_integration::TestClientStream;
 
 #[test]
@@ -327,3 +341,725 @@ where
     succeeded.store(true, std::sync::atomic::Ordering::Relaxed);
     join.join().unwrap();
 }
+
+#[test]
+fn test_validity_secure() {
+    with_validity(Duration::weeks(1), |mut client, mut io_loop, _| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Secure);
+        assert!(!response.answers().is_empty());
+    });
+}
+
+#[test]
+fn test_validity_insecure() {
+    with_validity(Duration::weeks(1), |mut client, mut io_loop, ds_queries| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.insecure.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Insecure);
+        assert_eq!(
+            *response.answers()[0].rdata(),
+            RData::A(Ipv4Addr::new(127, 0, 0, 1))
+        );
+
+        // the proof of the unsigned delegation is cached
+        let sent = ds_queries.load(Ordering::Relaxed);
+        let (_, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.insecure.example.com.", RecordType::A)))
+            .expect("query failed");
+        assert_eq!(validity, Validity::Insecure);
+        assert_eq!(ds_queries.load(Ordering::Relaxed), sent);
+
+        // an unsigned answer is an error of the validating handle
+        assert!(io_loop
+            .block_on(client.query(
+                Name::from_str("www.insecure.example.com.").unwrap(),
+                DNSClass::IN,
+                RecordType::A,
+            ))
+            .is_err());
+    });
+}
+
+#[test]
+fn test_validity_expired() {
+    with_validity(Duration::seconds(-60), |mut client, mut io_loop, _| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Bogus(BogusReason::SignatureExpired));
+        assert!(!response.answers().is_empty());
+    });
+}
+
+fn query(name: &str, record_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(Name::from_str(name).unwrap(), record_type))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true);
+
+    message
+}
+
+/// Sends DS queries to the catalog of the parent zone, as a recursive resolver would, and all
+///  other queries to the catalog of the child zone
+#[derive(Clone)]
+struct DelegationHandle<H: ClientHandle> {
+    parent: H,
+    child: H,
+    /// the number of DS queries sent to the parent
+    ds_queries: Arc<AtomicUsize>,
+}
+
+impl<H: ClientHandle> DnsHandle for DelegationHandle<H> {
+    type Response = <H as DnsHandle>::Response;
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        let request = request.into();
+        let is_ds = request
+            .queries()
+            .iter()
+            .any(|query| query.query_type() == RecordType::DNSSEC(DNSSECRecordType::DS));
+
+        if is_ds {
+            self.ds_queries.fetch_add(1, Ordering::Relaxed);
+            self.parent.send(request)
+        } else {
+            self.child.send(request)
+        }
+    }
+}
+
+/// example.com, signed for `sig_duration`, with a delegation to the unsigned
+///  insecure.example.com, a wildcard at *.wildcard.example.com, and a CNAME chain from
+///  x.y.example.com through alias.example.com
+///
+/// The zone is secured with NSEC3 records if there are `nsec3_params`, otherwise with NSEC records.
+fn create_delegating_example(
+    rsa: Rsa<Private>,
+    sig_duration: Duration,
+    nsec3_params: Option<NSEC3PARAM>,
+) -> InMemoryAuthority {
+    let mut authority = create_example();
+    authority.upsert(
+        Record::from_rdata(
+            Name::from_str("insecure.example.com.").unwrap(),
+            86400,
+            RData::NS(Name::from_str("ns.insecure.example.com.").unwrap()),
+        ),
+        0,
+    );
+    authority.upsert(
+        Record::from_rdata(
+            Name::from_str("*.wildcard.example.com.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 10)),
+        ),
+        0,
+    );
+    authority.upsert(
+        Record::from_rdata(
+            Name::from_str("x.y.example.com.").unwrap(),
+            86400,
+            RData::CNAME(Name::from_str("alias.example.com.").unwrap()),
+        ),
+        0,
+    );
+
+    let key = KeyPair::from_rsa(rsa).unwrap();
+    let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
+    let signer = Signer::dnssec(dnskey, key, authority.origin().clone().into(), sig_duration);
+
+    authority.set_nsec3_params(nsec3_params);
+    authority.add_zone_signing_key(signer).unwrap();
+    authority.secure_zone().unwrap();
+
+    authority
+}
+
+fn create_insecure_example() -> InMemoryAuthority {
+    let origin = Name::from_str("insecure.example.com.").unwrap();
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Master, false);
+
+    authority.upsert(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.insecure.example.com.").unwrap(),
+                Name::from_str("root.insecure.example.com.").unwrap(),
+                1,
+                7200,
+                3600,
+                1_209_600,
+                3600,
+            )),
+        ),
+        0,
+    );
+    authority.upsert(
+        Record::from_rdata(
+            Name::from_str("www.insecure.example.com.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        ),
+        0,
+    );
+
+    authority
+}
+
+fn with_validity<F>(sig_duration: Duration, test: F)
+where
+    F: Fn(
+        SecureDnsHandle<DelegationHandle<BasicClientHandle<DnsMultiplexerSerialResponse>>>,
+        Runtime,
+        Arc<AtomicUsize>,
+    ),
+{
+    let rsa = Rsa::generate(2048).unwrap();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(
+        &KeyPair::from_rsa(rsa.clone())
+            .unwrap()
+            .to_public_key()
+            .unwrap(),
+    );
+
+    let mut io_loop = Runtime::new().unwrap();
+    let mut client = |catalog: Catalog| {
+        let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+        let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+        io_loop.spawn(bg);
+        client
+    };
+
+    // the DS records of insecure.example.com are in the example.com zone
+    let mut parent = Catalog::new();
+    let authority = create_delegating_example(rsa.clone(), sig_duration, None);
+    parent.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut child = Catalog::new();
+    let authority = create_delegating_example(rsa, sig_duration, None);
+    child.upsert(authority.origin().clone(), Box::new(authority));
+    let authority = create_insecure_example();
+    child.upsert(authority.origin().clone(), Box::new(authority));
+
+    let ds_queries = Arc::new(AtomicUsize::new(0));
+    let handle = DelegationHandle {
+        parent: client(parent),
+        child: client(child),
+        ds_queries: Arc::clone(&ds_queries),
+    };
+
+    test(
+        SecureDnsHandle::with_trust_anchor(handle, trust_anchor),
+        io_loop,
+        ds_queries,
+    );
+}
+
+#[test]
+fn test_validity_wildcard() {
+    with_wildcard(true, |mut client, mut io_loop| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.wildcard.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Secure);
+        assert_eq!(
+            *response.answers()[0].rdata(),
+            RData::A(Ipv4Addr::new(127, 0, 0, 10))
+        );
+    });
+}
+
+#[test]
+fn test_validity_wildcard_without_proof() {
+    with_wildcard(false, |mut client, mut io_loop| {
+        let (_, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.wildcard.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Bogus(BogusReason::InvalidDenial));
+    });
+}
+
+/// Adds the NSEC proofs to answers expanded from a wildcard, which are not returned by the
+///  InMemoryAuthority, the NSEC of the wildcard covers the names it is expanded to
+#[derive(Clone)]
+struct WildcardProofHandle<H: ClientHandle> {
+    handle: H,
+    add_proofs: bool,
+}
+
+impl<H: ClientHandle> DnsHandle for WildcardProofHandle<H> {
+    type Response = Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        let mut handle = self.handle.clone();
+        let add_proofs = self.add_proofs;
+
+        Box::new(self.handle.send(request).and_then(move |response| {
+            // the RRSIGs of the answers have the labels of the wildcard
+            let wildcard = response
+                .answers()
+                .iter()
+                .filter_map(|rr| match *rr.rdata() {
+                    RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(sig),
+                    _ => None,
+                })
+                .find(|sig| sig.type_covered() == RecordType::A)
+                .map(|sig| {
+                    response.queries()[0]
+                        .name()
+                        .trim_to(sig.num_labels() as usize + 1)
+                        .into_wildcard()
+                });
+
+            match wildcard {
+                Some(wildcard) if add_proofs => Box::new(
+                    handle
+                        .send(dnssec_query(
+                            wildcard,
+                            RecordType::DNSSEC(DNSSECRecordType::NSEC),
+                        ))
+                        .map(move |proof| {
+                            let mut response = response;
+                            response.add_name_servers(proof.answers().to_vec());
+                            response
+                        }),
+                )
+                    as Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>,
+                _ => Box::new(finished(response)),
+            }
+        }))
+    }
+}
+
+/// A query for the records and their RRSIGs
+fn dnssec_query(name: Name, record_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(name, record_type))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .edns_mut()
+        .set_dnssec_ok(true);
+
+    message
+}
+
+fn with_wildcard<F>(add_proofs: bool, test: F)
+where
+    F: Fn(
+        SecureDnsHandle<WildcardProofHandle<BasicClientHandle<DnsMultiplexerSerialResponse>>>,
+        Runtime,
+    ),
+{
+    let rsa = Rsa::generate(2048).unwrap();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(
+        &KeyPair::from_rsa(rsa.clone())
+            .unwrap()
+            .to_public_key()
+            .unwrap(),
+    );
+
+    let mut catalog = Catalog::new();
+    let authority = create_delegating_example(rsa, Duration::weeks(1), None);
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+    io_loop.spawn(bg);
+
+    let handle = WildcardProofHandle {
+        handle: client,
+        add_proofs,
+    };
+
+    test(
+        SecureDnsHandle::with_trust_anchor(handle, trust_anchor),
+        io_loop,
+    );
+}
+
+#[test]
+fn test_validity_cname_chain() {
+    with_cname_chain(|mut client, mut io_loop| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("x.y.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        // the RRSIGs of the CNAMEs have the labels of their own owners, none is a wildcard
+        assert_eq!(validity, Validity::Secure);
+        assert!(response
+            .answers()
+            .iter()
+            .any(|rr| *rr.rdata() == RData::A(Ipv4Addr::new(93, 184, 216, 34))));
+    });
+}
+
+/// Returns the CNAME chain in the answers, as a recursive resolver would, instead of the
+///  additionals of the InMemoryAuthority
+#[derive(Clone)]
+struct CnameChainHandle<H: ClientHandle> {
+    handle: H,
+}
+
+impl<H: ClientHandle> DnsHandle for CnameChainHandle<H> {
+    type Response = Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        Box::new(self.handle.send(request).map(|mut response| {
+            let additionals = response.take_additionals();
+            response.add_answers(additionals);
+            response
+        }))
+    }
+}
+
+fn with_cname_chain<F>(test: F)
+where
+    F: Fn(
+        SecureDnsHandle<CnameChainHandle<BasicClientHandle<DnsMultiplexerSerialResponse>>>,
+        Runtime,
+    ),
+{
+    let rsa = Rsa::generate(2048).unwrap();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(
+        &KeyPair::from_rsa(rsa.clone())
+            .unwrap()
+            .to_public_key()
+            .unwrap(),
+    );
+
+    let mut catalog = Catalog::new();
+    let authority = create_delegating_example(rsa, Duration::weeks(1), None);
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+    io_loop.spawn(bg);
+
+    test(
+        SecureDnsHandle::with_trust_anchor(CnameChainHandle { handle: client }, trust_anchor),
+        io_loop,
+    );
+}
+
+#[test]
+fn test_validity_nsec3_nxdomain() {
+    with_nsec3(|mut client, mut io_loop| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("nonexistent.example.com.", RecordType::A)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Secure);
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert!(response.answers().is_empty());
+    });
+}
+
+#[test]
+fn test_validity_nsec3_no_data() {
+    with_nsec3(|mut client, mut io_loop| {
+        let (response, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.example.com.", RecordType::MX)))
+            .expect("query failed");
+
+        assert_eq!(validity, Validity::Secure);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.answers().is_empty());
+    });
+}
+
+/// example.com secured with NSEC3 records, with the parameters of the example zone of RFC 5155
+fn with_nsec3<F>(test: F)
+where
+    F: Fn(SecureDnsHandle<BasicClientHandle<DnsMultiplexerSerialResponse>>, Runtime),
+{
+    let rsa = Rsa::generate(2048).unwrap();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(
+        &KeyPair::from_rsa(rsa.clone())
+            .unwrap()
+            .to_public_key()
+            .unwrap(),
+    );
+
+    let nsec3_params = NSEC3PARAM::new(
+        Nsec3HashAlgorithm::SHA1,
+        false,
+        12,
+        vec![0xAA, 0xBB, 0xCC, 0xDD],
+    );
+
+    let mut catalog = Catalog::new();
+    let authority = create_delegating_example(rsa, Duration::weeks(1), Some(nsec3_params));
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+    io_loop.spawn(bg);
+
+    test(
+        SecureDnsHandle::with_trust_anchor(client, trust_anchor),
+        io_loop,
+    );
+}
+
+#[test]
+fn test_algorithm_policy_allowed() {
+    with_algorithm(
+        Algorithm::RSASHA1,
+        None,
+        AlgorithmPolicy::new(),
+        |mut client, mut io_loop| {
+            let (_, validity) = io_loop
+                .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+                .expect("query failed");
+
+            assert_eq!(validity, Validity::Secure);
+        },
+    );
+}
+
+#[test]
+fn test_algorithm_policy_insecure() {
+    let mut policy = AlgorithmPolicy::with_minimum(Algorithm::RSASHA256);
+    policy.set_disallowed_insecure(true);
+
+    // the trust anchor only lists the disallowed algorithm
+    with_algorithm(
+        Algorithm::RSASHA1,
+        None,
+        policy,
+        |mut client, mut io_loop| {
+            let (response, validity) = io_loop
+                .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+                .expect("query failed");
+
+            assert_eq!(validity, Validity::Insecure);
+            assert!(!response.answers().is_empty());
+        },
+    );
+}
+
+#[test]
+fn test_algorithm_policy_forged() {
+    let mut policy = AlgorithmPolicy::with_minimum(Algorithm::RSASHA256);
+    policy.set_disallowed_insecure(true);
+
+    // the zone is signed with an allowed algorithm, the RRSIGs of its records are forged with a
+    //  disallowed one
+    with_algorithm(
+        Algorithm::RSASHA256,
+        Some(Algorithm::RSASHA1),
+        policy,
+        |mut client, mut io_loop| {
+            let (_, validity) = io_loop
+                .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+                .expect("query failed");
+
+            assert_eq!(validity, Validity::Bogus(BogusReason::DisallowedAlgorithm));
+        },
+    );
+}
+
+#[test]
+fn test_algorithm_policy_bogus() {
+    let policy = AlgorithmPolicy::with_minimum(Algorithm::RSASHA256);
+
+    with_algorithm(
+        Algorithm::RSASHA1,
+        None,
+        policy,
+        |mut client, mut io_loop| {
+            let (_, validity) = io_loop
+                .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+                .expect("query failed");
+
+            assert_eq!(validity, Validity::Bogus(BogusReason::DisallowedAlgorithm));
+
+            // the rrset is rejected by the validating handle
+            assert!(io_loop
+                .block_on(client.query(
+                    Name::from_str("www.example.com.").unwrap(),
+                    DNSClass::IN,
+                    RecordType::A,
+                ))
+                .is_err());
+        },
+    );
+}
+
+/// Replaces the algorithm of the RRSIGs of all records but the DNSKEYs with the forged one
+#[derive(Clone)]
+struct ForgedRrsigHandle<H: ClientHandle> {
+    handle: H,
+    forged: Option<Algorithm>,
+}
+
+impl<H: ClientHandle> DnsHandle for ForgedRrsigHandle<H> {
+    type Response = Box<dyn Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        let forged = self.forged;
+
+        Box::new(self.handle.send(request).map(move |mut response| {
+            let forged = match forged {
+                Some(forged) => forged,
+                None => return response,
+            };
+
+            let forge = |records: Vec<Record>| {
+                records
+                    .into_iter()
+                    .map(|mut rr| {
+                        let sig = match *rr.rdata() {
+                            RData::DNSSEC(DNSSECRData::SIG(ref sig))
+                                if sig.type_covered()
+                                    != RecordType::DNSSEC(DNSSECRecordType::DNSKEY) =>
+                            {
+                                SIG::new(
+                                    sig.type_covered(),
+                                    forged,
+                                    sig.num_labels(),
+                                    sig.original_ttl(),
+                                    sig.sig_expiration(),
+                                    sig.sig_inception(),
+                                    sig.key_tag(),
+                                    sig.signer_name().clone(),
+                                    sig.sig().to_vec(),
+                                )
+                            }
+                            _ => return rr,
+                        };
+
+                        rr.set_rdata(RData::DNSSEC(DNSSECRData::SIG(sig)));
+                        rr
+                    })
+                    .collect()
+            };
+
+            let answers = forge(response.take_answers());
+            let name_servers = forge(response.take_name_servers());
+            let additionals = forge(response.take_additionals());
+            response.insert_answers(answers);
+            response.insert_name_servers(name_servers);
+            response.insert_additionals(additionals);
+
+            response
+        }))
+    }
+}
+
+/// example.com signed only with the algorithm, validated with the policy, the key of the zone is
+///  the trust anchor
+fn with_algorithm<F>(
+    algorithm: Algorithm,
+    forged: Option<Algorithm>,
+    algorithm_policy: AlgorithmPolicy,
+    test: F,
+) where
+    F: Fn(
+        SecureDnsHandle<ForgedRrsigHandle<BasicClientHandle<DnsMultiplexerSerialResponse>>>,
+        Runtime,
+    ),
+{
+    let key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+    let mut authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let dnskey = key.to_dnskey(algorithm).unwrap();
+    let mut trust_anchors = TrustAnchors::new();
+    trust_anchors.insert_dnskey(origin.clone(), dnskey.clone());
+
+    let signer = Signer::dnssec(dnskey, key, origin, Duration::weeks(1));
+    authority.add_zone_signing_key(signer).unwrap();
+    authority.secure_zone().unwrap();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+    io_loop.spawn(bg);
+
+    let client = ForgedRrsigHandle {
+        handle: client,
+        forged,
+    };
+    let mut client = SecureDnsHandle::with_trust_anchors(client, trust_anchors);
+    client.set_algorithm_policy(algorithm_policy);
+
+    test(client, io_loop);
+}
+
+#[test]
+fn test_trust_anchors_rollover() {
+    let old_key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let new_key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let old_dnskey = old_key.to_dnskey(Algorithm::RSASHA512).unwrap();
+    let new_dnskey = new_key.to_dnskey(Algorithm::RSASHA256).unwrap();
+
+    // the zone is signed with both keys, only the old one is a trust anchor, the catalog only
+    //  returns the RRSIGs of the strongest algorithm, those of the old key
+    let mut authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let signer = Signer::dnssec(
+        old_dnskey.clone(),
+        old_key,
+        origin.clone(),
+        Duration::weeks(1),
+    );
+    authority.add_zone_signing_key(signer).unwrap();
+    let signer = Signer::dnssec(
+        new_dnskey.clone(),
+        new_key,
+        origin.clone(),
+        Duration::weeks(1),
+    );
+    authority.add_zone_signing_key(signer).unwrap();
+    authority.secure_zone().unwrap();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(authority));
+
+    let mut io_loop = Runtime::new().unwrap();
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, client) = ClientFuture::new(stream, Box::new(sender), None);
+    io_loop.spawn(bg);
+
+    let mut trust_anchors = TrustAnchors::new();
+    trust_anchors.set_add_hold_down(0);
+    trust_anchors.insert_dnskey(origin.clone(), old_dnskey.clone());
+    let mut client = SecureDnsHandle::with_trust_anchors(client, trust_anchors);
+
+    // the new key is pending after the first validated DNSKEY rrset, and trusted after the next
+    for _ in 0..2 {
+        let (_, validity) = io_loop
+            .block_on(client.send_with_validity(query("www.example.com.", RecordType::A)))
+            .expect("query failed");
+        assert_eq!(validity, Validity::Secure);
+    }
+
+    let trust_anchors = client.trust_anchors();
+    assert!(trust_anchors.is_trusted(&origin, &old_dnskey));
+    assert!(trust_anchors.is_trusted(&origin, &new_dnskey));
+}
@@ -35,6 +35,7 @@ use trust_dns_proto::error::ProtoError;
 use trust_dns_proto::xfer::DnsRequestSender;
 
 use trust_dns_server::authority::{Authority, Catalog};
+use trust_dns_server::server::TokenBucketRateLimiter;
 use trust_dns_server::ServerFuture;
 
 use trust_dns_integration::authority::create_example;
@@ -54,7 +55,7 @@ fn test_server_www_udp() {
 
     let server_thread = thread::Builder::new()
         .name("test_server:udp:server".to_string())
-        .spawn(move || server_thread_udp(udp_socket, server_continue2))
+        .spawn(move || server_thread_udp(udp_socket, server_continue2, None))
         .unwrap();
 
     let client_thread = thread::Builder::new()
@@ -108,7 +109,7 @@ fn test_server_unknown_type() {
 
     let server_thread = thread::Builder::new()
         .name("test_server:udp:server".to_string())
-        .spawn(move || server_thread_udp(udp_socket, server_continue2))
+        .spawn(move || server_thread_udp(udp_socket, server_continue2, None))
         .unwrap();
 
     let conn = UdpClientConnection::new(ipaddr).unwrap();
@@ -142,6 +143,68 @@ fn test_server_unknown_type() {
     server_thread.join().unwrap();;
 }
 
+#[test]
+fn test_server_rate_limit_udp() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+
+    let ipaddr = udp_socket.local_addr().unwrap();
+    println!("udp_socket on port: {}", ipaddr);
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    // a burst of 2, past it every 2nd response is truncated and the others dropped
+    let rate_limiter = TokenBucketRateLimiter::new(1, 2);
+    let server_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp(udp_socket, server_continue2, Some(rate_limiter)))
+        .unwrap();
+
+    let client = std::net::UdpSocket::bind((Ipv4Addr::new(127, 0, 0, 1), 0)).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_millis(250)))
+        .unwrap();
+
+    let mut query = Message::new();
+    query.add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let query = query.to_vec().unwrap();
+
+    let mut buf = [0_u8; 512];
+    let responses: Vec<Option<Message>> = (0..4)
+        .map(|_| {
+            client.send_to(&query, ipaddr).unwrap();
+            client
+                .recv(&mut buf)
+                .ok()
+                .map(|len| Message::from_vec(&buf[..len]).expect("bad response"))
+        })
+        .collect();
+
+    for response in &responses[..2] {
+        let response = response
+            .as_ref()
+            .expect("response within the burst dropped");
+        assert!(!response.truncated());
+        assert!(!response.answers().is_empty());
+    }
+
+    assert!(
+        responses[2].is_none(),
+        "response past the burst not dropped"
+    );
+
+    let truncated = responses[3].as_ref().expect("response not truncated");
+    assert!(truncated.truncated());
+    assert!(truncated.answers().is_empty());
+    assert_eq!(truncated.queries().len(), 1);
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
 #[cfg(all(feature = "dns-over-openssl", not(feature = "dns-over-rustls")))]
 fn read_file(path: &str) -> Vec<u8> {
     use std::fs::File;
@@ -274,11 +337,18 @@ fn new_catalog() -> Catalog {
     catalog
 }
 
-fn server_thread_udp(udp_socket: UdpSocket, server_continue: Arc<AtomicBool>) {
+fn server_thread_udp(
+    udp_socket: UdpSocket,
+    server_continue: Arc<AtomicBool>,
+    rate_limiter: Option<TokenBucketRateLimiter>,
+) {
     let catalog = new_catalog();
 
     let mut io_loop = Runtime::new().unwrap();
-    let server = ServerFuture::new(catalog);
+    let mut server = ServerFuture::new(catalog);
+    if let Some(rate_limiter) = rate_limiter {
+        server.set_rate_limiter(rate_limiter);
+    }
     io_loop
         .block_on::<Box<dyn Future<Item = (), Error = ()> + Send>>(Box::new(future::lazy(|| {
             server.register_socket(udp_socket);
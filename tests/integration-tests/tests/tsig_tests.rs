@@ -0,0 +1,148 @@
+#![cfg(feature = "dnssec")]
+
+extern crate trust_dns_proto;
+
+use std::str::FromStr;
+
+use trust_dns_proto::error::ProtoErrorKind;
+use trust_dns_proto::op::{Message, Query, ResponseCode};
+use trust_dns_proto::rr::dnssec::rdata::{DNSSECRecordType, TsigAlgorithm};
+use trust_dns_proto::rr::dnssec::tsig::DEFAULT_FUDGE;
+use trust_dns_proto::rr::dnssec::{TsigSigner, TsigVerifier};
+use trust_dns_proto::rr::{Name, RecordType};
+
+const TIME: u32 = 1_577_836_800;
+
+fn signer() -> TsigSigner {
+    TsigSigner::new(
+        Name::from_str("transfer-key.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+        b"a secret shared by client and server".to_vec(),
+        DEFAULT_FUDGE,
+    )
+}
+
+fn signed_query_bytes(signer: &TsigSigner) -> Vec<u8> {
+    let mut message = Message::new();
+    message
+        .set_id(4242)
+        .set_recursion_desired(true)
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+
+    message.finalize(signer, TIME).expect("failed to sign");
+    message.to_vec().expect("failed to encode")
+}
+
+fn verification_failure(verifier: &TsigVerifier, bytes: &[u8]) -> ResponseCode {
+    let message = Message::from_vec(bytes).expect("failed to decode");
+    let error = verifier
+        .verify_message(&message, None, u64::from(TIME))
+        .unwrap_err();
+
+    match *error.kind() {
+        ProtoErrorKind::TsigVerificationFailed(code) => code,
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn test_signed_query_verifies() {
+    let signer = signer();
+    let bytes = signed_query_bytes(&signer);
+
+    let message = Message::from_vec(&bytes).expect("failed to decode");
+    let tsig = message.tsig().expect("tsig missing");
+    assert_eq!(tsig.rr_type(), RecordType::DNSSEC(DNSSECRecordType::TSIG));
+    assert_eq!(*message.sig0().last().unwrap(), *tsig);
+    assert!(message.additionals().is_empty());
+
+    signer
+        .to_verifier()
+        .verify_message(&message, None, u64::from(TIME) + 10)
+        .expect("failed to verify");
+}
+
+#[test]
+fn test_tampered_query_is_rejected() {
+    let signer = signer();
+    let mut bytes = signed_query_bytes(&signer);
+
+    // www.example.com. -> wwx.example.com.
+    let www = bytes
+        .windows(3)
+        .position(|w| w == b"www")
+        .expect("query name not found");
+    bytes[www + 2] = b'x';
+
+    assert_eq!(
+        verification_failure(&signer.to_verifier(), &bytes),
+        ResponseCode::BADSIG
+    );
+}
+
+#[test]
+fn test_rewritten_id_verifies_with_original_id() {
+    let signer = signer();
+    let mut bytes = signed_query_bytes(&signer);
+
+    // forwarders may rewrite the id, the MAC covers the original id of the TSIG
+    bytes[1] ^= 0x01;
+    let message = Message::from_vec(&bytes).expect("failed to decode");
+    assert_eq!(message.id(), 4242 ^ 0x01);
+    signer
+        .to_verifier()
+        .verify_message(&message, None, u64::from(TIME))
+        .expect("failed to verify");
+}
+
+#[test]
+fn test_tampered_flags_are_rejected() {
+    let signer = signer();
+    let mut bytes = signed_query_bytes(&signer);
+
+    // recursion desired -> recursion desired and truncated
+    bytes[2] ^= 0x02;
+
+    assert_eq!(
+        verification_failure(&signer.to_verifier(), &bytes),
+        ResponseCode::BADSIG
+    );
+}
+
+#[test]
+fn test_unknown_key_is_rejected() {
+    let bytes = signed_query_bytes(&signer());
+
+    let verifier = TsigVerifier::new(
+        Name::from_str("other-key.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+        b"a secret shared by client and server".to_vec(),
+    );
+    assert_eq!(
+        verification_failure(&verifier, &bytes),
+        ResponseCode::BADKEY
+    );
+}
+
+#[test]
+fn test_outside_fudge_is_rejected() {
+    let signer = signer();
+    let bytes = signed_query_bytes(&signer);
+    let message = Message::from_vec(&bytes).expect("failed to decode");
+
+    let error = signer
+        .to_verifier()
+        .verify_message(
+            &message,
+            None,
+            u64::from(TIME) + u64::from(DEFAULT_FUDGE) + 1,
+        )
+        .unwrap_err();
+    match *error.kind() {
+        ProtoErrorKind::TsigVerificationFailed(ResponseCode::BADTIME) => (),
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+}
@@ -58,6 +58,7 @@ pub fn main() {
                     | Algorithm::RSASHA512 => String::from("rsa"),
                     Algorithm::ECDSAP256SHA256 | Algorithm::ECDSAP384SHA384 => String::from("ecdsa"),
                     Algorithm::ED25519 => String::from("ed25519"),
+                    Algorithm::ECCGOST => String::from("gost"),
                     Algorithm::Unknown(v) => format!("unknown_{}",v),
                 };
 